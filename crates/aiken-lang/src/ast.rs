@@ -113,6 +113,18 @@ impl TypedModule {
             .find_map(|definition| definition.find_node(byte_index))
     }
 
+    /// The variables in scope at the given byte index, in the order they are bound. Later
+    /// variables shadow earlier ones of the same name.
+    pub fn variables_at(&self, byte_index: usize) -> Vec<(String, Span, Rc<Type>)> {
+        let mut variables = Vec::new();
+
+        for definition in self.definitions.iter() {
+            definition.collect_variables_at(byte_index, &mut variables);
+        }
+
+        variables
+    }
+
     pub fn has_definition(&self, name: &str) -> bool {
         self.definitions.iter().any(|def| match def {
             Definition::Fn(f) => f.public && f.name == name,
@@ -305,6 +317,7 @@ pub struct Function<T, Expr, Arg> {
     pub return_type: T,
     pub end_position: usize,
     pub on_test_failure: OnTestFailure,
+    pub decorators: Vec<Decorator>,
 }
 
 impl<T, Expr, Arg> Function<T, Expr, Arg> {
@@ -388,6 +401,7 @@ impl From<UntypedTest> for UntypedFunction {
             body: f.body,
             on_test_failure: f.on_test_failure,
             end_position: f.end_position,
+            decorators: f.decorators,
         }
     }
 }
@@ -405,6 +419,7 @@ impl From<TypedTest> for TypedFunction {
             body: f.body,
             on_test_failure: f.on_test_failure,
             end_position: f.end_position,
+            decorators: f.decorators,
         }
     }
 }
@@ -476,8 +491,15 @@ pub struct Decorator {
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum DecoratorKind {
-    Tag { value: String, base: Base },
+    Tag {
+        value: String,
+        base: Base,
+    },
     List,
+    Budget {
+        mem: Option<(String, Base)>,
+        cpu: Option<(String, Base)>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -571,6 +593,7 @@ impl UntypedValidator {
             return_annotation: Some(Annotation::boolean(location)),
             return_type: (),
             on_test_failure: OnTestFailure::FailImmediately,
+            decorators: vec![],
         }
     }
 }
@@ -926,6 +949,50 @@ impl TypedDefinition {
 
         located
     }
+
+    /// Collect the variables in scope at the given byte index, should it fall within the
+    /// definition. Unlike spans, which only cover the head of definitions, the whole body counts.
+    pub fn collect_variables_at(
+        &self,
+        byte_index: usize,
+        variables: &mut Vec<(String, Span, Rc<Type>)>,
+    ) {
+        let encloses = |location: Span, end_position: usize| {
+            location.start <= byte_index && byte_index <= end_position
+        };
+
+        match self {
+            Definition::Fn(func) if encloses(func.location, func.end_position) => {
+                variables.extend(func.arguments.iter().filter_map(TypedArg::variable));
+                func.body.collect_variables_at(byte_index, variables);
+            }
+
+            Definition::Test(func) | Definition::Benchmark(func)
+                if encloses(func.location, func.end_position) =>
+            {
+                variables.extend(func.arguments.iter().filter_map(|arg| arg.arg.variable()));
+                func.body.collect_variables_at(byte_index, variables);
+            }
+
+            Definition::Validator(validator)
+                if encloses(validator.location, validator.end_position) =>
+            {
+                variables.extend(validator.params.iter().filter_map(TypedArg::variable));
+
+                if let Some(handler) = validator
+                    .handlers
+                    .iter()
+                    .chain(std::iter::once(&validator.fallback))
+                    .find(|handler| encloses(handler.location, handler.end_position))
+                {
+                    variables.extend(handler.arguments.iter().filter_map(TypedArg::variable));
+                    handler.body.collect_variables_at(byte_index, variables);
+                }
+            }
+
+            _ => (),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -1159,6 +1226,20 @@ impl TypedArg {
         self.doc = Some(new_doc);
     }
 
+    /// The variable bound by the argument, if any. Arguments destructured by a pattern are given
+    /// an ordinal name which can't be referred to, and bind the variables of their pattern
+    /// through an assignment at the start of the body instead.
+    pub fn variable(&self) -> Option<(String, Span, Rc<Type>)> {
+        match &self.arg_name {
+            ArgName::Named { name, location, .. }
+                if !name.starts_with(|c: char| c.is_ascii_digit()) =>
+            {
+                Some((name.clone(), *location, self.tipo.clone()))
+            }
+            ArgName::Named { .. } | ArgName::Discarded { .. } => None,
+        }
+    }
+
     pub fn get_variable_name(&self) -> Option<&str> {
         self.arg_name.get_variable_name()
     }
@@ -1917,6 +1998,70 @@ impl TypedPattern {
         }
     }
 
+    /// The variables bound by the pattern, along with their types, given the type of the value
+    /// it matches.
+    pub fn bindings(&self, value: &Rc<Type>) -> Vec<(String, Span, Rc<Type>)> {
+        let mut bindings = Vec::new();
+        self.collect_bindings(value, &mut bindings);
+        bindings
+    }
+
+    fn collect_bindings(&self, value: &Rc<Type>, bindings: &mut Vec<(String, Span, Rc<Type>)>) {
+        let value = Type::collapse_links(value.clone());
+
+        match self {
+            Pattern::Int { .. } | Pattern::ByteArray { .. } | Pattern::Discard { .. } => (),
+
+            Pattern::Var { name, location } => {
+                bindings.push((name.clone(), *location, value));
+            }
+
+            Pattern::Assign {
+                name,
+                location,
+                pattern,
+            } => {
+                pattern.collect_bindings(&value, bindings);
+                bindings.push((name.clone(), *location, value));
+            }
+
+            Pattern::List { elements, tail, .. } => {
+                if let Some(element) = value.get_inner_types().first() {
+                    for e in elements {
+                        e.collect_bindings(element, bindings);
+                    }
+                }
+
+                if let Some(tail) = tail {
+                    tail.collect_bindings(&value, bindings);
+                }
+            }
+
+            Pattern::Tuple { elems, .. } => {
+                for (e, t) in elems.iter().zip(value.get_inner_types().iter()) {
+                    e.collect_bindings(t, bindings);
+                }
+            }
+
+            Pattern::Pair { fst, snd, .. } => {
+                if let [fst_t, snd_t] = &value.get_inner_types()[..] {
+                    fst.collect_bindings(fst_t, bindings);
+                    snd.collect_bindings(snd_t, bindings);
+                }
+            }
+
+            Pattern::Constructor {
+                arguments, tipo, ..
+            } => {
+                if let Type::Fn { args, .. } = &**tipo {
+                    for (e, t) in arguments.iter().zip(args.iter()) {
+                        e.value.collect_bindings(t, bindings);
+                    }
+                }
+            }
+        }
+    }
+
     // TODO: This function definition is weird, see where this is used and how.
     pub fn tipo(&self, value: &TypedExpr) -> Option<Rc<Type>> {
         match self {
@@ -2264,7 +2409,7 @@ impl TypedClause {
 
 pub struct UntypedClauseGuard {}
 
-pub type TypedIfBranch = IfBranch<TypedExpr, (TypedPattern, Rc<Type>)>;
+pub type TypedIfBranch = IfBranch<TypedExpr, (TypedPattern, Rc<Type>, Option<Annotation>)>;
 pub type UntypedIfBranch = IfBranch<UntypedExpr, AssignmentPattern>;
 
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -2364,7 +2509,9 @@ impl Display for TraceLevel {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(
+    Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
@@ -27,6 +27,8 @@ pub const PRNG: &str = "PRNG";
 pub const PRNG_CONSTRUCTORS: &[&str] = &["Seeded", "Replayed"];
 pub const REDEEMER_WRAPPER: &str = "RedeemerWrapper";
 pub const STRING: &str = "String";
+pub const TRANSACTION: &str = "Transaction";
+pub const TRANSACTION_MODULE: &str = "cardano/transaction";
 pub const VOID: &str = "Void";
 pub const VOID_CONSTRUCTORS: &[&str] = &["Void"];
 
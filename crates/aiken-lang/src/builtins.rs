@@ -1131,6 +1131,7 @@ pub fn prelude_functions(
             tipo: Type::data(),
         }],
         on_test_failure: OnTestFailure::FailImmediately,
+        decorators: vec![],
         doc: Some(
             indoc::indoc! {
                 r#"
@@ -1195,6 +1196,7 @@ pub fn prelude_functions(
             tipo: Type::data(),
         }],
         on_test_failure: OnTestFailure::FailImmediately,
+        decorators: vec![],
         doc: Some(
             indoc::indoc! {
                 r#"
@@ -1238,6 +1240,7 @@ pub fn prelude_functions(
                 tipo: Type::data(),
             }],
             on_test_failure: OnTestFailure::FailImmediately,
+            decorators: vec![],
             body: TypedExpr::Var {
                 location: Span::empty(),
                 constructor: ValueConstructor {
@@ -1289,6 +1292,7 @@ pub fn prelude_functions(
                 tipo: Type::bool(),
             }],
             on_test_failure: OnTestFailure::FailImmediately,
+            decorators: vec![],
             doc: Some(
                 indoc::indoc! {
                     r#"
@@ -1346,6 +1350,7 @@ pub fn prelude_functions(
                 tipo: a_var.clone(),
             }],
             on_test_failure: OnTestFailure::FailImmediately,
+            decorators: vec![],
             body: TypedExpr::Var {
                 location: Span::empty(),
                 constructor: ValueConstructor {
@@ -1388,6 +1393,7 @@ pub fn prelude_functions(
         },
         Function {
             on_test_failure: OnTestFailure::FailImmediately,
+            decorators: vec![],
             arguments: vec![
                 TypedArg {
                     arg_name: ArgName::Named {
@@ -1467,6 +1473,7 @@ pub fn prelude_functions(
         },
         Function {
             on_test_failure: OnTestFailure::FailImmediately,
+            decorators: vec![],
             arguments: vec![TypedArg {
                 arg_name: ArgName::Named {
                     name: "f".to_string(),
@@ -0,0 +1,316 @@
+use crate::{
+    ast::{Definition, FunctionAccessKey, Span, TypedFunction, TypedModule},
+    expr::TypedExpr,
+    tipo::Type,
+};
+
+/// Prefix of the labels traced by coverage probes. Labels start with a NUL byte
+/// so they aren't mistaken for regular traces; this second marker tells them
+/// apart from user-defined labels.
+const PROBE_LABEL_PREFIX: &str = "\u{1}coverage:";
+
+/// Identifies a point in the source code instrumented to record whether it is
+/// reached during evaluation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProbeId {
+    pub module: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl ProbeId {
+    fn new(module: &str, location: Span) -> Self {
+        ProbeId {
+            module: module.to_string(),
+            start: location.start,
+            end: location.end,
+        }
+    }
+
+    /// The (NUL-prefixed) trace emitted when the probe is reached.
+    fn to_trace(&self) -> String {
+        format!(
+            "\0{PROBE_LABEL_PREFIX}{}:{}:{}",
+            self.module, self.start, self.end
+        )
+    }
+
+    /// Recover a probe from a label collected during evaluation, if it is one.
+    pub fn from_label(label: &str) -> Option<Self> {
+        let mut parts = label.strip_prefix(PROBE_LABEL_PREFIX)?.rsplitn(3, ':');
+
+        let end = parts.next()?.parse().ok()?;
+        let start = parts.next()?.parse().ok()?;
+        let module = parts.next()?.to_string();
+
+        Some(ProbeId { module, start, end })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeKind {
+    /// The body of a function.
+    Function { name: String },
+    /// The body of a validator handler.
+    Handler { name: String },
+    /// One of the branches of an `if` or `when` expression; branches of the
+    /// same expression share the same `block`.
+    Branch { block: Span, index: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Probe {
+    pub id: ProbeId,
+    pub kind: ProbeKind,
+}
+
+/// Instrument the functions and validator handlers of a module with coverage
+/// probes, returning the instrumented definitions along with every probe
+/// inserted. Tests and benchmarks aren't instrumented.
+///
+/// A probe is a trace of a label identifying a location, evaluated before the
+/// function body or branch it guards. So evaluating instrumented code yields,
+/// amongst its labels, the probes that were reached.
+pub fn instrument_module(
+    module: &TypedModule,
+) -> (Vec<(FunctionAccessKey, TypedFunction)>, Vec<Probe>) {
+    let mut functions = vec![];
+    let mut probes = vec![];
+
+    for definition in module.definitions() {
+        match definition {
+            Definition::Fn(function) => {
+                let key = FunctionAccessKey {
+                    module_name: module.name.clone(),
+                    function_name: function.name.clone(),
+                };
+
+                let kind = ProbeKind::Function {
+                    name: function.name.clone(),
+                };
+
+                functions.push((
+                    key,
+                    instrument_function(&module.name, function.clone(), kind, &mut probes),
+                ));
+            }
+            Definition::Validator(validator) => {
+                for (key, handler) in validator.into_function_definitions(&module.name) {
+                    let kind = ProbeKind::Handler {
+                        name: key.function_name.clone(),
+                    };
+
+                    functions.push((
+                        key,
+                        instrument_function(&module.name, handler, kind, &mut probes),
+                    ));
+                }
+            }
+            Definition::TypeAlias(..)
+            | Definition::DataType(..)
+            | Definition::Use(..)
+            | Definition::ModuleConstant(..)
+            | Definition::Test(..)
+            | Definition::Benchmark(..) => {}
+        }
+    }
+
+    (functions, probes)
+}
+
+fn instrument_function(
+    module: &str,
+    mut function: TypedFunction,
+    kind: ProbeKind,
+    probes: &mut Vec<Probe>,
+) -> TypedFunction {
+    instrument_expr(module, &mut function.body, probes);
+
+    let id = ProbeId::new(module, function.location);
+
+    probe(&mut function.body, &id);
+
+    probes.push(Probe { id, kind });
+
+    function
+}
+
+fn instrument_expr(module: &str, expr: &mut TypedExpr, probes: &mut Vec<Probe>) {
+    match expr {
+        TypedExpr::UInt { .. }
+        | TypedExpr::String { .. }
+        | TypedExpr::ByteArray { .. }
+        | TypedExpr::CurvePoint { .. }
+        | TypedExpr::Var { .. }
+        | TypedExpr::ModuleSelect { .. }
+        | TypedExpr::ErrorTerm { .. } => {}
+
+        TypedExpr::Sequence { expressions, .. } | TypedExpr::Pipeline { expressions, .. } => {
+            for expression in expressions {
+                instrument_expr(module, expression, probes);
+            }
+        }
+
+        TypedExpr::Fn { body, .. } => instrument_expr(module, body, probes),
+
+        TypedExpr::List { elements, tail, .. } => {
+            for element in elements {
+                instrument_expr(module, element, probes);
+            }
+
+            if let Some(tail) = tail {
+                instrument_expr(module, tail, probes);
+            }
+        }
+
+        TypedExpr::Call { fun, args, .. } => {
+            instrument_expr(module, fun, probes);
+
+            for arg in args {
+                instrument_expr(module, &mut arg.value, probes);
+            }
+        }
+
+        TypedExpr::BinOp { left, right, .. } => {
+            instrument_expr(module, left, probes);
+            instrument_expr(module, right, probes);
+        }
+
+        TypedExpr::Assignment { value, .. } => instrument_expr(module, value, probes),
+
+        TypedExpr::Trace { then, text, .. } => {
+            instrument_expr(module, then, probes);
+            instrument_expr(module, text, probes);
+        }
+
+        TypedExpr::When {
+            location,
+            subject,
+            clauses,
+            ..
+        } => {
+            instrument_expr(module, subject, probes);
+
+            for (index, clause) in clauses.iter_mut().enumerate() {
+                instrument_expr(module, &mut clause.then, probes);
+
+                let id = ProbeId::new(module, clause.location);
+
+                probe(&mut clause.then, &id);
+
+                probes.push(Probe {
+                    id,
+                    kind: ProbeKind::Branch {
+                        block: *location,
+                        index,
+                    },
+                });
+            }
+        }
+
+        TypedExpr::If {
+            location,
+            branches,
+            final_else,
+            ..
+        } => {
+            let block = *location;
+
+            for (index, branch) in branches.iter_mut().enumerate() {
+                instrument_expr(module, &mut branch.condition, probes);
+                instrument_expr(module, &mut branch.body, probes);
+
+                let id = ProbeId::new(module, branch.body.location());
+
+                probe(&mut branch.body, &id);
+
+                probes.push(Probe {
+                    id,
+                    kind: ProbeKind::Branch { block, index },
+                });
+            }
+
+            instrument_expr(module, final_else, probes);
+
+            let id = ProbeId::new(module, final_else.location());
+
+            probe(final_else, &id);
+
+            probes.push(Probe {
+                id,
+                kind: ProbeKind::Branch {
+                    block,
+                    index: branches.len(),
+                },
+            });
+        }
+
+        TypedExpr::RecordAccess { record, .. } => instrument_expr(module, record, probes),
+
+        TypedExpr::Tuple { elems, .. } => {
+            for elem in elems {
+                instrument_expr(module, elem, probes);
+            }
+        }
+
+        TypedExpr::Pair { fst, snd, .. } => {
+            instrument_expr(module, fst, probes);
+            instrument_expr(module, snd, probes);
+        }
+
+        TypedExpr::TupleIndex { tuple, .. } => instrument_expr(module, tuple, probes),
+
+        TypedExpr::RecordUpdate { spread, args, .. } => {
+            instrument_expr(module, spread, probes);
+
+            for arg in args {
+                instrument_expr(module, &mut arg.value, probes);
+            }
+        }
+
+        TypedExpr::UnOp { value, .. } => instrument_expr(module, value, probes),
+    }
+}
+
+/// Trace the probe's label before evaluating the given expression.
+fn probe(expr: &mut TypedExpr, id: &ProbeId) {
+    let location = expr.location();
+
+    let then = std::mem::replace(
+        expr,
+        TypedExpr::ErrorTerm {
+            location,
+            tipo: Type::string(),
+        },
+    );
+
+    *expr = TypedExpr::Trace {
+        location,
+        tipo: then.tipo(),
+        text: Box::new(TypedExpr::String {
+            location,
+            tipo: Type::string(),
+            value: id.to_trace(),
+        }),
+        then: Box::new(then),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProbeId;
+
+    #[test]
+    fn probe_label_roundtrip() {
+        let id = ProbeId {
+            module: "foo/bar".to_string(),
+            start: 14,
+            end: 42,
+        };
+
+        let label = id.to_trace().strip_prefix('\0').unwrap().to_string();
+
+        assert_eq!(ProbeId::from_label(&label), Some(id));
+        assert_eq!(ProbeId::from_label("some label"), None);
+    }
+}
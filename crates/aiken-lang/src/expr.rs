@@ -109,6 +109,7 @@ pub enum TypedExpr {
         tipo: Rc<Type>,
         value: Box<Self>,
         pattern: TypedPattern,
+        annotation: Option<Annotation>,
         kind: TypedAssignmentKind,
         comment: Option<String>,
     },
@@ -254,6 +255,7 @@ impl TypedExpr {
             tipo: tipo.clone(),
             value: value.into(),
             pattern,
+            annotation: None,
             kind: AssignmentKind::let_(),
             comment: None,
             location,
@@ -271,6 +273,7 @@ impl TypedExpr {
             tipo: tipo.clone(),
             value: value.into(),
             pattern,
+            annotation: None,
             kind: if tipo.is_data() {
                 AssignmentKind::let_()
             } else {
@@ -573,6 +576,127 @@ impl TypedExpr {
         }
     }
 
+    /// Collect the variables in scope at the given byte index, bound within the expression.
+    pub fn collect_variables_at(
+        &self,
+        byte_index: usize,
+        variables: &mut Vec<(String, Span, Rc<Type>)>,
+    ) {
+        // The index may sit right at the end of an expression, as it does while typing one.
+        let encloses = |location: Span| location.start <= byte_index && byte_index <= location.end;
+
+        match self {
+            TypedExpr::Sequence { expressions, .. } | TypedExpr::Pipeline { expressions, .. } => {
+                for expression in expressions {
+                    if encloses(expression.location()) {
+                        return expression.collect_variables_at(byte_index, variables);
+                    }
+
+                    if expression.location().start > byte_index {
+                        return;
+                    }
+
+                    if let TypedExpr::Assignment { pattern, value, .. } = expression {
+                        variables.extend(pattern.bindings(&value.tipo()));
+                    }
+                }
+            }
+
+            TypedExpr::Fn { args, body, .. } => {
+                variables.extend(args.iter().filter_map(TypedArg::variable));
+                body.collect_variables_at(byte_index, variables);
+            }
+
+            TypedExpr::When {
+                subject, clauses, ..
+            } => {
+                if encloses(subject.location()) {
+                    return subject.collect_variables_at(byte_index, variables);
+                }
+
+                if let Some(clause) = clauses.iter().find(|clause| encloses(clause.location())) {
+                    variables.extend(clause.pattern.bindings(&subject.tipo()));
+                    clause.then.collect_variables_at(byte_index, variables);
+                }
+            }
+
+            TypedExpr::If {
+                branches,
+                final_else,
+                ..
+            } => {
+                for branch in branches {
+                    if encloses(branch.condition.location()) {
+                        return branch.condition.collect_variables_at(byte_index, variables);
+                    }
+
+                    if encloses(branch.body.location()) {
+                        if let Some((pattern, tipo, _)) = &branch.is {
+                            variables.extend(pattern.bindings(tipo));
+                        }
+
+                        return branch.body.collect_variables_at(byte_index, variables);
+                    }
+                }
+
+                final_else.collect_variables_at(byte_index, variables);
+            }
+
+            TypedExpr::ErrorTerm { .. }
+            | TypedExpr::Var { .. }
+            | TypedExpr::UInt { .. }
+            | TypedExpr::String { .. }
+            | TypedExpr::ByteArray { .. }
+            | TypedExpr::ModuleSelect { .. }
+            | TypedExpr::CurvePoint { .. } => (),
+
+            TypedExpr::Trace { text, then, .. } => [text, then]
+                .into_iter()
+                .filter(|e| encloses(e.location()))
+                .for_each(|e| e.collect_variables_at(byte_index, variables)),
+
+            TypedExpr::Tuple { elems, .. } => elems
+                .iter()
+                .filter(|e| encloses(e.location()))
+                .for_each(|e| e.collect_variables_at(byte_index, variables)),
+
+            TypedExpr::List { elements, tail, .. } => elements
+                .iter()
+                .chain(tail.as_deref())
+                .filter(|e| encloses(e.location()))
+                .for_each(|e| e.collect_variables_at(byte_index, variables)),
+
+            TypedExpr::Pair { fst, snd, .. } => [fst, snd]
+                .into_iter()
+                .filter(|e| encloses(e.location()))
+                .for_each(|e| e.collect_variables_at(byte_index, variables)),
+
+            TypedExpr::Call { fun, args, .. } => std::iter::once(fun.as_ref())
+                .chain(args.iter().map(|arg| &arg.value))
+                .filter(|e| encloses(e.location()))
+                .for_each(|e| e.collect_variables_at(byte_index, variables)),
+
+            TypedExpr::BinOp { left, right, .. } => [left, right]
+                .into_iter()
+                .filter(|e| encloses(e.location()))
+                .for_each(|e| e.collect_variables_at(byte_index, variables)),
+
+            TypedExpr::RecordUpdate { spread, args, .. } => std::iter::once(spread.as_ref())
+                .chain(args.iter().map(|arg| &arg.value))
+                .filter(|e| encloses(e.location()))
+                .for_each(|e| e.collect_variables_at(byte_index, variables)),
+
+            TypedExpr::Assignment { value, .. }
+            | TypedExpr::RecordAccess { record: value, .. }
+            | TypedExpr::TupleIndex { tuple: value, .. }
+            | TypedExpr::UnOp { value, .. } => {
+                if encloses(value.location()) {
+                    value.collect_variables_at(byte_index, variables);
+                }
+            }
+        }
+    }
+
     pub fn void(location: Span) -> Self {
         TypedExpr::Var {
             name: "Void".to_string(),
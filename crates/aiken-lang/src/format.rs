@@ -258,8 +258,11 @@ impl<'comments> Formatter<'comments> {
                 body,
                 end_position,
                 on_test_failure,
+                decorators,
                 ..
-            }) => self.definition_test(name, args, body, *end_position, on_test_failure),
+            }) => {
+                self.definition_test(name, args, body, *end_position, on_test_failure, decorators)
+            }
 
             Definition::Benchmark(Function {
                 name,
@@ -267,8 +270,16 @@ impl<'comments> Formatter<'comments> {
                 body,
                 end_position,
                 on_test_failure,
+                decorators,
                 ..
-            }) => self.definition_benchmark(name, args, body, *end_position, on_test_failure),
+            }) => self.definition_benchmark(
+                name,
+                args,
+                body,
+                *end_position,
+                on_test_failure,
+                decorators,
+            ),
 
             Definition::TypeAlias(TypeAlias {
                 alias,
@@ -650,10 +661,13 @@ impl<'comments> Formatter<'comments> {
         body: &'a UntypedExpr,
         end_location: usize,
         on_test_failure: &'a OnTestFailure,
+        decorators: &'a [Decorator],
     ) -> Document<'a> {
         // Fn name and args
-        let head = keyword
-            .to_doc()
+        let head = self
+            .decorator(decorators)
+            .append(if decorators.is_empty() { nil() } else { line() })
+            .append(keyword)
             .append(" ")
             .append(name)
             .append(wrap_args(args.iter().map(|e| (self.fn_arg_via(e), false))))
@@ -692,8 +706,17 @@ impl<'comments> Formatter<'comments> {
         body: &'a UntypedExpr,
         end_location: usize,
         on_test_failure: &'a OnTestFailure,
+        decorators: &'a [Decorator],
     ) -> Document<'a> {
-        self.definition_test_or_bench("test", name, args, body, end_location, on_test_failure)
+        self.definition_test_or_bench(
+            "test",
+            name,
+            args,
+            body,
+            end_location,
+            on_test_failure,
+            decorators,
+        )
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -704,8 +727,17 @@ impl<'comments> Formatter<'comments> {
         body: &'a UntypedExpr,
         end_location: usize,
         on_test_failure: &'a OnTestFailure,
+        decorators: &'a [Decorator],
     ) -> Document<'a> {
-        self.definition_test_or_bench("bench", name, args, body, end_location, on_test_failure)
+        self.definition_test_or_bench(
+            "bench",
+            name,
+            args,
+            body,
+            end_location,
+            on_test_failure,
+            decorators,
+        )
     }
 
     fn definition_validator<'a>(
@@ -1826,6 +1858,16 @@ impl<'comments> Formatter<'comments> {
                     docvec!["@tag(", self.uint(value, base), ")"]
                 }
                 crate::ast::DecoratorKind::List => "@list".to_doc(),
+                crate::ast::DecoratorKind::Budget { mem, cpu } => {
+                    let mut fields = Vec::new();
+                    if let Some((value, base)) = mem {
+                        fields.push(docvec!["mem: ", self.uint(value, base)]);
+                    }
+                    if let Some((value, base)) = cpu {
+                        fields.push(docvec!["cpu: ", self.uint(value, base)]);
+                    }
+                    docvec!["@budget(", join(fields, ", ".to_doc()), ")"]
+                }
             }),
             line(),
         )
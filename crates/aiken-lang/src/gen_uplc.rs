@@ -56,7 +56,11 @@ use uplc::{
     builder::{CONSTR_FIELDS_EXPOSER, CONSTR_INDEX_EXPOSER, EXPECT_ON_LIST},
     builtins::DefaultFunction,
     machine::cost_model::ExBudget,
-    optimize::{aiken_optimize_and_intern, interner::CodeGenInterner, shrinker::NO_INLINE},
+    optimize::{
+        OptLevel, aiken_optimize_and_intern_tracking, interner::CodeGenInterner,
+        shrinker::NO_INLINE,
+    },
+    source_map::{LocatedTerms, SourceLocation, SourceMap},
 };
 
 type Otherwise = Option<AirTree>;
@@ -74,8 +78,10 @@ pub struct CodeGenerator<'a> {
     data_types: IndexMap<&'a DataTypeKey, &'a TypedDataType>,
     module_types: IndexMap<&'a str, &'a TypeInfo>,
     module_src: IndexMap<&'a str, &'a (String, LineNumbers)>,
-    /// immutable option
+    /// immutable options
     tracing: TraceLevel,
+    /// Level to optimize programs for, if at all.
+    optimize: Option<OptLevel>,
     /// mutable index maps that are reset
     defined_functions: IndexMap<FunctionAccessKey, ()>,
     special_functions: CodeGenSpecialFuncs,
@@ -85,6 +91,8 @@ pub struct CodeGenerator<'a> {
     /// mutable and reset as well
     interner: AirInterner,
     id_gen: IdGenerator,
+    source_anchors: HashMap<String, SourceLocation>,
+    located_terms: LocatedTerms,
 }
 
 impl<'a> CodeGenerator<'a> {
@@ -109,12 +117,31 @@ impl<'a> CodeGenerator<'a> {
             module_types,
             module_src,
             tracing: tracing.trace_level(true),
+            optimize: Some(OptLevel::default()),
             defined_functions: IndexMap::new(),
             special_functions: CodeGenSpecialFuncs::new(),
             code_gen_functions: IndexMap::new(),
             cyclic_functions: IndexMap::new(),
             interner: AirInterner::new(),
             id_gen: IdGenerator::new(),
+            source_anchors: HashMap::new(),
+            located_terms: LocatedTerms::new(),
+        }
+    }
+
+    /// Leave generated programs as they are before any optimization pass. Those may still hold
+    /// markers meant for the optimizer, which 'clean_up_no_inlines' removes prior to evaluation.
+    pub fn without_optimization(self) -> Self {
+        CodeGenerator {
+            optimize: None,
+            ..self
+        }
+    }
+
+    pub fn with_opt_level(self, level: OptLevel) -> Self {
+        CodeGenerator {
+            optimize: Some(level),
+            ..self
         }
     }
 
@@ -124,12 +151,24 @@ impl<'a> CodeGenerator<'a> {
         self.cyclic_functions = IndexMap::new();
         self.interner = AirInterner::new();
         self.id_gen = IdGenerator::new();
+        self.source_anchors = HashMap::new();
+        self.located_terms = LocatedTerms::new();
         if reset_special_functions {
             self.special_functions = CodeGenSpecialFuncs::new();
         }
     }
 
     pub fn generate(&mut self, validator: &TypedValidator, module_name: &str) -> Program<Name> {
+        self.generate_with_source_map(validator, module_name).0
+    }
+
+    /// Like 'generate', but also returns a source map relating the generated
+    /// program to the validator's source code.
+    pub fn generate_with_source_map(
+        &mut self,
+        validator: &TypedValidator,
+        module_name: &str,
+    ) -> (Program<Name>, SourceMap) {
         let context_name = "__context__".to_string();
         let context_name_interned = introduce_name(&mut self.interner, &context_name);
         validator.params.iter().for_each(|arg| {
@@ -164,7 +203,9 @@ impl<'a> CodeGenerator<'a> {
                 .for_each(|arg_name| self.interner.pop_text(arg_name.to_string()))
         });
 
-        self.finalize(term)
+        let root = self.source_location(module_name, validator.location);
+
+        self.finalize(term, root)
     }
 
     pub fn generate_raw(
@@ -173,6 +214,17 @@ impl<'a> CodeGenerator<'a> {
         args: &[TypedArg],
         module_name: &str,
     ) -> Program<Name> {
+        self.generate_raw_with_source_map(body, args, module_name).0
+    }
+
+    /// Like 'generate_raw', but also returns a source map relating the
+    /// generated program to the expression's source code.
+    pub fn generate_raw_with_source_map(
+        &mut self,
+        body: &TypedExpr,
+        args: &[TypedArg],
+        module_name: &str,
+    ) -> (Program<Name>, SourceMap) {
         args.iter().for_each(|arg| {
             arg.get_variable_name()
                 .iter()
@@ -202,7 +254,9 @@ impl<'a> CodeGenerator<'a> {
                 .for_each(|arg_name| self.interner.pop_text(arg_name.to_string()))
         });
 
-        self.finalize(term)
+        let root = self.source_location(module_name, body.location());
+
+        self.finalize(term, root)
     }
 
     fn new_program<T>(&self, term: Term<T>) -> Program<T> {
@@ -214,10 +268,41 @@ impl<'a> CodeGenerator<'a> {
         Program { version, term }
     }
 
-    fn finalize(&mut self, mut term: Term<Name>) -> Program<Name> {
+    fn source_location(&self, module_name: &str, location: Span) -> Option<SourceLocation> {
+        let (_, line_numbers) = self.module_src.get(module_name)?;
+
+        Some(SourceLocation {
+            module: module_name.to_string(),
+            start: location.start,
+            end: location.end,
+            line: line_numbers.line_number(location.start)?,
+            name: None,
+        })
+    }
+
+    fn finalize(
+        &mut self,
+        mut term: Term<Name>,
+        root: Option<SourceLocation>,
+    ) -> (Program<Name>, SourceMap) {
         term = self.special_functions.apply_used_functions(term);
 
-        let program = aiken_optimize_and_intern(self.new_program(term));
+        let mut tracked = SourceMap::new();
+        tracked.locate_terms(&term, &std::mem::take(&mut self.located_terms));
+
+        let program = match self.optimize {
+            Some(level) => {
+                aiken_optimize_and_intern_tracking(self.new_program(term), level, &mut tracked)
+            }
+            None => {
+                let mut program = self.new_program(term);
+                CodeGenInterner::new().program(&mut program);
+                program
+            }
+        };
+
+        let mut source_map = SourceMap::from_bindings(&program.term, root, &self.source_anchors);
+        source_map.merge(tracked);
 
         // This is very important to call here.
         // If this isn't done, re-using the same instance
@@ -228,7 +313,22 @@ impl<'a> CodeGenerator<'a> {
         // method here.
         self.reset(true);
 
-        program
+        (program, source_map)
+    }
+
+    /// Like 'build', for an expression computed on its own (e.g. the last one of a sequence, or
+    /// the body of a branch), whose term is related to the expression's location.
+    fn build_located(&mut self, body: &TypedExpr, module_build_name: &str) -> AirTree {
+        let tree = self.build(body, module_build_name, &[]);
+
+        self.locate(module_build_name, body.location(), tree)
+    }
+
+    fn locate(&self, module_build_name: &str, location: Span, tree: AirTree) -> AirTree {
+        match self.source_location(module_build_name, location) {
+            Some(location) => AirTree::located(location, tree),
+            None => tree,
+        }
     }
 
     // TODO: pass mono types to build so monomorphization
@@ -247,6 +347,7 @@ impl<'a> CodeGenerator<'a> {
                 pattern,
                 kind,
                 comment,
+                ..
             } = body
             else {
                 panic!("Dangling expressions without an assignment")
@@ -289,7 +390,11 @@ impl<'a> CodeGenerator<'a> {
 
             let (then, context) = context.split_first().unwrap();
 
-            let then = self.build(then, module_build_name, context);
+            let then = if context.is_empty() {
+                self.build_located(then, module_build_name)
+            } else {
+                self.build(then, module_build_name, context)
+            };
 
             let tree = self.assignment(
                 pattern,
@@ -308,7 +413,7 @@ impl<'a> CodeGenerator<'a> {
             // Now pop off interned pattern
             pop_pattern(&mut self.interner, pattern);
 
-            tree
+            self.locate(module_build_name, *location, tree)
         } else {
             match body {
                 TypedExpr::Assignment { .. } => {
@@ -572,7 +677,7 @@ impl<'a> CodeGenerator<'a> {
                 } => AirTree::trace(
                     self.build(text, module_build_name, &[]),
                     tipo.clone(),
-                    self.build(then, module_build_name, &[]),
+                    self.build_located(then, module_build_name),
                 ),
 
                 TypedExpr::When {
@@ -591,7 +696,7 @@ impl<'a> CodeGenerator<'a> {
                         // Intern vars from pattern here
                         introduce_pattern(&mut self.interner, &last_clause.pattern);
 
-                        let clause_then = self.build(&last_clause.then, module_build_name, &[]);
+                        let clause_then = self.build_located(&last_clause.then, module_build_name);
 
                         let subject_type = subject.tipo();
 
@@ -662,16 +767,16 @@ impl<'a> CodeGenerator<'a> {
                     ..
                 } => {
                     branches.iter().rfold(
-                        self.build(final_else, module_build_name, &[]),
+                        self.build_located(final_else, module_build_name),
                         |acc, branch| {
                             let condition = self.build(&branch.condition, module_build_name, &[]);
 
                             match &branch.is {
-                                Some((pattern, tipo)) => {
+                                Some((pattern, tipo, _)) => {
                                     introduce_pattern(&mut self.interner, pattern);
                                     self.interner.intern("acc_var".to_string());
 
-                                    let body = self.build(&branch.body, module_build_name, &[]);
+                                    let body = self.build_located(&branch.body, module_build_name);
 
                                     let acc_var =
                                         self.interner.lookup_interned(&"acc_var".to_string());
@@ -706,7 +811,7 @@ impl<'a> CodeGenerator<'a> {
                                 None => AirTree::if_branch(
                                     tipo.clone(),
                                     condition,
-                                    self.build(&branch.body, module_build_name, &[]),
+                                    self.build_located(&branch.body, module_build_name),
                                     acc,
                                 ),
                             }
@@ -2776,7 +2881,7 @@ impl<'a> CodeGenerator<'a> {
                             .collect_vec(),
                         // The one reason we have to pass in mutable self
                         // So we can build the TypedExpr into Air
-                        self.build(then, module_build_name, &[]),
+                        self.build_located(then, module_build_name),
                         true,
                     ),
                     self.handle_decision_tree(
@@ -3614,10 +3719,9 @@ impl<'a> CodeGenerator<'a> {
                                 })
                                 .collect_vec();
 
-                            let mut function_air_tree_body = AirTree::no_op(self.build(
+                            let mut function_air_tree_body = AirTree::no_op(self.build_located(
                                 &function_def.body,
                                 &generic_function_key.module_name,
-                                &[],
                             ));
 
                             function_air_tree_body.traverse_tree_with(&mut |air_tree, _| {
@@ -3656,11 +3760,11 @@ impl<'a> CodeGenerator<'a> {
                             })
                             .collect_vec();
 
-                        let mut function_air_tree_body = AirTree::no_op(self.build(
-                            &function_def.body,
-                            &generic_function_key.module_name,
-                            &[],
-                        ));
+                        let mut function_air_tree_body =
+                            AirTree::no_op(self.build_located(
+                                &function_def.body,
+                                &generic_function_key.module_name,
+                            ));
 
                         function_air_tree_body.traverse_tree_with(&mut |air_tree, _| {
                             erase_opaque_type_operations(air_tree, &self.data_types);
@@ -4308,12 +4412,31 @@ impl<'a> CodeGenerator<'a> {
                 variant_name,
                 variant,
             } => {
+                let access_key = FunctionAccessKey {
+                    module_name: module_name.clone(),
+                    function_name: func_name.clone(),
+                };
+
                 let func_name = if module_name.is_empty() {
                     format!("{func_name}{variant_name}")
                 } else {
                     format!("{module_name}_{func_name}{variant_name}")
                 };
 
+                if let Some(location) = self
+                    .functions
+                    .get(&access_key)
+                    .and_then(|function| self.source_location(&module_name, function.location))
+                {
+                    self.source_anchors.insert(
+                        func_name.clone(),
+                        SourceLocation {
+                            name: Some(access_key.function_name),
+                            ..location
+                        },
+                    );
+                }
+
                 match variant {
                     air::FunctionVariants::Standard(params) => {
                         let mut func_body = arg_stack.pop().unwrap();
@@ -4828,7 +4951,7 @@ impl<'a> CodeGenerator<'a> {
                     Some(term)
                 } else {
                     let mut term = Term::empty_list();
-                    for (arg, tipo) in args.into_iter().zip(tuple_sub_types.into_iter()).rev() {
+                    for (arg, tipo) in args.into_iter().zip(tuple_sub_types).rev() {
                         term = Term::mk_cons()
                             .apply(builder::convert_type_to_data(arg, &tipo, &self.data_types))
                             .apply(term);
@@ -5143,6 +5266,13 @@ impl<'a> CodeGenerator<'a> {
             }
 
             Air::NoOp => None,
+            Air::Located { location } => {
+                if let Some(term) = arg_stack.last() {
+                    self.located_terms.insert(term, location);
+                }
+
+                None
+            }
             Air::SoftCastLet { name, tipo } => {
                 let value = arg_stack.pop().unwrap();
                 let then = arg_stack.pop().unwrap();
@@ -3,7 +3,7 @@ use crate::{
     tipo::{Type, ValueConstructor},
 };
 use std::rc::Rc;
-use uplc::builtins::DefaultFunction;
+use uplc::{builtins::DefaultFunction, source_map::SourceLocation};
 
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub enum ExpectLevel {
@@ -187,6 +187,9 @@ pub enum Air {
         tipo: Rc<Type>,
     },
     NoOp,
+    Located {
+        location: SourceLocation,
+    },
     FieldsEmpty {
         list_decorator: bool,
     },
@@ -6,7 +6,7 @@ use crate::{
 
 use itertools::Itertools;
 use std::{borrow::BorrowMut, rc::Rc, slice::Iter};
-use uplc::{builder::INNER_EXPECT_ON_LIST, builtins::DefaultFunction};
+use uplc::{builder::INNER_EXPECT_ON_LIST, builtins::DefaultFunction, source_map::SourceLocation};
 
 #[derive(Clone, Debug, PartialEq, Copy)]
 pub enum Fields {
@@ -217,6 +217,10 @@ pub enum AirTree {
     NoOp {
         then: Box<AirTree>,
     },
+    Located {
+        location: SourceLocation,
+        then: Box<AirTree>,
+    },
     // End Statements
 
     // Expressions
@@ -805,6 +809,14 @@ impl AirTree {
         AirTree::NoOp { then: then.into() }
     }
 
+    /// Relate the term generated from the given tree to the source code it comes from.
+    pub fn located(location: SourceLocation, then: AirTree) -> AirTree {
+        AirTree::Located {
+            location,
+            then: then.into(),
+        }
+    }
+
     pub fn fields_empty(
         constr: AirTree,
         then: AirTree,
@@ -1070,6 +1082,12 @@ impl AirTree {
                 air_vec.push(Air::NoOp);
                 then.create_air_vec(air_vec);
             }
+            AirTree::Located { location, then } => {
+                air_vec.push(Air::Located {
+                    location: location.clone(),
+                });
+                then.create_air_vec(air_vec);
+            }
             AirTree::Int { value } => air_vec.push(Air::Int {
                 value: value.clone(),
             }),
@@ -1330,7 +1348,8 @@ impl AirTree {
             | AirTree::PairAccessor { then, .. }
             | AirTree::FieldsEmpty { then, .. }
             | AirTree::ListEmpty { then, .. }
-            | AirTree::NoOp { then } => then.return_type(),
+            | AirTree::NoOp { then }
+            | AirTree::Located { then, .. } => then.return_type(),
         }
     }
 
@@ -1394,6 +1413,7 @@ impl AirTree {
             | AirTree::FieldsEmpty { .. }
             | AirTree::ListEmpty { .. }
             | AirTree::NoOp { .. }
+            | AirTree::Located { .. }
             | AirTree::Int { .. }
             | AirTree::String { .. }
             | AirTree::ByteArray { .. }
@@ -1601,6 +1621,7 @@ impl AirTree {
             AirTree::DefineFunc { .. }
             | AirTree::DefineCyclicFuncs { .. }
             | AirTree::NoOp { .. }
+            | AirTree::Located { .. }
             | AirTree::Int { .. }
             | AirTree::String { .. }
             | AirTree::ByteArray { .. }
@@ -1629,7 +1650,7 @@ impl AirTree {
         }
 
         match self {
-            AirTree::NoOp { then } => {
+            AirTree::NoOp { then } | AirTree::Located { then, .. } => {
                 then.do_traverse_tree_with(tree_path, current_depth + 1, Fields::FirstField, with);
             }
             AirTree::When {
@@ -2101,7 +2122,7 @@ impl AirTree {
                     }
                     _ => panic!("Tree Path index outside tree children nodes"),
                 },
-                AirTree::NoOp { then } => match field {
+                AirTree::NoOp { then } | AirTree::Located { then, .. } => match field {
                     Fields::FirstField => then.as_mut().do_find_air_tree_node(tree_path_iter),
                     _ => panic!("Tree Path index outside tree children nodes"),
                 },
@@ -5,15 +5,18 @@ use std::sync::{
 
 pub mod ast;
 pub mod builtins;
+pub mod coverage;
 pub mod error;
 pub mod expr;
 pub mod format;
 pub mod gen_uplc;
 pub mod levenshtein;
 pub mod line_numbers;
+pub mod mutation;
 pub mod parser;
 pub mod plutus_version;
 pub mod pretty;
+pub mod symbols;
 pub mod test_framework;
 pub mod tipo;
 pub mod utils;
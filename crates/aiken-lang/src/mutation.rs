@@ -0,0 +1,533 @@
+use crate::{
+    ast::{
+        AssignmentKind, BinOp, Definition, Pattern, Span, TypedFunction, TypedModule, TypedPattern,
+        TypedValidator,
+    },
+    expr::TypedExpr,
+    pretty::Documentable,
+    tipo::ValueConstructorVariant,
+};
+use std::fmt::{self, Display};
+
+/// A small, systematic change to the code of a function or validator handler, meant to be
+/// caught by tests. A mutation which no test catches (a.k.a a surviving mutant) points at a
+/// behaviour tests do not pin down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mutation {
+    pub module: String,
+    /// The function, or validator handler (as `validator.handler`), holding the mutation.
+    pub function: String,
+    pub location: Span,
+    pub kind: MutationKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MutationKind {
+    /// A comparison replaced by its negation, or `&&` and `||` swapped.
+    Operator { from: BinOp, to: BinOp },
+    /// A boolean literal, returned from a function or branch, replaced by its opposite.
+    Boolean { from: bool },
+    /// An `expect` removed from a sequence. Only expectations which don't bind any variable can
+    /// be removed, since what follows may otherwise depend on them.
+    DeleteExpect,
+    /// The body of a `when` clause replaced by a failure, as if the clause didn't exist.
+    DropClause { index: usize },
+}
+
+impl Display for MutationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MutationKind::Operator { from, to } => write!(
+                f,
+                "replaced `{}` with `{}`",
+                from.to_doc().to_pretty_string(80),
+                to.to_doc().to_pretty_string(80)
+            ),
+            MutationKind::Boolean { from } => write!(
+                f,
+                "returned `{}` instead of `{}`",
+                bool_name(!from),
+                bool_name(*from)
+            ),
+            MutationKind::DeleteExpect => write!(f, "deleted `expect`"),
+            MutationKind::DropClause { index } => write!(f, "dropped `when` clause #{}", index + 1),
+        }
+    }
+}
+
+/// List every mutation applicable to the functions and validator handlers of a module. Tests
+/// and benchmarks aren't mutated.
+pub fn mutations(module: &TypedModule) -> Vec<Mutation> {
+    let mut mutator = Mutator::new(&module.name, None);
+
+    mutator.module(&mut module.clone());
+
+    mutator.mutations
+}
+
+/// Apply a mutation, obtained from [`mutations`], to a copy of the module. Returns `None` when
+/// the mutation doesn't belong to the module.
+pub fn mutate_module(module: &TypedModule, mutation: &Mutation) -> Option<TypedModule> {
+    if module.name != mutation.module {
+        return None;
+    }
+
+    let mut mutated = module.clone();
+
+    let mut mutator = Mutator::new(&module.name, Some(mutation));
+
+    mutator.module(&mut mutated);
+
+    if mutator.applied { Some(mutated) } else { None }
+}
+
+struct Mutator<'a> {
+    module: &'a str,
+    function: String,
+    target: Option<&'a Mutation>,
+    mutations: Vec<Mutation>,
+    applied: bool,
+}
+
+impl<'a> Mutator<'a> {
+    fn new(module: &'a str, target: Option<&'a Mutation>) -> Self {
+        Mutator {
+            module,
+            function: String::new(),
+            target,
+            mutations: vec![],
+            applied: false,
+        }
+    }
+
+    /// Record a possible mutation, and tell whether it's the one to apply. Mutations sharing a
+    /// location and kind are indistinguishable, so only the first one is ever considered.
+    fn candidate(&mut self, location: Span, kind: MutationKind) -> bool {
+        let mutation = Mutation {
+            module: self.module.to_string(),
+            function: self.function.clone(),
+            location,
+            kind,
+        };
+
+        match self.target {
+            None => {
+                if !self.mutations.contains(&mutation) {
+                    self.mutations.push(mutation);
+                }
+                false
+            }
+            Some(target) if !self.applied && target == &mutation => {
+                self.applied = true;
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    fn module(&mut self, module: &mut TypedModule) {
+        for definition in module.definitions.iter_mut() {
+            match definition {
+                Definition::Fn(function) => {
+                    self.function = function.name.clone();
+                    self.function_body(function);
+                }
+                Definition::Validator(validator) => {
+                    let name = validator.name.clone();
+
+                    for handler in validator
+                        .handlers
+                        .iter_mut()
+                        .chain(std::iter::once(&mut validator.fallback))
+                    {
+                        self.function = TypedValidator::handler_name(&name, &handler.name);
+                        self.function_body(handler);
+                    }
+                }
+                Definition::TypeAlias(..)
+                | Definition::DataType(..)
+                | Definition::Use(..)
+                | Definition::ModuleConstant(..)
+                | Definition::Test(..)
+                | Definition::Benchmark(..) => {}
+            }
+        }
+    }
+
+    fn function_body(&mut self, function: &mut TypedFunction) {
+        self.expr(&mut function.body, true);
+    }
+
+    /// Visit an expression, knowing whether its value is the one returned by the enclosing
+    /// function (i.e. whether it's in tail position).
+    fn expr(&mut self, expr: &mut TypedExpr, tail: bool) {
+        match expr {
+            TypedExpr::UInt { .. }
+            | TypedExpr::String { .. }
+            | TypedExpr::ByteArray { .. }
+            | TypedExpr::CurvePoint { .. }
+            | TypedExpr::ModuleSelect { .. }
+            | TypedExpr::ErrorTerm { .. } => {}
+
+            TypedExpr::Var {
+                location,
+                name,
+                constructor,
+            } => {
+                if !tail || !constructor.tipo.is_bool() {
+                    return;
+                }
+
+                if let ValueConstructorVariant::Record {
+                    name: constructor_name,
+                    ..
+                } = &mut constructor.variant
+                {
+                    let from = name == "True";
+
+                    if (from || name == "False")
+                        && self.candidate(*location, MutationKind::Boolean { from })
+                    {
+                        *name = bool_name(!from).to_string();
+                        *constructor_name = name.clone();
+                    }
+                }
+            }
+
+            TypedExpr::Sequence { expressions, .. } => {
+                let last = expressions.len().saturating_sub(1);
+
+                let mut deleted = None;
+
+                for (index, expression) in expressions.iter_mut().enumerate() {
+                    if index < last
+                        && is_deletable_expect(expression)
+                        && self.candidate(expression.location(), MutationKind::DeleteExpect)
+                    {
+                        deleted = Some(index);
+                    }
+
+                    self.expr(expression, tail && index == last);
+                }
+
+                if let Some(index) = deleted {
+                    expressions.remove(index);
+                }
+            }
+
+            TypedExpr::Pipeline { expressions, .. } => {
+                let last = expressions.len().saturating_sub(1);
+
+                for (index, expression) in expressions.iter_mut().enumerate() {
+                    self.expr(expression, tail && index == last);
+                }
+            }
+
+            TypedExpr::Fn { body, .. } => self.expr(body, true),
+
+            TypedExpr::List { elements, tail, .. } => {
+                for element in elements {
+                    self.expr(element, false);
+                }
+
+                if let Some(tail) = tail {
+                    self.expr(tail, false);
+                }
+            }
+
+            TypedExpr::Call { fun, args, .. } => {
+                self.expr(fun, false);
+
+                for arg in args {
+                    self.expr(&mut arg.value, false);
+                }
+            }
+
+            TypedExpr::BinOp {
+                location,
+                name,
+                left,
+                right,
+                ..
+            } => {
+                if let Some(to) = opposite(*name) {
+                    if self.candidate(*location, MutationKind::Operator { from: *name, to }) {
+                        *name = to;
+                    }
+                }
+
+                self.expr(left, false);
+                self.expr(right, false);
+            }
+
+            TypedExpr::Assignment { value, .. } => self.expr(value, false),
+
+            TypedExpr::Trace { then, text, .. } => {
+                self.expr(then, tail);
+                self.expr(text, false);
+            }
+
+            TypedExpr::When {
+                subject, clauses, ..
+            } => {
+                self.expr(subject, false);
+
+                let droppable = clauses.len() > 1;
+
+                for (index, clause) in clauses.iter_mut().enumerate() {
+                    if droppable
+                        && !matches!(clause.then, TypedExpr::ErrorTerm { .. })
+                        && self.candidate(clause.location, MutationKind::DropClause { index })
+                    {
+                        clause.then = TypedExpr::ErrorTerm {
+                            location: clause.then.location(),
+                            tipo: clause.then.tipo(),
+                        };
+                    }
+
+                    self.expr(&mut clause.then, tail);
+                }
+            }
+
+            TypedExpr::If {
+                branches,
+                final_else,
+                ..
+            } => {
+                for branch in branches.iter_mut() {
+                    self.expr(&mut branch.condition, false);
+                    self.expr(&mut branch.body, tail);
+                }
+
+                self.expr(final_else, tail);
+            }
+
+            TypedExpr::RecordAccess { record, .. } => self.expr(record, false),
+
+            TypedExpr::Tuple { elems, .. } => {
+                for elem in elems {
+                    self.expr(elem, false);
+                }
+            }
+
+            TypedExpr::Pair { fst, snd, .. } => {
+                self.expr(fst, false);
+                self.expr(snd, false);
+            }
+
+            TypedExpr::TupleIndex { tuple, .. } => self.expr(tuple, false),
+
+            TypedExpr::RecordUpdate { spread, args, .. } => {
+                self.expr(spread, false);
+
+                for arg in args {
+                    self.expr(&mut arg.value, false);
+                }
+            }
+
+            TypedExpr::UnOp { value, .. } => self.expr(value, false),
+        }
+    }
+}
+
+/// The operator a binary operator is mutated into: comparisons are negated while `&&` and `||`
+/// are swapped. Both preserve the type of operands.
+fn opposite(op: BinOp) -> Option<BinOp> {
+    match op {
+        BinOp::And => Some(BinOp::Or),
+        BinOp::Or => Some(BinOp::And),
+        BinOp::Eq => Some(BinOp::NotEq),
+        BinOp::NotEq => Some(BinOp::Eq),
+        BinOp::LtInt => Some(BinOp::GtEqInt),
+        BinOp::GtEqInt => Some(BinOp::LtInt),
+        BinOp::GtInt => Some(BinOp::LtEqInt),
+        BinOp::LtEqInt => Some(BinOp::GtInt),
+        BinOp::AddInt | BinOp::SubInt | BinOp::MultInt | BinOp::DivInt | BinOp::ModInt => None,
+    }
+}
+
+fn bool_name(value: bool) -> &'static str {
+    if value { "True" } else { "False" }
+}
+
+fn is_deletable_expect(expr: &TypedExpr) -> bool {
+    matches!(
+        expr,
+        TypedExpr::Assignment {
+            kind: AssignmentKind::Expect { .. },
+            pattern,
+            ..
+        } if !binds_variables(pattern)
+    )
+}
+
+fn binds_variables(pattern: &TypedPattern) -> bool {
+    match pattern {
+        Pattern::Var { .. } | Pattern::Assign { .. } => true,
+        Pattern::Int { .. } | Pattern::ByteArray { .. } | Pattern::Discard { .. } => false,
+        Pattern::List { elements, tail, .. } => {
+            elements.iter().any(binds_variables) || tail.as_deref().is_some_and(binds_variables)
+        }
+        Pattern::Pair { fst, snd, .. } => binds_variables(fst) || binds_variables(snd),
+        Pattern::Tuple { elems, .. } => elems.iter().any(binds_variables),
+        Pattern::Constructor { arguments, .. } => {
+            arguments.iter().any(|arg| binds_variables(&arg.value))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mutation, MutationKind, mutate_module, mutations};
+    use crate::{
+        IdGenerator,
+        ast::{BinOp, Definition, ModuleKind, Tracing, TypedModule},
+        builtins,
+        expr::TypedExpr,
+        parser,
+    };
+    use indoc::indoc;
+    use std::collections::HashMap;
+
+    fn check(source_code: &str) -> TypedModule {
+        let id_gen = IdGenerator::new();
+
+        let mut module_types = HashMap::new();
+        module_types.insert("aiken".to_string(), builtins::prelude(&id_gen));
+        module_types.insert("aiken/builtin".to_string(), builtins::plutus(&id_gen));
+
+        let (mut ast, _) =
+            parser::module(source_code, ModuleKind::Lib).expect("Failed to parse module");
+
+        ast.name = "foo".to_string();
+
+        ast.infer(
+            &id_gen,
+            ModuleKind::Lib,
+            "test/project",
+            &module_types,
+            Tracing::silent(),
+            &mut vec![],
+            None,
+        )
+        .expect("Failed to type-check module")
+    }
+
+    const SOURCE_CODE: &str = indoc! { r#"
+        type Action {
+          Mint
+          Burn
+          Other
+        }
+
+        fn is_allowed(action: Action, quantity: Int) -> Bool {
+          expect quantity != 0
+          when action is {
+            Mint -> quantity > 0
+            Burn -> quantity < 0 && quantity > -10
+            Other -> False
+          }
+        }
+
+        test mint() {
+          is_allowed(Mint, 1)
+        }
+    "# };
+
+    #[test]
+    fn list_mutations() {
+        let module = check(SOURCE_CODE);
+
+        let kinds = mutations(&module)
+            .into_iter()
+            .map(|mutation| {
+                assert_eq!(mutation.module, "foo");
+                assert_eq!(mutation.function, "is_allowed");
+                mutation.kind
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                MutationKind::DeleteExpect,
+                MutationKind::Operator {
+                    from: BinOp::NotEq,
+                    to: BinOp::Eq
+                },
+                MutationKind::DropClause { index: 0 },
+                MutationKind::Operator {
+                    from: BinOp::GtInt,
+                    to: BinOp::LtEqInt
+                },
+                MutationKind::DropClause { index: 1 },
+                MutationKind::Operator {
+                    from: BinOp::And,
+                    to: BinOp::Or
+                },
+                MutationKind::Operator {
+                    from: BinOp::LtInt,
+                    to: BinOp::GtEqInt
+                },
+                MutationKind::Operator {
+                    from: BinOp::GtInt,
+                    to: BinOp::LtEqInt
+                },
+                MutationKind::DropClause { index: 2 },
+                MutationKind::Boolean { from: false },
+            ]
+        );
+    }
+
+    #[test]
+    fn mutate_only_target() {
+        let module = check(SOURCE_CODE);
+
+        let body = |module: &TypedModule| {
+            module
+                .definitions()
+                .find_map(|def| match def {
+                    Definition::Fn(function) => Some(function.body.clone()),
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        let all = mutations(&module);
+
+        let deleted = mutate_module(&module, &all[0]).expect("mutation applies");
+
+        match (body(&module), body(&deleted)) {
+            (
+                TypedExpr::Sequence { expressions, .. },
+                TypedExpr::Sequence {
+                    expressions: mutated,
+                    ..
+                },
+            ) => {
+                assert_eq!(expressions.len(), 2);
+                assert_eq!(mutated.len(), 1);
+                assert_eq!(mutated[0], expressions[1]);
+            }
+            _ => unreachable!("function body is a sequence"),
+        }
+
+        // Every mutation yields a module different from the original, and from one another.
+        let mutants = all
+            .iter()
+            .map(|mutation| body(&mutate_module(&module, mutation).unwrap()))
+            .collect::<Vec<_>>();
+
+        for (i, mutant) in mutants.iter().enumerate() {
+            assert_ne!(mutant, &body(&module));
+            assert!(mutants[i + 1..].iter().all(|other| other != mutant));
+        }
+
+        let elsewhere = Mutation {
+            module: "bar".to_string(),
+            ..all[0].clone()
+        };
+
+        assert!(mutate_module(&module, &elsewhere).is_none());
+    }
+}
@@ -44,4 +44,16 @@ mod tests {
             "#
         );
     }
+
+    #[test]
+    fn def_benchmark_budget() {
+        assert_definition!(
+            r#"
+            @budget(mem: 14000000)
+            bench foo(x via fuzz.any_int) {
+                True
+            }
+            "#
+        );
+    }
 }
@@ -8,12 +8,36 @@ pub fn decorators() -> impl Parser<Token, Vec<ast::Decorator>, Error = ParseErro
     let tag_value =
         select! { Token::Int { value, base } => ast::DecoratorKind::Tag { value, base } };
 
+    let budget_field = |label: &'static str| {
+        select! { Token::Name { name } if name == label => () }
+            .ignore_then(just(Token::Colon))
+            .ignore_then(select! { Token::Int { value, base } => (value, base) })
+    };
+
+    // Fields are given in any order, at most once each, and can be omitted.
+    let budget_value = budget_field("mem")
+        .then(just(Token::Comma).ignore_then(budget_field("cpu")).or_not())
+        .map(|(mem, cpu)| ast::DecoratorKind::Budget {
+            mem: Some(mem),
+            cpu,
+        })
+        .or(budget_field("cpu")
+            .then(just(Token::Comma).ignore_then(budget_field("mem")).or_not())
+            .map(|(cpu, mem)| ast::DecoratorKind::Budget {
+                mem,
+                cpu: Some(cpu),
+            }))
+        .then_ignore(just(Token::Comma).or_not());
+
     just(Token::At)
         .ignore_then(choice((
             select! { Token::Name { name } if name == "tag" => name }.ignore_then(
                 tag_value.delimited_by(just(Token::LeftParen), just(Token::RightParen)),
             ),
             select! { Token::Name { name } if name == "list" => ast::DecoratorKind::List },
+            select! { Token::Name { name } if name == "budget" => name }.ignore_then(
+                budget_value.delimited_by(just(Token::LeftParen), just(Token::RightParen)),
+            ),
         )))
         .map_with_span(|kind, span| ast::Decorator {
             kind,
@@ -41,6 +41,7 @@ pub fn parser() -> impl Parser<Token, ast::UntypedDefinition, Error = ParseError
                     return_annotation,
                     return_type: (),
                     on_test_failure: ast::OnTestFailure::FailImmediately,
+                    decorators: vec![],
                 })
             },
         )
@@ -68,4 +68,40 @@ mod tests {
             "#
         );
     }
+
+    #[test]
+    fn def_test_budget() {
+        assert_definition!(
+            r#"
+            @budget(mem: 1_000_000, cpu: 0xFFFF)
+            test foo() {
+                True
+            }
+            "#
+        );
+    }
+
+    #[test]
+    fn def_test_budget_cpu_first() {
+        assert_definition!(
+            r#"
+            @budget(cpu: 0xFFFF, mem: 1_000_000)
+            test foo() {
+                True
+            }
+            "#
+        );
+    }
+
+    #[test]
+    fn def_property_test_budget_cpu_only() {
+        assert_definition!(
+            r#"
+            @budget(cpu: 42,)
+            test foo(x via fuzz.any_int) fail {
+                True
+            }
+            "#
+        );
+    }
 }
@@ -5,6 +5,7 @@ use crate::{
     parser::{
         annotation,
         chain::{Chain, call::parser as call, field_access, tuple_index::parser as tuple_index},
+        definition::data_type,
         error::ParseError,
         expr::{self, bytearray, int as uint, list, pair, string, tuple, var},
         pattern,
@@ -14,7 +15,7 @@ use crate::{
 use chumsky::prelude::*;
 
 pub fn parser(keyword: Token) -> impl Parser<Token, ast::UntypedDefinition, Error = ParseError> {
-    just(keyword.clone())
+    let definition = just(keyword.clone())
         .ignore_then(select! {Token::Name {name} => name})
         .then(
             via()
@@ -49,6 +50,7 @@ pub fn parser(keyword: Token) -> impl Parser<Token, ast::UntypedDefinition, Erro
                     return_annotation: None,
                     return_type: (),
                     on_test_failure: fail.unwrap_or(OnTestFailure::FailImmediately),
+                    decorators: vec![],
                 }),
                 Token::Benchmark => ast::UntypedDefinition::Benchmark(ast::Function {
                     arguments,
@@ -61,10 +63,23 @@ pub fn parser(keyword: Token) -> impl Parser<Token, ast::UntypedDefinition, Erro
                     return_annotation: None,
                     return_type: (),
                     on_test_failure: fail.unwrap_or(OnTestFailure::FailImmediately),
+                    decorators: vec![],
                 }),
                 _ => unreachable!("Only Test and Benchmark tokens are supported"),
             },
-        )
+        );
+
+    data_type::decorators()
+        .then(definition)
+        .map(|(decorators, definition)| match definition {
+            ast::UntypedDefinition::Test(f) => {
+                ast::UntypedDefinition::Test(ast::Function { decorators, ..f })
+            }
+            ast::UntypedDefinition::Benchmark(f) => {
+                ast::UntypedDefinition::Benchmark(ast::Function { decorators, ..f })
+            }
+            _ => unreachable!("Only Test and Benchmark are supported"),
+        })
 }
 
 pub fn via() -> impl Parser<Token, ast::UntypedArgVia, Error = ParseError> {
@@ -104,6 +104,7 @@ pub fn args_and_body() -> impl Parser<Token, ast::UntypedFunction, Error = Parse
                         .or(Some(ast::Annotation::boolean(location))),
                     return_type: (),
                     on_test_failure: ast::OnTestFailure::FailImmediately,
+                    decorators: vec![],
                 }
             },
         )
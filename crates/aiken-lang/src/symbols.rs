@@ -0,0 +1,1142 @@
+use crate::{
+    ast::{
+        Annotation, CAPTURE_VARIABLE, CallArg, Definition, Pattern, RecordConstructor, Span,
+        TypedArg, TypedDataType, TypedModule, TypedPattern,
+    },
+    builtins::{BUILTIN, PRELUDE},
+    expr::TypedExpr,
+    tipo::{ModuleValueConstructor, PatternConstructor, Type, TypeVar, ValueConstructorVariant},
+};
+use std::collections::HashMap;
+
+/// Something a name may refer to, and which can be looked up across modules.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Symbol {
+    /// A top-level function or constant, or a constructor.
+    Value { module: String, name: String },
+    /// A type or a type alias.
+    Type { module: String, name: String },
+    /// A field of the records of a type.
+    Field {
+        module: String,
+        type_name: String,
+        label: String,
+    },
+    /// A function argument, a validator parameter or a variable bound by a pattern. Variables
+    /// are told apart by the location of their definition, as the type-checker does.
+    Variable { module: String, location: Span },
+    /// An imported module, whether it's referred to by its name or by an alias.
+    Module { name: String },
+}
+
+/// A name, as written in the source code of a module, along with the symbol it refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Occurrence {
+    pub location: Span,
+    pub symbol: Symbol,
+    /// Whether the occurrence defines the symbol, rather than refers to it.
+    pub is_definition: bool,
+    /// Whether the name stands for both a field label and a variable, as in `Point { x, y }`.
+    /// Such names come as two occurrences, one for each symbol.
+    pub is_shorthand: bool,
+}
+
+/// List the occurrences of every symbol a module defines or refers to, in the order they appear.
+/// Only symbols defined in source code, and the modules they're imported from, are listed: values
+/// and types of the prelude or builtins, as well as generic type variables, are left out.
+pub fn occurrences(module: &TypedModule, code: &str) -> Vec<Occurrence> {
+    let mut collector = Collector::new(module, code);
+
+    collector.module(module);
+
+    let mut occurrences = collector.occurrences;
+
+    occurrences.sort();
+    occurrences.dedup();
+
+    occurrences
+}
+
+struct Collector<'a> {
+    module: &'a str,
+    code: &'a str,
+    /// Imported modules, by the name they're referred to.
+    modules: HashMap<String, String>,
+    /// Types referable to without qualification, and the module defining them.
+    types: HashMap<String, (String, String)>,
+    occurrences: Vec<Occurrence>,
+}
+
+impl<'a> Collector<'a> {
+    fn new(module: &'a TypedModule, code: &'a str) -> Self {
+        let mut modules = HashMap::new();
+        let mut types = HashMap::new();
+
+        for definition in module.definitions() {
+            match definition {
+                Definition::Use(import) => {
+                    let name = import.module.join("/");
+
+                    for unqualified in import.unqualified.1.iter() {
+                        if unqualified.name.starts_with(char::is_uppercase) {
+                            types.insert(
+                                unqualified.variable_name().to_string(),
+                                (name.clone(), unqualified.name.clone()),
+                            );
+                        }
+                    }
+
+                    let alias = import
+                        .as_name
+                        .clone()
+                        .or_else(|| import.module.last().cloned())
+                        .unwrap_or_default();
+
+                    modules.insert(alias, name);
+                }
+                Definition::DataType(data_type) => {
+                    types.insert(
+                        data_type.name.clone(),
+                        (module.name.clone(), data_type.name.clone()),
+                    );
+                }
+                Definition::TypeAlias(alias) => {
+                    types.insert(
+                        alias.alias.clone(),
+                        (module.name.clone(), alias.alias.clone()),
+                    );
+                }
+                Definition::Fn(..)
+                | Definition::Test(..)
+                | Definition::Benchmark(..)
+                | Definition::Validator(..)
+                | Definition::ModuleConstant(..) => {}
+            }
+        }
+
+        Collector {
+            module: &module.name,
+            code,
+            modules,
+            types,
+            occurrences: vec![],
+        }
+    }
+
+    fn push(&mut self, location: Span, symbol: Symbol, is_definition: bool) {
+        self.occurrences.push(Occurrence {
+            location,
+            symbol,
+            is_definition,
+            is_shorthand: false,
+        });
+    }
+
+    fn value(&self, module: &str, name: &str) -> Option<Symbol> {
+        if is_builtin(module) {
+            return None;
+        }
+
+        Some(Symbol::Value {
+            module: module.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    fn own_value(&self, name: &str) -> Symbol {
+        Symbol::Value {
+            module: self.module.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    /// Record the alias of a module qualifying a name, written at the start of the given span.
+    fn qualifier(&mut self, location: Span, alias: &str) {
+        let Some(name) = self.modules.get(alias).cloned() else {
+            return;
+        };
+
+        let location = Span {
+            start: location.start,
+            end: location.start + alias.len(),
+        };
+
+        if self.code.get(location.start..location.end) == Some(alias) {
+            self.push(location, Symbol::Module { name }, false);
+        }
+    }
+
+    fn variable(&self, location: Span) -> Symbol {
+        Symbol::Variable {
+            module: self.module.to_string(),
+            location,
+        }
+    }
+
+    /// The field of the records of the given type (or returned by the given constructor).
+    fn field(&self, tipo: &Type, label: &str) -> Option<Symbol> {
+        let (module, type_name) = record_type(tipo)?;
+
+        if is_builtin(&module) {
+            return None;
+        }
+
+        Some(Symbol::Field {
+            module,
+            type_name,
+            label: label.to_string(),
+        })
+    }
+
+    /// The span of the first occurrence of a name within the given span, as a whole word.
+    fn find(&self, within: Span, name: &str) -> Option<Span> {
+        let text = self.code.get(within.start..within.end)?;
+
+        let is_identifier_char = |c: char| c.is_alphanumeric() || c == '_';
+
+        text.match_indices(name).find_map(|(ix, _)| {
+            let before = text[..ix].chars().next_back();
+            let after = text[ix + name.len()..].chars().next();
+
+            if before.is_some_and(is_identifier_char) || after.is_some_and(is_identifier_char) {
+                return None;
+            }
+
+            Some(Span {
+                start: within.start + ix,
+                end: within.start + ix + name.len(),
+            })
+        })
+    }
+
+    fn module(&mut self, module: &TypedModule) {
+        for definition in module.definitions() {
+            match definition {
+                Definition::Use(import) => {
+                    let name = import.module.join("/");
+
+                    if let Some(path) = self.find(import.location, &name) {
+                        self.push(path, Symbol::Module { name: name.clone() }, false);
+
+                        let alias = import.as_name.as_ref().and_then(|alias| {
+                            self.find(
+                                Span {
+                                    start: path.end,
+                                    end: import.location.end,
+                                },
+                                alias,
+                            )
+                        });
+
+                        if let Some(alias) = alias {
+                            self.push(alias, Symbol::Module { name: name.clone() }, false);
+                        }
+                    }
+
+                    for unqualified in import.unqualified.1.iter() {
+                        let location = Span {
+                            start: unqualified.location.start,
+                            end: unqualified.location.start + unqualified.name.len(),
+                        };
+
+                        if unqualified.name.starts_with(char::is_uppercase) {
+                            // Types and constructors share names, and imports bring in both.
+                            self.push(
+                                location,
+                                Symbol::Type {
+                                    module: name.clone(),
+                                    name: unqualified.name.clone(),
+                                },
+                                false,
+                            );
+                        }
+
+                        if let Some(symbol) = self.value(&name, &unqualified.name) {
+                            self.push(location, symbol, false);
+                        }
+                    }
+                }
+
+                Definition::Fn(function) => {
+                    if let Some(location) = self.find(function.location, &function.name) {
+                        self.push(location, self.own_value(&function.name), true);
+                    }
+
+                    for arg in function.arguments.iter() {
+                        self.arg(arg);
+                    }
+
+                    if let Some(annotation) = &function.return_annotation {
+                        self.annotation(annotation);
+                    }
+
+                    self.expr(&function.body);
+                }
+
+                Definition::Test(function) | Definition::Benchmark(function) => {
+                    for arg in function.arguments.iter() {
+                        self.arg(&arg.arg);
+                        self.expr(&arg.via);
+                    }
+
+                    if let Some(annotation) = &function.return_annotation {
+                        self.annotation(annotation);
+                    }
+
+                    self.expr(&function.body);
+                }
+
+                Definition::Validator(validator) => {
+                    for param in validator.params.iter() {
+                        self.arg(param);
+                    }
+
+                    for handler in validator
+                        .handlers
+                        .iter()
+                        .chain(std::iter::once(&validator.fallback))
+                    {
+                        for arg in handler.arguments.iter() {
+                            self.arg(arg);
+                        }
+
+                        if let Some(annotation) = &handler.return_annotation {
+                            self.annotation(annotation);
+                        }
+
+                        self.expr(&handler.body);
+                    }
+                }
+
+                Definition::ModuleConstant(constant) => {
+                    if let Some(location) = self.find(constant.location, &constant.name) {
+                        self.push(location, self.own_value(&constant.name), true);
+                    }
+
+                    if let Some(annotation) = &constant.annotation {
+                        self.annotation(annotation);
+                    }
+
+                    self.expr(&constant.value);
+                }
+
+                Definition::TypeAlias(alias) => {
+                    if let Some(location) = self.find(alias.location, &alias.alias) {
+                        self.push(
+                            location,
+                            Symbol::Type {
+                                module: self.module.to_string(),
+                                name: alias.alias.clone(),
+                            },
+                            true,
+                        );
+                    }
+
+                    self.annotation(&alias.annotation);
+                }
+
+                Definition::DataType(data_type) => self.data_type(data_type),
+            }
+        }
+    }
+
+    fn data_type(&mut self, data_type: &TypedDataType) {
+        let name = self.find(data_type.location, &data_type.name);
+
+        if let Some(location) = name {
+            self.push(
+                location,
+                Symbol::Type {
+                    module: self.module.to_string(),
+                    name: data_type.name.clone(),
+                },
+                true,
+            );
+        }
+
+        for constructor in data_type.constructors.iter() {
+            // The constructor of a record declared without one is named after the type, and
+            // defined by the same name.
+            let location = if constructor.sugar {
+                name
+            } else {
+                self.find(constructor.location, &constructor.name)
+            };
+
+            if let Some(location) = location {
+                self.push(location, self.own_value(&constructor.name), true);
+            }
+
+            self.constructor_arguments(data_type, constructor);
+        }
+    }
+
+    fn constructor_arguments(
+        &mut self,
+        data_type: &TypedDataType,
+        constructor: &RecordConstructor<std::rc::Rc<Type>>,
+    ) {
+        for arg in constructor.arguments.iter() {
+            if let Some(label) = &arg.label {
+                if let Some(location) = self.find(arg.location, label) {
+                    self.push(
+                        location,
+                        Symbol::Field {
+                            module: self.module.to_string(),
+                            type_name: data_type.name.clone(),
+                            label: label.clone(),
+                        },
+                        true,
+                    );
+                }
+            }
+
+            self.annotation(&arg.annotation);
+        }
+    }
+
+    fn arg(&mut self, arg: &TypedArg) {
+        if let Some(name) = arg.arg_name.get_variable_name() {
+            if is_variable_name(name) {
+                let location = arg.arg_name.location();
+
+                self.push(
+                    Span {
+                        start: location.end - name.len(),
+                        end: location.end,
+                    },
+                    self.variable(arg.location),
+                    true,
+                );
+            }
+        }
+
+        if let Some(annotation) = &arg.annotation {
+            self.annotation(annotation);
+        }
+    }
+
+    fn annotation(&mut self, annotation: &Annotation) {
+        match annotation {
+            Annotation::Constructor {
+                location,
+                module,
+                name,
+                arguments,
+            } => {
+                if let Some(alias) = module {
+                    self.qualifier(*location, alias);
+                }
+
+                let resolved = match module {
+                    Some(alias) => self
+                        .modules
+                        .get(alias)
+                        .map(|module| (module.clone(), name.clone())),
+                    None => self.types.get(name).cloned(),
+                };
+
+                if let Some((module, resolved_name)) = resolved {
+                    // Arguments only come after the name, and the module alias is lowercase.
+                    if let Some(location) = self.find(*location, name) {
+                        self.push(
+                            location,
+                            Symbol::Type {
+                                module,
+                                name: resolved_name,
+                            },
+                            false,
+                        );
+                    }
+                }
+
+                for argument in arguments {
+                    self.annotation(argument);
+                }
+            }
+
+            Annotation::Fn { arguments, ret, .. } => {
+                for argument in arguments {
+                    self.annotation(argument);
+                }
+                self.annotation(ret);
+            }
+
+            Annotation::Tuple { elems, .. } => {
+                for elem in elems {
+                    self.annotation(elem);
+                }
+            }
+
+            Annotation::Pair { fst, snd, .. } => {
+                self.annotation(fst);
+                self.annotation(snd);
+            }
+
+            Annotation::Var { .. } | Annotation::Hole { .. } => {}
+        }
+    }
+
+    /// Record the label of an argument given to a constructor, in an expression or a pattern.
+    /// Labels standing for a variable as well are recorded along with it; the variable is
+    /// defined there when the constructor is a pattern.
+    fn label<A>(
+        &mut self,
+        constructor: &Type,
+        arg: &CallArg<A>,
+        shorthand: Option<Symbol>,
+        binds: bool,
+    ) {
+        let Some(field) = arg
+            .label
+            .as_ref()
+            .and_then(|label| self.field(constructor, label))
+        else {
+            return;
+        };
+
+        match shorthand {
+            Some(variable) => {
+                for (symbol, is_definition) in [(field, false), (variable, binds)] {
+                    self.occurrences.push(Occurrence {
+                        location: arg.location,
+                        symbol,
+                        is_definition,
+                        is_shorthand: true,
+                    });
+                }
+            }
+            None => {
+                if let Some(location) = arg
+                    .label
+                    .as_ref()
+                    .and_then(|label| self.find(arg.location, label))
+                {
+                    self.push(location, field, false);
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &TypedExpr) {
+        match expr {
+            TypedExpr::UInt { .. }
+            | TypedExpr::String { .. }
+            | TypedExpr::ByteArray { .. }
+            | TypedExpr::CurvePoint { .. }
+            | TypedExpr::ErrorTerm { .. } => {}
+
+            TypedExpr::Var {
+                location,
+                constructor,
+                name,
+            } => {
+                let symbol = match &constructor.variant {
+                    ValueConstructorVariant::LocalVariable { location } => {
+                        if name.contains(CAPTURE_VARIABLE) || !is_variable_name(name) {
+                            None
+                        } else {
+                            Some(self.variable(*location))
+                        }
+                    }
+                    ValueConstructorVariant::ModuleConstant { module, name, .. }
+                    | ValueConstructorVariant::ModuleFn { module, name, .. } => {
+                        self.value(module, name)
+                    }
+                    ValueConstructorVariant::Record { module, name, .. } => {
+                        self.value(module, name)
+                    }
+                };
+
+                if let Some(symbol) = symbol {
+                    self.push(*location, symbol, false);
+                }
+            }
+
+            TypedExpr::ModuleSelect {
+                location,
+                label,
+                module_name,
+                module_alias,
+                constructor,
+                ..
+            } => {
+                self.qualifier(*location, module_alias);
+
+                let (module, name) = match constructor {
+                    ModuleValueConstructor::Record { name, .. } => (module_name, name),
+                    ModuleValueConstructor::Fn { module, name, .. }
+                    | ModuleValueConstructor::Constant { module, name, .. } => (module, name),
+                };
+
+                if let Some(symbol) = self.value(module, name) {
+                    self.push(
+                        Span {
+                            start: location.end - label.len(),
+                            end: location.end,
+                        },
+                        symbol,
+                        false,
+                    );
+                }
+            }
+
+            TypedExpr::Call { fun, args, .. } => {
+                self.expr(fun);
+
+                let is_constructor = match &**fun {
+                    TypedExpr::Var { constructor, .. } => {
+                        matches!(constructor.variant, ValueConstructorVariant::Record { .. })
+                    }
+                    TypedExpr::ModuleSelect { constructor, .. } => {
+                        matches!(constructor, ModuleValueConstructor::Record { .. })
+                    }
+                    _ => false,
+                };
+
+                let tipo = fun.tipo();
+
+                for arg in args {
+                    let shorthand = match &arg.value {
+                        TypedExpr::Var {
+                            location,
+                            name,
+                            constructor,
+                        } if is_shorthand(arg, *location, name) => match constructor.variant {
+                            ValueConstructorVariant::LocalVariable { location } => {
+                                Some(self.variable(location))
+                            }
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+
+                    if is_constructor {
+                        self.label(&tipo, arg, shorthand.clone(), false);
+                    }
+
+                    if shorthand.is_none() || !is_constructor {
+                        self.expr(&arg.value);
+                    }
+                }
+            }
+
+            TypedExpr::Fn {
+                args,
+                body,
+                return_annotation,
+                ..
+            } => {
+                for arg in args {
+                    self.arg(arg);
+                }
+
+                if let Some(annotation) = return_annotation {
+                    self.annotation(annotation);
+                }
+
+                self.expr(body);
+            }
+
+            TypedExpr::Sequence { expressions, .. } | TypedExpr::Pipeline { expressions, .. } => {
+                for expression in expressions {
+                    self.expr(expression);
+                }
+            }
+
+            TypedExpr::Assignment {
+                value,
+                pattern,
+                annotation,
+                ..
+            } => {
+                self.expr(value);
+                self.pattern(pattern);
+
+                if let Some(annotation) = annotation {
+                    self.annotation(annotation);
+                }
+            }
+
+            TypedExpr::When {
+                subject, clauses, ..
+            } => {
+                self.expr(subject);
+
+                for clause in clauses {
+                    self.pattern(&clause.pattern);
+                    self.expr(&clause.then);
+                }
+            }
+
+            TypedExpr::If {
+                branches,
+                final_else,
+                ..
+            } => {
+                for branch in branches {
+                    self.expr(&branch.condition);
+
+                    if let Some((pattern, _, annotation)) = &branch.is {
+                        self.pattern(pattern);
+
+                        if let Some(annotation) = annotation {
+                            self.annotation(annotation);
+                        }
+                    }
+
+                    self.expr(&branch.body);
+                }
+
+                self.expr(final_else);
+            }
+
+            TypedExpr::RecordAccess {
+                location,
+                label,
+                record,
+                ..
+            } => {
+                self.expr(record);
+
+                if let Some(field) = self.field(&record.tipo(), label) {
+                    self.push(
+                        Span {
+                            start: location.end - label.len(),
+                            end: location.end,
+                        },
+                        field,
+                        false,
+                    );
+                }
+            }
+
+            TypedExpr::RecordUpdate {
+                location,
+                tipo,
+                spread,
+                args,
+            } => {
+                // The constructor isn't kept past type-checking, but it's what precedes the
+                // braces, possibly qualified.
+                if let Some((module, _)) = record_type(tipo) {
+                    let written = self
+                        .code
+                        .get(location.start..spread.location().start)
+                        .and_then(|text| text.split('{').next())
+                        .map(|text| text.trim_end());
+
+                    if let Some((alias, _)) = written.and_then(|text| text.split_once('.')) {
+                        self.qualifier(*location, alias);
+                    }
+
+                    let constructor = written.and_then(|text| text.rsplit('.').next());
+
+                    if let Some(constructor) = constructor {
+                        let written = self.find(*location, constructor);
+
+                        if let (Some(written), Some(symbol)) =
+                            (written, self.value(&module, constructor))
+                        {
+                            self.push(written, symbol, false);
+                        }
+                    }
+                }
+
+                self.expr(spread);
+
+                for arg in args {
+                    if let Some(field) = self.field(tipo, &arg.label) {
+                        if let Some(location) = self.find(arg.location, &arg.label) {
+                            self.push(location, field, false);
+                        }
+                    }
+
+                    self.expr(&arg.value);
+                }
+            }
+
+            TypedExpr::Trace { then, text, .. } => {
+                self.expr(text);
+                self.expr(then);
+            }
+
+            TypedExpr::List { elements, tail, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+
+                if let Some(tail) = tail {
+                    self.expr(tail);
+                }
+            }
+
+            TypedExpr::Tuple { elems, .. } => {
+                for elem in elems {
+                    self.expr(elem);
+                }
+            }
+
+            TypedExpr::Pair { fst, snd, .. } => {
+                self.expr(fst);
+                self.expr(snd);
+            }
+
+            TypedExpr::BinOp { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+
+            TypedExpr::TupleIndex { tuple, .. } => self.expr(tuple),
+
+            TypedExpr::UnOp { value, .. } => self.expr(value),
+        }
+    }
+
+    fn pattern(&mut self, pattern: &TypedPattern) {
+        match pattern {
+            Pattern::Int { .. } | Pattern::ByteArray { .. } | Pattern::Discard { .. } => {}
+
+            Pattern::Var { location, name } => {
+                if is_variable_name(name) {
+                    self.push(*location, self.variable(*location), true);
+                }
+            }
+
+            Pattern::Assign {
+                name,
+                location,
+                pattern,
+            } => {
+                self.pattern(pattern);
+
+                self.push(
+                    Span {
+                        start: location.end - name.len(),
+                        end: location.end,
+                    },
+                    self.variable(*location),
+                    true,
+                );
+            }
+
+            Pattern::List { elements, tail, .. } => {
+                for element in elements {
+                    self.pattern(element);
+                }
+
+                if let Some(tail) = tail {
+                    self.pattern(tail);
+                }
+            }
+
+            Pattern::Pair { fst, snd, .. } => {
+                self.pattern(fst);
+                self.pattern(snd);
+            }
+
+            Pattern::Tuple { elems, .. } => {
+                for elem in elems {
+                    self.pattern(elem);
+                }
+            }
+
+            Pattern::Constructor {
+                location,
+                name,
+                arguments,
+                constructor,
+                tipo,
+                module,
+                ..
+            } => {
+                if let Some(alias) = module {
+                    self.qualifier(*location, alias);
+                }
+
+                let PatternConstructor::Record {
+                    name: constructor_name,
+                    ..
+                } = constructor;
+
+                if let Some((module, _)) = record_type(tipo) {
+                    let written = self.find(*location, name);
+
+                    if let (Some(location), Some(symbol)) =
+                        (written, self.value(&module, constructor_name))
+                    {
+                        self.push(location, symbol, false);
+                    }
+                }
+
+                for arg in arguments {
+                    let shorthand = match &arg.value {
+                        Pattern::Var { location, name } if is_shorthand(arg, *location, name) => {
+                            Some(self.variable(*location))
+                        }
+                        _ => None,
+                    };
+
+                    match shorthand {
+                        Some(variable) => self.label(tipo, arg, Some(variable), true),
+                        None => {
+                            self.label(tipo, arg, None, true);
+                            self.pattern(&arg.value);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether a value given to a constructor is a variable named after the label, standing for both
+/// as in `Point { x, y }`.
+fn is_shorthand<A>(arg: &CallArg<A>, location: Span, name: &str) -> bool {
+    arg.location == location && arg.label.as_deref() == Some(name)
+}
+
+/// The type of records, from either their type or the type of their constructor.
+fn record_type(tipo: &Type) -> Option<(String, String)> {
+    match tipo {
+        Type::Fn { ret, .. } => ret.qualifier(),
+        Type::Var { tipo, .. } => match &*tipo.borrow() {
+            TypeVar::Link { tipo } => record_type(tipo),
+            _ => None,
+        },
+        _ => tipo.qualifier(),
+    }
+}
+
+fn is_builtin(module: &str) -> bool {
+    module.is_empty() || module == PRELUDE || module == BUILTIN
+}
+
+/// Whether a name is one of a variable written in the source code, rather than one made up by
+/// the compiler.
+fn is_variable_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase()) && !name.contains(CAPTURE_VARIABLE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Symbol, occurrences};
+    use crate::{
+        IdGenerator,
+        ast::{ModuleKind, Tracing, TypedModule},
+        builtins, parser,
+    };
+    use indoc::indoc;
+    use std::collections::HashMap;
+
+    fn check(source_code: &str) -> TypedModule {
+        let id_gen = IdGenerator::new();
+
+        let mut module_types = HashMap::new();
+        module_types.insert("aiken".to_string(), builtins::prelude(&id_gen));
+        module_types.insert("aiken/builtin".to_string(), builtins::plutus(&id_gen));
+
+        let (mut ast, _) =
+            parser::module(source_code, ModuleKind::Validator).expect("Failed to parse module");
+
+        ast.name = "foo".to_string();
+
+        ast.infer(
+            &id_gen,
+            ModuleKind::Validator,
+            "test/project",
+            &module_types,
+            Tracing::silent(),
+            &mut vec![],
+            None,
+        )
+        .expect("Failed to type-check module")
+    }
+
+    const SOURCE_CODE: &str = indoc! { r#"
+        pub type Point {
+          x: Int,
+          y: Int,
+        }
+
+        pub type Shape {
+          Circle { center: Point, radius: Int }
+          Square(Point, Int)
+        }
+
+        type Origin = Point
+
+        const origin: Origin = Point { x: 0, y: 0 }
+
+        fn area(shape: Shape, precision scale: Int) -> Int {
+          let Point { x, y: why }: Point = origin
+          when shape is {
+            Circle { radius, .. } -> radius * scale + x + why
+            Square(p, side) -> side * p.x
+          }
+        }
+
+        fn make(x: Int) -> Point {
+          let p = Point { x, y: 1 }
+          Point { ..p, y: x }
+        }
+
+        validator main(param: Int) {
+          spend(datum: Option<Data>, _r: Data, _o: Data, _tx: Data) {
+            expect Some(data) = datum
+            if data is d: Point {
+              area(Circle { center: d, radius: param }, precision: 1) > make(param).y
+            } else {
+              False
+            }
+          }
+
+          else(_) {
+            fail
+          }
+        }
+    "# };
+
+    /// Describe the occurrences of a module, as "text: symbol", flagged with whether they define the
+    /// symbol and stand for both a field and a variable.
+    fn describe(source_code: &str) -> Vec<String> {
+        let module = check(source_code);
+
+        occurrences(&module, source_code)
+            .into_iter()
+            .map(|occurrence| {
+                let text = &source_code[occurrence.location.start..occurrence.location.end];
+
+                let symbol = match occurrence.symbol {
+                    Symbol::Value { name, .. } => format!("value {name}"),
+                    Symbol::Type { name, .. } => format!("type {name}"),
+                    Symbol::Field {
+                        type_name, label, ..
+                    } => format!("field {type_name}.{label}"),
+                    Symbol::Variable { location, .. } => format!("variable @{}", location.start),
+                    Symbol::Module { name } => format!("module {name}"),
+                };
+
+                format!(
+                    "{text}: {symbol}{}{}",
+                    if occurrence.is_definition {
+                        " (definition)"
+                    } else {
+                        ""
+                    },
+                    if occurrence.is_shorthand {
+                        " (shorthand)"
+                    } else {
+                        ""
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn list_occurrences() {
+        let occurrences = describe(SOURCE_CODE);
+
+        assert_eq!(
+            occurrences,
+            vec![
+                "Point: value Point (definition)",
+                "Point: type Point (definition)",
+                "x: field Point.x (definition)",
+                "y: field Point.y (definition)",
+                "Shape: type Shape (definition)",
+                "Circle: value Circle (definition)",
+                "center: field Shape.center (definition)",
+                "Point: type Point",
+                "radius: field Shape.radius (definition)",
+                "Square: value Square (definition)",
+                "Point: type Point",
+                "Origin: type Origin (definition)",
+                "Point: type Point",
+                "origin: value origin (definition)",
+                "Origin: type Origin",
+                "Point: value Point",
+                "x: field Point.x",
+                "y: field Point.y",
+                "area: value area (definition)",
+                "shape: variable @195 (definition)",
+                "Shape: type Shape",
+                "scale: variable @209 (definition)",
+                "Point: value Point",
+                "x: field Point.x (shorthand)",
+                "x: variable @254 (definition) (shorthand)",
+                "y: field Point.y",
+                "why: variable @260 (definition)",
+                "Point: type Point",
+                "origin: value origin",
+                "shape: variable @195",
+                "Circle: value Circle",
+                "radius: field Shape.radius (shorthand)",
+                "radius: variable @313 (definition) (shorthand)",
+                "radius: variable @313",
+                "scale: variable @209",
+                "x: variable @254",
+                "why: variable @260",
+                "Square: value Square",
+                "p: variable @365 (definition)",
+                "side: variable @368 (definition)",
+                "side: variable @368",
+                "p: variable @365",
+                "x: field Point.x",
+                "make: value make (definition)",
+                "x: variable @403 (definition)",
+                "Point: type Point",
+                "p: variable @428 (definition)",
+                "Point: value Point",
+                "x: field Point.x (shorthand)",
+                "x: variable @403 (shorthand)",
+                "y: field Point.y",
+                "Point: value Point",
+                "p: variable @428",
+                "y: field Point.y",
+                "x: variable @403",
+                "param: variable @490 (definition)",
+                "datum: variable @512 (definition)",
+                "data: variable @582 (definition)",
+                "datum: variable @512",
+                "data: variable @582",
+                "d: variable @611 (definition)",
+                "Point: type Point",
+                "area: value area",
+                "Circle: value Circle",
+                "center: field Shape.center",
+                "d: variable @611",
+                "radius: field Shape.radius",
+                "param: variable @490",
+                "make: value make",
+                "param: variable @490",
+                "y: field Point.y",
+            ]
+        );
+    }
+
+    #[test]
+    fn list_module_occurrences() {
+        let occurrences = describe(indoc! { r#"
+            use aiken/builtin as b
+
+            fn double(x: Int) -> Int {
+              b.add_integer(x, x)
+            }
+        "# });
+
+        assert_eq!(
+            occurrences,
+            vec![
+                "aiken/builtin: module aiken/builtin",
+                "b: module aiken/builtin",
+                "double: value double (definition)",
+                "x: variable @34 (definition)",
+                "b: module aiken/builtin",
+                "x: variable @34",
+                "x: variable @34",
+            ]
+        );
+    }
+}
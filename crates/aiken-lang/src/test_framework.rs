@@ -1,5 +1,9 @@
 use crate::{
-    ast::{BinOp, DataTypeKey, IfBranch, OnTestFailure, Span, TypedArg, TypedDataType, TypedTest},
+    ast::{
+        BinOp, DataTypeKey, Decorator, DecoratorKind, IfBranch, OnTestFailure, Span, TypedArg,
+        TypedDataType, TypedTest,
+    },
+    coverage::ProbeId,
     expr::{TypedExpr, UntypedExpr},
     format::Formatter,
     gen_uplc::CodeGenerator,
@@ -10,22 +14,36 @@ use cryptoxide::{blake2b::Blake2b, digest::Digest};
 use indexmap::IndexMap;
 use itertools::Itertools;
 use owo_colors::{OwoColorize, Stream, Stream::Stderr};
-use pallas_primitives::alonzo::{Constr, PlutusData};
+use pallas_primitives::{
+    alonzo::{Constr, PlutusData},
+    conway::{Language, ScriptHash},
+};
 use patricia_tree::PatriciaMap;
-#[cfg(not(target_family = "wasm"))]
-use std::time::Duration;
 use std::{
     borrow::Borrow,
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     convert::TryFrom,
     fmt::{Debug, Display},
     ops::Deref,
     path::PathBuf,
     rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 use uplc::{
     ast::{Constant, Data, Name, NamedDeBruijn, Program, Term},
-    machine::{cost_model::ExBudget, eval_result::EvalResult},
+    machine::{
+        cost_model::ExBudget,
+        eval_result::EvalResult,
+        profiler::Profile,
+        symbolic::{Input, Outcome, Prover},
+    },
+    optimize::{
+        OptLevel,
+        differential::{Divergence, Trace},
+    },
+    source_map::{SourceLocation, SourceMap},
+    tx::{self, script_context::PlutusScript},
 };
 use vec1::{Vec1, vec1};
 
@@ -67,8 +85,14 @@ impl Test {
         test: TypedTest,
         module_name: String,
         input_path: PathBuf,
+        limits: Limits,
     ) -> Test {
-        let program = generator.generate_raw(&test.body, &[], &module_name);
+        let (program, source_map) =
+            generator.generate_raw_with_source_map(&test.body, &[], &module_name);
+
+        // Operands of the assertion are evaluated within the test's limits as well, so that
+        // collecting a runaway test doesn't hang.
+        let deadline = limits.deadline();
 
         let assertion = match test.body.try_into() {
             Err(..) => None,
@@ -80,7 +104,7 @@ impl Test {
                         &module_name,
                     ))
                     .expect("failed to convert assertion operaand to NamedDeBruijn")
-                    .eval(ExBudget::max())
+                    .eval_version_with_deadline(limits.budget(), &Language::PlutusV3, deadline)
                     .unwrap_constant()
                     .map(|cst| (cst, side.tipo()))
                 };
@@ -99,43 +123,61 @@ impl Test {
         Test::UnitTest(UnitTest {
             input_path,
             module: module_name,
+            scenario: test
+                .return_type
+                .is_transaction()
+                .then(ScenarioScripts::default),
             name: test.name,
             program,
+            source_map,
             assertion,
             on_test_failure: test.on_test_failure,
+            limits,
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn property_test(
         input_path: PathBuf,
         module: String,
         name: String,
         on_test_failure: OnTestFailure,
         program: Program<Name>,
+        source_map: SourceMap,
         fuzzer: Fuzzer<Name>,
+        limits: Limits,
     ) -> Test {
         Test::PropertyTest(PropertyTest {
             input_path,
             module,
             name,
             program,
+            source_map,
             on_test_failure,
             fuzzer,
+            examples: vec![],
+            proof_depth: None,
+            limits,
         })
     }
 
+    /// Compile a test or benchmark. Limits set on the definition itself take precedence over the
+    /// given defaults.
     pub fn from_function_definition(
         generator: &mut CodeGenerator<'_>,
         test: TypedTest,
         module_name: String,
         input_path: PathBuf,
         kind: RunnableKind,
+        defaults: Limits,
     ) -> Test {
+        let limits = Limits::from_decorators(&test.decorators).or(defaults);
+
         if test.arguments.is_empty() {
             if matches!(kind, RunnableKind::Bench) {
                 unreachable!("benchmark must have at least one argument");
             } else {
-                Self::unit_test(generator, test, module_name, input_path)
+                Self::unit_test(generator, test, module_name, input_path, limits)
             }
         } else {
             let parameter = test.arguments.first().unwrap().to_owned();
@@ -146,7 +188,7 @@ impl Test {
 
             let stripped_type_info = convert_opaque_type(&type_info, generator.data_types(), true);
 
-            let program = generator.clone().generate_raw(
+            let (program, source_map) = generator.clone().generate_raw_with_source_map(
                 &test.body,
                 &[TypedArg {
                     tipo: stripped_type_info.clone(),
@@ -166,12 +208,14 @@ impl Test {
                     module: module_name,
                     name: test.name,
                     program,
+                    source_map,
                     on_test_failure: test.on_test_failure,
                     sampler: Sampler {
                         program: generator_program,
                         type_info,
                         stripped_type_info,
                     },
+                    limits,
                 }),
                 RunnableKind::Test => Self::property_test(
                     input_path,
@@ -179,11 +223,13 @@ impl Test {
                     test.name,
                     test.on_test_failure,
                     program,
+                    source_map,
                     Fuzzer {
                         program: generator_program,
                         stripped_type_info,
                         type_info,
                     },
+                    limits,
                 ),
             }
         }
@@ -205,10 +251,285 @@ impl Test {
             }
         }
     }
+
+    /// Replay the evaluations performed by 'run', attributing their budget
+    /// to the functions they go through. Property tests and benchmarks
+    /// accumulate the costs of every sampled value; the shrinking of
+    /// counterexamples isn't accounted for.
+    pub fn profile(
+        &self,
+        seed: u32,
+        max_success: usize,
+        plutus_version: &PlutusVersion,
+    ) -> Profile {
+        match self {
+            Test::UnitTest(unit_test) => unit_test.profile(plutus_version),
+            Test::PropertyTest(property_test) => {
+                property_test.profile(seed, max_success, plutus_version)
+            }
+            Test::Benchmark(benchmark) => benchmark.profile(seed, max_success, plutus_version),
+        }
+    }
+
+    /// Replay the evaluations performed by 'run', counting how many times each coverage probe
+    /// is reached. This is only meaningful for tests compiled from instrumented code.
+    pub fn coverage(
+        &self,
+        seed: u32,
+        max_success: usize,
+        plutus_version: &PlutusVersion,
+    ) -> BTreeMap<ProbeId, usize> {
+        let results = match self {
+            Test::UnitTest(unit_test) => vec![unit_test.eval(plutus_version)],
+            Test::PropertyTest(property_test) => property_test
+                .samples(seed, max_success)
+                .iter()
+                .map(|value| property_test.eval(value, plutus_version))
+                .collect(),
+            Test::Benchmark(benchmark) => benchmark
+                .samples(seed, max_success)
+                .iter()
+                .map(|value| benchmark.eval(value, plutus_version))
+                .collect(),
+        };
+
+        let mut hits = BTreeMap::new();
+
+        for label in results.iter().flat_map(|result| result.labels()) {
+            if let Some(probe) = ProbeId::from_label(&label) {
+                *hits.entry(probe).or_insert(0) += 1;
+            }
+        }
+
+        hits
+    }
+
+    /// Evaluate the test's program before and after every optimization pass, on the values 'run'
+    /// would try first, given the same test compiled without optimizations. Returns the first
+    /// value, if any, for which optimizing for the given level changes the outcome, along with
+    /// how it does.
+    pub fn differential(
+        &self,
+        unoptimized: &Test,
+        level: OptLevel,
+        seed: u32,
+        max_success: usize,
+        plutus_version: &PlutusVersion,
+    ) -> Option<(Option<PlutusData>, Divergence)> {
+        let values = match self {
+            Test::UnitTest(..) => vec![None],
+            Test::PropertyTest(property_test) => property_test
+                .samples(seed, max_success)
+                .into_iter()
+                .map(Some)
+                .collect(),
+            Test::Benchmark(benchmark) => benchmark
+                .samples(seed, max_success)
+                .into_iter()
+                .map(Some)
+                .collect(),
+        };
+
+        let trace = Trace::new(
+            match unoptimized {
+                Test::UnitTest(unit_test) => unit_test.program.clone(),
+                Test::PropertyTest(property_test) => property_test.program.clone(),
+                Test::Benchmark(benchmark) => benchmark.program.clone(),
+            },
+            level,
+        );
+
+        let language = plutus_version.into();
+
+        values.into_iter().find_map(|value| {
+            let divergence = trace.compare(&language, |program| match &value {
+                Some(value) => program.apply_data(value.clone()),
+                None => program,
+            })?;
+
+            Some((value, divergence))
+        })
+    }
+
+    /// Whether this is a unit test returning a transaction, to be evaluated against the project's
+    /// validators.
+    pub fn is_scenario(&self) -> bool {
+        matches!(
+            self,
+            Test::UnitTest(UnitTest {
+                scenario: Some(..),
+                ..
+            })
+        )
+    }
+
+    /// Provide the validators against which scenarios are evaluated. Other tests are left as is.
+    pub fn with_scenario_scripts(self, scripts: &ScenarioScripts) -> Self {
+        match self {
+            Test::UnitTest(UnitTest {
+                scenario: Some(..), ..
+            }) => Test::UnitTest(UnitTest {
+                scenario: Some(scripts.clone()),
+                ..self.into_unit_test()
+            }),
+            _ => self,
+        }
+    }
+
+    fn into_unit_test(self) -> UnitTest {
+        match self {
+            Test::UnitTest(unit_test) => unit_test,
+            _ => unreachable!("not a unit test"),
+        }
+    }
+
+    pub fn module(&self) -> &str {
+        match self {
+            Test::UnitTest(UnitTest { module, .. })
+            | Test::PropertyTest(PropertyTest { module, .. })
+            | Test::Benchmark(Benchmark { module, .. }) => module.as_str(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Test::UnitTest(UnitTest { name, .. })
+            | Test::PropertyTest(PropertyTest { name, .. })
+            | Test::Benchmark(Benchmark { name, .. }) => name.as_str(),
+        }
+    }
+
+    /// Provide choices of counterexamples found in previous runs, to be replayed by property
+    /// tests before sampling any new value.
+    pub fn with_examples(self, examples: Vec<Vec<u8>>) -> Self {
+        match self {
+            Test::PropertyTest(property_test) => Test::PropertyTest(PropertyTest {
+                examples,
+                ..property_test
+            }),
+            Test::UnitTest(..) | Test::Benchmark(..) => self,
+        }
+    }
+
+    /// Have property tests attempt a symbolic proof, up to the given depth, before sampling any
+    /// new value.
+    pub fn with_proof_depth(self, depth: usize) -> Self {
+        match self {
+            Test::PropertyTest(property_test) => Test::PropertyTest(PropertyTest {
+                proof_depth: Some(depth),
+                ..property_test
+            }),
+            Test::UnitTest(..) | Test::Benchmark(..) => self,
+        }
+    }
+}
+
+/// ----- Limits -----------------------------------------------------------------
+///
+/// Resources a test may consume before being aborted, either set through a '@budget' decorator
+/// or project-wide. The execution budget applies to each evaluation, that is, to each iteration
+/// of a property or a benchmark; whereas the timeout applies to the test as a whole. Limits left
+/// unset are unbounded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub mem: Option<i64>,
+    pub cpu: Option<i64>,
+    pub timeout: Option<Duration>,
+}
+
+impl Limits {
+    pub fn from_decorators(decorators: &[Decorator]) -> Self {
+        let parse = |value: &Option<(String, _)>| {
+            value.as_ref().map(|(value, _)| {
+                value
+                    .parse()
+                    .expect("budget decorator checked during type-checking")
+            })
+        };
+
+        decorators
+            .iter()
+            .find_map(|decorator| match &decorator.kind {
+                DecoratorKind::Budget { mem, cpu } => Some(Limits {
+                    mem: parse(mem),
+                    cpu: parse(cpu),
+                    timeout: None,
+                }),
+                DecoratorKind::Tag { .. } | DecoratorKind::List => None,
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn or(self, defaults: Limits) -> Self {
+        Limits {
+            mem: self.mem.or(defaults.mem),
+            cpu: self.cpu.or(defaults.cpu),
+            timeout: self.timeout.or(defaults.timeout),
+        }
+    }
+
+    pub fn budget(&self) -> ExBudget {
+        let max = ExBudget::max();
+        ExBudget {
+            mem: self.mem.unwrap_or(max.mem),
+            cpu: self.cpu.unwrap_or(max.cpu),
+        }
+    }
+
+    /// The instant past which a test starting now times out. The clock is only looked at when a
+    /// timeout is set, for it isn't available on every target.
+    fn deadline(&self) -> Option<Instant> {
+        self.timeout.map(|timeout| Instant::now() + timeout)
+    }
+
+    /// Whether an evaluation was aborted for reaching one of the limits.
+    pub fn exceeded_by(&self, result: &EvalResult) -> Option<LimitExceeded> {
+        match result.result {
+            Err(uplc::machine::Error::OutOfExError(..))
+                if self.mem.is_some() || self.cpu.is_some() =>
+            {
+                Some(LimitExceeded::Budget(self.budget()))
+            }
+            Err(uplc::machine::Error::TimedOut) => self.timeout.map(LimitExceeded::Timeout),
+            _ => None,
+        }
+    }
+}
+
+/// A test aborted for reaching one of its limits. This always counts as a failure, even for
+/// tests expected to fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitExceeded {
+    Budget(ExBudget),
+    Timeout(Duration),
+}
+
+impl Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LimitExceeded::Budget(ExBudget { mem, cpu }) => {
+                write!(f, "budget exceeded (mem: {mem}, cpu: {cpu})")
+            }
+            LimitExceeded::Timeout(timeout) => {
+                write!(f, "timed out after {}", format_duration(timeout))
+            }
+        }
+    }
+}
+
+fn format_duration(duration: &Duration) -> String {
+    if duration.as_secs() == 0 {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{}s", duration.as_secs())
+    }
 }
 
 /// ----- UnitTest -----------------------------------------------------------------
 ///
+/// A unit test may also return a transaction, in which case it describes a scenario: the
+/// transaction is built and its redeemers are evaluated against the project's validators, as the
+/// ledger would do. The test then passes when all validators succeed.
 #[derive(Debug, Clone)]
 pub struct UnitTest {
     pub input_path: PathBuf,
@@ -216,25 +537,36 @@ pub struct UnitTest {
     pub name: String,
     pub on_test_failure: OnTestFailure,
     pub program: Program<Name>,
+    pub source_map: SourceMap,
     pub assertion: Option<Assertion<(Constant, Rc<Type>)>>,
+    pub scenario: Option<ScenarioScripts>,
+    pub limits: Limits,
 }
 
+/// Compiled validators of a project, by script hash.
+pub type ScenarioScripts = Arc<HashMap<ScriptHash, PlutusScript>>;
+
 unsafe impl Send for UnitTest {}
 
 impl UnitTest {
     pub fn run(self, plutus_version: &PlutusVersion) -> UnitTestResult<(Constant, Rc<Type>)> {
-        let eval_result = Program::<NamedDeBruijn>::try_from(self.program.clone())
-            .unwrap()
-            .eval_version(ExBudget::max(), &plutus_version.into());
+        if let Some(scripts) = self.scenario.clone() {
+            return self.run_scenario(scripts, plutus_version);
+        }
+
+        let eval_result = self.eval_with_deadline(self.limits.deadline(), plutus_version);
 
         let is_evaluation_failure = eval_result.failed(true, &plutus_version.into());
 
-        let success = match self.on_test_failure {
-            OnTestFailure::SucceedEventually | OnTestFailure::SucceedImmediately => {
-                is_evaluation_failure
-            }
-            OnTestFailure::FailImmediately => !is_evaluation_failure,
-        };
+        let limit_exceeded = self.limits.exceeded_by(&eval_result);
+
+        let success = limit_exceeded.is_none()
+            && match self.on_test_failure {
+                OnTestFailure::SucceedEventually | OnTestFailure::SucceedImmediately => {
+                    is_evaluation_failure
+                }
+                OnTestFailure::FailImmediately => !is_evaluation_failure,
+            };
 
         let mut logs = Vec::new();
         if let Err(err) = eval_result.result() {
@@ -242,14 +574,142 @@ impl UnitTest {
         }
         logs.extend(eval_result.logs());
 
+        let failed_at = if eval_result.result().is_err()
+            && limit_exceeded.is_none()
+            && self.on_test_failure == OnTestFailure::FailImmediately
+        {
+            self.locate_failure(plutus_version)
+        } else {
+            None
+        };
+
         UnitTestResult {
             success,
             test: self.to_owned(),
             spent_budget: eval_result.cost(),
             logs,
             assertion: self.assertion,
+            limit_exceeded,
+            failed_at,
         }
     }
+
+    /// Evaluate the transaction returned by the test against the given validators. The budget
+    /// and traces of each validator add to those of the test itself. A transaction that can't be
+    /// built fails the test, regardless of whether it is expected to fail.
+    fn run_scenario(
+        self,
+        scripts: ScenarioScripts,
+        plutus_version: &PlutusVersion,
+    ) -> UnitTestResult<(Constant, Rc<Type>)> {
+        let eval_result = self.eval_with_deadline(self.limits.deadline(), plutus_version);
+
+        let limit_exceeded = self.limits.exceeded_by(&eval_result);
+
+        let mut spent_budget = eval_result.cost();
+
+        let mut logs = eval_result.logs();
+
+        let failed_at = if eval_result.result().is_err()
+            && limit_exceeded.is_none()
+            && self.on_test_failure == OnTestFailure::FailImmediately
+        {
+            self.locate_failure(plutus_version)
+        } else {
+            None
+        };
+
+        let transaction = match eval_result.result() {
+            Err(err) => Err(Some(err)),
+            Ok(Term::Constant(cst)) => match cst.as_ref() {
+                Constant::Data(transaction) => Ok(transaction.clone()),
+                _ => Err(None),
+            },
+            Ok(..) => Err(None),
+        };
+
+        let is_evaluation_failure = match transaction {
+            Ok(transaction) => match tx::eval_scenario(&transaction, &scripts) {
+                Ok(redeemers) => {
+                    for (_, result) in redeemers {
+                        spent_budget = spent_budget + result.cost();
+                        logs.extend(result.logs());
+                    }
+                    Ok(false)
+                }
+                Err(tx::error::Error::RedeemerError { tag, index, err }) => match *err {
+                    tx::error::Error::Machine(err, cost, traces) => {
+                        spent_budget = spent_budget + cost;
+                        logs.extend(traces.iter().filter_map(|trace| trace.clone().unwrap_log()));
+                        logs.push(format!("{tag}[{index}]: {err}"));
+                        Ok(true)
+                    }
+                    err => Err(format!("{tag}[{index}]: {err}")),
+                },
+                Err(err) => Err(err.to_string()),
+            },
+            Err(Some(err)) => {
+                logs.push(format!("{err}"));
+                Ok(true)
+            }
+            Err(None) => Err("test didn't evaluate to a transaction".to_string()),
+        };
+
+        let success = match is_evaluation_failure {
+            Ok(..) if limit_exceeded.is_some() => false,
+            Ok(is_evaluation_failure) => match self.on_test_failure {
+                OnTestFailure::SucceedEventually | OnTestFailure::SucceedImmediately => {
+                    is_evaluation_failure
+                }
+                OnTestFailure::FailImmediately => !is_evaluation_failure,
+            },
+            Err(err) => {
+                logs.push(err);
+                false
+            }
+        };
+
+        UnitTestResult {
+            success,
+            test: self.to_owned(),
+            spent_budget,
+            logs,
+            assertion: None,
+            limit_exceeded,
+            failed_at,
+        }
+    }
+
+    pub fn eval(&self, plutus_version: &PlutusVersion) -> EvalResult {
+        self.eval_with_deadline(None, plutus_version)
+    }
+
+    fn eval_with_deadline(
+        &self,
+        deadline: Option<Instant>,
+        plutus_version: &PlutusVersion,
+    ) -> EvalResult {
+        Program::<NamedDeBruijn>::try_from(self.program.clone())
+            .unwrap()
+            .eval_version_with_deadline(self.limits.budget(), &plutus_version.into(), deadline)
+    }
+
+    pub fn profile(&self, plutus_version: &PlutusVersion) -> Profile {
+        Program::<NamedDeBruijn>::try_from(self.program.clone())
+            .unwrap()
+            .profile(ExBudget::max(), &plutus_version.into(), &self.source_map)
+    }
+
+    /// Evaluate the test again to find where in the source code it fails, if it does.
+    fn locate_failure(&self, plutus_version: &PlutusVersion) -> Option<SourceLocation> {
+        Program::<NamedDeBruijn>::try_from(self.program.clone())
+            .unwrap()
+            .locate_failure(
+                self.limits.budget(),
+                &plutus_version.into(),
+                &self.source_map,
+            )
+    }
 }
 
 /// ----- PropertyTest -----------------------------------------------------------------
@@ -261,7 +721,13 @@ pub struct PropertyTest {
     pub name: String,
     pub on_test_failure: OnTestFailure,
     pub program: Program<Name>,
+    pub source_map: SourceMap,
     pub fuzzer: Fuzzer<Name>,
+    /// Choices of counterexamples found in previous runs, replayed first.
+    pub examples: Vec<Vec<u8>>,
+    /// Maximum depth of the symbolic proof attempted before running the property, if any.
+    pub proof_depth: Option<usize>,
+    pub limits: Limits,
 }
 
 unsafe impl Send for PropertyTest {}
@@ -327,11 +793,7 @@ impl Display for Event {
                     .if_supports_color(Stderr, |s| s.purple()),
                 format!(
                     "counterexample in {} after {steps} steps",
-                    if duration.as_secs() == 0 {
-                        format!("{}ms", duration.as_millis())
-                    } else {
-                        format!("{}s", duration.as_secs())
-                    }
+                    format_duration(duration)
                 )
                 .if_supports_color(Stderr, |s| s.bold()),
             )),
@@ -342,8 +804,14 @@ impl Display for Event {
 impl PropertyTest {
     pub const DEFAULT_MAX_SUCCESS: usize = 100;
 
-    /// Run a property test from a given seed. The property is run at most DEFAULT_MAX_SUCCESS times. It
-    /// may stops earlier on failure; in which case a 'counterexample' is returned.
+    pub const DEFAULT_PROOF_DEPTH: usize = Prover::DEFAULT_DEPTH;
+
+    /// Run a property test from a given seed. Counterexamples from previous runs are replayed
+    /// first, then the property is run at most DEFAULT_MAX_SUCCESS times. It may stops earlier on
+    /// failure; in which case a 'counterexample' is returned.
+    ///
+    /// When a proof depth is set, the property is first proven symbolically; it is then only run
+    /// if the proof is bounded, refuted or inconclusive.
     pub fn run(
         self,
         seed: u32,
@@ -351,30 +819,157 @@ impl PropertyTest {
         plutus_version: &PlutusVersion,
     ) -> PropertyTestResult<PlutusData> {
         let mut labels = BTreeMap::new();
+        let mut replayed = 0;
         let mut remaining = n;
+        let deadline = self.limits.deadline();
+
+        let proof = match self.proof_depth {
+            None => None,
+            Some(depth) => match self.prove(depth, plutus_version) {
+                // The prover isn't bound to the fuzzer's domain, so its counterexample may not be
+                // one the fuzzer can produce. It's only reported alongside the outcome of testing.
+                Outcome::Counterexample(value) => Some(Proof::Refuted(self.show(value))),
+                Outcome::Proved {
+                    paths,
+                    complete: true,
+                } => {
+                    return PropertyTestResult {
+                        test: self,
+                        counterexample: Ok(None),
+                        choices: None,
+                        iterations: 0,
+                        labels,
+                        logs: Vec::new(),
+                        limit_exceeded: None,
+                        proof: Some(Proof::Proved { paths }),
+                    };
+                }
+                Outcome::Proved {
+                    paths,
+                    complete: false,
+                } => Some(Proof::Bounded { paths, depth }),
+                Outcome::Unknown(reason) => Some(Proof::Unknown(reason)),
+            },
+        };
 
-        let (logs, counterexample, iterations) = match self.run_n_times(
-            &mut remaining,
-            Prng::from_seed(seed),
-            &mut labels,
-            plutus_version,
-        ) {
-            Ok(None) => (Vec::new(), Ok(None), n),
+        let (logs, counterexample, choices, iterations, limit_exceeded) = match self
+            .replay(&mut replayed, &mut labels, deadline, plutus_version)
+            .map(|counterexample| Ok(Some(counterexample)))
+            .unwrap_or_else(|| {
+                self.run_n_times(
+                    &mut remaining,
+                    Prng::from_seed(seed),
+                    &mut labels,
+                    deadline,
+                    plutus_version,
+                )
+            }) {
+            Ok(None) => (Vec::new(), Ok(None), None, replayed + n, None),
             Ok(Some(counterexample)) => (
                 self.eval(&counterexample.value, plutus_version).logs(),
                 Ok(Some(counterexample.value)),
-                n - remaining,
+                Some(counterexample.choices),
+                replayed + n - remaining,
+                counterexample.limit_exceeded,
+            ),
+            Err(FuzzerError { logs, uplc_error }) => (
+                logs,
+                Err(uplc_error),
+                None,
+                replayed + n - remaining + 1,
+                None,
             ),
-            Err(FuzzerError { logs, uplc_error }) => (logs, Err(uplc_error), n - remaining + 1),
         };
 
         PropertyTestResult {
             test: self,
             counterexample,
+            choices,
             iterations,
             labels,
             logs,
+            limit_exceeded,
+            proof,
+        }
+    }
+
+    /// Attempt to prove the property symbolically. The proof quantifies over every value of the
+    /// property's type, and not only over those produced by its fuzzer: counterexamples found by
+    /// the prover, confirmed by running the property against them, may lie outside of the
+    /// fuzzer's domain.
+    pub fn prove(&self, depth: usize, plutus_version: &PlutusVersion) -> Outcome {
+        let input = if self.fuzzer.stripped_type_info.is_int() {
+            Input::Integer
+        } else if self.fuzzer.stripped_type_info.is_bytearray() {
+            Input::ByteString
+        } else {
+            return Outcome::Unknown("only properties of an Int or a ByteArray are proven".into());
+        };
+
+        if self.on_test_failure != OnTestFailure::FailImmediately {
+            return Outcome::Unknown("only properties expected to hold are proven".into());
+        }
+
+        let program = Program::<NamedDeBruijn>::try_from(self.program.clone()).unwrap();
+
+        match Prover::new(plutus_version.into())
+            .with_depth(depth)
+            .prove(&program.term, input)
+        {
+            Outcome::Counterexample(value) => {
+                if self
+                    .eval(&value, plutus_version)
+                    .failed(true, &plutus_version.into())
+                {
+                    Outcome::Counterexample(value)
+                } else {
+                    Outcome::Unknown(format!(
+                        "counterexample {} didn't fail when run",
+                        Data::to_hex(value)
+                    ))
+                }
+            }
+            outcome => outcome,
+        }
+    }
+
+    /// Show a value of the property's type as source code.
+    fn show(&self, value: PlutusData) -> String {
+        match UntypedExpr::reify_data(
+            &IndexMap::new(),
+            value.clone(),
+            self.fuzzer.type_info.clone(),
+        ) {
+            Ok(expr) => Formatter::new().expr(&expr, false).to_pretty_string(60),
+            Err(..) => Data::to_hex(value),
+        }
+    }
+
+    /// Replay the counterexamples found in previous runs. Choices which no longer yield a value,
+    /// for example because the fuzzer has changed in the meantime, are ignored.
+    fn replay<'a>(
+        &'a self,
+        replayed: &mut usize,
+        labels: &mut BTreeMap<String, usize>,
+        deadline: Option<Instant>,
+        plutus_version: &'a PlutusVersion,
+    ) -> Option<Counterexample<'a>> {
+        for choices in self.examples.iter() {
+            let Ok(Some((prng, value))) = Prng::from_choices(choices).sample(&self.fuzzer.program)
+            else {
+                continue;
+            };
+
+            *replayed += 1;
+
+            let counterexample = self.check(&prng, value, labels, deadline, plutus_version);
+
+            if counterexample.is_some() {
+                return counterexample;
+            }
         }
+
+        None
     }
 
     pub fn run_n_times<'a>(
@@ -382,13 +977,14 @@ impl PropertyTest {
         remaining: &mut usize,
         initial_prng: Prng,
         labels: &mut BTreeMap<String, usize>,
+        deadline: Option<Instant>,
         plutus_version: &'a PlutusVersion,
     ) -> Result<Option<Counterexample<'a>>, FuzzerError> {
         let mut prng = initial_prng;
         let mut counterexample = None;
 
         while *remaining > 0 && counterexample.is_none() {
-            (prng, counterexample) = self.run_once(prng, labels, plutus_version)?;
+            (prng, counterexample) = self.run_once(prng, labels, deadline, plutus_version)?;
             *remaining -= 1;
         }
 
@@ -399,15 +995,34 @@ impl PropertyTest {
         &'a self,
         prng: Prng,
         labels: &mut BTreeMap<String, usize>,
+        deadline: Option<Instant>,
         plutus_version: &'a PlutusVersion,
     ) -> Result<(Prng, Option<Counterexample<'a>>), FuzzerError> {
-        use OnTestFailure::*;
-
         let (next_prng, value) = prng
             .sample(&self.fuzzer.program)?
             .expect("A seeded PRNG returned 'None' which indicates a fuzzer is ill-formed and implemented wrongly; please contact library's authors.");
 
-        let result = self.eval(&value, plutus_version);
+        let counterexample = self.check(&next_prng, value, labels, deadline, plutus_version);
+
+        Ok((next_prng, counterexample))
+    }
+
+    /// Evaluate the property against a value sampled from the given Prng, and yield a (simplified)
+    /// counterexample if it doesn't hold. Values for which the property reaches one of the test's
+    /// limits are counterexamples too, but aren't simplified.
+    fn check<'a>(
+        &'a self,
+        prng: &Prng,
+        value: PlutusData,
+        labels: &mut BTreeMap<String, usize>,
+        deadline: Option<Instant>,
+        plutus_version: &'a PlutusVersion,
+    ) -> Option<Counterexample<'a>> {
+        use OnTestFailure::*;
+
+        let result = self.eval_with_deadline(&value, deadline, plutus_version);
+
+        let limit_exceeded = self.limits.exceeded_by(&result);
 
         for label in result.labels() {
             // NOTE: There may be other log outputs that interefere with labels. So *by
@@ -423,23 +1038,29 @@ impl PropertyTest {
 
         let is_success = !is_failure;
 
-        let keep_counterexample = match self.on_test_failure {
-            FailImmediately | SucceedImmediately => is_failure,
-            SucceedEventually => is_success,
-        };
+        let keep_counterexample = limit_exceeded.is_some()
+            || match self.on_test_failure {
+                FailImmediately | SucceedImmediately => is_failure,
+                SucceedEventually => is_success,
+            };
 
         if keep_counterexample {
             let mut counterexample = Counterexample {
                 value,
-                choices: next_prng.choices(),
-                cache: Cache::new(|choices| {
+                choices: prng.choices(),
+                limit_exceeded,
+                cache: Cache::new(move |choices| {
                     match Prng::from_choices(choices).sample(&self.fuzzer.program) {
                         Err(..) => Status::Invalid,
                         Ok(None) => Status::Invalid,
                         Ok(Some((_, value))) => {
-                            let is_failure = self
-                                .eval(&value, plutus_version)
-                                .failed(true, &plutus_version.into());
+                            let result = self.eval_with_deadline(&value, deadline, plutus_version);
+
+                            if self.limits.exceeded_by(&result).is_some() {
+                                return Status::Invalid;
+                            }
+
+                            let is_failure = result.failed(true, &plutus_version.into());
 
                             match self.on_test_failure {
                                 FailImmediately | SucceedImmediately => {
@@ -463,22 +1084,66 @@ impl PropertyTest {
                 }),
             };
 
-            if !counterexample.choices.is_empty() {
+            if limit_exceeded.is_none() && !counterexample.choices.is_empty() {
                 counterexample.simplify();
             }
 
-            Ok((next_prng, Some(counterexample)))
+            Some(counterexample)
         } else {
-            Ok((next_prng, None))
+            None
         }
     }
 
     pub fn eval(&self, value: &PlutusData, plutus_version: &PlutusVersion) -> EvalResult {
+        self.eval_with_deadline(value, None, plutus_version)
+    }
+
+    fn eval_with_deadline(
+        &self,
+        value: &PlutusData,
+        deadline: Option<Instant>,
+        plutus_version: &PlutusVersion,
+    ) -> EvalResult {
         let program = self.program.apply_data(value.clone());
 
         Program::<NamedDeBruijn>::try_from(program)
             .unwrap()
-            .eval_version(ExBudget::max(), &plutus_version.into())
+            .eval_version_with_deadline(self.limits.budget(), &plutus_version.into(), deadline)
+    }
+
+    /// The values generated by the fuzzer in the first 'n' runs from a given seed.
+    pub fn samples(&self, seed: u32, n: usize) -> Vec<PlutusData> {
+        let mut samples = Vec::with_capacity(n);
+        let mut prng = Prng::from_seed(seed);
+
+        while samples.len() < n {
+            let Ok(Some((next_prng, value))) = prng.sample(&self.fuzzer.program) else {
+                break;
+            };
+
+            prng = next_prng;
+            samples.push(value);
+        }
+
+        samples
+    }
+
+    pub fn profile(&self, seed: u32, n: usize, plutus_version: &PlutusVersion) -> Profile {
+        let source_map = self.source_map.clone().applied();
+
+        let mut profile = Profile::default();
+
+        for value in self.samples(seed, n) {
+            let program = self.program.apply_data(value);
+
+            profile.merge(
+                Program::<NamedDeBruijn>::try_from(program)
+                    .unwrap()
+                    .profile(ExBudget::max(), &plutus_version.into(), &source_map),
+            );
+        }
+
+        profile
     }
 }
 
@@ -508,14 +1173,19 @@ pub enum BenchmarkError {
         logs: Vec<String>,
         uplc_error: uplc::machine::Error,
     },
+    #[error("Bench aborted: {limit}.")]
+    LimitExceeded {
+        logs: Vec<String>,
+        limit: LimitExceeded,
+    },
 }
 
 impl BenchmarkError {
     pub fn logs(&self) -> &[String] {
         match self {
-            BenchmarkError::SamplerError { logs, .. } | BenchmarkError::BenchError { logs, .. } => {
-                logs.as_slice()
-            }
+            BenchmarkError::SamplerError { logs, .. }
+            | BenchmarkError::BenchError { logs, .. }
+            | BenchmarkError::LimitExceeded { logs, .. } => logs.as_slice(),
         }
     }
 }
@@ -527,7 +1197,9 @@ pub struct Benchmark {
     pub name: String,
     pub on_test_failure: OnTestFailure,
     pub program: Program<Name>,
+    pub source_map: SourceMap,
     pub sampler: Sampler<Name>,
+    pub limits: Limits,
 }
 
 unsafe impl Send for Benchmark {}
@@ -545,6 +1217,7 @@ impl Benchmark {
         let mut prng = Prng::from_seed(seed);
         let mut error = None;
         let mut size = 0;
+        let deadline = self.limits.deadline();
 
         while error.is_none() && max_size >= size {
             let fuzzer = self
@@ -561,9 +1234,15 @@ impl Benchmark {
 
                 Ok(Some((new_prng, value))) => {
                     prng = new_prng;
-                    let result = self.eval(&value, plutus_version);
+                    let result = self.eval_with_deadline(&value, deadline, plutus_version);
                     match result.result() {
                         Ok(_) => measures.push((size, result.cost())),
+                        Err(..) if self.limits.exceeded_by(&result).is_some() => {
+                            error = Some(BenchmarkError::LimitExceeded {
+                                logs: result.logs(),
+                                limit: self.limits.exceeded_by(&result).unwrap(),
+                            });
+                        }
                         Err(uplc_error) => {
                             error = Some(BenchmarkError::BenchError {
                                 logs: result.logs(),
@@ -589,11 +1268,60 @@ impl Benchmark {
     }
 
     pub fn eval(&self, value: &PlutusData, plutus_version: &PlutusVersion) -> EvalResult {
+        self.eval_with_deadline(value, None, plutus_version)
+    }
+
+    fn eval_with_deadline(
+        &self,
+        value: &PlutusData,
+        deadline: Option<Instant>,
+        plutus_version: &PlutusVersion,
+    ) -> EvalResult {
         let program = self.program.apply_data(value.clone());
 
         Program::<NamedDeBruijn>::try_from(program)
             .unwrap()
-            .eval_version(ExBudget::max(), &plutus_version.into())
+            .eval_version_with_deadline(self.limits.budget(), &plutus_version.into(), deadline)
+    }
+
+    /// The values generated by the sampler for each size up to 'max_size', from a given seed.
+    pub fn samples(&self, seed: u32, max_size: usize) -> Vec<PlutusData> {
+        let mut samples = Vec::with_capacity(max_size + 1);
+        let mut prng = Prng::from_seed(seed);
+
+        for size in 0..=max_size {
+            let fuzzer = self
+                .sampler
+                .program
+                .apply_term(&Term::Constant(Constant::Integer(size.into()).into()));
+
+            let Ok(Some((next_prng, value))) = prng.sample(&fuzzer) else {
+                break;
+            };
+
+            prng = next_prng;
+            samples.push(value);
+        }
+
+        samples
+    }
+
+    pub fn profile(&self, seed: u32, max_size: usize, plutus_version: &PlutusVersion) -> Profile {
+        let source_map = self.source_map.clone().applied();
+
+        let mut profile = Profile::default();
+
+        for value in self.samples(seed, max_size) {
+            let program = self.program.apply_data(value);
+
+            profile.merge(
+                Program::<NamedDeBruijn>::try_from(program)
+                    .unwrap()
+                    .profile(ExBudget::max(), &plutus_version.into(), &source_map),
+            );
+        }
+
+        profile
     }
 }
 
@@ -781,6 +1509,7 @@ impl Prng {
 pub struct Counterexample<'a> {
     pub value: PlutusData,
     pub choices: Vec<u8>,
+    pub limit_exceeded: Option<LimitExceeded>,
     pub cache: Cache<'a, PlutusData>,
 }
 
@@ -1136,6 +1865,10 @@ impl<U, T> TestResult<U, T> {
     pub fn is_success(&self) -> bool {
         match self {
             TestResult::UnitTestResult(UnitTestResult { success, .. }) => *success,
+            TestResult::PropertyTestResult(PropertyTestResult {
+                limit_exceeded: Some(..),
+                ..
+            }) => false,
             TestResult::PropertyTestResult(PropertyTestResult {
                 counterexample: Err(..),
                 ..
@@ -1170,6 +1903,20 @@ impl<U, T> TestResult<U, T> {
         }
     }
 
+    /// The limit which caused the test to be aborted, if any.
+    pub fn limit_exceeded(&self) -> Option<LimitExceeded> {
+        match self {
+            TestResult::UnitTestResult(UnitTestResult { limit_exceeded, .. })
+            | TestResult::PropertyTestResult(PropertyTestResult { limit_exceeded, .. }) => {
+                *limit_exceeded
+            }
+            TestResult::BenchmarkResult(BenchmarkResult { error, .. }) => match error {
+                Some(BenchmarkError::LimitExceeded { limit, .. }) => Some(*limit),
+                _ => None,
+            },
+        }
+    }
+
     pub fn logs(&self) -> &[String] {
         match self {
             TestResult::UnitTestResult(UnitTestResult { logs, .. })
@@ -1188,6 +1935,9 @@ pub struct UnitTestResult<T> {
     pub logs: Vec<String>,
     pub test: UnitTest,
     pub assertion: Option<Assertion<T>>,
+    pub limit_exceeded: Option<LimitExceeded>,
+    /// Where in the source code the test failed unexpectedly, if known.
+    pub failed_at: Option<SourceLocation>,
 }
 
 unsafe impl<T> Send for UnitTestResult<T> {}
@@ -1202,6 +1952,8 @@ impl UnitTestResult<(Constant, Rc<Type>)> {
             spent_budget: self.spent_budget,
             logs: self.logs,
             test: self.test,
+            limit_exceeded: self.limit_exceeded,
+            failed_at: self.failed_at,
             assertion: self.assertion.and_then(|assertion| {
                 // No need to spend time/cpu on reifying assertions for successful
                 // tests since they aren't shown.
@@ -1231,13 +1983,33 @@ impl UnitTestResult<(Constant, Rc<Type>)> {
 pub struct PropertyTestResult<T> {
     pub test: PropertyTest,
     pub counterexample: Result<Option<T>, uplc::machine::Error>,
+    /// The choices that led to the counterexample, if any.
+    pub choices: Option<Vec<u8>>,
     pub iterations: usize,
     pub labels: BTreeMap<String, usize>,
     pub logs: Vec<String>,
+    pub limit_exceeded: Option<LimitExceeded>,
+    /// The outcome of the symbolic proof, when attempted.
+    pub proof: Option<Proof>,
 }
 
 unsafe impl<T> Send for PropertyTestResult<T> {}
 
+/// Outcome of the symbolic proof of a property test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Proof {
+    /// The property holds for every value of its type.
+    Proved { paths: usize },
+    /// The property holds for every value whose evaluation goes through at most 'depth' branches
+    /// depending on it. Other values were left to testing.
+    Bounded { paths: usize, depth: usize },
+    /// The prover found a value of the property's type, shown here, for which it fails. The
+    /// fuzzer may not produce it, so it isn't a counterexample of the test, which is run still.
+    Refuted(String),
+    /// The prover gave up, leaving the property to testing.
+    Unknown(String),
+}
+
 impl PropertyTestResult<PlutusData> {
     pub fn reify(
         self,
@@ -1254,10 +2026,13 @@ impl PropertyTestResult<PlutusData> {
                     .expect("failed to reify counterexample?")
                 })
             }),
+            choices: self.choices,
             iterations: self.iterations,
             test: self.test,
             labels: self.labels,
             logs: self.logs,
+            limit_exceeded: self.limit_exceeded,
+            proof: self.proof,
         }
     }
 }
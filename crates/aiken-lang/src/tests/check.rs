@@ -1935,6 +1935,48 @@ fn fuzzer_unify_across_deps() {
     assert!(matches!(result, Ok(..)), "{result:#?}");
 }
 
+#[test]
+fn test_returning_transaction() {
+    let dependency = r#"
+        pub type Transaction {
+          fee: Int,
+        }
+    "#;
+
+    let source_code = r#"
+        use cardano/transaction.{Transaction}
+
+        test scenario() {
+            Transaction { fee: 42 }
+        }
+    "#;
+
+    let result = check_with_deps(
+        parse(source_code),
+        vec![(parse_as(dependency, "cardano/transaction"))],
+    );
+
+    assert!(matches!(result, Ok(..)), "{result:#?}");
+}
+
+#[test]
+fn test_returning_foreign_transaction() {
+    let source_code = r#"
+        pub type Transaction {
+          fee: Int,
+        }
+
+        test scenario() {
+            Transaction { fee: 42 }
+        }
+    "#;
+
+    assert!(matches!(
+        check(parse(source_code)),
+        Err((_, Error::IllegalTestType { .. }))
+    ))
+}
+
 #[test]
 fn utf8_hex_literal_warning() {
     let source_code = r#"
@@ -4525,6 +4567,84 @@ fn decorator_validation_overlaping_tags_explicit() {
     ))
 }
 
+#[test]
+fn decorator_validation_legit_test_budget() {
+    let source_code = r#"
+        @budget(mem: 1_000_000, cpu: 0xFFFF)
+        test foo() {
+          True
+        }
+
+        @budget(cpu: 42)
+        test bar() fail {
+          False
+        }
+    "#;
+
+    assert!(dbg!(check(parse(source_code))).is_ok())
+}
+
+#[test]
+fn decorator_validation_budget_on_type() {
+    let source_code = r#"
+        @budget(mem: 100)
+        pub type Datum {
+          thing: Int,
+        }
+    "#;
+
+    assert!(matches!(
+        check(parse(source_code)),
+        Err((_, Error::DecoratorValidation { .. }))
+    ))
+}
+
+#[test]
+fn decorator_validation_tag_on_test() {
+    let source_code = r#"
+        @tag(1)
+        test foo() {
+          True
+        }
+    "#;
+
+    assert!(matches!(
+        check(parse(source_code)),
+        Err((_, Error::DecoratorValidation { .. }))
+    ))
+}
+
+#[test]
+fn decorator_validation_budget_too_large() {
+    let source_code = r#"
+        @budget(mem: 99999999999999999999)
+        test foo() {
+          True
+        }
+    "#;
+
+    assert!(matches!(
+        check(parse(source_code)),
+        Err((_, Error::DecoratorValidation { .. }))
+    ))
+}
+
+#[test]
+fn decorator_validation_budget_conflict() {
+    let source_code = r#"
+        @budget(mem: 100)
+        @budget(cpu: 100)
+        test foo() {
+          True
+        }
+    "#;
+
+    assert!(matches!(
+        check(parse(source_code)),
+        Err((_, Error::ConflictingDecorators { .. }))
+    ))
+}
+
 #[test]
 fn decorator_validation_overlaping_tags() {
     let source_code = r#"
@@ -4644,3 +4764,50 @@ fn incomplete_pair() {
         Err((_, Error::IncorrectFunctionCallArity { expected, .. })) if expected == 2
     ))
 }
+
+#[test]
+fn variables_in_scope() {
+    let source_code = r#"
+        fn foo(a: Int, b: Option<ByteArray>) -> Int {
+          let c = a + 1
+          when b is {
+            Some(bytes) -> {
+              expect [d, ..rest] = [bytes]
+              c * 2
+            }
+            None -> c
+          }
+        }
+    "#;
+
+    let (_, ast) = check(parse(source_code)).expect("module should type-check");
+
+    let variables_at = |needle: &str| {
+        ast.variables_at(source_code.find(needle).expect("needle"))
+            .into_iter()
+            .map(|(name, _, tipo)| (name, tipo.to_pretty(0)))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        variables_at("c * 2"),
+        vec![
+            ("a".to_string(), "Int".to_string()),
+            ("b".to_string(), "Option<ByteArray>".to_string()),
+            ("c".to_string(), "Int".to_string()),
+            ("bytes".to_string(), "ByteArray".to_string()),
+            ("d".to_string(), "ByteArray".to_string()),
+            ("rest".to_string(), "List<ByteArray>".to_string()),
+        ]
+    );
+
+    assert_eq!(
+        variables_at("None -> c")
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>(),
+        vec!["a", "b", "c"]
+    );
+
+    assert!(ast.variables_at(0).is_empty());
+}
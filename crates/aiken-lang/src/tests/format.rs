@@ -1610,6 +1610,23 @@ fn decorators_on_constructor() {
     );
 }
 
+#[test]
+fn budget_decorator() {
+    assert_format!(
+        r#"
+        @budget(mem: 1_000_000, cpu: 0xff)
+        test foo() {
+          True
+        }
+
+        @budget(cpu: 42)
+        bench bar(n via fuzz.int()) {
+          True
+        }
+        "#
+    );
+}
+
 #[test]
 fn long_import_line() {
     assert_format!(
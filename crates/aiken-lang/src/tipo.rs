@@ -21,6 +21,7 @@ mod infer;
 mod pattern;
 mod pipe;
 pub mod pretty;
+pub mod scope;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct TypeAliasAnnotation {
@@ -421,6 +422,18 @@ impl Type {
         }
     }
 
+    /// Check whether a type is the standard library's `cardano/transaction.Transaction`, which
+    /// tests may return to describe a full transaction scenario.
+    pub fn is_transaction(&self) -> bool {
+        match self {
+            Self::App { module, name, .. } => {
+                well_known::TRANSACTION == name && well_known::TRANSACTION_MODULE == module
+            }
+            Self::Var { tipo, .. } => tipo.borrow().is_transaction(),
+            _ => false,
+        }
+    }
+
     ///  Check whether a given type is fully specialized and has only one possible
     ///  form. Said differently, this recursively checks if the type still contains
     ///  unbound or generic variables.
@@ -1088,6 +1101,13 @@ impl TypeVar {
         }
     }
 
+    pub fn is_transaction(&self) -> bool {
+        match self {
+            Self::Link { tipo } => tipo.is_transaction(),
+            _ => false,
+        }
+    }
+
     pub fn is_generic(&self) -> bool {
         match self {
             TypeVar::Generic { .. } => true,
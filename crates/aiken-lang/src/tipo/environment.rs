@@ -272,6 +272,7 @@ impl<'a> Environment<'a> {
                 return_type,
                 end_position,
                 on_test_failure,
+                decorators,
             }) => {
                 // Lookup the inferred function information
                 let function = self
@@ -317,6 +318,7 @@ impl<'a> Environment<'a> {
                     body,
                     end_position,
                     on_test_failure,
+                    decorators,
                 })
             }
             Definition::Validator(Validator {
@@ -1083,9 +1083,10 @@ The best thing to do from here is to remove it."#))]
     #[error("I caught a test with an illegal return type.\n")]
     #[diagnostic(code("illegal::test::return"))]
     #[diagnostic(help(
-        "Tests must return either {Bool} or {Void}. Note that `expect` assignment are implicitly typed {Void} (and thus, may be the last expression of a test).",
+        "Tests must return either {Bool} or {Void}. Note that `expect` assignment are implicitly typed {Void} (and thus, may be the last expression of a test). Tests without arguments may also return a {Transaction} from the standard library, which is then evaluated against the project's validators.",
         Bool = "Bool".if_supports_color(Stderr, |s| s.cyan()),
         Void = "Void".if_supports_color(Stderr, |s| s.cyan()),
+        Transaction = "Transaction".if_supports_color(Stderr, |s| s.cyan()),
     ))]
     IllegalTestType {
         #[label("expected Bool or Void")]
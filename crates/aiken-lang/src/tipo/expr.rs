@@ -57,6 +57,7 @@ pub(crate) fn infer_function(
         return_annotation,
         end_position,
         on_test_failure,
+        decorators,
         return_type: _,
     } = fun;
 
@@ -224,6 +225,7 @@ pub(crate) fn infer_function(
         body,
         on_test_failure: on_test_failure.clone(),
         end_position: *end_position,
+        decorators: decorators.clone(),
     };
 
     environment
@@ -1630,6 +1632,7 @@ impl<'a, 'b> ExprTyper<'a, 'b> {
             tipo: value_typ,
             kind: kind.into(),
             pattern,
+            annotation: annotation.clone(),
             value: Box::new(typed_value),
             comment,
         })
@@ -1827,6 +1830,7 @@ impl<'a, 'b> ExprTyper<'a, 'b> {
                     value,
                     pattern,
                     tipo,
+                    annotation,
                     ..
                 } = typer.infer_assignment(
                     pattern,
@@ -1855,7 +1859,7 @@ impl<'a, 'b> ExprTyper<'a, 'b> {
                     typer.infer(branch.body.clone())?
                 };
 
-                Ok((*value, body, Some((pattern, tipo))))
+                Ok((*value, body, Some((pattern, tipo, annotation))))
             })?,
             None => {
                 let condition = self.infer(branch.condition.clone())?;
@@ -2932,6 +2936,7 @@ fn assert_assignment(expr: TypedExpr) -> Result<TypedExpr, Error> {
                     spread_location: None,
                     tipo: Type::void(),
                 },
+                annotation: None,
                 kind: AssignmentKind::let_(),
                 comment: None,
             });
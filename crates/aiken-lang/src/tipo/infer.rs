@@ -377,6 +377,8 @@ fn infer_definition(
         }
 
         Definition::Test(f) => {
+            validate_decorators_in_context(&f.decorators, DecoratorContext::Test, None)?;
+
             let top_level_scope = environment.open_new_scope();
             let (typed_via, annotation) = match f.arguments.first() {
                 Some(arg) => {
@@ -423,7 +425,9 @@ fn infer_definition(
 
             environment.close_scope(top_level_scope);
 
-            if is_bool.or(is_void).is_err() {
+            let is_scenario = typed_via.is_none() && typed_f.return_type.is_transaction();
+
+            if is_bool.or(is_void).is_err() && !is_scenario {
                 return Err(Error::IllegalTestType {
                     location: typed_f.location,
                 });
@@ -457,10 +461,13 @@ fn infer_definition(
                 body: typed_f.body,
                 on_test_failure: typed_f.on_test_failure,
                 end_position: typed_f.end_position,
+                decorators: typed_f.decorators,
             }))
         }
 
         Definition::Benchmark(f) => {
+            validate_decorators_in_context(&f.decorators, DecoratorContext::Benchmark, None)?;
+
             let top_level_scope = environment.open_new_scope();
             let err_incorrect_arity = || {
                 Err(Error::IncorrectBenchmarkArity {
@@ -520,6 +527,7 @@ fn infer_definition(
                 body: typed_f.body,
                 on_test_failure: typed_f.on_test_failure,
                 end_position: typed_f.end_position,
+                decorators: typed_f.decorators,
             }))
         }
 
@@ -1040,6 +1048,8 @@ pub enum DecoratorContext {
     Record,
     Enum,
     Constructor,
+    Test,
+    Benchmark,
 }
 
 impl fmt::Display for DecoratorContext {
@@ -1048,6 +1058,8 @@ impl fmt::Display for DecoratorContext {
             DecoratorContext::Record => write!(f, "record"),
             DecoratorContext::Enum => write!(f, "enum"),
             DecoratorContext::Constructor => write!(f, "constructor"),
+            DecoratorContext::Test => write!(f, "test"),
+            DecoratorContext::Benchmark => write!(f, "benchmark"),
         }
     }
 }
@@ -1117,6 +1129,8 @@ fn validate_decorators_in_context(
             });
         }
 
+        d1.kind.validate_arguments(d1.location)?;
+
         // Validate type constraints if applicable
         if let Some(t) = tipo {
             d1.kind.validate_type(&context, t, d1.location)?;
@@ -1141,6 +1155,28 @@ impl DecoratorKind {
         match self {
             DecoratorKind::Tag { .. } => &[DecoratorContext::Record, DecoratorContext::Constructor],
             DecoratorKind::List => &[DecoratorContext::Record],
+            DecoratorKind::Budget { .. } => &[DecoratorContext::Test, DecoratorContext::Benchmark],
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn validate_arguments(&self, location: Span) -> Result<(), Error> {
+        match self {
+            DecoratorKind::Tag { .. } => Ok(()),
+            DecoratorKind::List => Ok(()),
+            DecoratorKind::Budget { mem, cpu } => {
+                for (label, value) in [("mem", mem), ("cpu", cpu)] {
+                    if let Some((value, _)) = value {
+                        if value.parse::<i64>().is_err() {
+                            return Err(Error::DecoratorValidation {
+                                location,
+                                message: format!("{label} is too large to be used as a budget"),
+                            });
+                        }
+                    }
+                }
+                Ok(())
+            }
         }
     }
 
@@ -1154,6 +1190,7 @@ impl DecoratorKind {
         match self {
             DecoratorKind::Tag { .. } => Ok(()),
             DecoratorKind::List => Ok(()),
+            DecoratorKind::Budget { .. } => Ok(()),
         }
     }
 
@@ -1163,6 +1200,8 @@ impl DecoratorKind {
             (DecoratorKind::Tag { .. }, DecoratorKind::Tag { .. }) => true,
             (DecoratorKind::List, DecoratorKind::Tag { .. }) => true,
             (DecoratorKind::List, DecoratorKind::List) => true,
+            (DecoratorKind::Budget { .. }, DecoratorKind::Budget { .. }) => true,
+            (DecoratorKind::Budget { .. }, _) | (_, DecoratorKind::Budget { .. }) => false,
         }
     }
 }
@@ -0,0 +1,325 @@
+//! What's in scope at some point of a type-checked module, as needed by editors to suggest
+//! completions. The environment the module was checked in is gone by then, so it is rebuilt from
+//! the module's imports and definitions, and from the variables bound around the point.
+
+use super::{
+    AccessorsMap, RecordAccessor, Type, TypeConstructor, TypeInfo, ValueConstructor,
+    ValueConstructorVariant, environment::Environment, fields::FieldMap,
+};
+use crate::{
+    IdGenerator,
+    ast::{Definition, TypedModule, Use},
+};
+use std::{collections::HashMap, rc::Rc};
+
+#[derive(Debug, Clone)]
+pub struct Scope {
+    /// The module the scope belongs to.
+    pub module: String,
+    /// Values referable to without qualification: variables, the module's own functions,
+    /// constants and constructors, unqualified imports and the prelude.
+    pub values: HashMap<String, ValueConstructor>,
+    /// Types referable to without qualification.
+    pub types: HashMap<String, TypeConstructor>,
+    /// Constructors of the types defined in the module or in the prelude.
+    pub types_constructors: HashMap<String, Vec<String>>,
+    /// Field accessors of the types defined in the module or in the prelude.
+    pub accessors: HashMap<String, AccessorsMap>,
+    /// Imported modules, by the name they're referred to in the module.
+    pub modules: HashMap<String, String>,
+}
+
+impl Scope {
+    /// The scope at the given byte index of a module, which was checked against the given
+    /// modules. Imports which can't be resolved anymore are left out.
+    pub fn new(
+        module: &TypedModule,
+        byte_index: usize,
+        modules: &HashMap<String, TypeInfo>,
+    ) -> Self {
+        let mut warnings = Vec::new();
+
+        let mut environment = Environment::new(
+            IdGenerator::new(),
+            &module.name,
+            &module.kind,
+            modules,
+            &mut warnings,
+            None,
+        );
+
+        for definition in module.definitions() {
+            match definition {
+                Definition::Use(import) => {
+                    let _ = environment.register_import(&Definition::Use(Use {
+                        as_name: import.as_name.clone(),
+                        location: import.location,
+                        module: import.module.clone(),
+                        package: (),
+                        unqualified: import.unqualified.clone(),
+                    }));
+                }
+
+                Definition::Fn(function) => {
+                    let mut field_map = FieldMap::new(function.arguments.len(), true);
+
+                    for (index, arg) in function.arguments.iter().enumerate() {
+                        let _ = field_map.insert(arg.arg_name.get_label(), index, &arg.location);
+                    }
+
+                    environment.insert_variable(
+                        function.name.clone(),
+                        ValueConstructorVariant::ModuleFn {
+                            name: function.name.clone(),
+                            field_map: field_map.into_option(),
+                            module: module.name.clone(),
+                            arity: function.arguments.len(),
+                            location: function.location,
+                            builtin: None,
+                        },
+                        Type::function(
+                            function
+                                .arguments
+                                .iter()
+                                .map(|arg| arg.tipo.clone())
+                                .collect(),
+                            function.return_type.clone(),
+                        ),
+                    );
+                }
+
+                Definition::ModuleConstant(constant) => {
+                    environment.insert_variable(
+                        constant.name.clone(),
+                        ValueConstructorVariant::ModuleConstant {
+                            location: constant.location,
+                            module: module.name.clone(),
+                            name: constant.name.clone(),
+                        },
+                        constant.value.tipo(),
+                    );
+                }
+
+                Definition::DataType(data_type) => {
+                    let tipo = Rc::new(Type::App {
+                        public: data_type.public,
+                        contains_opaque: data_type.opaque,
+                        module: module.name.clone(),
+                        name: data_type.name.clone(),
+                        args: data_type.typed_parameters.clone(),
+                        alias: None,
+                    });
+
+                    let _ = environment.insert_type_constructor(
+                        data_type.name.clone(),
+                        TypeConstructor {
+                            public: data_type.public,
+                            location: data_type.location,
+                            module: module.name.clone(),
+                            parameters: data_type.typed_parameters.clone(),
+                            tipo: tipo.clone(),
+                        },
+                    );
+
+                    environment.insert_type_to_constructors(
+                        data_type.name.clone(),
+                        data_type
+                            .constructors
+                            .iter()
+                            .map(|constructor| constructor.name.clone())
+                            .collect(),
+                    );
+
+                    if let [constructor] = &data_type.constructors[..] {
+                        let accessors = constructor
+                            .arguments
+                            .iter()
+                            .enumerate()
+                            .filter_map(|(index, arg)| {
+                                let label = arg.label.clone()?;
+                                Some((
+                                    label.clone(),
+                                    RecordAccessor {
+                                        index: index as u64,
+                                        label,
+                                        tipo: arg.tipo.clone(),
+                                    },
+                                ))
+                            })
+                            .collect::<HashMap<_, _>>();
+
+                        if !accessors.is_empty() {
+                            environment.insert_accessors(
+                                &data_type.name,
+                                AccessorsMap {
+                                    public: data_type.public && !data_type.opaque,
+                                    tipo: tipo.clone(),
+                                    accessors,
+                                },
+                            );
+                        }
+                    }
+
+                    for constructor in data_type.constructors.iter() {
+                        let mut field_map = FieldMap::new(constructor.arguments.len(), false);
+
+                        for (index, arg) in constructor.arguments.iter().enumerate() {
+                            if let Some(label) = &arg.label {
+                                let _ = field_map.insert(label.clone(), index, &arg.location);
+                            }
+                        }
+
+                        environment.insert_variable(
+                            constructor.name.clone(),
+                            ValueConstructorVariant::Record {
+                                name: constructor.name.clone(),
+                                arity: constructor.arguments.len(),
+                                field_map: field_map.into_option(),
+                                location: constructor.location,
+                                module: module.name.clone(),
+                                constructors_count: data_type.constructors.len() as u16,
+                            },
+                            match constructor.arguments.len() {
+                                0 => tipo.clone(),
+                                _ => Type::function(
+                                    constructor
+                                        .arguments
+                                        .iter()
+                                        .map(|arg| arg.tipo.clone())
+                                        .collect(),
+                                    tipo.clone(),
+                                ),
+                            },
+                        );
+                    }
+                }
+
+                Definition::TypeAlias(alias) => {
+                    let _ = environment.insert_type_constructor(
+                        alias.alias.clone(),
+                        TypeConstructor {
+                            public: alias.public,
+                            location: alias.location,
+                            module: module.name.clone(),
+                            parameters: Vec::new(),
+                            tipo: alias.tipo.clone(),
+                        },
+                    );
+                }
+
+                Definition::Test(..) | Definition::Benchmark(..) | Definition::Validator(..) => (),
+            }
+        }
+
+        for (name, location, tipo) in module.variables_at(byte_index) {
+            environment.insert_variable(
+                name,
+                ValueConstructorVariant::LocalVariable { location },
+                tipo,
+            );
+        }
+
+        let values = environment
+            .local_value_names()
+            .into_iter()
+            .chain(environment.local_constructor_names())
+            .filter_map(|name| {
+                let value = environment.scope.get(&name)?.clone();
+                Some((name, value))
+            })
+            .collect();
+
+        let types = environment
+            .known_type_names()
+            .into_iter()
+            .filter_map(|name| {
+                let tipo = environment.module_types.get(&name)?.clone();
+                Some((name, tipo))
+            })
+            .collect();
+
+        Scope {
+            module: module.name.clone(),
+            values,
+            types,
+            types_constructors: environment.module_types_constructors.clone(),
+            accessors: environment.accessors.clone(),
+            modules: environment
+                .imported_modules
+                .iter()
+                .map(|(alias, (_, info))| (alias.clone(), info.name.clone()))
+                .collect(),
+        }
+    }
+
+    /// The field accessors of a record type, looking up types from other modules in the given
+    /// modules.
+    pub fn accessors<'a>(
+        &'a self,
+        tipo: &Type,
+        modules: &'a HashMap<String, TypeInfo>,
+    ) -> Option<&'a AccessorsMap> {
+        let (module, name) = tipo.qualifier()?;
+
+        if module.is_empty() || module == self.module {
+            self.accessors.get(&name)
+        } else {
+            modules.get(&module)?.accessors.get(&name)
+        }
+    }
+
+    /// The constructors of a type, along with the name they're referred to in the module: either
+    /// unqualified, or qualified by the module they're imported from.
+    pub fn constructors(
+        &self,
+        tipo: &Type,
+        modules: &HashMap<String, TypeInfo>,
+    ) -> Vec<(String, ValueConstructor)> {
+        let Some((module, name)) = tipo.qualifier() else {
+            return Vec::new();
+        };
+
+        if module.is_empty() || module == self.module {
+            return self
+                .types_constructors
+                .get(&name)
+                .into_iter()
+                .flatten()
+                .filter_map(|constructor| {
+                    let value = self.values.get(constructor)?.clone();
+                    Some((constructor.clone(), value))
+                })
+                .collect();
+        }
+
+        let Some(info) = modules.get(&module) else {
+            return Vec::new();
+        };
+
+        let alias = self
+            .modules
+            .iter()
+            .find(|(_, full_name)| **full_name == module)
+            .map(|(alias, _)| alias);
+
+        info.types_constructors
+            .get(&name)
+            .into_iter()
+            .flatten()
+            .filter_map(|constructor| {
+                let value = info.values.get(constructor)?.clone();
+
+                let imported_unqualified = self.values.get(constructor).is_some_and(|known| {
+                    matches!(&known.variant, ValueConstructorVariant::Record { module: m, .. } if *m == module)
+                });
+
+                let name = match alias {
+                    Some(alias) if !imported_unqualified => format!("{alias}.{constructor}"),
+                    _ => constructor.clone(),
+                };
+
+                Some((name, value))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,568 @@
+//! Call hierarchy: the functions a function, test or validator handler calls, and the ones
+//! calling it, across the project and its dependencies.
+//!
+//! Both come from the calls found in the last checked version of every module. Functions passed
+//! around as values, rather than called, aren't followed.
+
+use crate::{
+    outline,
+    server::lsp_project::LspProject,
+    utils::{path_to_uri, span_to_lsp_range},
+};
+use aiken_lang::{
+    ast::{Definition, Span},
+    expr::TypedExpr,
+    line_numbers::LineNumbers,
+    symbols::Symbol,
+    tipo::{ModuleValueConstructor, ValueConstructor, ValueConstructorVariant},
+};
+use aiken_project::module::CheckedModule;
+use lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, DocumentSymbol,
+    SymbolKind,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+/// A definition whose body may call functions: a function, a test, a benchmark, a constant or a
+/// handler of a validator, in which case it's named after both.
+struct Caller<'a> {
+    name: String,
+    symbol: DocumentSymbol,
+    bodies: Vec<&'a TypedExpr>,
+}
+
+/// The item at the given position of a module: either a definition named there, or a function
+/// referred to.
+pub fn prepare(
+    compiler: &LspProject,
+    module: &CheckedModule,
+    byte_index: usize,
+) -> Option<Vec<CallHierarchyItem>> {
+    let position = LineNumbers::new(&module.code).line_and_column_number(byte_index)?;
+
+    let position = lsp_types::Position {
+        line: position.line as u32 - 1,
+        character: position.column as u32 - 1,
+    };
+
+    let defined = callers(module).into_iter().find(|caller| {
+        caller.symbol.selection_range.start <= position
+            && position <= caller.symbol.selection_range.end
+    });
+
+    if let Some(caller) = defined {
+        return Some(vec![item(compiler, &module.name, &caller)?]);
+    }
+
+    let referred = compiler
+        .occurrences
+        .get(&module.name)?
+        .iter()
+        .filter(|occurrence| {
+            occurrence.location.start <= byte_index && byte_index <= occurrence.location.end
+        })
+        .find_map(|occurrence| match &occurrence.symbol {
+            Symbol::Value { module, name } => Some((module, name)),
+            _ => None,
+        });
+
+    let (module, name) = referred?;
+
+    let caller = callers(compiler.modules.get(module)?)
+        .into_iter()
+        .find(|caller| caller.name == *name)?;
+
+    Some(vec![item(compiler, module, &caller)?])
+}
+
+/// The definitions calling the given item, along with where they do.
+pub fn incoming_calls(
+    compiler: &LspProject,
+    target: &CallHierarchyItem,
+) -> Option<Vec<CallHierarchyIncomingCall>> {
+    let (module, name) = identify(target)?;
+
+    let mut modules = compiler.modules.values().collect::<Vec<_>>();
+
+    modules.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut incoming = Vec::new();
+
+    for checked in modules {
+        let line_numbers = LineNumbers::new(&checked.code);
+
+        for caller in callers(checked) {
+            let from_ranges = calls(&caller)
+                .into_iter()
+                .filter(|(callee, _)| callee.0 == module && callee.1 == name)
+                .map(|(_, location)| span_to_lsp_range(location, &line_numbers))
+                .collect::<Vec<_>>();
+
+            if from_ranges.is_empty() {
+                continue;
+            }
+
+            if let Some(from) = item(compiler, &checked.name, &caller) {
+                incoming.push(CallHierarchyIncomingCall { from, from_ranges });
+            }
+        }
+    }
+
+    Some(incoming)
+}
+
+/// The functions the given item calls, along with where it does.
+pub fn outgoing_calls(
+    compiler: &LspProject,
+    source: &CallHierarchyItem,
+) -> Option<Vec<CallHierarchyOutgoingCall>> {
+    let (module, name) = identify(source)?;
+
+    let checked = compiler.modules.get(&module)?;
+
+    let caller = callers(checked)
+        .into_iter()
+        .find(|caller| caller.name == name)?;
+
+    let line_numbers = LineNumbers::new(&checked.code);
+
+    let mut by_callee: BTreeMap<(String, String), Vec<lsp_types::Range>> = BTreeMap::new();
+
+    for (callee, location) in calls(&caller) {
+        by_callee
+            .entry(callee)
+            .or_default()
+            .push(span_to_lsp_range(location, &line_numbers));
+    }
+
+    let mut defined_in: HashMap<String, Vec<Caller<'_>>> = HashMap::new();
+
+    let mut outgoing = Vec::new();
+
+    for ((module, name), from_ranges) in by_callee {
+        // Builtins aren't defined in any module.
+        let Some(checked) = compiler.modules.get(&module) else {
+            continue;
+        };
+
+        let callers = defined_in
+            .entry(module.clone())
+            .or_insert_with(|| callers(checked));
+
+        if let Some(to) = callers
+            .iter()
+            .find(|caller| caller.name == name)
+            .and_then(|caller| item(compiler, &module, caller))
+        {
+            outgoing.push(CallHierarchyOutgoingCall { to, from_ranges });
+        }
+    }
+
+    Some(outgoing)
+}
+
+/// The definitions of a module which may call functions, as outlined in the module.
+fn callers(module: &CheckedModule) -> Vec<Caller<'_>> {
+    let mut bodies: HashMap<String, Vec<&TypedExpr>> = HashMap::new();
+
+    for definition in module.ast.definitions() {
+        match definition {
+            Definition::Fn(function) => {
+                bodies.insert(function.name.clone(), vec![&function.body]);
+            }
+
+            Definition::Test(test) | Definition::Benchmark(test) => {
+                bodies.insert(
+                    test.name.clone(),
+                    test.arguments
+                        .iter()
+                        .map(|arg| &arg.via)
+                        .chain(std::iter::once(&test.body))
+                        .collect(),
+                );
+            }
+
+            Definition::Validator(validator) => {
+                for handler in validator
+                    .handlers
+                    .iter()
+                    .chain(std::iter::once(&validator.fallback))
+                {
+                    bodies.insert(
+                        format!("{}.{}", validator.name, handler.name),
+                        vec![&handler.body],
+                    );
+                }
+            }
+
+            Definition::ModuleConstant(constant) => {
+                bodies.insert(constant.name.clone(), vec![&constant.value]);
+            }
+
+            Definition::DataType(..) | Definition::TypeAlias(..) | Definition::Use(..) => (),
+        }
+    }
+
+    outline::document_symbols(module)
+        .into_iter()
+        .flat_map(|symbol| {
+            if symbol.kind == SymbolKind::CLASS {
+                let validator = symbol.name.clone();
+
+                symbol
+                    .children
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|handler| (format!("{validator}.{}", handler.name), handler))
+                    .collect()
+            } else {
+                vec![(symbol.name.clone(), symbol)]
+            }
+        })
+        .filter_map(|(name, symbol)| {
+            Some(Caller {
+                bodies: bodies.remove(&name)?,
+                name,
+                symbol,
+            })
+        })
+        .collect()
+}
+
+fn item(compiler: &LspProject, module: &str, caller: &Caller<'_>) -> Option<CallHierarchyItem> {
+    let source = compiler.sources.get(module)?;
+
+    Some(CallHierarchyItem {
+        name: caller.name.clone(),
+        kind: caller.symbol.kind,
+        tags: None,
+        detail: Some(module.to_string()),
+        uri: path_to_uri(PathBuf::from(&source.path)).ok()?,
+        range: caller.symbol.range,
+        selection_range: caller.symbol.selection_range,
+        data: Some(serde_json::json!({ "module": module, "name": caller.name })),
+    })
+}
+
+/// The module and name of an item, as given back by the client.
+fn identify(item: &CallHierarchyItem) -> Option<(String, String)> {
+    let data = item.data.as_ref()?;
+
+    Some((
+        data.get("module")?.as_str()?.to_string(),
+        data.get("name")?.as_str()?.to_string(),
+    ))
+}
+
+/// The functions called within a definition, by module and name, along with the location of
+/// what's called, in order.
+fn calls(caller: &Caller<'_>) -> Vec<((String, String), Span)> {
+    let mut calls = Vec::new();
+
+    for body in caller.bodies.iter() {
+        collect(body, &mut calls);
+    }
+
+    calls
+}
+
+fn collect(expr: &TypedExpr, calls: &mut Vec<((String, String), Span)>) {
+    match expr {
+        TypedExpr::UInt { .. }
+        | TypedExpr::String { .. }
+        | TypedExpr::ByteArray { .. }
+        | TypedExpr::CurvePoint { .. }
+        | TypedExpr::Var { .. }
+        | TypedExpr::ModuleSelect { .. }
+        | TypedExpr::ErrorTerm { .. } => (),
+
+        TypedExpr::Sequence { expressions, .. } | TypedExpr::Pipeline { expressions, .. } => {
+            for expression in expressions {
+                collect(expression, calls);
+            }
+        }
+
+        TypedExpr::Call { fun, args, .. } => {
+            let callee = match fun.as_ref() {
+                TypedExpr::Var {
+                    constructor:
+                        ValueConstructor {
+                            variant: ValueConstructorVariant::ModuleFn { module, name, .. },
+                            ..
+                        },
+                    ..
+                }
+                | TypedExpr::ModuleSelect {
+                    constructor: ModuleValueConstructor::Fn { module, name, .. },
+                    ..
+                } => Some((module.clone(), name.clone())),
+                _ => None,
+            };
+
+            match callee {
+                Some(callee) => calls.push((callee, fun.location())),
+                None => collect(fun, calls),
+            }
+
+            for arg in args {
+                collect(&arg.value, calls);
+            }
+        }
+
+        TypedExpr::Fn { body, .. } => collect(body, calls),
+
+        TypedExpr::List { elements, tail, .. } => {
+            for element in elements {
+                collect(element, calls);
+            }
+
+            if let Some(tail) = tail {
+                collect(tail, calls);
+            }
+        }
+
+        TypedExpr::Tuple { elems, .. } => {
+            for elem in elems {
+                collect(elem, calls);
+            }
+        }
+
+        TypedExpr::RecordUpdate { spread, args, .. } => {
+            collect(spread, calls);
+
+            for arg in args {
+                collect(&arg.value, calls);
+            }
+        }
+
+        TypedExpr::When {
+            subject, clauses, ..
+        } => {
+            collect(subject, calls);
+
+            for clause in clauses {
+                collect(&clause.then, calls);
+            }
+        }
+
+        TypedExpr::If {
+            branches,
+            final_else,
+            ..
+        } => {
+            for branch in branches {
+                collect(&branch.condition, calls);
+                collect(&branch.body, calls);
+            }
+
+            collect(final_else, calls);
+        }
+
+        TypedExpr::BinOp { left, right, .. } => {
+            collect(left, calls);
+            collect(right, calls);
+        }
+
+        TypedExpr::Pair { fst, snd, .. } => {
+            collect(fst, calls);
+            collect(snd, calls);
+        }
+
+        TypedExpr::Trace { then, text, .. } => {
+            collect(text, calls);
+            collect(then, calls);
+        }
+
+        TypedExpr::Assignment { value, .. } => collect(value, calls),
+        TypedExpr::RecordAccess { record, .. } => collect(record, calls),
+        TypedExpr::TupleIndex { tuple, .. } => collect(tuple, calls),
+        TypedExpr::UnOp { value, .. } => collect(value, calls),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{incoming_calls, outgoing_calls, prepare};
+    use crate::{
+        server::lsp_project::LspProject,
+        tests::{index_of, project},
+    };
+    use indoc::indoc;
+    use lsp_types::CallHierarchyItem;
+
+    const PAYMENT: &str = indoc! { r#"
+        use fixture/money
+
+        pub fn transfer(from: ByteArray, to: ByteArray, amount: Int) -> Int {
+          amount + money.fee(from, to) + money.fee(to, from)
+        }
+
+        test transfer_costs_fees() {
+          transfer(#"00", #"01", 1) > 1
+        }
+    "#};
+
+    const MONEY: &str = indoc! { r#"
+        use aiken/builtin
+
+        pub fn fee(from: ByteArray, to: ByteArray) -> Int {
+          base(from) + base(to)
+        }
+
+        fn base(party: ByteArray) -> Int {
+          builtin.length_of_bytearray(party)
+        }
+    "#};
+
+    const PAY: &str = indoc! { r#"
+        use payment
+
+        validator pay {
+          spend(_datum: Option<Data>, redeemer: Int, _own_ref: Data, _self: Data) {
+            payment.transfer(#"00", #"01", redeemer) > 0
+          }
+
+          else(_) {
+            fail
+          }
+        }
+    "#};
+
+    fn payments() -> LspProject {
+        project(&[
+            ("lib/payment", PAYMENT),
+            ("lib/fixture/money", MONEY),
+            ("validators/pay", PAY),
+        ])
+    }
+
+    fn item(compiler: &LspProject, module: &str, text: &str, n: usize) -> CallHierarchyItem {
+        let items = prepare(
+            compiler,
+            &compiler.modules[module],
+            index_of(compiler, module, text, n) + 1,
+        )
+        .unwrap();
+
+        assert_eq!(items.len(), 1);
+
+        items.into_iter().next().unwrap()
+    }
+
+    /// Where a range starts, as '<line>:<column>'.
+    fn start(range: &lsp_types::Range) -> String {
+        format!("{}:{}", range.start.line, range.start.character)
+    }
+
+    #[test]
+    fn prepare_definition_or_reference() {
+        let compiler = payments();
+
+        let transfer = item(&compiler, "payment", "transfer", 0);
+
+        assert_eq!(transfer.name, "transfer");
+        assert_eq!(transfer.detail.as_deref(), Some("payment"));
+        assert_eq!(transfer.kind, lsp_types::SymbolKind::FUNCTION);
+        assert!(transfer.uri.path().ends_with("lib/payment.ak"));
+        assert_eq!(start(&transfer.selection_range), "2:7");
+
+        // Functions referred to are defined in the module they're imported from.
+        let fee = item(&compiler, "payment", "fee", 0);
+
+        assert_eq!(fee.name, "fee");
+        assert_eq!(fee.detail.as_deref(), Some("fixture/money"));
+        assert_eq!(start(&fee.selection_range), "2:7");
+
+        // Handlers are named after their validator.
+        let spend = item(&compiler, "pay", "spend", 0);
+
+        assert_eq!(spend.name, "pay.spend");
+        assert_eq!(spend.kind, lsp_types::SymbolKind::METHOD);
+    }
+
+    #[test]
+    fn incoming_across_modules() {
+        let compiler = payments();
+
+        let incoming = |item: &CallHierarchyItem| {
+            incoming_calls(&compiler, item)
+                .unwrap()
+                .into_iter()
+                .map(|call| {
+                    format!(
+                        "{} in {} at {}",
+                        call.from.name,
+                        call.from.detail.unwrap(),
+                        call.from_ranges
+                            .iter()
+                            .map(start)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            incoming(&item(&compiler, "payment", "transfer", 0)),
+            vec![
+                "pay.spend in pay at 4:4",
+                "transfer_costs_fees in payment at 7:2",
+            ]
+        );
+
+        assert_eq!(
+            incoming(&item(&compiler, "payment", "fee", 0)),
+            vec!["transfer in payment at 3:11, 3:33"]
+        );
+
+        assert!(incoming(&item(&compiler, "pay", "spend", 0)).is_empty());
+    }
+
+    #[test]
+    fn outgoing_across_modules() {
+        let compiler = payments();
+
+        let outgoing = |item: &CallHierarchyItem| {
+            outgoing_calls(&compiler, item)
+                .unwrap()
+                .into_iter()
+                .map(|call| {
+                    format!(
+                        "{} in {} at {}",
+                        call.to.name,
+                        call.to.detail.unwrap(),
+                        call.from_ranges
+                            .iter()
+                            .map(start)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            outgoing(&item(&compiler, "pay", "spend", 0)),
+            vec!["transfer in payment at 4:4"]
+        );
+
+        assert_eq!(
+            outgoing(&item(&compiler, "payment", "transfer", 0)),
+            vec!["fee in fixture/money at 3:11, 3:33"]
+        );
+
+        // Builtins aren't defined in any module.
+        let base = item(&compiler, "fixture/money", "base", 0);
+
+        assert!(outgoing(&base).is_empty());
+
+        assert_eq!(
+            outgoing(&item(&compiler, "fixture/money", "fee", 0)),
+            vec!["base in fixture/money at 3:2, 3:15"]
+        );
+    }
+}
@@ -0,0 +1,1054 @@
+//! Completion of identifiers.
+//!
+//! Completion requests come while a module is being edited, well before it parses again, let
+//! alone type-checks. So what kind of identifier is wanted is worked out from the edited text
+//! preceding the cursor, while candidates come from the scope of the last checked version of the
+//! module, around the same position.
+
+use crate::{
+    server::lsp_project::LspProject,
+    utils::{TextDiff, span_to_lsp_range},
+};
+use aiken_lang::{
+    ast::{Definition, Span},
+    line_numbers::LineNumbers,
+    tipo::{
+        Type, TypeConstructor, TypeInfo, ValueConstructor, ValueConstructorVariant,
+        pretty::Printer, scope::Scope,
+    },
+};
+use aiken_project::module::CheckedModule;
+use itertools::Itertools;
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionTextEdit, Documentation, MarkupContent,
+    MarkupKind, TextEdit,
+};
+use std::{collections::HashMap, rc::Rc};
+
+/// What the identifier under the cursor stands for, as far as the text preceding it tells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Context {
+    /// The path of an imported module, starting at the given byte index.
+    Module { path: String, start: usize },
+    /// A value or type imported unqualified from the given module.
+    Unqualified(String),
+    /// Whatever follows a dot after the given chain of identifiers: either a field, or a value or
+    /// type of an imported module.
+    Select(Vec<String>),
+    /// A type, in an annotation.
+    Annotation,
+    /// A constructor, in a pattern matching the value of the given chain of identifiers, when
+    /// it's known.
+    Pattern(Option<Vec<String>>),
+    /// An expression, possibly an argument of a call.
+    Expression(Option<Call>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Call {
+    /// The function or constructor called.
+    callee: Vec<String>,
+    /// Labels of the arguments given so far.
+    labels: Vec<String>,
+    /// How many arguments were given so far without a label, counting the value piped in.
+    positional: usize,
+}
+
+/// Completions at the given position of a module, whose current text may differ from the one
+/// last checked.
+pub fn completion(
+    compiler: &LspProject,
+    module: &CheckedModule,
+    text: &str,
+    position: lsp_types::Position,
+) -> Vec<CompletionItem> {
+    let line_numbers = LineNumbers::new(text);
+
+    let cursor = char_boundary(
+        text,
+        line_numbers.byte_index(position.line as usize, position.character as usize),
+    );
+
+    let Some(context) = context(&text[..cursor]) else {
+        return Vec::new();
+    };
+
+    let scope = Scope::new(
+        &module.ast,
+        checked_index(&module.code, text, cursor),
+        &compiler.module_types,
+    );
+
+    let modules = &compiler.module_types;
+
+    match context {
+        Context::Module { path, start } => {
+            let range = span_to_lsp_range(Span { start, end: cursor }, &line_numbers);
+
+            modules
+                .keys()
+                .filter(|name| name.starts_with(&path) && **name != module.name)
+                .sorted()
+                .map(|name| CompletionItem {
+                    label: name.clone(),
+                    kind: Some(CompletionItemKind::MODULE),
+                    documentation: module_documentation(compiler, name),
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                        range,
+                        new_text: name.clone(),
+                    })),
+                    ..Default::default()
+                })
+                .collect()
+        }
+
+        Context::Unqualified(path) => modules
+            .get(&path)
+            .map(|info| module_members(compiler, info))
+            .unwrap_or_default(),
+
+        Context::Select(chain) => {
+            if let [alias] = &chain[..] {
+                if !scope.values.contains_key(alias) {
+                    if let Some(info) = scope.modules.get(alias).and_then(|m| modules.get(m)) {
+                        return module_members(compiler, info);
+                    }
+                }
+            }
+
+            type_of(&scope, modules, &chain)
+                .map(|tipo| fields(compiler, &scope, &tipo))
+                .unwrap_or_default()
+        }
+
+        Context::Annotation => scope
+            .types
+            .iter()
+            .map(|(name, constructor)| type_item(compiler, name, name, constructor))
+            .chain(module_items(compiler, &scope))
+            .collect(),
+
+        Context::Pattern(subject) => {
+            let constructors = match subject.and_then(|chain| type_of(&scope, modules, &chain)) {
+                Some(tipo) => scope.constructors(&tipo, modules),
+                None => scope
+                    .values
+                    .iter()
+                    .filter(|(_, value)| {
+                        matches!(value.variant, ValueConstructorVariant::Record { .. })
+                    })
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect(),
+            };
+
+            constructors
+                .into_iter()
+                .map(|(name, value)| value_item(compiler, name, &value))
+                .collect()
+        }
+
+        Context::Expression(call) => call
+            .map(|call| labels(&scope, modules, &call))
+            .unwrap_or_default()
+            .into_iter()
+            .chain(
+                scope
+                    .values
+                    .iter()
+                    .map(|(name, value)| value_item(compiler, name.clone(), value)),
+            )
+            .chain(module_items(compiler, &scope))
+            .collect(),
+    }
+}
+
+/// Work out what the identifier being typed at the end of the given text stands for, if
+/// anything.
+fn context(text: &str) -> Option<Context> {
+    let before = text.trim_end_matches(is_identifier_char);
+    let word = &text[before.len()..];
+
+    let brackets = open_brackets(before)?;
+    let innermost = brackets.last().copied();
+
+    let line = line_at(before, before.len());
+    let statement = line.trim_start();
+    let trimmed = before.trim_end();
+
+    if let Some(path) = statement.strip_prefix("use ") {
+        if !path.contains('.') {
+            let path = path.trim_start();
+            return Some(Context::Module {
+                path: format!("{path}{word}"),
+                start: before.len() - path.len(),
+            });
+        }
+    }
+
+    // Unqualified imports may span several lines.
+    if let Some(('{', ix)) = innermost {
+        if let Some(path) = line_at(before, ix)
+            .trim_start()
+            .strip_prefix("use ")
+            .and_then(|path| path.trim_end().strip_suffix('.'))
+        {
+            return Some(Context::Unqualified(path.trim().to_string()));
+        }
+    }
+
+    if let Some(chain) = before.strip_suffix('.') {
+        let chain = chain_before(chain);
+        return (!chain.is_empty()).then_some(Context::Select(chain));
+    }
+
+    let when_subject = match innermost {
+        Some(('{', ix)) => when_subject(&before[..ix]),
+        _ => None,
+    };
+
+    if trimmed.ends_with("->") {
+        return Some(match when_subject {
+            Some(..) => Context::Expression(None),
+            None => Context::Annotation,
+        });
+    }
+
+    if matches!(innermost, Some(('<', _))) || in_type_definition(before, statement, &brackets) {
+        return Some(Context::Annotation);
+    }
+
+    if trimmed.ends_with(':') && !trimmed.ends_with("::") {
+        if in_signature(before, &brackets) || in_binding(statement) {
+            return Some(Context::Annotation);
+        }
+    } else if in_signature(before, &brackets) {
+        // Arguments being named.
+        return None;
+    }
+
+    if let Some(subject) = when_subject {
+        let clause_start = line.trim().is_empty() || line.trim_end().ends_with('|');
+        let previous_line = before[..before.len() - line.len()].trim_end();
+
+        if clause_start && !previous_line.ends_with("->") {
+            return Some(Context::Pattern(subject));
+        }
+    }
+
+    if let Some(condition) = strip_keyword_suffix(trimmed, "is") {
+        if let Some(ix) = condition.rfind("if ") {
+            return Some(Context::Pattern(exact_chain(&condition[ix + 3..])));
+        }
+    }
+
+    let call = match innermost {
+        Some((open @ ('(' | '{'), ix)) => {
+            let head = before[..ix].trim_end();
+
+            let callee = chain_before(head);
+
+            let is_constructor = callee
+                .last()
+                .is_some_and(|name| name.starts_with(char::is_uppercase));
+
+            let is_piped = open == '('
+                && head[..head.len() - callee.join(".").len()]
+                    .trim_end()
+                    .ends_with("|>");
+
+            let arguments = arguments(&before[ix + 1..]);
+
+            let (current, previous) = arguments.split_last()?;
+
+            let is_record = open == '{' && is_constructor;
+
+            (current.trim().is_empty() && (open == '(' || is_constructor)).then(|| Call {
+                callee,
+                labels: previous
+                    .iter()
+                    .filter_map(|argument| labelled(argument, is_record))
+                    .map(|label| label.to_string())
+                    .collect(),
+                positional: previous
+                    .iter()
+                    .filter(|argument| labelled(argument, is_record).is_none())
+                    .count()
+                    + usize::from(is_piped),
+            })
+        }
+        _ => None,
+    };
+
+    Some(Context::Expression(call))
+}
+
+/// The byte index in the checked text corresponding to the given one in the edited text. Text
+/// left untouched on either side of the edits maps exactly; anywhere within the edits maps to
+/// where they start, which is usually close enough to share the same scope.
+pub fn checked_index(checked: &str, edited: &str, index: usize) -> usize {
+    char_boundary(checked, TextDiff::new(checked, edited).checked_index(index))
+}
+
+pub fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+pub fn char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+
+    index
+}
+
+/// The part of the line holding the given byte index, up to that index.
+pub fn line_at(text: &str, index: usize) -> &str {
+    let start = text[..index].rfind('\n').map(|ix| ix + 1).unwrap_or(0);
+    &text[start..index]
+}
+
+fn ends_with_type_name(text: &str) -> bool {
+    text[text.trim_end_matches(is_identifier_char).len()..].starts_with(char::is_uppercase)
+}
+
+pub fn strip_keyword_suffix<'a>(text: &'a str, keyword: &str) -> Option<&'a str> {
+    text.strip_suffix(keyword)
+        .filter(|rest| !rest.ends_with(is_identifier_char))
+}
+
+/// The chain of identifiers separated by dots at the end of the given text, e.g. 'self.datum'.
+/// The chain is empty when the text doesn't end with one, or when it's preceded by anything
+/// else than an identifier, as in 'foo(bar).baz'.
+pub fn chain_before(text: &str) -> Vec<String> {
+    let mut chain = Vec::new();
+    let mut rest = text;
+
+    loop {
+        let start = rest.trim_end_matches(is_identifier_char).len();
+        let identifier = &rest[start..];
+
+        if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+            return Vec::new();
+        }
+
+        chain.push(identifier.to_string());
+        rest = &rest[..start];
+
+        match rest.strip_suffix('.') {
+            Some(prefix) if !prefix.ends_with('.') => rest = prefix,
+            _ => break,
+        }
+    }
+
+    chain.reverse();
+    chain
+}
+
+/// The chain of identifiers making up the whole of the given text, if it is one.
+fn exact_chain(text: &str) -> Option<Vec<String>> {
+    let text = text.trim();
+    let chain = chain_before(text);
+    (!chain.is_empty() && chain.join(".") == text).then_some(chain)
+}
+
+/// The subject of the 'when' expression whose clauses open right after the given text, if it is
+/// one. The subject itself is only known when it's a chain of identifiers.
+fn when_subject(text: &str) -> Option<Option<Vec<String>>> {
+    let head = strip_keyword_suffix(text.trim_end(), "is")?;
+    let ix = head.rfind("when ")?;
+    Some(exact_chain(&head[ix + 5..]))
+}
+
+/// The brackets left open at the end of the given text, the innermost one last, along with their
+/// byte index. Angle brackets only count when they follow a type name. Nothing is returned when
+/// the text ends within a string or a comment.
+pub fn open_brackets(text: &str) -> Option<Vec<(char, usize)>> {
+    let mut open: Vec<(char, usize)> = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((ix, c)) = chars.next() {
+        match c {
+            '"' => loop {
+                match chars.next() {
+                    None => return None,
+                    Some((_, '\\')) => {
+                        chars.next();
+                    }
+                    Some((_, '"')) => break,
+                    Some(..) => (),
+                }
+            },
+
+            '/' if chars.peek().is_some_and(|(_, c)| *c == '/') => loop {
+                match chars.next() {
+                    None => return None,
+                    Some((_, '\n')) => break,
+                    Some(..) => (),
+                }
+            },
+
+            '(' | '{' | '[' => open.push((c, ix)),
+
+            '<' if ends_with_type_name(&text[..ix]) => open.push((c, ix)),
+
+            ')' | '}' | ']' | '>' => {
+                let opening = match c {
+                    ')' => '(',
+                    '}' => '{',
+                    ']' => '[',
+                    _ => '<',
+                };
+
+                // Angle brackets left open were comparisons after all.
+                while c != '>' && open.last().is_some_and(|(c, _)| *c == '<') {
+                    open.pop();
+                }
+
+                // Closing angle brackets are also found in arrows and comparisons.
+                if open.last().is_some_and(|(c, _)| *c == opening) {
+                    open.pop();
+                }
+            }
+
+            _ => (),
+        }
+    }
+
+    Some(open)
+}
+
+/// The arguments in the given text, which follows an opening bracket: whatever lies between the
+/// commas that aren't nested within other brackets, strings or comments.
+pub fn arguments(text: &str) -> Vec<&str> {
+    let mut arguments = Vec::new();
+    let mut depth = 0_usize;
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((ix, c)) = chars.next() {
+        match c {
+            '"' => {
+                let mut escaped = false;
+
+                for (_, c) in chars.by_ref() {
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '"' => break,
+                        _ => (),
+                    }
+                }
+            }
+
+            '/' if chars.peek().is_some_and(|(_, c)| *c == '/') => {
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+
+            '(' | '[' | '{' => depth += 1,
+
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+
+            ',' if depth == 0 => {
+                arguments.push(&text[start..ix]);
+                start = ix + 1;
+            }
+
+            _ => (),
+        }
+    }
+
+    arguments.push(&text[start..]);
+
+    arguments
+}
+
+/// The label an argument is given by, if any.
+pub fn label_of(argument: &str) -> Option<&str> {
+    let (label, _) = argument.split_once(':')?;
+    let label = label.trim();
+
+    (label.starts_with(char::is_lowercase) && label.chars().all(is_identifier_char))
+        .then_some(label)
+}
+
+/// The label an argument is given by, also counting fields of a constructor given in shorthand.
+pub fn labelled(argument: &str, is_record: bool) -> Option<&str> {
+    label_of(argument).or_else(|| {
+        let argument = argument.trim();
+        (is_record && argument.chars().all(is_identifier_char)).then_some(argument)
+    })
+}
+
+/// Whether the text ends within the definition of a type, past the name of a constructor.
+pub fn in_type_definition(text: &str, statement: &str, brackets: &[(char, usize)]) -> bool {
+    let is_type_head = |line: &str| {
+        let line = line.trim_start();
+        let line = line.strip_prefix("pub ").unwrap_or(line);
+        let line = line.strip_prefix("opaque ").unwrap_or(line);
+        line.starts_with("type ")
+    };
+
+    match brackets {
+        [] => is_type_head(statement) && statement.contains('='),
+        [(_, ix), _, ..] => is_type_head(line_at(text, *ix)),
+        [_] => false,
+    }
+}
+
+/// Whether the text ends within the arguments of a function, handler, validator or test being
+/// defined.
+pub fn in_signature(text: &str, brackets: &[(char, usize)]) -> bool {
+    let Some(('(', ix)) = brackets.last() else {
+        return false;
+    };
+
+    let head = text[..*ix].trim_end();
+
+    if strip_keyword_suffix(head, "fn").is_some() {
+        return true;
+    }
+
+    let head = head.trim_end_matches(is_identifier_char).trim_end();
+
+    ["fn", "validator", "test", "bench"]
+        .iter()
+        .any(|keyword| strip_keyword_suffix(head, keyword).is_some())
+        || matches!(brackets, [('{', validator), _] if line_at(text, *validator).trim_start().starts_with("validator"))
+}
+
+/// Whether the statement is the left-hand side of an assignment.
+fn in_binding(statement: &str) -> bool {
+    (statement.starts_with("let ") || statement.starts_with("expect ")) && !statement.contains('=')
+}
+
+/// The type of the value a chain of identifiers refers to: a variable, or a value of an imported
+/// module, followed by record fields.
+pub fn type_of(
+    scope: &Scope,
+    modules: &HashMap<String, TypeInfo>,
+    chain: &[String],
+) -> Option<Rc<Type>> {
+    let (first, rest) = chain.split_first()?;
+
+    let (mut tipo, rest) = match scope.values.get(first) {
+        Some(value) => (value.tipo.clone(), rest),
+        None => {
+            let module = modules.get(scope.modules.get(first)?)?;
+            let (second, rest) = rest.split_first()?;
+            (module.values.get(second)?.tipo.clone(), rest)
+        }
+    };
+
+    for label in rest {
+        tipo = scope
+            .accessors(&tipo, modules)?
+            .accessors
+            .get(label)?
+            .tipo
+            .clone();
+    }
+
+    Some(tipo)
+}
+
+pub fn pretty(tipo: &Type) -> String {
+    Printer::new().pretty_print(tipo, 0)
+}
+
+pub fn markdown(value: String) -> Documentation {
+    Documentation::MarkupContent(MarkupContent {
+        kind: MarkupKind::Markdown,
+        value,
+    })
+}
+
+/// The doc comment of a top-level definition, or of a constructor.
+fn documentation(compiler: &LspProject, module: &str, name: &str) -> Option<Documentation> {
+    compiler
+        .modules
+        .get(module)?
+        .ast
+        .definitions()
+        .find_map(|definition| match definition {
+            Definition::Fn(function) if function.name == name => function.doc.clone(),
+            Definition::ModuleConstant(constant) if constant.name == name => constant.doc.clone(),
+            Definition::TypeAlias(alias) if alias.alias == name => alias.doc.clone(),
+            Definition::DataType(data_type) if data_type.name == name => data_type.doc.clone(),
+            Definition::DataType(data_type) => data_type
+                .constructors
+                .iter()
+                .find(|constructor| constructor.name == name)
+                .and_then(|constructor| constructor.doc.clone()),
+            _ => None,
+        })
+        .map(markdown)
+}
+
+fn module_documentation(compiler: &LspProject, module: &str) -> Option<Documentation> {
+    let docs = &compiler.modules.get(module)?.ast.docs;
+    (!docs.is_empty()).then(|| markdown(docs.join("\n")))
+}
+
+fn value_item(compiler: &LspProject, label: String, value: &ValueConstructor) -> CompletionItem {
+    let (kind, documentation) = match &value.variant {
+        ValueConstructorVariant::LocalVariable { .. } => (CompletionItemKind::VARIABLE, None),
+        ValueConstructorVariant::ModuleConstant { module, name, .. } => (
+            CompletionItemKind::CONSTANT,
+            documentation(compiler, module, name),
+        ),
+        ValueConstructorVariant::ModuleFn { module, name, .. } => (
+            CompletionItemKind::FUNCTION,
+            documentation(compiler, module, name),
+        ),
+        ValueConstructorVariant::Record { module, name, .. } => (
+            CompletionItemKind::CONSTRUCTOR,
+            documentation(compiler, module, name),
+        ),
+    };
+
+    CompletionItem {
+        label,
+        kind: Some(kind),
+        detail: Some(pretty(&value.tipo)),
+        documentation,
+        ..Default::default()
+    }
+}
+
+fn type_item(
+    compiler: &LspProject,
+    label: &str,
+    name: &str,
+    constructor: &TypeConstructor,
+) -> CompletionItem {
+    CompletionItem {
+        label: label.to_string(),
+        kind: Some(CompletionItemKind::STRUCT),
+        detail: Some(pretty(&constructor.tipo)),
+        documentation: documentation(compiler, &constructor.module, name),
+        ..Default::default()
+    }
+}
+
+fn module_items<'a>(
+    compiler: &'a LspProject,
+    scope: &'a Scope,
+) -> impl Iterator<Item = CompletionItem> + 'a {
+    scope.modules.iter().map(|(alias, module)| CompletionItem {
+        label: alias.clone(),
+        kind: Some(CompletionItemKind::MODULE),
+        detail: Some(module.clone()),
+        documentation: module_documentation(compiler, module),
+        ..Default::default()
+    })
+}
+
+/// The public values and types of a module.
+fn module_members(compiler: &LspProject, info: &TypeInfo) -> Vec<CompletionItem> {
+    info.values
+        .iter()
+        .map(|(name, value)| value_item(compiler, name.clone(), value))
+        .chain(
+            info.types
+                .iter()
+                .map(|(name, constructor)| type_item(compiler, name, name, constructor)),
+        )
+        .collect()
+}
+
+/// The fields of a record type.
+fn fields(compiler: &LspProject, scope: &Scope, tipo: &Type) -> Vec<CompletionItem> {
+    let Some((module, type_name)) = tipo.qualifier() else {
+        return Vec::new();
+    };
+
+    let Some(accessors) = scope.accessors(tipo, &compiler.module_types) else {
+        return Vec::new();
+    };
+
+    let module = if module.is_empty() {
+        scope.module.clone()
+    } else {
+        module
+    };
+
+    accessors
+        .accessors
+        .values()
+        .sorted_by_key(|accessor| accessor.index)
+        .map(|accessor| CompletionItem {
+            label: accessor.label.clone(),
+            kind: Some(CompletionItemKind::FIELD),
+            detail: Some(pretty(&accessor.tipo)),
+            documentation: field_documentation(compiler, &module, &type_name, &accessor.label),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn field_documentation(
+    compiler: &LspProject,
+    module: &str,
+    type_name: &str,
+    label: &str,
+) -> Option<Documentation> {
+    compiler
+        .modules
+        .get(module)?
+        .ast
+        .definitions()
+        .find_map(|definition| match definition {
+            Definition::DataType(data_type) if data_type.name == type_name => data_type
+                .constructors
+                .first()?
+                .arguments
+                .iter()
+                .find(|arg| arg.label.as_deref() == Some(label))?
+                .doc
+                .clone(),
+            _ => None,
+        })
+        .map(markdown)
+}
+
+/// The labelled arguments of the function or constructor called, which haven't been given yet,
+/// by label or position. They're listed first, in order.
+fn labels(scope: &Scope, modules: &HashMap<String, TypeInfo>, call: &Call) -> Vec<CompletionItem> {
+    let Some(callee) = callee(scope, modules, &call.callee) else {
+        return Vec::new();
+    };
+
+    let field_map = match &callee.variant {
+        ValueConstructorVariant::ModuleFn { field_map, .. }
+        | ValueConstructorVariant::Record { field_map, .. } => field_map.as_ref(),
+        ValueConstructorVariant::LocalVariable { .. }
+        | ValueConstructorVariant::ModuleConstant { .. } => None,
+    };
+
+    let Some(field_map) = field_map else {
+        return Vec::new();
+    };
+
+    let arg_types = callee.tipo.function_types().map(|(args, _)| args);
+
+    // Arguments given without a label fill the parameters not given by label, in order.
+    let taken = field_map
+        .fields
+        .iter()
+        .filter(|(label, _)| call.labels.contains(label))
+        .map(|(_, (index, _))| *index)
+        .collect::<Vec<_>>();
+
+    let filled = (0..field_map.arity)
+        .filter(|index| !taken.contains(index))
+        .take(call.positional)
+        .collect::<Vec<_>>();
+
+    field_map
+        .fields
+        .iter()
+        .filter(|(label, (index, _))| !call.labels.contains(label) && !filled.contains(index))
+        .sorted_by_key(|(_, (index, _))| *index)
+        .map(|(label, (index, _))| CompletionItem {
+            label: format!("{label}:"),
+            kind: Some(CompletionItemKind::PROPERTY),
+            detail: arg_types
+                .as_ref()
+                .and_then(|args| args.get(*index))
+                .map(|tipo| pretty(tipo)),
+            insert_text: Some(format!("{label}: ")),
+            sort_text: Some(format!("0{index:03}")),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// The value a callee refers to: one in scope, or a value of an imported module.
+pub fn callee<'a>(
+    scope: &'a Scope,
+    modules: &'a HashMap<String, TypeInfo>,
+    chain: &[String],
+) -> Option<&'a ValueConstructor> {
+    match chain {
+        [name] => scope.values.get(name),
+        [alias, name] => scope
+            .modules
+            .get(alias)
+            .and_then(|module| modules.get(module))
+            .and_then(|module| module.values.get(name)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::completion;
+    use crate::{server::lsp_project::LspProject, tests::project, utils::span_to_lsp_range};
+    use aiken_lang::{ast::Span, line_numbers::LineNumbers};
+    use indoc::indoc;
+    use lsp_types::{CompletionItem, Documentation};
+
+    const WALLET: &str = indoc! { r#"
+        use fixture/money.{Ada, Currency, Money, Other}
+
+        /// A wallet, holding money.
+        pub type Wallet {
+          /// Who owns it.
+          owner: ByteArray,
+          /// What it holds.
+          balance: Money,
+        }
+
+        /// The amount held in a wallet.
+        pub fn amount(wallet: Wallet) -> Int {
+          let balance = wallet.balance
+          balance.amount
+        }
+
+        pub fn code(currency: Currency) -> ByteArray {
+          when currency is {
+            Ada -> "ada"
+            Other { code } -> code
+          }
+        }
+
+        pub fn convert(wallet: Wallet) -> Money {
+          money.exchange(wallet.balance, to: Ada, at: money.rate)
+        }
+
+        pub fn empty() -> Wallet {
+          Wallet { owner: "", balance: money.ada(0) }
+        }
+    "#};
+
+    const MONEY: &str = indoc! { r#"
+        //// Amounts of money, in some currency.
+
+        /// An amount of money.
+        pub type Money {
+          /// How much of it.
+          amount: Int,
+          /// What it's counted in.
+          currency: Currency,
+        }
+
+        /// A currency, by its code.
+        pub type Currency {
+          /// The currency of Cardano.
+          Ada
+          /// Any other currency.
+          Other { code: ByteArray }
+        }
+
+        /// A usual exchange rate.
+        pub const rate: Int = 2
+
+        /// Some money in ada.
+        pub fn ada(amount: Int) -> Money {
+          Money { amount, currency: Ada }
+        }
+
+        /// Exchange money to another currency, at some rate.
+        pub fn exchange(money: Money, to currency: Currency, at rate: Int) -> Money {
+          Money { amount: money.amount * rate, currency }
+        }
+    "#};
+
+    fn wallet() -> LspProject {
+        project(&[("lib/wallet", WALLET), ("lib/fixture/money", MONEY)])
+    }
+
+    /// An item as '<label> <kind> <detail>', followed by its documentation if any.
+    fn show(item: &CompletionItem) -> String {
+        let mut shown = format!(
+            "{} {:?} {}",
+            item.label,
+            item.kind.unwrap(),
+            item.detail.as_deref().unwrap_or_default()
+        );
+
+        if let Some(Documentation::MarkupContent(doc)) = &item.documentation {
+            shown.push_str(&format!(" | {}", doc.value.trim()));
+        }
+
+        shown
+    }
+
+    /// The completions after typing some text right before the first occurrence of another, in
+    /// the wallet module.
+    fn complete(compiler: &LspProject, before: &str, typed: &str) -> Vec<String> {
+        let module = &compiler.modules["wallet"];
+
+        let at = module.code.find(before).unwrap();
+
+        let text = format!("{}{typed}{}", &module.code[..at], &module.code[at..]);
+
+        let cursor = at + typed.len();
+
+        let position = span_to_lsp_range(
+            Span {
+                start: cursor,
+                end: cursor,
+            },
+            &LineNumbers::new(&text),
+        )
+        .start;
+
+        completion(compiler, module, &text, position)
+            .iter()
+            .map(show)
+            .collect()
+    }
+
+    fn assert_contains(items: &[String], expected: &[&str]) {
+        for item in expected {
+            assert!(items.iter().any(|i| i == item), "no '{item}' in {items:#?}");
+        }
+    }
+
+    #[test]
+    fn complete_values() {
+        let compiler = wallet();
+
+        let items = complete(&compiler, "  balance.amount", "  b");
+
+        assert_contains(
+            &items,
+            &[
+                "balance Variable Money",
+                "wallet Variable Wallet",
+                "amount Function fn(Wallet) -> Int | The amount held in a wallet.",
+                "Ada Constructor Currency | The currency of Cardano.",
+                "Other Constructor fn(ByteArray) -> Currency | Any other currency.",
+                "money Module fixture/money | Amounts of money, in some currency.",
+            ],
+        );
+
+        // Variables of other functions aren't in scope.
+        assert!(!items.iter().any(|item| item.starts_with("currency ")));
+    }
+
+    #[test]
+    fn complete_module_members() {
+        let compiler = wallet();
+
+        let items = complete(&compiler, "  balance.amount", "  money.");
+
+        assert_contains(
+            &items,
+            &[
+                "rate Constant Int | A usual exchange rate.",
+                "ada Function fn(Int) -> Money | Some money in ada.",
+                "exchange Function fn(Money, Currency, Int) -> Money | Exchange money to another currency, at some rate.",
+                "Money Struct Money | An amount of money.",
+                "Currency Struct Currency | A currency, by its code.",
+            ],
+        );
+    }
+
+    #[test]
+    fn complete_fields() {
+        let compiler = wallet();
+
+        assert_eq!(
+            complete(&compiler, "  balance.amount", "  wallet."),
+            vec![
+                "owner Field ByteArray | Who owns it.",
+                "balance Field Money | What it holds.",
+            ]
+        );
+
+        // Through nested records, across modules.
+        assert_eq!(
+            complete(&compiler, "  balance.amount", "  wallet.balance."),
+            vec![
+                "amount Field Int | How much of it.",
+                "currency Field Currency | What it's counted in.",
+            ]
+        );
+    }
+
+    #[test]
+    fn complete_patterns() {
+        let compiler = wallet();
+
+        // Only the constructors of the type of the subject.
+        let mut items = complete(&compiler, "    Ada -> ", "    O");
+
+        items.sort();
+
+        assert_eq!(
+            items,
+            vec![
+                "Ada Constructor Currency | The currency of Cardano.",
+                "Other Constructor fn(ByteArray) -> Currency | Any other currency.",
+            ]
+        );
+    }
+
+    #[test]
+    fn complete_annotations() {
+        let compiler = wallet();
+
+        let expected = [
+            "Wallet Struct Wallet | A wallet, holding money.",
+            "Money Struct Money | An amount of money.",
+            "Currency Struct Currency | A currency, by its code.",
+        ];
+
+        // In a signature.
+        let items = complete(&compiler, "Currency) -> ByteArray", "C");
+        assert_contains(&items, &expected);
+        assert!(!items.iter().any(|item| item.starts_with("amount ")));
+
+        // As a return type.
+        assert_contains(&complete(&compiler, "Money {\n  money", "M"), &expected);
+
+        // On a binding.
+        assert_contains(&complete(&compiler, " = wallet.balance", ": M"), &expected);
+    }
+
+    #[test]
+    fn complete_labels() {
+        let compiler = wallet();
+
+        let labels = |items: Vec<String>| {
+            items
+                .into_iter()
+                .filter(|item| item.contains(": Property"))
+                .collect::<Vec<_>>()
+        };
+
+        // Arguments given by position, or piped in, aren't suggested again either.
+        assert_eq!(
+            labels(complete(&compiler, "to: Ada", "")),
+            vec!["to: Property Currency", "at: Property Int"]
+        );
+
+        assert_eq!(
+            labels(complete(
+                &compiler,
+                "  balance.amount",
+                "  wallet.balance |> money.exchange("
+            )),
+            vec!["to: Property Currency", "at: Property Int"]
+        );
+
+        assert_eq!(
+            labels(complete(&compiler, "at: money.rate", "")),
+            vec!["at: Property Int"]
+        );
+
+        // Likewise for the fields of a record being constructed.
+        assert_eq!(
+            labels(complete(&compiler, "balance: money.ada", "")),
+            vec!["balance: Property Money"]
+        );
+    }
+}
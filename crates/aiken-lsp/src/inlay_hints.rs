@@ -0,0 +1,405 @@
+//! Inlay hints, showing the types the type-checker inferred where none is written: on `let`
+//! bindings, including backpassing ones, on arguments of anonymous functions, and after each stage
+//! of pipelines spanning several lines.
+
+use crate::utils::TextDiff;
+use aiken_lang::{
+    ast::{
+        ArgName, AssignmentKind, CAPTURE_VARIABLE, Definition, PIPE_VARIABLE, Pattern, Span,
+        TypedArg,
+    },
+    expr::TypedExpr,
+    line_numbers::LineNumbers,
+    tipo::{Type, pretty::Printer},
+};
+use aiken_project::module::CheckedModule;
+use lsp_types::{InlayHint, InlayHintKind, InlayHintLabel};
+
+/// The hints of a module within the given range of its current text, which may differ from the
+/// one last checked. Hints within the edits since are left out.
+pub fn inlay_hints(module: &CheckedModule, text: &str, range: lsp_types::Range) -> Vec<InlayHint> {
+    let mut collector = Collector {
+        code: &module.code,
+        hints: Vec::new(),
+    };
+
+    for definition in module.ast.definitions() {
+        match definition {
+            Definition::Fn(function) => collector.expr(&function.body),
+
+            Definition::Test(test) | Definition::Benchmark(test) => {
+                for arg in test.arguments.iter() {
+                    collector.expr(&arg.via);
+                }
+
+                collector.expr(&test.body);
+            }
+
+            Definition::Validator(validator) => {
+                for handler in validator
+                    .handlers
+                    .iter()
+                    .chain(std::iter::once(&validator.fallback))
+                {
+                    collector.expr(&handler.body);
+                }
+            }
+
+            Definition::ModuleConstant(constant) => collector.expr(&constant.value),
+
+            Definition::DataType(..) | Definition::TypeAlias(..) | Definition::Use(..) => (),
+        }
+    }
+
+    let diff = TextDiff::new(&module.code, text);
+
+    let line_numbers = LineNumbers::new(text);
+
+    collector
+        .hints
+        .into_iter()
+        .filter_map(|(index, label, is_stage)| {
+            let index = diff.edited_span(Span::create(index, 0))?.start;
+
+            let position = line_numbers.line_and_column_number(index)?;
+
+            let position = lsp_types::Position {
+                line: position.line as u32 - 1,
+                character: position.column as u32 - 1,
+            };
+
+            if position < range.start || position > range.end {
+                return None;
+            }
+
+            Some(InlayHint {
+                position,
+                label: InlayHintLabel::String(label),
+                kind: Some(InlayHintKind::TYPE),
+                text_edits: None,
+                tooltip: None,
+                padding_left: Some(is_stage),
+                padding_right: None,
+                data: None,
+            })
+        })
+        .collect()
+}
+
+struct Collector<'a> {
+    code: &'a str,
+    /// Hints by the byte index they go at, in the checked text, along with whether they follow a
+    /// pipeline stage.
+    hints: Vec<(usize, String, bool)>,
+}
+
+impl Collector<'_> {
+    fn annotate(&mut self, location: Span, tipo: &Type) {
+        self.hints.push((
+            location.end,
+            format!(": {}", Printer::new().pretty_print(tipo, 0)),
+            false,
+        ));
+    }
+
+    fn arg(&mut self, arg: &TypedArg) {
+        if let ArgName::Named { name, location, .. } = &arg.arg_name {
+            if arg.annotation.is_none() && is_variable_name(name) {
+                self.annotate(*location, &arg.tipo);
+            }
+        }
+    }
+
+    /// Hint at the type of a pipeline stage, when the next one starts on another line.
+    fn stage(&mut self, stage: &TypedExpr) {
+        let location = stage.location();
+
+        let Some(rest) = self.code.get(location.end..) else {
+            return;
+        };
+
+        let next = rest.trim_start();
+
+        if next.starts_with("|>") && rest[..rest.len() - next.len()].contains('\n') {
+            self.hints.push((
+                location.end,
+                Printer::new().pretty_print(&stage.tipo(), 0),
+                true,
+            ));
+        }
+    }
+
+    fn expr(&mut self, expr: &TypedExpr) {
+        match expr {
+            TypedExpr::UInt { .. }
+            | TypedExpr::String { .. }
+            | TypedExpr::ByteArray { .. }
+            | TypedExpr::CurvePoint { .. }
+            | TypedExpr::Var { .. }
+            | TypedExpr::ModuleSelect { .. }
+            | TypedExpr::ErrorTerm { .. } => (),
+
+            TypedExpr::Sequence { expressions, .. } => {
+                for expression in expressions {
+                    self.expr(expression);
+                }
+            }
+
+            TypedExpr::Pipeline { expressions, .. } => {
+                for expression in expressions {
+                    match expression {
+                        TypedExpr::Assignment {
+                            pattern: Pattern::Var { name, .. },
+                            value,
+                            ..
+                        } if name == PIPE_VARIABLE => {
+                            self.stage(value);
+                            self.expr(value);
+                        }
+                        _ => self.expr(expression),
+                    }
+                }
+            }
+
+            TypedExpr::Assignment {
+                value,
+                pattern,
+                annotation,
+                kind,
+                ..
+            } => {
+                if let (AssignmentKind::Let { .. }, Pattern::Var { location, name }, None) =
+                    (kind, pattern, annotation)
+                {
+                    if is_variable_name(name) {
+                        self.annotate(*location, &value.tipo());
+                    }
+                }
+
+                self.expr(value);
+            }
+
+            TypedExpr::Fn {
+                args,
+                body,
+                is_capture,
+                ..
+            } => {
+                if !is_capture {
+                    for arg in args {
+                        self.arg(arg);
+                    }
+                }
+
+                self.expr(body);
+            }
+
+            TypedExpr::List { elements, tail, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+
+                if let Some(tail) = tail {
+                    self.expr(tail);
+                }
+            }
+
+            TypedExpr::Tuple { elems, .. } => {
+                for elem in elems {
+                    self.expr(elem);
+                }
+            }
+
+            TypedExpr::Call { fun, args, .. } => {
+                self.expr(fun);
+
+                for arg in args {
+                    self.expr(&arg.value);
+                }
+            }
+
+            TypedExpr::RecordUpdate { spread, args, .. } => {
+                self.expr(spread);
+
+                for arg in args {
+                    self.expr(&arg.value);
+                }
+            }
+
+            TypedExpr::When {
+                subject, clauses, ..
+            } => {
+                self.expr(subject);
+
+                for clause in clauses {
+                    self.expr(&clause.then);
+                }
+            }
+
+            TypedExpr::If {
+                branches,
+                final_else,
+                ..
+            } => {
+                for branch in branches {
+                    self.expr(&branch.condition);
+                    self.expr(&branch.body);
+                }
+
+                self.expr(final_else);
+            }
+
+            TypedExpr::BinOp { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+
+            TypedExpr::Pair { fst, snd, .. } => {
+                self.expr(fst);
+                self.expr(snd);
+            }
+
+            TypedExpr::Trace { then, text, .. } => {
+                self.expr(text);
+                self.expr(then);
+            }
+
+            TypedExpr::RecordAccess { record, .. } => self.expr(record),
+            TypedExpr::TupleIndex { tuple, .. } => self.expr(tuple),
+            TypedExpr::UnOp { value, .. } => self.expr(value),
+        }
+    }
+}
+
+/// Whether a name is one of a variable written in the source code, rather than one made up by
+/// the compiler for pipelines, captures or backpassing.
+fn is_variable_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase()) && !name.contains(CAPTURE_VARIABLE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::inlay_hints;
+    use crate::tests::project;
+    use indoc::indoc;
+    use lsp_types::{InlayHint, InlayHintLabel, Position, Range};
+
+    const SOURCE: &str = indoc! { r#"
+        fn apply(f: fn(Int) -> a, n: Int) -> a {
+          f(n)
+        }
+
+        fn then(n: Int, f: fn(Int) -> Option<Int>) -> Option<Int> {
+          f(n)
+        }
+
+        pub fn total(amounts: List<Int>) -> Int {
+          let count = 14
+          let sum: Int = count * 2
+          let (a, b) = (sum, count)
+          let doubled = apply(fn(n) { n * 2 }, a + b)
+          let described =
+            doubled
+              |> apply(fn(n: Int) { n + 1 }, _)
+              |> Some
+          expect Some(x) = described
+          x + apply(fn(n) { n }, 1) |> apply(fn(n) { n }, _)
+        }
+
+        pub fn backpass(n: Int) -> Option<Int> {
+          let m <- then(n)
+          Some(m)
+        }
+    "#};
+
+    /// Hints as '<line>:<column> <label>', with a leading space when they're padded.
+    fn show(hints: &[InlayHint]) -> Vec<String> {
+        hints
+            .iter()
+            .map(|hint| {
+                let InlayHintLabel::String(label) = &hint.label else {
+                    unreachable!()
+                };
+
+                format!(
+                    "{}:{} {}{label}",
+                    hint.position.line,
+                    hint.position.character,
+                    if hint.padding_left == Some(true) {
+                        " "
+                    } else {
+                        ""
+                    }
+                )
+            })
+            .collect()
+    }
+
+    fn whole() -> Range {
+        Range {
+            start: Position::new(0, 0),
+            end: Position::new(u32::MAX, 0),
+        }
+    }
+
+    #[test]
+    fn hint_inferred_types() {
+        let compiler = project(&[("lib/foo", SOURCE)]);
+
+        let hints = inlay_hints(&compiler.modules["foo"], SOURCE, whole());
+
+        // Annotated bindings and arguments, patterns other than variables and stages followed on
+        // the same line aren't hinted at.
+        assert_eq!(
+            show(&hints),
+            vec![
+                "9:11 : Int",
+                "12:13 : Int",
+                "12:26 : Int",
+                "13:15 : Option<Int>",
+                "14:11  Int",
+                "15:39  Int",
+                "18:16 : Int",
+                "18:41 : Int",
+                "22:7 : Int",
+            ]
+        );
+    }
+
+    #[test]
+    fn hint_within_range() {
+        let compiler = project(&[("lib/foo", SOURCE)]);
+
+        let hints = inlay_hints(
+            &compiler.modules["foo"],
+            SOURCE,
+            Range {
+                start: Position::new(13, 0),
+                end: Position::new(15, 80),
+            },
+        );
+
+        assert_eq!(
+            show(&hints),
+            vec!["13:15 : Option<Int>", "14:11  Int", "15:39  Int"]
+        );
+    }
+
+    #[test]
+    fn hint_edited_text() {
+        let compiler = project(&[("lib/foo", SOURCE)]);
+
+        let edited = SOURCE
+            .replace("let count = 14", "let count = 14\n  let extra = 1")
+            .replace("n * 2", "n * 3");
+
+        let hints = inlay_hints(&compiler.modules["foo"], &edited, whole());
+
+        // Hints in between edits are left out, while those following them move along.
+        assert_eq!(
+            show(&hints[..3]),
+            vec!["9:11 : Int", "14:15 : Option<Int>", "15:11  Int"]
+        );
+        assert_eq!(show(&hints[hints.len() - 1..]), vec!["23:7 : Int"]);
+    }
+}
@@ -4,13 +4,23 @@ use error::Error;
 use lsp_server::Connection;
 use std::env;
 
+mod call_hierarchy;
 mod cast;
+mod completion;
 mod edits;
 pub mod error;
+mod inlay_hints;
+mod outline;
 mod quickfix;
+mod references;
+mod semantic_tokens;
 pub mod server;
+mod signature_help;
 pub mod utils;
 
+#[cfg(test)]
+mod tests;
+
 #[allow(clippy::result_large_err)]
 pub fn start() -> Result<(), Error> {
     tracing::info!("Aiken language server starting");
@@ -53,19 +63,39 @@ pub fn start() -> Result<(), Error> {
 
 fn capabilities() -> lsp_types::ServerCapabilities {
     lsp_types::ServerCapabilities {
-        // THIS IS STILL WEIRD, ONLY ENABLE IF DEVELOPING
-        // completion_provider: Some(lsp_types::CompletionOptions {
-        //     resolve_provider: None,
-        //     trigger_characters: Some(vec![".".into(), " ".into()]),
-        //     all_commit_characters: None,
-        //     work_done_progress_options: lsp_types::WorkDoneProgressOptions {
-        //         work_done_progress: None,
-        //     },
-        // }),
+        completion_provider: Some(lsp_types::CompletionOptions {
+            trigger_characters: Some(vec![".".into()]),
+            ..Default::default()
+        }),
         code_action_provider: Some(lsp_types::CodeActionProviderCapability::Simple(true)),
         document_formatting_provider: Some(lsp_types::OneOf::Left(true)),
         definition_provider: Some(lsp_types::OneOf::Left(true)),
+        document_symbol_provider: Some(lsp_types::OneOf::Left(true)),
+        folding_range_provider: Some(lsp_types::FoldingRangeProviderCapability::Simple(true)),
         hover_provider: Some(lsp_types::HoverProviderCapability::Simple(true)),
+        references_provider: Some(lsp_types::OneOf::Left(true)),
+        rename_provider: Some(lsp_types::OneOf::Right(lsp_types::RenameOptions {
+            prepare_provider: Some(true),
+            work_done_progress_options: Default::default(),
+        })),
+        workspace_symbol_provider: Some(lsp_types::OneOf::Left(true)),
+        semantic_tokens_provider: Some(
+            lsp_types::SemanticTokensServerCapabilities::SemanticTokensOptions(
+                lsp_types::SemanticTokensOptions {
+                    work_done_progress_options: Default::default(),
+                    legend: semantic_tokens::legend(),
+                    range: None,
+                    full: Some(lsp_types::SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                },
+            ),
+        ),
+        inlay_hint_provider: Some(lsp_types::OneOf::Left(true)),
+        signature_help_provider: Some(lsp_types::SignatureHelpOptions {
+            trigger_characters: Some(vec!["(".into(), ",".into(), "{".into()]),
+            retrigger_characters: None,
+            work_done_progress_options: Default::default(),
+        }),
+        call_hierarchy_provider: Some(lsp_types::CallHierarchyServerCapability::Simple(true)),
         text_document_sync: Some(lsp_types::TextDocumentSyncCapability::Options(
             lsp_types::TextDocumentSyncOptions {
                 open_close: None,
@@ -0,0 +1,885 @@
+//! Outlines of modules: the symbols they define, as listed for a document or searched for across
+//! the workspace, and the ranges editors can fold.
+//!
+//! Both are derived from the definitions of the last checked version of a module.
+
+use crate::{
+    server::lsp_project::LspProject,
+    utils::{path_to_uri, span_to_lsp_range},
+};
+use aiken_lang::{
+    ast::{Definition, Function, RecordConstructorArg, Span, TypedDefinition, TypedFunction},
+    expr::TypedExpr,
+    line_numbers::LineNumbers,
+    tipo::{Type, pretty::Printer},
+};
+use aiken_project::module::CheckedModule;
+use lsp_types::{
+    DocumentSymbol, FoldingRange, FoldingRangeKind, OneOf, SymbolKind, WorkspaceSymbol,
+};
+use std::{collections::HashSet, path::PathBuf, rc::Rc};
+
+/// The symbols defined in a module, nested as they're written: handlers within validators,
+/// constructors within types and fields within constructors.
+pub fn document_symbols(module: &CheckedModule) -> Vec<DocumentSymbol> {
+    let outline = Outline::new(&module.code);
+
+    let mut symbols = module
+        .ast
+        .definitions()
+        .filter_map(|definition| outline.definition(definition))
+        .collect::<Vec<_>>();
+
+    // Definitions are reordered while type-checking.
+    symbols.sort_by_key(|symbol| (symbol.range.start.line, symbol.range.start.character));
+
+    symbols
+}
+
+/// The symbols of the project's own modules whose name fuzzily matches the query, best matches
+/// first.
+pub fn workspace_symbols(
+    compiler: &LspProject,
+    package: &str,
+    query: &str,
+) -> Vec<WorkspaceSymbol> {
+    let mut modules = compiler
+        .modules
+        .values()
+        .filter(|module| module.package == package)
+        .collect::<Vec<_>>();
+
+    modules.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut found = Vec::new();
+
+    for module in modules {
+        let Some(source) = compiler.sources.get(&module.name) else {
+            continue;
+        };
+
+        let Ok(uri) = path_to_uri(PathBuf::from(&source.path)) else {
+            continue;
+        };
+
+        let mut symbols = document_symbols(module)
+            .into_iter()
+            .map(|symbol| (module.name.clone(), symbol))
+            .collect::<Vec<_>>();
+
+        while let Some((container, symbol)) = symbols.pop() {
+            for child in symbol.children.iter().flatten() {
+                symbols.push((symbol.name.clone(), child.clone()));
+            }
+
+            if let Some(score) = fuzzy_score(&symbol.name, query) {
+                found.push((
+                    score,
+                    WorkspaceSymbol {
+                        name: symbol.name,
+                        kind: symbol.kind,
+                        tags: None,
+                        container_name: Some(container),
+                        location: OneOf::Left(lsp_types::Location {
+                            uri: uri.clone(),
+                            range: symbol.selection_range,
+                        }),
+                        data: None,
+                    },
+                ));
+            }
+        }
+    }
+
+    found.sort_by(|(a_score, a), (b_score, b)| {
+        a_score
+            .cmp(b_score)
+            .then(a.name.len().cmp(&b.name.len()))
+            .then(a.name.cmp(&b.name))
+    });
+
+    found.into_iter().map(|(_, symbol)| symbol).collect()
+}
+
+/// The ranges of a module which can be folded: definitions, blocks, `when` expressions and
+/// their clauses, consecutive imports, and doc comments spanning several lines.
+pub fn folding_ranges(module: &CheckedModule) -> Vec<FoldingRange> {
+    let mut folder = Folder {
+        outline: Outline::new(&module.code),
+        ranges: Vec::new(),
+        seen: HashSet::new(),
+    };
+
+    let mut imports: Option<Span> = None;
+
+    for definition in module.ast.definitions() {
+        match (definition, imports) {
+            (Definition::Use(import), Some(span)) => {
+                imports = Some(span.union(import.location));
+            }
+            (Definition::Use(import), None) => {
+                imports = Some(import.location);
+            }
+            (_, Some(span)) => {
+                folder.fold(span, Some(FoldingRangeKind::Imports));
+                imports = None;
+            }
+            (_, None) => (),
+        }
+
+        folder.definition(definition);
+    }
+
+    if let Some(span) = imports {
+        folder.fold(span, Some(FoldingRangeKind::Imports));
+    }
+
+    for comments in [&module.extra.module_comments, &module.extra.doc_comments] {
+        folder.comments(comments);
+    }
+
+    folder.ranges
+}
+
+/// How well a name matches a query: all the characters of the query must appear in the name, in
+/// order and regardless of case. The lower the score, the closer together they are, and to the
+/// start of the name.
+fn fuzzy_score(name: &str, query: &str) -> Option<usize> {
+    let mut score = 0;
+    let mut gap = 0;
+    let mut chars = name.chars().flat_map(char::to_lowercase);
+
+    for wanted in query.chars().flat_map(char::to_lowercase) {
+        loop {
+            if chars.next()? == wanted {
+                break;
+            }
+
+            gap += 1;
+        }
+
+        score += gap;
+        gap = 0;
+    }
+
+    Some(score)
+}
+
+struct Outline<'a> {
+    code: &'a str,
+    line_numbers: LineNumbers,
+}
+
+impl<'a> Outline<'a> {
+    fn new(code: &'a str) -> Self {
+        Outline {
+            code,
+            line_numbers: LineNumbers::new(code),
+        }
+    }
+
+    fn definition(&self, definition: &TypedDefinition) -> Option<DocumentSymbol> {
+        match definition {
+            Definition::Fn(function) => Some(self.symbol(
+                &function.name,
+                Some(function_signature(function)),
+                SymbolKind::FUNCTION,
+                function_span(function),
+                function.location,
+                Vec::new(),
+            )),
+
+            Definition::Test(test) => Some(self.symbol(
+                &test.name,
+                Some("test".to_string()),
+                SymbolKind::FUNCTION,
+                function_span(test),
+                test.location,
+                Vec::new(),
+            )),
+
+            Definition::Benchmark(benchmark) => Some(self.symbol(
+                &benchmark.name,
+                Some("bench".to_string()),
+                SymbolKind::FUNCTION,
+                function_span(benchmark),
+                benchmark.location,
+                Vec::new(),
+            )),
+
+            Definition::Validator(validator) => {
+                let mut handlers = validator
+                    .handlers
+                    .iter()
+                    .map(|handler| {
+                        self.symbol(
+                            &handler.name,
+                            Some(function_signature(handler)),
+                            SymbolKind::METHOD,
+                            function_span(handler),
+                            handler.location,
+                            Vec::new(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                // A fallback which isn't written shares the location of the validator.
+                if validator.fallback.location != validator.location {
+                    let span = fallback_span(&validator.fallback);
+
+                    handlers.push(self.symbol(
+                        &validator.fallback.name,
+                        Some(function_signature(&validator.fallback)),
+                        SymbolKind::METHOD,
+                        span,
+                        span,
+                        Vec::new(),
+                    ));
+                }
+
+                Some(self.symbol(
+                    &validator.name,
+                    None,
+                    SymbolKind::CLASS,
+                    Span {
+                        start: validator.location.start,
+                        end: validator.end_position + 1,
+                    },
+                    validator.location,
+                    handlers,
+                ))
+            }
+
+            Definition::DataType(data_type) => {
+                let children = match &data_type.constructors[..] {
+                    [constructor] if constructor.sugar => self.fields(&constructor.arguments),
+                    constructors => constructors
+                        .iter()
+                        .map(|constructor| {
+                            self.symbol(
+                                &constructor.name,
+                                None,
+                                SymbolKind::ENUM_MEMBER,
+                                constructor.location,
+                                constructor.location,
+                                self.fields(&constructor.arguments),
+                            )
+                        })
+                        .collect(),
+                };
+
+                Some(self.symbol(
+                    &data_type.name,
+                    None,
+                    if data_type.constructors.len() > 1 {
+                        SymbolKind::ENUM
+                    } else {
+                        SymbolKind::STRUCT
+                    },
+                    data_type.location,
+                    data_type.location,
+                    children,
+                ))
+            }
+
+            Definition::TypeAlias(alias) => Some(
+                self.symbol(
+                    &alias.alias,
+                    // The aliased type prints as the alias itself.
+                    self.code
+                        .get(alias.annotation.location().start..alias.annotation.location().end)
+                        .map(|annotation| annotation.to_string()),
+                    SymbolKind::TYPE_PARAMETER,
+                    alias.location,
+                    alias.location,
+                    Vec::new(),
+                ),
+            ),
+
+            Definition::ModuleConstant(constant) => Some(self.symbol(
+                &constant.name,
+                Some(Printer::new().pretty_print(&constant.value.tipo(), 0)),
+                SymbolKind::CONSTANT,
+                constant.location,
+                constant.location,
+                Vec::new(),
+            )),
+
+            Definition::Use(..) => None,
+        }
+    }
+
+    fn fields(&self, arguments: &[RecordConstructorArg<Rc<Type>>]) -> Vec<DocumentSymbol> {
+        arguments
+            .iter()
+            .filter_map(|arg| {
+                let label = arg.label.as_ref()?;
+
+                Some(self.symbol(
+                    label,
+                    Some(Printer::new().pretty_print(&arg.tipo, 0)),
+                    SymbolKind::FIELD,
+                    arg.location,
+                    arg.location,
+                    Vec::new(),
+                ))
+            })
+            .collect()
+    }
+
+    /// A symbol spanning the given range, whose name is selected where it first appears in the
+    /// given span.
+    fn symbol(
+        &self,
+        name: &str,
+        detail: Option<String>,
+        kind: SymbolKind,
+        range: Span,
+        named_within: Span,
+        children: Vec<DocumentSymbol>,
+    ) -> DocumentSymbol {
+        let selection = self.find(named_within, name).unwrap_or(Span {
+            start: range.start,
+            end: range.start,
+        });
+
+        #[allow(deprecated)]
+        DocumentSymbol {
+            name: name.to_string(),
+            detail,
+            kind,
+            tags: None,
+            deprecated: None,
+            range: span_to_lsp_range(range, &self.line_numbers),
+            selection_range: span_to_lsp_range(selection, &self.line_numbers),
+            children: if children.is_empty() {
+                None
+            } else {
+                Some(children)
+            },
+        }
+    }
+
+    /// The span of the first occurrence of a name within the given span, as a whole word.
+    fn find(&self, within: Span, name: &str) -> Option<Span> {
+        let text = self.code.get(within.start..within.end)?;
+
+        let is_identifier_char = |c: char| c.is_alphanumeric() || c == '_';
+
+        text.match_indices(name).find_map(|(ix, _)| {
+            let before = text[..ix].chars().next_back();
+            let after = text[ix + name.len()..].chars().next();
+
+            if before.is_some_and(is_identifier_char) || after.is_some_and(is_identifier_char) {
+                return None;
+            }
+
+            Some(Span {
+                start: within.start + ix,
+                end: within.start + ix + name.len(),
+            })
+        })
+    }
+
+    /// The given span, widened to the braces around it if any.
+    fn braced(&self, span: Span) -> Span {
+        let before = self.code[..span.start].trim_end();
+        let after = self.code[span.end..].trim_start();
+
+        if before.ends_with('{') && after.starts_with('}') {
+            Span {
+                start: before.len() - 1,
+                end: self.code.len() - after.len() + 1,
+            }
+        } else {
+            span
+        }
+    }
+}
+
+struct Folder<'a> {
+    outline: Outline<'a>,
+    ranges: Vec<FoldingRange>,
+    /// Lines already folded together, as the span of a block often matches the one of the
+    /// definition or expression it belongs to.
+    seen: HashSet<(u32, u32)>,
+}
+
+impl Folder<'_> {
+    /// Fold the lines of a span, but the first one, and the last one when it only closes a
+    /// delimiter.
+    fn fold(&mut self, span: Span, kind: Option<FoldingRangeKind>) {
+        let range = span_to_lsp_range(span, &self.outline.line_numbers);
+
+        let last_line = self.outline.code[..span.end]
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .trim();
+
+        let end_line = if matches!(last_line, "}" | ")" | "]") {
+            range.end.line.saturating_sub(1)
+        } else {
+            range.end.line
+        };
+
+        if end_line <= range.start.line || !self.seen.insert((range.start.line, end_line)) {
+            return;
+        }
+
+        self.ranges.push(FoldingRange {
+            start_line: range.start.line,
+            start_character: None,
+            end_line,
+            end_character: None,
+            kind,
+            collapsed_text: None,
+        });
+    }
+
+    /// Fold runs of comments on consecutive lines.
+    fn comments(&mut self, comments: &[Span]) {
+        let mut run: Option<(Span, usize)> = None;
+
+        for comment in comments {
+            let Some(line) = self.outline.line_numbers.line_number(comment.start) else {
+                continue;
+            };
+
+            run = match run {
+                Some((span, last)) if line == last + 1 => Some((span.union(*comment), line)),
+                Some((span, _)) => {
+                    self.fold(span, Some(FoldingRangeKind::Comment));
+                    Some((*comment, line))
+                }
+                None => Some((*comment, line)),
+            };
+        }
+
+        if let Some((span, _)) = run {
+            self.fold(span, Some(FoldingRangeKind::Comment));
+        }
+    }
+
+    fn definition(&mut self, definition: &TypedDefinition) {
+        match definition {
+            Definition::Fn(function) => self.function(function),
+
+            Definition::Test(test) | Definition::Benchmark(test) => self.function(test),
+
+            Definition::Validator(validator) => {
+                self.fold(
+                    Span {
+                        start: validator.location.start,
+                        end: validator.end_position + 1,
+                    },
+                    None,
+                );
+
+                for handler in validator.handlers.iter() {
+                    self.function(handler);
+                }
+
+                if validator.fallback.location != validator.location {
+                    self.fold(fallback_span(&validator.fallback), None);
+                    self.expr(&validator.fallback.body);
+                }
+            }
+
+            Definition::DataType(data_type) => {
+                self.fold(data_type.location, None);
+
+                for constructor in data_type.constructors.iter() {
+                    self.fold(constructor.location, None);
+                }
+            }
+
+            Definition::TypeAlias(alias) => self.fold(alias.location, None),
+
+            Definition::ModuleConstant(constant) => {
+                self.fold(constant.location, None);
+                self.expr(&constant.value);
+            }
+
+            Definition::Use(..) => (),
+        }
+    }
+
+    fn function<Arg>(&mut self, function: &Function<Rc<Type>, TypedExpr, Arg>) {
+        self.fold(function_span(function), None);
+        self.expr(&function.body);
+    }
+
+    fn expr(&mut self, expr: &TypedExpr) {
+        match expr {
+            TypedExpr::UInt { .. }
+            | TypedExpr::String { .. }
+            | TypedExpr::ByteArray { .. }
+            | TypedExpr::CurvePoint { .. }
+            | TypedExpr::Var { .. }
+            | TypedExpr::ModuleSelect { .. }
+            | TypedExpr::ErrorTerm { .. } => (),
+
+            TypedExpr::Sequence {
+                location,
+                expressions,
+            }
+            | TypedExpr::Pipeline {
+                location,
+                expressions,
+            } => {
+                self.fold(self.outline.braced(*location), None);
+
+                for expression in expressions {
+                    self.expr(expression);
+                }
+            }
+
+            TypedExpr::Fn { location, body, .. } => {
+                self.fold(*location, None);
+                self.expr(body);
+            }
+
+            TypedExpr::List {
+                location,
+                elements,
+                tail,
+                ..
+            } => {
+                self.fold(*location, None);
+
+                for element in elements {
+                    self.expr(element);
+                }
+
+                if let Some(tail) = tail {
+                    self.expr(tail);
+                }
+            }
+
+            TypedExpr::Tuple {
+                location, elems, ..
+            } => {
+                self.fold(*location, None);
+
+                for elem in elems {
+                    self.expr(elem);
+                }
+            }
+
+            TypedExpr::Call {
+                location,
+                fun,
+                args,
+                ..
+            } => {
+                self.fold(*location, None);
+                self.expr(fun);
+
+                for arg in args {
+                    self.expr(&arg.value);
+                }
+            }
+
+            TypedExpr::RecordUpdate {
+                location,
+                spread,
+                args,
+                ..
+            } => {
+                self.fold(*location, None);
+                self.expr(spread);
+
+                for arg in args {
+                    self.expr(&arg.value);
+                }
+            }
+
+            TypedExpr::When {
+                location,
+                subject,
+                clauses,
+                ..
+            } => {
+                self.fold(*location, None);
+                self.expr(subject);
+
+                for clause in clauses {
+                    self.fold(clause.location(), None);
+                    self.expr(&clause.then);
+                }
+            }
+
+            TypedExpr::If {
+                branches,
+                final_else,
+                ..
+            } => {
+                for branch in branches {
+                    self.expr(&branch.condition);
+                    self.fold(self.outline.braced(branch.body.location()), None);
+                    self.expr(&branch.body);
+                }
+
+                self.fold(self.outline.braced(final_else.location()), None);
+                self.expr(final_else);
+            }
+
+            TypedExpr::BinOp { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+
+            TypedExpr::Pair { fst, snd, .. } => {
+                self.expr(fst);
+                self.expr(snd);
+            }
+
+            TypedExpr::Trace { then, text, .. } => {
+                self.expr(text);
+                self.expr(then);
+            }
+
+            TypedExpr::Assignment { value, .. } => self.expr(value),
+            TypedExpr::RecordAccess { record, .. } => self.expr(record),
+            TypedExpr::TupleIndex { tuple, .. } => self.expr(tuple),
+            TypedExpr::UnOp { value, .. } => self.expr(value),
+        }
+    }
+}
+
+/// The span of a function, from its signature to its closing brace.
+fn function_span<Expr, Arg>(function: &Function<Rc<Type>, Expr, Arg>) -> Span {
+    Span {
+        start: function.location.start,
+        end: function.end_position + 1,
+    }
+}
+
+/// The span of a validator's fallback, whose location starts past the `else` keyword.
+fn fallback_span(fallback: &TypedFunction) -> Span {
+    Span {
+        start: fallback.location.start.saturating_sub(fallback.name.len()),
+        end: fallback.end_position + 1,
+    }
+}
+
+fn function_signature(function: &TypedFunction) -> String {
+    let tipo = Type::function(
+        function
+            .arguments
+            .iter()
+            .map(|arg| arg.tipo.clone())
+            .collect(),
+        function.return_type.clone(),
+    );
+
+    Printer::new().pretty_print(&tipo, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{document_symbols, folding_ranges, workspace_symbols};
+    use crate::{
+        server::lsp_project::LspProject,
+        tests::{PACKAGE, project},
+    };
+    use indoc::indoc;
+    use lsp_types::{DocumentSymbol, FoldingRangeKind};
+
+    const ESCROW: &str = indoc! { r#"
+        //// Escrow of funds, until a deadline
+        //// after which they may be refunded.
+
+        use fixture/keys.{Key}
+        use fixture/time.{Deadline}
+
+        /// What the funds are locked with,
+        /// by their owner.
+        pub type Datum {
+          owner: Key,
+          deadline: Deadline,
+        }
+
+        pub type Action {
+          Claim
+          Refund { amount: Int }
+        }
+
+        pub fn is_late(datum: Datum, now: Int) -> Bool {
+          when now > datum.deadline is {
+            True -> {
+              let late = True
+              late
+            }
+            False -> False
+          }
+        }
+
+        validator escrow {
+          spend(datum: Option<Datum>, _redeemer: Action, _own_ref: Data, _self: Data) {
+            expect Some(datum) = datum
+            is_late(datum, 42)
+          }
+
+          else(_) {
+            fail
+          }
+        }
+
+        test is_late_after_deadline() {
+          is_late(Datum { owner: "", deadline: 0 }, 1)
+        }
+    "#};
+
+    const KEYS: &str = indoc! { r#"
+        pub type Key =
+          ByteArray
+    "#};
+
+    const TIME: &str = indoc! { r#"
+        pub type Deadline =
+          Int
+    "#};
+
+    fn escrow() -> LspProject {
+        project(&[
+            ("validators/escrow", ESCROW),
+            ("lib/fixture/keys", KEYS),
+            ("lib/fixture/time", TIME),
+        ])
+    }
+
+    /// Symbols as '<name> <kind> <line>:<column> <detail>', children indented below their parent.
+    fn show(symbols: &[DocumentSymbol], depth: usize, lines: &mut Vec<String>) {
+        for symbol in symbols {
+            lines.push(format!(
+                "{}{} {:?} {}:{}{}",
+                "  ".repeat(depth),
+                symbol.name,
+                symbol.kind,
+                symbol.selection_range.start.line,
+                symbol.selection_range.start.character,
+                symbol
+                    .detail
+                    .as_ref()
+                    .map(|detail| format!(" {detail}"))
+                    .unwrap_or_default(),
+            ));
+
+            show(
+                symbol.children.as_deref().unwrap_or_default(),
+                depth + 1,
+                lines,
+            );
+        }
+    }
+
+    #[test]
+    fn outline_module() {
+        let compiler = escrow();
+
+        let symbols = document_symbols(&compiler.modules["escrow"]);
+
+        let mut lines = Vec::new();
+
+        show(&symbols, 0, &mut lines);
+
+        assert_eq!(
+            lines,
+            vec![
+                "Datum Struct 8:9",
+                "  owner Field 9:2 Key",
+                "  deadline Field 10:2 Deadline",
+                "Action Enum 13:9",
+                "  Claim EnumMember 14:2",
+                "  Refund EnumMember 15:2",
+                "    amount Field 15:11 Int",
+                "is_late Function 18:7 fn(Datum, Int) -> Bool",
+                "escrow Class 28:0",
+                "  spend Method 29:2 fn(Option<Datum>, Action, Data, Data) -> Bool",
+                "  else Method 34:2 fn(Data) -> Bool",
+                "is_late_after_deadline Function 39:5 test",
+            ]
+        );
+
+        // Symbols span their whole definition, doc comments aside.
+        let spans = symbols
+            .iter()
+            .map(|symbol| (symbol.range.start.line, symbol.range.end.line))
+            .collect::<Vec<_>>();
+
+        assert_eq!(spans, vec![(8, 11), (13, 16), (18, 26), (28, 37), (39, 41)]);
+    }
+
+    #[test]
+    fn search_workspace() {
+        let compiler = escrow();
+
+        let search = |query: &str| {
+            workspace_symbols(&compiler, PACKAGE, query)
+                .into_iter()
+                .map(|symbol| format!("{} in {}", symbol.name, symbol.container_name.unwrap()))
+                .collect::<Vec<_>>()
+        };
+
+        // Closer matches come first, regardless of case.
+        assert_eq!(
+            search("dl"),
+            vec![
+                "Deadline in fixture/time",
+                "deadline in Datum",
+                "is_late_after_deadline in escrow",
+            ]
+        );
+
+        assert_eq!(
+            search("late"),
+            vec!["is_late in escrow", "is_late_after_deadline in escrow"]
+        );
+
+        assert_eq!(search("DATUM"), vec!["Datum in escrow"]);
+
+        assert_eq!(search("key"), vec!["Key in fixture/keys"]);
+
+        assert!(search("zz").is_empty());
+
+        // Ties are broken by length, then name.
+        assert_eq!(
+            search("a")[..3],
+            ["Action in escrow", "amount in Refund", "Datum in escrow"]
+        );
+    }
+
+    #[test]
+    fn fold_module() {
+        let compiler = escrow();
+
+        let ranges = folding_ranges(&compiler.modules["escrow"])
+            .into_iter()
+            .map(|range| (range.start_line, range.end_line, range.kind))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            ranges,
+            vec![
+                (3, 4, Some(FoldingRangeKind::Imports)),
+                // Types, and constructors spanning several lines.
+                (8, 10, None),
+                (13, 15, None),
+                // The function, its 'when' and the block of its first clause.
+                (18, 25, None),
+                (19, 24, None),
+                (20, 22, None),
+                // The validator and its handlers.
+                (28, 36, None),
+                (29, 31, None),
+                (34, 35, None),
+                (39, 40, None),
+                // The module's doc comments, then the type's.
+                (0, 1, Some(FoldingRangeKind::Comment)),
+                (6, 7, Some(FoldingRangeKind::Comment)),
+            ]
+        );
+    }
+}
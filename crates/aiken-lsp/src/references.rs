@@ -0,0 +1,685 @@
+//! Finding and renaming symbols, across the project and its dependencies.
+//!
+//! Both work from the occurrences of symbols collected in every checked module. Symbols defined
+//! or imported by the same name, such as a record type and its constructor, are handled together.
+
+use crate::{
+    server::lsp_project::LspProject,
+    utils::{path_to_uri, span_to_lsp_range},
+};
+use aiken_lang::{
+    ast::Span,
+    builtins::PRELUDE,
+    parser::{lexer, token::Token},
+    symbols::{Occurrence, Symbol},
+    tipo::{ValueConstructorVariant, scope::Scope},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::PathBuf,
+};
+
+/// The locations of a symbol, optionally including its definition.
+pub fn references(
+    compiler: &LspProject,
+    module: &str,
+    byte_index: usize,
+    include_declaration: bool,
+) -> Vec<lsp_types::Location> {
+    let Some(occurrence) = occurrence_at(compiler, module, byte_index) else {
+        return Vec::new();
+    };
+
+    occurrences_of(compiler, &linked(compiler, &occurrence.symbol))
+        .into_iter()
+        .filter(|(_, occurrence)| include_declaration || !occurrence.is_definition)
+        .map(|(module, occurrence)| (module, occurrence.location))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|(module, location)| lsp_location(compiler, module, location))
+        .collect()
+}
+
+/// The name to rename, if there's one at the given position and it can be renamed.
+pub fn prepare_rename(
+    compiler: &LspProject,
+    package: &str,
+    module: &str,
+    byte_index: usize,
+) -> Result<Option<(Span, String)>, String> {
+    let Some(occurrence) = occurrence_at(compiler, module, byte_index) else {
+        return Ok(None);
+    };
+
+    let symbols = linked(compiler, &occurrence.symbol);
+
+    let name = definition_name(compiler, package, &occurrence.symbol, &symbols)?;
+
+    Ok(Some((occurrence.location, name)))
+}
+
+/// The edits renaming the symbol at the given position, wherever it appears. Renaming is refused
+/// when any module to edit has unsaved changes, since its occurrences are likely out of date.
+pub fn rename(
+    compiler: &LspProject,
+    package: &str,
+    module: &str,
+    byte_index: usize,
+    new_name: &str,
+    edited: &HashMap<String, String>,
+) -> Result<Option<lsp_types::WorkspaceEdit>, String> {
+    let Some(occurrence) = occurrence_at(compiler, module, byte_index) else {
+        return Ok(None);
+    };
+
+    let symbols = linked(compiler, &occurrence.symbol);
+
+    let old_name = definition_name(compiler, package, &occurrence.symbol, &symbols)?;
+
+    check_name(&old_name, new_name)?;
+
+    let mut edits = BTreeMap::new();
+
+    for (module, occurrence) in occurrences_of(compiler, &symbols) {
+        let (Some(checked), Some(source)) =
+            (compiler.modules.get(module), compiler.sources.get(module))
+        else {
+            continue;
+        };
+
+        if edited
+            .get(&source.path)
+            .is_some_and(|text| *text != checked.code)
+        {
+            return Err(format!(
+                "{} has unsaved changes: save it before renaming.",
+                source.path
+            ));
+        }
+
+        let Some(text) = checked
+            .code
+            .get(occurrence.location.start..occurrence.location.end)
+        else {
+            continue;
+        };
+
+        let new_text = if occurrence.is_shorthand {
+            // Expand the shorthand, keeping whichever of the field or variable isn't renamed.
+            match occurrence.symbol {
+                Symbol::Field { .. } => format!("{new_name}: {text}"),
+                _ => format!("{text}: {new_name}"),
+            }
+        } else if text == old_name {
+            new_name.to_string()
+        } else {
+            // Referred to by an alias, which remains valid.
+            continue;
+        };
+
+        edits.insert((module, occurrence.location), new_text);
+    }
+
+    if new_name != old_name {
+        check_conflicts(
+            compiler,
+            &symbols,
+            edits.keys().map(|(module, _)| *module),
+            new_name,
+        )?;
+    }
+
+    let mut changes: HashMap<lsp_types::Url, Vec<lsp_types::TextEdit>> = HashMap::new();
+
+    for ((module, location), new_text) in edits {
+        let Some(source) = compiler.sources.get(module) else {
+            continue;
+        };
+
+        let Ok(uri) = path_to_uri(PathBuf::from(&source.path)) else {
+            continue;
+        };
+
+        changes.entry(uri).or_default().push(lsp_types::TextEdit {
+            range: span_to_lsp_range(location, &source.line_numbers),
+            new_text,
+        });
+    }
+
+    Ok(Some(lsp_types::WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    }))
+}
+
+/// The occurrence at the given position. A shorthand stands for both a field and a variable, of
+/// which the variable is preferred; likewise, a name imported unqualified is preferably a type
+/// over a constructor.
+fn occurrence_at<'a>(
+    compiler: &'a LspProject,
+    module: &str,
+    byte_index: usize,
+) -> Option<&'a Occurrence> {
+    compiler
+        .occurrences
+        .get(module)?
+        .iter()
+        .filter(|occurrence| {
+            occurrence.location.start <= byte_index && byte_index <= occurrence.location.end
+        })
+        .min_by_key(|occurrence| match occurrence.symbol {
+            Symbol::Variable { .. } => 0,
+            Symbol::Type { .. } | Symbol::Module { .. } => 1,
+            Symbol::Value { .. } | Symbol::Field { .. } => 2,
+        })
+}
+
+/// The given symbol, along with the ones defined or imported by the same name, transitively.
+fn linked(compiler: &LspProject, symbol: &Symbol) -> BTreeSet<Symbol> {
+    let mut at_span: HashMap<(&str, Span), Vec<&Occurrence>> = HashMap::new();
+
+    for (module, occurrences) in compiler.occurrences.iter() {
+        for occurrence in occurrences.iter().filter(|o| !o.is_shorthand) {
+            at_span
+                .entry((module.as_str(), occurrence.location))
+                .or_default()
+                .push(occurrence);
+        }
+    }
+
+    let groups = at_span
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect::<Vec<_>>();
+
+    let mut symbols = BTreeSet::from([symbol.clone()]);
+
+    loop {
+        let before = symbols.len();
+
+        for group in groups.iter() {
+            if group.iter().any(|o| symbols.contains(&o.symbol)) {
+                symbols.extend(group.iter().map(|o| o.symbol.clone()));
+            }
+        }
+
+        if symbols.len() == before {
+            return symbols;
+        }
+    }
+}
+
+fn occurrences_of<'a>(
+    compiler: &'a LspProject,
+    symbols: &BTreeSet<Symbol>,
+) -> Vec<(&'a str, &'a Occurrence)> {
+    compiler
+        .occurrences
+        .iter()
+        .flat_map(|(module, occurrences)| {
+            occurrences
+                .iter()
+                .filter(|occurrence| symbols.contains(&occurrence.symbol))
+                .map(move |occurrence| (module.as_str(), occurrence))
+        })
+        .collect()
+}
+
+/// The name of a symbol as written where it's defined, provided it's defined in the project.
+fn definition_name(
+    compiler: &LspProject,
+    package: &str,
+    symbol: &Symbol,
+    symbols: &BTreeSet<Symbol>,
+) -> Result<String, String> {
+    if let Symbol::Module { .. } = symbol {
+        return Err("Modules can't be renamed.".to_string());
+    }
+
+    let definitions = occurrences_of(compiler, symbols)
+        .into_iter()
+        .filter(|(_, occurrence)| occurrence.is_definition)
+        .collect::<Vec<_>>();
+
+    if definitions.is_empty() {
+        return Err("This can't be renamed: its definition is nowhere to be found.".to_string());
+    }
+
+    for (module, _) in definitions.iter() {
+        if let Some(checked) = compiler.modules.get(*module) {
+            if checked.package != package {
+                return Err(format!(
+                    "This is defined in {}, a dependency, and can't be renamed.",
+                    checked.package
+                ));
+            }
+        }
+    }
+
+    definitions
+        .iter()
+        .find(|(_, occurrence)| occurrence.symbol == *symbol)
+        .or(definitions.first())
+        .and_then(|(module, occurrence)| {
+            compiler
+                .modules
+                .get(*module)?
+                .code
+                .get(occurrence.location.start..occurrence.location.end)
+        })
+        .map(|name| name.to_string())
+        .ok_or_else(|| "This can't be renamed.".to_string())
+}
+
+/// Check that no module to edit already refers to another value, or type, by the new name; nor
+/// does the prelude define one. Variables mustn't be renamed after another variable in scope
+/// wherever they're written either.
+fn check_conflicts<'a>(
+    compiler: &LspProject,
+    symbols: &BTreeSet<Symbol>,
+    modules: impl Iterator<Item = &'a str>,
+    new_name: &str,
+) -> Result<(), String> {
+    let same_namespace = |other: &Symbol| {
+        symbols.iter().any(|symbol| {
+            matches!(
+                (symbol, other),
+                (Symbol::Value { .. }, Symbol::Value { .. })
+                    | (Symbol::Type { .. }, Symbol::Type { .. })
+            )
+        })
+    };
+
+    if let Some(prelude) = compiler.module_types.get(PRELUDE) {
+        let defined = symbols.iter().any(|symbol| match symbol {
+            Symbol::Value { .. } => prelude.values.contains_key(new_name),
+            Symbol::Type { .. } => prelude.types.contains_key(new_name),
+            Symbol::Field { .. } | Symbol::Variable { .. } | Symbol::Module { .. } => false,
+        });
+
+        if defined {
+            return Err(format!("'{new_name}' is already defined in the prelude."));
+        }
+    }
+
+    for module in modules.collect::<BTreeSet<_>>() {
+        let (Some(checked), Some(occurrences)) = (
+            compiler.modules.get(module),
+            compiler.occurrences.get(module),
+        ) else {
+            continue;
+        };
+
+        let conflicts = occurrences.iter().any(|occurrence| {
+            !symbols.contains(&occurrence.symbol)
+                && same_namespace(&occurrence.symbol)
+                && checked
+                    .code
+                    .get(occurrence.location.start..occurrence.location.end)
+                    == Some(new_name)
+        });
+
+        if conflicts {
+            return Err(format!(
+                "'{new_name}' already refers to something else in module '{module}'."
+            ));
+        }
+
+        // Variables may also be shadowed by, or shadow, another variable in scope wherever
+        // they're written, even when nothing refers to it there.
+        let definitions = symbols
+            .iter()
+            .filter_map(|symbol| match symbol {
+                Symbol::Variable { location, .. } => Some(*location),
+                _ => None,
+            })
+            .collect::<BTreeSet<_>>();
+
+        if definitions.is_empty() {
+            continue;
+        }
+
+        let shadowed = occurrences
+            .iter()
+            .filter(|occurrence| symbols.contains(&occurrence.symbol))
+            .any(|occurrence| {
+                let scope = Scope::new(
+                    &checked.ast,
+                    occurrence.location.start,
+                    &compiler.module_types,
+                );
+
+                matches!(
+                    scope.values.get(new_name).map(|value| &value.variant),
+                    Some(ValueConstructorVariant::LocalVariable { location })
+                        if !definitions.contains(location)
+                )
+            });
+
+        if shadowed {
+            return Err(format!(
+                "'{new_name}' is already the name of a variable in scope in module '{module}'."
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that a new name is a valid identifier, of the same kind as the name it replaces.
+fn check_name(old_name: &str, new_name: &str) -> Result<(), String> {
+    let tokens = lexer::run(new_name)
+        .map(|lexed| {
+            lexed
+                .tokens
+                .into_iter()
+                .map(|(token, _)| token)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let expects_upper = old_name.starts_with(char::is_uppercase);
+
+    match &tokens[..] {
+        [Token::UpName { name }] if expects_upper && name == new_name => Ok(()),
+        [Token::Name { name }] if !expects_upper && name == new_name => Ok(()),
+        _ if expects_upper => Err(format!(
+            "'{new_name}' isn't a valid name here: it must be a single word starting with an uppercase letter."
+        )),
+        _ => Err(format!(
+            "'{new_name}' isn't a valid name here: it must be a single word starting with a lowercase letter, and not a keyword."
+        )),
+    }
+}
+
+fn lsp_location(
+    compiler: &LspProject,
+    module: &str,
+    location: Span,
+) -> Option<lsp_types::Location> {
+    let source = compiler.sources.get(module)?;
+
+    Some(lsp_types::Location {
+        uri: path_to_uri(PathBuf::from(&source.path)).ok()?,
+        range: span_to_lsp_range(location, &source.line_numbers),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{references, rename};
+    use crate::{
+        server::lsp_project::LspProject,
+        tests::{PACKAGE, index_of, project},
+    };
+    use indoc::indoc;
+    use std::collections::HashMap;
+
+    const SOURCE: &str = indoc! { r#"
+        pub fn total(amount: Int, fee: Int) -> Int {
+          let gross = amount + fee
+          let net = gross - fee
+          net
+        }
+
+        pub fn double(n: Int) -> Int {
+          n * 2
+        }
+    "#};
+
+    const MONEY: &str = indoc! { r#"
+        pub type Money {
+          Money { amount: Int, currency: ByteArray }
+        }
+
+        pub fn add(a: Money, b: Money) -> Money {
+          Money { amount: a.amount + b.amount, currency: a.currency }
+        }
+    "#};
+
+    const WALLET: &str = indoc! { r#"
+        use fixture/money.{Money}
+
+        pub fn fund(amount: Int) -> Money {
+          Money { amount, currency: "ada" }
+        }
+
+        pub fn balance(m: Money) -> Int {
+          let Money { amount, .. } = m
+          amount
+        }
+
+        pub fn twice(m: Money) -> Money {
+          money.add(m, m)
+        }
+    "#};
+
+    fn wallet() -> LspProject {
+        project(&[("lib/fixture/money", MONEY), ("lib/fixture/wallet", WALLET)])
+    }
+
+    /// Locations as '<file> <line>:<column>'.
+    fn show(locations: &[lsp_types::Location]) -> Vec<String> {
+        locations
+            .iter()
+            .map(|location| {
+                format!(
+                    "{} {}:{}",
+                    location.uri.path().rsplit('/').next().unwrap(),
+                    location.range.start.line,
+                    location.range.start.character
+                )
+            })
+            .collect()
+    }
+
+    /// Edits as '<file> <line>:<column> <new text>', in order.
+    fn edits(edit: lsp_types::WorkspaceEdit) -> Vec<String> {
+        let mut edits = edit
+            .changes
+            .unwrap()
+            .into_iter()
+            .flat_map(|(uri, edits)| {
+                let file = uri.path().rsplit('/').next().unwrap().to_string();
+                edits.into_iter().map(move |edit| (file.clone(), edit))
+            })
+            .collect::<Vec<_>>();
+
+        edits.sort_by_key(|(file, edit)| (file.clone(), edit.range.start));
+
+        edits
+            .into_iter()
+            .map(|(file, edit)| {
+                format!(
+                    "{file} {}:{} {}",
+                    edit.range.start.line, edit.range.start.character, edit.new_text
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn references_across_modules() {
+        let compiler = wallet();
+
+        let index = index_of(&compiler, "fixture/money", "add", 0);
+
+        assert_eq!(
+            show(&references(&compiler, "fixture/money", index, true)),
+            vec!["money.ak 4:7", "wallet.ak 12:8"]
+        );
+
+        assert_eq!(
+            show(&references(&compiler, "fixture/money", index, false)),
+            vec!["wallet.ak 12:8"]
+        );
+
+        // Likewise from where it's used.
+        let index = index_of(&compiler, "fixture/wallet", "add", 0);
+
+        assert_eq!(
+            show(&references(&compiler, "fixture/wallet", index, true)),
+            vec!["money.ak 4:7", "wallet.ak 12:8"]
+        );
+    }
+
+    #[test]
+    fn rename_field() {
+        let compiler = wallet();
+
+        let edit = rename(
+            &compiler,
+            PACKAGE,
+            "fixture/money",
+            index_of(&compiler, "fixture/money", "amount", 0),
+            "value",
+            &HashMap::new(),
+        )
+        .unwrap()
+        .unwrap();
+
+        // Shorthands are expanded, keeping the variable.
+        assert_eq!(
+            edits(edit),
+            vec![
+                "money.ak 1:10 value",
+                "money.ak 5:10 value",
+                "money.ak 5:20 value",
+                "money.ak 5:31 value",
+                "wallet.ak 3:10 value: amount",
+                "wallet.ak 7:14 value: amount",
+            ]
+        );
+    }
+
+    #[test]
+    fn rename_type_and_constructor() {
+        let compiler = wallet();
+
+        let expected = vec![
+            "money.ak 0:9 Cash",
+            "money.ak 1:2 Cash",
+            "money.ak 4:14 Cash",
+            "money.ak 4:24 Cash",
+            "money.ak 4:34 Cash",
+            "money.ak 5:2 Cash",
+            "wallet.ak 0:19 Cash",
+            "wallet.ak 2:28 Cash",
+            "wallet.ak 3:2 Cash",
+            "wallet.ak 6:18 Cash",
+            "wallet.ak 7:6 Cash",
+            "wallet.ak 11:16 Cash",
+            "wallet.ak 11:26 Cash",
+        ];
+
+        // Whether from the type or its constructor, both are renamed since they're imported
+        // together.
+        for (module, text, n) in [
+            ("fixture/money", "Money", 0),
+            ("fixture/money", "Money", 1),
+            ("fixture/wallet", "Money", 2),
+        ] {
+            let edit = rename(
+                &compiler,
+                PACKAGE,
+                module,
+                index_of(&compiler, module, text, n),
+                "Cash",
+                &HashMap::new(),
+            )
+            .unwrap()
+            .unwrap();
+
+            assert_eq!(edits(edit), expected, "from occurrence #{n} in '{module}'");
+        }
+    }
+
+    #[test]
+    fn rename_dependency() {
+        let compiler = wallet();
+
+        // From another package, the fixture is a dependency.
+        let result = rename(
+            &compiler,
+            "aiken-lang/other",
+            "fixture/wallet",
+            index_of(&compiler, "fixture/wallet", "add", 0),
+            "plus",
+            &HashMap::new(),
+        );
+
+        assert_eq!(
+            result,
+            Err(
+                "This is defined in aiken-lang/fixture, a dependency, and can't be renamed."
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn rename_variable() {
+        let compiler = project(&[("lib/foo", SOURCE)]);
+
+        let edit = rename(
+            &compiler,
+            PACKAGE,
+            "foo",
+            index_of(&compiler, "foo", "net", 0),
+            "result",
+            &HashMap::new(),
+        )
+        .unwrap()
+        .unwrap();
+
+        let edits = edit
+            .changes
+            .unwrap()
+            .into_values()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        assert_eq!(edits.len(), 2);
+        assert!(edits.iter().all(|edit| edit.new_text == "result"));
+    }
+
+    #[test]
+    fn rename_variable_after_another_in_scope() {
+        let compiler = project(&[("lib/foo", SOURCE)]);
+
+        // Nothing refers to 'gross' where 'net' is defined, yet it's in scope of its uses.
+        let result = rename(
+            &compiler,
+            PACKAGE,
+            "foo",
+            index_of(&compiler, "foo", "net", 0),
+            "gross",
+            &HashMap::new(),
+        );
+
+        assert!(result.is_err(), "{result:?}");
+
+        let result = rename(
+            &compiler,
+            PACKAGE,
+            "foo",
+            index_of(&compiler, "foo", "gross", 0),
+            "amount",
+            &HashMap::new(),
+        );
+
+        assert!(result.is_err(), "{result:?}");
+
+        // Variables of other functions aren't in scope.
+        let result = rename(
+            &compiler,
+            PACKAGE,
+            "foo",
+            index_of(&compiler, "foo", "n:", 0),
+            "fee",
+            &HashMap::new(),
+        );
+
+        assert!(result.is_ok(), "{result:?}");
+    }
+}
@@ -0,0 +1,457 @@
+//! Semantic tokens, telling apart what names stand for where a grammar can only guess from how
+//! they're written: constructors, types, opaque types, module aliases and so on.
+//!
+//! Tokens come from the occurrences of symbols in the last checked version of a module, each
+//! classified by looking up its definition. Values and types of the prelude and builtins aren't
+//! among them, and are left to the grammar.
+
+use crate::{server::lsp_project::LspProject, utils::TextDiff};
+use aiken_lang::{
+    ast::Definition,
+    line_numbers::LineNumbers,
+    symbols::{Occurrence, Symbol},
+};
+use aiken_project::module::CheckedModule;
+use itertools::Itertools;
+use lsp_types::{
+    SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensEdit,
+    SemanticTokensLegend,
+};
+use std::collections::HashMap;
+
+const TOKEN_TYPES: [SemanticTokenType; 6] = [
+    SemanticTokenType::NAMESPACE,
+    SemanticTokenType::TYPE,
+    SemanticTokenType::ENUM_MEMBER,
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::PROPERTY,
+];
+
+const TOKEN_MODIFIERS: [SemanticTokenModifier; 3] = [
+    SemanticTokenModifier::DECLARATION,
+    SemanticTokenModifier::READONLY,
+    SemanticTokenModifier::ABSTRACT,
+];
+
+const DECLARATION: u32 = 1;
+const READONLY: u32 = 1 << 1;
+const ABSTRACT: u32 = 1 << 2;
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: TOKEN_MODIFIERS.to_vec(),
+    }
+}
+
+/// The tokens of a module, positioned in its current text which may differ from the one last
+/// checked. Tokens within the edits since are left out.
+pub fn semantic_tokens(
+    compiler: &LspProject,
+    module: &CheckedModule,
+    text: &str,
+) -> Vec<SemanticToken> {
+    let Some(occurrences) = compiler.occurrences.get(&module.name) else {
+        return Vec::new();
+    };
+
+    let diff = TextDiff::new(&module.code, text);
+
+    let line_numbers = LineNumbers::new(text);
+
+    let mut definitions = HashMap::new();
+
+    let mut tokens = Vec::new();
+
+    let mut previous = (0, 0);
+
+    for (location, occurrences) in &occurrences
+        .iter()
+        .group_by(|occurrence| occurrence.location)
+    {
+        // Names standing for several symbols are highlighted as the one they're looked up as.
+        let Some(occurrence) = occurrences.min_by_key(|occurrence| match occurrence.symbol {
+            Symbol::Variable { .. } => 0,
+            Symbol::Type { .. } | Symbol::Module { .. } => 1,
+            Symbol::Value { .. } | Symbol::Field { .. } => 2,
+        }) else {
+            continue;
+        };
+
+        let Some(location) = diff.edited_span(location) else {
+            continue;
+        };
+
+        let Some(start) = line_numbers.line_and_column_number(location.start) else {
+            continue;
+        };
+
+        let (token_type, modifiers) = classify(compiler, &mut definitions, occurrence);
+
+        let line = start.line as u32 - 1;
+        let column = start.column as u32 - 1;
+
+        tokens.push(SemanticToken {
+            delta_line: line - previous.0,
+            delta_start: if line == previous.0 {
+                column - previous.1
+            } else {
+                column
+            },
+            length: (location.end - location.start) as u32,
+            token_type: TOKEN_TYPES
+                .iter()
+                .position(|known| *known == token_type)
+                .unwrap_or_default() as u32,
+            token_modifiers_bitset: if occurrence.is_definition {
+                modifiers | DECLARATION
+            } else {
+                modifiers
+            },
+        });
+
+        previous = (line, column);
+    }
+
+    tokens
+}
+
+/// The edits turning previous tokens into new ones: whatever lies between what they have in
+/// common at the start and at the end.
+pub fn delta(previous: &[SemanticToken], tokens: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
+    let prefix = previous
+        .iter()
+        .zip(tokens)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let suffix = previous
+        .iter()
+        .rev()
+        .zip(tokens.iter().rev())
+        .take(previous.len().min(tokens.len()) - prefix)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    if prefix == previous.len() && prefix == tokens.len() {
+        return Vec::new();
+    }
+
+    // Edits count integers, of which each token takes five.
+    vec![SemanticTokensEdit {
+        start: 5 * prefix as u32,
+        delete_count: 5 * (previous.len() - prefix - suffix) as u32,
+        data: Some(tokens[prefix..tokens.len() - suffix].to_vec()),
+    }]
+}
+
+/// What values and types are, as defined in their module.
+#[derive(Default)]
+struct Definitions {
+    values: HashMap<String, (SemanticTokenType, u32)>,
+    types: HashMap<String, u32>,
+}
+
+impl Definitions {
+    fn new(module: &CheckedModule) -> Self {
+        let mut definitions = Definitions::default();
+
+        for definition in module.ast.definitions() {
+            match definition {
+                Definition::Fn(function) => {
+                    definitions
+                        .values
+                        .insert(function.name.clone(), (SemanticTokenType::FUNCTION, 0));
+                }
+
+                Definition::ModuleConstant(constant) => {
+                    definitions.values.insert(
+                        constant.name.clone(),
+                        (SemanticTokenType::VARIABLE, READONLY),
+                    );
+                }
+
+                Definition::DataType(data_type) => {
+                    let modifiers = if data_type.opaque { ABSTRACT } else { 0 };
+
+                    definitions.types.insert(data_type.name.clone(), modifiers);
+
+                    for constructor in data_type.constructors.iter() {
+                        definitions.values.insert(
+                            constructor.name.clone(),
+                            (SemanticTokenType::ENUM_MEMBER, 0),
+                        );
+                    }
+                }
+
+                Definition::TypeAlias(alias) => {
+                    definitions.types.insert(alias.alias.clone(), 0);
+                }
+
+                Definition::Use(..)
+                | Definition::Test(..)
+                | Definition::Benchmark(..)
+                | Definition::Validator(..) => (),
+            }
+        }
+
+        definitions
+    }
+}
+
+fn classify<'a>(
+    compiler: &LspProject,
+    definitions: &mut HashMap<&'a str, Definitions>,
+    occurrence: &'a Occurrence,
+) -> (SemanticTokenType, u32) {
+    match &occurrence.symbol {
+        Symbol::Module { .. } => (SemanticTokenType::NAMESPACE, 0),
+
+        Symbol::Type { module, name } => (
+            SemanticTokenType::TYPE,
+            defined_in(compiler, definitions, module)
+                .types
+                .get(name)
+                .copied()
+                .unwrap_or_default(),
+        ),
+
+        Symbol::Value { module, name } => defined_in(compiler, definitions, module)
+            .values
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| {
+                if name.starts_with(char::is_uppercase) {
+                    (SemanticTokenType::ENUM_MEMBER, 0)
+                } else {
+                    (SemanticTokenType::FUNCTION, 0)
+                }
+            }),
+
+        Symbol::Field { .. } => (SemanticTokenType::PROPERTY, 0),
+
+        Symbol::Variable { .. } => (SemanticTokenType::VARIABLE, 0),
+    }
+}
+
+fn defined_in<'a, 'b>(
+    compiler: &LspProject,
+    definitions: &'b mut HashMap<&'a str, Definitions>,
+    module: &'a str,
+) -> &'b Definitions {
+    definitions.entry(module).or_insert_with(|| {
+        compiler
+            .modules
+            .get(module)
+            .map(Definitions::new)
+            .unwrap_or_default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TOKEN_TYPES, delta, semantic_tokens};
+    use crate::tests::project;
+    use indoc::indoc;
+    use lsp_types::{SemanticToken, SemanticTokensEdit};
+
+    const SOURCE: &str = indoc! { r#"
+        use fixture/money.{Lovelace} as money
+
+        pub opaque type Wallet {
+          owner: ByteArray,
+          balance: Lovelace,
+        }
+
+        pub type Action {
+          Pay { amount: Int }
+          Close
+        }
+
+        const fee: Int = 2
+
+        pub fn pay(wallet: Wallet, action: Action) -> Int {
+          when action is {
+            Pay { amount } -> wallet.balance - money.to_lovelace(amount) - fee
+            Close -> 0
+          }
+        }
+    "#};
+
+    const MONEY: &str = indoc! { r#"
+        pub type Lovelace =
+          Int
+
+        pub fn to_lovelace(ada: Int) -> Lovelace {
+          ada * 1000000
+        }
+    "#};
+
+    /// Tokens as '<line>:<column> <text> <type> <modifiers>', positioned absolutely.
+    fn decode(text: &str, tokens: &[SemanticToken]) -> Vec<String> {
+        let lines = text.lines().collect::<Vec<_>>();
+
+        let (mut line, mut column) = (0, 0);
+
+        tokens
+            .iter()
+            .map(|token| {
+                if token.delta_line > 0 {
+                    column = 0;
+                }
+
+                line += token.delta_line;
+                column += token.delta_start;
+
+                let start = column as usize;
+
+                format!(
+                    "{line}:{column} {} {} {}",
+                    &lines[line as usize][start..start + token.length as usize],
+                    TOKEN_TYPES[token.token_type as usize].as_str(),
+                    token.token_modifiers_bitset,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tokens_in_order() {
+        let compiler = project(&[("lib/wallet", SOURCE), ("lib/fixture/money", MONEY)]);
+
+        let tokens = semantic_tokens(&compiler, &compiler.modules["wallet"], SOURCE);
+
+        // Modifiers: 1 for declarations, 2 for constants and 4 for opaque types.
+        assert_eq!(
+            decode(SOURCE, &tokens),
+            vec![
+                "0:4 fixture/money namespace 0",
+                "0:19 Lovelace type 0",
+                "0:32 money namespace 0",
+                "2:16 Wallet type 5",
+                "3:2 owner property 1",
+                "4:2 balance property 1",
+                "4:11 Lovelace type 0",
+                "7:9 Action type 1",
+                "8:2 Pay enumMember 1",
+                "8:8 amount property 1",
+                "9:2 Close enumMember 1",
+                "12:6 fee variable 3",
+                "14:7 pay function 1",
+                "14:11 wallet variable 1",
+                "14:19 Wallet type 4",
+                "14:27 action variable 1",
+                "14:35 Action type 0",
+                "15:7 action variable 0",
+                "16:4 Pay enumMember 0",
+                "16:10 amount variable 1",
+                "16:22 wallet variable 0",
+                "16:29 balance property 0",
+                "16:39 money namespace 0",
+                "16:45 to_lovelace function 0",
+                "16:57 amount variable 0",
+                "16:67 fee variable 2",
+                "17:4 Close enumMember 0",
+            ]
+        );
+
+        // Tokens are positioned relative to the previous one: by column on the same line, and
+        // from the start of the line otherwise.
+        assert_eq!(
+            tokens[..4]
+                .iter()
+                .map(|token| (token.delta_line, token.delta_start, token.length))
+                .collect::<Vec<_>>(),
+            vec![(0, 4, 13), (0, 15, 8), (0, 13, 5), (2, 16, 6)]
+        );
+    }
+
+    #[test]
+    fn tokens_of_edited_text() {
+        let compiler = project(&[("lib/wallet", SOURCE), ("lib/fixture/money", MONEY)]);
+
+        let edited = SOURCE.replace(
+            "const fee: Int = 2",
+            "const fee: Int = 2\nconst tip: Int = 1",
+        );
+
+        let tokens = semantic_tokens(&compiler, &compiler.modules["wallet"], &edited);
+
+        // What follows the edit is moved down a line, while the new constant isn't known yet.
+        let decoded = decode(&edited, &tokens);
+
+        assert_eq!(decoded[11], "12:6 fee variable 3");
+        assert_eq!(decoded[12], "15:7 pay function 1");
+        assert_eq!(decoded.len(), 27);
+    }
+
+    fn token(delta_line: u32, delta_start: u32) -> SemanticToken {
+        SemanticToken {
+            delta_line,
+            delta_start,
+            length: 3,
+            token_type: 0,
+            token_modifiers_bitset: 0,
+        }
+    }
+
+    #[test]
+    fn delta_identical() {
+        let tokens = vec![token(0, 1), token(1, 2)];
+
+        assert_eq!(delta(&tokens, &tokens), vec![]);
+    }
+
+    #[test]
+    fn delta_insert() {
+        let (a, b, c) = (token(0, 1), token(1, 2), token(2, 3));
+
+        assert_eq!(
+            delta(&[a, c], &[a, b, c]),
+            vec![SemanticTokensEdit {
+                start: 5,
+                delete_count: 0,
+                data: Some(vec![b]),
+            }]
+        );
+    }
+
+    #[test]
+    fn delta_delete() {
+        let (a, b, c) = (token(0, 1), token(1, 2), token(2, 3));
+
+        assert_eq!(
+            delta(&[a, b, c], &[a]),
+            vec![SemanticTokensEdit {
+                start: 5,
+                delete_count: 10,
+                data: Some(vec![]),
+            }]
+        );
+    }
+
+    #[test]
+    fn delta_overlapping_suffix() {
+        let (a, b) = (token(0, 1), token(1, 2));
+
+        // What's common at the end overlaps what's common at the start, which counts first.
+        assert_eq!(
+            delta(&[a, b, a], &[a, b, a, b, a]),
+            vec![SemanticTokensEdit {
+                start: 15,
+                delete_count: 0,
+                data: Some(vec![b, a]),
+            }]
+        );
+
+        assert_eq!(
+            delta(&[a, a, a], &[a, a]),
+            vec![SemanticTokensEdit {
+                start: 10,
+                delete_count: 5,
+                data: Some(vec![]),
+            }]
+        );
+    }
+}
@@ -1,16 +1,19 @@
 use self::lsp_project::LspProject;
 use crate::{
+    call_hierarchy,
     cast::{cast_notification, cast_request},
+    completion,
     error::Error as ServerError,
-    quickfix,
+    inlay_hints, outline, quickfix,
     quickfix::Quickfix,
+    references, semantic_tokens, signature_help,
     utils::{
         COMPILING_PROGRESS_TOKEN, CREATE_COMPILING_PROGRESS_TOKEN, path_to_uri, span_to_lsp_range,
         text_edit_replace, uri_to_module_name,
     },
 };
 use aiken_lang::{
-    ast::{Definition, Located, ModuleKind, Span, Use},
+    ast::{Located, ModuleKind, Span},
     error::ExtraData,
     line_numbers::LineNumbers,
     parser,
@@ -22,7 +25,6 @@ use aiken_project::{
     module::CheckedModule,
 };
 use indoc::formatdoc;
-use itertools::Itertools;
 use lsp_server::{Connection, Message};
 use lsp_types::{
     DocumentFormattingParams, InitializeParams, TextEdit,
@@ -31,8 +33,11 @@ use lsp_types::{
         Notification, Progress, PublishDiagnostics, ShowMessage,
     },
     request::{
-        CodeActionRequest, Completion, Formatting, GotoDefinition, HoverRequest, Request,
-        WorkDoneProgressCreate,
+        CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls, CallHierarchyPrepare,
+        CodeActionRequest, Completion, DocumentSymbolRequest, FoldingRangeRequest, Formatting,
+        GotoDefinition, HoverRequest, InlayHintRequest, PrepareRenameRequest, References, Rename,
+        Request, SemanticTokensFullDeltaRequest, SemanticTokensFullRequest, SignatureHelpRequest,
+        WorkDoneProgressCreate, WorkspaceSymbolRequest,
     },
 };
 use miette::Diagnostic;
@@ -71,6 +76,13 @@ pub struct Server {
 
     /// An instance of a LspProject
     compiler: Option<LspProject>,
+
+    /// Semantic tokens last sent for each document, along with their result id, from which
+    /// following requests may only ask for the changes.
+    semantic_tokens: HashMap<lsp_types::Url, (String, Vec<lsp_types::SemanticToken>)>,
+
+    /// The number of semantic tokens results sent so far, from which result ids are made.
+    semantic_tokens_results: u64,
 }
 
 impl Server {
@@ -231,6 +243,8 @@ impl Server {
 
                 self.edited.remove(params.text_document.uri.path());
 
+                self.semantic_tokens.remove(&params.text_document.uri);
+
                 Ok(())
             }
 
@@ -319,6 +333,161 @@ impl Server {
                 })
             }
 
+            References::METHOD => {
+                let params = cast_request::<References>(request)?;
+
+                let locations = self.references(params);
+
+                Ok(lsp_server::Response {
+                    id,
+                    error: None,
+                    result: Some(serde_json::to_value(locations)?),
+                })
+            }
+
+            PrepareRenameRequest::METHOD => {
+                let params = cast_request::<PrepareRenameRequest>(request)?;
+
+                Ok(match self.prepare_rename(params) {
+                    Ok(response) => lsp_server::Response {
+                        id,
+                        error: None,
+                        result: Some(serde_json::to_value(response)?),
+                    },
+                    Err(message) => request_failed(id, message),
+                })
+            }
+
+            Rename::METHOD => {
+                let params = cast_request::<Rename>(request)?;
+
+                Ok(match self.rename(params) {
+                    Ok(edit) => lsp_server::Response {
+                        id,
+                        error: None,
+                        result: Some(serde_json::to_value(edit)?),
+                    },
+                    Err(message) => request_failed(id, message),
+                })
+            }
+
+            DocumentSymbolRequest::METHOD => {
+                let params = cast_request::<DocumentSymbolRequest>(request)?;
+
+                let symbols = self
+                    .module_for_uri(&params.text_document.uri)
+                    .map(|module| {
+                        lsp_types::DocumentSymbolResponse::Nested(outline::document_symbols(module))
+                    });
+
+                Ok(lsp_server::Response {
+                    id,
+                    error: None,
+                    result: Some(serde_json::to_value(symbols)?),
+                })
+            }
+
+            WorkspaceSymbolRequest::METHOD => {
+                let params = cast_request::<WorkspaceSymbolRequest>(request)?;
+
+                let symbols = self.workspace_symbols(&params.query);
+
+                Ok(lsp_server::Response {
+                    id,
+                    error: None,
+                    result: Some(serde_json::to_value(symbols)?),
+                })
+            }
+
+            FoldingRangeRequest::METHOD => {
+                let params = cast_request::<FoldingRangeRequest>(request)?;
+
+                let ranges = self
+                    .module_for_uri(&params.text_document.uri)
+                    .map(outline::folding_ranges);
+
+                Ok(lsp_server::Response {
+                    id,
+                    error: None,
+                    result: Some(serde_json::to_value(ranges)?),
+                })
+            }
+
+            SemanticTokensFullRequest::METHOD => {
+                let params = cast_request::<SemanticTokensFullRequest>(request)?;
+
+                let tokens =
+                    self.semantic_tokens(params.text_document.uri)
+                        .map(|(result_id, data)| {
+                            lsp_types::SemanticTokensResult::Tokens(lsp_types::SemanticTokens {
+                                result_id: Some(result_id),
+                                data,
+                            })
+                        });
+
+                Ok(lsp_server::Response {
+                    id,
+                    error: None,
+                    result: Some(serde_json::to_value(tokens)?),
+                })
+            }
+
+            SemanticTokensFullDeltaRequest::METHOD => {
+                let params = cast_request::<SemanticTokensFullDeltaRequest>(request)?;
+
+                let previous = self
+                    .semantic_tokens
+                    .get(&params.text_document.uri)
+                    .filter(|(result_id, _)| *result_id == params.previous_result_id)
+                    .map(|(_, previous)| previous.clone());
+
+                let tokens =
+                    self.semantic_tokens(params.text_document.uri)
+                        .map(|(result_id, data)| match previous {
+                            Some(previous) => {
+                                lsp_types::SemanticTokensFullDeltaResult::TokensDelta(
+                                    lsp_types::SemanticTokensDelta {
+                                        result_id: Some(result_id),
+                                        edits: semantic_tokens::delta(&previous, &data),
+                                    },
+                                )
+                            }
+                            None => lsp_types::SemanticTokensFullDeltaResult::Tokens(
+                                lsp_types::SemanticTokens {
+                                    result_id: Some(result_id),
+                                    data,
+                                },
+                            ),
+                        });
+
+                Ok(lsp_server::Response {
+                    id,
+                    error: None,
+                    result: Some(serde_json::to_value(tokens)?),
+                })
+            }
+
+            InlayHintRequest::METHOD => {
+                let params = cast_request::<InlayHintRequest>(request)?;
+
+                let hints = self
+                    .module_for_uri(&params.text_document.uri)
+                    .map(|module| {
+                        let text = self
+                            .edited
+                            .get(params.text_document.uri.path())
+                            .unwrap_or(&module.code);
+
+                        inlay_hints::inlay_hints(module, text, params.range)
+                    });
+
+                Ok(lsp_server::Response {
+                    id,
+                    error: None,
+                    result: Some(serde_json::to_value(hints)?),
+                })
+            }
+
             Completion::METHOD => {
                 let params = cast_request::<Completion>(request).expect("cast Completion");
 
@@ -331,6 +500,65 @@ impl Server {
                 })
             }
 
+            SignatureHelpRequest::METHOD => {
+                let params = cast_request::<SignatureHelpRequest>(request)?;
+
+                let help = self.signature_help(params.text_document_position_params);
+
+                Ok(lsp_server::Response {
+                    id,
+                    error: None,
+                    result: Some(serde_json::to_value(help)?),
+                })
+            }
+
+            CallHierarchyPrepare::METHOD => {
+                let params = cast_request::<CallHierarchyPrepare>(request)?;
+
+                let items = self.compiler.as_ref().and_then(|compiler| {
+                    let (module, byte_index) =
+                        self.module_at_position(&params.text_document_position_params)?;
+
+                    call_hierarchy::prepare(compiler, module, byte_index)
+                });
+
+                Ok(lsp_server::Response {
+                    id,
+                    error: None,
+                    result: Some(serde_json::to_value(items)?),
+                })
+            }
+
+            CallHierarchyIncomingCalls::METHOD => {
+                let params = cast_request::<CallHierarchyIncomingCalls>(request)?;
+
+                let calls = self
+                    .compiler
+                    .as_ref()
+                    .and_then(|compiler| call_hierarchy::incoming_calls(compiler, &params.item));
+
+                Ok(lsp_server::Response {
+                    id,
+                    error: None,
+                    result: Some(serde_json::to_value(calls)?),
+                })
+            }
+
+            CallHierarchyOutgoingCalls::METHOD => {
+                let params = cast_request::<CallHierarchyOutgoingCalls>(request)?;
+
+                let calls = self
+                    .compiler
+                    .as_ref()
+                    .and_then(|compiler| call_hierarchy::outgoing_calls(compiler, &params.item));
+
+                Ok(lsp_server::Response {
+                    id,
+                    error: None,
+                    result: Some(serde_json::to_value(calls)?),
+                })
+            }
+
             CodeActionRequest::METHOD => {
                 let mut actions = Vec::new();
 
@@ -386,57 +614,39 @@ impl Server {
         &self,
         params: lsp_types::CompletionParams,
     ) -> Option<Vec<lsp_types::CompletionItem>> {
-        let found = self
-            .node_at_position(&params.text_document_position)
-            .map(|(_, found)| found);
-
-        match found {
-            // TODO: test
-            None => self.completion_for_import(&[]),
-            Some(Located::Definition(Definition::Use(Use { module, .. }))) => {
-                self.completion_for_import(module)
-            }
-
-            // TODO: autocompletion for patterns
-            Some(Located::Pattern(_pattern, _value)) => None,
+        let compiler = self.compiler.as_ref()?;
 
-            // TODO: autocompletion for other definitions
-            Some(Located::Definition(_expression)) => None,
+        let position = params.text_document_position;
 
-            // TODO: autocompletion for expressions
-            Some(Located::Expression(_expression)) => None,
+        let module = self.module_for_uri(&position.text_document.uri)?;
 
-            // TODO: autocompletion for arguments?
-            Some(Located::Argument(_arg_name, _tipo)) => None,
+        let text = self
+            .edited
+            .get(position.text_document.uri.path())
+            .unwrap_or(&module.code);
 
-            // TODO: autocompletion for annotation?
-            Some(Located::Annotation(_annotation)) => None,
-        }
+        Some(completion::completion(
+            compiler,
+            module,
+            text,
+            position.position,
+        ))
     }
 
-    fn completion_for_import(&self, module: &[String]) -> Option<Vec<lsp_types::CompletionItem>> {
+    fn signature_help(
+        &self,
+        params: lsp_types::TextDocumentPositionParams,
+    ) -> Option<lsp_types::SignatureHelp> {
         let compiler = self.compiler.as_ref()?;
 
-        // TODO: Test
-        let dependencies_modules = compiler.project.importable_modules();
-
-        // TODO: Test
-        let project_modules = compiler.modules.keys().cloned();
-
-        let modules = dependencies_modules
-            .into_iter()
-            .chain(project_modules)
-            .sorted()
-            .filter(|m| m.starts_with(&module.join("/")))
-            .map(|label| lsp_types::CompletionItem {
-                label,
-                kind: None,
-                documentation: None,
-                ..Default::default()
-            })
-            .collect();
+        let module = self.module_for_uri(&params.text_document.uri)?;
+
+        let text = self
+            .edited
+            .get(params.text_document.uri.path())
+            .unwrap_or(&module.code);
 
-        Some(modules)
+        signature_help::signature_help(compiler, module, text, params.position)
     }
 
     #[allow(clippy::result_large_err)]
@@ -485,18 +695,122 @@ impl Server {
         &self,
         params: &lsp_types::TextDocumentPositionParams,
     ) -> Option<(LineNumbers, Located<'_>)> {
-        let module = self.module_for_uri(&params.text_document.uri)?;
+        let (module, byte_index) = self.module_at_position(params)?;
+
+        let node = module.find_node(byte_index)?;
+
+        Some((LineNumbers::new(&module.code), node))
+    }
 
-        let line_numbers = LineNumbers::new(&module.code);
+    /// The module of a text document, along with the byte index of a position in it.
+    fn module_at_position(
+        &self,
+        params: &lsp_types::TextDocumentPositionParams,
+    ) -> Option<(&CheckedModule, usize)> {
+        let module = self.module_for_uri(&params.text_document.uri)?;
 
-        let byte_index = line_numbers.byte_index(
+        let byte_index = LineNumbers::new(&module.code).byte_index(
             params.position.line as usize,
             params.position.character as usize,
         );
 
-        let node = module.find_node(byte_index)?;
+        Some((module, byte_index))
+    }
+
+    fn references(&self, params: lsp_types::ReferenceParams) -> Option<Vec<lsp_types::Location>> {
+        let compiler = self.compiler.as_ref()?;
+
+        let (module, byte_index) = self.module_at_position(&params.text_document_position)?;
 
-        Some((line_numbers, node))
+        Some(references::references(
+            compiler,
+            &module.name,
+            byte_index,
+            params.context.include_declaration,
+        ))
+    }
+
+    fn prepare_rename(
+        &self,
+        params: lsp_types::TextDocumentPositionParams,
+    ) -> Result<Option<lsp_types::PrepareRenameResponse>, String> {
+        let (Some(compiler), Some(config)) = (self.compiler.as_ref(), self.config.as_ref()) else {
+            return Ok(None);
+        };
+
+        let Some((module, byte_index)) = self.module_at_position(&params) else {
+            return Ok(None);
+        };
+
+        let renamed = references::prepare_rename(
+            compiler,
+            &config.name.to_string(),
+            &module.name,
+            byte_index,
+        )?;
+
+        Ok(renamed.map(|(span, placeholder)| {
+            lsp_types::PrepareRenameResponse::RangeWithPlaceholder {
+                range: span_to_lsp_range(span, &LineNumbers::new(&module.code)),
+                placeholder,
+            }
+        }))
+    }
+
+    fn rename(
+        &self,
+        params: lsp_types::RenameParams,
+    ) -> Result<Option<lsp_types::WorkspaceEdit>, String> {
+        let (Some(compiler), Some(config)) = (self.compiler.as_ref(), self.config.as_ref()) else {
+            return Ok(None);
+        };
+
+        let Some((module, byte_index)) = self.module_at_position(&params.text_document_position)
+        else {
+            return Ok(None);
+        };
+
+        references::rename(
+            compiler,
+            &config.name.to_string(),
+            &module.name,
+            byte_index,
+            &params.new_name,
+            &self.edited,
+        )
+    }
+
+    /// The semantic tokens of a document, remembered along with a new result id.
+    fn semantic_tokens(
+        &mut self,
+        uri: lsp_types::Url,
+    ) -> Option<(String, Vec<lsp_types::SemanticToken>)> {
+        let compiler = self.compiler.as_ref()?;
+
+        let module = self.module_for_uri(&uri)?;
+
+        let text = self.edited.get(uri.path()).unwrap_or(&module.code);
+
+        let tokens = semantic_tokens::semantic_tokens(compiler, module, text);
+
+        self.semantic_tokens_results += 1;
+
+        let result_id = self.semantic_tokens_results.to_string();
+
+        self.semantic_tokens
+            .insert(uri, (result_id.clone(), tokens.clone()));
+
+        Some((result_id, tokens))
+    }
+
+    fn workspace_symbols(&self, query: &str) -> Option<lsp_types::WorkspaceSymbolResponse> {
+        let (Some(compiler), Some(config)) = (self.compiler.as_ref(), self.config.as_ref()) else {
+            return None;
+        };
+
+        Some(lsp_types::WorkspaceSymbolResponse::Nested(
+            outline::workspace_symbols(compiler, &config.name.to_string(), query),
+        ))
     }
 
     fn module_for_uri(&self, uri: &url::Url) -> Option<&CheckedModule> {
@@ -615,6 +929,8 @@ impl Server {
             stored_diagnostics: HashMap::new(),
             stored_messages: Vec::new(),
             compiler: None,
+            semantic_tokens: HashMap::new(),
+            semantic_tokens_results: 0,
         };
 
         server.create_new_compiler();
@@ -883,3 +1199,16 @@ impl Server {
         Ok(())
     }
 }
+
+/// A response reporting why a request couldn't be fulfilled, for the client to show.
+fn request_failed(id: lsp_server::RequestId, message: String) -> lsp_server::Response {
+    lsp_server::Response {
+        id,
+        result: None,
+        error: Some(lsp_server::ResponseError {
+            code: lsp_server::ErrorCode::RequestFailed as i32,
+            message,
+            data: None,
+        }),
+    }
+}
@@ -1,4 +1,10 @@
-use aiken_lang::{ast::Tracing, line_numbers::LineNumbers, test_framework::PropertyTest};
+use aiken_lang::{
+    ast::Tracing,
+    line_numbers::LineNumbers,
+    symbols::{self, Occurrence},
+    test_framework::PropertyTest,
+    tipo::TypeInfo,
+};
 use aiken_project::{
     Project, config::ProjectConfig, error::Error as ProjectError, module::CheckedModule,
     telemetry::CoverageMode,
@@ -18,6 +24,10 @@ pub struct LspProject {
     pub project: Project<super::telemetry::Lsp>,
     pub modules: HashMap<String, CheckedModule>,
     pub sources: HashMap<String, SourceInfo>,
+    /// Interfaces of the prelude and of every module checked so far.
+    pub module_types: HashMap<String, TypeInfo>,
+    /// Occurrences of the symbols defined or referred to in each module, dependencies included.
+    pub occurrences: HashMap<String, Vec<Occurrence>>,
 }
 
 impl LspProject {
@@ -26,6 +36,8 @@ impl LspProject {
             project: Project::new_with_config(config, root, telemetry),
             modules: HashMap::new(),
             sources: HashMap::new(),
+            module_types: HashMap::new(),
+            occurrences: HashMap::new(),
         }
     }
 
@@ -42,6 +54,12 @@ impl LspProject {
             CoverageMode::default(),
             Tracing::verbose(),
             false,
+            false,
+            false,
+            false,
+            false,
+            None,
+            false,
             None,
         );
 
@@ -64,10 +82,22 @@ impl LspProject {
 
             module.attach_doc_and_module_comments();
 
+            self.occurrences.insert(
+                module.name.to_string(),
+                symbols::occurrences(&module.ast, &module.code),
+            );
+
             self.sources.insert(module.name.to_string(), source);
             self.modules.insert(module.name.to_string(), module);
         }
 
+        self.module_types = self.project.module_types().clone();
+
+        for module in self.modules.values() {
+            self.module_types
+                .insert(module.name.clone(), module.ast.type_info.clone());
+        }
+
         result?;
 
         Ok(())
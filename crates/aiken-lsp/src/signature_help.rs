@@ -0,0 +1,552 @@
+//! Signature help: the arguments of the function, or constructor, called where the cursor is,
+//! highlighting the one being written.
+//!
+//! Like completion, the call is worked out from the edited text preceding the cursor, while what
+//! it calls is looked up in the scope of the last checked version of the module. Arguments are
+//! shown as written where the callee is defined, labels and patterns included.
+
+use crate::{
+    completion::{
+        arguments, callee, chain_before, char_boundary, checked_index, in_signature,
+        in_type_definition, label_of, labelled, line_at, markdown, open_brackets, pretty,
+        strip_keyword_suffix, type_of,
+    },
+    server::lsp_project::LspProject,
+};
+use aiken_lang::{
+    ast::{Definition, Span},
+    line_numbers::LineNumbers,
+    tipo::{ValueConstructor, ValueConstructorVariant, scope::Scope},
+};
+use aiken_project::module::CheckedModule;
+use lsp_types::{ParameterInformation, ParameterLabel, SignatureHelp, SignatureInformation};
+use std::collections::HashSet;
+
+/// A call being written, as far as the text preceding the cursor tells.
+#[derive(Debug)]
+struct Call<'a> {
+    /// The function or constructor called.
+    callee: Vec<String>,
+    /// Whether it's a constructor whose fields are given within braces.
+    is_record: bool,
+    /// Whether it's a stage of a pipeline, whose first argument is the value piped in.
+    is_piped: bool,
+    /// The arguments given so far, the last one being written.
+    arguments: Vec<&'a str>,
+}
+
+/// An argument of a signature, along with its doc comment.
+struct Parameter {
+    text: String,
+    doc: Option<String>,
+}
+
+/// The signature of the call surrounding the given position of a module, whose current text may
+/// differ from the one last checked.
+pub fn signature_help(
+    compiler: &LspProject,
+    module: &CheckedModule,
+    text: &str,
+    position: lsp_types::Position,
+) -> Option<SignatureHelp> {
+    let line_numbers = LineNumbers::new(text);
+
+    let cursor = char_boundary(
+        text,
+        line_numbers.byte_index(position.line as usize, position.character as usize),
+    );
+
+    let call = call(&text[..cursor])?;
+
+    let scope = Scope::new(
+        &module.ast,
+        checked_index(&module.code, text, cursor),
+        &compiler.module_types,
+    );
+
+    let value = callee(&scope, &compiler.module_types, &call.callee);
+
+    let tipo = match value {
+        Some(value) => value.tipo.clone(),
+        None => type_of(&scope, &compiler.module_types, &call.callee)?,
+    };
+
+    let (arg_types, return_type) = tipo.function_types()?;
+
+    let mut labels = vec![None; arg_types.len()];
+
+    let field_map = value.and_then(|value| match &value.variant {
+        ValueConstructorVariant::ModuleFn { field_map, .. }
+        | ValueConstructorVariant::Record { field_map, .. } => field_map.as_ref(),
+        ValueConstructorVariant::LocalVariable { .. }
+        | ValueConstructorVariant::ModuleConstant { .. } => None,
+    });
+
+    if let Some(field_map) = field_map {
+        for (label, (index, _)) in field_map.fields.iter() {
+            // Arguments given by pattern have a label made up, which can't be written.
+            if label.starts_with(char::is_alphabetic) {
+                if let Some(slot) = labels.get_mut(*index) {
+                    *slot = Some(label.as_str());
+                }
+            }
+        }
+    }
+
+    let (parameters, documentation) = match value.and_then(|value| definition(compiler, value)) {
+        Some((parameters, documentation)) if parameters.len() == arg_types.len() => {
+            (parameters, documentation)
+        }
+        _ => (
+            arg_types
+                .iter()
+                .zip(labels.iter())
+                .map(|(tipo, label)| Parameter {
+                    text: match label {
+                        Some(label) => format!("{label}: {}", pretty(tipo)),
+                        None => pretty(tipo),
+                    },
+                    doc: None,
+                })
+                .collect(),
+            None,
+        ),
+    };
+
+    let mut label = call.callee.join(".");
+
+    label.push_str(if call.is_record { " { " } else { "(" });
+
+    let mut offsets = Vec::new();
+
+    for (ix, parameter) in parameters.iter().enumerate() {
+        if ix > 0 {
+            label.push_str(", ");
+        }
+
+        let start = utf16_len(&label);
+        label.push_str(&parameter.text);
+        offsets.push([start, utf16_len(&label)]);
+    }
+
+    if call.is_record {
+        label.push_str(" }");
+    } else {
+        label.push_str(&format!(") -> {}", pretty(&return_type)));
+    }
+
+    // Past the last parameter, none is highlighted.
+    let active = active_parameter(&call, &labels).unwrap_or(parameters.len()) as u32;
+
+    Some(SignatureHelp {
+        signatures: vec![SignatureInformation {
+            label,
+            documentation: documentation.map(markdown),
+            parameters: Some(
+                parameters
+                    .into_iter()
+                    .zip(offsets)
+                    .map(|(parameter, offsets)| ParameterInformation {
+                        label: ParameterLabel::LabelOffsets(offsets),
+                        documentation: parameter.doc.map(markdown),
+                    })
+                    .collect(),
+            ),
+            active_parameter: Some(active),
+        }],
+        active_signature: Some(0),
+        active_parameter: Some(active),
+    })
+}
+
+/// Work out the innermost call the given text ends within, if any. Lists, tuples and
+/// parentheses may come in between, but not blocks.
+fn call(text: &str) -> Option<Call<'_>> {
+    let brackets = open_brackets(text)?;
+
+    if in_signature(text, &brackets)
+        || in_type_definition(text, line_at(text, text.len()).trim_start(), &brackets)
+    {
+        return None;
+    }
+
+    for &(open, ix) in brackets.iter().rev() {
+        let head = text[..ix].trim_end();
+
+        let callee = chain_before(head);
+
+        let before = head[..head.len() - callee.join(".").len()].trim_end();
+
+        // Braces after a type name also open the body of functions returning it, or the
+        // constructors of a type.
+        let is_record = open == '{'
+            && callee
+                .last()
+                .is_some_and(|name| name.starts_with(char::is_uppercase))
+            && !before.ends_with("->")
+            && strip_keyword_suffix(before, "type").is_none();
+
+        match open {
+            '(' if !callee.is_empty() => (),
+            '{' if is_record => (),
+            '(' | '[' => continue,
+            _ => return None,
+        }
+
+        return Some(Call {
+            callee,
+            is_record,
+            is_piped: open == '(' && before.ends_with("|>"),
+            arguments: arguments(&text[ix + 1..]),
+        });
+    }
+
+    None
+}
+
+/// The index of the parameter the argument being written stands for. Labelled arguments go
+/// where their label says, and the others fill the remaining parameters in order.
+fn active_parameter(call: &Call<'_>, labels: &[Option<&str>]) -> Option<usize> {
+    let index_of = |label: &str| labels.iter().position(|known| *known == Some(label));
+
+    let labelled = |argument| labelled(argument, call.is_record);
+
+    let (current, previous) = call.arguments.split_last()?;
+
+    if let Some(label) = labelled(current).filter(|label| !label.is_empty()) {
+        if let Some(index) = index_of(label) {
+            return Some(index);
+        }
+
+        // A label being written, or one that doesn't exist.
+        if label_of(current).is_some() || !call.is_record {
+            return None;
+        }
+    }
+
+    let taken = previous
+        .iter()
+        .filter_map(|argument| labelled(argument))
+        .filter_map(index_of)
+        .collect::<HashSet<_>>();
+
+    let positional = previous
+        .iter()
+        .filter(|argument| labelled(argument).is_none())
+        .count()
+        + usize::from(call.is_piped);
+
+    (0..labels.len())
+        .filter(|index| !taken.contains(index))
+        .nth(positional)
+}
+
+/// The arguments of a function, or constructor, as written where it's defined, along with its
+/// doc comment. Arguments written without annotation are given their inferred type.
+fn definition(
+    compiler: &LspProject,
+    value: &ValueConstructor,
+) -> Option<(Vec<Parameter>, Option<String>)> {
+    let written = |code: &str, location: Span| {
+        code.get(location.start..location.end)
+            .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+    };
+
+    match &value.variant {
+        ValueConstructorVariant::ModuleFn { module, name, .. } => {
+            let checked = compiler.modules.get(module)?;
+
+            checked
+                .ast
+                .definitions()
+                .find_map(|definition| match definition {
+                    Definition::Fn(function) if function.name == *name => Some(function),
+                    _ => None,
+                })
+                .and_then(|function| {
+                    let parameters = function
+                        .arguments
+                        .iter()
+                        .map(|arg| {
+                            let mut text = written(&checked.code, arg.location)?;
+
+                            if arg.annotation.is_none() {
+                                text.push_str(&format!(": {}", pretty(&arg.tipo)));
+                            }
+
+                            Some(Parameter {
+                                text,
+                                doc: arg.doc.clone(),
+                            })
+                        })
+                        .collect::<Option<Vec<_>>>()?;
+
+                    Some((parameters, function.doc.clone()))
+                })
+        }
+
+        ValueConstructorVariant::Record { module, name, .. } => {
+            let checked = compiler.modules.get(module)?;
+
+            checked
+                .ast
+                .definitions()
+                .find_map(|definition| match definition {
+                    Definition::DataType(data_type) => data_type
+                        .constructors
+                        .iter()
+                        .find(|constructor| constructor.name == *name),
+                    _ => None,
+                })
+                .and_then(|constructor| {
+                    let parameters = constructor
+                        .arguments
+                        .iter()
+                        .map(|arg| {
+                            Some(Parameter {
+                                text: written(&checked.code, arg.location)?,
+                                doc: arg.doc.clone(),
+                            })
+                        })
+                        .collect::<Option<Vec<_>>>()?;
+
+                    Some((parameters, constructor.doc.clone()))
+                })
+        }
+
+        ValueConstructorVariant::LocalVariable { .. }
+        | ValueConstructorVariant::ModuleConstant { .. } => None,
+    }
+}
+
+/// The length of a text in UTF-16 code units, which offsets within labels count.
+fn utf16_len(text: &str) -> u32 {
+    text.encode_utf16().count() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::signature_help;
+    use crate::tests::project;
+    use indoc::indoc;
+    use lsp_types::{Documentation, ParameterLabel, Position, SignatureHelp};
+
+    const SOURCE: &str = indoc! { r#"
+        use fixture/money
+
+        /// Transfer an amount between two parties.
+        pub fn transfer(
+          /// Who pays.
+          from: ByteArray,
+          to: ByteArray,
+          amount: Int,
+        ) -> Int {
+          amount + money.fee(from, to)
+        }
+
+        pub type Payment {
+          payer: ByteArray,
+          amount: Int,
+        }
+
+        pub fn sum(Pair(a, b): Pair<Int, Int>, c: Int) -> Int {
+          a + b + c
+        }
+
+        test example() {
+          transfer(#"00", #"01", 1) > 0
+        }
+    "#};
+
+    const MONEY: &str = indoc! { r#"
+        pub fn fee(_from: ByteArray, _to: ByteArray) -> Int {
+          2
+        }
+    "#};
+
+    /// The signature's label, with the active parameter within brackets.
+    fn show(help: &SignatureHelp) -> String {
+        let signature = &help.signatures[0];
+
+        let mut label = signature.label.encode_utf16().collect::<Vec<_>>();
+
+        if let Some(active) = signature
+            .active_parameter
+            .and_then(|active| signature.parameters.as_ref()?.get(active as usize))
+        {
+            let ParameterLabel::LabelOffsets([start, end]) = active.label else {
+                unreachable!()
+            };
+
+            label.insert(end as usize, u16::from(b']'));
+            label.insert(start as usize, u16::from(b'['));
+        }
+
+        String::from_utf16(&label).unwrap()
+    }
+
+    /// The help given at the end of a line appended to the test's body.
+    fn help_at(line: &str) -> Option<String> {
+        let compiler = project(&[("lib/foo", SOURCE), ("lib/fixture/money", MONEY)]);
+
+        let text = SOURCE.replace(
+            "  transfer(#\"00\", #\"01\", 1) > 0\n",
+            &format!("  {line}\n"),
+        );
+
+        let (row, column) = text
+            .lines()
+            .enumerate()
+            .find(|(_, l)| l.trim_start() == line)
+            .map(|(row, l)| (row, l.len()))
+            .unwrap();
+
+        signature_help(
+            &compiler,
+            &compiler.modules["foo"],
+            &text,
+            Position::new(row as u32, column as u32),
+        )
+        .map(|help| show(&help))
+    }
+
+    #[test]
+    fn active_positional() {
+        assert_eq!(
+            help_at("transfer(").as_deref(),
+            Some("transfer([from: ByteArray], to: ByteArray, amount: Int) -> Int")
+        );
+
+        assert_eq!(
+            help_at("transfer(#\"00\", ").as_deref(),
+            Some("transfer(from: ByteArray, [to: ByteArray], amount: Int) -> Int")
+        );
+
+        // Commas nested within other brackets don't separate arguments.
+        assert_eq!(
+            help_at("transfer([1, 2], ").as_deref(),
+            Some("transfer(from: ByteArray, [to: ByteArray], amount: Int) -> Int")
+        );
+
+        assert_eq!(
+            help_at("money.fee(").as_deref(),
+            Some("money.fee([_from: ByteArray], _to: ByteArray) -> Int")
+        );
+
+        assert_eq!(help_at("transfer(#\"00\")"), None);
+    }
+
+    #[test]
+    fn active_labelled() {
+        // Labelled arguments take their parameter, and the others fill the remaining ones.
+        assert_eq!(
+            help_at("transfer(amount: 1, ").as_deref(),
+            Some("transfer([from: ByteArray], to: ByteArray, amount: Int) -> Int")
+        );
+
+        assert_eq!(
+            help_at("transfer(to: #\"00\", #\"01\", ").as_deref(),
+            Some("transfer(from: ByteArray, to: ByteArray, [amount: Int]) -> Int")
+        );
+
+        assert_eq!(
+            help_at("transfer(#\"00\", amount: ").as_deref(),
+            Some("transfer(from: ByteArray, to: ByteArray, [amount: Int]) -> Int")
+        );
+
+        // No parameter goes by a label which doesn't exist.
+        assert_eq!(
+            help_at("transfer(xyz: ").as_deref(),
+            Some("transfer(from: ByteArray, to: ByteArray, amount: Int) -> Int")
+        );
+    }
+
+    #[test]
+    fn active_piped() {
+        // The value piped in is the first argument.
+        assert_eq!(
+            help_at("#\"00\" |> transfer(").as_deref(),
+            Some("transfer(from: ByteArray, [to: ByteArray], amount: Int) -> Int")
+        );
+
+        assert_eq!(
+            help_at("#\"00\" |> transfer(#\"01\", ").as_deref(),
+            Some("transfer(from: ByteArray, to: ByteArray, [amount: Int]) -> Int")
+        );
+    }
+
+    #[test]
+    fn active_field() {
+        assert_eq!(
+            help_at("Payment { ").as_deref(),
+            Some("Payment { [payer: ByteArray], amount: Int }")
+        );
+
+        // Fields given in shorthand are labelled too.
+        assert_eq!(
+            help_at("Payment { amount, ").as_deref(),
+            Some("Payment { [payer: ByteArray], amount: Int }")
+        );
+
+        assert_eq!(
+            help_at("Payment { payer: #\"00\", am").as_deref(),
+            Some("Payment { payer: ByteArray, [amount: Int] }")
+        );
+    }
+
+    #[test]
+    fn active_by_pattern() {
+        // Arguments given by pattern are shown as written, and can't be given by label.
+        assert_eq!(
+            help_at("sum(").as_deref(),
+            Some("sum([Pair(a, b): Pair<Int, Int>], c: Int) -> Int")
+        );
+
+        assert_eq!(
+            help_at("sum(Pair(1, 2), ").as_deref(),
+            Some("sum(Pair(a, b): Pair<Int, Int>, [c: Int]) -> Int")
+        );
+
+        assert_eq!(
+            help_at("sum(c: 1, ").as_deref(),
+            Some("sum([Pair(a, b): Pair<Int, Int>], c: Int) -> Int")
+        );
+    }
+
+    #[test]
+    fn documentation() {
+        let compiler = project(&[("lib/foo", SOURCE), ("lib/fixture/money", MONEY)]);
+
+        let text = SOURCE.replace("transfer(#\"00\", #\"01\", 1) > 0", "transfer(");
+
+        let help = signature_help(
+            &compiler,
+            &compiler.modules["foo"],
+            &text,
+            Position::new(22, 11),
+        )
+        .unwrap();
+
+        let signature = &help.signatures[0];
+
+        let doc = |documentation: &Option<Documentation>| match documentation {
+            Some(Documentation::MarkupContent(content)) => Some(content.value.trim().to_string()),
+            _ => None,
+        };
+
+        assert_eq!(
+            doc(&signature.documentation).as_deref(),
+            Some("Transfer an amount between two parties.")
+        );
+
+        assert_eq!(
+            signature
+                .parameters
+                .iter()
+                .flatten()
+                .map(|parameter| doc(&parameter.documentation))
+                .collect::<Vec<_>>(),
+            vec![Some("Who pays.".to_string()), None, None]
+        );
+    }
+}
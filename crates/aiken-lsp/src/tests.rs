@@ -0,0 +1,66 @@
+use crate::server::{lsp_project::LspProject, telemetry::Lsp};
+use aiken_project::config::ProjectConfig;
+use std::{
+    fs,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// The package of projects made up by 'project'.
+pub const PACKAGE: &str = "aiken-lang/fixture";
+
+/// A project made up of the given modules, by path from the root of the project, without
+/// extension, and source code; checked as the language server does.
+pub fn project(modules: &[(&str, &str)]) -> LspProject {
+    static PROJECTS: AtomicUsize = AtomicUsize::new(0);
+
+    let root = std::env::temp_dir().join(format!(
+        "aiken-lsp-{}-{}",
+        std::process::id(),
+        PROJECTS.fetch_add(1, Ordering::SeqCst)
+    ));
+
+    let _ = fs::remove_dir_all(&root);
+
+    fs::create_dir_all(&root).unwrap();
+
+    fs::write(
+        root.join("aiken.toml"),
+        format!("name = \"{PACKAGE}\"\nversion = \"0.0.0\"\nplutus = \"v3\"\n"),
+    )
+    .unwrap();
+
+    for (path, code) in modules {
+        let path = root.join(format!("{path}.ak"));
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        fs::write(path, code).unwrap();
+    }
+
+    let root = root.canonicalize().unwrap();
+
+    let config = ProjectConfig::load(&root).unwrap();
+
+    let mut compiler = LspProject::new(config, root.clone(), Lsp);
+
+    let result = compiler.compile();
+
+    // Modules are kept in memory once checked.
+    let _ = fs::remove_dir_all(&root);
+
+    if let Err(errors) = result {
+        panic!("failed to check the project: {errors:#?}");
+    }
+
+    compiler
+}
+
+/// The byte index of the n-th (from 0) occurrence of a text within the code of a module.
+pub fn index_of(compiler: &LspProject, module: &str, text: &str, n: usize) -> usize {
+    compiler.modules[module]
+        .code
+        .match_indices(text)
+        .nth(n)
+        .unwrap_or_else(|| panic!("no occurrence #{n} of '{text}' in '{module}'"))
+        .0
+}
@@ -100,3 +100,64 @@ pub fn uri_to_module_name(uri: &url::Url, root: &Path) -> Option<String> {
 
     Some(module_name)
 }
+
+/// How the text of a module being edited differs from the text last checked: both share a
+/// common prefix and suffix, around the edits.
+pub struct TextDiff {
+    prefix: usize,
+    suffix: usize,
+    checked_len: usize,
+    edited_len: usize,
+}
+
+impl TextDiff {
+    pub fn new(checked: &str, edited: &str) -> Self {
+        let prefix = checked
+            .bytes()
+            .zip(edited.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let suffix = checked
+            .bytes()
+            .rev()
+            .zip(edited.bytes().rev())
+            .take(checked.len().min(edited.len()) - prefix)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        TextDiff {
+            prefix,
+            suffix,
+            checked_len: checked.len(),
+            edited_len: edited.len(),
+        }
+    }
+
+    /// The byte index in the checked text corresponding to the given one in the edited text.
+    /// Anywhere within the edits maps to where they start.
+    pub fn checked_index(&self, index: usize) -> usize {
+        if index <= self.prefix {
+            index
+        } else if index >= self.edited_len - self.suffix {
+            index + self.checked_len - self.edited_len
+        } else {
+            self.prefix
+        }
+    }
+
+    /// The span in the edited text corresponding to the given one in the checked text, unless
+    /// it overlaps the edits.
+    pub fn edited_span(&self, span: Span) -> Option<Span> {
+        if span.end <= self.prefix {
+            Some(span)
+        } else if span.start >= self.checked_len - self.suffix {
+            Some(Span {
+                start: span.start + self.edited_len - self.checked_len,
+                end: span.end + self.edited_len - self.checked_len,
+            })
+        } else {
+            None
+        }
+    }
+}
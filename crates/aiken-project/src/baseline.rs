@@ -0,0 +1,422 @@
+//! Benchmark results saved under a name, for later runs to be compared against. Baselines are
+//! stored under `build/baselines/<name>.json`, as the measures of each benchmark by module and
+//! name.
+//!
+//! Since benchmarks are sampled from a seed, two runs rarely measure the same values. So rather
+//! than comparing measures point by point, a growth curve is fitted to the measures of each run,
+//! and both curves are compared at the largest size measured by both.
+
+use crate::error::Error;
+use aiken_lang::test_framework::{BenchmarkResult, TestResult};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Display, fs, path::Path};
+use uplc::machine::cost_model::ExBudget;
+
+/// Relative increase of execution units above which a benchmark is considered to have regressed,
+/// unless specified otherwise.
+pub const DEFAULT_THRESHOLD: f64 = 0.05;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Baseline(BTreeMap<String, BTreeMap<String, Vec<Measure>>>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Measure {
+    pub size: usize,
+    pub mem: i64,
+    pub cpu: i64,
+}
+
+impl Baseline {
+    /// Measures of the benchmarks which ran until completion.
+    pub fn from_results<U, T>(results: &[TestResult<U, T>]) -> Self {
+        let mut baseline = Baseline::default();
+
+        for result in results {
+            if let TestResult::BenchmarkResult(BenchmarkResult {
+                bench,
+                measures,
+                error: None,
+            }) = result
+            {
+                baseline.0.entry(bench.module.clone()).or_default().insert(
+                    bench.name.clone(),
+                    measures
+                        .iter()
+                        .map(|(size, ExBudget { mem, cpu })| Measure {
+                            size: *size,
+                            mem: *mem,
+                            cpu: *cpu,
+                        })
+                        .collect(),
+                );
+            }
+        }
+
+        baseline
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path).map_err(|error| Error::FileIo {
+            error,
+            path: Box::new(path.to_path_buf()),
+        })?;
+
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let json = serde_json::to_string_pretty(self).unwrap();
+
+        fs::write(path, format!("{json}\n")).map_err(|error| Error::FileIo {
+            error,
+            path: Box::new(path.to_path_buf()),
+        })
+    }
+
+    pub fn get(&self, module: &str, name: &str) -> Option<&[Measure]> {
+        self.0
+            .get(module)
+            .and_then(|benchmarks| benchmarks.get(name))
+            .map(|measures| measures.as_slice())
+    }
+
+    /// Compare the benchmarks of a run to those of this baseline. Benchmarks not found in the
+    /// baseline, or with too few measures to fit a curve, are reported as new.
+    pub fn compare(&self, name: &str, current: &Baseline, threshold: f64) -> BaselineReport {
+        let mut comparisons = Vec::new();
+        let mut new = Vec::new();
+
+        for (module, benchmarks) in current.0.iter() {
+            for (bench, measures) in benchmarks.iter() {
+                match self
+                    .get(module, bench)
+                    .and_then(|baseline| Comparison::new(module, bench, baseline, measures))
+                {
+                    Some(comparison) => comparisons.push(comparison),
+                    None => new.push(format!("{module}.{bench}")),
+                }
+            }
+        }
+
+        BaselineReport {
+            name: name.to_string(),
+            threshold,
+            comparisons,
+            new,
+        }
+    }
+}
+
+/// The outcome of comparing a run against a baseline.
+#[derive(Debug, Clone)]
+pub struct BaselineReport {
+    pub name: String,
+    pub threshold: f64,
+    pub comparisons: Vec<Comparison>,
+    pub new: Vec<String>,
+}
+
+impl BaselineReport {
+    pub fn regressions(&self) -> impl Iterator<Item = &Comparison> {
+        self.comparisons
+            .iter()
+            .filter(|comparison| comparison.is_regression(self.threshold))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub module: String,
+    pub name: String,
+    /// The size at which both curves are compared.
+    pub size: usize,
+    pub mem: Change,
+    pub cpu: Change,
+}
+
+impl Comparison {
+    fn new(module: &str, name: &str, baseline: &[Measure], current: &[Measure]) -> Option<Self> {
+        let max_size = |measures: &[Measure]| measures.iter().map(|m| m.size).max();
+
+        let size = max_size(baseline)?.min(max_size(current)?);
+
+        let change = |units: fn(&Measure) -> i64| {
+            let points = |measures: &[Measure]| {
+                measures
+                    .iter()
+                    .map(|m| (m.size, units(m)))
+                    .collect::<Vec<_>>()
+            };
+
+            Some(Change::new(
+                Curve::fit(&points(baseline))?,
+                Curve::fit(&points(current))?,
+                size,
+            ))
+        };
+
+        Some(Comparison {
+            module: module.to_string(),
+            name: name.to_string(),
+            size,
+            mem: change(|m| m.mem)?,
+            cpu: change(|m| m.cpu)?,
+        })
+    }
+
+    pub fn is_regression(&self, threshold: f64) -> bool {
+        self.mem.relative() > threshold || self.cpu.relative() > threshold
+    }
+
+    pub fn is_improvement(&self, threshold: f64) -> bool {
+        !self.is_regression(threshold)
+            && (self.mem.relative() < -threshold || self.cpu.relative() < -threshold)
+    }
+}
+
+/// How execution units evolved between the baseline and the current run, as estimated by their
+/// respective curves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Change {
+    pub baseline: Curve,
+    pub current: Curve,
+    pub before: f64,
+    pub after: f64,
+}
+
+impl Change {
+    fn new(baseline: Curve, current: Curve, size: usize) -> Self {
+        Change {
+            baseline,
+            current,
+            before: baseline.at(size),
+            after: current.at(size),
+        }
+    }
+
+    /// The relative increase (or decrease, when negative) of execution units.
+    pub fn relative(&self) -> f64 {
+        if self.before > 0.0 {
+            (self.after - self.before) / self.before
+        } else if self.after > 0.0 {
+            f64::INFINITY
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Asymptotic growth of execution units with the size of the sampled values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Growth {
+    Constant,
+    Logarithmic,
+    Linear,
+    Linearithmic,
+    Quadratic,
+}
+
+impl Growth {
+    const ALL: [Growth; 5] = [
+        Growth::Constant,
+        Growth::Logarithmic,
+        Growth::Linear,
+        Growth::Linearithmic,
+        Growth::Quadratic,
+    ];
+
+    fn apply(&self, size: f64) -> f64 {
+        match self {
+            Growth::Constant => 0.0,
+            Growth::Logarithmic => (size + 1.0).ln(),
+            Growth::Linear => size,
+            Growth::Linearithmic => size * (size + 1.0).ln(),
+            Growth::Quadratic => size * size,
+        }
+    }
+}
+
+impl Display for Growth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Growth::Constant => "O(1)",
+            Growth::Logarithmic => "O(log n)",
+            Growth::Linear => "O(n)",
+            Growth::Linearithmic => "O(n log n)",
+            Growth::Quadratic => "O(n²)",
+        })
+    }
+}
+
+/// A curve of the form `intercept + slope * growth(size)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Curve {
+    pub growth: Growth,
+    pub intercept: f64,
+    pub slope: f64,
+}
+
+impl Curve {
+    /// Fit measures with least squares, for each growth, and keep the one which explains them
+    /// best. A faster growth is only preferred when it reduces the residual error by a noticeable
+    /// share of the total variance, so that noise doesn't pass for a change of complexity.
+    pub fn fit(points: &[(usize, i64)]) -> Option<Curve> {
+        if points.is_empty() {
+            return None;
+        }
+
+        let n = points.len() as f64;
+        let mean = points.iter().map(|(_, y)| *y as f64).sum::<f64>() / n;
+        let variance = points
+            .iter()
+            .map(|(_, y)| (*y as f64 - mean).powi(2))
+            .sum::<f64>();
+
+        let mut best: Option<(Curve, f64)> = None;
+
+        for growth in Growth::ALL {
+            let Some(curve) = Curve::least_squares(growth, points, mean) else {
+                continue;
+            };
+
+            let residual = points
+                .iter()
+                .map(|(x, y)| (*y as f64 - curve.at(*x)).powi(2))
+                .sum::<f64>();
+
+            match best {
+                Some((_, best_residual)) if best_residual - residual <= 0.01 * variance => {}
+                _ => best = Some((curve, residual)),
+            }
+        }
+
+        best.map(|(curve, _)| curve)
+    }
+
+    fn least_squares(growth: Growth, points: &[(usize, i64)], mean: f64) -> Option<Curve> {
+        if growth == Growth::Constant {
+            return Some(Curve {
+                growth,
+                intercept: mean,
+                slope: 0.0,
+            });
+        }
+
+        let n = points.len() as f64;
+        let xs = points
+            .iter()
+            .map(|(x, _)| growth.apply(*x as f64))
+            .collect::<Vec<_>>();
+        let mean_x = xs.iter().sum::<f64>() / n;
+
+        let (covariance, variance) =
+            xs.iter()
+                .zip(points.iter())
+                .fold((0.0, 0.0), |(covariance, variance), (x, (_, y))| {
+                    (
+                        covariance + (x - mean_x) * (*y as f64 - mean),
+                        variance + (x - mean_x).powi(2),
+                    )
+                });
+
+        if variance == 0.0 {
+            return None;
+        }
+
+        let slope = covariance / variance;
+
+        // Execution units don't shrink as values grow; a decreasing curve is just noise.
+        if slope < 0.0 {
+            return None;
+        }
+
+        Some(Curve {
+            growth,
+            intercept: mean - slope * mean_x,
+            slope,
+        })
+    }
+
+    pub fn at(&self, size: usize) -> f64 {
+        self.intercept + self.slope * self.growth.apply(size as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measures(f: impl Fn(usize) -> i64) -> Vec<Measure> {
+        (0..=30)
+            .map(|size| Measure {
+                size,
+                mem: f(size),
+                cpu: 10 * f(size),
+            })
+            .collect()
+    }
+
+    fn points(f: impl Fn(usize) -> i64) -> Vec<(usize, i64)> {
+        (0..=30).map(|size| (size, f(size))).collect()
+    }
+
+    #[test]
+    fn fit_growth() {
+        let fit = |f: fn(usize) -> i64| Curve::fit(&points(f)).unwrap().growth;
+
+        assert_eq!(fit(|_| 1000), Growth::Constant);
+        assert_eq!(fit(|n| 100 + 42 * n as i64), Growth::Linear);
+        assert_eq!(fit(|n| 100 + 3 * (n * n) as i64), Growth::Quadratic);
+        assert_eq!(
+            fit(|n| 100 + (1000.0 * (n as f64 + 1.0).ln()) as i64),
+            Growth::Logarithmic
+        );
+        // Noise on top of a linear growth.
+        assert_eq!(
+            fit(|n| 100 + 42 * n as i64 + if n % 2 == 0 { 5 } else { -5 }),
+            Growth::Linear
+        );
+    }
+
+    #[test]
+    fn compare_regression() {
+        let baseline = Baseline(BTreeMap::from([(
+            "foo".to_string(),
+            BTreeMap::from([
+                ("linear".to_string(), measures(|n| 100 + 42 * n as i64)),
+                ("stable".to_string(), measures(|n| 100 + n as i64)),
+            ]),
+        )]));
+
+        let current = Baseline(BTreeMap::from([(
+            "foo".to_string(),
+            BTreeMap::from([
+                ("linear".to_string(), measures(|n| 100 + 3 * (n * n) as i64)),
+                ("stable".to_string(), measures(|n| 101 + n as i64)),
+                ("new".to_string(), measures(|n| n as i64)),
+            ]),
+        )]));
+
+        let report = baseline.compare("main", &current, DEFAULT_THRESHOLD);
+
+        assert_eq!(report.new, vec!["foo.new".to_string()]);
+        assert_eq!(report.comparisons.len(), 2);
+
+        let regressions = report.regressions().collect::<Vec<_>>();
+        assert_eq!(regressions.len(), 1);
+
+        let regression = regressions[0];
+        assert_eq!(regression.name, "linear");
+        assert_eq!(regression.size, 30);
+        assert_eq!(regression.mem.baseline.growth, Growth::Linear);
+        assert_eq!(regression.mem.current.growth, Growth::Quadratic);
+        assert!(regression.cpu.relative() > 0.5);
+    }
+}
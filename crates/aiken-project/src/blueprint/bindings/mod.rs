@@ -0,0 +1,368 @@
+//! Off-chain bindings generated from a blueprint.
+//!
+//! Data-types found in the blueprint's definitions are turned into native data structures of the
+//! target language, along with serializers from and to a minimal representation of Plutus data
+//! that is emitted alongside. Validators are emitted as constants holding their compiled code and
+//! hash.
+
+mod python;
+mod rust;
+mod typescript;
+
+use super::{
+    Blueprint,
+    definitions::Definitions,
+    schema::{Annotated, Data, Declaration, Items, Schema},
+    validator::Validator,
+};
+use std::collections::{BTreeMap, HashMap};
+use uplc::ast::SerializableProgram;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    TypeScript,
+    Rust,
+    Python,
+}
+
+/// Generate bindings in the given language, for all data-types of a blueprint and the validators
+/// matching the given predicate.
+pub fn generate(
+    blueprint: &Blueprint,
+    language: Language,
+    include: impl Fn(&Validator<SerializableProgram>) -> bool,
+) -> String {
+    let bindings = Bindings::new(blueprint, include);
+
+    match language {
+        Language::TypeScript => typescript::generate(&bindings),
+        Language::Rust => rust::generate(&bindings),
+        Language::Python => python::generate(&bindings),
+    }
+}
+
+/// A language-agnostic view of the bindings to generate.
+#[derive(Debug)]
+struct Bindings {
+    title: String,
+    records: Vec<Record>,
+    scripts: Vec<Script>,
+}
+
+/// The shape of values as seen off-chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Type {
+    Integer,
+    Bytes,
+    Bool,
+    Data,
+    List(Box<Type>),
+    Tuple(Vec<Type>),
+    Map(Box<Type>, Box<Type>),
+    Named(String),
+}
+
+/// A data-type made of one or more constructors.
+#[derive(Debug)]
+struct Record {
+    name: String,
+    description: Option<String>,
+    constructors: Vec<Constructor>,
+}
+
+#[derive(Debug)]
+struct Constructor {
+    name: String,
+    index: usize,
+    fields: Vec<Field>,
+}
+
+#[derive(Debug)]
+struct Field {
+    name: String,
+    tipo: Type,
+}
+
+#[derive(Debug)]
+struct Script {
+    name: String,
+    title: String,
+    compiled_code: String,
+    hash: String,
+}
+
+impl Record {
+    fn is_enum(&self) -> bool {
+        self.constructors.len() > 1
+    }
+}
+
+impl Bindings {
+    fn new(
+        blueprint: &Blueprint,
+        include: impl Fn(&Validator<SerializableProgram>) -> bool,
+    ) -> Self {
+        let mut definitions = blueprint.definitions.clone();
+        for validator in blueprint.validators.iter() {
+            definitions.merge(&mut validator.definitions.clone());
+        }
+
+        let names = type_names(&definitions);
+
+        let resolver = Resolver {
+            definitions: &definitions,
+            names: &names,
+        };
+
+        let records = definitions
+            .iter()
+            .filter_map(|(reference, schema)| {
+                let name = names.get(&reference.as_key())?;
+                match &schema.annotated {
+                    Schema::Data(Data::AnyOf(constructors)) => Some(Record {
+                        name: name.clone(),
+                        description: schema.description.clone(),
+                        constructors: constructors
+                            .iter()
+                            .map(|constructor| Constructor {
+                                name: match &constructor.title {
+                                    Some(title) => pascal_case(title),
+                                    None if constructors.len() == 1 => name.clone(),
+                                    None => format!("{name}{}", constructor.annotated.index),
+                                },
+                                index: constructor.annotated.index,
+                                fields: constructor
+                                    .annotated
+                                    .fields
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(ix, field)| Field {
+                                        name: match &field.title {
+                                            Some(title) => snake_case(title),
+                                            None => format!("field_{ix}"),
+                                        },
+                                        tipo: resolver.declaration(&field.annotated),
+                                    })
+                                    .collect(),
+                            })
+                            .collect(),
+                    }),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        let scripts = blueprint
+            .validators
+            .iter()
+            .filter(|validator| include(validator))
+            .map(|validator| {
+                let (hash, _) = validator.program.compiled_code_and_hash();
+                Script {
+                    name: screaming_snake_case(&validator.title),
+                    title: validator.title.clone(),
+                    compiled_code: hex::encode(validator.program.inner().to_cbor().unwrap()),
+                    hash: hash.to_string(),
+                }
+            })
+            .collect();
+
+        Bindings {
+            title: blueprint.preamble.title.clone(),
+            records,
+            scripts,
+        }
+    }
+}
+
+struct Resolver<'a> {
+    definitions: &'a Definitions<Annotated<Schema>>,
+    names: &'a HashMap<String, String>,
+}
+
+impl Resolver<'_> {
+    fn declaration(&self, declaration: &Declaration<Data>) -> Type {
+        match declaration {
+            Declaration::Inline(data) => self.data(data),
+            Declaration::Referenced(reference) => {
+                let key = reference.as_key();
+
+                if key == "Bool" {
+                    return Type::Bool;
+                }
+
+                if let Some(name) = self.names.get(&key) {
+                    return Type::Named(name.clone());
+                }
+
+                match self.definitions.try_lookup(reference).map(|s| &s.annotated) {
+                    Some(Schema::Data(data)) => self.data(data),
+                    Some(Schema::Integer) => Type::Integer,
+                    Some(Schema::Bytes) => Type::Bytes,
+                    _ => Type::Data,
+                }
+            }
+        }
+    }
+
+    fn data(&self, data: &Data) -> Type {
+        match data {
+            Data::Integer => Type::Integer,
+            Data::Bytes => Type::Bytes,
+            Data::List(Items::One(item)) => Type::List(Box::new(self.declaration(item))),
+            Data::List(Items::Many(items)) => Type::Tuple(
+                items
+                    .iter()
+                    .map(|item| self.declaration(&item.annotated))
+                    .collect(),
+            ),
+            Data::Map(keys, values) => Type::Map(
+                Box::new(self.declaration(keys)),
+                Box::new(self.declaration(values)),
+            ),
+            // NOTE: Constructors are always defined by reference, except for the odd wrapped
+            // redeemer which we needn't expose.
+            Data::AnyOf(..) | Data::Opaque => Type::Data,
+        }
+    }
+}
+
+/// Assign a name to every definition made of constructors. Names are derived from the type name
+/// and its arguments (e.g. `Option<aiken/interval/Interval>` becomes `OptionInterval`), and
+/// qualified by their module when ambiguous.
+fn type_names(definitions: &Definitions<Annotated<Schema>>) -> HashMap<String, String> {
+    let keys = definitions
+        .iter()
+        .filter(|(reference, schema)| {
+            reference.as_key() != "Bool"
+                && matches!(schema.annotated, Schema::Data(Data::AnyOf(..)))
+        })
+        .map(|(reference, _)| reference.as_key())
+        .collect::<Vec<_>>();
+
+    let mut occurrences: BTreeMap<String, usize> = BTreeMap::new();
+    for key in keys.iter() {
+        *occurrences.entry(type_name(key, false)).or_default() += 1;
+    }
+
+    keys.into_iter()
+        .map(|key| {
+            let name = type_name(&key, false);
+            if occurrences[&name] > 1 {
+                (key.clone(), type_name(&key, true))
+            } else {
+                (key, name)
+            }
+        })
+        .collect()
+}
+
+fn type_name(key: &str, qualified: bool) -> String {
+    let (head, args) = match key.split_once('<') {
+        Some((head, args)) => (head, args.strip_suffix('>').unwrap_or(args)),
+        None => (key, ""),
+    };
+
+    let mut name = if qualified {
+        head.split('/').map(pascal_case).collect::<String>()
+    } else {
+        pascal_case(head.rsplit('/').next().unwrap_or(head))
+    };
+
+    for arg in split_arguments(args) {
+        name.push_str(&type_name(arg, qualified));
+    }
+
+    name
+}
+
+/// Split type arguments on top-level commas.
+fn split_arguments(args: &str) -> Vec<&str> {
+    let mut depth = 0;
+    let mut start = 0;
+    let mut result = vec![];
+
+    for (ix, c) in args.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                result.push(&args[start..ix]);
+                start = ix + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if start < args.len() {
+        result.push(&args[start..]);
+    }
+
+    result
+}
+
+fn words(s: &str) -> impl Iterator<Item = &str> {
+    s.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+}
+
+fn pascal_case(s: &str) -> String {
+    words(s)
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(head) => head.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn snake_case(s: &str) -> String {
+    words(s).collect::<Vec<_>>().join("_")
+}
+
+fn screaming_snake_case(s: &str) -> String {
+    words(s)
+        .map(|word| word.to_ascii_uppercase())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blueprint() -> Blueprint {
+        serde_json::from_str(include_str!("fixtures/registry.json")).unwrap()
+    }
+
+    #[test]
+    fn type_names_from_references() {
+        assert_eq!(type_name("hello_world/Datum", false), "Datum");
+        assert_eq!(type_name("hello_world/Datum", true), "HelloWorldDatum");
+        assert_eq!(
+            type_name("Option<aiken/interval/Interval<Int>>", false),
+            "OptionIntervalInt"
+        );
+        assert_eq!(
+            type_name("Pairs<ByteArray,List<Int>>", false),
+            "PairsByteArrayListInt"
+        );
+    }
+
+    #[test]
+    fn typescript() {
+        insta::assert_snapshot!(generate(&blueprint(), Language::TypeScript, |_| true));
+    }
+
+    #[test]
+    fn rust() {
+        insta::assert_snapshot!(generate(&blueprint(), Language::Rust, |_| true));
+    }
+
+    #[test]
+    fn python() {
+        insta::assert_snapshot!(generate(&blueprint(), Language::Python, |_| true));
+    }
+}
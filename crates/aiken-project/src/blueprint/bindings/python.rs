@@ -0,0 +1,285 @@
+use super::{Bindings, Constructor, Record, Type};
+use std::fmt::Write;
+
+const PRELUDE: &str = r#"from __future__ import annotations
+
+from dataclasses import dataclass
+from typing import List, Tuple, Union
+
+
+@dataclass(frozen=True)
+class Constr:
+    index: int
+    fields: List[PlutusData]
+
+
+@dataclass(frozen=True)
+class Map:
+    entries: List[Tuple[PlutusData, PlutusData]]
+
+
+PlutusData = Union[Constr, int, bytes, List["PlutusData"], Map]
+
+
+@dataclass(frozen=True)
+class Validator:
+    title: str
+    compiled_code: str
+    hash: str
+
+
+def _expect_constr(data: PlutusData, type_name: str) -> Constr:
+    if isinstance(data, Constr):
+        return data
+    raise ValueError(f"expected a constructor for {type_name}, got: {data!r}")
+
+
+def _expect_int(data: PlutusData) -> int:
+    if isinstance(data, int):
+        return data
+    raise ValueError(f"expected an integer, got: {data!r}")
+
+
+def _expect_bytes(data: PlutusData) -> bytes:
+    if isinstance(data, bytes):
+        return data
+    raise ValueError(f"expected bytes, got: {data!r}")
+
+
+def _expect_bool(data: PlutusData) -> bool:
+    constr = _expect_constr(data, "Bool")
+    if constr.index in (0, 1) and not constr.fields:
+        return constr.index == 1
+    raise ValueError(f"expected a boolean, got: {data!r}")
+
+
+def _expect_list(data: PlutusData) -> List[PlutusData]:
+    if isinstance(data, list):
+        return data
+    raise ValueError(f"expected a list, got: {data!r}")
+
+
+def _expect_tuple(data: PlutusData, arity: int) -> List[PlutusData]:
+    items = _expect_list(data)
+    if len(items) == arity:
+        return items
+    raise ValueError(f"expected a tuple of {arity} elements, got: {data!r}")
+
+
+def _expect_map(data: PlutusData) -> List[Tuple[PlutusData, PlutusData]]:
+    if isinstance(data, Map):
+        return data.entries
+    raise ValueError(f"expected a map, got: {data!r}")
+"#;
+
+pub fn generate(bindings: &Bindings) -> String {
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "# Generated by Aiken from the blueprint of '{}'. Do not edit by hand.\n",
+        bindings.title
+    )
+    .unwrap();
+
+    out.push_str(PRELUDE);
+
+    for record in bindings.records.iter() {
+        if record.is_enum() {
+            enum_record(&mut out, record);
+        } else {
+            out.push_str("\n\n@dataclass(frozen=True)\n");
+            writeln!(out, "class {}:", record.name).unwrap();
+            docstring(&mut out, record);
+            constructor_class(&mut out, &record.constructors[0]);
+            from_data(&mut out, record);
+        }
+    }
+
+    for script in bindings.scripts.iter() {
+        writeln!(
+            out,
+            "\n\n{} = Validator(\n    title=\"{}\",\n    compiled_code=\"{}\",\n    hash=\"{}\",\n)",
+            script.name, script.title, script.compiled_code, script.hash
+        )
+        .unwrap();
+    }
+
+    out
+}
+
+/// Enums are made of a base class, holding the deserializer, and one subclass per constructor.
+fn enum_record(out: &mut String, record: &Record) {
+    writeln!(out, "\n\nclass {}:", record.name).unwrap();
+    docstring(out, record);
+    writeln!(
+        out,
+        "    def to_data(self) -> PlutusData:\n        raise NotImplementedError"
+    )
+    .unwrap();
+    from_data(out, record);
+
+    for constructor in record.constructors.iter() {
+        out.push_str("\n\n@dataclass(frozen=True)\n");
+        writeln!(
+            out,
+            "class {}({}):",
+            class_name(record, constructor),
+            record.name
+        )
+        .unwrap();
+        constructor_class(out, constructor);
+    }
+}
+
+fn docstring(out: &mut String, record: &Record) {
+    if let Some(description) = &record.description {
+        writeln!(out, "    \"\"\"{description}\"\"\"\n").unwrap();
+    }
+}
+
+fn class_name(record: &Record, constructor: &Constructor) -> String {
+    if record.is_enum() {
+        format!("{}{}", record.name, constructor.name)
+    } else {
+        record.name.clone()
+    }
+}
+
+fn constructor_class(out: &mut String, constructor: &Constructor) {
+    for field in constructor.fields.iter() {
+        writeln!(out, "    {}: {}", field.name, type_of(&field.tipo)).unwrap();
+    }
+
+    if !constructor.fields.is_empty() {
+        out.push('\n');
+    }
+
+    writeln!(
+        out,
+        "    def to_data(self) -> PlutusData:\n        return Constr({}, [{}])",
+        constructor.index,
+        constructor
+            .fields
+            .iter()
+            .map(|field| to_data(&field.tipo, &format!("self.{}", field.name), 0))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+    .unwrap();
+}
+
+fn from_data(out: &mut String, record: &Record) {
+    let name = &record.name;
+
+    writeln!(
+        out,
+        "\n    @staticmethod\n    def from_data(data: PlutusData) -> {name}:"
+    )
+    .unwrap();
+    writeln!(out, "        constr = _expect_constr(data, \"{name}\")").unwrap();
+
+    for constructor in record.constructors.iter() {
+        writeln!(
+            out,
+            "        if constr.index == {} and len(constr.fields) == {}:",
+            constructor.index,
+            constructor.fields.len()
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "            return {}({})",
+            class_name(record, constructor),
+            constructor
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(ix, field)| self::from_data_expr(
+                    &field.tipo,
+                    &format!("constr.fields[{ix}]"),
+                    0
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+        .unwrap();
+    }
+
+    writeln!(
+        out,
+        "        raise ValueError(f\"invalid {name}: {{data!r}}\")"
+    )
+    .unwrap();
+}
+
+fn type_of(tipo: &Type) -> String {
+    match tipo {
+        Type::Integer => "int".to_string(),
+        Type::Bytes => "bytes".to_string(),
+        Type::Bool => "bool".to_string(),
+        Type::Data => "PlutusData".to_string(),
+        Type::List(item) => format!("List[{}]", type_of(item)),
+        Type::Tuple(items) => format!(
+            "Tuple[{}]",
+            items.iter().map(type_of).collect::<Vec<_>>().join(", ")
+        ),
+        Type::Map(key, value) => format!("List[Tuple[{}, {}]]", type_of(key), type_of(value)),
+        Type::Named(name) => name.clone(),
+    }
+}
+
+fn to_data(tipo: &Type, value: &str, depth: usize) -> String {
+    match tipo {
+        Type::Integer | Type::Bytes | Type::Data => value.to_string(),
+        Type::Bool => format!("Constr(1 if {value} else 0, [])"),
+        Type::List(item) => format!(
+            "[{} for x{depth} in {value}]",
+            to_data(item, &format!("x{depth}"), depth + 1)
+        ),
+        Type::Tuple(items) => format!(
+            "[{}]",
+            items
+                .iter()
+                .enumerate()
+                .map(|(ix, item)| to_data(item, &format!("{value}[{ix}]"), depth))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Type::Map(key, val) => format!(
+            "Map([({}, {}) for (k{depth}, v{depth}) in {value}])",
+            to_data(key, &format!("k{depth}"), depth + 1),
+            to_data(val, &format!("v{depth}"), depth + 1)
+        ),
+        Type::Named(..) => format!("{value}.to_data()"),
+    }
+}
+
+fn from_data_expr(tipo: &Type, data: &str, depth: usize) -> String {
+    match tipo {
+        Type::Integer => format!("_expect_int({data})"),
+        Type::Bytes => format!("_expect_bytes({data})"),
+        Type::Bool => format!("_expect_bool({data})"),
+        Type::Data => data.to_string(),
+        Type::List(item) => format!(
+            "[{} for x{depth} in _expect_list({data})]",
+            from_data_expr(item, &format!("x{depth}"), depth + 1)
+        ),
+        Type::Tuple(items) => format!(
+            "(lambda xs{depth}: ({},))(_expect_tuple({data}, {}))",
+            items
+                .iter()
+                .enumerate()
+                .map(|(ix, item)| from_data_expr(item, &format!("xs{depth}[{ix}]"), depth + 1))
+                .collect::<Vec<_>>()
+                .join(", "),
+            items.len()
+        ),
+        Type::Map(key, val) => format!(
+            "[({}, {}) for (k{depth}, v{depth}) in _expect_map({data})]",
+            from_data_expr(key, &format!("k{depth}"), depth + 1),
+            from_data_expr(val, &format!("v{depth}"), depth + 1)
+        ),
+        Type::Named(name) => format!("{name}.from_data({data})"),
+    }
+}
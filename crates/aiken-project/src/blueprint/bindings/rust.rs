@@ -0,0 +1,362 @@
+use super::{Bindings, Constructor, Record, Type};
+use std::fmt::Write;
+
+const PRELUDE: &str = r#"use num_bigint::BigInt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlutusData {
+    Constr(u64, Vec<PlutusData>),
+    Int(BigInt),
+    Bytes(Vec<u8>),
+    List(Vec<PlutusData>),
+    Map(Vec<(PlutusData, PlutusData)>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError(pub String);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Validator {
+    pub title: &'static str,
+    pub compiled_code: &'static str,
+    pub hash: &'static str,
+}
+
+#[allow(dead_code)]
+fn expect_constr<'a>(data: &'a PlutusData, type_name: &str) -> Result<(u64, &'a [PlutusData]), DecodeError> {
+    match data {
+        PlutusData::Constr(index, fields) => Ok((*index, fields)),
+        _ => Err(DecodeError(format!("expected a constructor for {type_name}, got: {data:?}"))),
+    }
+}
+
+#[allow(dead_code)]
+fn expect_int(data: &PlutusData) -> Result<BigInt, DecodeError> {
+    match data {
+        PlutusData::Int(n) => Ok(n.clone()),
+        _ => Err(DecodeError(format!("expected an integer, got: {data:?}"))),
+    }
+}
+
+#[allow(dead_code)]
+fn expect_bytes(data: &PlutusData) -> Result<Vec<u8>, DecodeError> {
+    match data {
+        PlutusData::Bytes(bytes) => Ok(bytes.clone()),
+        _ => Err(DecodeError(format!("expected bytes, got: {data:?}"))),
+    }
+}
+
+#[allow(dead_code)]
+fn expect_bool(data: &PlutusData) -> Result<bool, DecodeError> {
+    match expect_constr(data, "Bool")? {
+        (0, []) => Ok(false),
+        (1, []) => Ok(true),
+        _ => Err(DecodeError(format!("expected a boolean, got: {data:?}"))),
+    }
+}
+
+#[allow(dead_code)]
+fn expect_list(data: &PlutusData) -> Result<&[PlutusData], DecodeError> {
+    match data {
+        PlutusData::List(items) => Ok(items),
+        _ => Err(DecodeError(format!("expected a list, got: {data:?}"))),
+    }
+}
+
+#[allow(dead_code)]
+fn expect_tuple(data: &PlutusData, arity: usize) -> Result<&[PlutusData], DecodeError> {
+    match expect_list(data)? {
+        items if items.len() == arity => Ok(items),
+        _ => Err(DecodeError(format!("expected a tuple of {arity} elements, got: {data:?}"))),
+    }
+}
+
+#[allow(dead_code)]
+fn expect_map(data: &PlutusData) -> Result<&[(PlutusData, PlutusData)], DecodeError> {
+    match data {
+        PlutusData::Map(entries) => Ok(entries),
+        _ => Err(DecodeError(format!("expected a map, got: {data:?}"))),
+    }
+}
+"#;
+
+pub fn generate(bindings: &Bindings) -> String {
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "// Generated by Aiken from the blueprint of '{}'. Do not edit by hand.\n//\n// Plutus integers are unbounded, and thus represented as 'num_bigint::BigInt': this code\n// depends on the 'num-bigint' crate.\n",
+        bindings.title
+    )
+    .unwrap();
+
+    out.push_str(PRELUDE);
+
+    for record in bindings.records.iter() {
+        out.push('\n');
+        record_type(&mut out, record);
+        out.push('\n');
+        record_serializers(&mut out, record);
+    }
+
+    for script in bindings.scripts.iter() {
+        writeln!(
+            out,
+            "\npub const {}: Validator = Validator {{\n    title: \"{}\",\n    compiled_code: \"{}\",\n    hash: \"{}\",\n}};",
+            script.name, script.title, script.compiled_code, script.hash
+        )
+        .unwrap();
+    }
+
+    out
+}
+
+fn record_type(out: &mut String, record: &Record) {
+    if let Some(description) = &record.description {
+        writeln!(out, "/// {description}").unwrap();
+    }
+
+    writeln!(out, "#[derive(Debug, Clone, PartialEq, Eq)]").unwrap();
+
+    if record.is_enum() {
+        writeln!(out, "pub enum {} {{", record.name).unwrap();
+        for constructor in record.constructors.iter() {
+            if constructor.fields.is_empty() {
+                writeln!(out, "    {},", constructor.name).unwrap();
+            } else {
+                writeln!(out, "    {} {{", constructor.name).unwrap();
+                fields(out, record, constructor, "        ");
+                writeln!(out, "    }},").unwrap();
+            }
+        }
+    } else {
+        writeln!(out, "pub struct {} {{", record.name).unwrap();
+        fields(out, record, &record.constructors[0], "    pub ");
+    }
+
+    writeln!(out, "}}").unwrap();
+}
+
+fn fields(out: &mut String, record: &Record, constructor: &Constructor, prefix: &str) {
+    for field in constructor.fields.iter() {
+        writeln!(
+            out,
+            "{prefix}{}: {},",
+            identifier(&field.name),
+            field_type(record, &field.tipo)
+        )
+        .unwrap();
+    }
+}
+
+fn record_serializers(out: &mut String, record: &Record) {
+    writeln!(out, "impl {} {{", record.name).unwrap();
+
+    writeln!(out, "    pub fn to_data(&self) -> PlutusData {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    for constructor in record.constructors.iter() {
+        let pattern = pattern(record, constructor);
+        let fields = constructor
+            .fields
+            .iter()
+            .map(|field| to_data(&field.tipo, &identifier(&field.name), 0))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(
+            out,
+            "            {pattern} => PlutusData::Constr({}, vec![{fields}]),",
+            constructor.index
+        )
+        .unwrap();
+    }
+    writeln!(out, "        }}").unwrap();
+    writeln!(out, "    }}\n").unwrap();
+
+    writeln!(
+        out,
+        "    pub fn from_data(data: &PlutusData) -> Result<Self, DecodeError> {{"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "        match expect_constr(data, \"{}\")? {{",
+        record.name
+    )
+    .unwrap();
+    for constructor in record.constructors.iter() {
+        let bindings = (0..constructor.fields.len())
+            .map(|ix| format!("f{ix}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let fields = constructor
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(ix, field)| {
+                let value = format!("{}?", from_data(&field.tipo, &format!("f{ix}"), 0));
+                let value = if is_boxed(record, &field.tipo) {
+                    format!("Box::new({value})")
+                } else {
+                    value
+                };
+                format!("{}: {value}", identifier(&field.name))
+            })
+            .collect();
+
+        writeln!(
+            out,
+            "            ({}, [{bindings}]) => Ok({}),",
+            constructor.index,
+            braced(&path(record, constructor), fields),
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "            _ => Err(DecodeError(format!(\"invalid {}: {{data:?}}\"))),",
+        record.name
+    )
+    .unwrap();
+    writeln!(out, "        }}").unwrap();
+    writeln!(out, "    }}").unwrap();
+
+    writeln!(out, "}}").unwrap();
+}
+
+fn path(record: &Record, constructor: &Constructor) -> String {
+    if record.is_enum() {
+        format!("Self::{}", constructor.name)
+    } else {
+        "Self".to_string()
+    }
+}
+
+fn pattern(record: &Record, constructor: &Constructor) -> String {
+    braced(
+        &path(record, constructor),
+        constructor
+            .fields
+            .iter()
+            .map(|field| identifier(&field.name))
+            .collect(),
+    )
+}
+
+fn braced(head: &str, fields: Vec<String>) -> String {
+    if fields.is_empty() {
+        format!("{head} {{}}")
+    } else {
+        format!("{head} {{ {} }}", fields.join(", "))
+    }
+}
+
+/// Direct self-references must be boxed to give the type a known size.
+fn is_boxed(record: &Record, tipo: &Type) -> bool {
+    matches!(tipo, Type::Named(name) if name == &record.name)
+}
+
+fn field_type(record: &Record, tipo: &Type) -> String {
+    if is_boxed(record, tipo) {
+        format!("Box<{}>", type_of(tipo))
+    } else {
+        type_of(tipo)
+    }
+}
+
+fn type_of(tipo: &Type) -> String {
+    match tipo {
+        Type::Integer => "BigInt".to_string(),
+        Type::Bytes => "Vec<u8>".to_string(),
+        Type::Bool => "bool".to_string(),
+        Type::Data => "PlutusData".to_string(),
+        Type::List(item) => format!("Vec<{}>", type_of(item)),
+        Type::Tuple(items) => format!(
+            "({})",
+            items.iter().map(type_of).collect::<Vec<_>>().join(", ")
+        ),
+        Type::Map(key, value) => format!("Vec<({}, {})>", type_of(key), type_of(value)),
+        Type::Named(name) => name.clone(),
+    }
+}
+
+/// Serialize a value, given by reference.
+fn to_data(tipo: &Type, value: &str, depth: usize) -> String {
+    match tipo {
+        Type::Integer => format!("PlutusData::Int({value}.clone())"),
+        Type::Bytes => format!("PlutusData::Bytes({value}.to_vec())"),
+        Type::Bool => format!("PlutusData::Constr(if *{value} {{ 1 }} else {{ 0 }}, vec![])"),
+        Type::Data => format!("{value}.clone()"),
+        Type::List(item) => format!(
+            "PlutusData::List({value}.iter().map(|x{depth}| {}).collect())",
+            to_data(item, &format!("x{depth}"), depth + 1)
+        ),
+        Type::Tuple(items) => format!(
+            "{{ let ({}) = {value}; PlutusData::List(vec![{}]) }}",
+            (0..items.len())
+                .map(|ix| format!("t{depth}_{ix}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            items
+                .iter()
+                .enumerate()
+                .map(|(ix, item)| to_data(item, &format!("t{depth}_{ix}"), depth + 1))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Type::Map(key, val) => format!(
+            "PlutusData::Map({value}.iter().map(|(k{depth}, v{depth})| ({}, {})).collect())",
+            to_data(key, &format!("k{depth}"), depth + 1),
+            to_data(val, &format!("v{depth}"), depth + 1)
+        ),
+        Type::Named(..) => format!("{value}.to_data()"),
+    }
+}
+
+/// Deserialize some data, given by reference, into a `Result`.
+fn from_data(tipo: &Type, data: &str, depth: usize) -> String {
+    match tipo {
+        Type::Integer => format!("expect_int({data})"),
+        Type::Bytes => format!("expect_bytes({data})"),
+        Type::Bool => format!("expect_bool({data})"),
+        Type::Data => format!("Ok::<_, DecodeError>({data}.clone())"),
+        Type::List(item) => format!(
+            "expect_list({data}).and_then(|xs{depth}| xs{depth}.iter().map(|x{depth}| {}).collect::<Result<Vec<_>, _>>())",
+            from_data(item, &format!("x{depth}"), depth + 1)
+        ),
+        Type::Tuple(items) => format!(
+            "expect_tuple({data}, {}).and_then(|xs{depth}| Ok(({},)))",
+            items.len(),
+            items
+                .iter()
+                .enumerate()
+                .map(|(ix, item)| format!(
+                    "{}?",
+                    from_data(item, &format!("&xs{depth}[{ix}]"), depth + 1)
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Type::Map(key, val) => format!(
+            "expect_map({data}).and_then(|kvs{depth}| kvs{depth}.iter().map(|(k{depth}, v{depth})| Ok(({}?, {}?))).collect::<Result<Vec<_>, DecodeError>>())",
+            from_data(key, &format!("k{depth}"), depth + 1),
+            from_data(val, &format!("v{depth}"), depth + 1)
+        ),
+        Type::Named(name) => format!("{name}::from_data({data})"),
+    }
+}
+
+fn identifier(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else",
+        "enum", "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+        "move", "mut", "pub", "ref", "return", "static", "struct", "super", "trait", "true",
+        "type", "unsafe", "use", "where", "while", "yield",
+    ];
+
+    if KEYWORDS.contains(&name) {
+        format!("r#{name}")
+    } else {
+        name.to_string()
+    }
+}
@@ -0,0 +1,257 @@
+use super::{Bindings, Record, Type};
+use std::fmt::Write;
+
+const PRELUDE: &str = r#"export type PlutusData =
+  | { constructor: number; fields: PlutusData[] }
+  | { int: bigint }
+  | { bytes: string }
+  | { list: PlutusData[] }
+  | { map: [PlutusData, PlutusData][] };
+
+function expectConstr(data: PlutusData, type: string): { constructor: number; fields: PlutusData[] } {
+  if ("fields" in data) return data;
+  throw new Error(`expected a constructor for ${type}, got: ${JSON.stringify(data)}`);
+}
+
+function expectInt(data: PlutusData): bigint {
+  if ("int" in data) return data.int;
+  throw new Error("expected an integer");
+}
+
+function expectBytes(data: PlutusData): string {
+  if ("bytes" in data) return data.bytes;
+  throw new Error("expected bytes");
+}
+
+function expectBool(data: PlutusData): boolean {
+  const { constructor, fields } = expectConstr(data, "Bool");
+  if (constructor <= 1 && fields.length === 0) return constructor === 1;
+  throw new Error("expected a boolean");
+}
+
+function expectList(data: PlutusData): PlutusData[] {
+  if ("list" in data) return data.list;
+  throw new Error("expected a list");
+}
+
+function expectTuple(data: PlutusData, arity: number): PlutusData[] {
+  const items = expectList(data);
+  if (items.length === arity) return items;
+  throw new Error(`expected a tuple of ${arity} elements`);
+}
+
+function expectMap(data: PlutusData): [PlutusData, PlutusData][] {
+  if ("map" in data) return data.map;
+  throw new Error("expected a map");
+}
+"#;
+
+pub fn generate(bindings: &Bindings) -> String {
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "// Generated by Aiken from the blueprint of '{}'. Do not edit by hand.\n",
+        bindings.title
+    )
+    .unwrap();
+
+    out.push_str(PRELUDE);
+
+    for record in bindings.records.iter() {
+        out.push('\n');
+        record_type(&mut out, record);
+        out.push('\n');
+        record_serializers(&mut out, record);
+    }
+
+    for script in bindings.scripts.iter() {
+        writeln!(
+            out,
+            "\nexport const {} = {{\n  title: \"{}\",\n  compiledCode: \"{}\",\n  hash: \"{}\",\n}} as const;",
+            script.name, script.title, script.compiled_code, script.hash
+        )
+        .unwrap();
+    }
+
+    out
+}
+
+fn record_type(out: &mut String, record: &Record) {
+    if let Some(description) = &record.description {
+        writeln!(out, "/** {description} */").unwrap();
+    }
+
+    write!(out, "export type {} =", record.name).unwrap();
+
+    for constructor in record.constructors.iter() {
+        if record.is_enum() {
+            write!(out, "\n  | {{ kind: \"{}\"", constructor.name).unwrap();
+        } else {
+            out.push_str(" {");
+        }
+
+        for (ix, field) in constructor.fields.iter().enumerate() {
+            let separator = if ix == 0 && !record.is_enum() {
+                " "
+            } else {
+                "; "
+            };
+            write!(out, "{separator}{}: {}", field.name, type_of(&field.tipo)).unwrap();
+        }
+
+        out.push_str(" }");
+    }
+
+    out.push_str(";\n");
+}
+
+fn record_serializers(out: &mut String, record: &Record) {
+    let name = &record.name;
+
+    writeln!(out, "export const {name} = {{").unwrap();
+
+    writeln!(out, "  toData(value: {name}): PlutusData {{").unwrap();
+    if record.is_enum() {
+        writeln!(out, "    switch (value.kind) {{").unwrap();
+    }
+    for constructor in record.constructors.iter() {
+        let fields = constructor
+            .fields
+            .iter()
+            .map(|field| to_data(&field.tipo, &format!("value.{}", field.name), 0))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let indent = if record.is_enum() {
+            writeln!(out, "      case \"{}\":", constructor.name).unwrap();
+            "        "
+        } else {
+            "    "
+        };
+
+        writeln!(
+            out,
+            "{indent}return {{ constructor: {}, fields: [{fields}] }};",
+            constructor.index
+        )
+        .unwrap();
+    }
+    if record.is_enum() {
+        writeln!(out, "    }}").unwrap();
+    }
+    writeln!(out, "  }},").unwrap();
+
+    writeln!(out, "  fromData(data: PlutusData): {name} {{").unwrap();
+    writeln!(
+        out,
+        "    const {{ constructor, fields }} = expectConstr(data, \"{name}\");"
+    )
+    .unwrap();
+    for constructor in record.constructors.iter() {
+        let mut value = vec![];
+        if record.is_enum() {
+            value.push(format!("kind: \"{}\"", constructor.name));
+        }
+        for (ix, field) in constructor.fields.iter().enumerate() {
+            value.push(format!(
+                "{}: {}",
+                field.name,
+                from_data(&field.tipo, &format!("fields[{ix}]"), 0)
+            ));
+        }
+
+        writeln!(
+            out,
+            "    if (constructor === {} && fields.length === {}) {{\n      return {{ {} }};\n    }}",
+            constructor.index,
+            constructor.fields.len(),
+            value.join(", ")
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "    throw new Error(`invalid {name}: ${{JSON.stringify(data)}}`);"
+    )
+    .unwrap();
+    writeln!(out, "  }},").unwrap();
+
+    writeln!(out, "}};").unwrap();
+}
+
+fn type_of(tipo: &Type) -> String {
+    match tipo {
+        Type::Integer => "bigint".to_string(),
+        Type::Bytes => "string".to_string(),
+        Type::Bool => "boolean".to_string(),
+        Type::Data => "PlutusData".to_string(),
+        Type::List(item) => format!("Array<{}>", type_of(item)),
+        Type::Tuple(items) => format!(
+            "[{}]",
+            items.iter().map(type_of).collect::<Vec<_>>().join(", ")
+        ),
+        Type::Map(key, value) => format!("Array<[{}, {}]>", type_of(key), type_of(value)),
+        Type::Named(name) => name.clone(),
+    }
+}
+
+fn to_data(tipo: &Type, value: &str, depth: usize) -> String {
+    match tipo {
+        Type::Integer => format!("{{ int: {value} }}"),
+        Type::Bytes => format!("{{ bytes: {value} }}"),
+        Type::Bool => format!("{{ constructor: {value} ? 1 : 0, fields: [] }}"),
+        Type::Data => value.to_string(),
+        Type::List(item) => format!(
+            "{{ list: {value}.map((x{depth}) => ({})) }}",
+            to_data(item, &format!("x{depth}"), depth + 1)
+        ),
+        Type::Tuple(items) => format!(
+            "{{ list: [{}] }}",
+            items
+                .iter()
+                .enumerate()
+                .map(|(ix, item)| to_data(item, &format!("{value}[{ix}]"), depth))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Type::Map(key, val) => format!(
+            "{{ map: {value}.map(([k{depth}, v{depth}]) => [{}, {}] as [PlutusData, PlutusData]) }}",
+            to_data(key, &format!("k{depth}"), depth + 1),
+            to_data(val, &format!("v{depth}"), depth + 1)
+        ),
+        Type::Named(name) => format!("{name}.toData({value})"),
+    }
+}
+
+fn from_data(tipo: &Type, data: &str, depth: usize) -> String {
+    match tipo {
+        Type::Integer => format!("expectInt({data})"),
+        Type::Bytes => format!("expectBytes({data})"),
+        Type::Bool => format!("expectBool({data})"),
+        Type::Data => data.to_string(),
+        Type::List(item) => format!(
+            "expectList({data}).map((x{depth}) => {})",
+            from_data(item, &format!("x{depth}"), depth + 1)
+        ),
+        Type::Tuple(items) => format!(
+            "((xs{depth}) => [{}] as {})(expectTuple({data}, {}))",
+            items
+                .iter()
+                .enumerate()
+                .map(|(ix, item)| from_data(item, &format!("xs{depth}[{ix}]"), depth + 1))
+                .collect::<Vec<_>>()
+                .join(", "),
+            type_of(tipo),
+            items.len()
+        ),
+        Type::Map(key, val) => format!(
+            "expectMap({data}).map(([k{depth}, v{depth}]) => [{}, {}] as [{}, {}])",
+            from_data(key, &format!("k{depth}"), depth + 1),
+            from_data(val, &format!("v{depth}"), depth + 1),
+            type_of(key),
+            type_of(val)
+        ),
+        Type::Named(name) => format!("{name}.fromData({data})"),
+    }
+}
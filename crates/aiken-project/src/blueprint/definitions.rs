@@ -56,6 +56,13 @@ impl<T> Definitions<T> {
         self.inner.get(&reference.as_key()).and_then(|v| v.as_ref())
     }
 
+    /// Iterate over all resolved definitions, ordered by reference.
+    pub fn iter(&self) -> impl Iterator<Item = (Reference, &T)> {
+        self.inner
+            .iter()
+            .filter_map(|(key, value)| Some((Reference::new(key), value.as_ref()?)))
+    }
+
     /// Merge two set of definitions together. Prioritize callee.
     pub fn merge(&mut self, other: &mut Definitions<T>) {
         self.inner.append(&mut other.inner);
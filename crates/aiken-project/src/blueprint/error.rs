@@ -142,6 +142,47 @@ pub enum Error {
     #[diagnostic(code("aiken::blueprint::parse::parameter"))]
     #[diagnostic(help("{hint}"))]
     MalformedParameter { hint: String },
+
+    #[error("I can't fuzz the given validator handler.")]
+    #[diagnostic(code("aiken::blueprint::fuzz::unsupported"))]
+    #[diagnostic(help("{hint}"))]
+    UnsupportedFuzzTarget { hint: String },
+
+    #[error(
+        "I found {} suspicious input(s) while fuzzing.",
+        findings.if_supports_color(Stdout, |s| s.red())
+    )]
+    #[diagnostic(code("aiken::blueprint::fuzz::findings"))]
+    #[diagnostic(help(
+        "Inputs made up from a handler's schemas alone, in a transaction that neither signs nor pays anything, shouldn't be accepted; and a handler shouldn't crash on them with anything else than an explicit failure (e.g. 'fail' or a failed 'expect'). Both usually point at a missing check."
+    ))]
+    FuzzFindings { findings: usize },
+
+    #[error(
+        "The validator {} compiles to {} bytes, more than the {} allowed.",
+        script.validator.if_supports_color(Stdout, |s| s.bright_blue()),
+        script.size.if_supports_color(Stdout, |s| s.red()),
+        script.limit.if_supports_color(Stdout, |s| s.green()),
+    )]
+    #[diagnostic(code("aiken::blueprint::size"))]
+    #[diagnostic(help("{}", hint_size(script)))]
+    ScriptTooLarge {
+        script: Box<OversizedScript>,
+        #[label("too large")]
+        location: Span,
+        #[source_code]
+        source_code: NamedSource<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct OversizedScript {
+    pub validator: String,
+    pub size: usize,
+    pub limit: usize,
+    /// The definitions and constants contributing the most bytes, along with how many.
+    pub definitions: Vec<(String, usize)>,
+    pub constants: Vec<(String, usize)>,
 }
 
 unsafe impl Send for Error {}
@@ -193,3 +234,49 @@ fn hint_validators(known_validators: &BTreeSet<(String, String, bool)>, hint: &s
             .if_supports_color(Stderr, |s| s.green()),
     )
 }
+
+fn hint_size(
+    OversizedScript {
+        definitions,
+        constants,
+        ..
+    }: &OversizedScript,
+) -> String {
+    let section = |title: &str, entries: &[(String, usize)]| {
+        if entries.is_empty() {
+            return String::new();
+        }
+
+        let pad = entries
+            .iter()
+            .map(|(_, size)| size.to_string().len())
+            .max()
+            .unwrap_or_default();
+
+        format!(
+            "{title}\n\n{}\n\n",
+            entries
+                .iter()
+                .map(|(name, size)| format!(
+                    "  {size:>pad$} byte{}  {}",
+                    if *size == 1 { " " } else { "s" },
+                    name.if_supports_color(Stderr, |s| s.bright_blue())
+                ))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    };
+
+    format!(
+        "{}{}Scripts must fit in transactions to be used, whether as reference scripts or not. The limit is set by '{max_script_size}' under the [build] section of aiken.toml.",
+        section(
+            "Definitions taking the most space, not counting those they hold:",
+            definitions
+        ),
+        section(
+            "Constants taking the most space, across all their occurrences:",
+            constants
+        ),
+        max_script_size = "max_script_size".if_supports_color(Stderr, |s| s.yellow()),
+    )
+}
@@ -0,0 +1,908 @@
+use super::{
+    definitions::Definitions,
+    error::Error,
+    parameter::Parameter,
+    schema::{Annotated, Data, Declaration, Items, Schema},
+    validator::Validator,
+};
+use num_bigint::BigInt;
+use pallas_addresses::{
+    Address, Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart, StakeAddress,
+};
+use pallas_codec::utils::{CborWrap, KeyValuePairs, NonEmptyKeyValuePairs, NonZeroInt};
+use pallas_crypto::hash::Hash;
+use pallas_primitives::{
+    ExUnits,
+    conway::{
+        Language, PostAlonzoTransactionOutput, PseudoDatumOption, Redeemer, RedeemerTag,
+        StakeCredential, TransactionInput, TransactionOutput, Value,
+    },
+};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    mem,
+};
+use uplc::{
+    PlutusData,
+    ast::{
+        Constant, Data as UplcData, DeBruijn, NamedDeBruijn, Program, SerializableProgram, Term,
+        TermPath,
+    },
+    machine::{
+        self,
+        cost_model::ExBudget,
+        value::{from_pallas_bigint, to_pallas_bigint},
+    },
+    tx::{
+        script_context::{
+            ScriptContext, ScriptInfo, ScriptPurpose, TimeRange, TxInInfo, TxInfo, TxInfoV3,
+        },
+        to_plutus_data::{MintValue, ToPlutusData},
+    },
+};
+
+pub const DEFAULT_RUNS: usize = 1000;
+
+/// How deep generated values may nest before only the smallest constructors and empty
+/// collections get picked, so that recursive types remain finite.
+const MAX_DEPTH: usize = 6;
+
+/// The validator handlers that can be fuzzed, as found at the end of their blueprint title.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Purpose {
+    Spend,
+    Mint,
+    Withdraw,
+}
+
+impl Purpose {
+    pub fn from_title(title: &str) -> Option<Self> {
+        match title.rsplit('.').next() {
+            Some("spend") => Some(Purpose::Spend),
+            Some("mint") => Some(Purpose::Mint),
+            Some("withdraw") => Some(Purpose::Withdraw),
+            _ => None,
+        }
+    }
+}
+
+/// Everything a handler is given that comes from its blueprint schemas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Input {
+    pub parameters: Vec<PlutusData>,
+    pub datum: Option<PlutusData>,
+    pub redeemer: PlutusData,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FindingKind {
+    /// The handler accepted an input made up from its schemas alone, in a transaction that
+    /// neither signs nor pays anything.
+    Succeeded,
+    /// The handler failed with an error other than an explicit `fail` (or failed `expect`); for
+    /// example a builtin applied to an unexpected value, or an exhausted budget.
+    Crashed(machine::Error),
+}
+
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub input: Input,
+    pub logs: Vec<String>,
+    /// How many inputs led to the same kind of finding; only the first one is kept.
+    pub occurrences: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub title: String,
+    pub runs: usize,
+    pub corpus: usize,
+    pub branches_taken: usize,
+    pub branches_total: usize,
+    pub findings: Vec<Finding>,
+}
+
+/// A coverage-guided fuzzer for a single validator handler.
+///
+/// Inputs are generated from the schemas of the handler's parameters, datum and redeemer, and
+/// given to the handler through a minimal transaction spending, minting or withdrawing with the
+/// script. Inputs taking branches of the program that no previous input took are kept in a corpus,
+/// and later mutated to reach further into the program.
+pub struct Fuzzer<'a> {
+    title: String,
+    purpose: Purpose,
+    program: &'a Program<DeBruijn>,
+    generator: Generator<'a>,
+    parameters: Vec<&'a Data>,
+    datum: Option<&'a Data>,
+    redeemer: &'a Data,
+    branches: BTreeSet<TermPath>,
+    taken: BTreeSet<TermPath>,
+    corpus: Vec<Input>,
+    findings: BTreeMap<String, Finding>,
+    rng: StdRng,
+}
+
+impl<'a> Fuzzer<'a> {
+    pub fn new(
+        validator: &'a Validator<SerializableProgram>,
+        definitions: &'a Definitions<Annotated<Schema>>,
+        seed: u32,
+    ) -> Result<Self, Error> {
+        let purpose =
+            Purpose::from_title(&validator.title).ok_or_else(|| Error::UnsupportedFuzzTarget {
+                hint: format!(
+                    "{} isn't a spend, mint or withdraw handler; other handlers would need a certificate, a vote or a proposal to be made up as well.",
+                    validator.title
+                ),
+            })?;
+
+        let program = match &validator.program {
+            SerializableProgram::PlutusV3Program(program) => program,
+            _ => {
+                return Err(Error::UnsupportedFuzzTarget {
+                    hint: "Only Plutus V3 validators can be fuzzed.".to_string(),
+                });
+            }
+        };
+
+        let parameters = validator
+            .parameters
+            .iter()
+            .map(|parameter| resolve_parameter(parameter, definitions))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let datum = match purpose {
+            Purpose::Spend => validator
+                .datum
+                .as_ref()
+                .map(|datum| resolve_parameter(datum, definitions))
+                .transpose()?
+                .or(Some(&Data::Opaque)),
+            Purpose::Mint | Purpose::Withdraw => None,
+        };
+
+        let redeemer = validator
+            .redeemer
+            .as_ref()
+            .map(|redeemer| resolve_parameter(redeemer, definitions))
+            .transpose()?
+            .unwrap_or(&Data::Opaque);
+
+        // Parameters and the script context are applied as constants, which hold no branches;
+        // so the branches of every applied program are found at the same paths.
+        let placeholder = UplcData::integer(0.into());
+        let applied = (0..=parameters.len()).fold(
+            Program::<NamedDeBruijn>::from(program.clone()),
+            |program, _| program.apply_data(placeholder.clone()),
+        );
+        let branches = machine::coverage::branches(&applied.term);
+
+        Ok(Fuzzer {
+            title: validator.title.clone(),
+            purpose,
+            program,
+            generator: Generator::new(definitions, &program.term),
+            parameters,
+            datum,
+            redeemer,
+            branches,
+            taken: BTreeSet::new(),
+            corpus: vec![],
+            findings: BTreeMap::new(),
+            rng: StdRng::seed_from_u64(seed as u64),
+        })
+    }
+
+    pub fn run(mut self, runs: usize) -> Report {
+        for _ in 0..runs {
+            let input = match self.corpus.choose(&mut self.rng) {
+                Some(parent) if self.rng.gen_bool(0.8) => {
+                    let parent = parent.clone();
+                    self.mutate_input(&parent)
+                }
+                _ => self.generate_input(),
+            };
+
+            self.evaluate(input);
+        }
+
+        Report {
+            title: self.title,
+            runs,
+            corpus: self.corpus.len(),
+            branches_taken: self.taken.len(),
+            branches_total: self.branches.len(),
+            findings: self.findings.into_values().collect(),
+        }
+    }
+
+    /// Inputs made up from the handler's schemas alone, without any guidance from coverage.
+    pub fn inputs(mut self, n: usize) -> Vec<Input> {
+        (0..n).map(|_| self.generate_input()).collect()
+    }
+
+    fn evaluate(&mut self, input: Input) {
+        let (result, coverage) = apply(
+            self.purpose,
+            Program::<NamedDeBruijn>::from(self.program.clone()),
+            &input,
+        )
+        .cover(ExBudget::default(), &Language::PlutusV3, &self.branches);
+
+        let before = self.taken.len();
+        self.taken.extend(coverage.taken);
+        if self.taken.len() > before {
+            self.corpus.push(input.clone());
+        }
+
+        let kind = match result {
+            Ok(_) => FindingKind::Succeeded,
+            Err(machine::Error::EvaluationFailure) => return,
+            Err(error) => FindingKind::Crashed(error),
+        };
+
+        // Errors often carry the values they were raised on; so findings are told apart by the
+        // kind of error only.
+        let key = match &kind {
+            FindingKind::Succeeded => String::new(),
+            FindingKind::Crashed(error) => format!("{:?}", mem::discriminant(error)),
+        };
+
+        self.findings
+            .entry(key)
+            .and_modify(|finding| finding.occurrences += 1)
+            .or_insert_with(|| Finding {
+                kind,
+                input,
+                logs: coverage.logs,
+                occurrences: 1,
+            });
+    }
+
+    fn generate_input(&mut self) -> Input {
+        let Fuzzer { generator, rng, .. } = self;
+
+        Input {
+            parameters: self
+                .parameters
+                .iter()
+                .map(|data| generator.generate(data, rng, 0))
+                .collect(),
+            // A spent output may also come without any datum.
+            datum: match self.datum {
+                Some(data) if rng.gen_bool(0.9) => Some(generator.generate(data, rng, 0)),
+                _ => None,
+            },
+            redeemer: generator.generate(self.redeemer, rng, 0),
+        }
+    }
+
+    fn mutate_input(&mut self, parent: &Input) -> Input {
+        let Fuzzer { generator, rng, .. } = self;
+
+        let mut input = parent.clone();
+
+        match rng.gen_range(0..input.parameters.len() + 2) {
+            ix if ix < input.parameters.len() => {
+                input.parameters[ix] =
+                    generator.mutate(self.parameters[ix], &input.parameters[ix], rng, 0);
+            }
+            ix if ix == input.parameters.len() => {
+                input.datum = match (self.datum, &input.datum) {
+                    (Some(schema), Some(datum)) if rng.gen_bool(0.9) => {
+                        Some(generator.mutate(schema, datum, rng, 0))
+                    }
+                    (Some(schema), None) => Some(generator.generate(schema, rng, 0)),
+                    _ => None,
+                };
+            }
+            _ => {
+                input.redeemer = generator.mutate(self.redeemer, &input.redeemer, rng, 0);
+            }
+        }
+
+        input
+    }
+}
+
+fn resolve_parameter<'a>(
+    parameter: &'a Parameter,
+    definitions: &'a Definitions<Annotated<Schema>>,
+) -> Result<&'a Data, Error> {
+    match parameter.schema.schema(definitions) {
+        Some(Schema::Data(data)) => Ok(data),
+        Some(_) => Ok(&Data::Opaque),
+        None => Err(Error::UnresolvedSchemaReference {
+            reference: parameter.schema.reference().unwrap().clone(),
+        }),
+    }
+}
+
+/// Generates values conforming to blueprint schemas. Integers and byte arrays are sometimes
+/// picked among the constants of the fuzzed program, which are what validators usually compare
+/// their inputs against.
+pub struct Generator<'a> {
+    definitions: &'a Definitions<Annotated<Schema>>,
+    integers: Vec<BigInt>,
+    bytes: Vec<Vec<u8>>,
+}
+
+impl<'a> Generator<'a> {
+    pub fn new(definitions: &'a Definitions<Annotated<Schema>>, program: &Term<DeBruijn>) -> Self {
+        let mut integers = BTreeSet::new();
+        let mut bytes = BTreeSet::new();
+
+        let mut terms = vec![program];
+        while let Some(term) = terms.pop() {
+            if let Term::Constant(constant) = term {
+                match constant.as_ref() {
+                    Constant::Integer(n) => {
+                        integers.insert(n.clone());
+                    }
+                    Constant::ByteString(b) => {
+                        bytes.insert(b.clone());
+                    }
+                    _ => (),
+                }
+            }
+            terms.extend(term.children());
+        }
+
+        Generator {
+            definitions,
+            integers: integers.into_iter().collect(),
+            bytes: bytes.into_iter().collect(),
+        }
+    }
+
+    fn resolve(&self, declaration: &'a Declaration<Data>) -> &'a Data {
+        declaration
+            .schema(self.definitions)
+            .unwrap_or(&Data::Opaque)
+    }
+
+    /// Generate a value conforming to the given schema.
+    pub fn generate(&self, data: &'a Data, rng: &mut StdRng, depth: usize) -> PlutusData {
+        match data {
+            Data::Integer => UplcData::integer(self.integer(rng)),
+            Data::Bytes => UplcData::bytestring(self.bytes(rng)),
+            Data::List(Items::One(item)) => {
+                let item = self.resolve(item);
+                UplcData::list(
+                    (0..length(rng, depth))
+                        .map(|_| self.generate(item, rng, depth + 1))
+                        .collect(),
+                )
+            }
+            Data::List(Items::Many(items)) => UplcData::list(
+                items
+                    .iter()
+                    .map(|item| self.generate(self.resolve(&item.annotated), rng, depth + 1))
+                    .collect(),
+            ),
+            Data::Map(key, value) => {
+                let (key, value) = (self.resolve(key), self.resolve(value));
+                UplcData::map(
+                    (0..length(rng, depth))
+                        .map(|_| {
+                            (
+                                self.generate(key, rng, depth + 1),
+                                self.generate(value, rng, depth + 1),
+                            )
+                        })
+                        .collect(),
+                )
+            }
+            Data::AnyOf(constructors) => {
+                let constructor = if depth >= MAX_DEPTH {
+                    constructors
+                        .iter()
+                        .min_by_key(|constructor| constructor.annotated.fields.len())
+                } else {
+                    constructors.choose(rng)
+                };
+
+                match constructor {
+                    Some(constructor) => UplcData::constr(
+                        constructor.annotated.index as u64,
+                        constructor
+                            .annotated
+                            .fields
+                            .iter()
+                            .map(|field| {
+                                self.generate(self.resolve(&field.annotated), rng, depth + 1)
+                            })
+                            .collect(),
+                    ),
+                    None => self.arbitrary(rng, depth),
+                }
+            }
+            Data::Opaque => self.arbitrary(rng, depth),
+        }
+    }
+
+    /// Derive a value from another, by generating again one of its parts. Values that don't
+    /// conform to the schema are generated anew.
+    pub fn mutate(
+        &self,
+        data: &'a Data,
+        value: &PlutusData,
+        rng: &mut StdRng,
+        depth: usize,
+    ) -> PlutusData {
+        if rng.gen_bool(0.25) {
+            return self.generate(data, rng, depth);
+        }
+
+        match (data, value) {
+            (Data::Integer, PlutusData::BigInt(n)) => {
+                let n = from_pallas_bigint(n);
+                let n = match rng.gen_range(0..4) {
+                    0 => n + 1,
+                    1 => n - 1,
+                    2 => -n,
+                    _ => n * 2,
+                };
+                PlutusData::BigInt(to_pallas_bigint(&n))
+            }
+            (Data::Bytes, PlutusData::BoundedBytes(bytes)) => {
+                let mut bytes = bytes.to_vec();
+                match bytes.len() {
+                    0 => bytes.push(rng.r#gen()),
+                    len => {
+                        let ix = rng.gen_range(0..len);
+                        if rng.gen_bool(0.5) {
+                            bytes[ix] = rng.r#gen();
+                        } else {
+                            bytes.remove(ix);
+                        }
+                    }
+                }
+                UplcData::bytestring(bytes)
+            }
+            (Data::List(Items::One(item)), PlutusData::Array(elems)) if !elems.is_empty() => {
+                let item = self.resolve(item);
+                let mut elems = elems.iter().cloned().collect::<Vec<_>>();
+                let ix = rng.gen_range(0..elems.len());
+                match rng.gen_range(0..3) {
+                    0 => {
+                        elems.remove(ix);
+                    }
+                    1 => elems.push(self.generate(item, rng, depth + 1)),
+                    _ => elems[ix] = self.mutate(item, &elems[ix], rng, depth + 1),
+                }
+                UplcData::list(elems)
+            }
+            (Data::List(Items::Many(items)), PlutusData::Array(elems))
+                if !items.is_empty() && items.len() == elems.len() =>
+            {
+                let mut elems = elems.iter().cloned().collect::<Vec<_>>();
+                let ix = rng.gen_range(0..elems.len());
+                elems[ix] = self.mutate(
+                    self.resolve(&items[ix].annotated),
+                    &elems[ix],
+                    rng,
+                    depth + 1,
+                );
+                UplcData::list(elems)
+            }
+            (Data::Map(key, value), PlutusData::Map(pairs)) if !pairs.is_empty() => {
+                let mut pairs = pairs.iter().cloned().collect::<Vec<_>>();
+                let ix = rng.gen_range(0..pairs.len());
+                let (k, v) = pairs[ix].clone();
+                pairs[ix] = if rng.gen_bool(0.5) {
+                    (self.mutate(self.resolve(key), &k, rng, depth + 1), v)
+                } else {
+                    (k, self.mutate(self.resolve(value), &v, rng, depth + 1))
+                };
+                UplcData::map(pairs)
+            }
+            (Data::AnyOf(constructors), PlutusData::Constr(constr)) => {
+                let index = constr_index(constr.tag, constr.any_constructor);
+
+                let schemas = constructors
+                    .iter()
+                    .find(|constructor| Some(constructor.annotated.index as u64) == index)
+                    .map(|constructor| &constructor.annotated.fields)
+                    .filter(|fields| !fields.is_empty() && fields.len() == constr.fields.len());
+
+                match (index, schemas) {
+                    (Some(index), Some(schemas)) => {
+                        let mut fields = constr.fields.iter().cloned().collect::<Vec<_>>();
+                        let ix = rng.gen_range(0..fields.len());
+                        fields[ix] = self.mutate(
+                            self.resolve(&schemas[ix].annotated),
+                            &fields[ix],
+                            rng,
+                            depth + 1,
+                        );
+                        UplcData::constr(index, fields)
+                    }
+                    _ => self.generate(data, rng, depth),
+                }
+            }
+            _ => self.generate(data, rng, depth),
+        }
+    }
+
+    /// Any value, for opaque schemas.
+    fn arbitrary(&self, rng: &mut StdRng, depth: usize) -> PlutusData {
+        let leaf = depth >= MAX_DEPTH;
+
+        match rng.gen_range(0..if leaf { 2 } else { 5 }) {
+            0 => UplcData::integer(self.integer(rng)),
+            1 => UplcData::bytestring(self.bytes(rng)),
+            2 => UplcData::list(
+                (0..length(rng, depth))
+                    .map(|_| self.arbitrary(rng, depth + 1))
+                    .collect(),
+            ),
+            3 => UplcData::map(
+                (0..length(rng, depth))
+                    .map(|_| {
+                        (
+                            self.arbitrary(rng, depth + 1),
+                            self.arbitrary(rng, depth + 1),
+                        )
+                    })
+                    .collect(),
+            ),
+            _ => UplcData::constr(
+                rng.gen_range(0..4),
+                (0..length(rng, depth))
+                    .map(|_| self.arbitrary(rng, depth + 1))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Integers are mostly picked close to zero, where comparisons in validators usually draw
+    /// their lines, but also cover the whole range of 64 and 128-bit integers.
+    fn integer(&self, rng: &mut StdRng) -> BigInt {
+        match rng.gen_range(0..10) {
+            0..=2 => match self.integers.choose(rng) {
+                Some(n) => n + rng.gen_range(-1..=1),
+                None => BigInt::from(rng.gen_range(-3..=3)),
+            },
+            3..=4 => BigInt::from(rng.gen_range(-3..=3)),
+            5..=6 => BigInt::from(rng.gen_range(-1000..=1000)),
+            7..=8 => BigInt::from(rng.r#gen::<i64>()),
+            _ => BigInt::from(rng.r#gen::<i128>()),
+        }
+    }
+
+    /// Byte arrays favor the lengths of hashes (28 and 32 bytes) found in transactions.
+    fn bytes(&self, rng: &mut StdRng) -> Vec<u8> {
+        let len = match rng.gen_range(0..6) {
+            0 => match self.bytes.choose(rng) {
+                Some(bytes) => return bytes.clone(),
+                None => 0,
+            },
+            1 => 0,
+            2 => 28,
+            3 => 32,
+            _ => rng.gen_range(1..64),
+        };
+
+        (0..len).map(|_| rng.r#gen()).collect()
+    }
+}
+
+fn constr_index(tag: u64, any_constructor: Option<u64>) -> Option<u64> {
+    match tag {
+        121..=127 => Some(tag - 121),
+        1280..=1400 => Some(tag - 1280 + 7),
+        102 => any_constructor,
+        _ => None,
+    }
+}
+
+fn length(rng: &mut StdRng, depth: usize) -> usize {
+    if depth >= MAX_DEPTH {
+        0
+    } else {
+        rng.gen_range(0..4)
+    }
+}
+
+/// Apply an input to the program of a handler: parameters first, then the context of a
+/// transaction performing the handler's purpose with the resulting script.
+pub fn apply(
+    purpose: Purpose,
+    program: Program<NamedDeBruijn>,
+    input: &Input,
+) -> Program<NamedDeBruijn> {
+    let program = input.parameters.iter().fold(program, |program, parameter| {
+        program.apply_data(parameter.clone())
+    });
+
+    let (hash, _) =
+        SerializableProgram::PlutusV3Program(Program::<DeBruijn>::from(program.clone()))
+            .compiled_code_and_hash();
+
+    let context = script_context(purpose, hash, input.datum.as_ref(), input.redeemer.clone());
+
+    program.apply_data(context)
+}
+
+/// A Plutus V3 script context for a transaction spending, minting or withdrawing with the given
+/// script, and doing nothing else.
+fn script_context(
+    purpose: Purpose,
+    hash: Hash<28>,
+    datum: Option<&PlutusData>,
+    redeemer: PlutusData,
+) -> PlutusData {
+    let out_ref = TransactionInput {
+        transaction_id: Hash::new([0; 32]),
+        index: 0,
+    };
+
+    let address = ShelleyAddress::new(
+        Network::Testnet,
+        ShelleyPaymentPart::Script(hash),
+        ShelleyDelegationPart::Script(hash),
+    );
+
+    let (script_info, tag) = match purpose {
+        Purpose::Spend => (
+            ScriptInfo::Spending(out_ref.clone(), datum.cloned()),
+            RedeemerTag::Spend,
+        ),
+        Purpose::Mint => (ScriptInfo::Minting(hash), RedeemerTag::Mint),
+        Purpose::Withdraw => (
+            ScriptInfo::Rewarding(StakeCredential::ScriptHash(hash)),
+            RedeemerTag::Reward,
+        ),
+    };
+
+    let inputs = match purpose {
+        Purpose::Spend => vec![TxInInfo {
+            out_ref: out_ref.clone(),
+            resolved: TransactionOutput::PostAlonzo(PostAlonzoTransactionOutput {
+                address: Address::from(address.clone()).to_vec().into(),
+                value: Value::Coin(2_000_000),
+                datum_option: datum.map(|datum| PseudoDatumOption::Data(CborWrap(datum.clone()))),
+                script_ref: None,
+            }),
+        }],
+        Purpose::Mint | Purpose::Withdraw => vec![],
+    };
+
+    let mint = match purpose {
+        Purpose::Mint => NonEmptyKeyValuePairs::Def(vec![(
+            hash,
+            NonEmptyKeyValuePairs::Def(vec![(
+                vec![].into(),
+                NonZeroInt::try_from(1).expect("1 is non-zero"),
+            )]),
+        )]),
+        Purpose::Spend | Purpose::Withdraw => NonEmptyKeyValuePairs::Indef(vec![]),
+    };
+
+    let withdrawals = match purpose {
+        Purpose::Withdraw => vec![(
+            Address::from(StakeAddress::try_from(address).expect("address has a delegation part")),
+            0,
+        )],
+        Purpose::Spend | Purpose::Mint => vec![],
+    };
+
+    let purpose: ScriptPurpose = match &script_info {
+        ScriptInfo::Spending(out_ref, _) => ScriptInfo::Spending(out_ref.clone(), ()),
+        ScriptInfo::Minting(policy) => ScriptInfo::Minting(*policy),
+        ScriptInfo::Rewarding(credential) => ScriptInfo::Rewarding(credential.clone()),
+        _ => unreachable!("only spend, mint and withdraw handlers are fuzzed"),
+    };
+
+    let tx_info = TxInfoV3 {
+        inputs,
+        reference_inputs: vec![],
+        outputs: vec![],
+        fee: 0,
+        mint: MintValue { mint_value: mint },
+        certificates: vec![],
+        withdrawals: KeyValuePairs::from(withdrawals),
+        valid_range: TimeRange {
+            lower_bound: None,
+            upper_bound: None,
+        },
+        signatories: vec![],
+        redeemers: KeyValuePairs::from(vec![(
+            purpose,
+            Redeemer {
+                tag,
+                index: 0,
+                data: redeemer.clone(),
+                ex_units: ExUnits { mem: 0, steps: 0 },
+            },
+        )]),
+        data: KeyValuePairs::from(vec![]),
+        id: Hash::new([0; 32]),
+        votes: KeyValuePairs::from(vec![]),
+        proposal_procedures: vec![],
+        current_treasury_amount: None,
+        treasury_donation: None,
+    };
+
+    ScriptContext::V3 {
+        tx_info: Box::new(TxInfo::V3(tx_info)),
+        redeemer,
+        purpose: Box::new(script_info),
+    }
+    .to_plutus_data()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{module::CheckedModules, tests::TestProject};
+    use aiken_lang::{
+        ast::{TraceLevel, Tracing},
+        plutus_version::PlutusVersion,
+    };
+
+    fn validator(code: &str, handler: &str) -> Validator<SerializableProgram> {
+        let mut project = TestProject::new();
+
+        let modules = CheckedModules::singleton(project.check(project.parse(code)));
+
+        let mut generator = project.new_generator(Tracing::All(TraceLevel::Verbose));
+
+        let (module, def) = modules.validators().next().unwrap();
+
+        Validator::from_checked_module(
+            &modules,
+            &mut generator,
+            module,
+            def,
+            &PlutusVersion::V3,
+            false,
+        )
+        .unwrap()
+        .into_iter()
+        .find(|validator| validator.title.ends_with(handler))
+        .unwrap()
+    }
+
+    fn fuzz(validator: &Validator<SerializableProgram>) -> Report {
+        Fuzzer::new(validator, &validator.definitions, 42)
+            .unwrap()
+            .run(DEFAULT_RUNS)
+    }
+
+    #[test]
+    fn generate_conforming_values() {
+        let validator = validator(
+            r#"
+            pub type Datum {
+              Locked { owner: ByteArray, amounts: List<Int>, tag: Option<ByteArray> }
+              Open
+            }
+
+            validator foo(_param: Pairs<ByteArray, Int>) {
+              spend(_datum: Option<Datum>, _redeemer: (Int, ByteArray), _ref: Data, _tx: Data) {
+                True
+              }
+
+              else(_) {
+                fail
+              }
+            }
+            "#,
+            "spend",
+        );
+
+        let program = match &validator.program {
+            SerializableProgram::PlutusV3Program(program) => program,
+            _ => unreachable!(),
+        };
+
+        let generator = Generator::new(&validator.definitions, &program.term);
+
+        let mut rng = StdRng::seed_from_u64(42);
+
+        let schemas = validator
+            .parameters
+            .iter()
+            .chain(validator.datum.iter())
+            .chain(validator.redeemer.iter());
+
+        for parameter in schemas {
+            let data = resolve_parameter(parameter, &validator.definitions).unwrap();
+
+            let mut value = generator.generate(data, &mut rng, 0);
+
+            for _ in 0..100 {
+                assert!(
+                    parameter
+                        .validate(&validator.definitions, &Constant::Data(value.clone()))
+                        .is_ok(),
+                    "{value:?}"
+                );
+
+                value = generator.mutate(data, &value, &mut rng, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn find_unexpected_success() {
+        let validator = validator(
+            r#"
+            validator foo {
+              spend(_datum: Option<Data>, redeemer: Int, _ref: Data, _tx: Data) {
+                redeemer == 1337
+              }
+
+              else(_) {
+                fail
+              }
+            }
+            "#,
+            "spend",
+        );
+
+        let report = fuzz(&validator);
+
+        assert_eq!(report.findings.len(), 1);
+
+        let finding = &report.findings[0];
+        assert_eq!(finding.kind, FindingKind::Succeeded);
+        assert_eq!(finding.input.redeemer, UplcData::integer(1337.into()));
+        assert!(report.branches_taken > 0);
+    }
+
+    #[test]
+    fn find_crash() {
+        let validator = validator(
+            r#"
+            validator foo {
+              mint(redeemer: Int, _policy_id: ByteArray, _tx: Data) {
+                expect redeemer > -1000
+                100 / redeemer > 1000
+              }
+
+              else(_) {
+                fail
+              }
+            }
+            "#,
+            "mint",
+        );
+
+        let report = fuzz(&validator);
+
+        assert_eq!(report.findings.len(), 1);
+
+        let finding = &report.findings[0];
+        assert!(matches!(finding.kind, FindingKind::Crashed(_)));
+        assert_eq!(finding.input.redeemer, UplcData::integer(0.into()));
+    }
+
+    #[test]
+    fn unsupported_handler() {
+        let validator = validator(
+            r#"
+            validator foo {
+              publish(_redeemer: Data, _certificate: Data, _tx: Data) {
+                True
+              }
+
+              else(_) {
+                fail
+              }
+            }
+            "#,
+            "publish",
+        );
+
+        assert!(matches!(
+            Fuzzer::new(&validator, &validator.definitions, 42),
+            Err(Error::UnsupportedFuzzTarget { .. })
+        ));
+    }
+}
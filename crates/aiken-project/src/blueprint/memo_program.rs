@@ -1,9 +1,12 @@
 use aiken_lang::{ast::TypedValidator, gen_uplc::CodeGenerator};
-use uplc::ast::{DeBruijn, Program};
+use uplc::{
+    ast::{DeBruijn, Program},
+    source_map::SourceMap,
+};
 
 #[derive(Default)]
 pub struct MemoProgram {
-    program: Option<Program<DeBruijn>>,
+    program: Option<(Program<DeBruijn>, SourceMap)>,
 }
 
 impl MemoProgram {
@@ -12,10 +15,13 @@ impl MemoProgram {
         generator: &mut CodeGenerator,
         def: &TypedValidator,
         module_name: &str,
-    ) -> Program<DeBruijn> {
+    ) -> (Program<DeBruijn>, SourceMap) {
         match self.program.take() {
             None => {
-                let new_program = generator.generate(def, module_name).to_debruijn().unwrap();
+                let (new_program, source_map) =
+                    generator.generate_with_source_map(def, module_name);
+
+                let new_program = (new_program.to_debruijn().unwrap(), source_map);
 
                 self.program.replace(new_program.clone());
 
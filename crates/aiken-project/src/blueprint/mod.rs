@@ -1,5 +1,7 @@
+pub mod bindings;
 pub mod definitions;
 pub mod error;
+pub mod fuzz;
 mod memo_program;
 pub mod parameter;
 pub mod schema;
@@ -67,6 +69,7 @@ impl Blueprint {
         modules: &CheckedModules,
         generator: &mut CodeGenerator,
         export_all_types: bool,
+        with_source_map: bool,
     ) -> Result<Self, Error> {
         let preamble = config.into();
 
@@ -81,6 +84,7 @@ impl Blueprint {
                     validator,
                     def,
                     &config.plutus,
+                    with_source_map,
                 )?
                 .into_iter()
                 .map(|mut schema| {
@@ -18,6 +18,7 @@ use std::borrow::Borrow;
 use uplc::{
     PlutusData,
     ast::{Constant, SerializableProgram},
+    source_map::SourceMap,
 };
 
 #[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize)]
@@ -40,6 +41,11 @@ pub struct Validator<T> {
     #[serde(flatten)]
     pub program: T,
 
+    #[serde(rename = "sourceMap")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub source_map: Option<SourceMap>,
+
     #[serde(skip_serializing_if = "Definitions::is_empty")]
     #[serde(default)]
     pub definitions: Definitions<Annotated<Schema>>,
@@ -213,6 +219,7 @@ impl Validator<()> {
             datum,
             redeemer,
             program: (),
+            source_map: None,
             definitions,
         })
     }
@@ -224,7 +231,10 @@ impl Validator<()> {
         generator: &mut CodeGenerator,
         def: &TypedValidator,
         module_name: &str,
+        with_source_map: bool,
     ) -> Validator<SerializableProgram> {
+        let (program, source_map) = program.get(generator, def, module_name);
+
         Validator {
             title: self.title,
             description: self.description,
@@ -235,7 +245,12 @@ impl Validator<()> {
                 PlutusVersion::V1 => SerializableProgram::PlutusV1Program,
                 PlutusVersion::V2 => SerializableProgram::PlutusV2Program,
                 PlutusVersion::V3 => SerializableProgram::PlutusV3Program,
-            }(program.get(generator, def, module_name)),
+            }(program),
+            source_map: if with_source_map {
+                Some(source_map)
+            } else {
+                None
+            },
             definitions: self.definitions,
         }
     }
@@ -248,6 +263,7 @@ impl Validator<SerializableProgram> {
         module: &CheckedModule,
         def: &TypedValidator,
         plutus_version: &PlutusVersion,
+        with_source_map: bool,
     ) -> Result<Vec<Self>, Error> {
         let mut program = MemoProgram::default();
 
@@ -280,7 +296,14 @@ impl Validator<SerializableProgram> {
         Ok(validators
             .into_iter()
             .map(|validator| {
-                validator.attach_program(&mut program, plutus_version, generator, def, &module.name)
+                validator.attach_program(
+                    &mut program,
+                    plutus_version,
+                    generator,
+                    def,
+                    &module.name,
+                    with_source_map,
+                )
             })
             .collect())
     }
@@ -296,6 +319,7 @@ impl Validator<SerializableProgram> {
                 head.validate(definitions, &Constant::Data(arg.clone()))?;
                 Ok(Self {
                     program: self.program.map(|program| program.apply_data(arg.clone())),
+                    source_map: self.source_map.map(|source_map| source_map.applied()),
                     parameters: tail.to_vec(),
                     ..self
                 })
@@ -378,7 +402,7 @@ mod tests {
                 .next()
                 .expect("source code did no yield any validator");
 
-            let validators = Validator::from_checked_module(&modules, &mut generator, validator, def, &PlutusVersion::default());
+            let validators = Validator::from_checked_module(&modules, &mut generator, validator, def, &PlutusVersion::default(), false);
 
             match validators.as_deref() {
                 Err(e) => insta::with_settings!({
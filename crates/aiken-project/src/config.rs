@@ -5,6 +5,7 @@ use aiken_lang::{
     ast::{Annotation, ByteArrayFormatPreference, ModuleConstant, Span, UntypedDefinition},
     expr::UntypedExpr,
     parser::token::Base,
+    test_framework::Limits,
 };
 pub use aiken_lang::{plutus_version::PlutusVersion, version::compiler_version};
 use glob::glob;
@@ -20,6 +21,7 @@ use std::{
     fs, io,
     path::{Path, PathBuf},
 };
+use uplc::optimize::OptLevel;
 
 #[derive(Deserialize, Serialize, Clone)]
 pub struct ProjectConfig {
@@ -49,6 +51,76 @@ pub struct ProjectConfig {
 
     #[serde(default)]
     pub config: BTreeMap<String, BTreeMap<String, SimpleExpr>>,
+
+    #[serde(default, skip_serializing_if = "TestConfig::is_empty")]
+    pub test: TestConfig,
+
+    #[serde(default, skip_serializing_if = "BuildConfig::is_empty")]
+    pub build: BuildConfig,
+}
+
+/// Options applying to the code generated for the project, unless overridden on the command-line.
+/// For example:
+///
+/// ```toml
+/// [build]
+/// optimization = "cpu" # or "size" (default), or "none"
+/// max_script_size = 12000 # bytes, for each validator
+/// ```
+#[derive(Deserialize, Serialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct BuildConfig {
+    #[serde(default)]
+    pub optimization: OptLevel,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_script_size: Option<usize>,
+}
+
+impl BuildConfig {
+    pub fn is_empty(&self) -> bool {
+        self == &BuildConfig::default()
+    }
+}
+
+/// Limits applying to every test and benchmark of the project, unless overridden by a '@budget'
+/// decorator. For example:
+///
+/// ```toml
+/// [test]
+/// timeout = 10 # seconds
+///
+/// [test.budget]
+/// mem = 14000000
+/// cpu = 10000000000
+/// ```
+#[derive(Deserialize, Serialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct TestConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<BudgetConfig>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct BudgetConfig {
+    pub mem: Option<i64>,
+    pub cpu: Option<i64>,
+}
+
+impl TestConfig {
+    pub fn is_empty(&self) -> bool {
+        self == &TestConfig::default()
+    }
+
+    pub fn limits(&self) -> Limits {
+        let budget = self.budget.unwrap_or_default();
+        Limits {
+            mem: budget.mem,
+            cpu: budget.cpu,
+            timeout: self.timeout.map(std::time::Duration::from_secs),
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -394,6 +466,8 @@ impl ProjectConfig {
                 source: Platform::Github,
             }],
             config: BTreeMap::new(),
+            test: TestConfig::default(),
+            build: BuildConfig::default(),
         }
     }
 
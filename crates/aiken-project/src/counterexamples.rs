@@ -0,0 +1,146 @@
+//! Counterexamples found by property tests, persisted across runs so that a regression stays
+//! caught even when the seed changes. Counterexamples are recorded as the choices that led to
+//! them, by module and test name, in one of two databases:
+//!
+//! - `build/counterexamples.json`, which is filled and cleaned up by each run;
+//! - `regressions.json`, at the root of the project, which is meant to be kept under version
+//!   control. Entries only ever get there when promoted from the former.
+//!
+//! Both are replayed before any new value is sampled.
+
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Counterexamples(BTreeMap<String, BTreeMap<String, Vec<String>>>);
+
+impl Counterexamples {
+    /// Load counterexamples from disk; a missing file has none.
+    #[allow(clippy::result_large_err)]
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path).map_err(|error| Error::FileIo {
+            error,
+            path: Box::new(path.to_path_buf()),
+        })?;
+
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        if self.0.is_empty() && !path.exists() {
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let json = serde_json::to_string_pretty(self).unwrap();
+
+        fs::write(path, format!("{json}\n")).map_err(|error| Error::FileIo {
+            error,
+            path: Box::new(path.to_path_buf()),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Choices recorded for a test, most recent first. Entries that aren't valid hex-encoded
+    /// choices are ignored.
+    pub fn get(&self, module: &str, name: &str) -> Vec<Vec<u8>> {
+        self.0
+            .get(module)
+            .and_then(|tests| tests.get(name))
+            .map(|examples| {
+                examples
+                    .iter()
+                    .filter_map(|choices| hex::decode(choices).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Record choices for a test, unless already known.
+    pub fn insert(&mut self, module: &str, name: &str, choices: &[u8]) {
+        let choices = hex::encode(choices);
+
+        let examples = self
+            .0
+            .entry(module.to_string())
+            .or_default()
+            .entry(name.to_string())
+            .or_default();
+
+        if !examples.contains(&choices) {
+            examples.insert(0, choices);
+        }
+    }
+
+    /// Forget all choices recorded for a test, returning them.
+    pub fn remove(&mut self, module: &str, name: &str) -> Vec<Vec<u8>> {
+        let examples = self.get(module, name);
+
+        if let Some(tests) = self.0.get_mut(module) {
+            tests.remove(name);
+            if tests.is_empty() {
+                self.0.remove(module);
+            }
+        }
+
+        examples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_get_remove() {
+        let mut counterexamples = Counterexamples::default();
+
+        counterexamples.insert("foo/bar", "prop_a", &[1, 2, 3]);
+        counterexamples.insert("foo/bar", "prop_a", &[4]);
+        counterexamples.insert("foo/bar", "prop_a", &[1, 2, 3]);
+        counterexamples.insert("foo/bar", "prop_b", &[]);
+
+        assert_eq!(
+            counterexamples.get("foo/bar", "prop_a"),
+            vec![vec![4], vec![1, 2, 3]]
+        );
+        assert_eq!(
+            counterexamples.get("foo/bar", "prop_b"),
+            vec![Vec::<u8>::new()]
+        );
+        assert!(counterexamples.get("foo", "prop_a").is_empty());
+
+        assert_eq!(
+            serde_json::to_value(&counterexamples).unwrap(),
+            serde_json::json!({
+                "foo/bar": {
+                    "prop_a": ["04", "010203"],
+                    "prop_b": [""],
+                }
+            })
+        );
+
+        assert_eq!(
+            counterexamples.remove("foo/bar", "prop_b"),
+            vec![Vec::<u8>::new()]
+        );
+        assert_eq!(
+            counterexamples.remove("foo/bar", "prop_a"),
+            vec![vec![4], vec![1, 2, 3]]
+        );
+        assert!(counterexamples.is_empty());
+    }
+}
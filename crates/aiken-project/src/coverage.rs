@@ -0,0 +1,253 @@
+use aiken_lang::{
+    ast::Span,
+    coverage::{Probe, ProbeId, ProbeKind},
+    line_numbers::LineNumbers,
+};
+use std::{collections::BTreeMap, fmt::Write, path::PathBuf};
+
+/// A function, or validator handler, and how many times its body was entered.
+#[derive(Debug, Clone)]
+pub struct FunctionCoverage {
+    pub name: String,
+    pub line: usize,
+    pub hits: usize,
+}
+
+/// A branch of an `if` or `when` expression and how many times it was taken.
+#[derive(Debug, Clone)]
+pub struct BranchCoverage {
+    pub line: usize,
+    /// Index of the enclosing `if` or `when` expression within the module.
+    pub block: usize,
+    pub index: usize,
+    pub hits: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModuleCoverage {
+    pub name: String,
+    pub path: PathBuf,
+    pub functions: Vec<FunctionCoverage>,
+    pub handlers: Vec<FunctionCoverage>,
+    pub branches: Vec<BranchCoverage>,
+}
+
+impl ModuleCoverage {
+    pub fn new(
+        name: &str,
+        path: PathBuf,
+        line_numbers: &LineNumbers,
+        probes: Vec<Probe>,
+        hits: &BTreeMap<ProbeId, usize>,
+    ) -> Self {
+        let mut functions = vec![];
+        let mut handlers = vec![];
+        let mut branches = vec![];
+        let mut blocks: Vec<Span> = vec![];
+
+        for Probe { id, kind } in probes {
+            let line = line_numbers.line_number(id.start).unwrap_or_default();
+            let hits = hits.get(&id).copied().unwrap_or_default();
+
+            match kind {
+                ProbeKind::Function { name } => {
+                    functions.push(FunctionCoverage { name, line, hits })
+                }
+                ProbeKind::Handler { name } => handlers.push(FunctionCoverage { name, line, hits }),
+                ProbeKind::Branch { block, index } => {
+                    let block = match blocks.iter().position(|known| known == &block) {
+                        Some(ix) => ix,
+                        None => {
+                            blocks.push(block);
+                            blocks.len() - 1
+                        }
+                    };
+
+                    branches.push(BranchCoverage {
+                        line,
+                        block,
+                        index,
+                        hits,
+                    });
+                }
+            }
+        }
+
+        ModuleCoverage {
+            name: name.to_string(),
+            path,
+            functions,
+            handlers,
+            branches,
+        }
+    }
+
+    /// Number of functions and handlers entered at least once, over their total.
+    pub fn functions_ratio(&self) -> (usize, usize) {
+        let functions = self.functions.iter().chain(self.handlers.iter());
+        (
+            functions
+                .clone()
+                .filter(|function| function.hits > 0)
+                .count(),
+            functions.count(),
+        )
+    }
+
+    /// Number of branches taken at least once, over their total.
+    pub fn branches_ratio(&self) -> (usize, usize) {
+        (
+            self.branches
+                .iter()
+                .filter(|branch| branch.hits > 0)
+                .count(),
+            self.branches.len(),
+        )
+    }
+
+    pub fn uncovered_handlers(&self) -> impl Iterator<Item = &FunctionCoverage> {
+        self.handlers.iter().filter(|handler| handler.hits == 0)
+    }
+
+    /// Hits per line, taking for each line the most hit probe it holds.
+    fn lines(&self) -> BTreeMap<usize, usize> {
+        let mut lines = BTreeMap::new();
+
+        let probes = self
+            .functions
+            .iter()
+            .chain(self.handlers.iter())
+            .map(|function| (function.line, function.hits))
+            .chain(
+                self.branches
+                    .iter()
+                    .map(|branch| (branch.line, branch.hits)),
+            );
+
+        for (line, hits) in probes {
+            lines
+                .entry(line)
+                .and_modify(|most: &mut usize| *most = (*most).max(hits))
+                .or_insert(hits);
+        }
+
+        lines
+    }
+}
+
+/// Render coverage as an LCOV tracefile, with one record per module.
+pub fn to_lcov(modules: &[ModuleCoverage]) -> String {
+    let mut lcov = String::new();
+
+    for module in modules {
+        let functions = module.functions.iter().chain(module.handlers.iter());
+
+        let _ = writeln!(lcov, "TN:");
+        let _ = writeln!(lcov, "SF:{}", module.path.display());
+
+        for function in functions.clone() {
+            let _ = writeln!(lcov, "FN:{},{}", function.line, function.name);
+        }
+
+        for function in functions {
+            let _ = writeln!(lcov, "FNDA:{},{}", function.hits, function.name);
+        }
+
+        let (hit, found) = module.functions_ratio();
+        let _ = writeln!(lcov, "FNF:{found}");
+        let _ = writeln!(lcov, "FNH:{hit}");
+
+        for branch in module.branches.iter() {
+            let _ = writeln!(
+                lcov,
+                "BRDA:{},{},{},{}",
+                branch.line, branch.block, branch.index, branch.hits
+            );
+        }
+
+        let (hit, found) = module.branches_ratio();
+        let _ = writeln!(lcov, "BRF:{found}");
+        let _ = writeln!(lcov, "BRH:{hit}");
+
+        let lines = module.lines();
+
+        for (line, hits) in lines.iter() {
+            let _ = writeln!(lcov, "DA:{line},{hits}");
+        }
+
+        let _ = writeln!(lcov, "LF:{}", lines.len());
+        let _ = writeln!(
+            lcov,
+            "LH:{}",
+            lines.values().filter(|hits| **hits > 0).count()
+        );
+
+        let _ = writeln!(lcov, "end_of_record");
+    }
+
+    lcov
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BranchCoverage, FunctionCoverage, ModuleCoverage, to_lcov};
+    use indoc::indoc;
+    use std::path::PathBuf;
+
+    #[test]
+    fn lcov_record() {
+        let module = ModuleCoverage {
+            name: "foo".to_string(),
+            path: PathBuf::from("validators/foo.ak"),
+            functions: vec![FunctionCoverage {
+                name: "double".to_string(),
+                line: 1,
+                hits: 3,
+            }],
+            handlers: vec![FunctionCoverage {
+                name: "foo.spend".to_string(),
+                line: 10,
+                hits: 0,
+            }],
+            branches: vec![
+                BranchCoverage {
+                    line: 2,
+                    block: 0,
+                    index: 0,
+                    hits: 3,
+                },
+                BranchCoverage {
+                    line: 4,
+                    block: 0,
+                    index: 1,
+                    hits: 0,
+                },
+            ],
+        };
+
+        assert_eq!(
+            to_lcov(&[module]),
+            indoc! {"
+                TN:
+                SF:validators/foo.ak
+                FN:1,double
+                FN:10,foo.spend
+                FNDA:3,double
+                FNDA:0,foo.spend
+                FNF:2
+                FNH:1
+                BRDA:2,0,0,3
+                BRDA:4,0,1,0
+                BRF:2
+                BRH:1
+                DA:1,3
+                DA:2,3
+                DA:4,0
+                DA:10,0
+                LF:4
+                LH:2
+                end_of_record
+            "}
+        );
+    }
+}
@@ -24,6 +24,7 @@ use std::{
     io,
     path::{Path, PathBuf},
 };
+use uplc::{optimize::differential::Divergence, source_map::SourceLocation};
 use zip::result::ZipError;
 
 pub enum TomlLoadingContext {
@@ -118,12 +119,20 @@ pub enum Error {
         error: Box<tipo::error::Error>,
     },
 
-    #[error("{name} failed{}", if *verbose { format!("\n{src}") } else { String::new() } )]
+    #[error(
+        "{name} failed{}{}",
+        match failed_at {
+            Some(location) => format!(" at {}:{}", location.module, location.line),
+            None => String::new(),
+        },
+        if *verbose { format!("\n{src}") } else { String::new() }
+    )]
     TestFailure {
         name: Box<String>,
         path: Box<PathBuf>,
         verbose: bool,
         src: Box<String>,
+        failed_at: Option<Box<SourceLocation>>,
     },
 
     #[error(
@@ -158,6 +167,26 @@ pub enum Error {
     #[error("I located conditional modules under 'env', but no default one!")]
     NoDefaultEnvironment,
 
+    #[error("I couldn't find any benchmark baseline named '{name}'.")]
+    BaselineNotFound {
+        name: String,
+        known_baselines: Vec<String>,
+    },
+
+    #[error("{module}.{name} regressed compared to baseline '{baseline}'.")]
+    BenchmarkRegression {
+        module: String,
+        name: String,
+        baseline: String,
+    },
+
+    #[error("{title} behaves differently once optimized.")]
+    OptimizerDivergence {
+        title: String,
+        input: Option<String>,
+        divergence: Box<Divergence>,
+    },
+
     #[error(
         "I couldn't find any script matching {} in your blueprint (plutus.json).",
         script_hash.to_string().if_supports_color(Stdout, |s| s.yellow()),
@@ -300,21 +329,26 @@ impl Error {
     }
 
     pub fn from_test_result<U, T>(result: &TestResult<U, T>, verbose: bool) -> Self {
-        let (name, path, src) = match result {
-            TestResult::UnitTestResult(UnitTestResult { test, .. }) => (
+        let (name, path, src, failed_at) = match result {
+            TestResult::UnitTestResult(UnitTestResult {
+                test, failed_at, ..
+            }) => (
                 test.name.to_string(),
                 test.input_path.to_path_buf(),
                 test.program.to_pretty(),
+                failed_at.clone().map(Box::new),
             ),
             TestResult::PropertyTestResult(PropertyTestResult { test, .. }) => (
                 test.name.to_string(),
                 test.input_path.to_path_buf(),
                 test.program.to_pretty(),
+                None,
             ),
             TestResult::BenchmarkResult(BenchmarkResult { bench, .. }) => (
                 bench.name.to_string(),
                 bench.input_path.to_path_buf(),
                 bench.program.to_pretty(),
+                None,
             ),
         };
 
@@ -323,6 +357,7 @@ impl Error {
             path: Box::new(path),
             src: Box::new(src),
             verbose,
+            failed_at,
         }
     }
 }
@@ -365,6 +400,9 @@ impl ExtraData for Error {
             | Error::MalformedStakeAddress { .. }
             | Error::Module { .. }
             | Error::NoDefaultEnvironment
+            | Error::BaselineNotFound { .. }
+            | Error::BenchmarkRegression { .. }
+            | Error::OptimizerDivergence { .. }
             | Error::ModuleNotFound { .. }
             | Error::ExportNotFound { .. }
             | Error::ScriptOverrideNotFound { .. }
@@ -397,6 +435,9 @@ impl GetSource for Error {
             | Error::ModuleNotFound { .. }
             | Error::ExportNotFound { .. }
             | Error::NoDefaultEnvironment
+            | Error::BaselineNotFound { .. }
+            | Error::BenchmarkRegression { .. }
+            | Error::OptimizerDivergence { .. }
             | Error::Module { .. }
             | Error::ScriptOverrideNotFound { .. }
             | Error::ScriptOverrideArgumentParseError { .. } => None,
@@ -427,6 +468,9 @@ impl GetSource for Error {
             | Error::Json { .. }
             | Error::MalformedStakeAddress { .. }
             | Error::NoDefaultEnvironment
+            | Error::BaselineNotFound { .. }
+            | Error::BenchmarkRegression { .. }
+            | Error::OptimizerDivergence { .. }
             | Error::ModuleNotFound { .. }
             | Error::ExportNotFound { .. }
             | Error::Module { .. }
@@ -466,6 +510,9 @@ impl Diagnostic for Error {
             )))),
             Error::TomlLoading { .. } => Some(boxed(Box::new("aiken::loading::toml"))),
             Error::TestFailure { path, .. } => Some(boxed(Box::new(path.to_str().unwrap_or("")))),
+            Error::OptimizerDivergence { .. } => {
+                Some(boxed(Box::new("aiken::optimizer::divergence")))
+            }
             Error::Http(_) => Some(Box::new("aiken::packages::download")),
             Error::UnknownPackageVersion { .. } => {
                 Some(boxed(Box::new("aiken::packages::resolve")))
@@ -484,6 +531,8 @@ impl Diagnostic for Error {
             | Error::ExportNotFound { .. }
             | Error::ModuleNotFound { .. }
             | Error::NoDefaultEnvironment
+            | Error::BaselineNotFound { .. }
+            | Error::BenchmarkRegression { .. }
             | Error::ScriptOverrideNotFound { .. }
             | Error::ScriptOverrideArgumentParseError { .. } => None,
             Error::Module(e) => e.code().map(boxed),
@@ -511,6 +560,21 @@ impl Diagnostic for Error {
             Error::NoDefaultEnvironment => Some(Box::new(
                 "Environment module names are free, but there must be at least one named 'default.ak'.",
             )),
+            Error::BaselineNotFound {
+                name,
+                known_baselines,
+            } => Some(Box::new(if known_baselines.is_empty() {
+                format!("Save one first with `aiken bench --save-baseline {name}`.")
+            } else {
+                format!(
+                    "I know about the following baselines:\n{}",
+                    known_baselines
+                        .iter()
+                        .map(|s| format!("─▶ {}", s.if_supports_color(Stdout, |s| s.purple())))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                )
+            })),
             Error::TomlLoading { help, .. } => Some(Box::new(help)),
 
             Error::ModuleNotFound { known_modules, .. } => Some(Box::new(format!(
@@ -544,10 +608,28 @@ impl Diagnostic for Error {
                     .join("\n"),
             ))),
             Error::ScriptOverrideArgumentParseError { error, .. } => error.help(),
+            Error::OptimizerDivergence {
+                input, divergence, ..
+            } => Some(Box::new(format!(
+                "Unoptimized, it {expected}{input}; but it {actual} after the pass #{index} of the optimizer, '{pass}'{reducer}.\n\nThis is a bug in the compiler. Please report it, along with the code leading to it.",
+                expected = divergence.expected,
+                actual = divergence.actual,
+                input = input
+                    .as_ref()
+                    .map(|input| format!(" given {input}"))
+                    .unwrap_or_default(),
+                index = divergence.index,
+                pass = divergence.pass.if_supports_color(Stderr, |s| s.purple()),
+                reducer = divergence
+                    .reducer
+                    .map(|reducer| format!(" (once its '{reducer}' rewrite is enabled)"))
+                    .unwrap_or_default(),
+            ))),
             Error::Module(e) => e.help(),
             Error::StandardIo(_)
             | Error::Format { .. }
             | Error::TestFailure { .. }
+            | Error::BenchmarkRegression { .. }
             | Error::Http(_)
             | Error::ZipExtract(_)
             | Error::JoinError(_)
@@ -585,6 +667,9 @@ impl Diagnostic for Error {
             | Error::Json { .. }
             | Error::MalformedStakeAddress { .. }
             | Error::NoDefaultEnvironment
+            | Error::BaselineNotFound { .. }
+            | Error::BenchmarkRegression { .. }
+            | Error::OptimizerDivergence { .. }
             | Error::ModuleNotFound { .. }
             | Error::ScriptOverrideNotFound { .. }
             | Error::ScriptOverrideArgumentParseError { .. } => None,
@@ -605,6 +690,9 @@ impl Diagnostic for Error {
             | Error::ModuleNotFound { .. }
             | Error::ExportNotFound { .. }
             | Error::NoDefaultEnvironment
+            | Error::BaselineNotFound { .. }
+            | Error::BenchmarkRegression { .. }
+            | Error::OptimizerDivergence { .. }
             | Error::StandardIo(_)
             | Error::MissingManifest { .. }
             | Error::Format { .. }
@@ -645,6 +733,9 @@ impl Diagnostic for Error {
             | Error::Json { .. }
             | Error::MalformedStakeAddress { .. }
             | Error::NoDefaultEnvironment
+            | Error::BaselineNotFound { .. }
+            | Error::BenchmarkRegression { .. }
+            | Error::OptimizerDivergence { .. }
             | Error::ScriptOverrideNotFound { .. }
             | Error::ScriptOverrideArgumentParseError { .. } => None,
 
@@ -664,6 +755,9 @@ impl Diagnostic for Error {
             | Error::Parse { .. }
             | Error::StandardIo(_)
             | Error::NoDefaultEnvironment
+            | Error::BaselineNotFound { .. }
+            | Error::BenchmarkRegression { .. }
+            | Error::OptimizerDivergence { .. }
             | Error::MissingManifest { .. }
             | Error::TomlLoading { .. }
             | Error::Format { .. }
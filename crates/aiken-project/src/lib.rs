@@ -1,5 +1,8 @@
+pub mod baseline;
 pub mod blueprint;
 pub mod config;
+pub mod counterexamples;
+pub mod coverage;
 pub mod deps;
 pub mod docs;
 pub mod error;
@@ -7,10 +10,12 @@ pub mod export;
 pub mod format;
 pub mod github;
 pub mod module;
+pub mod mutation;
 pub mod options;
 pub mod package_name;
 pub mod paths;
 pub mod pretty;
+pub mod profile;
 pub mod telemetry;
 pub mod watch;
 
@@ -20,13 +25,19 @@ mod test_framework;
 mod tests;
 
 use crate::{
+    baseline::Baseline,
     blueprint::{
         Blueprint,
+        error::OversizedScript,
+        fuzz,
         schema::{Annotated, Schema},
     },
     config::ProjectConfig,
+    counterexamples::Counterexamples,
+    coverage::ModuleCoverage,
     error::{Error, Warning},
     module::{CheckedModule, CheckedModules, ParsedModule, ParsedModules},
+    mutation::{ModuleMutants, Mutant},
     options::BlueprintExport,
     telemetry::{CoverageMode, Event},
 };
@@ -37,11 +48,12 @@ use aiken_lang::{
         TypedFunction, UntypedDefinition,
     },
     builtins,
+    coverage::instrument_module,
     expr::{TypedExpr, UntypedExpr},
     format::{Formatter, MAX_COLUMNS},
     gen_uplc::CodeGenerator,
     line_numbers::LineNumbers,
-    test_framework::{RunnableKind, Test, TestResult},
+    test_framework::{Limits, RunnableKind, ScenarioScripts, Test, TestResult},
     tipo::{Type, TypeInfo},
     utils,
 };
@@ -53,7 +65,7 @@ use package_name::PackageName;
 use pallas_addresses::{Address, Network, ShelleyAddress, ShelleyDelegationPart, StakePayload};
 use pallas_primitives::conway::PolicyId;
 use std::{
-    collections::{BTreeSet, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs::{self, File},
     io::BufReader,
     path::{Path, PathBuf},
@@ -62,9 +74,18 @@ use std::{
 use telemetry::EventListener;
 use uplc::{
     PlutusData,
-    ast::{Constant, Name, Program},
+    ast::{Constant, Data, Name, Program, Term},
+    flat::breakdown::Breakdown,
+    optimize::{OptLevel, differential::Trace, report::Report},
+    source_map::SourceLocation,
 };
 
+/// How many inputs are made up for each validator handler when checking optimizations.
+const DIFFERENTIAL_INPUTS: usize = 100;
+
+/// How many definitions, and constants, are listed when breaking down the size of a validator.
+const SIZE_BREAKDOWN_ENTRIES: usize = 10;
+
 #[derive(Debug)]
 pub struct Source {
     pub path: PathBuf,
@@ -168,6 +189,7 @@ where
             utils::indexmap::as_str_ref_values(&self.module_sources),
             tracing,
         )
+        .with_opt_level(self.config.build.optimization)
     }
 
     pub fn warnings(&mut self) -> Vec<Warning> {
@@ -182,6 +204,11 @@ where
         self.module_types.keys().cloned().collect()
     }
 
+    /// Interfaces of the modules known to the type-checker, the prelude included.
+    pub fn module_types(&self) -> &HashMap<String, TypeInfo> {
+        &self.module_types
+    }
+
     pub fn checkpoint(&self) -> Checkpoint {
         Checkpoint {
             module_types: self.module_types.clone(),
@@ -201,20 +228,35 @@ where
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn build(
         &mut self,
         uplc: bool,
+        verify_optimizations: bool,
+        optimization: Option<OptLevel>,
+        opt_report: bool,
         tracing: Tracing,
         blueprint_path: PathBuf,
         blueprint_export: BlueprintExport,
+        source_map: bool,
         env: Option<String>,
     ) -> Result<(), Vec<Error>> {
+        // The level given on the command-line prevails over the project's configuration.
+        if let Some(optimization) = optimization {
+            self.config.build.optimization = optimization;
+        }
+
         let options = Options {
-            code_gen_mode: CodeGenMode::Build(uplc),
+            code_gen_mode: CodeGenMode::Build {
+                uplc,
+                verify_optimizations,
+                opt_report,
+            },
             tracing,
             env,
             blueprint_path,
             blueprint_export,
+            source_map,
         };
 
         self.compile(options)
@@ -281,6 +323,12 @@ where
         coverage_mode: CoverageMode,
         tracing: Tracing,
         plain_numbers: bool,
+        profile: bool,
+        coverage: bool,
+        promote: bool,
+        mutate: bool,
+        prove: Option<usize>,
+        verify_optimizations: bool,
         env: Option<String>,
     ) -> Result<(), Vec<Error>> {
         let options = Options {
@@ -297,6 +345,12 @@ where
                     property_max_success,
                     coverage_mode,
                     plain_numbers,
+                    profile,
+                    coverage,
+                    promote,
+                    mutate,
+                    prove,
+                    verify_optimizations,
                 }
             },
             blueprint_path: self.blueprint_path(None),
@@ -315,6 +369,10 @@ where
         max_size: usize,
         tracing: Tracing,
         plain_numbers: bool,
+        profile: bool,
+        baseline: Option<String>,
+        save_baseline: Option<String>,
+        threshold: f64,
         env: Option<String>,
     ) -> Result<(), Vec<Error>> {
         let options = Options {
@@ -326,6 +384,10 @@ where
                 seed,
                 max_size,
                 plain_numbers,
+                profile,
+                baseline,
+                save_baseline,
+                threshold,
             },
             blueprint_path: self.blueprint_path(None),
             ..Options::default()
@@ -403,7 +465,11 @@ where
         self.type_check(&mut modules, options.tracing, env, true)?;
 
         match options.code_gen_mode {
-            CodeGenMode::Build(uplc_dump) => {
+            CodeGenMode::Build {
+                uplc: uplc_dump,
+                verify_optimizations,
+                opt_report,
+            } => {
                 self.event_listener
                     .handle_event(Event::GeneratingBlueprint {
                         path: options.blueprint_path.clone(),
@@ -420,6 +486,7 @@ where
                     &self.checked_modules,
                     &mut generator,
                     options.blueprint_export == BlueprintExport::AllTypes,
+                    options.source_map,
                 )
                 .map_err(|err| Error::Blueprint(err.into()))?;
 
@@ -432,6 +499,32 @@ where
                     self.warnings.push(Warning::NoValidators);
                 }
 
+                if let Some(limit) = self.config.build.max_script_size {
+                    let errors = self.check_script_sizes(&blueprint, limit, options.tracing);
+
+                    if !errors.is_empty() {
+                        return Err(errors);
+                    }
+                }
+
+                if verify_optimizations {
+                    let errors = self.differential_validators(&blueprint, options.tracing);
+
+                    if !errors.is_empty() {
+                        return Err(errors);
+                    }
+                }
+
+                if opt_report {
+                    let reports = self.report_optimizations(options.tracing);
+
+                    self.event_listener
+                        .handle_event(Event::FinishedOptimizations {
+                            level: self.config.build.optimization,
+                            reports,
+                        });
+                }
+
                 if uplc_dump {
                     self.dump_uplc(&blueprint)?;
                 }
@@ -454,16 +547,57 @@ where
                 property_max_success,
                 coverage_mode,
                 plain_numbers,
+                profile,
+                coverage,
+                promote,
+                mutate,
+                prove,
+                verify_optimizations,
             } => {
                 let tests =
                     self.collect_tests(verbose, match_tests, exact_match, options.tracing)?;
 
+                let tests = self.with_counterexamples(tests, promote)?;
+
+                let tests = match prove {
+                    Some(depth) => tests
+                        .into_iter()
+                        .map(|test| test.with_proof_depth(depth))
+                        .collect(),
+                    None => tests,
+                };
+
                 if !tests.is_empty() {
                     self.event_listener.handle_event(Event::RunningTests);
                 }
 
+                if profile {
+                    self.profile_runnables(&tests, "check", seed, property_max_success)?;
+                }
+
+                let coverage = if coverage {
+                    Some(self.coverage_runnables(
+                        &tests,
+                        seed,
+                        property_max_success,
+                        options.tracing,
+                    )?)
+                } else {
+                    None
+                };
+
+                let divergences = if verify_optimizations {
+                    self.differential_runnables(&tests, seed, property_max_success, options.tracing)
+                } else {
+                    Vec::new()
+                };
+
+                let runnables = if mutate { tests.clone() } else { Vec::new() };
+
                 let tests = self.run_runnables(tests, seed, property_max_success);
 
+                self.save_counterexamples(&tests)?;
+
                 self.checks_count = if tests.is_empty() {
                     None
                 } else {
@@ -484,6 +618,7 @@ where
                             Some(Error::from_test_result(e, verbose))
                         }
                     })
+                    .chain(divergences)
                     .collect();
 
                 self.event_listener.handle_event(Event::FinishedTests {
@@ -493,6 +628,25 @@ where
                     plain_numbers,
                 });
 
+                if let Some((path, modules)) = coverage {
+                    self.event_listener
+                        .handle_event(Event::FinishedCoverage { path, modules });
+                }
+
+                // Mutants are only meaningful against a passing test suite, which would
+                // otherwise kill every one of them.
+                if mutate && errors.is_empty() && !runnables.is_empty() {
+                    let modules = self.mutation_runnables(
+                        &runnables,
+                        seed,
+                        property_max_success,
+                        options.tracing,
+                    )?;
+
+                    self.event_listener
+                        .handle_event(Event::FinishedMutations { modules });
+                }
+
                 if !errors.is_empty() {
                     Err(errors)
                 } else {
@@ -505,9 +659,17 @@ where
                 seed,
                 max_size,
                 plain_numbers,
+                profile,
+                baseline,
+                save_baseline,
+                threshold,
             } => {
                 let verbose = false;
 
+                let baseline = baseline
+                    .map(|name| self.load_baseline(&name).map(|baseline| (name, baseline)))
+                    .transpose()?;
+
                 let benchmarks = self.collect_benchmarks(
                     verbose,
                     match_benchmarks,
@@ -519,9 +681,13 @@ where
                     self.event_listener.handle_event(Event::RunningBenchmarks);
                 }
 
+                if profile {
+                    self.profile_runnables(&benchmarks, "bench", seed, max_size)?;
+                }
+
                 let benchmarks = self.run_runnables(benchmarks, seed, max_size);
 
-                let errors: Vec<Error> = benchmarks
+                let mut errors: Vec<Error> = benchmarks
                     .iter()
                     .filter_map(|e| {
                         if e.is_success() {
@@ -532,12 +698,39 @@ where
                     })
                     .collect();
 
+                let current = Baseline::from_results(&benchmarks);
+
+                let report =
+                    baseline.map(|(name, baseline)| baseline.compare(&name, &current, threshold));
+
+                if let Some(report) = &report {
+                    errors.extend(report.regressions().map(|comparison| {
+                        Error::BenchmarkRegression {
+                            module: comparison.module.clone(),
+                            name: comparison.name.clone(),
+                            baseline: report.name.clone(),
+                        }
+                    }));
+                }
+
                 self.event_listener.handle_event(Event::FinishedBenchmarks {
                     seed,
                     benchmarks,
                     plain_numbers,
+                    baseline: report,
                 });
 
+                if let Some(name) = save_baseline {
+                    let path = self.root.join(paths::baseline(&name));
+
+                    self.event_listener.handle_event(Event::SavingBaseline {
+                        name,
+                        path: path.clone(),
+                    });
+
+                    current.save(&path)?;
+                }
+
                 if !errors.is_empty() {
                     Err(errors)
                 } else {
@@ -1072,9 +1265,33 @@ where
                 module_name,
                 input_path,
                 kind,
+                self.config.test.limits(),
             ));
         }
 
+        // Tests returning a transaction are evaluated against the project's validators, so we
+        // only compile those when there's at least one such test.
+        //
+        // NOTE: Validators are compiled with the same tracing as tests. Hence, their hashes are
+        // those of a build with the same trace options.
+        if tests.iter().any(Test::is_scenario) {
+            let blueprint = Blueprint::new(
+                &self.config,
+                &self.checked_modules,
+                &mut self.new_generator(tracing),
+                false,
+                false,
+            )
+            .map_err(|err| Error::Blueprint(err.into()))?;
+
+            let scripts = ScenarioScripts::new(blueprint.into());
+
+            tests = tests
+                .into_iter()
+                .map(|test| test.with_scenario_scripts(&scripts))
+                .collect();
+        }
+
         // NOTE: The filtering syntax for tests isn't quite obvious. A common pitfall when willing
         // to match over a top-level module is to simple pass in `-m module_name`, which will be
         // treated as a match for a test name.
@@ -1154,6 +1371,741 @@ where
             .collect()
     }
 
+    /// Load a baseline previously saved with 'aiken bench --save-baseline'.
+    #[allow(clippy::result_large_err)]
+    fn load_baseline(&self, name: &str) -> Result<Baseline, Error> {
+        let path = self.root.join(paths::baseline(name));
+
+        if !path.exists() {
+            let mut known_baselines: Vec<String> = fs::read_dir(self.root.join(paths::baselines()))
+                .map(|entries| {
+                    entries
+                        .filter_map(|entry| {
+                            let path = entry.ok()?.path();
+                            (path.extension()? == "json")
+                                .then(|| path.file_stem()?.to_str().map(str::to_string))?
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            known_baselines.sort();
+
+            return Err(Error::BaselineNotFound {
+                name: name.to_string(),
+                known_baselines,
+            });
+        }
+
+        Baseline::load(&path)
+    }
+
+    /// Attach to property tests the counterexamples found in previous runs, as well as the
+    /// regressions recorded for them. When promoting, counterexamples of the given tests are
+    /// first moved to the regressions.
+    #[allow(clippy::result_large_err)]
+    fn with_counterexamples(&self, tests: Vec<Test>, promote: bool) -> Result<Vec<Test>, Error> {
+        let counterexamples_path = self.root.join(paths::counterexamples());
+        let regressions_path = self.root.join(paths::regressions());
+
+        let mut counterexamples = Counterexamples::load(&counterexamples_path)?;
+        let mut regressions = Counterexamples::load(&regressions_path)?;
+
+        if promote {
+            let mut count = 0;
+
+            for test in tests
+                .iter()
+                .filter(|test| matches!(test, Test::PropertyTest(..)))
+            {
+                for choices in counterexamples.remove(test.module(), test.name()) {
+                    regressions.insert(test.module(), test.name(), &choices);
+                    count += 1;
+                }
+            }
+
+            self.event_listener
+                .handle_event(Event::PromotingCounterexamples {
+                    path: regressions_path.clone(),
+                    count,
+                });
+
+            regressions.save(&regressions_path)?;
+            counterexamples.save(&counterexamples_path)?;
+        }
+
+        Ok(tests
+            .into_iter()
+            .map(|test| {
+                let mut examples = regressions.get(test.module(), test.name());
+                for choices in counterexamples.get(test.module(), test.name()) {
+                    if !examples.contains(&choices) {
+                        examples.push(choices);
+                    }
+                }
+                test.with_examples(examples)
+            })
+            .collect())
+    }
+
+    /// Record the counterexamples of failing property tests, so they're replayed on the next
+    /// runs, and forget those of passing property tests.
+    #[allow(clippy::result_large_err)]
+    fn save_counterexamples(
+        &self,
+        tests: &[TestResult<UntypedExpr, UntypedExpr>],
+    ) -> Result<(), Error> {
+        let path = self.root.join(paths::counterexamples());
+
+        let mut counterexamples = Counterexamples::load(&path)?;
+
+        for test in tests.iter() {
+            if let TestResult::PropertyTestResult(result) = test {
+                let (module, name) = (result.test.module.as_str(), result.test.name.as_str());
+
+                match (test.is_success(), &result.choices) {
+                    (false, Some(choices)) => counterexamples.insert(module, name, choices),
+                    (true, _) => {
+                        counterexamples.remove(module, name);
+                    }
+                    (false, None) => {}
+                }
+            }
+        }
+
+        counterexamples.save(&path)
+    }
+
+    /// Profile tests or benchmarks and write the resulting reports in the build folder: one JSON
+    /// report, and folded stacks of cpu and mem units, ready to be turned into flamegraphs.
+    #[allow(clippy::result_large_err)]
+    fn profile_runnables(
+        &self,
+        tests: &[Test],
+        kind: &str,
+        seed: u32,
+        max_success: usize,
+    ) -> Result<(), Error> {
+        use rayon::prelude::*;
+
+        let plutus_version = &self.config.plutus;
+
+        let profiles = tests
+            .to_vec()
+            .into_par_iter()
+            .map(|test| {
+                (
+                    format!("{}.{}", test.module(), test.name()),
+                    test.profile(seed, max_success, plutus_version),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let dir = self.root.join(paths::profile());
+
+        self.event_listener
+            .handle_event(Event::ExportingProfile { path: dir.clone() });
+
+        fs::create_dir_all(&dir)?;
+
+        let mut cpu = String::new();
+        let mut mem = String::new();
+
+        for (title, profile) in profiles.iter() {
+            cpu.push_str(&profile.folded(title, |budget| budget.cpu));
+            mem.push_str(&profile.folded(title, |budget| budget.mem));
+        }
+
+        let report = serde_json::json!({
+            "seed": seed,
+            "profiles": profiles
+                .iter()
+                .map(|(title, profile)| profile::to_json(title, profile))
+                .collect::<Vec<_>>(),
+        });
+
+        for (path, contents) in [
+            (dir.join(format!("{kind}.cpu.folded")), cpu),
+            (dir.join(format!("{kind}.mem.folded")), mem),
+            (
+                dir.join(format!("{kind}.json")),
+                serde_json::to_string_pretty(&report).unwrap(),
+            ),
+        ] {
+            fs::write(&path, contents).map_err(|error| Error::FileIo {
+                error,
+                path: Box::new(path),
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Replay tests against instrumented versions of the project's functions and validators,
+    /// recording which functions, handlers and branches they reach. The results are written
+    /// as an LCOV tracefile in the build folder, whose path is returned along with them.
+    #[allow(clippy::result_large_err)]
+    fn coverage_runnables(
+        &self,
+        tests: &[Test],
+        seed: u32,
+        max_success: usize,
+        tracing: Tracing,
+    ) -> Result<(PathBuf, Vec<ModuleCoverage>), Error> {
+        use rayon::prelude::*;
+
+        let mut modules = self
+            .checked_modules
+            .values()
+            .filter(|checked_module| {
+                checked_module.package == self.config.name.to_string()
+                    && (checked_module.kind.is_lib() || checked_module.kind.is_validator())
+            })
+            .collect::<Vec<_>>();
+
+        modules.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut functions = self.functions.clone();
+
+        let mut probes = Vec::with_capacity(modules.len());
+
+        for checked_module in modules.iter() {
+            let (instrumented, module_probes) = instrument_module(&checked_module.ast);
+            functions.extend(instrumented);
+            probes.push(module_probes);
+        }
+
+        let mut generator = CodeGenerator::new(
+            self.config.plutus,
+            utils::indexmap::as_ref_values(&functions),
+            utils::indexmap::as_ref_values(&self.constants),
+            utils::indexmap::as_ref_values(&self.data_types),
+            utils::indexmap::as_str_ref_values(&self.module_types),
+            utils::indexmap::as_str_ref_values(&self.module_sources),
+            tracing,
+        );
+
+        let tests = self.recompile_tests(&mut generator, tests, self.config.test.limits());
+
+        let plutus_version = &self.config.plutus;
+
+        let hits = tests
+            .into_par_iter()
+            .map(|test| test.coverage(seed, max_success, plutus_version))
+            .reduce(BTreeMap::new, |mut hits, more| {
+                for (probe, n) in more {
+                    *hits.entry(probe).or_default() += n;
+                }
+                hits
+            });
+
+        let report = modules
+            .into_iter()
+            .zip(probes)
+            .map(|(checked_module, module_probes)| {
+                let path = checked_module
+                    .input_path
+                    .strip_prefix(&self.root)
+                    .unwrap_or(&checked_module.input_path)
+                    .to_path_buf();
+
+                let line_numbers = LineNumbers::new(&checked_module.code);
+
+                ModuleCoverage::new(
+                    &checked_module.name,
+                    path,
+                    &line_numbers,
+                    module_probes,
+                    &hits,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let dir = self.root.join(paths::coverage());
+
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join("lcov.info");
+
+        fs::write(&path, coverage::to_lcov(&report)).map_err(|error| Error::FileIo {
+            error,
+            path: Box::new(path.clone()),
+        })?;
+
+        Ok((path, report))
+    }
+
+    /// Run tests against mutants of the project's functions and validators, that is, copies of
+    /// them with one small change each. A mutant survives when every test still passes.
+    #[allow(clippy::result_large_err)]
+    fn mutation_runnables(
+        &self,
+        tests: &[Test],
+        seed: u32,
+        max_success: usize,
+        tracing: Tracing,
+    ) -> Result<Vec<ModuleMutants>, Error> {
+        use rayon::prelude::*;
+
+        let mut modules = self
+            .checked_modules
+            .values()
+            .filter(|checked_module| {
+                checked_module.package == self.config.name.to_string()
+                    && (checked_module.kind.is_lib() || checked_module.kind.is_validator())
+            })
+            .collect::<Vec<_>>();
+
+        modules.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mutations = modules
+            .iter()
+            .map(|checked_module| aiken_lang::mutation::mutations(&checked_module.ast))
+            .collect::<Vec<_>>();
+
+        self.event_listener.handle_event(Event::RunningMutations {
+            mutants: mutations.iter().map(Vec::len).sum(),
+        });
+
+        let limits = self.config.test.limits();
+
+        let limits = Limits {
+            timeout: limits.timeout.or(Some(mutation::DEFAULT_TIMEOUT)),
+            ..limits
+        };
+
+        // Scenarios evaluate the validators of the blueprint rather than their handlers, so
+        // those are compiled again from mutated modules when needed.
+        let mut scenario_modules = if tests.iter().any(Test::is_scenario) {
+            Some(self.checked_modules.clone())
+        } else {
+            None
+        };
+
+        let plutus_version = &self.config.plutus;
+
+        let mut functions = self.functions.clone();
+
+        let mut report = Vec::with_capacity(modules.len());
+
+        for (checked_module, mutations) in modules.into_iter().zip(mutations) {
+            let line_numbers = LineNumbers::new(&checked_module.code);
+
+            let mut mutants = Vec::with_capacity(mutations.len());
+
+            for mutation in mutations {
+                let mutated = aiken_lang::mutation::mutate_module(&checked_module.ast, &mutation)
+                    .expect("mutation listed from the same module");
+
+                mutated.register_definitions(
+                    &mut functions,
+                    &mut IndexMap::new(),
+                    &mut IndexMap::new(),
+                );
+
+                let mut generator = CodeGenerator::new(
+                    self.config.plutus,
+                    utils::indexmap::as_ref_values(&functions),
+                    utils::indexmap::as_ref_values(&self.constants),
+                    utils::indexmap::as_ref_values(&self.data_types),
+                    utils::indexmap::as_str_ref_values(&self.module_types),
+                    utils::indexmap::as_str_ref_values(&self.module_sources),
+                    tracing,
+                );
+
+                let mut mutant_tests = self.recompile_tests(&mut generator, tests, limits);
+
+                if let Some(scenario_modules) = scenario_modules.as_mut() {
+                    if let Some(scenario_module) = scenario_modules.get_mut(&checked_module.name) {
+                        scenario_module.ast = mutated;
+                    }
+
+                    let blueprint = Blueprint::new(
+                        &self.config,
+                        scenario_modules,
+                        &mut generator,
+                        false,
+                        false,
+                    )
+                    .map_err(|err| Error::Blueprint(err.into()))?;
+
+                    let scripts = ScenarioScripts::new(blueprint.into());
+
+                    mutant_tests = mutant_tests
+                        .into_iter()
+                        .map(|test| test.with_scenario_scripts(&scripts))
+                        .collect();
+                }
+
+                drop(generator);
+
+                let killed_by = mutant_tests.into_par_iter().find_map_any(|test| {
+                    let title = format!("{}.{}", test.module(), test.name());
+
+                    if test.run(seed, max_success, plutus_version).is_success() {
+                        None
+                    } else {
+                        Some(title)
+                    }
+                });
+
+                mutants.push(Mutant::new(mutation, &line_numbers, killed_by));
+
+                // Restore the original definitions for the next mutant.
+                checked_module.ast.register_definitions(
+                    &mut functions,
+                    &mut IndexMap::new(),
+                    &mut IndexMap::new(),
+                );
+
+                if let Some(scenario_modules) = scenario_modules.as_mut() {
+                    if let Some(scenario_module) = scenario_modules.get_mut(&checked_module.name) {
+                        scenario_module.ast = checked_module.ast.clone();
+                    }
+                }
+            }
+
+            report.push(ModuleMutants {
+                name: checked_module.name.clone(),
+                path: checked_module
+                    .input_path
+                    .strip_prefix(&self.root)
+                    .unwrap_or(&checked_module.input_path)
+                    .to_path_buf(),
+                mutants,
+            });
+        }
+
+        Ok(report)
+    }
+
+    /// Evaluate tests before and after every optimization pass, on the values they're run with.
+    /// Tests are compiled anew, without optimizations, to that end.
+    fn differential_runnables(
+        &self,
+        tests: &[Test],
+        seed: u32,
+        max_success: usize,
+        tracing: Tracing,
+    ) -> Vec<Error> {
+        let mut generator = self.new_generator(tracing).without_optimization();
+
+        let unoptimized = self.recompile_tests(&mut generator, tests, self.config.test.limits());
+
+        let plutus_version = &self.config.plutus;
+
+        let data_types = utils::indexmap::as_ref_values(&self.data_types);
+
+        // Divergences hold values which can't be sent across threads, so unlike runs, these
+        // evaluations are sequential.
+        tests
+            .iter()
+            .zip(unoptimized)
+            .filter_map(|(test, unoptimized)| {
+                let (value, divergence) = test.differential(
+                    &unoptimized,
+                    self.config.build.optimization,
+                    seed,
+                    max_success,
+                    plutus_version,
+                )?;
+                Some((test, value, divergence))
+            })
+            .map(|(test, value, divergence)| Error::OptimizerDivergence {
+                title: format!("{}.{}", test.module(), test.name()),
+                input: value.map(|value| match &test {
+                    Test::PropertyTest(property_test) => UntypedExpr::reify_data(
+                        &data_types,
+                        value.clone(),
+                        property_test.fuzzer.type_info.clone(),
+                    )
+                    .map(|expr| {
+                        Formatter::new()
+                            .expr(&expr, false)
+                            .to_pretty_string(MAX_COLUMNS)
+                    })
+                    .unwrap_or_else(|_| format!("#{}", Data::to_hex(value))),
+                    _ => format!("#{}", Data::to_hex(value)),
+                }),
+                divergence: Box::new(divergence),
+            })
+            .collect()
+    }
+
+    /// Check the flat-encoded size of every validator against the given limit. Validators
+    /// exceeding it are compiled anew, along with a source map, to break their size down.
+    fn check_script_sizes(
+        &self,
+        blueprint: &Blueprint,
+        limit: usize,
+        tracing: Tracing,
+    ) -> Vec<Error> {
+        let mut generator = self.new_generator(tracing);
+
+        let mut errors = Vec::new();
+
+        let modules = self.checked_modules.values().filter(|checked_module| {
+            checked_module.package == self.config.name.to_string()
+                && checked_module.kind.is_validator()
+        });
+
+        for checked_module in modules {
+            for validator in checked_module
+                .ast
+                .definitions()
+                .filter_map(|def| match def {
+                    Definition::Validator(validator) => Some(validator),
+                    _ => None,
+                })
+            {
+                let prefix = format!("{}.{}.", checked_module.name, validator.name);
+
+                let Some(size) = blueprint
+                    .validators
+                    .iter()
+                    .find(|handler| handler.title.starts_with(&prefix))
+                    .and_then(|handler| handler.program.inner().to_flat().ok())
+                    .map(|bytes| bytes.len())
+                else {
+                    continue;
+                };
+
+                if size <= limit {
+                    continue;
+                }
+
+                let (program, source_map) =
+                    generator.generate_with_source_map(validator, &checked_module.name);
+
+                let (definitions, constants) = match Breakdown::new(&program) {
+                    None => (vec![], vec![]),
+                    Some(breakdown) => (
+                        breakdown
+                            .definitions
+                            .into_iter()
+                            .filter(|definition| definition.size > 0)
+                            // Functions are either located in the source, or helpers generated
+                            // by the compiler. Other bindings are merely local variables.
+                            .filter_map(|definition| {
+                                let name = match source_map.get(&definition.path) {
+                                    Some(SourceLocation {
+                                        module,
+                                        name: Some(name),
+                                        ..
+                                    }) => format!("{module}.{name}"),
+                                    _ => match program.term.at_path(&definition.path) {
+                                        Some(Term::Lambda { .. }) => {
+                                            format!("{} (helper)", definition.name)
+                                        }
+                                        _ => return None,
+                                    },
+                                };
+                                Some((name, definition.size))
+                            })
+                            .take(SIZE_BREAKDOWN_ENTRIES)
+                            .collect(),
+                        breakdown
+                            .constants
+                            .into_iter()
+                            .filter(|constants| constants.size > 0)
+                            .take(SIZE_BREAKDOWN_ENTRIES)
+                            .map(|constants| {
+                                let mut name = Term::<Name>::Constant(constants.constant)
+                                    .to_pretty()
+                                    .split_whitespace()
+                                    .collect::<Vec<_>>()
+                                    .join(" ");
+
+                                if name.chars().count() > 48 {
+                                    name =
+                                        format!("{}...", name.chars().take(45).collect::<String>());
+                                }
+
+                                if constants.occurrences > 1 {
+                                    name = format!("{name} (×{})", constants.occurrences);
+                                }
+
+                                (name, constants.size)
+                            })
+                            .collect(),
+                    ),
+                };
+
+                errors.push(Error::Blueprint(
+                    blueprint::error::Error::ScriptTooLarge {
+                        script: Box::new(OversizedScript {
+                            validator: format!("{}.{}", checked_module.name, validator.name),
+                            size,
+                            limit,
+                            definitions,
+                            constants,
+                        }),
+                        location: validator.location,
+                        source_code: NamedSource::new(
+                            checked_module.input_path.display().to_string(),
+                            checked_module.code.clone(),
+                        ),
+                    }
+                    .into(),
+                ));
+            }
+        }
+
+        errors
+    }
+
+    /// Optimize the project's validators anew, gathering statistics about every pass.
+    fn report_optimizations(&self, tracing: Tracing) -> Vec<(String, Report)> {
+        let mut generator = self.new_generator(tracing).without_optimization();
+
+        self.checked_modules
+            .values()
+            .filter(|checked_module| {
+                checked_module.package == self.config.name.to_string()
+                    && checked_module.kind.is_validator()
+            })
+            .flat_map(|checked_module| {
+                checked_module
+                    .ast
+                    .definitions()
+                    .filter_map(|def| match def {
+                        Definition::Validator(validator) => Some(validator),
+                        _ => None,
+                    })
+                    .map(|validator| {
+                        let (_, report) = Report::new(
+                            generator.generate(validator, &checked_module.name),
+                            self.config.build.optimization,
+                        );
+
+                        (
+                            format!("{}.{}", checked_module.name, validator.name),
+                            report,
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Evaluate validators before and after every optimization pass, on inputs made up from the
+    /// schemas of their handlers as 'aiken blueprint fuzz' does. Only the spend, mint and withdraw
+    /// handlers of Plutus V3 validators can be given such inputs; others are left out.
+    fn differential_validators(&self, blueprint: &Blueprint, tracing: Tracing) -> Vec<Error> {
+        let mut generator = self.new_generator(tracing).without_optimization();
+
+        let language = (&self.config.plutus).into();
+
+        let mut errors = Vec::new();
+
+        let modules = self.checked_modules.values().filter(|checked_module| {
+            checked_module.package == self.config.name.to_string()
+                && checked_module.kind.is_validator()
+        });
+
+        for checked_module in modules {
+            for validator in checked_module
+                .ast
+                .definitions()
+                .filter_map(|def| match def {
+                    Definition::Validator(validator) => Some(validator),
+                    _ => None,
+                })
+            {
+                let prefix = format!("{}.{}.", checked_module.name, validator.name);
+
+                let trace = Trace::new(
+                    generator.generate(validator, &checked_module.name),
+                    self.config.build.optimization,
+                );
+
+                for handler in blueprint
+                    .validators
+                    .iter()
+                    .filter(|handler| handler.title.starts_with(&prefix))
+                {
+                    let (Some(purpose), Ok(fuzzer)) = (
+                        fuzz::Purpose::from_title(&handler.title),
+                        fuzz::Fuzzer::new(handler, &blueprint.definitions, 0),
+                    ) else {
+                        continue;
+                    };
+
+                    let divergence =
+                        fuzzer
+                            .inputs(DIFFERENTIAL_INPUTS)
+                            .into_iter()
+                            .find_map(|input| {
+                                trace
+                                    .compare(&language, |program| {
+                                        fuzz::apply(purpose, program, &input)
+                                    })
+                                    .map(|divergence| (input, divergence))
+                            });
+
+                    if let Some((input, divergence)) = divergence {
+                        let mut data = input
+                            .parameters
+                            .iter()
+                            .enumerate()
+                            .map(|(ix, parameter)| {
+                                format!("parameter {} #{}", ix + 1, Data::to_hex(parameter.clone()))
+                            })
+                            .collect::<Vec<_>>();
+
+                        if let Some(datum) = input.datum {
+                            data.push(format!("datum #{}", Data::to_hex(datum)));
+                        }
+
+                        data.push(format!("redeemer #{}", Data::to_hex(input.redeemer)));
+
+                        errors.push(Error::OptimizerDivergence {
+                            title: handler.title.clone(),
+                            input: Some(data.join(", ")),
+                            divergence: Box::new(divergence),
+                        });
+                    }
+                }
+            }
+        }
+
+        errors
+    }
+
+    /// Compile tests anew from their definitions, with a generator which may know of different
+    /// functions than the one they were first compiled with.
+    fn recompile_tests(
+        &self,
+        generator: &mut CodeGenerator<'_>,
+        tests: &[Test],
+        limits: Limits,
+    ) -> Vec<Test> {
+        tests
+            .iter()
+            .filter_map(|test| {
+                let checked_module = self.checked_modules.get(test.module())?;
+
+                checked_module
+                    .ast
+                    .definitions()
+                    .find_map(|def| match def {
+                        Definition::Test(func) if func.name == test.name() => Some(func),
+                        _ => None,
+                    })
+                    .map(|func| {
+                        Test::from_function_definition(
+                            generator,
+                            func.to_owned(),
+                            checked_module.name.clone(),
+                            checked_module.input_path.clone(),
+                            RunnableKind::Test,
+                            limits,
+                        )
+                    })
+            })
+            .collect()
+    }
+
     #[allow(clippy::result_large_err)]
     fn aiken_files(&mut self, dir: &Path, kind: ModuleKind) -> Result<(), Error> {
         let mut has_default = None;
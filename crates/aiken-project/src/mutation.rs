@@ -0,0 +1,61 @@
+use aiken_lang::{
+    line_numbers::{LineColumn, LineNumbers},
+    mutation::{Mutation, MutationKind},
+};
+use std::{path::PathBuf, time::Duration};
+
+/// How long tests may run against a mutant, unless a timeout is configured for tests already.
+/// Mutations easily turn recursions into endless loops, which would otherwise only stop once
+/// the maximum budget is exhausted.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A mutant, and the first test found to fail because of it, if any.
+#[derive(Debug, Clone)]
+pub struct Mutant {
+    pub function: String,
+    pub line: usize,
+    pub column: usize,
+    pub kind: MutationKind,
+    pub killed_by: Option<String>,
+}
+
+impl Mutant {
+    pub fn new(mutation: Mutation, line_numbers: &LineNumbers, killed_by: Option<String>) -> Self {
+        let LineColumn { line, column } = line_numbers
+            .line_and_column_number(mutation.location.start)
+            .unwrap_or(LineColumn { line: 0, column: 0 });
+
+        Mutant {
+            function: mutation.function,
+            line,
+            column,
+            kind: mutation.kind,
+            killed_by,
+        }
+    }
+
+    pub fn survived(&self) -> bool {
+        self.killed_by.is_none()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModuleMutants {
+    pub name: String,
+    pub path: PathBuf,
+    pub mutants: Vec<Mutant>,
+}
+
+impl ModuleMutants {
+    pub fn survivors(&self) -> impl Iterator<Item = &Mutant> {
+        self.mutants.iter().filter(|mutant| mutant.survived())
+    }
+
+    /// Number of mutants killed by tests, over their total.
+    pub fn killed_ratio(&self) -> (usize, usize) {
+        (
+            self.mutants.len() - self.survivors().count(),
+            self.mutants.len(),
+        )
+    }
+}
@@ -8,6 +8,7 @@ pub struct Options {
     pub env: Option<String>,
     pub blueprint_path: PathBuf,
     pub blueprint_export: BlueprintExport,
+    pub source_map: bool,
 }
 
 impl Default for Options {
@@ -18,6 +19,7 @@ impl Default for Options {
             env: None,
             blueprint_path: PathBuf::from("plutus.json"),
             blueprint_export: BlueprintExport::OnlyBinaryInterface,
+            source_map: false,
         }
     }
 }
@@ -47,14 +49,28 @@ pub enum CodeGenMode {
         property_max_success: usize,
         coverage_mode: CoverageMode,
         plain_numbers: bool,
+        profile: bool,
+        coverage: bool,
+        promote: bool,
+        mutate: bool,
+        prove: Option<usize>,
+        verify_optimizations: bool,
+    },
+    Build {
+        uplc: bool,
+        verify_optimizations: bool,
+        opt_report: bool,
     },
-    Build(bool),
     Benchmark {
         match_benchmarks: Option<Vec<String>>,
         exact_match: bool,
         seed: u32,
         max_size: usize,
         plain_numbers: bool,
+        profile: bool,
+        baseline: Option<String>,
+        save_baseline: Option<String>,
+        threshold: f64,
     },
     NoOp,
 }
@@ -20,6 +20,30 @@ pub fn build() -> PathBuf {
     PathBuf::from("build")
 }
 
+pub fn profile() -> PathBuf {
+    build().join("profile")
+}
+
+pub fn coverage() -> PathBuf {
+    build().join("coverage")
+}
+
+pub fn baselines() -> PathBuf {
+    build().join("baselines")
+}
+
+pub fn baseline(name: &str) -> PathBuf {
+    baselines().join(format!("{name}.json"))
+}
+
+pub fn counterexamples() -> PathBuf {
+    build().join("counterexamples.json")
+}
+
+pub fn regressions() -> PathBuf {
+    PathBuf::from("regressions.json")
+}
+
 pub fn packages() -> PathBuf {
     build().join("packages")
 }
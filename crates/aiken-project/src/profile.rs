@@ -0,0 +1,46 @@
+use serde_json::json;
+use uplc::machine::{cost_model::ExBudget, profiler::Profile};
+
+/// Summarize a profile as JSON, listing functions from the most to the least
+/// expensive in cpu units spent in their own body, and likewise lines of source
+/// code.
+pub fn to_json(title: &str, profile: &Profile) -> serde_json::Value {
+    let mut functions = profile.functions().collect::<Vec<_>>();
+
+    functions.sort_by_key(|(_, function)| std::cmp::Reverse(function.self_budget.cpu));
+
+    let mut lines = profile.lines().collect::<Vec<_>>();
+
+    lines.sort_by_key(|(_, budget)| std::cmp::Reverse(budget.cpu));
+
+    json!({
+        "title": title,
+        "execution_units": execution_units(&profile.total()),
+        "functions": functions
+            .into_iter()
+            .map(|(name, function)| json!({
+                "name": name,
+                "module": function.location.module,
+                "line": function.location.line,
+                "calls": function.calls,
+                "self": execution_units(&function.self_budget),
+                "total": execution_units(&function.total_budget),
+            }))
+            .collect::<Vec<_>>(),
+        "lines": lines
+            .into_iter()
+            .map(|((module, line), budget)| json!({
+                "module": module,
+                "line": line,
+                "self": execution_units(budget),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn execution_units(budget: &ExBudget) -> serde_json::Value {
+    json!({
+        "mem": budget.mem,
+        "cpu": budget.cpu,
+    })
+}
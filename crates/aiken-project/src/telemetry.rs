@@ -1,3 +1,4 @@
+use crate::{baseline::BaselineReport, coverage::ModuleCoverage, mutation::ModuleMutants};
 use aiken_lang::{
     expr::UntypedExpr,
     test_framework::{BenchmarkResult, PropertyTestResult, TestResult, UnitTestResult},
@@ -10,7 +11,10 @@ use std::{
     path::PathBuf,
 };
 pub use terminal::Terminal;
-use uplc::machine::cost_model::ExBudget;
+use uplc::{
+    machine::cost_model::ExBudget,
+    optimize::{OptLevel, report::Report},
+};
 
 mod json;
 mod terminal;
@@ -39,6 +43,17 @@ pub enum Event {
     DumpingUPLC {
         path: PathBuf,
     },
+    ExportingProfile {
+        path: PathBuf,
+    },
+    PromotingCounterexamples {
+        path: PathBuf,
+        count: usize,
+    },
+    SavingBaseline {
+        name: String,
+        path: PathBuf,
+    },
     GeneratingUPLCFor {
         name: String,
         path: PathBuf,
@@ -55,10 +70,26 @@ pub enum Event {
         tests: Vec<TestResult<UntypedExpr, UntypedExpr>>,
         plain_numbers: bool,
     },
+    FinishedCoverage {
+        path: PathBuf,
+        modules: Vec<ModuleCoverage>,
+    },
+    RunningMutations {
+        mutants: usize,
+    },
+    FinishedMutations {
+        modules: Vec<ModuleMutants>,
+    },
+    FinishedOptimizations {
+        level: OptLevel,
+        /// Statistics about each pass, for every validator.
+        reports: Vec<(String, Report)>,
+    },
     FinishedBenchmarks {
         seed: u32,
         benchmarks: Vec<TestResult<UntypedExpr, UntypedExpr>>,
         plain_numbers: bool,
+        baseline: Option<BaselineReport>,
     },
     WaitingForBuildDirLock,
     ResolvingPackages {
@@ -1,11 +1,15 @@
 use super::{Event, EventListener, group_by_module};
+use crate::baseline::{BaselineReport, Change};
 use aiken_lang::{
     ast::OnTestFailure,
     expr::UntypedExpr,
     format::Formatter,
-    test_framework::{AssertionStyleOptions, PropertyTestResult, TestResult, UnitTestResult},
+    test_framework::{
+        AssertionStyleOptions, LimitExceeded, Proof, PropertyTestResult, TestResult, UnitTestResult,
+    },
 };
 use serde_json::json;
+use uplc::optimize::report::Stats;
 
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Json;
@@ -40,7 +44,10 @@ impl EventListener for Json {
                 println!("{}", serde_json::to_string_pretty(&json_output).unwrap());
             }
             Event::FinishedBenchmarks {
-                benchmarks, seed, ..
+                benchmarks,
+                seed,
+                baseline,
+                ..
             } => {
                 let benchmark_results: Vec<_> = benchmarks
                     .into_iter()
@@ -64,11 +71,67 @@ impl EventListener for Json {
                     })
                     .collect();
 
-                let json = serde_json::json!({
+                let mut json = serde_json::json!({
                     "benchmarks": benchmark_results,
                     "seed": seed,
                 });
 
+                if let Some(report) = baseline {
+                    json["baseline"] = fmt_baseline_report_json(&report);
+                }
+
+                println!("{}", serde_json::to_string_pretty(&json).unwrap());
+            }
+            // The LCOV report is the machine-readable output for coverage.
+            Event::FinishedCoverage { .. } => {}
+            Event::FinishedMutations { modules } => {
+                let json = json!({
+                    "mutants": modules.iter().map(|module| {
+                        let (killed, total) = module.killed_ratio();
+                        json!({
+                            "name": module.name,
+                            "path": module.path,
+                            "killed": killed,
+                            "total": total,
+                            "mutants": module.mutants.iter().map(|mutant| json!({
+                                "function": mutant.function,
+                                "line": mutant.line,
+                                "column": mutant.column,
+                                "mutation": mutant.kind.to_string(),
+                                "status": if mutant.survived() { "survived" } else { "killed" },
+                                "killed_by": mutant.killed_by,
+                            })).collect::<Vec<_>>(),
+                        })
+                    }).collect::<Vec<_>>(),
+                });
+
+                println!("{}", serde_json::to_string_pretty(&json).unwrap());
+            }
+            Event::FinishedOptimizations { level, reports } => {
+                let stats = |stats: &Stats| {
+                    json!({
+                        "nodes": stats.node_count,
+                        "flat_size": stats.flat_size,
+                        "estimated_cost": {
+                            "mem": stats.cost.mem,
+                            "cpu": stats.cost.cpu,
+                        },
+                    })
+                };
+
+                let json = json!({
+                    "level": level.to_string(),
+                    "validators": reports.iter().map(|(title, report)| json!({
+                        "title": title,
+                        "original": stats(&report.original),
+                        "passes": report.passes.iter().map(|(pass, after)| {
+                            let mut json = stats(after);
+                            json["pass"] = json!(pass.to_string());
+                            json
+                        }).collect::<Vec<_>>(),
+                    })).collect::<Vec<_>>(),
+                });
+
                 println!("{}", serde_json::to_string_pretty(&json).unwrap());
             }
             _ => super::Terminal.handle_event(event),
@@ -76,6 +139,44 @@ impl EventListener for Json {
     }
 }
 
+fn fmt_baseline_report_json(report: &BaselineReport) -> serde_json::Value {
+    let fmt_change = |change: &Change| {
+        json!({
+            "before": change.before.round() as i64,
+            "after": change.after.round() as i64,
+            "change": change.relative(),
+            "growth": {
+                "baseline": change.baseline.growth.to_string(),
+                "current": change.current.growth.to_string(),
+            },
+        })
+    };
+
+    json!({
+        "name": report.name,
+        "threshold": report.threshold,
+        "comparisons": report.comparisons.iter().map(|comparison| {
+            let status = if comparison.is_regression(report.threshold) {
+                "regression"
+            } else if comparison.is_improvement(report.threshold) {
+                "improvement"
+            } else {
+                "unchanged"
+            };
+
+            json!({
+                "module": comparison.module,
+                "name": comparison.name,
+                "size": comparison.size,
+                "status": status,
+                "memory": fmt_change(&comparison.mem),
+                "cpu": fmt_change(&comparison.cpu),
+            })
+        }).collect::<Vec<_>>(),
+        "new": report.new,
+    })
+}
+
 fn fmt_test_json(result: &TestResult<UntypedExpr, UntypedExpr>) -> serde_json::Value {
     let on_test_failure = match result {
         TestResult::UnitTestResult(UnitTestResult { test, .. }) => &test.on_test_failure,
@@ -83,9 +184,16 @@ fn fmt_test_json(result: &TestResult<UntypedExpr, UntypedExpr>) -> serde_json::V
         TestResult::BenchmarkResult(_) => unreachable!("benchmark returned in JSON output"),
     };
 
+    let status = match result.limit_exceeded() {
+        Some(LimitExceeded::Budget(..)) => "budget_exceeded",
+        Some(LimitExceeded::Timeout(..)) => "timeout",
+        None if result.is_success() => "pass",
+        None => "fail",
+    };
+
     let mut test = json!({
         "title": result.title(),
-        "status": if result.is_success() { "pass" } else { "fail" },
+        "status": status,
         "on_failure": match on_test_failure {
             OnTestFailure::FailImmediately => "fail_immediately" ,
             OnTestFailure::SucceedEventually => "succeed_eventually" ,
@@ -97,23 +205,28 @@ fn fmt_test_json(result: &TestResult<UntypedExpr, UntypedExpr>) -> serde_json::V
         TestResult::UnitTestResult(UnitTestResult {
             spent_budget,
             assertion,
+            failed_at,
             ..
         }) => {
             test["execution_units"] = json!({
                 "mem": spent_budget.mem,
                 "cpu": spent_budget.cpu,
             });
-            if !result.is_success() {
+            if !result.is_success() && result.limit_exceeded().is_none() {
                 if let Some(assertion) = assertion {
                     test["assertion"] =
                         json!(assertion.to_string(false, &AssertionStyleOptions::new(None)));
                 }
             }
+            if let Some(location) = failed_at {
+                test["failed_at"] = json!({ "module": location.module, "line": location.line });
+            }
         }
         TestResult::PropertyTestResult(PropertyTestResult {
             iterations,
             labels,
             counterexample,
+            proof,
             ..
         }) => {
             test["iterations"] = json!(iterations);
@@ -125,10 +238,30 @@ fn fmt_test_json(result: &TestResult<UntypedExpr, UntypedExpr>) -> serde_json::V
                 Ok(None) => json!(null),
                 Err(err) => json!({"error": err.to_string()}),
             };
+            if let Some(proof) = proof {
+                test["proof"] = match proof {
+                    Proof::Proved { paths } => json!({ "status": "proved", "paths": paths }),
+                    Proof::Bounded { paths, depth } => {
+                        json!({ "status": "bounded", "paths": paths, "depth": depth })
+                    }
+                    Proof::Refuted(value) => json!({ "status": "refuted", "value": value }),
+                    Proof::Unknown(reason) => json!({ "status": "unknown", "reason": reason }),
+                };
+            }
         }
         TestResult::BenchmarkResult(_) => unreachable!("benchmark returned in JSON output"),
     }
 
+    match result.limit_exceeded() {
+        Some(LimitExceeded::Budget(budget)) => {
+            test["limit"] = json!({ "mem": budget.mem, "cpu": budget.cpu });
+        }
+        Some(LimitExceeded::Timeout(timeout)) => {
+            test["limit"] = json!({ "timeout_ms": timeout.as_millis() });
+        }
+        None => {}
+    }
+
     if !result.logs().is_empty() {
         test["traces"] = json!(result.logs());
     }
@@ -191,7 +324,26 @@ pub fn json_schema() -> serde_json::Value {
       },
       "Status": {
        "type": "string",
-       "enum": [ "pass", "fail" ]
+       "enum": [ "pass", "fail", "budget_exceeded", "timeout" ]
+      },
+      "Limit": {
+        "oneOf": [
+          {
+            "type": "object",
+            "required": [ "mem", "cpu" ],
+            "properties": {
+              "mem": { "type": "integer" },
+              "cpu": { "type": "integer" }
+            }
+          },
+          {
+            "type": "object",
+            "required": [ "timeout_ms" ],
+            "properties": {
+              "timeout_ms": { "type": "integer" }
+            }
+          }
+        ]
       },
       "OnFailure": {
        "type": "string",
@@ -228,6 +380,7 @@ pub fn json_schema() -> serde_json::Value {
             }
         },
         "assertion": { "type": "string" },
+        "limit": { "$ref": "#/properties/definitions/Limit" },
       }
     });
 
@@ -265,6 +418,20 @@ pub fn json_schema() -> serde_json::Value {
               }
             }
           ]
+        },
+        "limit": { "$ref": "#/properties/definitions/Limit" },
+        "proof": {
+          "type": "object",
+          "required": [ "status" ],
+          "properties": {
+            "status": {
+              "type": "string",
+              "enum": [ "proved", "bounded", "refuted", "unknown" ]
+            },
+            "paths": { "type": "integer" },
+            "depth": { "type": "integer" },
+            "reason": { "type": "string" }
+          }
         }
       }
     });
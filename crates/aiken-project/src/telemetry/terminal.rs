@@ -1,11 +1,18 @@
 use super::{DownloadSource, Event, EventListener, find_max_execution_units, group_by_module};
-use crate::{CoverageMode, pretty};
+use crate::{
+    CoverageMode,
+    baseline::{BaselineReport, Change, Comparison},
+    coverage::ModuleCoverage,
+    mutation::ModuleMutants,
+    pretty,
+};
 use aiken_lang::{
     ast::OnTestFailure,
     expr::UntypedExpr,
     format::Formatter,
     test_framework::{
-        AssertionStyleOptions, BenchmarkResult, PropertyTestResult, TestResult, UnitTestResult,
+        AssertionStyleOptions, BenchmarkResult, Proof, PropertyTestResult, TestResult,
+        UnitTestResult,
     },
 };
 use numfmt::{Precision, Scales};
@@ -68,6 +75,43 @@ impl EventListener for Terminal {
                         .if_supports_color(Stderr, |s| s.purple())
                 );
             }
+            Event::ExportingProfile { path } => {
+                eprintln!(
+                    "{} {} ({})",
+                    "    Exporting"
+                        .if_supports_color(Stderr, |s| s.bold())
+                        .if_supports_color(Stderr, |s| s.purple()),
+                    "profile".if_supports_color(Stderr, |s| s.bold()),
+                    path.display()
+                        .if_supports_color(Stderr, |s| s.bright_blue())
+                );
+            }
+            Event::PromotingCounterexamples { path, count } => {
+                eprintln!(
+                    "{} {} ({})",
+                    "    Promoting"
+                        .if_supports_color(Stderr, |s| s.bold())
+                        .if_supports_color(Stderr, |s| s.purple()),
+                    format!(
+                        "{count} counterexample{}",
+                        if count == 1 { "" } else { "s" }
+                    )
+                    .if_supports_color(Stderr, |s| s.bold()),
+                    path.display()
+                        .if_supports_color(Stderr, |s| s.bright_blue())
+                );
+            }
+            Event::SavingBaseline { name, path } => {
+                eprintln!(
+                    "{} {} ({})",
+                    "       Saving"
+                        .if_supports_color(Stderr, |s| s.bold())
+                        .if_supports_color(Stderr, |s| s.purple()),
+                    format!("baseline '{name}'").if_supports_color(Stderr, |s| s.bold()),
+                    path.display()
+                        .if_supports_color(Stderr, |s| s.bright_blue())
+                );
+            }
             Event::DumpingUPLC { path } => {
                 eprintln!(
                     "{} {} ({})",
@@ -240,6 +284,138 @@ impl EventListener for Terminal {
                     println!();
                 }
             }
+            Event::FinishedCoverage { path, modules } => {
+                eprintln!(
+                    "{} {} ({})",
+                    "    Exporting"
+                        .if_supports_color(Stderr, |s| s.bold())
+                        .if_supports_color(Stderr, |s| s.purple()),
+                    "coverage".if_supports_color(Stderr, |s| s.bold()),
+                    path.display()
+                        .if_supports_color(Stderr, |s| s.bright_blue())
+                );
+
+                if modules.is_empty() {
+                    return;
+                }
+
+                let title = "coverage"
+                    .if_supports_color(Stderr, |s| s.bold())
+                    .if_supports_color(Stderr, |s| s.blue())
+                    .to_string();
+
+                let max_name = modules
+                    .iter()
+                    .map(|m| m.name.len())
+                    .max()
+                    .unwrap_or_default();
+
+                let body = modules
+                    .iter()
+                    .map(|module| fmt_module_coverage(module, max_name))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+
+                let (functions_hit, functions) = modules
+                    .iter()
+                    .map(|module| module.functions_ratio())
+                    .fold((0, 0), |(hit, total), (h, t)| (hit + h, total + t));
+
+                let (branches_hit, branches) = modules
+                    .iter()
+                    .map(|module| module.branches_ratio())
+                    .fold((0, 0), |(hit, total), (h, t)| (hit + h, total + t));
+
+                let summary = format!(
+                    "{} | {}",
+                    format!("{functions_hit}/{functions} functions")
+                        .if_supports_color(Stderr, |s| s.bold()),
+                    format!("{branches_hit}/{branches} branches")
+                        .if_supports_color(Stderr, |s| s.bold()),
+                );
+
+                println!(
+                    "\n{}\n",
+                    pretty::indent(
+                        &pretty::open_box(&title, &body, &summary, |border| border
+                            .if_supports_color(Stderr, |s| s.bright_black())
+                            .to_string()),
+                        4
+                    )
+                );
+            }
+            Event::RunningMutations { mutants } => {
+                eprintln!(
+                    "{} {}",
+                    "     Mutating"
+                        .if_supports_color(Stderr, |s| s.bold())
+                        .if_supports_color(Stderr, |s| s.purple()),
+                    format!(
+                        "{mutants} mutant{} ...",
+                        if mutants == 1 { "" } else { "s" }
+                    )
+                    .if_supports_color(Stderr, |s| s.bold())
+                );
+            }
+            Event::FinishedMutations { modules } => {
+                if modules.is_empty() {
+                    return;
+                }
+
+                let title = "mutants"
+                    .if_supports_color(Stderr, |s| s.bold())
+                    .if_supports_color(Stderr, |s| s.blue())
+                    .to_string();
+
+                let max_name = modules
+                    .iter()
+                    .map(|m| m.name.len())
+                    .max()
+                    .unwrap_or_default();
+
+                let body = modules
+                    .iter()
+                    .map(|module| fmt_module_mutants(module, max_name))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+
+                let (killed, total) = modules
+                    .iter()
+                    .map(|module| module.killed_ratio())
+                    .fold((0, 0), |(killed, total), (k, t)| (killed + k, total + t));
+
+                let summary = format!(
+                    "{} | {}",
+                    format!("{killed}/{total} killed").if_supports_color(Stderr, |s| s.bold()),
+                    format!("{} survived", total - killed)
+                        .if_supports_color(Stderr, |s| s.bright_red())
+                        .if_supports_color(Stderr, |s| s.bold()),
+                );
+
+                println!(
+                    "\n{}\n",
+                    pretty::indent(
+                        &pretty::open_box(&title, &body, &summary, |border| border
+                            .if_supports_color(Stderr, |s| s.bright_black())
+                            .to_string()),
+                        4
+                    )
+                );
+            }
+            Event::FinishedOptimizations { level, reports } => {
+                for (title, report) in reports {
+                    eprintln!(
+                        "{} {} {}",
+                        "    Optimized"
+                            .if_supports_color(Stderr, |s| s.bold())
+                            .if_supports_color(Stderr, |s| s.purple()),
+                        title.if_supports_color(Stderr, |s| s.bright_blue()),
+                        format!("(for {level})").if_supports_color(Stderr, |s| s.bright_black()),
+                    );
+
+                    println!("\n{}", pretty::indent(&report.to_string(), 4));
+                }
+            }
             Event::ResolvingPackages { name } => {
                 eprintln!(
                     "{} {}",
@@ -304,6 +480,7 @@ impl EventListener for Terminal {
                 seed,
                 benchmarks,
                 plain_numbers,
+                baseline,
             } => {
                 let (max_mem, max_cpu, max_iter) = find_max_execution_units(&benchmarks);
 
@@ -356,6 +533,26 @@ impl EventListener for Terminal {
                     );
                 }
 
+                if let Some(report) = baseline {
+                    println!(
+                        "{}\n",
+                        pretty::indent(
+                            &pretty::open_box(
+                                &format!("baseline '{}'", report.name)
+                                    .if_supports_color(Stderr, |s| s.bold())
+                                    .if_supports_color(Stderr, |s| s.blue())
+                                    .to_string(),
+                                &fmt_baseline_report(&report),
+                                &fmt_baseline_summary(&report),
+                                |border| border
+                                    .if_supports_color(Stderr, |s| s.bright_black())
+                                    .to_string()
+                            ),
+                            4
+                        )
+                    );
+                }
+
                 if !benchmarks.is_empty() {
                     println!();
                 }
@@ -364,6 +561,102 @@ impl EventListener for Terminal {
     }
 }
 
+fn fmt_baseline_report(report: &BaselineReport) -> String {
+    let max_title = report
+        .comparisons
+        .iter()
+        .map(|c| c.module.len() + c.name.len() + 1)
+        .max()
+        .unwrap_or_default();
+
+    let mut lines = report
+        .comparisons
+        .iter()
+        .map(|comparison| fmt_comparison(comparison, report.threshold, max_title))
+        .collect::<Vec<_>>();
+
+    lines.extend(report.new.iter().map(|title| {
+        format!(
+            "{} {title}",
+            "NEW "
+                .if_supports_color(Stderr, |s| s.bold())
+                .if_supports_color(Stderr, |s| s.bright_blue())
+        )
+    }));
+
+    lines.join("\n")
+}
+
+fn fmt_comparison(comparison: &Comparison, threshold: f64, max_title: usize) -> String {
+    let label = if comparison.is_regression(threshold) {
+        "SLOW"
+            .if_supports_color(Stderr, |s| s.bold())
+            .if_supports_color(Stderr, |s| s.red())
+            .to_string()
+    } else if comparison.is_improvement(threshold) {
+        "FAST"
+            .if_supports_color(Stderr, |s| s.bold())
+            .if_supports_color(Stderr, |s| s.green())
+            .to_string()
+    } else {
+        "SAME".if_supports_color(Stderr, |s| s.dimmed()).to_string()
+    };
+
+    let change = |change: &Change| {
+        let relative = change.relative();
+        let percent = format!("{:>+8.2}%", 100.0 * relative);
+        let percent = if relative > threshold {
+            percent.if_supports_color(Stderr, |s| s.red()).to_string()
+        } else if relative < -threshold {
+            percent.if_supports_color(Stderr, |s| s.green()).to_string()
+        } else {
+            percent
+        };
+
+        if change.baseline.growth == change.current.growth {
+            format!("{percent} {}", change.current.growth)
+        } else {
+            format!(
+                "{percent} {} → {}",
+                change.baseline.growth,
+                change
+                    .current
+                    .growth
+                    .if_supports_color(Stderr, |s| s.yellow())
+            )
+        }
+    };
+
+    format!(
+        "{label} {:<max_title$} [mem: {}, cpu: {}] at size {}",
+        format!("{}.{}", comparison.module, comparison.name),
+        change(&comparison.mem),
+        change(&comparison.cpu),
+        comparison.size,
+    )
+}
+
+fn fmt_baseline_summary(report: &BaselineReport) -> String {
+    let regressions = report.regressions().count();
+    let improvements = report
+        .comparisons
+        .iter()
+        .filter(|comparison| comparison.is_improvement(report.threshold))
+        .count();
+
+    format!(
+        "{} | {} | {} | threshold={}",
+        format!("{} compared", report.comparisons.len()).if_supports_color(Stderr, |s| s.bold()),
+        format!("{regressions} regressed")
+            .if_supports_color(Stderr, |s| s.bright_red())
+            .if_supports_color(Stderr, |s| s.bold()),
+        format!("{improvements} improved")
+            .if_supports_color(Stderr, |s| s.bright_green())
+            .if_supports_color(Stderr, |s| s.bold()),
+        format!("{}%", 100.0 * report.threshold).if_supports_color(Stderr, |s| s.bold()),
+    )
+}
+
 fn fmt_test(
     result: &TestResult<UntypedExpr, UntypedExpr>,
     max_mem: usize,
@@ -424,6 +717,15 @@ fn fmt_test(
                     .to_string()),
             );
         }
+        TestResult::PropertyTestResult(PropertyTestResult {
+            proof: Some(Proof::Proved { paths }),
+            ..
+        }) => {
+            test = format!(
+                "{test} [proved over {paths} path{}]",
+                if *paths > 1 { "s" } else { "" }
+            );
+        }
         TestResult::PropertyTestResult(PropertyTestResult { iterations, .. }) => {
             test = format!(
                 "{test} [after {} test{}]",
@@ -538,6 +840,16 @@ fn fmt_test(
 
     // Annotations
     match result {
+        TestResult::UnitTestResult(..) | TestResult::PropertyTestResult(..)
+            if result.limit_exceeded().is_some() =>
+        {
+            test = format!(
+                "{test}\n{}",
+                format!("× {}", result.limit_exceeded().unwrap())
+                    .if_supports_color(Stderr, |s| s.red())
+                    .if_supports_color(Stderr, |s| s.bold())
+            );
+        }
         TestResult::UnitTestResult(UnitTestResult {
             assertion: Some(assertion),
             test: unit_test,
@@ -558,6 +870,21 @@ fn fmt_test(
         _ => (),
     }
 
+    if let TestResult::UnitTestResult(UnitTestResult {
+        failed_at: Some(location),
+        ..
+    }) = result
+    {
+        if !result.is_success() {
+            test = format!(
+                "{test}\n{}",
+                format!("× failed at {}:{}", location.module, location.line)
+                    .if_supports_color(Stderr, |s| s.red())
+                    .if_supports_color(Stderr, |s| s.bold())
+            );
+        }
+    }
+
     // CounterExamples
     if let TestResult::PropertyTestResult(PropertyTestResult { counterexample, .. }) = result {
         match counterexample {
@@ -620,6 +947,30 @@ fn fmt_test(
         }
     }
 
+    // Proofs
+    if let TestResult::PropertyTestResult(PropertyTestResult {
+        proof: Some(proof), ..
+    }) = result
+    {
+        let summary = match proof {
+            Proof::Proved { .. } => None,
+            Proof::Bounded { paths, depth } => Some(format!(
+                "proved up to depth {depth} over {paths} paths, tested beyond"
+            )),
+            Proof::Refuted(value) => Some(format!(
+                "refuted by the prover with {value}, which the fuzzer may not produce"
+            )),
+            Proof::Unknown(reason) => Some(format!("not proved: {reason}")),
+        };
+
+        if let Some(summary) = summary {
+            test = format!(
+                "{test}\n{}",
+                format!("· {summary}").if_supports_color(Stderr, |s| s.bold())
+            );
+        }
+    }
+
     // Labels
     if let TestResult::PropertyTestResult(PropertyTestResult {
         labels, iterations, ..
@@ -714,6 +1065,87 @@ fn fmt_test_summary<T>(tests: &[&TestResult<T, T>], styled: bool) -> String {
     )
 }
 
+fn fmt_module_coverage(module: &ModuleCoverage, max_name: usize) -> String {
+    let (functions_hit, functions) = module.functions_ratio();
+    let (branches_hit, branches) = module.branches_ratio();
+
+    let ratio = |hit: usize, total: usize| {
+        let ratio = format!("{:>7}", format!("{hit}/{total}"));
+        if hit == total {
+            ratio
+                .if_supports_color(Stderr, |s| s.bright_green())
+                .to_string()
+        } else {
+            ratio.if_supports_color(Stderr, |s| s.yellow()).to_string()
+        }
+    };
+
+    let mut lines = vec![format!(
+        "{:<max_name$}  functions {}  branches {}",
+        module.name,
+        ratio(functions_hit, functions),
+        ratio(branches_hit, branches),
+    )];
+
+    for handler in module.uncovered_handlers() {
+        lines.push(format!(
+            "  {} {} {}",
+            "✗ uncovered handler"
+                .if_supports_color(Stderr, |s| s.bright_red())
+                .if_supports_color(Stderr, |s| s.bold()),
+            handler
+                .name
+                .if_supports_color(Stderr, |s| s.bright_red())
+                .if_supports_color(Stderr, |s| s.bold()),
+            format!("({}:{})", module.path.display(), handler.line)
+                .if_supports_color(Stderr, |s| s.bright_black()),
+        ));
+    }
+
+    lines.join("\n")
+}
+
+fn fmt_module_mutants(module: &ModuleMutants, max_name: usize) -> String {
+    let (killed, total) = module.killed_ratio();
+
+    let ratio = format!("{:>7}", format!("{killed}/{total}"));
+
+    let mut lines = vec![format!(
+        "{:<max_name$}  killed {}",
+        module.name,
+        if killed == total {
+            ratio
+                .if_supports_color(Stderr, |s| s.bright_green())
+                .to_string()
+        } else {
+            ratio.if_supports_color(Stderr, |s| s.yellow()).to_string()
+        },
+    )];
+
+    for mutant in module.survivors() {
+        lines.push(format!(
+            "  {} {} {} {}",
+            "✗ survived"
+                .if_supports_color(Stderr, |s| s.bright_red())
+                .if_supports_color(Stderr, |s| s.bold()),
+            mutant
+                .function
+                .if_supports_color(Stderr, |s| s.bright_red())
+                .if_supports_color(Stderr, |s| s.bold()),
+            mutant.kind,
+            format!(
+                "({}:{}:{})",
+                module.path.display(),
+                mutant.line,
+                mutant.column
+            )
+            .if_supports_color(Stderr, |s| s.bright_black()),
+        ));
+    }
+
+    lines.join("\n")
+}
+
 fn plot(
     color: &RGB8,
     points: Vec<(f32, f32)>,
@@ -23,12 +23,17 @@ mod test {
     use std::{
         collections::{BTreeMap, HashMap},
         path::PathBuf,
+        time::Duration,
     };
-    use uplc::PlutusData;
+    use uplc::{PlutusData, machine::cost_model::ExBudget, optimize::OptLevel};
 
     const TEST_KIND: ModuleKind = ModuleKind::Lib;
 
     pub fn test_from_source(src: &str) -> (Test, IndexMap<DataTypeKey, TypedDataType>) {
+        compile_test(src, true)
+    }
+
+    fn compile_test(src: &str, optimize: bool) -> (Test, IndexMap<DataTypeKey, TypedDataType>) {
         let id_gen = IdGenerator::new();
 
         let module_name = "";
@@ -87,7 +92,7 @@ mod test {
             },
         );
 
-        let mut generator = CodeGenerator::new(
+        let generator = CodeGenerator::new(
             PlutusVersion::default(),
             utils::indexmap::as_ref_values(&functions),
             utils::indexmap::as_ref_values(&constants),
@@ -97,6 +102,12 @@ mod test {
             Tracing::All(TraceLevel::Verbose),
         );
 
+        let mut generator = if optimize {
+            generator
+        } else {
+            generator.without_optimization()
+        };
+
         (
             Test::from_function_definition(
                 &mut generator,
@@ -104,12 +115,13 @@ mod test {
                 module_name.to_string(),
                 PathBuf::new(),
                 RunnableKind::Test,
+                Limits::default(),
             ),
             data_types,
         )
     }
 
-    fn property(src: &str) -> (PropertyTest, impl Fn(PlutusData) -> String) {
+    fn with_prelude(src: &str) -> String {
         let prelude = indoc! { r#"
             use aiken/builtin
 
@@ -199,9 +211,11 @@ mod test {
             }
         "#};
 
-        let src = format!("{prelude}\n{src}");
+        format!("{prelude}\n{src}")
+    }
 
-        match test_from_source(&src) {
+    fn property(src: &str) -> (PropertyTest, impl Fn(PlutusData) -> String) {
+        match test_from_source(&with_prelude(src)) {
             (Test::PropertyTest(test), data_types) => {
                 let type_info = test.fuzzer.type_info.clone();
 
@@ -257,6 +271,7 @@ mod test {
             &mut remaining,
             Prng::from_seed(42),
             &mut labels,
+            None,
             plutus_version,
         ) {
             Ok(Some(counterexample)) => counterexample,
@@ -368,6 +383,316 @@ mod test {
         assert_eq!(reify(counterexample.value), "1");
     }
 
+    #[test]
+    fn test_prop_replay_examples() {
+        let (prop, reify) = property(indoc! { r#"
+            test foo(n: Int via int()) {
+                n != 137
+            }
+        "#});
+
+        let plutus_version = PlutusVersion::default();
+
+        // No random samples; only examples are tried, skipping those the fuzzer can't replay.
+        let result = PropertyTest {
+            examples: vec![vec![], vec![14], vec![137]],
+            ..prop.clone()
+        }
+        .run(42, 0, &plutus_version);
+
+        assert_eq!(result.iterations, 2);
+        assert_eq!(result.choices, Some(vec![137]));
+        assert_eq!(reify(result.counterexample.unwrap().unwrap()), "137");
+
+        let result = PropertyTest {
+            examples: vec![vec![14]],
+            ..prop
+        }
+        .run(42, 0, &plutus_version);
+
+        assert_eq!(result.iterations, 1);
+        assert_eq!(result.choices, None);
+        assert!(matches!(result.counterexample, Ok(None)));
+    }
+
+    #[test]
+    fn test_prop_prove_counterexample() {
+        let (prop, reify) = property(indoc! { r#"
+            test foo(n: Int via int()) {
+                n != 1000 && n != 42
+            }
+        "#});
+
+        let plutus_version = PlutusVersion::default();
+
+        // The prover isn't bound to the fuzzer's range, so its counterexample is only reported
+        // next to the outcome of testing.
+        let result = PropertyTest {
+            proof_depth: Some(PropertyTest::DEFAULT_PROOF_DEPTH),
+            ..prop.clone()
+        }
+        .run(42, 0, &plutus_version);
+
+        assert_eq!(result.proof, Some(Proof::Refuted("1000".to_string())));
+        assert_eq!(result.iterations, 0);
+        assert!(matches!(result.counterexample, Ok(None)));
+
+        let result = PropertyTest {
+            proof_depth: Some(PropertyTest::DEFAULT_PROOF_DEPTH),
+            ..prop
+        }
+        .run(42, PropertyTest::DEFAULT_MAX_SUCCESS, &plutus_version);
+
+        assert_eq!(result.proof, Some(Proof::Refuted("1000".to_string())));
+        assert_eq!(reify(result.counterexample.unwrap().unwrap()), "42");
+    }
+
+    #[test]
+    fn test_prop_prove_outside_of_fuzzer_domain() {
+        let (prop, _) = property(indoc! { r#"
+            fn int_between_1_and_100() -> Fuzzer<Int> {
+                int() |> map(fn(n) { n % 100 + 1 })
+            }
+
+            test foo(n: Int via int_between_1_and_100()) {
+                n > 0
+            }
+        "#});
+
+        let result = PropertyTest {
+            proof_depth: Some(PropertyTest::DEFAULT_PROOF_DEPTH),
+            ..prop
+        }
+        .run(
+            42,
+            PropertyTest::DEFAULT_MAX_SUCCESS,
+            &PlutusVersion::default(),
+        );
+
+        assert_eq!(result.proof, Some(Proof::Refuted("0".to_string())));
+        assert_eq!(result.iterations, PropertyTest::DEFAULT_MAX_SUCCESS);
+        assert!(matches!(result.counterexample, Ok(None)));
+    }
+
+    #[test]
+    fn test_prop_prove() {
+        let (prop, _) = property(indoc! { r#"
+            test foo(n: Int via int()) {
+                let fee = n * 2 + 10
+                if n > 100 {
+                    fee > 210
+                } else {
+                    fee <= 210
+                }
+            }
+        "#});
+
+        let plutus_version = PlutusVersion::default();
+
+        let result = PropertyTest {
+            proof_depth: Some(PropertyTest::DEFAULT_PROOF_DEPTH),
+            ..prop.clone()
+        }
+        .run(42, PropertyTest::DEFAULT_MAX_SUCCESS, &plutus_version);
+
+        assert_eq!(result.proof, Some(Proof::Proved { paths: 2 }));
+        assert_eq!(result.iterations, 0);
+        assert!(matches!(result.counterexample, Ok(None)));
+
+        // Too shallow to follow both branches; the property is tested as well.
+        let result = PropertyTest {
+            proof_depth: Some(0),
+            ..prop
+        }
+        .run(42, PropertyTest::DEFAULT_MAX_SUCCESS, &plutus_version);
+
+        assert_eq!(result.proof, Some(Proof::Bounded { paths: 1, depth: 0 }));
+        assert_eq!(result.iterations, PropertyTest::DEFAULT_MAX_SUCCESS);
+    }
+
+    #[test]
+    fn test_prop_prove_unsupported() {
+        let (prop, _) = property(indoc! { r#"
+            test foo(n: Int via int()) {
+                n / 2 < 200
+            }
+        "#});
+
+        let result = PropertyTest {
+            proof_depth: Some(PropertyTest::DEFAULT_PROOF_DEPTH),
+            ..prop
+        }
+        .run(
+            42,
+            PropertyTest::DEFAULT_MAX_SUCCESS,
+            &PlutusVersion::default(),
+        );
+
+        assert!(matches!(result.proof, Some(Proof::Unknown(..))));
+        assert!(matches!(result.counterexample, Ok(None)));
+    }
+
+    #[test]
+    fn test_differential() {
+        let plutus_version = PlutusVersion::default();
+
+        for src in [
+            indoc! { r#"
+                fn sum(xs: List<Int>) -> Int {
+                  when xs is {
+                    [] -> 0
+                    [x, ..rest] -> x + sum(rest)
+                  }
+                }
+
+                test foo() {
+                  sum([1, 2, 3]) == 6
+                }
+            "#},
+            indoc! { r#"
+                test foo(n: Int via int()) {
+                  when [n, n + 1, n * 2] is {
+                    [a, b, ..] -> a < b
+                    _ -> False
+                  }
+                }
+            "#},
+            indoc! { r#"
+                test foo(n: Int via int()) {
+                  expect n < 128
+                  builtin.integer_to_bytearray(True, 0, n) != #""
+                }
+            "#},
+        ] {
+            let src = with_prelude(src);
+
+            let (optimized, _) = compile_test(&src, true);
+            let (unoptimized, _) = compile_test(&src, false);
+
+            assert_eq!(
+                optimized.differential(
+                    &unoptimized,
+                    OptLevel::Size,
+                    42,
+                    PropertyTest::DEFAULT_MAX_SUCCESS,
+                    &plutus_version
+                ),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn test_prop_budget_exceeded() {
+        let (prop, reify) = property(indoc! { r#"
+            fn sum(n: Int) -> Int {
+              if n <= 0 {
+                0
+              } else {
+                n + sum(n - 1)
+              }
+            }
+
+            @budget(cpu: 10_000_000)
+            test foo(n: Int via int()) fail {
+                sum(n) < 0
+            }
+        "#});
+
+        let result = prop.run(
+            42,
+            PropertyTest::DEFAULT_MAX_SUCCESS,
+            &PlutusVersion::default(),
+        );
+
+        let ExBudget { mem, .. } = ExBudget::max();
+
+        assert_eq!(
+            result.limit_exceeded,
+            Some(LimitExceeded::Budget(ExBudget {
+                mem,
+                cpu: 10_000_000
+            }))
+        );
+
+        // Values reaching a limit aren't simplified.
+        let counterexample = reify(result.counterexample.clone().unwrap().unwrap());
+        assert!(counterexample.parse::<i64>().unwrap() > 0);
+
+        assert!(!TestResult::PropertyTestResult::<(), _>(result).is_success());
+    }
+
+    #[test]
+    fn test_unit_limits_exceeded() {
+        let (test, _) = test_from_source(indoc! { r#"
+            fn forever(n: Int) -> Int {
+              forever(n + 1)
+            }
+
+            @budget(mem: 1_000_000)
+            test foo() fail {
+              forever(0) == 0
+            }
+        "#});
+
+        let Test::UnitTest(test) = test else {
+            panic!("Expected to yield a UnitTest");
+        };
+
+        let result = test.clone().run(&PlutusVersion::default());
+
+        assert!(!result.success);
+        assert!(matches!(
+            result.limit_exceeded,
+            Some(LimitExceeded::Budget(ExBudget { mem: 1_000_000, .. }))
+        ));
+
+        let timeout = Duration::from_millis(50);
+
+        let result = UnitTest {
+            limits: Limits {
+                mem: None,
+                timeout: Some(timeout),
+                ..test.limits
+            },
+            ..test
+        }
+        .run(&PlutusVersion::default());
+
+        assert!(!result.success);
+        assert_eq!(result.limit_exceeded, Some(LimitExceeded::Timeout(timeout)));
+    }
+
+    #[test]
+    fn test_unit_failed_at() {
+        let (test, _) = test_from_source(indoc! { r#"
+            fn check(n: Int) -> Int {
+              if n > 10 {
+                fail @"too big"
+              } else {
+                n
+              }
+            }
+
+            test foo() {
+              let x = check(3)
+              check(x * 5) == 15
+            }
+        "#});
+
+        let Test::UnitTest(test) = test else {
+            panic!("Expected to yield a UnitTest");
+        };
+
+        let result = test.run(&PlutusVersion::default());
+
+        assert!(!result.success);
+
+        let failed_at = result.failed_at.expect("no location for the failure");
+
+        assert_eq!(failed_at.line, 3);
+    }
+
     #[test]
     fn test_prop_combine() {
         let (prop, reify) = property(indoc! { r#"
@@ -5,7 +5,7 @@ use aiken_lang::{
     ast::{TraceLevel, Tracing},
     test_framework::Benchmark,
 };
-use aiken_project::watch::with_project;
+use aiken_project::{baseline, watch::with_project};
 use rand::prelude::*;
 use std::{path::PathBuf, process};
 
@@ -71,6 +71,27 @@ pub struct Args {
     /// When enabled, print execution units as plain numbers
     #[clap(long)]
     plain_numbers: bool,
+
+    /// When enabled, break down the execution units spent by each benchmark per function and per
+    /// line, and export them under 'build/profile' as a JSON report and folded stacks (for
+    /// flamegraphs).
+    #[clap(long)]
+    profile: bool,
+
+    /// Save the results of this run as a baseline under 'build/baselines', for later runs to be
+    /// compared against with `--baseline`.
+    #[clap(long, value_name = "NAME")]
+    save_baseline: Option<String>,
+
+    /// Compare the results of this run against a baseline previously saved with
+    /// `--save-baseline`, and fail when any benchmark regressed.
+    #[clap(long, value_name = "NAME")]
+    baseline: Option<String>,
+
+    /// The increase of memory or cpu units (in percent) above which a benchmark is considered to
+    /// have regressed compared to its baseline.
+    #[clap(long, default_value_t = 100.0 * baseline::DEFAULT_THRESHOLD, requires = "baseline")]
+    threshold: f64,
 }
 
 pub fn exec(
@@ -84,6 +105,10 @@ pub fn exec(
         trace_filter,
         trace_level,
         plain_numbers,
+        profile,
+        save_baseline,
+        baseline,
+        threshold,
     }: Args,
 ) -> miette::Result<()> {
     let mut rng = rand::thread_rng();
@@ -101,6 +126,10 @@ pub fn exec(
                 None => Tracing::All(trace_level),
             },
             plain_numbers,
+            profile,
+            baseline.clone(),
+            save_baseline.clone(),
+            threshold / 100.0,
             env.clone(),
         )
     });
@@ -1,4 +1,8 @@
-use aiken_project::blueprint::{Blueprint, error::Error as BlueprintError};
+use aiken_project::blueprint::{
+    Blueprint,
+    bindings::{self, Language},
+    error::Error as BlueprintError,
+};
 use clap::ValueEnum;
 use miette::IntoDiagnostic;
 use serde_json::json;
@@ -19,7 +23,8 @@ pub struct Args {
     #[clap(short, long)]
     validator: Option<String>,
 
-    /// Format to convert to
+    /// Format to convert to. Off-chain bindings (typescript, rust, python) cover all data-types
+    /// of the blueprint, and every validator matching the module and validator filters.
     #[clap(long, default_value = "cardano-cli")]
     to: Format,
 }
@@ -27,6 +32,9 @@ pub struct Args {
 #[derive(Copy, Clone, ValueEnum)]
 pub enum Format {
     CardanoCli,
+    Typescript,
+    Rust,
+    Python,
 }
 
 pub fn exec(
@@ -53,6 +61,23 @@ pub fn exec(
     let blueprint: Blueprint =
         serde_json::from_reader(BufReader::new(blueprint)).into_diagnostic()?;
 
+    let language = match to {
+        Format::CardanoCli => None,
+        Format::Typescript => Some(Language::TypeScript),
+        Format::Rust => Some(Language::Rust),
+        Format::Python => Some(Language::Python),
+    };
+
+    if let Some(language) = language {
+        let bindings = bindings::generate(&blueprint, language, |v| {
+            let (known_module, known_validator) = v.get_module_and_name();
+            module.as_deref().is_none_or(|m| m == known_module)
+                && validator.as_deref().is_none_or(|v| v == known_validator)
+        });
+        print!("{bindings}");
+        return Ok(());
+    }
+
     // Perform the conversion
     let when_too_many =
         |known_validators| BlueprintError::MoreThanOneValidatorFound { known_validators };
@@ -64,6 +89,7 @@ pub fn exec(
         when_too_many,
         when_missing,
         |validator| match to {
+            Format::Typescript | Format::Rust | Format::Python => unreachable!(),
             Format::CardanoCli => {
                 let cbor_bytes = validator.program.inner().to_cbor().unwrap();
 
@@ -0,0 +1,226 @@
+use aiken_project::{
+    blueprint::{
+        self, Blueprint,
+        fuzz::{self, Finding, FindingKind, Fuzzer, Report},
+    },
+    error::Error,
+    pretty::multiline,
+    watch::{with_project, without_project},
+};
+use owo_colors::{OwoColorize, Stream::Stderr};
+use rand::prelude::*;
+use std::{fs::File, io::BufReader, path::PathBuf};
+use uplc::{PlutusData, ast::Data as UplcData};
+
+/// Fuzz validator handlers with inputs generated from their blueprint schemas.
+///
+/// Parameters, datum and redeemer are generated from the schemas found in the blueprint, and
+/// given to the handler through a minimal transaction spending, minting or withdrawing with the
+/// script. Inputs reaching branches of the program that no previous input reached are mutated to
+/// explore further. Inputs making the handler succeed, or crash with an error other than an
+/// explicit failure, are reported.
+#[derive(clap::Args)]
+#[clap(disable_version_flag(true))]
+pub struct Args {
+    /// Optional path to the blueprint file to be used as input.
+    ///
+    /// [default: plutus.json]
+    #[clap(
+        short,
+        long = "in",
+        value_parser,
+        value_name = "FILEPATH",
+        verbatim_doc_comment
+    )]
+    input: Option<PathBuf>,
+
+    /// Name of the validator's module within the project. Optional if there's only one validator.
+    #[clap(short, long)]
+    module: Option<String>,
+
+    /// Name of the validator within the module. Optional if there's only one validator.
+    #[clap(short, long)]
+    validator: Option<String>,
+
+    /// Name of the handler to fuzz (e.g. 'spend'). Every spend, mint and withdraw handler of the
+    /// validator is fuzzed when omitted.
+    #[clap(long)]
+    handler: Option<String>,
+
+    /// An initial seed to initialize the pseudo-random generator.
+    #[clap(long, value_name = "UINT")]
+    seed: Option<u32>,
+
+    /// Number of inputs to evaluate each handler against.
+    #[clap(long, default_value_t = fuzz::DEFAULT_RUNS, value_name = "UINT")]
+    runs: usize,
+}
+
+pub fn exec(
+    Args {
+        input,
+        module,
+        validator,
+        handler,
+        seed,
+        runs,
+    }: Args,
+) -> miette::Result<()> {
+    let seed = seed.unwrap_or_else(|| rand::thread_rng().r#gen());
+
+    let fuzz = |blueprint_input_path: PathBuf| {
+        let blueprint = File::open(&blueprint_input_path)
+            .map_err(|_| blueprint::error::Error::InvalidOrMissingFile)
+            .map_err(|err| Error::Blueprint(err.into()))?;
+
+        let blueprint: Blueprint =
+            serde_json::from_reader(BufReader::new(blueprint)).map_err(Error::from)?;
+
+        let when_too_many = |known_validators| blueprint::error::Error::MoreThanOneValidatorFound {
+            known_validators,
+        };
+        let when_missing =
+            |known_validators| blueprint::error::Error::NoValidatorNotFound { known_validators };
+
+        let prefix = blueprint
+            .with_validator(
+                module.as_deref(),
+                validator.as_deref(),
+                when_too_many,
+                when_missing,
+                |validator| {
+                    let (module, validator) = validator.get_module_and_name();
+                    Ok(format!("{module}.{validator}."))
+                },
+            )
+            .map_err(|err| Error::Blueprint(err.into()))?;
+
+        let handlers = blueprint
+            .validators
+            .iter()
+            .filter(|validator| validator.title.starts_with(&prefix))
+            .collect::<Vec<_>>();
+
+        let targets = handlers
+            .iter()
+            .filter(|validator| match &handler {
+                Some(handler) => validator.title[prefix.len()..] == *handler,
+                None => fuzz::Purpose::from_title(&validator.title).is_some(),
+            })
+            .collect::<Vec<_>>();
+
+        if targets.is_empty() {
+            return Err(Error::Blueprint(
+                blueprint::error::Error::UnsupportedFuzzTarget {
+                    hint: format!(
+                        "Here are the handlers I've found: {}. I can only fuzz spend, mint and withdraw handlers.",
+                        handlers
+                            .iter()
+                            .map(|validator| &validator.title[prefix.len()..])
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                }
+                .into(),
+            )
+            .into());
+        }
+
+        let mut findings = 0;
+
+        for target in targets {
+            eprintln!(
+                "{} {} {}",
+                "      Fuzzing"
+                    .if_supports_color(Stderr, |s| s.purple())
+                    .if_supports_color(Stderr, |s| s.bold()),
+                target.title.if_supports_color(Stderr, |s| s.bright_blue()),
+                format!("(runs={runs}, seed={seed})")
+                    .if_supports_color(Stderr, |s| s.bright_black()),
+            );
+
+            let report = Fuzzer::new(target, &blueprint.definitions, seed)
+                .map_err(|err| Error::Blueprint(err.into()))?
+                .run(runs);
+
+            findings += report.findings.len();
+
+            print_report(&report);
+        }
+
+        if findings > 0 {
+            Err(Error::Blueprint(blueprint::error::Error::FuzzFindings { findings }.into()).into())
+        } else {
+            Ok(())
+        }
+    };
+
+    match input {
+        Some(path) => without_project(|| fuzz(path.clone())).map_err(|_| std::process::exit(1)),
+        None => with_project(None, false, false, false, |p| {
+            let path = p.blueprint_path(None);
+            fuzz(path)
+        })
+        .map_err(|_| std::process::exit(1)),
+    }
+}
+
+fn print_report(report: &Report) {
+    eprintln!(
+        "{} {}/{} branches, {} input(s) kept",
+        "      Covered"
+            .if_supports_color(Stderr, |s| s.purple())
+            .if_supports_color(Stderr, |s| s.bold()),
+        report.branches_taken,
+        report.branches_total,
+        report.corpus,
+    );
+
+    for Finding {
+        kind,
+        input,
+        logs,
+        occurrences,
+    } in report.findings.iter()
+    {
+        let label = match kind {
+            FindingKind::Succeeded => "    Succeeded",
+            FindingKind::Crashed(_) => "      Crashed",
+        };
+
+        eprintln!(
+            "{} {}",
+            label
+                .if_supports_color(Stderr, |s| s.red())
+                .if_supports_color(Stderr, |s| s.bold()),
+            format!("on {occurrences} input(s), first with:")
+                .if_supports_color(Stderr, |s| s.bright_black()),
+        );
+
+        if let FindingKind::Crashed(error) = kind {
+            eprintln!("{:>13} {}", "error", indent(&error.to_string()));
+        }
+
+        for (ix, parameter) in input.parameters.iter().enumerate() {
+            eprintln!("{:>13} {}", format!("parameter {}", ix + 1), hex(parameter));
+        }
+
+        if let Some(datum) = &input.datum {
+            eprintln!("{:>13} {}", "datum", hex(datum));
+        }
+
+        eprintln!("{:>13} {}", "redeemer", hex(&input.redeemer));
+
+        for log in logs {
+            eprintln!("{:>13} {}", "trace", indent(log));
+        }
+    }
+}
+
+fn hex(data: &PlutusData) -> String {
+    multiline(48, UplcData::to_hex(data.clone())).join(&format!("\n{:>14}", ""))
+}
+
+fn indent(s: &str) -> String {
+    s.lines().collect::<Vec<_>>().join(&format!("\n{:>14}", ""))
+}
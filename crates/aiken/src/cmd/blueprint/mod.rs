@@ -1,6 +1,7 @@
 pub mod address;
 pub mod apply;
 pub mod convert;
+pub mod fuzz;
 pub mod hash;
 pub mod policy;
 
@@ -15,6 +16,7 @@ pub enum Cmd {
     Hash(hash::Args),
     Apply(apply::Args),
     Convert(convert::Args),
+    Fuzz(fuzz::Args),
 }
 
 pub fn exec(cmd: Cmd) -> miette::Result<()> {
@@ -24,5 +26,6 @@ pub fn exec(cmd: Cmd) -> miette::Result<()> {
         Cmd::Hash(args) => hash::exec(args),
         Cmd::Apply(args) => apply::exec(args),
         Cmd::Convert(args) => convert::exec(args),
+        Cmd::Fuzz(args) => fuzz::exec(args),
     }
 }
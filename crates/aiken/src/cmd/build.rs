@@ -7,6 +7,7 @@ use aiken_project::{
 };
 use clap::builder::{MapValueParser, PossibleValuesParser, TypedValueParser};
 use std::{path::PathBuf, process};
+use uplc::optimize::OptLevel;
 
 #[derive(clap::Args)]
 #[clap(disable_version_flag(true))]
@@ -84,6 +85,37 @@ pub struct Args {
     /// signatures. Off by default.
     #[clap(short = 'I', long)]
     include_all_types: bool,
+
+    /// Include, next to each validator in the blueprint, a source map relating sub-terms of the
+    /// compiled program to the Aiken definitions they originate from. Off by default.
+    #[clap(long)]
+    source_map: bool,
+
+    /// [debug] Evaluate validators before and after every optimization pass, on inputs made up
+    /// from the schemas of their spend, mint and withdraw handlers (Plutus V3 only), and fail on
+    /// any difference in outcome, naming the first pass to introduce it.
+    #[clap(long)]
+    verify_optimizations: bool,
+
+    /// What to optimize validators for:
+    ///
+    ///   - size:
+    ///       make programs as small as possible
+    ///
+    ///   - cpu:
+    ///       favour execution costs over size
+    ///
+    ///   - none:
+    ///       leave programs unoptimized
+    ///
+    /// [optional] [default: as set in aiken.toml, or size]
+    #[clap(short = 'O', long, value_parser=opt_level_parser(), verbatim_doc_comment)]
+    opt_level: Option<OptLevel>,
+
+    /// Print, for every validator, the node count, flat size and estimated execution cost of the
+    /// program after each optimization pass.
+    #[clap(long)]
+    opt_report: bool,
 }
 
 pub fn exec(
@@ -98,18 +130,26 @@ pub fn exec(
         output,
         env,
         include_all_types,
+        source_map,
+        verify_optimizations,
+        opt_level,
+        opt_report,
     }: Args,
 ) -> miette::Result<()> {
     let result = if watch {
         watch_project(directory.as_deref(), watch::default_filter, 500, |p| {
             p.build(
                 uplc,
+                verify_optimizations,
+                opt_level,
+                opt_report,
                 match trace_filter {
                     Some(trace_filter) => trace_filter(trace_level),
                     None => Tracing::All(trace_level),
                 },
                 p.blueprint_path(output.as_deref()),
                 BlueprintExport::from(include_all_types),
+                source_map,
                 env.clone(),
             )
         })
@@ -117,12 +157,16 @@ pub fn exec(
         with_project(directory.as_deref(), deny, silent, true, |p| {
             p.build(
                 uplc,
+                verify_optimizations,
+                opt_level,
+                opt_report,
                 match trace_filter {
                     Some(trace_filter) => trace_filter(trace_level),
                     None => Tracing::All(trace_level),
                 },
                 p.blueprint_path(output.as_deref()),
                 BlueprintExport::from(include_all_types),
+                source_map,
                 env.clone(),
             )
         })
@@ -153,3 +197,13 @@ pub fn trace_level_parser() -> MapValueParser<PossibleValuesParser, fn(String) -
         _ => unreachable!(),
     })
 }
+
+#[allow(clippy::type_complexity)]
+pub fn opt_level_parser() -> MapValueParser<PossibleValuesParser, fn(String) -> OptLevel> {
+    PossibleValuesParser::new(["size", "cpu", "none"]).map(|s| match s.as_str() {
+        "size" => OptLevel::Size,
+        "cpu" => OptLevel::Cpu,
+        "none" => OptLevel::None,
+        _ => unreachable!(),
+    })
+}
@@ -129,6 +129,49 @@ pub struct Args {
     /// When enabled, print execution units as plain numbers
     #[clap(long)]
     plain_numbers: bool,
+
+    /// When enabled, break down the execution units spent by each test per function and per line,
+    /// and export them under 'build/profile' as a JSON report and folded stacks (for flamegraphs).
+    #[clap(long)]
+    profile: bool,
+
+    /// When enabled, record which functions, validator handlers and branches are reached by the
+    /// tests, print a summary per module and export an LCOV report to 'build/coverage/lcov.info'.
+    #[clap(long)]
+    coverage: bool,
+
+    /// When enabled, promote the counterexamples found by previous runs of the selected property
+    /// tests into permanent regressions, recorded in 'regressions.json' at the root of the project
+    /// and meant to be kept under version control. Counterexamples are otherwise saved under
+    /// 'build/counterexamples.json' and replayed first until they no longer fail.
+    #[clap(long)]
+    promote: bool,
+
+    /// When enabled and all tests pass, run the tests again against mutants of the project's
+    /// functions and validators: copies with a flipped comparison, swapped '&&'/'||', negated
+    /// boolean result, deleted 'expect' or dropped 'when' clause. Mutants that no test catches
+    /// are reported per module, along with their location.
+    #[clap(long)]
+    mutate: bool,
+
+    /// [experimental] When enabled, attempt to prove property tests over an 'Int' or a 'ByteArray'
+    /// before running them, by evaluating them symbolically and solving the linear constraints
+    /// they put on the value. A proof holds for every value of the type, regardless of the fuzzer.
+    /// Values refuting the property are only reported alongside the tests, as the fuzzer may not
+    /// produce them. Properties going through operations the prover can't reason about (e.g.
+    /// non-linear arithmetic, hashing or division of the value) are only tested.
+    #[clap(long)]
+    prove: bool,
+
+    /// Maximum number of branches depending on the value followed by the prover, past which a
+    /// proof only holds for the values taking shorter paths.
+    #[clap(long, default_value_t = PropertyTest::DEFAULT_PROOF_DEPTH, value_name = "UINT")]
+    prove_depth: usize,
+
+    /// [debug] Evaluate tests before and after every optimization pass, on the values they're run
+    /// with, and fail on any difference in outcome, naming the first pass to introduce it.
+    #[clap(long)]
+    verify_optimizations: bool,
 }
 
 pub fn exec(
@@ -149,6 +192,13 @@ pub fn exec(
         max_success,
         env,
         plain_numbers,
+        profile,
+        coverage,
+        promote,
+        mutate,
+        prove,
+        prove_depth,
+        verify_optimizations,
     }: Args,
 ) -> miette::Result<()> {
     if show_json_schema {
@@ -160,6 +210,8 @@ pub fn exec(
 
     let seed = seed.unwrap_or_else(|| rng.r#gen());
 
+    let prove = prove.then_some(prove_depth);
+
     let result = if watch {
         watch_project(directory.as_deref(), watch::default_filter, 500, |p| {
             p.check(
@@ -175,6 +227,12 @@ pub fn exec(
                     None => Tracing::All(trace_level),
                 },
                 plain_numbers,
+                profile,
+                coverage,
+                promote,
+                mutate,
+                prove,
+                verify_optimizations,
                 env.clone(),
             )
         })
@@ -193,6 +251,12 @@ pub fn exec(
                     None => Tracing::All(trace_level),
                 },
                 plain_numbers,
+                profile,
+                coverage,
+                promote,
+                mutate,
+                prove,
+                verify_optimizations,
                 env.clone(),
             )
         })
@@ -15,11 +15,17 @@ use pallas_primitives::{
 };
 use pallas_traverse::{Era, MultiEraTx};
 use serde_json::json;
-use std::{collections::HashMap, fmt, fs, path::PathBuf, process};
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    process,
+};
 use uplc::{
-    machine::Trace,
+    machine::{Trace, cost_model::ExBudget},
+    source_map::{SourceLocation, SourceMap},
     tx::{
-        self, redeemer_tag_to_string,
+        self, DataLookupTable, ProtocolParameters, UtxoSnapshot, redeemer_tag_to_string,
         script_context::{PlutusScript, ResolvedInput, SlotConfig},
     },
 };
@@ -37,12 +43,55 @@ pub struct Args {
     cbor: bool,
 
     /// A file containing cbor hex for the raw inputs
-    #[clap(value_name = "FILEPATH")]
-    raw_inputs: PathBuf,
+    #[clap(value_name = "FILEPATH", required_unless_present = "utxos")]
+    raw_inputs: Option<PathBuf>,
 
     /// A file containing cbor hex for the raw outputs
-    #[clap(value_name = "FILEPATH")]
-    raw_outputs: PathBuf,
+    #[clap(value_name = "FILEPATH", required_unless_present = "utxos")]
+    raw_outputs: Option<PathBuf>,
+
+    /// A UTxO snapshot to resolve the transaction inputs, reference inputs and collateral from,
+    /// instead of raw inputs and outputs. Either as JSON (in the shape of 'cardano-cli query utxo',
+    /// Ogmios or Kupo) or as CBOR (a map from inputs to outputs).
+    #[clap(long, value_name = "FILEPATH", conflicts_with_all = ["raw_inputs", "raw_outputs"], verbatim_doc_comment)]
+    utxos: Option<PathBuf>,
+
+    /// Protocol parameters (as output by 'cardano-cli query protocol-parameters') to simulate
+    /// the transaction against. Their cost models and maximum execution units are used to
+    /// evaluate scripts.
+    #[clap(long, value_name = "FILEPATH", verbatim_doc_comment)]
+    protocol_params: Option<PathBuf>,
+
+    /// Validate the transaction against the protocol parameters before evaluating its scripts:
+    /// fees, collateral, value conservation, execution units, validity interval and script data
+    /// hash.
+    #[clap(long, requires = "protocol_params", verbatim_doc_comment)]
+    validate_phase_one: bool,
+
+    /// Current slot, to check against the validity interval of the transaction
+    #[clap(long, value_name = "SLOT", requires = "validate_phase_one")]
+    current_slot: Option<u64>,
+
+    /// Write the transaction to the given file with the execution units of its redeemers
+    /// replaced by the measured ones (plus a safety margin), and its script data hash updated.
+    /// Requires protocol parameters for the cost models. The fee is left untouched, and the
+    /// transaction must be signed again.
+    #[clap(
+        long,
+        value_name = "FILEPATH",
+        requires = "protocol_params",
+        verbatim_doc_comment
+    )]
+    write_ex_units: Option<PathBuf>,
+
+    /// Safety margin added to the measured execution units, in percent
+    #[clap(
+        long,
+        default_value_t = 5,
+        value_name = "PERCENT",
+        requires = "write_ex_units"
+    )]
+    ex_units_margin: u64,
 
     /// Time between each slot
     #[clap(short, long, default_value_t = 1000, value_name = "MILLISECOND")]
@@ -72,6 +121,12 @@ pub fn exec(
         cbor,
         raw_inputs,
         raw_outputs,
+        utxos,
+        protocol_params,
+        validate_phase_one,
+        current_slot,
+        write_ex_units,
+        ex_units_margin,
         slot_length,
         zero_time,
         zero_slot,
@@ -86,26 +141,41 @@ pub fn exec(
             .if_supports_color(Stderr, |s| s.bold())
     );
 
-    let (tx_bytes, inputs_bytes, outputs_bytes) = if cbor {
-        (
-            fs::read(input).into_diagnostic()?,
-            fs::read(raw_inputs).into_diagnostic()?,
-            fs::read(raw_outputs).into_diagnostic()?,
-        )
+    let tx_bytes = if cbor {
+        fs::read(input).into_diagnostic()?
     } else {
         let cbor_hex = fs::read_to_string(input).into_diagnostic()?;
-        let inputs_hex = fs::read_to_string(raw_inputs).into_diagnostic()?;
-        let outputs_hex = fs::read_to_string(raw_outputs).into_diagnostic()?;
-
-        (
-            hex::decode(cbor_hex.trim()).into_diagnostic()?,
-            hex::decode(inputs_hex.trim()).into_diagnostic()?,
-            hex::decode(outputs_hex.trim()).into_diagnostic()?,
-        )
+        hex::decode(cbor_hex.trim()).into_diagnostic()?
     };
 
     let tx = MultiEraTx::decode_for_era(Era::Conway, &tx_bytes).into_diagnostic()?;
 
+    let utxos = match (utxos, raw_inputs, raw_outputs) {
+        (Some(snapshot), _, _) => Utxos::Snapshot(
+            UtxoSnapshot::decode(&fs::read(snapshot).into_diagnostic()?)
+                .unwrap_or_else(|err| fail(err)),
+        ),
+        (None, Some(raw_inputs), Some(raw_outputs)) => {
+            let (inputs_bytes, outputs_bytes) = if cbor {
+                (
+                    fs::read(raw_inputs).into_diagnostic()?,
+                    fs::read(raw_outputs).into_diagnostic()?,
+                )
+            } else {
+                let inputs_hex = fs::read_to_string(raw_inputs).into_diagnostic()?;
+                let outputs_hex = fs::read_to_string(raw_outputs).into_diagnostic()?;
+
+                (
+                    hex::decode(inputs_hex.trim()).into_diagnostic()?,
+                    hex::decode(outputs_hex.trim()).into_diagnostic()?,
+                )
+            };
+
+            Utxos::Raw(inputs_bytes, outputs_bytes)
+        }
+        _ => unreachable!("clap requires either a snapshot or raw inputs and outputs"),
+    };
+
     let mut overrides: HashMap<ScriptHash, PlutusScript> = HashMap::new();
 
     let blueprint_path = blueprint.map(Ok::<_, miette::Error>).unwrap_or_else(|| {
@@ -190,25 +260,65 @@ pub fn exec(
         tx.hash()
     );
 
-    let inputs = Vec::<TransactionInput>::decode_fragment(&inputs_bytes).unwrap();
-    let outputs = Vec::<TransactionOutput>::decode_fragment(&outputs_bytes).unwrap();
+    if let Some(tx_conway) = tx.as_conway() {
+        let resolved_inputs: Vec<ResolvedInput> = match utxos {
+            Utxos::Snapshot(snapshot) => {
+                snapshot.resolve(tx_conway).unwrap_or_else(|err| fail(err))
+            }
+            Utxos::Raw(inputs_bytes, outputs_bytes) => {
+                let inputs = Vec::<TransactionInput>::decode_fragment(&inputs_bytes).unwrap();
+                let outputs = Vec::<TransactionOutput>::decode_fragment(&outputs_bytes).unwrap();
+
+                inputs
+                    .iter()
+                    .zip(outputs.iter())
+                    .map(|(input, output)| ResolvedInput {
+                        input: input.clone(),
+                        output: output.clone(),
+                    })
+                    .collect()
+            }
+        };
 
-    let resolved_inputs: Vec<ResolvedInput> = inputs
-        .iter()
-        .zip(outputs.iter())
-        .map(|(input, output)| ResolvedInput {
-            input: input.clone(),
-            output: output.clone(),
-        })
-        .collect();
+        let params = protocol_params
+            .map(|path| {
+                fs::read_to_string(path).into_diagnostic().map(|json| {
+                    ProtocolParameters::from_json(&json).unwrap_or_else(|err| fail(err))
+                })
+            })
+            .transpose()?;
+
+        if let Some(params) = params.as_ref().filter(|_| validate_phase_one) {
+            eprintln!(
+                "{:>13} phase one",
+                "Validating"
+                    .if_supports_color(Stderr, |s| s.purple())
+                    .if_supports_color(Stderr, |s| s.bold()),
+            );
+
+            let lookup_table = DataLookupTable::from_transaction(tx_conway, &resolved_inputs);
+
+            tx::eval_phase_one_with_params(
+                tx_conway,
+                &resolved_inputs,
+                &lookup_table,
+                params,
+                current_slot,
+            )
+            .unwrap_or_else(|err| fail(err));
+        }
 
-    if let Some(tx_conway) = tx.as_conway() {
         let slot_config = SlotConfig {
             zero_time,
             zero_slot,
             slot_length,
         };
 
+        let initial_budget = params.as_ref().map(|params| ExBudget {
+            mem: params.max_tx_ex_units.mem as i64,
+            cpu: params.max_tx_ex_units.steps as i64,
+        });
+
         let with_redeemer = |redeemer: &Redeemer| {
             eprintln!(
                 "{:>13} {}[{}]",
@@ -220,99 +330,134 @@ pub fn exec(
             )
         };
 
-        let result = tx::eval_phase_two_with_override(
+        let redeemers = match tx::eval_phase_two_with_override(
             tx_conway,
             &resolved_inputs,
-            None,
-            None,
+            params.as_ref().map(|params| &params.cost_models),
+            initial_budget.as_ref(),
             &slot_config,
-            overrides,
+            overrides.clone(),
             true,
             with_redeemer,
-        );
+        ) {
+            Ok(redeemers) => redeemers,
+            Err(err) => {
+                let location = match &err {
+                    tx::error::Error::RedeemerError { tag, index, .. } => tx::locate_failure(
+                        tx_conway,
+                        &resolved_inputs,
+                        &slot_config,
+                        overrides,
+                        &source_maps(&blueprint_path),
+                        tag,
+                        *index,
+                    )
+                    .ok()
+                    .flatten(),
+                    _ => None,
+                };
+
+                fail_at(err, location)
+            }
+        };
 
-        match result {
-            Ok(redeemers) => {
-                let is_terminal = matches!(EventTarget::default(), EventTarget::Terminal(..));
-
-                if is_terminal {
-                    redeemers.iter().for_each(|(redeemer, eval)| {
-                        eprintln!(
-                            "{} {}={}\n{:>6}{:>11}={}",
-                            format!(
-                                "{:>13}",
-                                format!(
-                                    "{}[{}]",
-                                    redeemer_tag_to_string(&redeemer.tag),
-                                    redeemer.index
-                                )
-                            )
-                            .if_supports_color(Stderr, |s| s.purple())
-                            .if_supports_color(Stderr, |s| s.bold()),
-                            "mem".if_supports_color(Stderr, |s| s.bold()),
-                            redeemer.ex_units.mem,
-                            "│"
-                                .if_supports_color(Stderr, |s| s.purple())
-                                .if_supports_color(Stderr, |s| s.bold()),
-                            "cpu".if_supports_color(Stderr, |s| s.bold()),
-                            redeemer.ex_units.steps,
-                        );
-
-                        let traces = eval.traces();
-
-                        eprintln!(
-                            "{:>13} {}",
-                            "└ Traces"
-                                .if_supports_color(Stderr, |s| s.purple())
-                                .if_supports_color(Stderr, |s| s.bold()),
-                            if traces.is_empty() {
-                                "ø".to_string()
-                            } else {
-                                traces
-                                    .into_iter()
-                                    .filter_map(|trace| match trace {
-                                        Trace::Log(s) => Some(s),
-                                        Trace::Label(_) => None,
-                                    })
-                                    .collect::<Vec<_>>()
-                                    .join("\n              ")
-                            }
-                        );
-                    });
-                } else {
-                    // this should allow N scripts to be
-                    let summary: Vec<serde_json::Value> = redeemers
-                        .iter()
-                        .map(|(redeemer, eval)| {
-                            json!({
-                                "mem": redeemer.ex_units.mem as i64,
-                                "cpu": redeemer.ex_units.steps as i64,
-                                "traces": eval.traces().into_iter().filter_map(|trace| match trace {
-                                    Trace::Log(s) => Some(s),
-                                    Trace::Label(_) => None,
-                                }).collect::<Vec<_>>(),
-                            })
-                        })
-                        .collect();
+        let is_terminal = matches!(EventTarget::default(), EventTarget::Terminal(..));
+
+        if is_terminal {
+            redeemers.iter().for_each(|(redeemer, eval)| {
+                eprintln!(
+                    "{} {}={}\n{:>6}{:>11}={}",
+                    format!(
+                        "{:>13}",
+                        format!(
+                            "{}[{}]",
+                            redeemer_tag_to_string(&redeemer.tag),
+                            redeemer.index
+                        )
+                    )
+                    .if_supports_color(Stderr, |s| s.purple())
+                    .if_supports_color(Stderr, |s| s.bold()),
+                    "mem".if_supports_color(Stderr, |s| s.bold()),
+                    redeemer.ex_units.mem,
+                    "│"
+                        .if_supports_color(Stderr, |s| s.purple())
+                        .if_supports_color(Stderr, |s| s.bold()),
+                    "cpu".if_supports_color(Stderr, |s| s.bold()),
+                    redeemer.ex_units.steps,
+                );
+
+                let traces = eval.traces();
 
-                    println!(
-                        "{}",
-                        serde_json::to_string_pretty(&summary)
-                            .map_err(|_| fmt::Error)
-                            .into_diagnostic()?
-                    );
-                }
-            }
-            Err(err) => {
                 eprintln!(
                     "{:>13} {}",
-                    "Error"
-                        .if_supports_color(Stderr, |s| s.red())
+                    "└ Traces"
+                        .if_supports_color(Stderr, |s| s.purple())
                         .if_supports_color(Stderr, |s| s.bold()),
-                    err.red()
+                    if traces.is_empty() {
+                        "ø".to_string()
+                    } else {
+                        traces
+                            .into_iter()
+                            .filter_map(|trace| match trace {
+                                Trace::Log(s) => Some(s),
+                                Trace::Label(_) => None,
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n              ")
+                    }
                 );
+            });
+        } else {
+            // this should allow N scripts to be
+            let summary: Vec<serde_json::Value> = redeemers
+                .iter()
+                .map(|(redeemer, eval)| {
+                    json!({
+                        "mem": redeemer.ex_units.mem as i64,
+                        "cpu": redeemer.ex_units.steps as i64,
+                        "traces": eval.traces().into_iter().filter_map(|trace| match trace {
+                            Trace::Log(s) => Some(s),
+                            Trace::Label(_) => None,
+                        }).collect::<Vec<_>>(),
+                    })
+                })
+                .collect();
+
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&summary)
+                    .map_err(|_| fmt::Error)
+                    .into_diagnostic()?
+            );
+        }
 
-                process::exit(1);
+        if let (Some(path), Some(params)) = (write_ex_units, params) {
+            eprintln!(
+                "{:>13} execution units to {}",
+                "Writing"
+                    .if_supports_color(Stderr, |s| s.purple())
+                    .if_supports_color(Stderr, |s| s.bold()),
+                path.display()
+            );
+
+            let evaluated = redeemers
+                .into_iter()
+                .map(|(redeemer, _)| redeemer)
+                .collect::<Vec<_>>();
+
+            let bytes = tx::apply_ex_units(
+                tx_conway,
+                &resolved_inputs,
+                &evaluated,
+                ex_units_margin,
+                &params.cost_models,
+            )
+            .unwrap_or_else(|err| fail(err));
+
+            if cbor {
+                fs::write(path, bytes).into_diagnostic()?;
+            } else {
+                fs::write(path, hex::encode(bytes)).into_diagnostic()?;
             }
         }
     }
@@ -320,6 +465,56 @@ pub fn exec(
     Ok(())
 }
 
+fn fail(err: tx::error::Error) -> ! {
+    fail_at(err, None)
+}
+
+/// Like 'fail', also telling where in the source code a script failed, if known.
+fn fail_at(err: tx::error::Error, location: Option<SourceLocation>) -> ! {
+    eprintln!(
+        "{:>13} {}",
+        "Error"
+            .if_supports_color(Stderr, |s| s.red())
+            .if_supports_color(Stderr, |s| s.bold()),
+        err.red()
+    );
+
+    if let Some(location) = location {
+        eprintln!(
+            "{:>13} {}:{}",
+            "at".if_supports_color(Stderr, |s| s.red())
+                .if_supports_color(Stderr, |s| s.bold()),
+            location.module,
+            location.line
+        );
+    }
+
+    process::exit(1);
+}
+
+/// Source maps of the validators in the blueprint at the given path, by script hash. Validators
+/// compiled without, or a blueprint that can't be read, give none.
+fn source_maps(blueprint_path: &Path) -> HashMap<ScriptHash, SourceMap> {
+    Project::<Terminal>::blueprint(blueprint_path)
+        .map(|blueprint| {
+            blueprint
+                .validators
+                .into_iter()
+                .filter_map(|validator| {
+                    let (hash, _) = validator.program.compiled_code_and_hash();
+                    Some((hash, validator.source_map?))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Where to resolve the transaction inputs from.
+enum Utxos {
+    Snapshot(UtxoSnapshot),
+    Raw(Vec<u8>, Vec<u8>),
+}
+
 fn get_override_part<'a>(
     parts: &'_ mut std::str::Split<'a, &'a str>,
     when_missing: ScriptOverrideArgumentError,
@@ -1,7 +1,11 @@
 use miette::IntoDiagnostic;
 use pallas_primitives::conway::Language;
 use serde_json::json;
-use std::{path::PathBuf, process};
+use std::{
+    io::{self, BufRead, Write},
+    path::PathBuf,
+    process,
+};
 
 use strum::IntoEnumIterator;
 
@@ -9,8 +13,9 @@ use uplc::{
     ast::{FakeNamedDeBruijn, Name, NamedDeBruijn, Program, Term},
     builtins::DefaultFunction,
     machine::{
-        TERM_COUNT,
-        cost_model::{ExBudget, StepKind},
+        Machine, TERM_COUNT,
+        cost_model::{CostModel, ExBudget, StepKind},
+        debugger::{Breakpoint, Debugger, Event, Focus},
     },
     parser,
 };
@@ -30,6 +35,15 @@ pub struct Args {
     #[clap(short, long, default_value_t = false)]
     debug: bool,
 
+    /// Step through the evaluation interactively, pausing at each CEK machine step
+    #[clap(short, long, default_value_t = false)]
+    interactive: bool,
+
+    /// Pause the interactive evaluation on a builtin (e.g. 'equalsInteger') or
+    /// a term path (e.g. '0.1.1'). Implies --interactive.
+    #[clap(short, long = "break", value_name = "BREAKPOINT")]
+    breakpoints: Vec<Breakpoint>,
+
     /// Arguments to pass to the UPLC program
     args: Vec<String>,
 }
@@ -40,6 +54,8 @@ pub fn exec(
         flat,
         args,
         debug,
+        interactive,
+        breakpoints,
         cbor,
     }: Args,
 ) -> miette::Result<()> {
@@ -78,6 +94,18 @@ pub fn exec(
 
     let program = Program::<NamedDeBruijn>::try_from(program).into_diagnostic()?;
 
+    if interactive || !breakpoints.is_empty() {
+        let machine = Machine::new(Language::PlutusV3, CostModel::default(), budget, 200);
+
+        let mut debugger = machine.debugger(program.term);
+
+        for breakpoint in breakpoints {
+            debugger.add_breakpoint(breakpoint);
+        }
+
+        return interact(debugger);
+    }
+
     let eval_result = if debug {
         program.eval_debug(ExBudget::default(), &Language::PlutusV3)
     } else {
@@ -154,3 +182,167 @@ pub fn exec(
         }
     }
 }
+
+const HELP: &str = r#"Commands:
+  s, step [n]       perform n steps (default: 1); an empty line steps once
+  c, continue       run until the next breakpoint or the end of the evaluation
+  b, break <bp>     add a breakpoint on a builtin name or a term path
+  d, delete <bp>    remove a breakpoint
+  l, list           list breakpoints
+  t, term           show the current term or returned value
+  e, env            show the current environment
+  k, stack          show the continuation stack
+  $, budget         show the budget spent so far
+  q, quit           abort the evaluation
+  h, help           show this message"#;
+
+fn interact(mut debugger: Debugger) -> miette::Result<()> {
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    if debugger.breakpoints().is_empty() {
+        print_status(&debugger);
+    } else {
+        let event = debugger.resume();
+        report(&debugger, event);
+    }
+
+    while !debugger.is_finished() {
+        print!("(debug) ");
+        io::stdout().flush().into_diagnostic()?;
+
+        let Some(line) = lines.next() else {
+            break;
+        };
+
+        let line = line.into_diagnostic()?;
+
+        let mut words = line.split_whitespace();
+
+        match (words.next(), words.next()) {
+            (None, _) => {
+                let event = debugger.step();
+                report(&debugger, event);
+            }
+            (Some("s" | "step"), n) => {
+                let n = n.and_then(|n| n.parse::<usize>().ok()).unwrap_or(1);
+                let mut event = Event::Stepped;
+                for _ in 0..n {
+                    event = debugger.step();
+                    if event != Event::Stepped {
+                        break;
+                    }
+                }
+                report(&debugger, event);
+            }
+            (Some("c" | "continue"), _) => {
+                let event = debugger.resume();
+                report(&debugger, event);
+            }
+            (Some("b" | "break"), Some(bp)) => match bp.parse::<Breakpoint>() {
+                Ok(bp) => debugger.add_breakpoint(bp),
+                Err(e) => eprintln!("{e}"),
+            },
+            (Some("d" | "delete"), Some(bp)) => match bp.parse::<Breakpoint>() {
+                Ok(bp) if debugger.remove_breakpoint(&bp) => {}
+                Ok(bp) => eprintln!("no such breakpoint: {bp}"),
+                Err(e) => eprintln!("{e}"),
+            },
+            (Some("l" | "list"), _) => {
+                for bp in debugger.breakpoints() {
+                    println!("{bp}");
+                }
+            }
+            (Some("t" | "term"), _) => print_focus(&debugger),
+            (Some("e" | "env"), _) => {
+                for (var, value) in debugger.env() {
+                    println!("{var} = {}", pretty(value));
+                }
+            }
+            (Some("k" | "stack"), _) => {
+                for (depth, frame) in debugger.stack().into_iter().enumerate() {
+                    println!("#{depth} {frame}");
+                }
+            }
+            (Some("$" | "budget"), _) => {
+                let spent = debugger.budget_spent();
+                println!("cpu: {}\nmemory: {}", spent.cpu, spent.mem);
+            }
+            (Some("q" | "quit"), _) => return Ok(()),
+            (Some("h" | "help"), _) => println!("{HELP}"),
+            (Some(cmd), _) => eprintln!("unknown command '{cmd}', type 'help' for a list"),
+        }
+    }
+
+    let spent = debugger.budget_spent();
+
+    match debugger.result() {
+        Some(Ok(term)) => {
+            let output = json!({
+                "result": pretty(term.clone()),
+                "cpu": spent.cpu,
+                "mem": spent.mem,
+                "steps": debugger.steps(),
+            });
+
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&output).into_diagnostic()?
+            );
+
+            Ok(())
+        }
+        Some(Err(err)) => {
+            eprintln!("\nError\n-----\n\n{err}\n");
+
+            eprintln!("\nCosts\n-----\ncpu: {}\nmemory: {}", spent.cpu, spent.mem);
+
+            process::exit(1)
+        }
+        None => Ok(()),
+    }
+}
+
+fn report(debugger: &Debugger, event: Event) {
+    if let Event::Breakpoint(bp) = event {
+        println!("hit breakpoint: {bp}");
+    }
+
+    if !debugger.is_finished() {
+        print_status(debugger);
+    }
+}
+
+fn print_status(debugger: &Debugger) {
+    let spent = debugger.budget_spent();
+
+    println!(
+        "step {} | path {} | cpu {} | mem {}",
+        debugger.steps(),
+        debugger
+            .path()
+            .map(|path| path.to_string())
+            .unwrap_or_else(|| "?".to_string()),
+        spent.cpu,
+        spent.mem,
+    );
+
+    print_focus(debugger);
+}
+
+fn print_focus(debugger: &Debugger) {
+    match debugger.focus() {
+        Some(Focus::Compute(term)) => println!("compute {}", pretty(term)),
+        Some(Focus::Return(value)) => println!("return {}", pretty(value)),
+        None => {}
+    }
+}
+
+/// Terms under evaluation may be open; fall back to their de Bruijn form
+/// when they can't be converted back to names.
+fn pretty(term: Term<NamedDeBruijn>) -> String {
+    match Term::<Name>::try_from(term.clone()) {
+        Ok(term) => term.to_pretty(),
+        Err(_) => term.to_pretty(),
+    }
+}
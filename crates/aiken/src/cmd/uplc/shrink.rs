@@ -2,10 +2,11 @@ use miette::IntoDiagnostic;
 use std::path::PathBuf;
 use uplc::{
     ast::{DeBruijn, Name, NamedDeBruijn, Program},
-    optimize::aiken_optimize_and_intern,
+    optimize::{OptLevel, aiken_optimize_and_intern_with, report::Report},
 };
 
 use super::{Format, encode};
+use crate::cmd::build::opt_level_parser;
 
 #[derive(clap::Args)]
 #[clap(disable_version_flag(true))]
@@ -29,6 +30,15 @@ pub struct Args {
     /// Input file contents will be hex decoded
     #[clap(long)]
     hex: bool,
+
+    /// What to optimize for
+    #[clap(short = 'O', long, value_parser=opt_level_parser(), default_value = "size")]
+    opt_level: OptLevel,
+
+    /// Print the node count, flat size and estimated execution cost of the program after each
+    /// optimization pass
+    #[clap(long)]
+    opt_report: bool,
 }
 
 pub fn exec(
@@ -38,6 +48,8 @@ pub fn exec(
         to,
         cbor,
         hex,
+        opt_level,
+        opt_report,
     }: Args,
 ) -> miette::Result<()> {
     let bytes = if hex {
@@ -79,7 +91,13 @@ pub fn exec(
         }
     };
 
-    let optimized_program = aiken_optimize_and_intern(program);
+    let optimized_program = if opt_report {
+        let (optimized_program, report) = Report::new(program, opt_level);
+        eprintln!("{report}");
+        optimized_program
+    } else {
+        aiken_optimize_and_intern_with(program, opt_level, &mut |_, _| {})
+    };
 
     match to {
         Format::Name => encode::encode(optimized_program, cbor, hex),
@@ -3,12 +3,15 @@ use crate::{
     debruijn::{self, Converter},
     flat::Binder,
     machine::{
-        Machine,
+        Error as MachineError, Machine,
         cost_model::{CostModel, ExBudget, initialize_cost_model},
+        coverage::Coverage,
         eval_result::EvalResult,
+        profiler::Profile,
         value::to_pallas_bigint,
     },
     optimize::interner::CodeGenInterner,
+    source_map::{SourceLocation, SourceMap},
     tx::script_context::PlutusScript,
 };
 use num_bigint::BigInt;
@@ -24,10 +27,12 @@ use serde::{
     ser::{Serialize, SerializeStruct, Serializer},
 };
 use std::{
+    collections::BTreeSet,
     convert::AsRef,
     fmt::{self, Display},
     hash::{self, Hash},
     rc::Rc,
+    time::Instant,
 };
 
 /// This represents a program in Untyped Plutus Core.
@@ -339,6 +344,101 @@ impl<T> Term<T> {
     }
 }
 
+impl<T> Term<T> {
+    /// The direct sub-terms of this term, in the order used by `TermPath`:
+    /// the function before the argument of an application, the scrutinee
+    /// before the branches of a case.
+    pub fn children(&self) -> Vec<&Term<T>> {
+        match self {
+            Term::Var(_) | Term::Constant(_) | Term::Error | Term::Builtin(_) => vec![],
+            Term::Delay(body) | Term::Force(body) | Term::Lambda { body, .. } => {
+                vec![body.as_ref()]
+            }
+            Term::Apply { function, argument } => vec![function.as_ref(), argument.as_ref()],
+            Term::Constr { fields, .. } => fields.iter().collect(),
+            Term::Case { constr, branches } => std::iter::once(constr.as_ref())
+                .chain(branches.iter())
+                .collect(),
+        }
+    }
+
+    /// Lookup the sub-term located at the given path, if any.
+    pub fn at_path(&self, path: &TermPath) -> Option<&Term<T>> {
+        path.0
+            .iter()
+            .try_fold(self, |term, ix| term.children().get(*ix).copied())
+    }
+}
+
+/// A path from the root of a term down to one of its sub-terms. Each
+/// segment is the index of the child to descend into, following the
+/// order given by `Term::children`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TermPath(pub Vec<usize>);
+
+impl TermPath {
+    pub fn root() -> Self {
+        TermPath(vec![])
+    }
+
+    pub fn child(&self, ix: usize) -> Self {
+        let mut segments = self.0.clone();
+        segments.push(ix);
+        TermPath(segments)
+    }
+
+    pub fn parent(&self) -> Option<Self> {
+        let (_, init) = self.0.split_last()?;
+        Some(TermPath(init.to_vec()))
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn starts_with(&self, prefix: &TermPath) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+}
+
+impl Display for TermPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_root() {
+            return f.write_str("root");
+        }
+
+        let segments = self
+            .0
+            .iter()
+            .map(|ix| ix.to_string())
+            .collect::<Vec<_>>()
+            .join(".");
+
+        f.write_str(&segments)
+    }
+}
+
+impl std::str::FromStr for TermPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if s.is_empty() || s == "root" {
+            return Ok(TermPath::root());
+        }
+
+        s.split('.')
+            .map(|segment| {
+                segment
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid term path segment '{segment}'"))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(TermPath)
+    }
+}
+
 impl<T> TryInto<PlutusData> for Term<T> {
     type Error = String;
 
@@ -857,7 +957,19 @@ impl Program<NamedDeBruijn> {
 
     /// Evaluate a Program as a specific PlutusVersion
     pub fn eval_version(self, initial_budget: ExBudget, version: &Language) -> EvalResult {
-        let mut machine = Machine::new(version.clone(), CostModel::default(), initial_budget, 200);
+        self.eval_version_with_deadline(initial_budget, version, None)
+    }
+
+    /// Evaluate a Program as a specific PlutusVersion, failing with 'Error::TimedOut'
+    /// if the evaluation is still running past the given deadline.
+    pub fn eval_version_with_deadline(
+        self,
+        initial_budget: ExBudget,
+        version: &Language,
+        deadline: Option<Instant>,
+    ) -> EvalResult {
+        let mut machine = Machine::new(version.clone(), CostModel::default(), initial_budget, 200)
+            .with_deadline(deadline);
 
         let term = machine.run(self.term);
 
@@ -870,6 +982,47 @@ impl Program<NamedDeBruijn> {
         )
     }
 
+    /// Evaluate a Program as a specific PlutusVersion, attributing the budget
+    /// spent to the functions located by the given source map.
+    pub fn profile(
+        self,
+        initial_budget: ExBudget,
+        version: &Language,
+        source_map: &SourceMap,
+    ) -> Profile {
+        let machine = Machine::new(version.clone(), CostModel::default(), initial_budget, 200);
+
+        let (_, profile) = machine.profile(self.term, source_map);
+
+        profile
+    }
+
+    /// Evaluate a Program as a specific PlutusVersion and, should it fail, find where in the
+    /// source code it did. See [`Machine::locate_failure`].
+    pub fn locate_failure(
+        self,
+        initial_budget: ExBudget,
+        version: &Language,
+        source_map: &SourceMap,
+    ) -> Option<SourceLocation> {
+        let machine = Machine::new(version.clone(), CostModel::default(), initial_budget, 200);
+
+        machine.locate_failure(self.term, source_map)
+    }
+
+    /// Evaluate a Program as a specific PlutusVersion, recording which of the given branches are
+    /// taken. See [`crate::machine::coverage::branches`].
+    pub fn cover(
+        self,
+        initial_budget: ExBudget,
+        version: &Language,
+        branches: &BTreeSet<TermPath>,
+    ) -> (Result<Term<NamedDeBruijn>, MachineError>, Coverage) {
+        let machine = Machine::new(version.clone(), CostModel::default(), initial_budget, 200);
+
+        machine.cover(self.term, branches)
+    }
+
     pub fn eval_as(
         self,
         version: &Language,
@@ -0,0 +1,421 @@
+//! A runner for the Plutus conformance test suite.
+//!
+//! The suite is a directory tree in which each test case is a '.uplc' program, next to a
+//! '.uplc.expected' file holding the expected result (or 'parse error' / 'evaluation failure'),
+//! and optionally a '.uplc.budget.expected' file holding the expected execution units. Results
+//! are compared for every language, while budgets are only compared for Plutus V3, the version
+//! the reference budgets are computed for.
+
+use crate::{
+    ast::{Name, NamedDeBruijn, Program, Term},
+    builtins::DefaultFunction,
+    machine::cost_model::ExBudget,
+    parser,
+};
+use pallas_primitives::conway::Language;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ffi::OsStr,
+    fmt::{self, Display},
+    fs, io,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+const PARSE_ERROR: &str = "parse error";
+const EVALUATION_FAILURE: &str = "evaluation failure";
+
+#[derive(Debug, Clone)]
+pub struct Runner {
+    language: Language,
+    costs: Option<Vec<i64>>,
+}
+
+impl Runner {
+    /// Run test cases against the default cost model.
+    pub fn new(language: Language) -> Self {
+        Runner {
+            language,
+            costs: None,
+        }
+    }
+
+    /// Run test cases against the cost model given by these parameters, as found in protocol
+    /// parameters.
+    pub fn with_costs(self, costs: Vec<i64>) -> Self {
+        Runner {
+            costs: Some(costs),
+            ..self
+        }
+    }
+
+    /// Run every test case found under the given directory, in lexicographic order of their paths.
+    pub fn run(&self, root: &Path) -> io::Result<Report> {
+        let mut cases = Vec::new();
+
+        for entry in WalkDir::new(root).sort_by_file_name() {
+            let entry = entry?;
+
+            if entry.path().extension().and_then(OsStr::to_str) == Some("uplc") {
+                cases.push(self.run_case(entry.path())?);
+            }
+        }
+
+        Ok(Report { cases })
+    }
+
+    pub fn run_case(&self, path: &Path) -> io::Result<Case> {
+        let code = fs::read_to_string(path)?;
+        let expected = fs::read_to_string(path.with_extension("uplc.expected"))?;
+        let expected_budget = fs::read_to_string(path.with_extension("uplc.budget.expected")).ok();
+
+        let program = parser::program(&code).ok();
+
+        let builtins = program
+            .as_ref()
+            .map(|program| builtins(&program.term))
+            .unwrap_or_default();
+
+        let (actual, budget) = match program {
+            None => (Err(PARSE_ERROR.to_string()), None),
+            Some(program) => self.eval(program),
+        };
+
+        let expected = expected_result(&expected);
+
+        let verdict = if expected != actual {
+            Verdict::WrongResult {
+                expected: show(expected),
+                actual: show(actual),
+            }
+        } else {
+            match (
+                self.language == Language::PlutusV3,
+                budget,
+                expected_budget.and_then(|src| budget::ex_budget(&src).ok()),
+            ) {
+                (true, Some(actual), Some(expected)) if actual != expected => {
+                    Verdict::WrongBudget { expected, actual }
+                }
+                _ => Verdict::Passed,
+            }
+        };
+
+        Ok(Case {
+            path: path.to_path_buf(),
+            builtins,
+            verdict,
+        })
+    }
+
+    fn eval(
+        &self,
+        program: Program<Name>,
+    ) -> (Result<Program<NamedDeBruijn>, String>, Option<ExBudget>) {
+        let Ok(program) = Program::<NamedDeBruijn>::try_from(program) else {
+            return (Err(EVALUATION_FAILURE.to_string()), None);
+        };
+
+        let version = program.version;
+
+        let eval = match &self.costs {
+            None => program.eval_version(ExBudget::default(), &self.language),
+            Some(costs) => program.eval_as(&self.language, costs, None),
+        };
+
+        let cost = eval.cost();
+
+        match eval.result() {
+            Ok(term) => (Ok(Program { version, term }), Some(cost)),
+            Err(..) => (Err(EVALUATION_FAILURE.to_string()), Some(cost)),
+        }
+    }
+}
+
+fn expected_result(code: &str) -> Result<Program<NamedDeBruijn>, String> {
+    if code.contains(PARSE_ERROR) {
+        Err(PARSE_ERROR.to_string())
+    } else if code.contains(EVALUATION_FAILURE) {
+        Err(EVALUATION_FAILURE.to_string())
+    } else {
+        parser::program(code)
+            .ok()
+            .and_then(|program| program.try_into().ok())
+            .ok_or_else(|| code.trim().to_string())
+    }
+}
+
+fn show(result: Result<Program<NamedDeBruijn>, String>) -> String {
+    match result {
+        Ok(program) => program.to_pretty(),
+        Err(error) => error,
+    }
+}
+
+/// The builtins a term refers to.
+fn builtins(term: &Term<Name>) -> BTreeSet<DefaultFunction> {
+    let mut builtins = BTreeSet::new();
+    let mut terms = vec![term];
+
+    while let Some(term) = terms.pop() {
+        if let Term::Builtin(fun) = term {
+            builtins.insert(*fun);
+        }
+        terms.extend(term.children());
+    }
+
+    builtins
+}
+
+peg::parser! {
+    grammar budget() for str {
+        pub rule ex_budget() -> ExBudget
+          = "({" _* "cpu" _* ":" _* cpu:decimal() _* "|" _* "mem" _* ":" _* mem:decimal() _* "})" _* {
+              ExBudget { cpu, mem }
+          }
+
+        rule decimal() -> i64
+          = n:$(['0'..='9']+) {? n.parse().or(Err("decimal")) }
+
+        rule _ = [' ' | '\n' | '\r' | '\t'] / "--" $([^ '\n']*) "\n"
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Passed,
+    WrongResult {
+        expected: String,
+        actual: String,
+    },
+    /// The result is the expected one, but evaluating the program spent a different budget.
+    WrongBudget {
+        expected: ExBudget,
+        actual: ExBudget,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Case {
+    pub path: PathBuf,
+    /// Builtins the program refers to, to which the outcome of the case is attributed.
+    pub builtins: BTreeSet<DefaultFunction>,
+    pub verdict: Verdict,
+}
+
+impl Case {
+    pub fn passed(&self) -> bool {
+        self.verdict == Verdict::Passed
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub passed: usize,
+    pub failed: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub cases: Vec<Case>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.cases.iter().all(Case::passed)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &Case> {
+        self.cases.iter().filter(|case| !case.passed())
+    }
+
+    pub fn total(&self) -> Tally {
+        self.cases.iter().fold(Tally::default(), tally)
+    }
+
+    /// Outcomes of the test cases referring to each builtin. A case referring to several builtins
+    /// counts for each of them.
+    pub fn by_builtin(&self) -> BTreeMap<DefaultFunction, Tally> {
+        let mut builtins = BTreeMap::new();
+
+        for case in self.cases.iter() {
+            for fun in case.builtins.iter() {
+                let entry = builtins.entry(*fun).or_default();
+                *entry = tally(*entry, case);
+            }
+        }
+
+        builtins
+    }
+}
+
+fn tally(Tally { passed, failed }: Tally, case: &Case) -> Tally {
+    if case.passed() {
+        Tally {
+            passed: passed + 1,
+            failed,
+        }
+    } else {
+        Tally {
+            passed,
+            failed: failed + 1,
+        }
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let by_builtin = self.by_builtin();
+
+        let pad = by_builtin
+            .keys()
+            .map(|fun| fun.to_string().len())
+            .max()
+            .unwrap_or_default();
+
+        for (fun, Tally { passed, failed }) in by_builtin {
+            writeln!(
+                f,
+                "{} {fun:<pad$} {passed:>4} passed {failed:>4} failed",
+                if failed == 0 { "PASS" } else { "FAIL" },
+                fun = fun.to_string(),
+            )?;
+        }
+
+        for case in self.failures() {
+            writeln!(f, "\n{}", case.path.display())?;
+
+            match &case.verdict {
+                Verdict::Passed => {}
+                Verdict::WrongResult { expected, actual } => {
+                    writeln!(
+                        f,
+                        "  expected: {}",
+                        expected.replace('\n', "\n            ")
+                    )?;
+                    writeln!(f, "  actual:   {}", actual.replace('\n', "\n            "))?;
+                }
+                Verdict::WrongBudget { expected, actual } => {
+                    writeln!(
+                        f,
+                        "  expected: {{ mem: {}, cpu: {} }}",
+                        expected.mem, expected.cpu
+                    )?;
+                    writeln!(
+                        f,
+                        "  actual:   {{ mem: {}, cpu: {} }}",
+                        actual.mem, actual.cpu
+                    )?;
+                }
+            }
+        }
+
+        let Tally { passed, failed } = self.total();
+
+        write!(
+            f,
+            "\n{} cases: {passed} passed, {failed} failed",
+            passed + failed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Runner, Tally, Verdict};
+    use crate::{builtins::DefaultFunction, machine::cost_model::ExBudget};
+    use pallas_primitives::conway::Language;
+    use std::{fs, path::Path};
+
+    fn write_case(dir: &Path, name: &str, program: &str, expected: &str, budget: Option<&str>) {
+        let dir = dir.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(format!("{name}.uplc")), program).unwrap();
+        fs::write(dir.join(format!("{name}.uplc.expected")), expected).unwrap();
+        if let Some(budget) = budget {
+            fs::write(dir.join(format!("{name}.uplc.budget.expected")), budget).unwrap();
+        }
+    }
+
+    #[test]
+    fn report_per_builtin() {
+        let dir = std::env::temp_dir().join(format!("uplc-conformance-{}", std::process::id()));
+
+        write_case(
+            &dir,
+            "add",
+            "(program 1.0.0 [ [ (builtin addInteger) (con integer 1)] (con integer 1) ])",
+            "(program 1.0.0 (con integer 2))",
+            Some("({cpu: 181308\n| mem: 602})"),
+        );
+        write_case(
+            &dir,
+            "add-budget",
+            "(program 1.0.0 [ [ (builtin addInteger) (con integer 1)] (con integer 1) ])",
+            "(program 1.0.0 (con integer 2))",
+            Some("({cpu: 1\n| mem: 1})"),
+        );
+        write_case(
+            &dir,
+            "div",
+            "(program 1.0.0 [ [ (builtin divideInteger) (con integer 1)] (con integer 0) ])",
+            "(program 1.0.0 (con integer 0))",
+            None,
+        );
+        write_case(&dir, "garbage", "(program", "parse error", None);
+
+        let report = Runner::new(Language::PlutusV3).run(&dir).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!report.passed());
+        assert_eq!(
+            report.total(),
+            Tally {
+                passed: 2,
+                failed: 2
+            }
+        );
+        assert_eq!(
+            report.by_builtin().into_iter().collect::<Vec<_>>(),
+            vec![
+                (
+                    DefaultFunction::AddInteger,
+                    Tally {
+                        passed: 1,
+                        failed: 1
+                    }
+                ),
+                (
+                    DefaultFunction::DivideInteger,
+                    Tally {
+                        passed: 0,
+                        failed: 1
+                    }
+                ),
+            ]
+        );
+
+        let verdicts = report
+            .failures()
+            .map(|case| case.verdict.clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            verdicts,
+            vec![
+                Verdict::WrongBudget {
+                    expected: ExBudget { mem: 1, cpu: 1 },
+                    actual: ExBudget {
+                        mem: 602,
+                        cpu: 181308
+                    },
+                },
+                Verdict::WrongResult {
+                    expected: "(program\n  1.0.0\n  (con integer 0)\n)".to_string(),
+                    actual: "evaluation failure".to_string(),
+                },
+            ]
+        );
+    }
+}
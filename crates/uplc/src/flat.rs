@@ -14,6 +14,8 @@ use pallas_codec::flat::{
 use pallas_primitives::{Fragment, conway::PlutusData};
 use std::{collections::VecDeque, fmt::Debug, rc::Rc};
 
+pub mod breakdown;
+
 const BUILTIN_TAG_WIDTH: u32 = 7;
 const CONST_TAG_WIDTH: u32 = 4;
 const TERM_TAG_WIDTH: u32 = 4;
@@ -0,0 +1,271 @@
+//! Attribution of the flat-encoded size of a program to its parts.
+//!
+//! The size of a part is measured as the number of bytes the encoded program would save without
+//! it, that is, with the part replaced by 'error'. Parts are the definitions bound in the
+//! program, as recognized by source maps, and the constants it holds, which are counted across
+//! all their occurrences.
+
+use crate::{
+    ast::{Constant, DeBruijn, Name, Program, Term, TermPath},
+    source_map::bound_terms,
+};
+use std::rc::Rc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakdown {
+    /// Size of the whole program, in bytes.
+    pub total: usize,
+    /// Definitions by decreasing size, not counting the definitions nested in them.
+    pub definitions: Vec<Definition>,
+    /// Constants by decreasing size.
+    pub constants: Vec<Constants>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    /// The name the definition is bound to.
+    pub name: String,
+    /// Path to the body of the definition.
+    pub path: TermPath,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constants {
+    pub constant: Rc<Constant>,
+    pub occurrences: usize,
+    pub size: usize,
+}
+
+impl Breakdown {
+    /// Break a program down, or fail if it can't be encoded.
+    pub fn new(program: &Program<Name>) -> Option<Self> {
+        let total = flat_size(program)?;
+
+        let mut bindings = Vec::new();
+        let mut constants: Vec<(Rc<Constant>, usize)> = Vec::new();
+        let mut stack = vec![(&program.term, TermPath::root())];
+
+        while let Some((term, path)) = stack.pop() {
+            bindings.extend(
+                bound_terms(term, &path)
+                    .into_iter()
+                    .map(|(path, name)| (name.clone(), path)),
+            );
+
+            if let Term::Constant(constant) = term {
+                match constants.iter_mut().find(|(known, _)| known == constant) {
+                    Some((_, occurrences)) => *occurrences += 1,
+                    None => constants.push((constant.clone(), 1)),
+                }
+            }
+
+            for (ix, child) in term.children().into_iter().enumerate() {
+                stack.push((child, path.child(ix)));
+            }
+        }
+
+        let saving = |term: Term<Name>| {
+            flat_size(&Program {
+                version: program.version,
+                term,
+            })
+            .map(|size| total.saturating_sub(size))
+        };
+
+        let inclusive = bindings
+            .into_iter()
+            .map(|(name, path)| {
+                let size = saving(erase_at(&program.term, &path.0))?;
+                Some((name, path, size))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        // The definition each definition is nested in, if any, is the closest enclosing one.
+        let parents = inclusive
+            .iter()
+            .map(|(_, path, _)| {
+                inclusive
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, other, _))| other != path && path.starts_with(other))
+                    .max_by_key(|(_, (_, other, _))| other.0.len())
+                    .map(|(ix, _)| ix)
+            })
+            .collect::<Vec<_>>();
+
+        let mut definitions = inclusive
+            .iter()
+            .enumerate()
+            .map(|(ix, (name, path, size))| {
+                let nested = parents
+                    .iter()
+                    .zip(inclusive.iter())
+                    .filter(|(parent, _)| **parent == Some(ix))
+                    .map(|(_, (_, _, size))| size)
+                    .sum::<usize>();
+
+                Definition {
+                    name: name.clone(),
+                    path: path.clone(),
+                    size: size.saturating_sub(nested),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        definitions.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+
+        let mut constants = constants
+            .into_iter()
+            .map(|(constant, occurrences)| {
+                let size = saving(erase_constant(&program.term, &constant))?;
+                Some(Constants {
+                    constant,
+                    occurrences,
+                    size,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        constants.sort_by_key(|c| std::cmp::Reverse(c.size));
+
+        Some(Breakdown {
+            total,
+            definitions,
+            constants,
+        })
+    }
+}
+
+fn flat_size(program: &Program<Name>) -> Option<usize> {
+    Program::<DeBruijn>::try_from(program.clone())
+        .ok()?
+        .to_flat()
+        .ok()
+        .map(|bytes| bytes.len())
+}
+
+/// Replace the sub-term found at the given path by 'error'.
+fn erase_at(term: &Term<Name>, path: &[usize]) -> Term<Name> {
+    let Some((ix, rest)) = path.split_first() else {
+        return Term::Error;
+    };
+
+    match term {
+        Term::Delay(body) => Term::Delay(erase_at(body, rest).into()),
+        Term::Force(body) => Term::Force(erase_at(body, rest).into()),
+        Term::Lambda {
+            parameter_name,
+            body,
+        } => Term::Lambda {
+            parameter_name: parameter_name.clone(),
+            body: erase_at(body, rest).into(),
+        },
+        Term::Apply { function, argument } if *ix == 0 => Term::Apply {
+            function: erase_at(function, rest).into(),
+            argument: argument.clone(),
+        },
+        Term::Apply { function, argument } => Term::Apply {
+            function: function.clone(),
+            argument: erase_at(argument, rest).into(),
+        },
+        Term::Constr { tag, fields } => {
+            let mut fields = fields.clone();
+            fields[*ix] = erase_at(&fields[*ix], rest);
+            Term::Constr { tag: *tag, fields }
+        }
+        Term::Case { constr, branches } if *ix == 0 => Term::Case {
+            constr: erase_at(constr, rest).into(),
+            branches: branches.clone(),
+        },
+        Term::Case { constr, branches } => {
+            let mut branches = branches.clone();
+            branches[ix - 1] = erase_at(&branches[ix - 1], rest);
+            Term::Case {
+                constr: constr.clone(),
+                branches,
+            }
+        }
+        Term::Var(..) | Term::Constant(..) | Term::Error | Term::Builtin(..) => term.clone(),
+    }
+}
+
+/// Replace every occurrence of the given constant by 'error'.
+fn erase_constant(term: &Term<Name>, constant: &Rc<Constant>) -> Term<Name> {
+    match term {
+        Term::Constant(other) if other == constant => Term::Error,
+        Term::Delay(body) => Term::Delay(erase_constant(body, constant).into()),
+        Term::Force(body) => Term::Force(erase_constant(body, constant).into()),
+        Term::Lambda {
+            parameter_name,
+            body,
+        } => Term::Lambda {
+            parameter_name: parameter_name.clone(),
+            body: erase_constant(body, constant).into(),
+        },
+        Term::Apply { function, argument } => Term::Apply {
+            function: erase_constant(function, constant).into(),
+            argument: erase_constant(argument, constant).into(),
+        },
+        Term::Constr { tag, fields } => Term::Constr {
+            tag: *tag,
+            fields: fields
+                .iter()
+                .map(|field| erase_constant(field, constant))
+                .collect(),
+        },
+        Term::Case { constr, branches } => Term::Case {
+            constr: erase_constant(constr, constant).into(),
+            branches: branches
+                .iter()
+                .map(|branch| erase_constant(branch, constant))
+                .collect(),
+        },
+        Term::Var(..) | Term::Constant(..) | Term::Error | Term::Builtin(..) => term.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Breakdown;
+    use crate::{
+        ast::{Constant, TermPath},
+        parser,
+    };
+
+    #[test]
+    fn attribute_definitions_and_constants() {
+        let program = parser::program(
+            r#"(program 1.1.0
+                [ (lam big
+                    [ (lam small
+                        [ [ (builtin appendByteString) big ] [ small (con bytestring #0000000000000000000000000000000000000000000000000000000000000000) ] ])
+                      (lam x x) ])
+                  (lam y [ [ (builtin appendByteString) y ] (con bytestring #0000000000000000000000000000000000000000000000000000000000000000) ]) ])"#,
+        )
+        .unwrap();
+
+        let breakdown = Breakdown::new(&program).unwrap();
+
+        assert_eq!(
+            breakdown
+                .definitions
+                .iter()
+                .map(|definition| definition.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["big", "small"]
+        );
+        assert_eq!(breakdown.definitions[0].path, TermPath(vec![1]));
+        assert!(breakdown.definitions[0].size > 32);
+        assert!(breakdown.definitions[1].size <= 2);
+
+        let largest = &breakdown.constants[0];
+        assert_eq!(
+            largest.constant.as_ref(),
+            &Constant::ByteString(vec![0; 32])
+        );
+        assert_eq!(largest.occurrences, 2);
+        assert!(largest.size >= 64);
+        assert!(largest.size < breakdown.total);
+    }
+}
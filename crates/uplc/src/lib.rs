@@ -1,12 +1,14 @@
 pub mod ast;
 pub mod builder;
 pub mod builtins;
+pub mod conformance;
 mod debruijn;
 pub mod flat;
 pub mod machine;
 pub mod optimize;
 pub mod parser;
 mod pretty;
+pub mod source_map;
 pub mod tx;
 
 pub use pallas_codec::utils::KeyValuePairs;
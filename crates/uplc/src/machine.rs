@@ -1,12 +1,16 @@
-use std::{fmt::Display, rc::Rc};
+use std::{fmt::Display, rc::Rc, time::Instant};
 
 use crate::ast::{Constant, NamedDeBruijn, Term, Type};
 
 pub mod cost_model;
+pub mod coverage;
+pub mod debugger;
 mod discharge;
 mod error;
 pub mod eval_result;
+pub mod profiler;
 pub mod runtime;
+pub mod symbolic;
 pub mod value;
 
 use cost_model::{ExBudget, StepKind};
@@ -27,21 +31,67 @@ enum MachineState {
 
 #[derive(Clone)]
 enum Context {
-    FrameAwaitArg(Value, Box<Context>),
-    FrameAwaitFunTerm(Env, Term<NamedDeBruijn>, Box<Context>),
-    FrameAwaitFunValue(Value, Box<Context>),
-    FrameForce(Box<Context>),
+    FrameAwaitArg(Value, Continuation),
+    FrameAwaitFunTerm(Env, Rc<Term<NamedDeBruijn>>, Continuation),
+    FrameAwaitFunValue(Value, Continuation),
+    FrameForce(Continuation),
     FrameConstr(
         Env,
         usize,
         Vec<Term<NamedDeBruijn>>,
         Vec<Value>,
-        Box<Context>,
+        Continuation,
     ),
-    FrameCases(Env, Vec<Term<NamedDeBruijn>>, Box<Context>),
+    FrameCases(Env, Vec<Term<NamedDeBruijn>>, Continuation),
     NoFrame,
 }
 
+/// The frames following a given one. Frames can nest arbitrarily deep, so they are dropped
+/// iteratively rather than recursively; an evaluation aborted deep into a recursion would
+/// otherwise overflow the stack while unwinding.
+#[derive(Clone)]
+struct Continuation(Box<Context>);
+
+impl Continuation {
+    fn into_inner(mut self) -> Context {
+        std::mem::replace(&mut self.0, Context::NoFrame)
+    }
+}
+
+impl std::ops::Deref for Continuation {
+    type Target = Context;
+
+    fn deref(&self) -> &Context {
+        &self.0
+    }
+}
+
+impl From<Context> for Continuation {
+    fn from(context: Context) -> Self {
+        Continuation(Box::new(context))
+    }
+}
+
+impl Drop for Continuation {
+    fn drop(&mut self) {
+        let mut context = std::mem::replace(self.0.as_mut(), Context::NoFrame);
+
+        loop {
+            context = match &mut context {
+                Context::FrameAwaitArg(_, next)
+                | Context::FrameAwaitFunTerm(_, _, next)
+                | Context::FrameAwaitFunValue(_, next)
+                | Context::FrameForce(next)
+                | Context::FrameConstr(_, _, _, _, next)
+                | Context::FrameCases(_, _, next) => {
+                    std::mem::replace(next.0.as_mut(), Context::NoFrame)
+                }
+                Context::NoFrame => break,
+            };
+        }
+    }
+}
+
 pub const TERM_COUNT: usize = 9;
 pub const BUILTIN_COUNT: usize = 87;
 
@@ -84,6 +134,7 @@ pub struct Machine {
     pub traces: Vec<Trace>,
     pub spend_counter: Option<[i64; (TERM_COUNT + BUILTIN_COUNT) * 2]>,
     version: Language,
+    deadline: Option<Instant>,
 }
 
 impl Machine {
@@ -101,6 +152,7 @@ impl Machine {
             traces: vec![],
             spend_counter: None,
             version,
+            deadline: None,
         }
     }
 
@@ -118,9 +170,16 @@ impl Machine {
             traces: vec![],
             spend_counter: Some([0; (TERM_COUNT + BUILTIN_COUNT) * 2]),
             version,
+            deadline: None,
         }
     }
 
+    /// Abort evaluation once the given instant is past. The clock is only looked at when
+    /// unbudgeted steps are spent, so evaluation may slightly overrun the deadline.
+    pub fn with_deadline(self, deadline: Option<Instant>) -> Machine {
+        Machine { deadline, ..self }
+    }
+
     pub fn run(&mut self, term: Term<NamedDeBruijn>) -> Result<Term<NamedDeBruijn>, Error> {
         use MachineState::*;
 
@@ -132,15 +191,22 @@ impl Machine {
 
         loop {
             state = match state {
-                Compute(context, env, t) => self.compute(context, env, t)?,
-                Return(context, value) => self.return_compute(context, value)?,
                 Done(t) => {
                     return Ok(t);
                 }
+                _ => self.step(state)?,
             };
         }
     }
 
+    fn step(&mut self, state: MachineState) -> Result<MachineState, Error> {
+        match state {
+            MachineState::Compute(context, env, t) => self.compute(context, env, t),
+            MachineState::Return(context, value) => self.return_compute(context, value),
+            done @ MachineState::Done(_) => Ok(done),
+        }
+    }
+
     fn compute(
         &mut self,
         context: Context,
@@ -179,11 +245,7 @@ impl Machine {
                 self.step_and_maybe_spend(StepKind::Apply)?;
 
                 Ok(MachineState::Compute(
-                    Context::FrameAwaitFunTerm(
-                        env.clone(),
-                        argument.as_ref().clone(),
-                        context.into(),
-                    ),
+                    Context::FrameAwaitFunTerm(env.clone(), argument, context.into()),
                     env,
                     function.as_ref().clone(),
                 ))
@@ -259,14 +321,16 @@ impl Machine {
 
                 Ok(MachineState::Done(term))
             }
-            Context::FrameForce(ctx) => self.force_evaluate(*ctx, value),
+            Context::FrameForce(ctx) => self.force_evaluate(ctx.into_inner(), value),
             Context::FrameAwaitFunTerm(arg_env, arg, ctx) => Ok(MachineState::Compute(
                 Context::FrameAwaitArg(value, ctx),
                 arg_env,
-                arg,
+                arg.as_ref().clone(),
             )),
-            Context::FrameAwaitArg(fun, ctx) => self.apply_evaluate(*ctx, fun, value),
-            Context::FrameAwaitFunValue(arg, ctx) => self.apply_evaluate(*ctx, value, arg),
+            Context::FrameAwaitArg(fun, ctx) => self.apply_evaluate(ctx.into_inner(), fun, value),
+            Context::FrameAwaitFunValue(arg, ctx) => {
+                self.apply_evaluate(ctx.into_inner(), value, arg)
+            }
             Context::FrameConstr(env, tag, mut fields, mut resolved_fields, ctx) => {
                 resolved_fields.push(value);
 
@@ -280,7 +344,7 @@ impl Machine {
                     ))
                 } else {
                     Ok(MachineState::Return(
-                        *ctx,
+                        ctx.into_inner(),
                         Value::Constr {
                             tag,
                             fields: resolved_fields,
@@ -291,7 +355,7 @@ impl Machine {
             Context::FrameCases(env, branches, ctx) => match value {
                 Value::Constr { tag, fields } => match branches.get(tag) {
                     Some(t) => Ok(MachineState::Compute(
-                        transfer_arg_stack(fields, *ctx),
+                        transfer_arg_stack(fields, ctx.into_inner()),
                         env,
                         t.clone(),
                     )),
@@ -424,6 +488,12 @@ impl Machine {
 
         self.unbudgeted_steps[9] = 0;
 
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                return Err(Error::TimedOut);
+            }
+        }
+
         Ok(())
     }
 
@@ -473,12 +543,23 @@ impl From<&Constant> for Type {
 mod tests {
     use num_bigint::BigInt;
 
-    use super::{cost_model::ExBudget, runtime::Compressable};
+    use super::{Context, cost_model::ExBudget, runtime::Compressable};
     use crate::{
         ast::{Constant, NamedDeBruijn, Program, Term},
         builtins::DefaultFunction,
     };
 
+    #[test]
+    fn drop_deep_context() {
+        let mut context = Context::NoFrame;
+
+        for _ in 0..1_000_000 {
+            context = Context::FrameForce(context.into());
+        }
+
+        drop(context);
+    }
+
     #[test]
     fn add_big_ints() {
         let program: Program<NamedDeBruijn> = Program {
@@ -58,6 +58,17 @@ impl std::ops::Sub for ExBudget {
     }
 }
 
+impl std::ops::Add for ExBudget {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        ExBudget {
+            mem: self.mem + rhs.mem,
+            cpu: self.cpu + rhs.cpu,
+        }
+    }
+}
+
 #[derive(Default, Debug, PartialEq)]
 pub struct CostModel {
     pub machine_costs: MachineCosts,
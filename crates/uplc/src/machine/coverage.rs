@@ -0,0 +1,170 @@
+use super::{Error, Machine, cost_model::ExBudget, debugger::Event};
+use crate::ast::{NamedDeBruijn, Term, TermPath};
+use std::collections::BTreeSet;
+
+/// Branches of a term taken by an evaluation, along with the traces it emitted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    pub taken: BTreeSet<TermPath>,
+    pub logs: Vec<String>,
+    pub spent: ExBudget,
+}
+
+/// Paths of the terms that are only computed under some condition: the branches of a `case`, and
+/// the bodies of `delay`, through which the branches of `ifThenElse` and of the `choose*`
+/// builtins are kept lazy.
+pub fn branches(term: &Term<NamedDeBruijn>) -> BTreeSet<TermPath> {
+    let mut branches = BTreeSet::new();
+    collect_branches(term, TermPath::root(), &mut branches);
+    branches
+}
+
+fn collect_branches(term: &Term<NamedDeBruijn>, path: TermPath, branches: &mut BTreeSet<TermPath>) {
+    match term {
+        Term::Delay(body) => {
+            branches.insert(path.child(0));
+            collect_branches(body, path.child(0), branches);
+        }
+        Term::Case {
+            constr,
+            branches: cases,
+        } => {
+            collect_branches(constr, path.child(0), branches);
+            for (ix, case) in cases.iter().enumerate() {
+                branches.insert(path.child(ix + 1));
+                collect_branches(case, path.child(ix + 1), branches);
+            }
+        }
+        Term::Force(body) | Term::Lambda { body, .. } => {
+            collect_branches(body, path.child(0), branches)
+        }
+        Term::Apply { function, argument } => {
+            collect_branches(function, path.child(0), branches);
+            collect_branches(argument, path.child(1), branches);
+        }
+        Term::Constr { fields, .. } => {
+            for (ix, field) in fields.iter().enumerate() {
+                collect_branches(field, path.child(ix), branches);
+            }
+        }
+        Term::Var(_) | Term::Constant(_) | Term::Error | Term::Builtin(_) => {}
+    }
+}
+
+impl Machine {
+    /// Run a term to completion, recording which of the given branches are taken.
+    ///
+    /// Branches are recognised by the path of the term computed when entering them, which may be
+    /// unknown for leaves (variables, constants, builtins) that are branches of a `case`; those
+    /// are never reported as taken.
+    pub fn cover(
+        self,
+        term: Term<NamedDeBruijn>,
+        branches: &BTreeSet<TermPath>,
+    ) -> (Result<Term<NamedDeBruijn>, Error>, Coverage) {
+        let mut debugger = self.debugger(term);
+
+        let mut taken = BTreeSet::new();
+
+        while debugger.step() != Event::Finished {
+            if debugger.is_computing() {
+                if let Some(path) = debugger.path().filter(|path| branches.contains(*path)) {
+                    taken.insert(path.clone());
+                }
+            }
+        }
+
+        let result = debugger
+            .result()
+            .cloned()
+            .expect("covered evaluation finished without result");
+
+        let coverage = Coverage {
+            taken,
+            logs: debugger
+                .traces()
+                .iter()
+                .cloned()
+                .filter_map(|trace| trace.unwrap_log())
+                .collect(),
+            spent: debugger.budget_spent(),
+        };
+
+        (result, coverage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::branches;
+    use crate::{
+        ast::{Name, NamedDeBruijn, Program, TermPath},
+        machine::{
+            Machine,
+            cost_model::{CostModel, ExBudget},
+        },
+        parser,
+    };
+    use pallas_primitives::conway::Language;
+
+    fn program(src: &str) -> Program<NamedDeBruijn> {
+        let program: Program<Name> = parser::program(src).unwrap();
+        program.try_into().unwrap()
+    }
+
+    fn cover(program: Program<NamedDeBruijn>) -> Vec<TermPath> {
+        let branches = branches(&program.term);
+
+        let machine = Machine::new(
+            Language::PlutusV3,
+            CostModel::default(),
+            ExBudget::default(),
+            200,
+        );
+
+        let (result, coverage) = machine.cover(program.term, &branches);
+
+        assert!(result.is_ok(), "{result:?}");
+
+        coverage.taken.into_iter().collect()
+    }
+
+    #[test]
+    fn if_then_else_branches() {
+        let program = program(
+            r#"
+            (program 1.0.0
+              (force [
+                [ [ (force (builtin ifThenElse)) (con bool False) ] (delay (con integer 1)) ]
+                (delay (con integer 2))
+              ])
+            )
+            "#,
+        );
+
+        let all = branches(&program.term);
+        assert_eq!(all.len(), 2);
+
+        let taken = cover(program);
+        assert_eq!(taken, vec![TermPath(vec![0, 1, 0])]);
+        assert!(all.contains(&taken[0]));
+    }
+
+    #[test]
+    fn case_branches() {
+        let program = program(
+            r#"
+            (program 1.1.0
+              (case (constr 1 (con integer 14))
+                (lam x (con integer 0))
+                (lam x x)
+              )
+            )
+            "#,
+        );
+
+        assert_eq!(branches(&program.term).len(), 2);
+
+        assert_eq!(cover(program), vec![TermPath(vec![2])]);
+    }
+}
@@ -0,0 +1,705 @@
+use super::{
+    Context, Error, Machine, MachineState, Trace,
+    cost_model::{ExBudget, StepKind},
+    discharge,
+    runtime::BuiltinRuntime,
+    value::Value,
+};
+use crate::{
+    ast::{NamedDeBruijn, Term, TermPath},
+    builtins::DefaultFunction,
+    source_map::{SourceLocation, SourceMap},
+};
+use std::{collections::HashMap, fmt::Display, rc::Rc, str::FromStr};
+
+/// A condition under which a `Debugger` pauses when resumed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Breakpoint {
+    /// Pause right before the given builtin is called, that is, when it
+    /// receives its last argument (or force).
+    Builtin(DefaultFunction),
+    /// Pause when the machine starts computing the term at the given path.
+    Path(TermPath),
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::Builtin(fun) => write!(f, "builtin {fun}"),
+            Breakpoint::Path(path) => write!(f, "path {path}"),
+        }
+    }
+}
+
+impl FromStr for Breakpoint {
+    type Err = String;
+
+    /// Parse a breakpoint either as a builtin name (e.g. `addInteger`) or as a
+    /// dotted term path (e.g. `0.1.1` or `root`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if let Ok(fun) = DefaultFunction::from_str(s) {
+            return Ok(Breakpoint::Builtin(fun));
+        }
+
+        s.parse::<TermPath>()
+            .map(Breakpoint::Path)
+            .map_err(|_| format!("'{s}' is neither a builtin name nor a term path"))
+    }
+}
+
+/// What happened after stepping the machine.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Stepped,
+    Breakpoint(Breakpoint),
+    Finished,
+}
+
+/// What the machine is currently looking at.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Focus {
+    /// A term about to be computed, in the current environment.
+    Compute(Term<NamedDeBruijn>),
+    /// A value being returned to the innermost frame of the continuation.
+    Return(Term<NamedDeBruijn>),
+}
+
+/// A frame of the continuation stack.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    /// A function value waiting for its argument to be computed.
+    AwaitArg(Term<NamedDeBruijn>),
+    /// An argument left to compute once the function has been.
+    AwaitFunTerm(Term<NamedDeBruijn>),
+    /// An argument value waiting for the function to be computed.
+    AwaitFunValue(Term<NamedDeBruijn>),
+    Force,
+    Constr {
+        tag: usize,
+        resolved: usize,
+        remaining: usize,
+    },
+    Cases {
+        branches: usize,
+    },
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Frame::AwaitArg(fun) => write!(f, "[{} _]", fun.to_pretty()),
+            Frame::AwaitFunTerm(arg) => write!(f, "[_ {}]", arg.to_pretty()),
+            Frame::AwaitFunValue(arg) => write!(f, "[_ {}]", arg.to_pretty()),
+            Frame::Force => write!(f, "(force _)"),
+            Frame::Constr {
+                tag,
+                resolved,
+                remaining,
+            } => write!(
+                f,
+                "(constr {tag} ..{resolved} fields.. _ ..{remaining} fields..)"
+            ),
+            Frame::Cases { branches } => write!(f, "(case _ ..{branches} branches..)"),
+        }
+    }
+}
+
+/// A CEK machine evaluating a term one step at a time.
+///
+/// Every state transition of the machine counts as one step. Between steps,
+/// the current term (or returned value), environment, continuation and the
+/// budget spent so far can be inspected.
+pub struct Debugger {
+    machine: Machine,
+    initial_budget: ExBudget,
+    state: Option<MachineState>,
+    result: Option<Result<Term<NamedDeBruijn>, Error>>,
+    breakpoints: Vec<Breakpoint>,
+    steps: usize,
+    path: Option<TermPath>,
+    computed: Option<TermPath>,
+    paths: HashMap<*const Term<NamedDeBruijn>, TermPath>,
+    // Kept around so that the addresses indexed in 'paths' remain valid.
+    _program: Term<NamedDeBruijn>,
+}
+
+impl Machine {
+    /// Start evaluating a term step by step instead of running it to
+    /// completion.
+    pub fn debugger(mut self, term: Term<NamedDeBruijn>) -> Debugger {
+        let initial_budget = self.ex_budget;
+
+        let startup_budget = self.costs.machine_costs.get(StepKind::StartUp);
+
+        let mut paths = HashMap::new();
+        index_paths(&term, &TermPath::root(), &mut paths);
+
+        let (state, result) = match self.spend_budget(startup_budget) {
+            Ok(()) => (
+                Some(MachineState::Compute(
+                    Context::NoFrame,
+                    Rc::new(vec![]),
+                    term.clone(),
+                )),
+                None,
+            ),
+            Err(e) => (None, Some(Err(e))),
+        };
+
+        Debugger {
+            machine: self,
+            initial_budget,
+            state,
+            result,
+            breakpoints: vec![],
+            steps: 0,
+            path: Some(TermPath::root()),
+            computed: Some(TermPath::root()),
+            paths,
+            _program: term,
+        }
+    }
+
+    /// Run a term to completion and, should it fail, find where in the source code it did: that
+    /// is, the location of the last term computed, according to the given source map.
+    pub fn locate_failure(
+        self,
+        term: Term<NamedDeBruijn>,
+        source_map: &SourceMap,
+    ) -> Option<SourceLocation> {
+        let mut debugger = self.debugger(term);
+
+        while debugger.step() != Event::Finished {}
+
+        match debugger.result()? {
+            Ok(_) => None,
+            Err(_) => {
+                let (_, location) = source_map.lookup(debugger.failed_at()?)?;
+                Some(location.clone())
+            }
+        }
+    }
+}
+
+impl Debugger {
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+        let before = self.breakpoints.len();
+        self.breakpoints.retain(|b| b != breakpoint);
+        before != self.breakpoints.len()
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Perform a single transition of the machine.
+    pub fn step(&mut self) -> Event {
+        let Some(state) = self.state.take() else {
+            return Event::Finished;
+        };
+
+        let next_path = self.next_path(&state);
+
+        self.steps += 1;
+
+        match self.machine.step(state) {
+            Ok(MachineState::Done(term)) => {
+                self.path = None;
+                self.result = Some(Ok(term));
+                Event::Finished
+            }
+            Ok(state) => {
+                self.path = match &state {
+                    MachineState::Compute(_, _, term) => next_path.or_else(|| self.resolve(term)),
+                    _ => None,
+                };
+
+                if self.path.is_some() {
+                    self.computed.clone_from(&self.path);
+                }
+
+                let event = self
+                    .breakpoints
+                    .iter()
+                    .find(|breakpoint| self.hits(breakpoint, &state))
+                    .cloned()
+                    .map(Event::Breakpoint)
+                    .unwrap_or(Event::Stepped);
+
+                self.state = Some(state);
+
+                event
+            }
+            Err(e) => {
+                self.path = None;
+                self.result = Some(Err(e));
+                Event::Finished
+            }
+        }
+    }
+
+    /// Step until a breakpoint is hit or the evaluation finishes.
+    pub fn resume(&mut self) -> Event {
+        loop {
+            match self.step() {
+                Event::Stepped => continue,
+                event => return event,
+            }
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// The outcome of the evaluation, once finished.
+    pub fn result(&self) -> Option<&Result<Term<NamedDeBruijn>, Error>> {
+        self.result.as_ref()
+    }
+
+    /// Number of machine transitions performed so far.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn focus(&self) -> Option<Focus> {
+        match self.state.as_ref()? {
+            MachineState::Compute(_, _, term) => Some(Focus::Compute(term.clone())),
+            MachineState::Return(_, value) => {
+                Some(Focus::Return(discharge::value_as_term(value.clone())))
+            }
+            MachineState::Done(term) => Some(Focus::Return(term.clone())),
+        }
+    }
+
+    /// The path, from the root of the evaluated term, of the term currently
+    /// being computed.
+    ///
+    /// Paths are tracked through pointer identity of sub-terms, so they may
+    /// be unknown for leaves (variables, constants, builtins) that are
+    /// fields of a constr or branches of a case.
+    pub fn path(&self) -> Option<&TermPath> {
+        self.path.as_ref()
+    }
+
+    /// The path of the last term computed, once the evaluation failed; which is the one failing,
+    /// or an argument of the builtin failing.
+    pub fn failed_at(&self) -> Option<&TermPath> {
+        match self.result.as_ref()? {
+            Ok(_) => None,
+            Err(_) => self.computed.as_ref(),
+        }
+    }
+
+    /// Whether the machine is about to compute a term, as opposed to returning
+    /// a value to the continuation.
+    pub fn is_computing(&self) -> bool {
+        matches!(self.state, Some(MachineState::Compute(..)))
+    }
+
+    /// Number of frames in the continuation. Unlike [`Self::stack`], this
+    /// doesn't discharge any value and is cheap enough to call on every step.
+    pub fn depth(&self) -> usize {
+        let mut context = match self.state.as_ref() {
+            Some(MachineState::Compute(context, _, _)) | Some(MachineState::Return(context, _)) => {
+                context
+            }
+            _ => return 0,
+        };
+
+        let mut depth = 0;
+
+        loop {
+            context = match context {
+                Context::NoFrame => return depth,
+                Context::FrameAwaitArg(_, ctx)
+                | Context::FrameAwaitFunTerm(_, _, ctx)
+                | Context::FrameAwaitFunValue(_, ctx)
+                | Context::FrameForce(ctx)
+                | Context::FrameConstr(_, _, _, _, ctx)
+                | Context::FrameCases(_, _, ctx) => ctx,
+            };
+            depth += 1;
+        }
+    }
+
+    /// The values bound in the current environment, starting with the most
+    /// recently bound (i.e. de Bruijn index 1).
+    pub fn env(&self) -> Vec<(String, Term<NamedDeBruijn>)> {
+        let env = match self.state.as_ref() {
+            Some(MachineState::Compute(_, env, _)) => env.clone(),
+            _ => return vec![],
+        };
+
+        env.iter()
+            .rev()
+            .enumerate()
+            .map(|(ix, value)| {
+                (
+                    format!("i{}", ix + 1),
+                    discharge::value_as_term(value.clone()),
+                )
+            })
+            .collect()
+    }
+
+    /// The continuation stack, innermost frame first.
+    pub fn stack(&self) -> Vec<Frame> {
+        let mut frames = vec![];
+
+        let mut context = match self.state.as_ref() {
+            Some(MachineState::Compute(context, _, _)) | Some(MachineState::Return(context, _)) => {
+                context
+            }
+            _ => return frames,
+        };
+
+        loop {
+            context = match context {
+                Context::NoFrame => return frames,
+                Context::FrameAwaitArg(fun, ctx) => {
+                    frames.push(Frame::AwaitArg(discharge::value_as_term(fun.clone())));
+                    ctx
+                }
+                Context::FrameAwaitFunTerm(_, arg, ctx) => {
+                    frames.push(Frame::AwaitFunTerm(arg.as_ref().clone()));
+                    ctx
+                }
+                Context::FrameAwaitFunValue(arg, ctx) => {
+                    frames.push(Frame::AwaitFunValue(discharge::value_as_term(arg.clone())));
+                    ctx
+                }
+                Context::FrameForce(ctx) => {
+                    frames.push(Frame::Force);
+                    ctx
+                }
+                Context::FrameConstr(_, tag, fields, resolved, ctx) => {
+                    frames.push(Frame::Constr {
+                        tag: *tag,
+                        resolved: resolved.len(),
+                        remaining: fields.len(),
+                    });
+                    ctx
+                }
+                Context::FrameCases(_, branches, ctx) => {
+                    frames.push(Frame::Cases {
+                        branches: branches.len(),
+                    });
+                    ctx
+                }
+            }
+        }
+    }
+
+    /// Budget consumed since the start of the evaluation, including steps
+    /// not yet charged because of the machine's slippage.
+    pub fn budget_spent(&self) -> ExBudget {
+        let pending = self.machine.unbudgeted_steps[..9].iter().enumerate().fold(
+            ExBudget { mem: 0, cpu: 0 },
+            |total, (i, n)| {
+                let mut cost = StepKind::try_from(i as u8)
+                    .map(|step| self.machine.costs.machine_costs.get(step))
+                    .unwrap_or(ExBudget { mem: 0, cpu: 0 });
+                cost.occurrences(*n as i64);
+                total + cost
+            },
+        );
+
+        self.initial_budget - self.machine.ex_budget + pending
+    }
+
+    pub fn traces(&self) -> &[Trace] {
+        &self.machine.traces
+    }
+
+    fn hits(&self, breakpoint: &Breakpoint, state: &MachineState) -> bool {
+        match breakpoint {
+            Breakpoint::Path(path) => {
+                matches!(state, MachineState::Compute(..)) && self.path.as_ref() == Some(path)
+            }
+            Breakpoint::Builtin(fun) => match state {
+                MachineState::Return(Context::FrameAwaitArg(function, _), _)
+                | MachineState::Return(Context::FrameAwaitFunValue(_, _), function) => {
+                    matches!(
+                        function,
+                        Value::Builtin { fun: f, runtime }
+                            if f == fun && saturated_by_argument(runtime)
+                    )
+                }
+                MachineState::Return(Context::FrameForce(_), value) => matches!(
+                    value,
+                    Value::Builtin { fun: f, runtime }
+                        if f == fun && saturated_by_force(runtime)
+                ),
+                _ => false,
+            },
+        }
+    }
+
+    /// Predict the path of the next computed term from the current state,
+    /// when it can be known from the shape of the transition.
+    fn next_path(&self, state: &MachineState) -> Option<TermPath> {
+        match state {
+            MachineState::Compute(_, _, term) => match term {
+                Term::Apply { .. } | Term::Force(_) | Term::Case { .. } => {
+                    self.path.as_ref().map(|path| path.child(0))
+                }
+                Term::Constr { fields, .. } if !fields.is_empty() => {
+                    self.path.as_ref().map(|path| path.child(0))
+                }
+                _ => None,
+            },
+            MachineState::Return(Context::FrameAwaitFunTerm(_, arg, _), _) => self.lookup(arg),
+            MachineState::Return(Context::FrameAwaitArg(Value::Lambda { body, .. }, _), _)
+            | MachineState::Return(Context::FrameAwaitFunValue(..), Value::Lambda { body, .. })
+            | MachineState::Return(Context::FrameForce(_), Value::Delay(body, _)) => {
+                self.lookup(body)
+            }
+            _ => None,
+        }
+    }
+
+    /// Recover the path of a term from the paths of its children.
+    fn resolve(&self, term: &Term<NamedDeBruijn>) -> Option<TermPath> {
+        match term {
+            Term::Delay(body) | Term::Force(body) | Term::Lambda { body, .. } => {
+                self.lookup(body)?.parent()
+            }
+            Term::Apply { function, .. } => self.lookup(function)?.parent(),
+            Term::Case { constr, .. } => self.lookup(constr)?.parent(),
+            Term::Constr { fields, .. } => fields
+                .iter()
+                .find_map(|field| self.resolve(field))
+                .and_then(|path| path.parent()),
+            Term::Var(_) | Term::Constant(_) | Term::Error | Term::Builtin(_) => None,
+        }
+    }
+
+    fn lookup(&self, term: &Rc<Term<NamedDeBruijn>>) -> Option<TermPath> {
+        self.paths.get(&Rc::as_ptr(term)).cloned()
+    }
+}
+
+fn saturated_by_argument(runtime: &BuiltinRuntime) -> bool {
+    !runtime.needs_force() && runtime.args.len() + 1 == runtime.fun.arity()
+}
+
+fn saturated_by_force(runtime: &BuiltinRuntime) -> bool {
+    runtime.forces + 1 == runtime.fun.force_count() && runtime.is_ready()
+}
+
+fn index_paths(
+    term: &Term<NamedDeBruijn>,
+    path: &TermPath,
+    paths: &mut HashMap<*const Term<NamedDeBruijn>, TermPath>,
+) {
+    let mut index_rc = |child: &Rc<Term<NamedDeBruijn>>, ix: usize| {
+        let child_path = path.child(ix);
+        paths
+            .entry(Rc::as_ptr(child))
+            .or_insert_with(|| child_path.clone());
+        index_paths(child, &child_path, paths);
+    };
+
+    match term {
+        Term::Delay(body) | Term::Force(body) | Term::Lambda { body, .. } => index_rc(body, 0),
+        Term::Apply { function, argument } => {
+            index_rc(function, 0);
+            index_rc(argument, 1);
+        }
+        Term::Case { constr, branches } => {
+            index_rc(constr, 0);
+            for (ix, branch) in branches.iter().enumerate() {
+                index_paths(branch, &path.child(ix + 1), paths);
+            }
+        }
+        Term::Constr { fields, .. } => {
+            for (ix, field) in fields.iter().enumerate() {
+                index_paths(field, &path.child(ix), paths);
+            }
+        }
+        Term::Var(_) | Term::Constant(_) | Term::Error | Term::Builtin(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Breakpoint, Event, Focus, Frame};
+    use crate::{
+        ast::{Name, NamedDeBruijn, Program, Term, TermPath},
+        builtins::DefaultFunction,
+        machine::{
+            Machine,
+            cost_model::{CostModel, ExBudget},
+        },
+        parser,
+        source_map::{SourceLocation, SourceMap},
+    };
+    use pallas_primitives::conway::Language;
+
+    fn debugger(src: &str) -> (super::Debugger, Term<NamedDeBruijn>) {
+        let program: Program<Name> = parser::program(src).unwrap();
+        let program: Program<NamedDeBruijn> = program.try_into().unwrap();
+
+        let machine = Machine::new(
+            Language::PlutusV3,
+            CostModel::default(),
+            ExBudget::default(),
+            200,
+        );
+
+        (machine.debugger(program.term.clone()), program.term)
+    }
+
+    #[test]
+    fn stepping_agrees_with_run() {
+        let src =
+            "(program 1.0.0 [(lam x [(builtin addInteger) x (con integer 2)]) (con integer 40)])";
+
+        let (mut dbg, term) = debugger(src);
+
+        while dbg.step() != Event::Finished {}
+
+        let eval_result = Program {
+            version: (1, 0, 0),
+            term,
+        }
+        .eval(ExBudget::default());
+
+        assert_eq!(dbg.result().cloned(), Some(eval_result.result()));
+        assert_eq!(dbg.budget_spent(), eval_result.cost());
+    }
+
+    #[test]
+    fn break_on_builtin() {
+        let src =
+            "(program 1.0.0 [(lam x [(builtin addInteger) x (con integer 2)]) (con integer 40)])";
+
+        let (mut dbg, _) = debugger(src);
+
+        dbg.add_breakpoint(Breakpoint::Builtin(DefaultFunction::AddInteger));
+
+        assert_eq!(
+            dbg.resume(),
+            Event::Breakpoint(Breakpoint::Builtin(DefaultFunction::AddInteger))
+        );
+
+        assert_eq!(dbg.focus(), Some(Focus::Return(Term::integer(2.into()))));
+
+        assert_eq!(
+            dbg.stack().first(),
+            Some(&Frame::AwaitArg(
+                Term::add_integer().apply(Term::integer(40.into()))
+            ))
+        );
+
+        assert_eq!(dbg.resume(), Event::Finished);
+
+        assert_eq!(dbg.result().cloned(), Some(Ok(Term::integer(42.into()))));
+    }
+
+    #[test]
+    fn break_on_path() {
+        let src =
+            "(program 1.0.0 [(lam x [(builtin addInteger) x (con integer 2)]) (con integer 40)])";
+
+        let (mut dbg, term) = debugger(src);
+
+        let path: TermPath = "0.0".parse().unwrap();
+
+        dbg.add_breakpoint(Breakpoint::Path(path.clone()));
+
+        assert_eq!(
+            dbg.resume(),
+            Event::Breakpoint(Breakpoint::Path(path.clone()))
+        );
+
+        assert_eq!(dbg.path(), Some(&path));
+
+        assert_eq!(
+            dbg.focus(),
+            term.at_path(&path).cloned().map(Focus::Compute)
+        );
+    }
+
+    #[test]
+    fn failed_at_last_computed_term() {
+        let src = "(program 1.0.0 [(lam x [(builtin divideInteger) x (con integer 0)]) (con integer 40)])";
+
+        let (mut dbg, _) = debugger(src);
+
+        while dbg.step() != Event::Finished {}
+
+        assert!(matches!(dbg.result(), Some(Err(_))));
+
+        assert_eq!(dbg.failed_at(), Some(&"0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn locate_failure() {
+        let src = "(program 1.0.0 [(lam x [(lam y (error)) x]) (con integer 40)])";
+
+        let program: Program<Name> = parser::program(src).unwrap();
+        let program: Program<NamedDeBruijn> = program.try_into().unwrap();
+
+        let location = SourceLocation {
+            module: "foo".to_string(),
+            start: 14,
+            end: 42,
+            line: 2,
+            name: None,
+        };
+
+        let mut source_map = SourceMap::new();
+        source_map.insert(
+            TermPath::root(),
+            SourceLocation {
+                line: 1,
+                ..location.clone()
+            },
+        );
+        source_map.insert("0.0.0".parse().unwrap(), location.clone());
+
+        let machine = || {
+            Machine::new(
+                Language::PlutusV3,
+                CostModel::default(),
+                ExBudget::default(),
+                200,
+            )
+        };
+
+        assert_eq!(
+            machine().locate_failure(program.term.clone(), &source_map),
+            Some(location)
+        );
+
+        assert_eq!(
+            machine().locate_failure(Term::integer(1.into()), &source_map),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_breakpoints() {
+        assert_eq!(
+            "equalsInteger".parse::<Breakpoint>(),
+            Ok(Breakpoint::Builtin(DefaultFunction::EqualsInteger))
+        );
+
+        assert_eq!(
+            "1.0.2".parse::<Breakpoint>(),
+            Ok(Breakpoint::Path(TermPath(vec![1, 0, 2])))
+        );
+
+        assert!("foo".parse::<Breakpoint>().is_err());
+    }
+}
@@ -0,0 +1,428 @@
+use super::{Error, Machine, cost_model::ExBudget, debugger::Event};
+use crate::{
+    ast::{NamedDeBruijn, Term, TermPath},
+    source_map::{SourceLocation, SourceMap},
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Costs attributed to a single function across a profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionProfile {
+    pub location: SourceLocation,
+    /// Number of times the function's body was entered.
+    pub calls: usize,
+    /// Budget spent in the function itself, excluding its callees.
+    pub self_budget: ExBudget,
+    /// Budget spent in the function and its callees. Recursive calls are
+    /// only counted once.
+    pub total_budget: ExBudget,
+}
+
+/// Budget spent by an evaluation, broken down by call stacks of the functions
+/// located in a source map, and by lines of source code.
+///
+/// Functions are entered when the machine starts computing their body, and
+/// left when that body returns to the frame that was on top of the
+/// continuation at that time; so tail calls replace their caller on the
+/// stack. Functions that aren't in the source map (e.g. because they've been
+/// inlined) are attributed to their caller.
+///
+/// Each step is attributed to the line of the innermost located term enclosing
+/// the term computed last, so values returned (e.g. to builtins) count towards
+/// the expression that computed them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    stacks: BTreeMap<Vec<String>, ExBudget>,
+    functions: BTreeMap<String, FunctionProfile>,
+    lines: BTreeMap<(String, usize), ExBudget>,
+}
+
+impl Machine {
+    /// Run a term to completion, keeping track of the budget spent in each
+    /// of the functions located by the given source map.
+    pub fn profile(
+        self,
+        term: Term<NamedDeBruijn>,
+        source_map: &SourceMap,
+    ) -> (Result<Term<NamedDeBruijn>, Error>, Profile) {
+        let entry_points = entry_points(&term, source_map);
+
+        let mut debugger = self.debugger(term);
+
+        let mut profile = Profile::default();
+
+        let mut stack: Vec<(String, usize)> = vec![];
+
+        let mut lines: HashMap<TermPath, Option<(String, usize)>> = HashMap::new();
+
+        let mut line = source_map
+            .lookup(&TermPath::root())
+            .map(|(_, location)| (location.module.clone(), location.line));
+
+        let mut spent = debugger.budget_spent();
+
+        profile.charge(&stack, line.as_ref(), spent);
+
+        loop {
+            let event = debugger.step();
+
+            let now = debugger.budget_spent();
+            profile.charge(&stack, line.as_ref(), now - spent);
+            spent = now;
+
+            if event == Event::Finished {
+                break;
+            }
+
+            if let Some(path) = debugger.path() {
+                line = lines
+                    .entry(path.clone())
+                    .or_insert_with(|| {
+                        source_map
+                            .lookup(path)
+                            .map(|(_, location)| (location.module.clone(), location.line))
+                    })
+                    .clone();
+            }
+
+            let depth = debugger.depth();
+
+            let entered = if debugger.is_computing() {
+                debugger.path().and_then(|path| entry_points.get(path))
+            } else {
+                None
+            };
+
+            // Function bodies, and the terms in tail position within them, are
+            // computed with the continuation of their caller. So functions
+            // entered at the current depth are left when returning a value or
+            // when tail-calling another function.
+            if entered.is_some() || !debugger.is_computing() {
+                stack.retain(|(_, entered_at)| *entered_at < depth);
+            } else {
+                stack.retain(|(_, entered_at)| *entered_at <= depth);
+            }
+
+            if let Some(location) = entered {
+                let label = label(location);
+                profile.enter(&label, location);
+                stack.push((label, depth));
+            }
+        }
+
+        profile.compute_totals();
+
+        let result = debugger
+            .result()
+            .cloned()
+            .expect("profiled evaluation finished without result");
+
+        (result, profile)
+    }
+}
+
+impl Profile {
+    /// Budget spent in each distinct call stack, outermost function first.
+    pub fn stacks(&self) -> impl Iterator<Item = (&[String], &ExBudget)> {
+        self.stacks
+            .iter()
+            .map(|(stack, budget)| (stack.as_slice(), budget))
+    }
+
+    /// Costs aggregated per function, indexed by their fully qualified names.
+    pub fn functions(&self) -> impl Iterator<Item = (&String, &FunctionProfile)> {
+        self.functions.iter()
+    }
+
+    pub fn function(&self, name: &str) -> Option<&FunctionProfile> {
+        self.functions.get(name)
+    }
+
+    /// Budget spent on each line of source code, by module and line number.
+    pub fn lines(&self) -> impl Iterator<Item = ((&str, usize), &ExBudget)> {
+        self.lines
+            .iter()
+            .map(|((module, line), budget)| ((module.as_str(), *line), budget))
+    }
+
+    /// The total budget spent.
+    pub fn total(&self) -> ExBudget {
+        self.stacks
+            .values()
+            .fold(ExBudget { mem: 0, cpu: 0 }, |total, budget| total + *budget)
+    }
+
+    /// Combine two profiles, e.g. from several evaluations of the same program.
+    pub fn merge(&mut self, other: Profile) {
+        for (stack, budget) in other.stacks {
+            self.stacks
+                .entry(stack)
+                .and_modify(|total| *total = *total + budget)
+                .or_insert(budget);
+        }
+
+        for (name, function) in other.functions {
+            self.functions
+                .entry(name)
+                .and_modify(|total| {
+                    total.calls += function.calls;
+                    total.self_budget = total.self_budget + function.self_budget;
+                    total.total_budget = total.total_budget + function.total_budget;
+                })
+                .or_insert(function);
+        }
+
+        for (line, budget) in other.lines {
+            self.lines
+                .entry(line)
+                .and_modify(|total| *total = *total + budget)
+                .or_insert(budget);
+        }
+    }
+
+    /// Render the profile as folded stacks, one per line, as expected by
+    /// flamegraph tools. Stacks are prefixed with the given root frame, and
+    /// weighted by the given measure of their budget.
+    pub fn folded(&self, root: &str, measure: impl Fn(&ExBudget) -> i64) -> String {
+        self.stacks
+            .iter()
+            .map(|(stack, budget)| {
+                let frames = std::iter::once(root)
+                    .chain(stack.iter().map(String::as_str))
+                    .collect::<Vec<_>>()
+                    .join(";");
+                format!("{frames} {}\n", measure(budget))
+            })
+            .collect()
+    }
+
+    fn charge(
+        &mut self,
+        stack: &[(String, usize)],
+        line: Option<&(String, usize)>,
+        budget: ExBudget,
+    ) {
+        if budget.mem == 0 && budget.cpu == 0 {
+            return;
+        }
+
+        if let Some(line) = line {
+            self.lines
+                .entry(line.clone())
+                .and_modify(|total| *total = *total + budget)
+                .or_insert(budget);
+        }
+
+        if let Some(function) = stack
+            .last()
+            .and_then(|(label, _)| self.functions.get_mut(label))
+        {
+            function.self_budget = function.self_budget + budget;
+        }
+
+        let key = stack.iter().map(|(label, _)| label.clone()).collect();
+
+        self.stacks
+            .entry(key)
+            .and_modify(|total| *total = *total + budget)
+            .or_insert(budget);
+    }
+
+    fn enter(&mut self, label: &str, location: &SourceLocation) {
+        self.functions
+            .entry(label.to_string())
+            .or_insert_with(|| FunctionProfile {
+                location: location.clone(),
+                calls: 0,
+                self_budget: ExBudget { mem: 0, cpu: 0 },
+                total_budget: ExBudget { mem: 0, cpu: 0 },
+            })
+            .calls += 1;
+    }
+
+    fn compute_totals(&mut self) {
+        for (stack, budget) in self.stacks.iter() {
+            for label in stack.iter().collect::<BTreeSet<_>>() {
+                if let Some(function) = self.functions.get_mut(label) {
+                    function.total_budget = function.total_budget + *budget;
+                }
+            }
+        }
+    }
+}
+
+fn label(location: &SourceLocation) -> String {
+    let name = location.name.as_deref().unwrap_or_default();
+
+    if location.module.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{name}", location.module)
+    }
+}
+
+/// Paths of the terms computed when entering named definitions of a source
+/// map; that is, their body once stripped from parameters and delays.
+fn entry_points<'a>(
+    term: &Term<NamedDeBruijn>,
+    source_map: &'a SourceMap,
+) -> HashMap<TermPath, &'a SourceLocation> {
+    source_map
+        .iter()
+        .filter(|(_, location)| location.name.is_some())
+        .filter_map(|(path, location)| {
+            let mut path = path.clone();
+            let mut body = term.at_path(&path)?;
+
+            while let Term::Lambda { body: next, .. } | Term::Delay(next) = body {
+                path = path.child(0);
+                body = next.as_ref();
+            }
+
+            Some((path, location))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{Name, NamedDeBruijn, Program},
+        machine::{
+            Machine,
+            cost_model::{CostModel, ExBudget},
+        },
+        parser,
+        source_map::{SourceLocation, SourceMap},
+    };
+    use pallas_primitives::conway::Language;
+    use std::collections::{BTreeSet, HashMap};
+
+    fn location(name: &str, line: usize) -> SourceLocation {
+        SourceLocation {
+            module: "foo".to_string(),
+            start: 0,
+            end: 0,
+            line,
+            name: Some(name.to_string()),
+        }
+    }
+
+    const SUM_OF_SQUARES: &str = r#"
+        (program 1.0.0
+          [ (lam square
+              [ (lam sum_of_squares
+                  [ sum_of_squares (con integer 3) (con integer 4) ]
+                )
+                (lam x (lam y [ (builtin addInteger) [square x] [square y] ]))
+              ]
+            )
+            (lam n [ (builtin multiplyInteger) n n ])
+          ]
+        )
+    "#;
+
+    #[test]
+    fn profile_attributes_costs_to_functions() {
+        let src = SUM_OF_SQUARES;
+
+        let program: Program<Name> = parser::program(src).unwrap();
+
+        let bindings = HashMap::from([
+            ("square".to_string(), location("square", 1)),
+            ("sum_of_squares".to_string(), location("sum_of_squares", 5)),
+        ]);
+
+        let source_map = SourceMap::from_bindings(&program.term, None, &bindings);
+
+        let program: Program<NamedDeBruijn> = program.try_into().unwrap();
+
+        let machine = Machine::new(
+            Language::PlutusV3,
+            CostModel::default(),
+            ExBudget::default(),
+            200,
+        );
+
+        let (result, profile) = machine.profile(program.term.clone(), &source_map);
+
+        let eval_result = program.eval(ExBudget::default());
+
+        assert_eq!(result, eval_result.result());
+        assert_eq!(profile.total(), eval_result.cost());
+
+        let square = profile.function("foo.square").unwrap();
+        let sum_of_squares = profile.function("foo.sum_of_squares").unwrap();
+
+        assert_eq!(square.calls, 2);
+        assert_eq!(sum_of_squares.calls, 1);
+        assert_eq!(
+            sum_of_squares.total_budget,
+            sum_of_squares.self_budget + square.total_budget
+        );
+
+        assert!(
+            profile
+                .stacks()
+                .any(|(stack, _)| stack == ["foo.sum_of_squares", "foo.square"])
+        );
+
+        let folded = profile.folded("test", |budget| budget.cpu);
+
+        assert!(
+            folded
+                .lines()
+                .any(|line| line.starts_with("test;foo.sum_of_squares;foo.square "))
+        );
+    }
+
+    #[test]
+    fn profile_attributes_costs_to_lines() {
+        let program: Program<Name> = parser::program(SUM_OF_SQUARES).unwrap();
+
+        let bindings = HashMap::from([
+            ("square".to_string(), location("square", 1)),
+            ("sum_of_squares".to_string(), location("sum_of_squares", 5)),
+        ]);
+
+        let mut source_map =
+            SourceMap::from_bindings(&program.term, Some(location("test", 10)), &bindings);
+
+        // [square y], within sum_of_squares
+        source_map.insert(
+            "0.0.1.0.0.1".parse().unwrap(),
+            SourceLocation {
+                name: None,
+                ..location("sum_of_squares", 6)
+            },
+        );
+
+        let program: Program<NamedDeBruijn> = program.try_into().unwrap();
+
+        let machine = Machine::new(
+            Language::PlutusV3,
+            CostModel::default(),
+            ExBudget::default(),
+            200,
+        );
+
+        let (_, profile) = machine.profile(program.term, &source_map);
+
+        let lines = profile
+            .lines()
+            .map(|((module, line), budget)| ((module.to_string(), line), *budget))
+            .collect::<HashMap<_, _>>();
+
+        assert_eq!(
+            lines.keys().map(|(_, line)| *line).collect::<BTreeSet<_>>(),
+            [1, 5, 6, 10].into()
+        );
+
+        assert_eq!(
+            lines
+                .values()
+                .fold(ExBudget { mem: 0, cpu: 0 }, |total, budget| total + *budget),
+            profile.total()
+        );
+    }
+}
@@ -0,0 +1,747 @@
+//! Symbolic evaluation of functions of a single integer or bytestring, passed as data.
+//!
+//! The function is evaluated once per path, with its argument left symbolic. Arithmetic and
+//! comparisons on the argument are tracked as linear expressions and constraints, and branching
+//! on a constraint follows whichever outcomes are satisfiable along with the constraints of the
+//! path so far. Constraints being over a single variable, they're decided by narrowing down
+//! intervals of it rather than by a general-purpose solver. Paths ending in a failure yield
+//! concrete counterexamples, while paths going through operations that can't be reasoned about
+//! symbolically (e.g. hashing, division or serialisation of the argument) leave the outcome
+//! unknown.
+
+use super::{Trace, Value as MachineValue, runtime::BuiltinSemantics};
+use crate::{
+    ast::{Constant, NamedDeBruijn, Term},
+    builtins::DefaultFunction,
+};
+use num_bigint::BigInt;
+use pallas_primitives::conway::{Language, PlutusData};
+use std::rc::Rc;
+
+mod interval;
+
+pub use interval::{Atom, Linear, MAX_BYTESTRING_LENGTH, Model, solve};
+
+/// The kind of value a function is proven over, and given to it as data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Integer,
+    ByteString,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// No path leads to a failure. The proof is complete unless some paths were cut short for
+    /// going through more branches than the maximum depth, in which case it only covers the inputs
+    /// following the other paths.
+    Proved { paths: usize, complete: bool },
+    /// An input for which the evaluation fails.
+    Counterexample(PlutusData),
+    /// Some path goes through an operation that can't be reasoned about symbolically.
+    Unknown(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Prover {
+    version: Language,
+    depth: usize,
+    paths: usize,
+    steps: usize,
+}
+
+impl Prover {
+    pub const DEFAULT_DEPTH: usize = 16;
+
+    const MAX_PATHS: usize = 1024;
+
+    const MAX_STEPS: usize = 1_000_000;
+
+    pub fn new(version: Language) -> Self {
+        Prover {
+            version,
+            depth: Self::DEFAULT_DEPTH,
+            paths: Self::MAX_PATHS,
+            steps: Self::MAX_STEPS,
+        }
+    }
+
+    /// The maximum number of branches on the argument followed by any single path.
+    pub fn with_depth(self, depth: usize) -> Self {
+        Prover { depth, ..self }
+    }
+
+    /// Explore the paths of a function of the given input, which fails when its evaluation errors
+    /// or, for Plutus V3, returns anything but 'True' or unit.
+    pub fn prove(&self, term: &Term<NamedDeBruijn>, input: Input) -> Outcome {
+        let mut pending = vec![Vec::new()];
+        let mut paths = 0;
+        let mut complete = true;
+        let mut unknown = None;
+
+        while let Some(replay) = pending.pop() {
+            if paths == self.paths {
+                complete = false;
+                break;
+            }
+
+            paths += 1;
+
+            let mut path = Path::new(self, input, &replay);
+
+            let end = match path.run(term) {
+                Ok(value) => path.failed(value),
+                Err(stop) => Err(stop),
+            };
+
+            pending.append(&mut path.forks);
+
+            match end {
+                Ok(false) => {}
+                Ok(true) | Err(Stop::Failed) => {
+                    if let Some(model) = solve(input, &path.constraints) {
+                        return Outcome::Counterexample(model.into());
+                    }
+                }
+                Err(Stop::TooDeep) => complete = false,
+                Err(Stop::Unknown(reason)) => {
+                    unknown.get_or_insert(reason);
+                }
+            }
+        }
+
+        match unknown {
+            Some(reason) => Outcome::Unknown(reason),
+            None => Outcome::Proved { paths, complete },
+        }
+    }
+}
+
+type Env = Rc<Vec<Value>>;
+
+#[derive(Debug, Clone)]
+enum Value {
+    Con(Rc<Constant>),
+    Sym(Sym),
+    Delay(Rc<Term<NamedDeBruijn>>, Env),
+    Lambda(Rc<Term<NamedDeBruijn>>, Env),
+    Builtin {
+        fun: DefaultFunction,
+        forces: u32,
+        args: Vec<Value>,
+    },
+    Constr {
+        tag: usize,
+        fields: Vec<Value>,
+    },
+}
+
+/// Values depending on the argument.
+#[derive(Debug, Clone)]
+enum Sym {
+    Integer(Linear),
+    /// The bytestring argument.
+    ByteString,
+    Bool(Atom),
+    /// The integer argument as data, or an expression of it.
+    IData(Linear),
+    /// The bytestring argument as data.
+    BData,
+}
+
+enum Frame {
+    AwaitArg(Value),
+    AwaitFunTerm(Env, Rc<Term<NamedDeBruijn>>),
+    AwaitFunValue(Value),
+    Force,
+    Constr(Env, usize, Vec<Term<NamedDeBruijn>>, Vec<Value>),
+    Cases(Env, Vec<Term<NamedDeBruijn>>),
+}
+
+enum State {
+    Compute(Env, Term<NamedDeBruijn>),
+    Return(Value),
+}
+
+enum Stop {
+    /// The evaluation errored.
+    Failed,
+    /// The path goes through more branches than the maximum depth.
+    TooDeep,
+    Unknown(String),
+}
+
+/// A single evaluation, following the given branches first. Branches beyond those are explored
+/// as they come, the alternatives being recorded as forks to follow in later evaluations.
+struct Path<'a> {
+    prover: &'a Prover,
+    input: Input,
+    replay: &'a [bool],
+    decisions: Vec<bool>,
+    constraints: Vec<Atom>,
+    forks: Vec<Vec<bool>>,
+}
+
+impl<'a> Path<'a> {
+    fn new(prover: &'a Prover, input: Input, replay: &'a [bool]) -> Self {
+        Path {
+            prover,
+            input,
+            replay,
+            decisions: Vec::new(),
+            constraints: Vec::new(),
+            forks: Vec::new(),
+        }
+    }
+
+    fn run(&mut self, term: &Term<NamedDeBruijn>) -> Result<Value, Stop> {
+        let argument = match self.input {
+            Input::Integer => Sym::IData(Linear::var()),
+            Input::ByteString => Sym::BData,
+        };
+
+        let mut stack = vec![Frame::AwaitFunValue(Value::Sym(argument))];
+
+        let mut state = State::Compute(Rc::new(Vec::new()), term.clone());
+
+        for _ in 0..self.prover.steps {
+            state = match state {
+                State::Compute(env, term) => self.compute(&mut stack, env, term)?,
+                State::Return(value) => match stack.pop() {
+                    None => return Ok(value),
+                    Some(frame) => self.return_compute(&mut stack, frame, value)?,
+                },
+            };
+        }
+
+        Err(Stop::Unknown(format!(
+            "evaluation exceeded {} steps",
+            self.prover.steps
+        )))
+    }
+
+    /// Whether the evaluation fails with the given result, branching on it if it's symbolic.
+    fn failed(&mut self, value: Value) -> Result<bool, Stop> {
+        if self.prover.version != Language::PlutusV3 {
+            return Ok(false);
+        }
+
+        match value {
+            Value::Con(constant) => Ok(!matches!(
+                constant.as_ref(),
+                Constant::Unit | Constant::Bool(true)
+            )),
+            Value::Sym(Sym::Bool(atom)) => Ok(!self.decide(atom)?),
+            _ => Ok(true),
+        }
+    }
+
+    fn compute(
+        &mut self,
+        stack: &mut Vec<Frame>,
+        env: Env,
+        term: Term<NamedDeBruijn>,
+    ) -> Result<State, Stop> {
+        Ok(match term {
+            Term::Var(name) => State::Return(
+                env.get(env.len().wrapping_sub(usize::from(name.index)))
+                    .cloned()
+                    .ok_or(Stop::Failed)?,
+            ),
+            Term::Delay(body) => State::Return(Value::Delay(body, env)),
+            Term::Lambda { body, .. } => State::Return(Value::Lambda(body, env)),
+            Term::Apply { function, argument } => {
+                stack.push(Frame::AwaitFunTerm(env.clone(), argument));
+                State::Compute(env, function.as_ref().clone())
+            }
+            Term::Constant(constant) => State::Return(Value::Con(constant)),
+            Term::Force(body) => {
+                stack.push(Frame::Force);
+                State::Compute(env, body.as_ref().clone())
+            }
+            Term::Error => return Err(Stop::Failed),
+            Term::Builtin(fun) => State::Return(Value::Builtin {
+                fun,
+                forces: 0,
+                args: Vec::new(),
+            }),
+            Term::Constr { tag, mut fields } => {
+                fields.reverse();
+                match fields.pop() {
+                    None => State::Return(Value::Constr {
+                        tag,
+                        fields: Vec::new(),
+                    }),
+                    Some(field) => {
+                        stack.push(Frame::Constr(env.clone(), tag, fields, Vec::new()));
+                        State::Compute(env, field)
+                    }
+                }
+            }
+            Term::Case { constr, branches } => {
+                stack.push(Frame::Cases(env.clone(), branches));
+                State::Compute(env, constr.as_ref().clone())
+            }
+        })
+    }
+
+    fn return_compute(
+        &mut self,
+        stack: &mut Vec<Frame>,
+        frame: Frame,
+        value: Value,
+    ) -> Result<State, Stop> {
+        match frame {
+            Frame::Force => self.force(value),
+            Frame::AwaitFunTerm(env, argument) => {
+                stack.push(Frame::AwaitArg(value));
+                Ok(State::Compute(env, argument.as_ref().clone()))
+            }
+            Frame::AwaitArg(function) => self.apply(function, value),
+            Frame::AwaitFunValue(argument) => self.apply(value, argument),
+            Frame::Constr(env, tag, mut fields, mut resolved) => {
+                resolved.push(value);
+                match fields.pop() {
+                    None => Ok(State::Return(Value::Constr {
+                        tag,
+                        fields: resolved,
+                    })),
+                    Some(field) => {
+                        stack.push(Frame::Constr(env.clone(), tag, fields, resolved));
+                        Ok(State::Compute(env, field))
+                    }
+                }
+            }
+            Frame::Cases(env, mut branches) => match value {
+                Value::Constr { tag, fields } if tag < branches.len() => {
+                    stack.extend(fields.into_iter().rev().map(Frame::AwaitFunValue));
+                    Ok(State::Compute(env, branches.swap_remove(tag)))
+                }
+                _ => Err(Stop::Failed),
+            },
+        }
+    }
+
+    fn force(&mut self, value: Value) -> Result<State, Stop> {
+        match value {
+            Value::Delay(body, env) => Ok(State::Compute(env, body.as_ref().clone())),
+            Value::Builtin { fun, forces, args } if forces < fun.force_count() => {
+                self.saturate(fun, forces + 1, args)
+            }
+            _ => Err(Stop::Failed),
+        }
+    }
+
+    fn apply(&mut self, function: Value, argument: Value) -> Result<State, Stop> {
+        match function {
+            Value::Lambda(body, mut env) => {
+                Rc::make_mut(&mut env).push(argument);
+                Ok(State::Compute(env, body.as_ref().clone()))
+            }
+            Value::Builtin {
+                fun,
+                forces,
+                mut args,
+            } if forces == fun.force_count() && args.len() < fun.arity() => {
+                args.push(argument);
+                self.saturate(fun, forces, args)
+            }
+            _ => Err(Stop::Failed),
+        }
+    }
+
+    /// Call a builtin once it has received all its arguments.
+    fn saturate(
+        &mut self,
+        fun: DefaultFunction,
+        forces: u32,
+        args: Vec<Value>,
+    ) -> Result<State, Stop> {
+        if args.len() == fun.arity() && forces == fun.force_count() {
+            self.call(fun, args).map(State::Return)
+        } else {
+            Ok(State::Return(Value::Builtin { fun, forces, args }))
+        }
+    }
+
+    fn call(&mut self, fun: DefaultFunction, mut args: Vec<Value>) -> Result<Value, Stop> {
+        use DefaultFunction::*;
+
+        // Builtins that select one of their arguments don't need them to be constants.
+        let selected = match (fun, args.first()) {
+            (IfThenElse, Some(Value::Sym(Sym::Bool(atom)))) => {
+                let atom = atom.clone();
+                Some(if self.decide(atom)? { 1 } else { 2 })
+            }
+            (IfThenElse, Some(Value::Con(constant))) => match constant.as_ref() {
+                Constant::Bool(true) => Some(1),
+                Constant::Bool(false) => Some(2),
+                _ => return Err(Stop::Failed),
+            },
+            (ChooseUnit, Some(Value::Con(constant))) => match constant.as_ref() {
+                Constant::Unit => Some(1),
+                _ => return Err(Stop::Failed),
+            },
+            (Trace, Some(Value::Con(constant))) => match constant.as_ref() {
+                Constant::String(..) => Some(1),
+                _ => return Err(Stop::Failed),
+            },
+            (ChooseList, Some(Value::Con(constant))) => match constant.as_ref() {
+                Constant::ProtoList(_, xs) if xs.is_empty() => Some(1),
+                Constant::ProtoList(..) => Some(2),
+                _ => return Err(Stop::Failed),
+            },
+            (ChooseData, Some(Value::Con(constant))) => match constant.as_ref() {
+                Constant::Data(PlutusData::Constr(..)) => Some(1),
+                Constant::Data(PlutusData::Map(..)) => Some(2),
+                Constant::Data(PlutusData::Array(..)) => Some(3),
+                Constant::Data(PlutusData::BigInt(..)) => Some(4),
+                Constant::Data(PlutusData::BoundedBytes(..)) => Some(5),
+                _ => return Err(Stop::Failed),
+            },
+            (ChooseData, Some(Value::Sym(Sym::IData(..)))) => Some(4),
+            (ChooseData, Some(Value::Sym(Sym::BData))) => Some(5),
+            (IfThenElse | ChooseUnit | Trace | ChooseList | ChooseData, _) => {
+                return Err(Stop::Failed);
+            }
+            _ => None,
+        };
+
+        if let Some(ix) = selected {
+            return Ok(args.swap_remove(ix));
+        }
+
+        if args.iter().all(|arg| matches!(arg, Value::Con(..))) {
+            return self.call_concrete(fun, args);
+        }
+
+        self.call_symbolic(fun, args)
+    }
+
+    fn call_concrete(&mut self, fun: DefaultFunction, args: Vec<Value>) -> Result<Value, Stop> {
+        let args = args
+            .into_iter()
+            .map(|arg| match arg {
+                Value::Con(constant) => MachineValue::Con(constant),
+                _ => unreachable!("non-constant argument to a concrete builtin call"),
+            })
+            .collect::<Vec<_>>();
+
+        let mut traces: Vec<Trace> = Vec::new();
+
+        match fun.call(
+            BuiltinSemantics::from(&self.prover.version),
+            &args,
+            &mut traces,
+        ) {
+            Ok(MachineValue::Con(constant)) => Ok(Value::Con(constant)),
+            Ok(..) => Err(Stop::Unknown(format!(
+                "unsupported result of builtin '{fun}'"
+            ))),
+            Err(..) => Err(Stop::Failed),
+        }
+    }
+
+    fn call_symbolic(&mut self, fun: DefaultFunction, args: Vec<Value>) -> Result<Value, Stop> {
+        use DefaultFunction::*;
+
+        match (fun, args.as_slice()) {
+            (AddInteger, [x, y]) => Ok(integer(linear(x)?.add(&linear(y)?))),
+            (SubtractInteger, [x, y]) => Ok(integer(linear(x)?.sub(&linear(y)?))),
+            (MultiplyInteger, [x, y]) => {
+                let (x, y) = (linear(x)?, linear(y)?);
+                match (x.as_constant(), y.as_constant()) {
+                    (Some(k), _) => Ok(integer(y.scale(k))),
+                    (_, Some(k)) => Ok(integer(x.scale(k))),
+                    _ => Err(self.unsupported(fun)),
+                }
+            }
+            (EqualsInteger, [x, y]) => Ok(boolean(Atom::Eq(linear(x)?.sub(&linear(y)?)))),
+            // x < y ⇔ x - y + 1 ≤ 0
+            (LessThanInteger, [x, y]) => Ok(boolean(Atom::Le(
+                linear(x)?
+                    .sub(&linear(y)?)
+                    .add(&Linear::constant(BigInt::from(1))),
+            ))),
+            (LessThanEqualsInteger, [x, y]) => Ok(boolean(Atom::Le(linear(x)?.sub(&linear(y)?)))),
+            (IData, [Value::Sym(Sym::Integer(e))]) => Ok(Value::Sym(Sym::IData(e.clone()))),
+            (UnIData, [Value::Sym(Sym::IData(e))]) => Ok(integer(e.clone())),
+            (BData, [Value::Sym(Sym::ByteString)]) => Ok(Value::Sym(Sym::BData)),
+            (UnBData, [Value::Sym(Sym::BData)]) => Ok(Value::Sym(Sym::ByteString)),
+            (
+                UnIData | UnBData | UnConstrData | UnMapData | UnListData,
+                [Value::Sym(Sym::IData(..) | Sym::BData)],
+            ) => Err(Stop::Failed),
+            (EqualsData, [x, y]) => equals_data(x, y),
+            (EqualsByteString, [x, y]) => match (x, y) {
+                (Value::Sym(Sym::ByteString), Value::Sym(Sym::ByteString)) => {
+                    Ok(Value::Con(Constant::Bool(true).into()))
+                }
+                (Value::Sym(Sym::ByteString), Value::Con(constant))
+                | (Value::Con(constant), Value::Sym(Sym::ByteString)) => match constant.as_ref() {
+                    Constant::ByteString(bytes) => Ok(boolean(Atom::BytesEq(bytes.clone()))),
+                    _ => Err(Stop::Failed),
+                },
+                _ => Err(Stop::Failed),
+            },
+            (LengthOfByteString, [Value::Sym(Sym::ByteString)]) => Ok(integer(Linear::var())),
+            _ => Err(self.unsupported(fun)),
+        }
+    }
+
+    fn unsupported(&self, fun: DefaultFunction) -> Stop {
+        Stop::Unknown(format!("builtin '{fun}' applied to the argument"))
+    }
+
+    /// Branch on a constraint, following the outcome given for replay if any, and otherwise the
+    /// one outcome that is satisfiable, or both by recording a fork for the negative one.
+    fn decide(&mut self, atom: Atom) -> Result<bool, Stop> {
+        let ix = self.decisions.len();
+
+        let holds = match self.replay.get(ix) {
+            Some(holds) => *holds,
+            None if ix >= self.prover.depth => return Err(Stop::TooDeep),
+            None => {
+                let mut constraints = self.constraints.clone();
+
+                constraints.push(atom.clone());
+                let can_hold = solve(self.input, &constraints).is_some();
+
+                constraints.pop();
+                constraints.push(atom.negate());
+                let can_fail = solve(self.input, &constraints).is_some();
+
+                if can_hold && can_fail {
+                    let mut fork = self.decisions.clone();
+                    fork.push(false);
+                    self.forks.push(fork);
+                }
+
+                can_hold
+            }
+        };
+
+        self.decisions.push(holds);
+        self.constraints
+            .push(if holds { atom } else { atom.negate() });
+
+        Ok(holds)
+    }
+}
+
+fn integer(e: Linear) -> Value {
+    match e.as_constant() {
+        Some(n) => Value::Con(Constant::Integer(n.clone()).into()),
+        None => Value::Sym(Sym::Integer(e)),
+    }
+}
+
+fn boolean(atom: Atom) -> Value {
+    match atom.evaluate() {
+        Some(b) => Value::Con(Constant::Bool(b).into()),
+        None => Value::Sym(Sym::Bool(atom)),
+    }
+}
+
+fn linear(value: &Value) -> Result<Linear, Stop> {
+    match value {
+        Value::Con(constant) => match constant.as_ref() {
+            Constant::Integer(n) => Ok(Linear::constant(n.clone())),
+            _ => Err(Stop::Failed),
+        },
+        Value::Sym(Sym::Integer(e)) => Ok(e.clone()),
+        _ => Err(Stop::Failed),
+    }
+}
+
+fn equals_data(x: &Value, y: &Value) -> Result<Value, Stop> {
+    let (sym, other) = match (x, y) {
+        (Value::Sym(sym), other) | (other, Value::Sym(sym)) => (sym, other),
+        _ => return Err(Stop::Failed),
+    };
+
+    match (sym, other) {
+        (Sym::IData(e), Value::Sym(Sym::IData(f))) => Ok(boolean(Atom::Eq(e.sub(f)))),
+        (Sym::BData, Value::Sym(Sym::BData)) => Ok(Value::Con(Constant::Bool(true).into())),
+        (Sym::IData(e), Value::Con(constant)) => match constant.as_ref() {
+            Constant::Data(PlutusData::BigInt(n)) => Ok(boolean(Atom::Eq(
+                e.sub(&Linear::constant(super::value::from_pallas_bigint(n))),
+            ))),
+            Constant::Data(..) => Ok(Value::Con(Constant::Bool(false).into())),
+            _ => Err(Stop::Failed),
+        },
+        (Sym::BData, Value::Con(constant)) => match constant.as_ref() {
+            Constant::Data(PlutusData::BoundedBytes(bytes)) => {
+                Ok(boolean(Atom::BytesEq(bytes.to_vec())))
+            }
+            Constant::Data(..) => Ok(Value::Con(Constant::Bool(false).into())),
+            _ => Err(Stop::Failed),
+        },
+        _ => Err(Stop::Failed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Input, Outcome, Prover};
+    use crate::{
+        ast::{Data, Name, NamedDeBruijn, Program},
+        parser,
+    };
+    use num_bigint::BigInt;
+    use pallas_primitives::conway::Language;
+
+    fn prove(src: &str, input: Input) -> Outcome {
+        let program: Program<Name> = parser::program(src).unwrap();
+        let program: Program<NamedDeBruijn> = program.try_into().unwrap();
+        Prover::new(Language::PlutusV3).prove(&program.term, input)
+    }
+
+    #[test]
+    fn integer_counterexample() {
+        // \x -> if unIData x == 137 then False else True
+        let outcome = prove(
+            r#"
+            (program 1.0.0
+              (lam x
+                (force [
+                  [ [ (force (builtin ifThenElse))
+                      [ [ (builtin equalsInteger) [ (builtin unIData) x ] ] (con integer 137) ]
+                    ]
+                    (delay (con bool False)) ]
+                  (delay (con bool True))
+                ])
+              )
+            )
+            "#,
+            Input::Integer,
+        );
+
+        assert_eq!(
+            outcome,
+            Outcome::Counterexample(Data::integer(BigInt::from(137)))
+        );
+    }
+
+    #[test]
+    fn integer_proof() {
+        // \x -> let n = unIData x in if n < 10 then n < 20 else 5 < n
+        let outcome = prove(
+            r#"
+            (program 1.0.0
+              (lam x
+                [
+                  (lam n
+                    (force [
+                      [ [ (force (builtin ifThenElse))
+                          [ [ (builtin lessThanInteger) n ] (con integer 10) ]
+                        ]
+                        (delay [ [ (builtin lessThanInteger) n ] (con integer 20) ]) ]
+                      (delay [ [ (builtin lessThanInteger) (con integer 5) ] n ])
+                    ])
+                  )
+                  [ (builtin unIData) x ]
+                ]
+              )
+            )
+            "#,
+            Input::Integer,
+        );
+
+        assert_eq!(
+            outcome,
+            Outcome::Proved {
+                paths: 2,
+                complete: true
+            }
+        );
+    }
+
+    #[test]
+    fn bytestring_counterexample() {
+        // \x -> let bs = unBData x in lengthOfByteString bs < 3 || bs == "abc"
+        let outcome = prove(
+            r#"
+            (program 1.0.0
+              (lam x
+                [
+                  (lam bs
+                    (force [
+                      [ [ (force (builtin ifThenElse))
+                          [ [ (builtin lessThanInteger) [ (builtin lengthOfByteString) bs ] ] (con integer 3) ]
+                        ]
+                        (delay (con bool True)) ]
+                      (delay [ [ (builtin equalsByteString) bs ] (con bytestring #616263) ])
+                    ])
+                  )
+                  [ (builtin unBData) x ]
+                ]
+              )
+            )
+            "#,
+            Input::ByteString,
+        );
+
+        assert_eq!(
+            outcome,
+            Outcome::Counterexample(Data::bytestring(vec![0, 0, 0]))
+        );
+    }
+
+    #[test]
+    fn unsupported_builtin() {
+        // \x -> divideInteger (unIData x) 2 < 100
+        let outcome = prove(
+            r#"
+            (program 1.0.0
+              (lam x
+                [ [ (builtin lessThanInteger)
+                    [ [ (builtin divideInteger) [ (builtin unIData) x ] ] (con integer 2) ]
+                  ]
+                  (con integer 100)
+                ]
+              )
+            )
+            "#,
+            Input::Integer,
+        );
+
+        assert!(matches!(outcome, Outcome::Unknown(..)), "{outcome:?}");
+    }
+
+    #[test]
+    fn bounded_depth() {
+        // \x -> (fix \go n -> if n <= 0 then True else go (n - 1)) (unIData x)
+        let outcome = prove(
+            r#"
+            (program 1.0.0
+              (lam x
+                [
+                  [
+                    (lam f [ (lam s [ s s ]) (lam s (lam n [ [ f [ s s ] ] n ])) ])
+                    (lam go (lam n
+                      (force [
+                        [ [ (force (builtin ifThenElse))
+                            [ [ (builtin lessThanEqualsInteger) n ] (con integer 0) ]
+                          ]
+                          (delay (con bool True)) ]
+                        (delay [ go [ [ (builtin subtractInteger) n ] (con integer 1) ] ])
+                      ])
+                    ))
+                  ]
+                  [ (builtin unIData) x ]
+                ]
+              )
+            )
+            "#,
+            Input::Integer,
+        );
+
+        assert_eq!(
+            outcome,
+            Outcome::Proved {
+                paths: Prover::DEFAULT_DEPTH + 1,
+                complete: false
+            }
+        );
+    }
+}
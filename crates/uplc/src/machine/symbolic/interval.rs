@@ -0,0 +1,331 @@
+//! A decision procedure for the path conditions gathered during symbolic evaluation.
+//!
+//! This isn't a general-purpose (SMT) solver: it only handles the narrow fragment below, which is
+//! all the symbolic evaluator produces, and anything beyond it is left unknown by the evaluator.
+//!
+//! Path conditions are conjunctions of linear (dis)equalities and inequalities over a single
+//! integer variable, which stands for either the integer input or the length of the bytestring
+//! input, along with (dis)equalities of the bytestring input itself. Over a single variable, each
+//! constraint either narrows down an interval or excludes a point from it, so satisfiability is
+//! decided exactly and models are picked as close to zero as possible.
+
+use super::Input;
+use crate::ast::Data;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use pallas_primitives::conway::PlutusData;
+
+/// Bytestrings longer than this can't be part of a transaction, and aren't considered as models.
+pub const MAX_BYTESTRING_LENGTH: usize = 16384;
+
+/// An affine expression 'a * x + b' of the variable 'x'.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Linear {
+    pub a: BigInt,
+    pub b: BigInt,
+}
+
+impl Linear {
+    pub fn var() -> Self {
+        Linear {
+            a: BigInt::one(),
+            b: BigInt::zero(),
+        }
+    }
+
+    pub fn constant(b: BigInt) -> Self {
+        Linear {
+            a: BigInt::zero(),
+            b,
+        }
+    }
+
+    pub fn as_constant(&self) -> Option<&BigInt> {
+        self.a.is_zero().then_some(&self.b)
+    }
+
+    pub fn add(&self, other: &Linear) -> Linear {
+        Linear {
+            a: &self.a + &other.a,
+            b: &self.b + &other.b,
+        }
+    }
+
+    pub fn sub(&self, other: &Linear) -> Linear {
+        Linear {
+            a: &self.a - &other.a,
+            b: &self.b - &other.b,
+        }
+    }
+
+    pub fn scale(&self, k: &BigInt) -> Linear {
+        Linear {
+            a: &self.a * k,
+            b: &self.b * k,
+        }
+    }
+
+    /// The value of 'x' for which the expression is zero, if it is an integer.
+    fn root(&self) -> Option<BigInt> {
+        let (x, remainder) = (-&self.b).div_rem(&self.a);
+        remainder.is_zero().then_some(x)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Atom {
+    /// a * x + b = 0
+    Eq(Linear),
+    /// a * x + b ≠ 0
+    Ne(Linear),
+    /// a * x + b ≤ 0
+    Le(Linear),
+    /// The bytestring input equals the given one.
+    BytesEq(Vec<u8>),
+    /// The bytestring input differs from the given one.
+    BytesNe(Vec<u8>),
+}
+
+impl Atom {
+    pub fn negate(&self) -> Atom {
+        match self {
+            Atom::Eq(e) => Atom::Ne(e.clone()),
+            Atom::Ne(e) => Atom::Eq(e.clone()),
+            // ¬(e ≤ 0) ⇔ e ≥ 1 ⇔ -e + 1 ≤ 0
+            Atom::Le(e) => Atom::Le(Linear::constant(BigInt::one()).sub(e)),
+            Atom::BytesEq(bytes) => Atom::BytesNe(bytes.clone()),
+            Atom::BytesNe(bytes) => Atom::BytesEq(bytes.clone()),
+        }
+    }
+
+    /// The truth value of the atom, when it doesn't depend on the input.
+    pub fn evaluate(&self) -> Option<bool> {
+        match self {
+            Atom::Eq(e) => e.as_constant().map(|b| b.is_zero()),
+            Atom::Ne(e) => e.as_constant().map(|b| !b.is_zero()),
+            Atom::Le(e) => e.as_constant().map(|b| !b.is_positive()),
+            Atom::BytesEq(..) | Atom::BytesNe(..) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Model {
+    Integer(BigInt),
+    ByteString(Vec<u8>),
+}
+
+impl From<Model> for PlutusData {
+    fn from(model: Model) -> Self {
+        match model {
+            Model::Integer(n) => Data::integer(n),
+            Model::ByteString(bytes) => Data::bytestring(bytes),
+        }
+    }
+}
+
+/// Find an input satisfying all the given atoms, if any.
+pub fn solve(input: Input, atoms: &[Atom]) -> Option<Model> {
+    let mut lo = match input {
+        Input::Integer => None,
+        Input::ByteString => Some(BigInt::zero()),
+    };
+    let mut hi = match input {
+        Input::Integer => None,
+        Input::ByteString => Some(BigInt::from(MAX_BYTESTRING_LENGTH)),
+    };
+    let mut holes = Vec::new();
+    let mut equal: Option<&Vec<u8>> = None;
+    let mut different = Vec::new();
+
+    for atom in atoms {
+        match atom {
+            Atom::Eq(e) if e.a.is_zero() => {
+                if !e.b.is_zero() {
+                    return None;
+                }
+            }
+            Atom::Eq(e) => {
+                let x = e.root()?;
+                raise(&mut lo, x.clone());
+                lower(&mut hi, x);
+            }
+            Atom::Ne(e) if e.a.is_zero() => {
+                if e.b.is_zero() {
+                    return None;
+                }
+            }
+            Atom::Ne(e) => holes.extend(e.root()),
+            Atom::Le(e) if e.a.is_zero() => {
+                if e.b.is_positive() {
+                    return None;
+                }
+            }
+            Atom::Le(Linear { a, b }) => {
+                // a * x ≤ -b
+                if a.is_positive() {
+                    lower(&mut hi, (-b).div_floor(a));
+                } else {
+                    raise(&mut lo, -((-b).div_floor(&-a)));
+                }
+            }
+            Atom::BytesEq(bytes) => {
+                if equal.is_some_and(|other| other != bytes) {
+                    return None;
+                }
+                equal = Some(bytes);
+                raise(&mut lo, BigInt::from(bytes.len()));
+                lower(&mut hi, BigInt::from(bytes.len()));
+            }
+            Atom::BytesNe(bytes) => different.push(bytes),
+        }
+    }
+
+    if let (Some(lo), Some(hi)) = (&lo, &hi) {
+        if lo > hi {
+            return None;
+        }
+    }
+
+    if let Some(bytes) = equal {
+        return if holes.contains(&BigInt::from(bytes.len())) || different.contains(&bytes) {
+            None
+        } else {
+            Some(Model::ByteString(bytes.clone()))
+        };
+    }
+
+    let start = match (&lo, &hi) {
+        (Some(lo), _) if lo.is_positive() => lo.clone(),
+        (_, Some(hi)) if hi.is_negative() => hi.clone(),
+        _ => BigInt::zero(),
+    };
+
+    // Try values of the interval by increasing distance to the start. There are finitely many
+    // holes and bytestrings to avoid, so this terminates unless the interval is exhausted first.
+    let mut distance = BigInt::zero();
+    loop {
+        let above = &start + &distance;
+        let below = &start - &distance;
+
+        let above = hi.as_ref().is_none_or(|hi| &above <= hi).then_some(above);
+        let below = lo.as_ref().is_none_or(|lo| &below >= lo).then_some(below);
+
+        if above.is_none() && below.is_none() {
+            return None;
+        }
+
+        for x in above
+            .into_iter()
+            .chain(below.filter(|_| !distance.is_zero()))
+        {
+            if holes.contains(&x) {
+                continue;
+            }
+
+            match input {
+                Input::Integer => return Some(Model::Integer(x)),
+                Input::ByteString => {
+                    if let Some(bytes) = fresh(x.to_usize()?, &different) {
+                        return Some(Model::ByteString(bytes));
+                    }
+                }
+            }
+        }
+
+        distance += 1;
+    }
+}
+
+fn raise(bound: &mut Option<BigInt>, x: BigInt) {
+    if bound.as_ref().is_none_or(|lo| &x > lo) {
+        *bound = Some(x);
+    }
+}
+
+fn lower(bound: &mut Option<BigInt>, x: BigInt) {
+    if bound.as_ref().is_none_or(|hi| &x < hi) {
+        *bound = Some(x);
+    }
+}
+
+/// A bytestring of the given length that is none of the given ones, counting up from zeroes.
+fn fresh(length: usize, different: &[&Vec<u8>]) -> Option<Vec<u8>> {
+    (0..=different.len() as u64)
+        .take_while(|k| length >= 8 || *k < 1 << (8 * length))
+        .map(|k| {
+            let mut bytes = vec![0; length];
+            let be = k.to_be_bytes();
+            let n = length.min(be.len());
+            bytes[length - n..].copy_from_slice(&be[be.len() - n..]);
+            bytes
+        })
+        .find(|bytes| !different.contains(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Atom, Input, Linear, Model, solve};
+    use num_bigint::BigInt;
+
+    fn linear(a: i64, b: i64) -> Linear {
+        Linear {
+            a: BigInt::from(a),
+            b: BigInt::from(b),
+        }
+    }
+
+    #[test]
+    fn integer_constraints() {
+        // 3x - 7 ≤ 0 ∧ -x + 1 ≤ 0 ∧ x ≠ 1  ⇒  x = 2
+        let atoms = [
+            Atom::Le(linear(3, -7)),
+            Atom::Le(linear(-1, 1)),
+            Atom::Ne(linear(1, -1)),
+        ];
+        assert_eq!(
+            solve(Input::Integer, &atoms),
+            Some(Model::Integer(BigInt::from(2)))
+        );
+
+        // 2x + 1 = 0 has no integer solution.
+        assert_eq!(solve(Input::Integer, &[Atom::Eq(linear(2, 1))]), None);
+
+        // x ≤ -5 ∧ x ≠ -5  ⇒  x = -6
+        let atoms = [Atom::Le(linear(1, 5)), Atom::Ne(linear(1, 5))];
+        assert_eq!(
+            solve(Input::Integer, &atoms),
+            Some(Model::Integer(BigInt::from(-6)))
+        );
+
+        // x ≤ 0 ∧ x ≥ 0 ∧ x ≠ 0
+        let atoms = [
+            Atom::Le(linear(1, 0)),
+            Atom::Le(linear(-1, 0)),
+            Atom::Ne(linear(1, 0)),
+        ];
+        assert_eq!(solve(Input::Integer, &atoms), None);
+    }
+
+    #[test]
+    fn bytestring_constraints() {
+        // len ≥ 1 ∧ bs ≠ #00 ∧ bs ≠ #01  ⇒  bs = #02
+        let atoms = [
+            Atom::Le(linear(-1, 1)),
+            Atom::BytesNe(vec![0]),
+            Atom::BytesNe(vec![1]),
+        ];
+        assert_eq!(
+            solve(Input::ByteString, &atoms),
+            Some(Model::ByteString(vec![2]))
+        );
+
+        // len ≤ 2 ∧ bs = #616263
+        let atoms = [Atom::Le(linear(1, -2)), Atom::BytesEq(b"abc".to_vec())];
+        assert_eq!(solve(Input::ByteString, &atoms), None);
+
+        // len < 0
+        assert_eq!(solve(Input::ByteString, &[Atom::Le(linear(1, 1))]), None);
+    }
+}
@@ -1,15 +1,96 @@
-use crate::ast::{Name, Program};
+use crate::{
+    ast::{Name, Program},
+    source_map::SourceMap,
+};
 
+pub mod differential;
 pub mod interner;
+pub mod report;
 pub mod shrinker;
 
+/// The passes making up the optimization pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum Pass {
+    CompressCurvePoints,
+    RunOncePass,
+    MultiPass,
+    BuiltinCurryReducer,
+    CleanUpNoInlines,
+    Afterwards,
+}
+
+/// What programs are optimized for.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, strum::Display,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum OptLevel {
+    /// Leave programs as generated, but for the rewrites they can't do without, such as the
+    /// compression of curve points.
+    None,
+    /// Make programs as small as possible.
+    #[default]
+    Size,
+    /// Make programs as cheap to run as possible, when it conflicts with their size. Partially
+    /// applied builtins aren't hoisted out of functions: binding them takes steps, which their
+    /// uses only make up for once enough of them are evaluated.
+    Cpu,
+}
+
 pub fn aiken_optimize_and_intern(program: Program<Name>) -> Program<Name> {
-    let mut prog = program.run_once_pass();
+    aiken_optimize_and_intern_with(program, OptLevel::default(), &mut |_, _| {})
+}
+
+/// Like 'aiken_optimize_and_intern', but optimizing for the given level, and handing every pass
+/// performed, in order, to 'inspect' along with the program it produced.
+pub fn aiken_optimize_and_intern_with(
+    program: Program<Name>,
+    level: OptLevel,
+    inspect: &mut impl FnMut(Pass, &Program<Name>),
+) -> Program<Name> {
+    optimize(program, level, &mut SourceMap::new(), inspect)
+}
+
+/// Like 'aiken_optimize_and_intern', but optimizing for the given level, and keeping the given
+/// source map of the program up to date with the rewrites performed.
+pub fn aiken_optimize_and_intern_tracking(
+    program: Program<Name>,
+    level: OptLevel,
+    source_map: &mut SourceMap,
+) -> Program<Name> {
+    optimize(program, level, source_map, &mut |_, _| {})
+}
+
+fn optimize(
+    program: Program<Name>,
+    level: OptLevel,
+    source_map: &mut SourceMap,
+    inspect: &mut impl FnMut(Pass, &Program<Name>),
+) -> Program<Name> {
+    if level == OptLevel::None {
+        // Curve points are otherwise compressed along with the other rewrites run once.
+        let mut prog = program.compress_curve_points_tracking(source_map);
+        inspect(Pass::CompressCurvePoints, &prog);
+
+        prog = prog.clean_up_no_inlines_tracking(source_map);
+        inspect(Pass::CleanUpNoInlines, &prog);
+
+        prog = prog.afterwards_tracking(false, source_map);
+        inspect(Pass::Afterwards, &prog);
+
+        return prog;
+    }
+
+    let mut prog = program.run_once_pass_tracking(source_map);
+    inspect(Pass::RunOncePass, &prog);
 
     let mut prev_count = 0;
 
     loop {
-        let (current_program, context) = prog.multi_pass();
+        let (current_program, context) = prog.multi_pass_tracking(source_map);
+        inspect(Pass::MultiPass, &current_program);
 
         if context.node_count == prev_count {
             prog = current_program;
@@ -20,23 +101,35 @@ pub fn aiken_optimize_and_intern(program: Program<Name>) -> Program<Name> {
         }
     }
 
-    prog = prog
-        .builtin_curry_reducer()
-        .multi_pass()
-        .0
-        .builtin_curry_reducer();
+    if level == OptLevel::Size {
+        prog = prog.builtin_curry_reducer_tracking(source_map);
+        inspect(Pass::BuiltinCurryReducer, &prog);
 
-    loop {
-        let (current_program, context) = prog.multi_pass();
+        prog = prog.multi_pass_tracking(source_map).0;
+        inspect(Pass::MultiPass, &prog);
 
-        if context.node_count == prev_count {
-            prog = current_program;
-            break;
-        } else {
-            prog = current_program;
-            prev_count = context.node_count;
+        prog = prog.builtin_curry_reducer_tracking(source_map);
+        inspect(Pass::BuiltinCurryReducer, &prog);
+
+        loop {
+            let (current_program, context) = prog.multi_pass_tracking(source_map);
+            inspect(Pass::MultiPass, &current_program);
+
+            if context.node_count == prev_count {
+                prog = current_program;
+                break;
+            } else {
+                prog = current_program;
+                prev_count = context.node_count;
+            }
         }
     }
 
-    prog.clean_up_no_inlines().afterwards()
+    prog = prog.clean_up_no_inlines_tracking(source_map);
+    inspect(Pass::CleanUpNoInlines, &prog);
+
+    prog = prog.afterwards_tracking(true, source_map);
+    inspect(Pass::Afterwards, &prog);
+
+    prog
 }
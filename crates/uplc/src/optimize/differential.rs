@@ -0,0 +1,272 @@
+//! Differential evaluation of programs before and after optimization.
+//!
+//! Optimizations must not change what a program does: for any input, the optimized program
+//! ought to return the same value as the original one, or fail whenever the original one fails.
+//! A trace records the program produced by each pass of the optimization pipeline, so that when
+//! the original and optimized programs disagree on an input, the divergence can be traced back
+//! to the first pass after which the outcome differs.
+//!
+//! Only constant results are compared; programs returning functions are merely expected to
+//! succeed on both ends. Evaluations running out of budget are inconclusive and never reported.
+
+use super::{
+    OptLevel, Pass, aiken_optimize_and_intern_with, interner::CodeGenInterner, shrinker::Reducer,
+};
+use crate::{
+    ast::{Constant, Name, NamedDeBruijn, Program, Term},
+    machine::{self, cost_model::ExBudget},
+    source_map::SourceMap,
+};
+use pallas_primitives::conway::Language;
+use std::{fmt, rc::Rc};
+use strum::IntoEnumIterator;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Returned(Rc<Constant>),
+    /// The program evaluated to a value that isn't a constant, such as a function.
+    Succeeded,
+    Failed,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Returned(constant) => write!(
+                f,
+                "returns {}",
+                Term::<NamedDeBruijn>::Constant(constant.clone()).to_pretty()
+            ),
+            Outcome::Succeeded => write!(f, "succeeds"),
+            Outcome::Failed => write!(f, "fails"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub pass: Pass,
+    /// Position of the pass in the pipeline, counting from 1.
+    pub index: usize,
+    /// The first rewrite of a 'multi_pass' changing the outcome once enabled along with those
+    /// preceding it, when it's a 'multi_pass' that diverges.
+    pub reducer: Option<Reducer>,
+    /// Outcome of the original program.
+    pub expected: Outcome,
+    /// Outcome of the program produced by the pass.
+    pub actual: Outcome,
+}
+
+#[derive(Debug, Clone)]
+pub struct Trace {
+    pub original: Program<Name>,
+    pub passes: Vec<(Pass, Program<Name>)>,
+}
+
+impl Trace {
+    /// Optimize a program as 'aiken_optimize_and_intern' does for the given level, keeping the
+    /// program produced by every pass along the way.
+    pub fn new(program: Program<Name>, level: OptLevel) -> Self {
+        let mut original = program;
+
+        CodeGenInterner::new().program(&mut original);
+
+        let mut passes = Vec::new();
+
+        aiken_optimize_and_intern_with(original.clone(), level, &mut |pass, program| {
+            passes.push((pass, program.clone()))
+        });
+
+        Trace { original, passes }
+    }
+
+    pub fn optimized(&self) -> &Program<Name> {
+        self.passes
+            .last()
+            .map(|(_, program)| program)
+            .unwrap_or(&self.original)
+    }
+
+    /// Evaluate the original and optimized programs on the same input, which 'apply' supplies to
+    /// either of them. When their outcomes differ, find the first pass responsible.
+    pub fn compare(
+        &self,
+        language: &Language,
+        apply: impl Fn(Program<NamedDeBruijn>) -> Program<NamedDeBruijn>,
+    ) -> Option<Divergence> {
+        let expected = outcome(&self.original, language, &apply)?;
+
+        let optimized = outcome(self.optimized(), language, &apply)?;
+
+        if optimized == expected {
+            return None;
+        }
+
+        let mut input = &self.original;
+
+        for (index, (pass, program)) in self.passes.iter().enumerate() {
+            match outcome(program, language, &apply) {
+                Some(actual) if actual != expected => {
+                    // Rewrites are enabled one after the other, until the pass diverges.
+                    let reducer = match pass {
+                        Pass::MultiPass => {
+                            let reducers = Reducer::iter().collect::<Vec<_>>();
+
+                            (1..=reducers.len())
+                                .find(|enabled| {
+                                    let (reduced, _) = input.clone().multi_pass_with(
+                                        &reducers[..*enabled],
+                                        &mut SourceMap::new(),
+                                    );
+
+                                    outcome(&reduced, language, &apply)
+                                        .is_some_and(|outcome| outcome != expected)
+                                })
+                                .map(|enabled| reducers[enabled - 1])
+                        }
+                        _ => None,
+                    };
+
+                    return Some(Divergence {
+                        pass: *pass,
+                        index: index + 1,
+                        reducer,
+                        expected,
+                        actual,
+                    });
+                }
+                _ => input = program,
+            }
+        }
+
+        unreachable!("the optimized program is the one produced by the last pass")
+    }
+}
+
+fn outcome(
+    program: &Program<Name>,
+    language: &Language,
+    apply: impl Fn(Program<NamedDeBruijn>) -> Program<NamedDeBruijn>,
+) -> Option<Outcome> {
+    // Passes may leave behind markers which only the optimizer understands, and which are
+    // removed before programs are evaluated.
+    let Ok(program) = Program::<NamedDeBruijn>::try_from(program.clone().clean_up_no_inlines())
+    else {
+        return Some(Outcome::Failed);
+    };
+
+    match apply(program)
+        .eval_version(ExBudget::max(), language)
+        .result()
+    {
+        Ok(Term::Constant(constant)) => Some(Outcome::Returned(constant)),
+        Ok(..) => Some(Outcome::Succeeded),
+        Err(machine::Error::OutOfExError(..)) => None,
+        Err(..) => Some(Outcome::Failed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Divergence, Outcome, Trace};
+    use crate::{
+        ast::{Constant, Data, Name, Program},
+        optimize::{OptLevel, Pass},
+        parser,
+    };
+    use pallas_primitives::conway::Language;
+
+    fn program(src: &str) -> Program<Name> {
+        parser::program(src).unwrap()
+    }
+
+    #[test]
+    fn sound_optimizations_agree() {
+        let trace = Trace::new(
+            program(
+                r#"(program 1.1.0
+                (lam d
+                  [(lam f [ [f (con integer 1)] [ (builtin unIData) d ] ])
+                   (lam a (lam b [ [ (builtin addInteger) a ] b ]))]))"#,
+            ),
+            OptLevel::Size,
+        );
+
+        assert!(!trace.passes.is_empty());
+
+        for n in [0, 41, -1] {
+            assert_eq!(
+                trace.compare(&Language::PlutusV3, |program| {
+                    program.apply_data(Data::integer(n.into()))
+                }),
+                None
+            );
+        }
+
+        // The program fails on anything but integers, before and after optimization alike.
+        assert_eq!(
+            trace.compare(&Language::PlutusV3, |program| {
+                program.apply_data(Data::bytestring(vec![]))
+            }),
+            None
+        );
+
+        // Functions can't be compared, only their success.
+        assert_eq!(
+            Trace::new(program("(program 1.1.0 (lam x x))"), OptLevel::Size)
+                .compare(&Language::PlutusV3, |program| program),
+            None
+        );
+    }
+
+    #[test]
+    fn names_the_first_diverging_pass() {
+        let original = program(
+            "(program 1.1.0 (lam d [ [ (builtin equalsInteger) [ (builtin unIData) d ] ] (con integer 0) ]))",
+        );
+
+        let broken = program("(program 1.1.0 (lam d (con bool True)))");
+
+        let trace = Trace {
+            original: original.clone(),
+            passes: vec![
+                (Pass::RunOncePass, original.clone()),
+                (Pass::BuiltinCurryReducer, broken.clone()),
+                (Pass::Afterwards, broken),
+            ],
+        };
+
+        assert_eq!(
+            trace.compare(&Language::PlutusV3, |program| {
+                program.apply_data(Data::integer(0.into()))
+            }),
+            None
+        );
+
+        assert_eq!(
+            trace.compare(&Language::PlutusV3, |program| {
+                program.apply_data(Data::integer(1.into()))
+            }),
+            Some(Divergence {
+                pass: Pass::BuiltinCurryReducer,
+                index: 2,
+                reducer: None,
+                expected: Outcome::Returned(Constant::Bool(false).into()),
+                actual: Outcome::Returned(Constant::Bool(true).into()),
+            })
+        );
+
+        // Failing instead of returning a value is a divergence too.
+        assert_eq!(
+            trace
+                .compare(&Language::PlutusV3, |program| {
+                    program.apply_data(Data::bytestring(vec![]))
+                })
+                .map(|divergence| (divergence.expected, divergence.actual)),
+            Some((
+                Outcome::Failed,
+                Outcome::Returned(Constant::Bool(true).into())
+            ))
+        );
+    }
+}
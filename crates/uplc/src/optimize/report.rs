@@ -0,0 +1,272 @@
+//! Statistics about the programs produced by each pass of the optimization pipeline.
+//!
+//! Besides node counts and flat sizes, passes are judged by a static estimate of the execution
+//! cost of programs: the cost of the machine steps needed to evaluate every node once. Builtins
+//! are left out, and so is how many times each node actually gets evaluated, which depends on
+//! inputs. The estimate is no substitute for evaluating programs, but it tells whether a pass
+//! leaves the machine with more or less work to do.
+
+use super::{OptLevel, Pass, aiken_optimize_and_intern_with, interner::CodeGenInterner};
+use crate::{
+    ast::{DeBruijn, Name, Program, Term},
+    machine::cost_model::{ExBudget, MachineCosts, StepKind},
+};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub node_count: usize,
+    /// Size in bytes of the flat-encoded program, when it can be encoded at all. Programs holding
+    /// BLS12-381 elements can't be, until the first pass compresses them.
+    pub flat_size: Option<usize>,
+    pub cost: ExBudget,
+}
+
+impl Stats {
+    pub fn new(program: &Program<Name>) -> Self {
+        let costs = MachineCosts::default();
+
+        let mut node_count = 0;
+        let mut cost = ExBudget { mem: 0, cpu: 0 };
+        let mut terms = vec![&program.term];
+
+        while let Some(term) = terms.pop() {
+            node_count += 1;
+
+            let step = match term {
+                Term::Var(..) => Some(StepKind::Var),
+                Term::Delay(..) => Some(StepKind::Delay),
+                Term::Lambda { .. } => Some(StepKind::Lambda),
+                Term::Apply { .. } => Some(StepKind::Apply),
+                Term::Constant(..) => Some(StepKind::Constant),
+                Term::Force(..) => Some(StepKind::Force),
+                Term::Error => None,
+                Term::Builtin(..) => Some(StepKind::Builtin),
+                Term::Constr { .. } => Some(StepKind::Constr),
+                Term::Case { .. } => Some(StepKind::Case),
+            };
+
+            if let Some(step) = step {
+                cost = cost + costs.get(step);
+            }
+
+            terms.extend(term.children());
+        }
+
+        // Markers left for the optimizer aren't part of programs once encoded.
+        let flat_size = Program::<DeBruijn>::try_from(program.clone().clean_up_no_inlines())
+            .ok()
+            .and_then(|program| program.to_flat().ok())
+            .map(|bytes| bytes.len());
+
+        Stats {
+            node_count,
+            flat_size,
+            cost,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub level: OptLevel,
+    pub original: Stats,
+    pub passes: Vec<(Pass, Stats)>,
+}
+
+impl Report {
+    /// Optimize a program as 'aiken_optimize_and_intern' does for the given level, gathering
+    /// statistics about the program produced by every pass along the way.
+    pub fn new(program: Program<Name>, level: OptLevel) -> (Program<Name>, Self) {
+        let mut original = program;
+
+        CodeGenInterner::new().program(&mut original);
+
+        let mut passes = Vec::new();
+
+        let optimized =
+            aiken_optimize_and_intern_with(original.clone(), level, &mut |pass, program| {
+                passes.push((pass, Stats::new(program)))
+            });
+
+        let report = Report {
+            level,
+            original: Stats::new(&original),
+            passes,
+        };
+
+        (optimized, report)
+    }
+
+    pub fn optimized(&self) -> &Stats {
+        self.passes
+            .last()
+            .map(|(_, stats)| stats)
+            .unwrap_or(&self.original)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flat_size = |stats: &Stats| {
+            stats
+                .flat_size
+                .map(|size| size.to_string())
+                .unwrap_or_else(|| "-".to_string())
+        };
+
+        let delta = |after: i64, before: i64| match after - before {
+            0 => String::new(),
+            delta => format!("{delta:+}"),
+        };
+
+        writeln!(
+            f,
+            "{:>3}  {:<22} {:>8} {:>7} {:>9} {:>7} {:>12} {:>11} {:>9} {:>8}",
+            "#", "pass", "nodes", "Δ", "flat size", "Δ", "est. cpu", "Δ", "est. mem", "Δ"
+        )?;
+
+        writeln!(
+            f,
+            "{:>3}  {:<22} {:>8} {:>7} {:>9} {:>7} {:>12} {:>11} {:>9} {:>8}",
+            "",
+            "(original)",
+            self.original.node_count,
+            "",
+            flat_size(&self.original),
+            "",
+            self.original.cost.cpu,
+            "",
+            self.original.cost.mem,
+            "",
+        )?;
+
+        let mut before = &self.original;
+
+        for (index, (pass, after)) in self.passes.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>3}  {:<22} {:>8} {:>7} {:>9} {:>7} {:>12} {:>11} {:>9} {:>8}",
+                index + 1,
+                pass.to_string(),
+                after.node_count,
+                delta(after.node_count as i64, before.node_count as i64),
+                flat_size(after),
+                match (after.flat_size, before.flat_size) {
+                    (Some(after), Some(before)) => delta(after as i64, before as i64),
+                    _ => String::new(),
+                },
+                after.cost.cpu,
+                delta(after.cost.cpu, before.cost.cpu),
+                after.cost.mem,
+                delta(after.cost.mem, before.cost.mem),
+            )?;
+
+            before = after;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Report, Stats};
+    use crate::{
+        ast::{Name, Program},
+        machine::cost_model::ExBudget,
+        optimize::{OptLevel, Pass, aiken_optimize_and_intern},
+        parser,
+    };
+
+    fn program(src: &str) -> Program<Name> {
+        parser::program(src).unwrap()
+    }
+
+    #[test]
+    fn stats() {
+        let stats = Stats::new(&program(
+            "(program 1.1.0 [ (lam x [ (builtin iData) x ]) (con integer 1) ])",
+        ));
+
+        assert_eq!(stats.node_count, 6);
+        assert_eq!(
+            stats.cost,
+            ExBudget {
+                mem: 600,
+                cpu: 96000
+            }
+        );
+        assert!(stats.flat_size.is_some());
+    }
+
+    #[test]
+    fn report_every_pass() {
+        let src = r#"(program 1.1.0
+            (lam d
+              [(lam f [ [f (con integer 1)] [ (builtin unIData) d ] ])
+               (lam a (lam b [ [ (builtin addInteger) a ] b ]))]))"#;
+
+        let (optimized, report) = Report::new(program(src), OptLevel::Size);
+
+        assert_eq!(optimized, aiken_optimize_and_intern(program(src)));
+        assert_eq!(
+            report.passes.first().map(|(pass, _)| *pass),
+            Some(Pass::RunOncePass)
+        );
+        assert_eq!(
+            report.passes.last().map(|(pass, _)| *pass),
+            Some(Pass::Afterwards)
+        );
+        assert!(report.optimized().node_count < report.original.node_count);
+        assert!(report.optimized().flat_size < report.original.flat_size);
+        assert!(report.optimized().cost.cpu < report.original.cost.cpu);
+
+        let (_, report) = Report::new(program(src), OptLevel::Cpu);
+
+        assert!(
+            report
+                .passes
+                .iter()
+                .all(|(pass, _)| *pass != Pass::BuiltinCurryReducer)
+        );
+
+        let (_, report) = Report::new(program(src), OptLevel::None);
+
+        assert_eq!(
+            report
+                .passes
+                .iter()
+                .map(|(pass, _)| *pass)
+                .collect::<Vec<_>>(),
+            vec![
+                Pass::CompressCurvePoints,
+                Pass::CleanUpNoInlines,
+                Pass::Afterwards
+            ]
+        );
+        assert_eq!(report.optimized(), &report.original);
+    }
+
+    #[test]
+    fn compress_curve_points_unoptimized() {
+        let src = r#"(program 1.1.0
+            [ (builtin bls12_381_G1_equal)
+              (con bls12_381_G1_element 0x97f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb)
+              (con bls12_381_G1_element 0x97f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb) ])"#;
+
+        let (optimized, report) = Report::new(program(src), OptLevel::None);
+
+        // Curve points can't be serialised as such, so even unoptimized programs are rewritten.
+        assert!(report.original.flat_size.is_none());
+        assert!(report.optimized().flat_size.is_some());
+        assert!(report.optimized().cost.cpu > report.original.cost.cpu);
+        assert_eq!(
+            optimized
+                .to_pretty()
+                .matches("bls12_381_G1_uncompress")
+                .count(),
+            1
+        );
+    }
+}
@@ -1,16 +1,17 @@
 use super::interner::CodeGenInterner;
 use crate::{
-    ast::{Constant, Data, Name, NamedDeBruijn, Program, Term, Type},
+    ast::{Constant, Data, Name, NamedDeBruijn, Program, Term, TermPath, Type},
     builder::{CONSTR_FIELDS_EXPOSER, CONSTR_INDEX_EXPOSER, INDICES_CONVERTER},
     builtins::DefaultFunction,
     machine::{cost_model::ExBudget, runtime::Compressable, value::from_pallas_bigint},
+    source_map::SourceMap,
 };
 use blst::{blst_p1, blst_p2};
 use indexmap::IndexMap;
 use itertools::{FoldWhile, Itertools};
 use pallas_primitives::conway::{BigInt, PlutusData};
 use std::{cmp::Ordering, iter, ops::Neg, rc::Rc};
-use strum::IntoEnumIterator;
+use strum::{EnumIter, IntoEnumIterator};
 
 #[derive(Eq, Hash, PartialEq, Clone, Debug, PartialOrd)]
 pub enum ScopePath {
@@ -877,6 +878,24 @@ pub struct Context {
     pub blst_p2_list: Vec<blst_p2>,
     pub write_bits_convert: bool,
     pub node_count: usize,
+    /// Source map kept up to date with the rewrites performed, if any.
+    pub source_map: SourceMap,
+    /// Path of the term being visited.
+    pub path: TermPath,
+}
+
+/// The rewrites attempted on every node by 'multi_pass', in order, until one applies. Constants
+/// are flipped on nodes none of the others applied to, as 'convert_arithmetic_ops' requires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum Reducer {
+    LambdaReducer,
+    IdentityReducer,
+    InlineReducer,
+    ForceDelayReducer,
+    CastDataReducer,
+    BuiltinEvalReducer,
+    ConvertArithmeticOps,
 }
 
 #[derive(Clone, Debug)]
@@ -899,6 +918,7 @@ impl Term<Name> {
             Term::Apply { function, argument } => {
                 let arg = Rc::make_mut(argument);
 
+                context.path.0.push(1);
                 arg.traverse_uplc_with_helper(
                     &scope.push(ScopePath::ARG),
                     vec![],
@@ -907,6 +927,8 @@ impl Term<Name> {
                     context,
                     inline_lambda,
                 );
+                context.path.0.pop();
+
                 let apply_id = id_gen.next_id();
 
                 // Here we must clone since we must leave the original AST alone
@@ -914,6 +936,7 @@ impl Term<Name> {
 
                 let func = Rc::make_mut(function);
 
+                context.path.0.push(0);
                 func.traverse_uplc_with_helper(
                     &scope.push(ScopePath::FUNC),
                     arg_stack,
@@ -922,8 +945,9 @@ impl Term<Name> {
                     context,
                     inline_lambda,
                 );
+                context.path.0.pop();
 
-                with(Some(apply_id), self, vec![], scope, context);
+                self.visit_with(Some(apply_id), vec![], scope, with, context);
             }
             Term::Force(f) => {
                 let f = Rc::make_mut(f);
@@ -931,9 +955,11 @@ impl Term<Name> {
 
                 arg_stack.push(Args::Force(force_id));
 
+                context.path.0.push(0);
                 f.traverse_uplc_with_helper(scope, arg_stack, id_gen, with, context, inline_lambda);
+                context.path.0.pop();
 
-                with(Some(force_id), self, vec![], scope, context);
+                self.visit_with(Some(force_id), vec![], scope, with, context);
             }
             Term::Delay(d) => {
                 let d = Rc::make_mut(d);
@@ -945,9 +971,11 @@ impl Term<Name> {
                     })
                     .unwrap_or_default();
 
+                context.path.0.push(0);
                 d.traverse_uplc_with_helper(scope, arg_stack, id_gen, with, context, inline_lambda);
+                context.path.0.pop();
 
-                with(None, self, delay_arg, scope, context);
+                self.visit_with(None, delay_arg, scope, with, context);
             }
             Term::Lambda {
                 parameter_name,
@@ -973,7 +1001,7 @@ impl Term<Name> {
                     // Pass in either one or zero args.
                     // For lambda we run the function with first then recurse on the body or replaced term
 
-                    with(None, self, args, scope, context);
+                    self.visit_with(None, args, scope, with, context);
 
                     match self {
                         Term::Lambda {
@@ -981,6 +1009,7 @@ impl Term<Name> {
                             body,
                         } if *parameter_name == p => {
                             let body = Rc::make_mut(body);
+                            context.path.0.push(0);
                             body.traverse_uplc_with_helper(
                                 scope,
                                 arg_stack,
@@ -989,6 +1018,7 @@ impl Term<Name> {
                                 context,
                                 inline_lambda,
                             );
+                            context.path.0.pop();
                         }
                         other => other.traverse_uplc_with_helper(
                             scope,
@@ -1002,6 +1032,7 @@ impl Term<Name> {
                 } else {
                     let body = Rc::make_mut(body);
 
+                    context.path.0.push(0);
                     body.traverse_uplc_with_helper(
                         scope,
                         arg_stack,
@@ -1010,13 +1041,15 @@ impl Term<Name> {
                         context,
                         inline_lambda,
                     );
+                    context.path.0.pop();
 
-                    with(None, self, args, scope, context);
+                    self.visit_with(None, args, scope, with, context);
                 }
             }
 
             Term::Case { constr, branches } => {
                 let constr = Rc::make_mut(constr);
+                context.path.0.push(0);
                 constr.traverse_uplc_with_helper(
                     scope,
                     vec![],
@@ -1025,10 +1058,12 @@ impl Term<Name> {
                     context,
                     inline_lambda,
                 );
+                context.path.0.pop();
 
                 if branches.len() == 1 {
                     // save a potentially big clone
                     // where currently all cases will be 1 branch
+                    context.path.0.push(1);
                     branches[0].traverse_uplc_with_helper(
                         scope,
                         arg_stack,
@@ -1037,8 +1072,10 @@ impl Term<Name> {
                         context,
                         inline_lambda,
                     );
+                    context.path.0.pop();
                 } else {
-                    for branch in branches {
+                    for (ix, branch) in branches.iter_mut().enumerate() {
+                        context.path.0.push(ix + 1);
                         branch.traverse_uplc_with_helper(
                             scope,
                             arg_stack.clone(),
@@ -1047,11 +1084,13 @@ impl Term<Name> {
                             context,
                             inline_lambda,
                         );
+                        context.path.0.pop();
                     }
                 }
             }
             Term::Constr { fields, .. } => {
-                for field in fields {
+                for (ix, field) in fields.iter_mut().enumerate() {
+                    context.path.0.push(ix);
                     field.traverse_uplc_with_helper(
                         scope,
                         vec![],
@@ -1060,6 +1099,7 @@ impl Term<Name> {
                         context,
                         inline_lambda,
                     );
+                    context.path.0.pop();
                 }
             }
 
@@ -1072,15 +1112,37 @@ impl Term<Name> {
                     }
                 }
                 // Pass in args up to function arity.
-                with(None, self, args, scope, context);
+                self.visit_with(None, args, scope, with, context);
             }
             term => {
-                with(None, term, vec![], scope, context);
+                term.visit_with(None, vec![], scope, with, context);
             }
         }
         context.node_count += 1;
     }
 
+    /// Call 'with' on a term, moving the entries of the source map located within it along
+    /// with the rewrite performed, if any.
+    fn visit_with(
+        &mut self,
+        id: Option<usize>,
+        args: Vec<Args>,
+        scope: &Scope,
+        with: &mut impl FnMut(Option<usize>, &mut Term<Name>, Vec<Args>, &Scope, &mut Context),
+        context: &mut Context,
+    ) {
+        if context.source_map.is_empty() {
+            with(id, self, args, scope, context);
+            return;
+        }
+
+        let before = self.clone();
+
+        with(id, self, args, scope, context);
+
+        context.source_map.relocate(&context.path, &before, self);
+    }
+
     fn substitute_var(&mut self, original: Rc<Name>, replace_with: &Term<Name>) {
         match self {
             Term::Var(name) if *name == original => {
@@ -1114,10 +1176,8 @@ impl Term<Name> {
             Term::Lambda {
                 parameter_name,
                 body,
-            } => {
-                if *parameter_name != original {
-                    Rc::make_mut(body).replace_identity_usage(original.clone());
-                }
+            } if *parameter_name != original => {
+                Rc::make_mut(body).replace_identity_usage(original.clone());
             }
             Term::Apply { function, argument } => {
                 let func = Rc::make_mut(function);
@@ -2053,16 +2113,14 @@ impl Term<Name> {
     ) -> bool {
         let mut changed = false;
         match self {
-            Term::Builtin(d @ DefaultFunction::SubtractInteger) => {
-                if arg_stack.len() == d.arity() {
-                    let Some(Args::Apply(apply_id, Term::Constant(_))) = arg_stack.last() else {
-                        return false;
-                    };
-                    changed = true;
-                    context.constants_to_flip.push(*apply_id);
+            Term::Builtin(d @ DefaultFunction::SubtractInteger) if arg_stack.len() == d.arity() => {
+                let Some(Args::Apply(apply_id, Term::Constant(_))) = arg_stack.last() else {
+                    return false;
+                };
+                changed = true;
+                context.constants_to_flip.push(*apply_id);
 
-                    *self = Term::Builtin(DefaultFunction::AddInteger);
-                }
+                *self = Term::Builtin(DefaultFunction::AddInteger);
             }
             Term::Constr { .. } => todo!(),
             Term::Case { .. } => todo!(),
@@ -2273,6 +2331,16 @@ impl Program<Name> {
         self,
         inline_lambda: bool,
         with: &mut impl FnMut(Option<usize>, &mut Term<Name>, Vec<Args>, &Scope, &mut Context),
+    ) -> (Self, Context) {
+        self.traverse_uplc_tracking(inline_lambda, &mut SourceMap::new(), with)
+    }
+
+    /// Like 'traverse_uplc_with', but keeping the given source map up to date with the rewrites.
+    fn traverse_uplc_tracking(
+        self,
+        inline_lambda: bool,
+        source_map: &mut SourceMap,
+        with: &mut impl FnMut(Option<usize>, &mut Term<Name>, Vec<Args>, &Scope, &mut Context),
     ) -> (Self, Context) {
         let mut term = self.term;
         let scope = Scope::new();
@@ -2288,6 +2356,8 @@ impl Program<Name> {
             blst_p2_list: vec![],
             write_bits_convert: false,
             node_count: 0,
+            source_map: std::mem::take(source_map),
+            path: TermPath::root(),
         };
 
         term.traverse_uplc_with_helper(
@@ -2298,6 +2368,9 @@ impl Program<Name> {
             &mut context,
             inline_lambda,
         );
+
+        *source_map = std::mem::take(&mut context.source_map);
+
         (
             Program {
                 version: self.version,
@@ -2308,36 +2381,35 @@ impl Program<Name> {
     }
     // This runs the optimizations that are only done a single time
     pub fn run_once_pass(self) -> Self {
+        self.run_once_pass_tracking(&mut SourceMap::new())
+    }
+
+    /// Like 'run_once_pass', but keeping the given source map up to date.
+    pub fn run_once_pass_tracking(self, source_map: &mut SourceMap) -> Self {
         // First pass is necessary to ensure fst_pair and snd_pair are inlined before
         // builtin_force_reducer is run
         let (program, context) = self
-            .traverse_uplc_with(false, &mut |id, term, _arg_stack, scope, context| {
-                term.inline_constr_ops(id, vec![], scope, context);
-            })
+            .traverse_uplc_tracking(
+                false,
+                source_map,
+                &mut |id, term, _arg_stack, scope, context| {
+                    term.inline_constr_ops(id, vec![], scope, context);
+                },
+            )
             .0
-            .traverse_uplc_with(false, &mut |id, term, arg_stack, scope, context| {
-                term.bls381_compressor(id, vec![], scope, context);
-                term.builtin_force_reducer(id, arg_stack, scope, context);
-                term.remove_inlined_ids(id, vec![], scope, context);
-            });
-
-        let mut term = program.term;
-
-        for (index, blst_p1) in context.blst_p1_list.into_iter().enumerate() {
-            let compressed = blst_p1.compress();
-
-            term = term
-                .lambda(format!("blst_p1_index_{index}"))
-                .apply(Term::bls12_381_g1_uncompress().apply(Term::byte_string(compressed)));
-        }
+            .traverse_uplc_tracking(
+                false,
+                source_map,
+                &mut |id, term, arg_stack, scope, context| {
+                    term.bls381_compressor(id, vec![], scope, context);
+                    term.builtin_force_reducer(id, arg_stack, scope, context);
+                    term.remove_inlined_ids(id, vec![], scope, context);
+                },
+            );
 
-        for (index, blst_p2) in context.blst_p2_list.into_iter().enumerate() {
-            let compressed = blst_p2.compress();
+        let before = program.term.clone();
 
-            term = term
-                .lambda(format!("blst_p2_index_{index}"))
-                .apply(Term::bls12_381_g2_uncompress().apply(Term::byte_string(compressed)));
-        }
+        let mut term = bind_curve_points(program.term, context.blst_p1_list, context.blst_p2_list);
 
         for default_func in context.builtins_map.keys().sorted().cloned() {
             term = term.lambda(default_func.wrapped_name());
@@ -2351,6 +2423,8 @@ impl Program<Name> {
             });
         }
 
+        source_map.relocate(&TermPath::root(), &before, &term);
+
         let mut program = Program {
             version: program.version,
             term,
@@ -2365,42 +2439,86 @@ impl Program<Name> {
         Program::<Name>::try_from(program).unwrap()
     }
 
-    pub fn multi_pass(self) -> (Self, Context) {
-        self.traverse_uplc_with(true, &mut |id, term, arg_stack, scope, context| {
-            let false = term.lambda_reducer(id, arg_stack.clone(), scope, context) else {
-                term.remove_inlined_ids(id, vec![], scope, context);
-                return;
-            };
+    /// Replace curve points, which can't be serialised, by variables bound to their compressed
+    /// form once uncompressed. Unlike the other passes, it's required of every program.
+    pub fn compress_curve_points_tracking(self, source_map: &mut SourceMap) -> Self {
+        let (program, context) = self.traverse_uplc_tracking(
+            false,
+            source_map,
+            &mut |id, term, _arg_stack, scope, context| {
+                term.bls381_compressor(id, vec![], scope, context);
+            },
+        );
 
-            let false = term.identity_reducer(id, arg_stack.clone(), scope, context) else {
-                term.remove_inlined_ids(id, vec![], scope, context);
-                return;
-            };
+        let before = program.term.clone();
 
-            let false = term.inline_reducer(id, arg_stack.clone(), scope, context) else {
-                term.remove_inlined_ids(id, vec![], scope, context);
-                return;
-            };
+        let term = bind_curve_points(program.term, context.blst_p1_list, context.blst_p2_list);
 
-            let false = term.force_delay_reducer(id, arg_stack.clone(), scope, context) else {
-                term.remove_inlined_ids(id, vec![], scope, context);
-                return;
-            };
+        source_map.relocate(&TermPath::root(), &before, &term);
 
-            let false = term.cast_data_reducer(id, arg_stack.clone(), scope, context) else {
-                term.remove_inlined_ids(id, vec![], scope, context);
-                return;
-            };
+        Program {
+            version: program.version,
+            term,
+        }
+    }
 
-            let false = term.builtin_eval_reducer(id, arg_stack.clone(), scope, context) else {
-                term.remove_inlined_ids(id, vec![], scope, context);
-                return;
-            };
+    pub fn multi_pass(self) -> (Self, Context) {
+        self.multi_pass_tracking(&mut SourceMap::new())
+    }
 
-            term.convert_arithmetic_ops(id, arg_stack, scope, context);
-            term.flip_constants(id, vec![], scope, context);
-            term.remove_inlined_ids(id, vec![], scope, context);
-        })
+    /// Like 'multi_pass', but keeping the given source map up to date.
+    pub fn multi_pass_tracking(self, source_map: &mut SourceMap) -> (Self, Context) {
+        self.multi_pass_with(&Reducer::iter().collect::<Vec<_>>(), source_map)
+    }
+
+    /// Like 'multi_pass_tracking', but only attempting the given rewrites, still in the order of
+    /// 'Reducer'.
+    pub fn multi_pass_with(
+        self,
+        reducers: &[Reducer],
+        source_map: &mut SourceMap,
+    ) -> (Self, Context) {
+        self.traverse_uplc_tracking(
+            true,
+            source_map,
+            &mut |id, term, arg_stack, scope, context| {
+                for reducer in Reducer::iter().filter(|reducer| reducers.contains(reducer)) {
+                    let reduced = match reducer {
+                        Reducer::LambdaReducer => {
+                            term.lambda_reducer(id, arg_stack.clone(), scope, context)
+                        }
+                        Reducer::IdentityReducer => {
+                            term.identity_reducer(id, arg_stack.clone(), scope, context)
+                        }
+                        Reducer::InlineReducer => {
+                            term.inline_reducer(id, arg_stack.clone(), scope, context)
+                        }
+                        Reducer::ForceDelayReducer => {
+                            term.force_delay_reducer(id, arg_stack.clone(), scope, context)
+                        }
+                        Reducer::CastDataReducer => {
+                            term.cast_data_reducer(id, arg_stack.clone(), scope, context)
+                        }
+                        Reducer::BuiltinEvalReducer => {
+                            term.builtin_eval_reducer(id, arg_stack.clone(), scope, context)
+                        }
+                        // The last rewrite, after which constants are flipped regardless.
+                        Reducer::ConvertArithmeticOps => {
+                            term.convert_arithmetic_ops(id, arg_stack.clone(), scope, context);
+                            false
+                        }
+                    };
+
+                    if reduced {
+                        term.remove_inlined_ids(id, vec![], scope, context);
+                        return;
+                    }
+                }
+
+                term.flip_constants(id, vec![], scope, context);
+                term.remove_inlined_ids(id, vec![], scope, context);
+            },
+        )
     }
 
     pub fn run_one_opt(
@@ -2423,27 +2541,65 @@ impl Program<Name> {
     }
 
     pub fn clean_up_no_inlines(self) -> Self {
-        self.traverse_uplc_with(true, &mut |id, term, _arg_stack, scope, context| {
-            term.remove_no_inlines(id, vec![], scope, context);
-        })
+        self.clean_up_no_inlines_tracking(&mut SourceMap::new())
+    }
+
+    /// Like 'clean_up_no_inlines', but keeping the given source map up to date.
+    pub fn clean_up_no_inlines_tracking(self, source_map: &mut SourceMap) -> Self {
+        self.traverse_uplc_tracking(
+            true,
+            source_map,
+            &mut |id, term, _arg_stack, scope, context| {
+                term.remove_no_inlines(id, vec![], scope, context);
+            },
+        )
         .0
     }
 
     pub fn afterwards(self) -> Self {
-        let (mut program, context) =
-            self.traverse_uplc_with(true, &mut |id, term, arg_stack, scope, context| {
+        self.afterwards_with(true)
+    }
+
+    /// Like 'afterwards', but only performing the rewrites generated programs rely on when
+    /// 'reduce' is false, i.e. converting the indices given to 'writeBits'.
+    pub fn afterwards_with(self, reduce: bool) -> Self {
+        self.afterwards_tracking(reduce, &mut SourceMap::new())
+    }
+
+    /// Like 'afterwards_with', but keeping the given source map up to date.
+    pub fn afterwards_tracking(self, reduce: bool, source_map: &mut SourceMap) -> Self {
+        let (mut program, context) = self.traverse_uplc_tracking(
+            true,
+            source_map,
+            &mut |id, term, arg_stack, scope, context| {
                 term.write_bits_convert_arg(id, arg_stack, scope, context);
-            });
+            },
+        );
 
-        program = program
-            .split_body_lambda_reducer()
-            .traverse_uplc_with(true, &mut |id, term, _arg_stack, scope, context| {
-                term.case_constr_apply_reducer(id, vec![], scope, context);
-            })
-            .0;
+        if reduce {
+            let before = program.term.clone();
+
+            program = program.split_body_lambda_reducer();
+
+            source_map.relocate(&TermPath::root(), &before, &program.term);
+
+            program = program
+                .traverse_uplc_tracking(
+                    true,
+                    source_map,
+                    &mut |id, term, _arg_stack, scope, context| {
+                        term.case_constr_apply_reducer(id, vec![], scope, context);
+                    },
+                )
+                .0;
+        }
 
         if context.write_bits_convert {
+            let before = program.term.clone();
+
             program.term = program.term.data_list_to_integer_list();
+
+            source_map.relocate(&TermPath::root(), &before, &program.term);
         }
 
         let mut interner = CodeGenInterner::new();
@@ -2457,6 +2613,11 @@ impl Program<Name> {
 
     // This one doesn't use the context since it's complicated and traverses the ast twice
     pub fn builtin_curry_reducer(self) -> Self {
+        self.builtin_curry_reducer_tracking(&mut SourceMap::new())
+    }
+
+    /// Like 'builtin_curry_reducer', but keeping the given source map up to date.
+    pub fn builtin_curry_reducer_tracking(self, source_map: &mut SourceMap) -> Self {
         let mut curried_terms = vec![];
         let mut id_mapped_curry_terms: IndexMap<CurriedName, (Scope, Term<Name>, usize)> =
             IndexMap::new();
@@ -2471,89 +2632,89 @@ impl Program<Name> {
         let (step_a, _) = self.traverse_uplc_with(
             false,
             &mut |_id, term, arg_stack, scope, _context| match term {
-                Term::Builtin(func) => {
-                    if func.can_curry_builtin() && arg_stack.len() == func.arity() {
-                        let arg_stack = arg_stack
-                            .into_iter()
-                            .map(|item| {
-                                let Args::Apply(arg_id, arg) = item else {
-                                    unreachable!()
-                                };
-                                (arg_id, arg)
-                            })
-                            .collect_vec();
-                        // In the case of order agnostic builtins we want to sort the args by constant first
-                        // This gives us the opportunity to curry constants that often pop up in the code
+                Term::Builtin(func)
+                    if func.can_curry_builtin() && arg_stack.len() == func.arity() =>
+                {
+                    let arg_stack = arg_stack
+                        .into_iter()
+                        .map(|item| {
+                            let Args::Apply(arg_id, arg) = item else {
+                                unreachable!()
+                            };
+                            (arg_id, arg)
+                        })
+                        .collect_vec();
+                    // In the case of order agnostic builtins we want to sort the args by constant first
+                    // This gives us the opportunity to curry constants that often pop up in the code
 
-                        let builtin_args = BuiltinArgs::args_from_arg_stack(arg_stack, *func);
+                    let builtin_args = BuiltinArgs::args_from_arg_stack(arg_stack, *func);
 
-                        // First we see if we have already curried this builtin before
-                        let mut id_vec = if let Some((index, _)) =
-                            curried_terms.iter_mut().find_position(
-                                |curried_term: &&mut CurriedBuiltin| curried_term.func == *func,
-                            ) {
-                            // We found it the builtin was curried before
-                            // So now we merge the new args into the existing curried builtin
-                            let curried_builtin = curried_terms.swap_remove(index);
+                    // First we see if we have already curried this builtin before
+                    let mut id_vec = if let Some((index, _)) =
+                        curried_terms.iter_mut().find_position(
+                            |curried_term: &&mut CurriedBuiltin| curried_term.func == *func,
+                        ) {
+                        // We found it the builtin was curried before
+                        // So now we merge the new args into the existing curried builtin
+                        let curried_builtin = curried_terms.swap_remove(index);
 
-                            let curried_builtin =
-                                curried_builtin.merge_node_by_path(builtin_args.clone());
+                        let curried_builtin =
+                            curried_builtin.merge_node_by_path(builtin_args.clone());
 
-                            flipped_terms
-                                .insert(scope.clone(), curried_builtin.is_flipped(&builtin_args));
+                        flipped_terms
+                            .insert(scope.clone(), curried_builtin.is_flipped(&builtin_args));
 
-                            let Some(id_vec) = curried_builtin.get_id_args(builtin_args) else {
-                                unreachable!();
-                            };
+                        let Some(id_vec) = curried_builtin.get_id_args(builtin_args) else {
+                            unreachable!();
+                        };
 
-                            curried_terms.push(curried_builtin);
+                        curried_terms.push(curried_builtin);
 
-                            id_vec
-                        } else {
-                            // Brand new builtin so we add it to the list
-                            let curried_builtin = builtin_args.clone().args_to_curried_args(*func);
+                        id_vec
+                    } else {
+                        // Brand new builtin so we add it to the list
+                        let curried_builtin = builtin_args.clone().args_to_curried_args(*func);
 
-                            let Some(id_vec) = curried_builtin.get_id_args(builtin_args) else {
-                                unreachable!();
-                            };
+                        let Some(id_vec) = curried_builtin.get_id_args(builtin_args) else {
+                            unreachable!();
+                        };
 
-                            curried_terms.push(curried_builtin);
+                        curried_terms.push(curried_builtin);
 
-                            id_vec
-                        };
+                        id_vec
+                    };
 
-                        while let Some(node) = id_vec.pop() {
-                            let mut id_only_vec =
-                                id_vec.iter().map(|item| item.curried_id).collect_vec();
+                    while let Some(node) = id_vec.pop() {
+                        let mut id_only_vec =
+                            id_vec.iter().map(|item| item.curried_id).collect_vec();
 
-                            id_only_vec.push(node.curried_id);
+                        id_only_vec.push(node.curried_id);
 
-                            let curry_name = CurriedName {
-                                func_name: func.aiken_name(),
-                                id_vec: id_only_vec,
-                            };
+                        let curry_name = CurriedName {
+                            func_name: func.aiken_name(),
+                            id_vec: id_only_vec,
+                        };
 
-                            if let Some((map_scope, _, occurrences)) =
-                                id_mapped_curry_terms.get_mut(&curry_name)
-                            {
-                                *map_scope = map_scope.common_ancestor(scope);
-                                *occurrences += 1;
-                            } else if id_vec.is_empty() {
-                                id_mapped_curry_terms.insert(
-                                    curry_name,
-                                    (scope.clone(), Term::Builtin(*func).apply(node.term), 1),
-                                );
-                            } else {
-                                let var_name = id_vec_function_to_var(
-                                    &func.aiken_name(),
-                                    &id_vec.iter().map(|item| item.curried_id).collect_vec(),
-                                );
-
-                                id_mapped_curry_terms.insert(
-                                    curry_name,
-                                    (scope.clone(), Term::var(var_name).apply(node.term), 1),
-                                );
-                            }
+                        if let Some((map_scope, _, occurrences)) =
+                            id_mapped_curry_terms.get_mut(&curry_name)
+                        {
+                            *map_scope = map_scope.common_ancestor(scope);
+                            *occurrences += 1;
+                        } else if id_vec.is_empty() {
+                            id_mapped_curry_terms.insert(
+                                curry_name,
+                                (scope.clone(), Term::Builtin(*func).apply(node.term), 1),
+                            );
+                        } else {
+                            let var_name = id_vec_function_to_var(
+                                &func.aiken_name(),
+                                &id_vec.iter().map(|item| item.curried_id).collect_vec(),
+                            );
+
+                            id_mapped_curry_terms.insert(
+                                curry_name,
+                                (scope.clone(), Term::var(var_name).apply(node.term), 1),
+                            );
                         }
                     }
                 }
@@ -2585,8 +2746,9 @@ impl Program<Name> {
                 }
             });
 
-        let (mut step_b, _) = step_a.traverse_uplc_with(
+        let (mut step_b, _) = step_a.traverse_uplc_tracking(
             false,
+            source_map,
             &mut |id, term, arg_stack, scope, _context| match term {
                 Term::Builtin(func) => {
                     if func.can_curry_builtin() && arg_stack.len() == func.arity() {
@@ -2706,14 +2868,40 @@ fn id_vec_function_to_var(func_name: &str, id_vec: &[usize]) -> String {
     )
 }
 
+/// Bind the variables standing for the given curve points, as named by 'bls381_compressor'.
+fn bind_curve_points(
+    mut term: Term<Name>,
+    blst_p1_list: Vec<blst_p1>,
+    blst_p2_list: Vec<blst_p2>,
+) -> Term<Name> {
+    for (index, blst_p1) in blst_p1_list.into_iter().enumerate() {
+        let compressed = blst_p1.compress();
+
+        term = term
+            .lambda(format!("blst_p1_index_{index}"))
+            .apply(Term::bls12_381_g1_uncompress().apply(Term::byte_string(compressed)));
+    }
+
+    for (index, blst_p2) in blst_p2_list.into_iter().enumerate() {
+        let compressed = blst_p2.compress();
+
+        term = term
+            .lambda(format!("blst_p2_index_{index}"))
+            .apply(Term::bls12_381_g2_uncompress().apply(Term::byte_string(compressed)));
+    }
+
+    term
+}
+
 #[cfg(test)]
 mod tests {
-    use super::NO_INLINE;
+    use super::{NO_INLINE, Reducer};
     use crate::{
         ast::{Constant, Data, Name, NamedDeBruijn, Program, Term},
         builder::{CONSTR_FIELDS_EXPOSER, CONSTR_INDEX_EXPOSER},
         builtins::DefaultFunction,
         optimize::interner::CodeGenInterner,
+        source_map::SourceMap,
     };
     use pallas_primitives::conway::{BigInt, PlutusData};
     use pretty_assertions::assert_eq;
@@ -3876,4 +4064,31 @@ mod tests {
             })
         });
     }
+
+    #[test]
+    fn multi_pass_with_some_reducers() {
+        let program: Program<Name> = Program {
+            version: (1, 1, 0),
+            term: Term::subtract_integer()
+                .apply(Term::var("foo"))
+                .apply(Term::integer(2.into()))
+                .lambda("foo")
+                .apply(Term::integer(5.into())),
+        };
+
+        // The lambda is left alone, but the constant is flipped along with the subtraction.
+        let expected = Program {
+            version: (1, 1, 0),
+            term: Term::add_integer()
+                .apply(Term::var("foo"))
+                .apply(Term::integer((-2).into()))
+                .lambda("foo")
+                .apply(Term::integer(5.into())),
+        };
+
+        compare_optimization(expected, program, |p| {
+            p.multi_pass_with(&[Reducer::ConvertArithmeticOps], &mut SourceMap::new())
+                .0
+        });
+    }
 }
@@ -0,0 +1,726 @@
+use crate::ast::{Name, Term, TermPath};
+use serde::{
+    de::{self, Deserialize, Deserializer},
+    ser::{Serialize, SerializeMap, Serializer},
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque, btree_map},
+    hash::{DefaultHasher, Hash, Hasher},
+    mem,
+    rc::Rc,
+};
+
+/// A location in the source code a term was generated from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct SourceLocation {
+    pub module: String,
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    /// The name of the definition found at this location, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Relates sub-terms of a program, identified by their path from the root, to
+/// the source code they were generated from.
+///
+/// Lookups return the innermost entry enclosing a path, so a map needs not be
+/// exhaustive: every term is attributed to the closest located ancestor.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    entries: BTreeMap<TermPath, SourceLocation>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Locate the bodies of definitions bound in a program.
+    ///
+    /// Definitions are recognized by the name of the lambda they're bound to,
+    /// that is, terms of the form `[(lam name rest) body]`, possibly nested
+    /// or batched by the optimizer into `(case (constr 0 body ...) (lam name ...))`. Because binder
+    /// names are preserved by the optimizer, building the map from the final
+    /// program keeps it valid regardless of the rewrites performed in-between.
+    pub fn from_bindings(
+        term: &Term<Name>,
+        root: Option<SourceLocation>,
+        bindings: &HashMap<String, SourceLocation>,
+    ) -> Self {
+        let mut source_map = SourceMap::new();
+
+        if let Some(root) = root {
+            source_map.insert(TermPath::root(), root);
+        }
+
+        let mut stack = vec![(term, TermPath::root())];
+
+        while let Some((term, path)) = stack.pop() {
+            for (body, name) in bound_terms(term, &path) {
+                if let Some(location) = bindings.get(name) {
+                    source_map
+                        .entries
+                        .entry(body)
+                        .or_insert_with(|| location.clone());
+                }
+            }
+
+            for (ix, child) in term.children().into_iter().enumerate() {
+                stack.push((child, path.child(ix)));
+            }
+        }
+
+        source_map
+    }
+
+    /// Locate the terms of a program recorded while it was generated. Terms already located,
+    /// e.g. as the bodies of definitions, keep their location.
+    pub fn locate_terms(&mut self, term: &Term<Name>, located: &LocatedTerms) {
+        let mut stack = vec![(term, TermPath::root())];
+
+        while let Some((term, path)) = stack.pop() {
+            if let Some(location) = identity(term).and_then(|id| located.locations.get(&id)) {
+                self.entries
+                    .entry(path.clone())
+                    .or_insert_with(|| location.clone());
+            }
+
+            for (ix, child) in term.children().into_iter().enumerate() {
+                stack.push((child, path.child(ix)));
+            }
+        }
+    }
+
+    /// Add the entries of another map of the same program, for terms not located already.
+    pub fn merge(&mut self, other: SourceMap) {
+        for (path, location) in other.entries {
+            self.entries.entry(path).or_insert(location);
+        }
+    }
+
+    /// Keep the map up to date with the rewrite of the term at the given path, from 'before'
+    /// into 'after'.
+    ///
+    /// Terms located within the rewritten one follow it: when found unchanged in the rewritten
+    /// term, e.g. once inlined or unwrapped, they're located there. Otherwise, they keep their
+    /// position relative to the part of 'before' that 'after' is shaped like, if any, as when
+    /// a variable gets substituted. Terms that can't be followed are attributed to the whole
+    /// rewritten term, unless another term located there already.
+    pub fn relocate(&mut self, at: &TermPath, before: &Term<Name>, after: &Term<Name>) {
+        let moved = self
+            .entries
+            .range(at..)
+            .take_while(|(path, _)| path.starts_with(at))
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+
+        if moved.is_empty() || same(before, after) {
+            return;
+        }
+
+        let prefixes = moved
+            .iter()
+            .flat_map(|path| {
+                (at.0.len()..=path.0.len()).map(|len| path.0[at.0.len()..len].to_vec())
+            })
+            .collect::<HashSet<_>>();
+
+        let mut digests_before = HashMap::new();
+
+        digest(before, &mut vec![], &mut |path, digest| {
+            if prefixes.contains(path) {
+                digests_before.insert(path.to_vec(), digest);
+            }
+        });
+
+        let mut found_after = HashMap::new();
+
+        digest(after, &mut vec![], &mut |path, digest| {
+            found_after.entry(digest).or_insert_with(|| path.to_vec());
+        });
+
+        let spine = spine(before, after);
+
+        let mut relocated = moved
+            .into_iter()
+            .map(|path| {
+                let location = self
+                    .entries
+                    .remove(&path)
+                    .expect("relocated path is in the map");
+
+                let relative = &path.0[at.0.len()..];
+
+                let unchanged = (0..=relative.len()).find_map(|len| {
+                    let found = found_after.get(digests_before.get(&relative[..len])?)?;
+                    Some([found.as_slice(), &relative[len..]].concat())
+                });
+
+                let (target, precise) = match (unchanged, &spine) {
+                    (Some(target), _) => (target, true),
+                    (None, Some(spine)) if relative.starts_with(spine) => {
+                        let mut target = vec![];
+                        let mut term = after;
+
+                        for ix in relative[spine.len()..].iter() {
+                            match term.children().get(*ix) {
+                                Some(child) => {
+                                    target.push(*ix);
+                                    term = child;
+                                }
+                                None => break,
+                            }
+                        }
+
+                        let precise = target.len() == relative.len() - spine.len();
+
+                        (target, precise)
+                    }
+                    (None, _) => (vec![], false),
+                };
+
+                let mut path = at.0.clone();
+                path.extend(target);
+
+                (TermPath(path), location, precise)
+            })
+            .collect::<Vec<_>>();
+
+        // Terms followed precisely take precedence, and so do definitions over expressions.
+        relocated.sort_by_key(|(_, location, precise)| (!precise, location.name.is_none()));
+
+        for (path, location, _) in relocated {
+            if let btree_map::Entry::Vacant(entry) = self.entries.entry(path) {
+                entry.insert(location);
+            }
+        }
+    }
+
+    pub fn insert(&mut self, path: TermPath, location: SourceLocation) {
+        self.entries.insert(path, location);
+    }
+
+    pub fn get(&self, path: &TermPath) -> Option<&SourceLocation> {
+        self.entries.get(path)
+    }
+
+    /// Find the innermost located term enclosing the given path.
+    pub fn lookup(&self, path: &TermPath) -> Option<(&TermPath, &SourceLocation)> {
+        self.entries
+            .range(..=path)
+            .rev()
+            .find(|(candidate, _)| path.starts_with(candidate))
+    }
+
+    /// Adjust the map to a program that has been applied to an argument; the
+    /// original term now sits in function position of the application.
+    pub fn applied(self) -> Self {
+        SourceMap {
+            entries: self
+                .entries
+                .into_iter()
+                .map(|(TermPath(segments), location)| {
+                    let mut path = vec![0];
+                    path.extend(segments);
+                    (TermPath(path), location)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&TermPath, &SourceLocation)> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Paths of the terms bound to a name by the given term, if any.
+pub(crate) fn bound_terms<'a>(
+    term: &'a Term<Name>,
+    path: &TermPath,
+) -> Vec<(TermPath, &'a String)> {
+    let (head, arguments) = match term {
+        Term::Apply { .. } => {
+            let mut arguments = vec![];
+            let mut head = term;
+            let mut head_path = path.clone();
+            while let Term::Apply { function, .. } = head {
+                arguments.push(head_path.child(1));
+                head = function.as_ref();
+                head_path = head_path.child(0);
+            }
+            arguments.reverse();
+            (head, arguments)
+        }
+        Term::Case { constr, branches } if branches.len() == 1 => match constr.as_ref() {
+            Term::Constr { tag: 0, fields } => (
+                &branches[0],
+                (0..fields.len())
+                    .map(|ix| path.child(0).child(ix))
+                    .collect(),
+            ),
+            _ => return vec![],
+        },
+        _ => return vec![],
+    };
+
+    let mut bound = vec![];
+    let mut body = head;
+    for argument in arguments {
+        match body {
+            Term::Lambda {
+                parameter_name,
+                body: next,
+            } => {
+                bound.push((argument, &parameter_name.text));
+                body = next.as_ref();
+            }
+            _ => break,
+        }
+    }
+    bound
+}
+
+/// Terms located while a program is being generated, to be found back once part of the program.
+///
+/// Terms are recognized by the sub-terms (and names) they share with the program. So recorded
+/// terms are kept alive alongside, lest the addresses of their sub-terms be reused.
+#[derive(Debug, Clone, Default)]
+pub struct LocatedTerms {
+    locations: HashMap<Identity, SourceLocation>,
+    terms: Vec<Term<Name>>,
+}
+
+impl LocatedTerms {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the location of a term, unless it can't be recognized (i.e. it's a leaf or a
+    /// constr) or is already located.
+    pub fn insert(&mut self, term: &Term<Name>, location: SourceLocation) {
+        if let Some(id) = identity(term) {
+            if let std::collections::hash_map::Entry::Vacant(entry) = self.locations.entry(id) {
+                entry.insert(location);
+                self.terms.push(term.clone());
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+}
+
+/// The kind of a term along with the addresses of its direct sub-terms and names.
+type Identity = (u8, Vec<usize>);
+
+fn identity(term: &Term<Name>) -> Option<Identity> {
+    fn address<T>(rc: &Rc<T>) -> usize {
+        Rc::as_ptr(rc) as usize
+    }
+
+    match term {
+        Term::Delay(body) => Some((1, vec![address(body)])),
+        Term::Lambda {
+            parameter_name,
+            body,
+        } => Some((2, vec![address(parameter_name), address(body)])),
+        Term::Apply { function, argument } => Some((3, vec![address(function), address(argument)])),
+        Term::Force(body) => Some((5, vec![address(body)])),
+        Term::Case { constr, .. } => Some((9, vec![address(constr)])),
+        Term::Var(_) | Term::Constant(_) | Term::Error | Term::Builtin(_) | Term::Constr { .. } => {
+            None
+        }
+    }
+}
+
+/// Whether two terms are the same, without looking into the sub-terms they share.
+fn same(left: &Term<Name>, right: &Term<Name>) -> bool {
+    fn same_rc(left: &Rc<Term<Name>>, right: &Rc<Term<Name>>) -> bool {
+        Rc::ptr_eq(left, right) || same(left, right)
+    }
+
+    match (left, right) {
+        (Term::Delay(left), Term::Delay(right)) | (Term::Force(left), Term::Force(right)) => {
+            same_rc(left, right)
+        }
+        (
+            Term::Lambda {
+                parameter_name: left_name,
+                body: left,
+            },
+            Term::Lambda {
+                parameter_name: right_name,
+                body: right,
+            },
+        ) => left_name == right_name && same_rc(left, right),
+        (
+            Term::Apply {
+                function: left_function,
+                argument: left_argument,
+            },
+            Term::Apply {
+                function: right_function,
+                argument: right_argument,
+            },
+        ) => same_rc(left_function, right_function) && same_rc(left_argument, right_argument),
+        (
+            Term::Case {
+                constr: left_constr,
+                branches: left,
+            },
+            Term::Case {
+                constr: right_constr,
+                branches: right,
+            },
+        ) => {
+            same_rc(left_constr, right_constr)
+                && left.len() == right.len()
+                && left
+                    .iter()
+                    .zip(right)
+                    .all(|(left, right)| same(left, right))
+        }
+        (
+            Term::Constr {
+                tag: left_tag,
+                fields: left,
+            },
+            Term::Constr {
+                tag: right_tag,
+                fields: right,
+            },
+        ) => {
+            left_tag == right_tag
+                && left.len() == right.len()
+                && left
+                    .iter()
+                    .zip(right)
+                    .all(|(left, right)| same(left, right))
+        }
+        _ => left == right,
+    }
+}
+
+/// Digest every sub-term of a term, bottom-up, along with its path. Variables, names and
+/// constants are digested by address, so that only copies of a same term share digests.
+fn digest(term: &Term<Name>, path: &mut Vec<usize>, with: &mut impl FnMut(&[usize], u64)) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    match term {
+        Term::Var(name) => (0_u8, Rc::as_ptr(name) as usize).hash(&mut hasher),
+        Term::Delay(_) => 1_u8.hash(&mut hasher),
+        Term::Lambda { parameter_name, .. } => {
+            (2_u8, Rc::as_ptr(parameter_name) as usize).hash(&mut hasher)
+        }
+        Term::Apply { .. } => 3_u8.hash(&mut hasher),
+        Term::Constant(constant) => (4_u8, Rc::as_ptr(constant) as usize).hash(&mut hasher),
+        Term::Force(_) => 5_u8.hash(&mut hasher),
+        Term::Error => 6_u8.hash(&mut hasher),
+        Term::Builtin(fun) => (7_u8, fun).hash(&mut hasher),
+        Term::Constr { tag, .. } => (8_u8, tag).hash(&mut hasher),
+        Term::Case { branches, .. } => (9_u8, branches.len()).hash(&mut hasher),
+    }
+
+    for (ix, child) in term.children().into_iter().enumerate() {
+        path.push(ix);
+        digest(child, path, with).hash(&mut hasher);
+        path.pop();
+    }
+
+    let digest = hasher.finish();
+
+    with(path, digest);
+
+    digest
+}
+
+/// The path of the outermost sub-term of 'before' that 'after' is shaped like, up to variables
+/// having been substituted, if any.
+fn spine(before: &Term<Name>, after: &Term<Name>) -> Option<Vec<usize>> {
+    const MAX_DEPTH: usize = 3;
+
+    fn shaped_like(before: &Term<Name>, after: &Term<Name>) -> bool {
+        if let Term::Var(_) = before {
+            return true;
+        }
+
+        let (before_children, after_children) = (before.children(), after.children());
+
+        mem::discriminant(before) == mem::discriminant(after)
+            && before_children.len() == after_children.len()
+            && before_children
+                .into_iter()
+                .zip(after_children)
+                .all(|(before, after)| shaped_like(before, after))
+    }
+
+    let mut queue = VecDeque::from([(before, vec![])]);
+
+    while let Some((term, path)) = queue.pop_front() {
+        if shaped_like(term, after) {
+            return Some(path);
+        }
+
+        if path.len() < MAX_DEPTH {
+            for (ix, child) in term.children().into_iter().enumerate() {
+                let mut path = path.clone();
+                path.push(ix);
+                queue.push_back((child, path));
+            }
+        }
+    }
+
+    None
+}
+
+impl Serialize for SourceMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for (path, location) in self.entries.iter() {
+            map.serialize_entry(&path.to_string(), location)?;
+        }
+        map.end()
+    }
+}
+
+impl<'a> Deserialize<'a> for SourceMap {
+    fn deserialize<D: Deserializer<'a>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries = BTreeMap::<String, SourceLocation>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(path, location)| {
+                path.parse::<TermPath>()
+                    .map(|path| (path, location))
+                    .map_err(de::Error::custom)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(SourceMap { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SourceLocation, SourceMap};
+    use crate::{
+        ast::{Name, Program, Term, TermPath},
+        optimize::{self, OptLevel},
+    };
+    use std::collections::HashMap;
+
+    fn location(module: &str, start: usize) -> SourceLocation {
+        SourceLocation {
+            module: module.to_string(),
+            start,
+            end: start + 10,
+            line: 1,
+            name: None,
+        }
+    }
+
+    #[test]
+    fn from_bindings_locates_bodies() {
+        // [(lam foo [foo (con integer 1)]) (lam x x)]
+        let term: Term<Name> = Term::var("foo")
+            .apply(Term::integer(1.into()))
+            .lambda("foo")
+            .apply(Term::var("x").lambda("x"));
+
+        let bindings = HashMap::from([("foo".to_string(), location("bar", 42))]);
+
+        let source_map = SourceMap::from_bindings(&term, Some(location("bar", 0)), &bindings);
+
+        assert_eq!(source_map.len(), 2);
+
+        assert_eq!(
+            source_map.get(&TermPath(vec![1])),
+            Some(&location("bar", 42))
+        );
+
+        assert_eq!(
+            source_map.lookup(&TermPath(vec![1, 0])),
+            Some((&TermPath(vec![1]), &location("bar", 42)))
+        );
+
+        assert_eq!(
+            source_map.lookup(&TermPath(vec![0, 0])),
+            Some((&TermPath::root(), &location("bar", 0)))
+        );
+    }
+
+    #[test]
+    fn from_bindings_locates_batched_bodies() {
+        // (case (constr 0 (con integer 1) (lam x x)) (lam bar (lam foo [foo bar])))
+        let term: Term<Name> = Term::Case {
+            constr: Term::Constr {
+                tag: 0,
+                fields: vec![Term::integer(1.into()), Term::var("x").lambda("x")],
+            }
+            .into(),
+            branches: vec![
+                Term::var("foo")
+                    .apply(Term::var("bar"))
+                    .lambda("foo")
+                    .lambda("bar"),
+            ],
+        };
+
+        let bindings = HashMap::from([("foo".to_string(), location("bar", 42))]);
+
+        let source_map = SourceMap::from_bindings(&term, None, &bindings);
+
+        assert_eq!(source_map.len(), 1);
+
+        assert_eq!(
+            source_map.get(&TermPath(vec![0, 1])),
+            Some(&location("bar", 42))
+        );
+    }
+
+    #[test]
+    fn from_bindings_locates_nested_bodies() {
+        // [[(lam bar (lam foo [foo bar])) (con integer 1)] (lam x x)]
+        let term: Term<Name> = Term::var("foo")
+            .apply(Term::var("bar"))
+            .lambda("foo")
+            .lambda("bar")
+            .apply(Term::integer(1.into()))
+            .apply(Term::var("x").lambda("x"));
+
+        let bindings = HashMap::from([("foo".to_string(), location("bar", 42))]);
+
+        let source_map = SourceMap::from_bindings(&term, None, &bindings);
+
+        assert_eq!(source_map.len(), 1);
+
+        assert_eq!(
+            source_map.get(&TermPath(vec![1])),
+            Some(&location("bar", 42))
+        );
+    }
+
+    #[test]
+    fn relocate_unwrapped_term() {
+        // (force (delay [f (con integer 1)])) -> [f (con integer 1)]
+        let body: Term<Name> = Term::var("f").apply(Term::integer(1.into()));
+
+        let before = body.clone().delay().force();
+
+        let mut source_map = SourceMap::new();
+        source_map.insert(TermPath(vec![0]), location("foo", 0));
+        source_map.insert(TermPath(vec![0, 0]), location("foo", 14));
+        source_map.insert(TermPath(vec![0, 0, 1]), location("foo", 28));
+
+        source_map.relocate(&TermPath::root(), &before, &body);
+
+        assert_eq!(source_map.len(), 2);
+        assert_eq!(
+            source_map.get(&TermPath::root()),
+            Some(&location("foo", 14))
+        );
+        assert_eq!(
+            source_map.get(&TermPath(vec![1])),
+            Some(&location("foo", 28))
+        );
+    }
+
+    #[test]
+    fn relocate_inlined_argument() {
+        // [(lam x [f x]) (force g)] -> [f (force g)]
+        let f: Term<Name> = Term::var("f");
+        let argument: Term<Name> = Term::var("g").force();
+
+        let before = f
+            .clone()
+            .apply(Term::var("x"))
+            .lambda("x")
+            .apply(argument.clone());
+
+        let after = f.apply(argument);
+
+        // The rewritten term sits at 1.0, next to another located term left untouched.
+        let mut source_map = SourceMap::new();
+        source_map.insert(TermPath(vec![0]), location("bar", 0));
+        source_map.insert(TermPath(vec![1, 0, 0, 0]), location("foo", 0));
+        source_map.insert(TermPath(vec![1, 0, 1]), location("foo", 14));
+
+        source_map.relocate(&TermPath(vec![1, 0]), &before, &after);
+
+        assert_eq!(source_map.len(), 3);
+        assert_eq!(
+            source_map.get(&TermPath(vec![0])),
+            Some(&location("bar", 0))
+        );
+        assert_eq!(
+            source_map.get(&TermPath(vec![1, 0])),
+            Some(&location("foo", 0))
+        );
+        assert_eq!(
+            source_map.get(&TermPath(vec![1, 0, 1])),
+            Some(&location("foo", 14))
+        );
+    }
+
+    #[test]
+    fn relocate_through_optimizations() {
+        // [(lam x [(builtin sha2_256) x]) (con bytestring #00)]
+        let body: Term<Name> = Term::sha2_256().apply(Term::var("x"));
+
+        let program = Program {
+            version: (1, 1, 0),
+            term: body.lambda("x").apply(Term::byte_string(vec![0])),
+        };
+
+        let mut source_map = SourceMap::new();
+        source_map.insert(TermPath::root(), location("foo", 0));
+        source_map.insert(TermPath(vec![0, 0]), location("foo", 14));
+
+        let program =
+            optimize::aiken_optimize_and_intern_tracking(program, OptLevel::Size, &mut source_map);
+
+        assert_eq!(
+            program.term,
+            Term::sha2_256().apply(Term::byte_string(vec![0]))
+        );
+
+        assert_eq!(
+            source_map.get(&TermPath::root()),
+            Some(&location("foo", 14))
+        );
+    }
+
+    #[test]
+    fn serialization_roundtrip() {
+        let mut source_map = SourceMap::new();
+        source_map.insert(TermPath::root(), location("foo", 0));
+        source_map.insert(TermPath(vec![0, 1, 1]), location("foo", 14));
+
+        let json = serde_json::to_value(&source_map).unwrap();
+
+        assert!(json.get("0.1.1").is_some());
+
+        assert_eq!(
+            serde_json::from_value::<SourceMap>(json).unwrap(),
+            source_map
+        );
+
+        assert_eq!(
+            source_map
+                .clone()
+                .applied()
+                .get(&TermPath(vec![0, 0, 1, 1])),
+            source_map.get(&TermPath(vec![0, 1, 1]))
+        );
+    }
+}
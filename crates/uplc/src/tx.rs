@@ -4,28 +4,42 @@ use crate::{
     PlutusData,
     ast::{DeBruijn, Program},
     machine::{cost_model::ExBudget, eval_result::EvalResult},
+    source_map::{SourceLocation, SourceMap},
 };
 use error::Error;
 use pallas_addresses::ScriptHash;
+use pallas_codec::{
+    minicbor,
+    utils::{MaybeIndefArray, NonEmptyKeyValuePairs},
+};
 use pallas_primitives::{
     Fragment,
     conway::{
-        CostModels, ExUnits, MintedTx, Redeemer, Redeemers, RedeemersKey, TransactionInput,
-        TransactionOutput,
+        CostModels, ExUnits, MintedTx, Redeemer, RedeemerTag, Redeemers, RedeemersKey,
+        RedeemersValue, TransactionInput, TransactionOutput,
     },
 };
 use pallas_traverse::{Era, MultiEraTx};
-pub use phase_one::{eval_phase_one, redeemer_tag_to_string};
+pub use phase_one::{
+    eval_phase_one, eval_phase_one_with_params, redeemer_tag_to_string, script_data_hash,
+};
+use phase_one::{hash_script_data, script_languages};
+pub use protocol_parameters::ProtocolParameters;
+pub use scenario::{SCENARIO_SLOT_CONFIG, build_scenario, eval_scenario};
 use script_context::PlutusScript;
 pub use script_context::{DataLookupTable, ResolvedInput, SlotConfig};
+pub use utxo::UtxoSnapshot;
 
 pub mod error;
 pub mod eval;
 mod phase_one;
+pub mod protocol_parameters;
+mod scenario;
 pub mod script_context;
 #[cfg(test)]
 mod tests;
 pub mod to_plutus_data;
+pub mod utxo;
 
 /// Evaluate the scripts in a transaction using
 /// the UPLC Cek Machine. This function collects
@@ -119,6 +133,51 @@ pub fn eval_phase_two_with_override(
     }
 }
 
+/// Find where in the source code the script of a redeemer failing in [`eval_phase_two_with_override`]
+/// fails, according to the source maps of the scripts given by hash. The redeemer is given by its
+/// tag and index, as reported by [`Error::RedeemerError`].
+pub fn locate_failure(
+    tx: &MintedTx,
+    utxos: &[ResolvedInput],
+    slot_config: &SlotConfig,
+    override_scripts: HashMap<ScriptHash, PlutusScript>,
+    source_maps: &HashMap<ScriptHash, SourceMap>,
+    tag: &str,
+    index: u32,
+) -> Result<Option<SourceLocation>, Error> {
+    let Some(redeemers) = tx.transaction_witness_set.redeemer.as_ref() else {
+        return Ok(None);
+    };
+
+    let Some((key, data, ex_units)) = iter_redeemers(redeemers)
+        .find(|(key, _, _)| redeemer_tag_to_string(&key.tag) == tag && key.index == index)
+    else {
+        return Ok(None);
+    };
+
+    let mut lookup_table = DataLookupTable::from_transaction(tx, utxos);
+
+    override_scripts
+        .into_iter()
+        .for_each(|(hash, script)| lookup_table.override_script(hash, script));
+
+    let redeemer = Redeemer {
+        tag: key.tag,
+        index: key.index,
+        data: data.clone(),
+        ex_units,
+    };
+
+    eval::locate_failure(
+        tx,
+        utxos,
+        slot_config,
+        &redeemer,
+        &lookup_table,
+        source_maps,
+    )
+}
+
 /// This function is the same as [`eval_phase_two`]
 /// but the inputs are raw bytes.
 /// initial_budget expects (cpu, mem).
@@ -187,6 +246,167 @@ transaction in a format suitable for the Conway era."#
     }
 }
 
+/// Rewrite the execution units of a transaction's redeemers with the ones measured by
+/// [`eval_phase_two`], increased by a safety margin (in percent), and recompute its script
+/// data hash with the given cost models. Returns the CBOR of the updated transaction, which
+/// is otherwise serialized as it was.
+///
+/// Redeemers without a measure are left untouched, and so is the fee. Since the transaction
+/// body changes, it must be (re-)signed afterwards.
+pub fn apply_ex_units(
+    tx: &MintedTx,
+    utxos: &[ResolvedInput],
+    evaluated: &[Redeemer],
+    margin: u64,
+    cost_mdls: &CostModels,
+) -> Result<Vec<u8>, Error> {
+    let redeemers = match tx.transaction_witness_set.redeemer.as_deref() {
+        Some(redeemers) => redeemers.clone(),
+        None => return Ok(tx.encode_fragment()?),
+    };
+
+    let fit = |tag: RedeemerTag, index: u32, ex_units: ExUnits| {
+        evaluated
+            .iter()
+            .find(|redeemer| redeemer.tag == tag && redeemer.index == index)
+            .map(|redeemer| ExUnits {
+                mem: (redeemer.ex_units.mem * (100 + margin)).div_ceil(100),
+                steps: (redeemer.ex_units.steps * (100 + margin)).div_ceil(100),
+            })
+            .unwrap_or(ex_units)
+    };
+
+    let redeemers = match redeemers {
+        Redeemers::List(redeemers) => {
+            let fitted = |redeemers: Vec<Redeemer>| {
+                redeemers
+                    .into_iter()
+                    .map(|redeemer| Redeemer {
+                        ex_units: fit(redeemer.tag, redeemer.index, redeemer.ex_units),
+                        ..redeemer
+                    })
+                    .collect()
+            };
+
+            Redeemers::List(match redeemers {
+                MaybeIndefArray::Def(redeemers) => MaybeIndefArray::Def(fitted(redeemers)),
+                MaybeIndefArray::Indef(redeemers) => MaybeIndefArray::Indef(fitted(redeemers)),
+            })
+        }
+        Redeemers::Map(redeemers) => {
+            let fitted = |redeemers: Vec<(RedeemersKey, RedeemersValue)>| {
+                redeemers
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let ex_units = fit(key.tag, key.index, value.ex_units);
+                        (key, RedeemersValue { ex_units, ..value })
+                    })
+                    .collect()
+            };
+
+            Redeemers::Map(match redeemers {
+                NonEmptyKeyValuePairs::Def(redeemers) => {
+                    NonEmptyKeyValuePairs::Def(fitted(redeemers))
+                }
+                NonEmptyKeyValuePairs::Indef(redeemers) => {
+                    NonEmptyKeyValuePairs::Indef(fitted(redeemers))
+                }
+            })
+        }
+    };
+
+    // Only the redeemers and the script data hash are re-serialized, so that everything else
+    // (and in particular the datums, which are hashed as they're serialized) stays as is.
+    let witness_set = set_map_entry(
+        tx.transaction_witness_set.raw_cbor(),
+        5,
+        &redeemers.encode_fragment()?,
+    )?;
+
+    let languages = script_languages(tx, utxos, &DataLookupTable::from_transaction(tx, utxos))?;
+
+    let body = match hash_script_data(&witness_set, &languages, cost_mdls)? {
+        Some(hash) => set_map_entry(
+            tx.transaction_body.raw_cbor(),
+            11,
+            &minicbor::to_vec(hash).expect("encoding to a vector is infallible"),
+        )?,
+        None => tx.transaction_body.raw_cbor().to_vec(),
+    };
+
+    // A transaction is an array of 4 elements: body, witnesses, validity and auxiliary data.
+    let mut bytes = vec![0x84];
+    bytes.extend(body);
+    bytes.extend(witness_set);
+    bytes.extend(minicbor::to_vec(tx.success).expect("encoding to a vector is infallible"));
+    bytes.extend(minicbor::to_vec(&tx.auxiliary_data).expect("encoding to a vector is infallible"));
+
+    Ok(bytes)
+}
+
+/// An entry of a CBOR map with an unsigned integer key, as (key, raw key, raw value).
+type MapEntry<'a> = (u64, &'a [u8], &'a [u8]);
+
+/// Entries of a CBOR map with unsigned integer keys, along with the length of the map, if
+/// definite.
+fn map_entries(raw: &[u8]) -> Result<(Option<u64>, Vec<MapEntry<'_>>), Error> {
+    let mut decoder = minicbor::Decoder::new(raw);
+    let len = decoder.map()?;
+    let mut entries = vec![];
+
+    while len.is_none_or(|len| (entries.len() as u64) < len) {
+        if len.is_none() && decoder.datatype()? == minicbor::data::Type::Break {
+            break;
+        }
+
+        let key_start = decoder.position();
+        let key = decoder.u64()?;
+        let value_start = decoder.position();
+        decoder.skip()?;
+
+        entries.push((
+            key,
+            &raw[key_start..value_start],
+            &raw[value_start..decoder.position()],
+        ));
+    }
+
+    Ok((len, entries))
+}
+
+/// Replace, or add, the (raw) value at the given key of a CBOR map, preserving the encoding
+/// of every other entry.
+fn set_map_entry(raw: &[u8], key: u64, value: &[u8]) -> Result<Vec<u8>, Error> {
+    let (len, mut entries) = map_entries(raw)?;
+
+    let new_key = minicbor::to_vec(key).expect("encoding to a vector is infallible");
+
+    match entries.iter_mut().find(|(k, _, _)| *k == key) {
+        Some(entry) => entry.2 = value,
+        None => entries.push((key, &new_key, value)),
+    }
+
+    let mut bytes = vec![];
+    let mut encoder = minicbor::Encoder::new(&mut bytes);
+
+    match len {
+        Some(_) => encoder.map(entries.len() as u64),
+        None => encoder.begin_map(),
+    }
+    .expect("encoding to a vector is infallible");
+
+    for (_, key, value) in entries {
+        bytes.extend_from_slice(key);
+        bytes.extend_from_slice(value);
+    }
+
+    if len.is_none() {
+        bytes.push(0xFF);
+    }
+
+    Ok(bytes)
+}
+
 pub fn apply_params_to_script(
     params_bytes: &[u8], // PlutusData array
     plutus_script_bytes: &[u8],
@@ -2,7 +2,7 @@ use crate::{
     TransactionInput,
     machine::{self, Trace, cost_model::ExBudget},
 };
-use pallas_primitives::conway::Language;
+use pallas_primitives::conway::{ExUnits, Language};
 
 #[derive(thiserror::Error, Debug, miette::Diagnostic)]
 pub enum Error {
@@ -62,8 +62,12 @@ pub enum Error {
     },
     #[error("extraneous redeemer")]
     ExtraneousRedeemer,
-    #[error("resolved Input not found")]
+    #[error("resolved Input not found\n{:>13} {}#{}", "Input", .0.transaction_id, .0.index)]
     ResolvedInputNotFound(TransactionInput),
+    #[error("invalid UTxO snapshot\n{:>13} {}", "Reason", .0)]
+    InvalidUtxoSnapshot(String),
+    #[error("invalid transaction scenario\n{:>13} {}", "Reason", .0)]
+    InvalidScenario(String),
     #[error("redeemer points to a non-script withdrawal")]
     NonScriptWithdrawal,
     #[error("stake credential points to a non-script withdrawal")]
@@ -102,4 +106,129 @@ pub enum Error {
     ApplyParamsError,
     #[error("validity start or end too far in the past")]
     SlotTooFarInThePast { oldest_allowed: u64 },
+    #[error("invalid protocol parameters\n{:>13} {}", "Reason", .0)]
+    InvalidProtocolParameters(String),
+    #[error(
+        "invalid validity interval\n{:>13} {}",
+        "Interval",
+        format!("[{lower}; {upper}[")
+    )]
+    InvalidValidityInterval { lower: u64, upper: u64 },
+    #[error(
+        "current slot outside of validity interval\n{:>13} {}\n{:>13} {}",
+        "Slot",
+        slot,
+        "Interval",
+        format!(
+            "[{}; {}[",
+            lower.map(|n| n.to_string()).unwrap_or_else(|| "-∞".to_string()),
+            upper.map(|n| n.to_string()).unwrap_or_else(|| "+∞".to_string()),
+        )
+    )]
+    OutsideValidityInterval {
+        slot: u64,
+        lower: Option<u64>,
+        upper: Option<u64>,
+    },
+    #[error(
+        "transaction too large\n{:>13} {}\n{:>13} {}",
+        "Size",
+        size,
+        "Max",
+        max
+    )]
+    TxTooLarge { size: u64, max: u64 },
+    #[error(
+        "fee too small\n{:>13} {}\n{:>13} {}",
+        "Provided",
+        provided,
+        "Min",
+        min
+    )]
+    FeeTooSmall { provided: u64, min: u64 },
+    #[error(
+        "not enough lovelace in output\n{:>13} {}\n{:>13} {}\n{:>13} {}",
+        "Output",
+        index,
+        "Provided",
+        provided,
+        "Min",
+        min
+    )]
+    OutputTooSmall {
+        index: usize,
+        provided: u64,
+        min: u64,
+    },
+    #[error(
+        "output value too large\n{:>13} {}\n{:>13} {}\n{:>13} {}",
+        "Output",
+        index,
+        "Size",
+        size,
+        "Max",
+        max
+    )]
+    OutputValueTooLarge { index: usize, size: u64, max: u64 },
+    #[error("missing collateral inputs in a transaction with scripts")]
+    NoCollateralInputs,
+    #[error(
+        "too many collateral inputs\n{:>13} {}\n{:>13} {}",
+        "Count",
+        count,
+        "Max",
+        max
+    )]
+    TooManyCollateralInputs { count: usize, max: u64 },
+    #[error(
+        "collateral input locked by a script\n{:>13} {}",
+        "Input",
+        format!("{}#{}", .0.transaction_id, .0.index)
+    )]
+    ScriptLockedCollateral(TransactionInput),
+    #[error("collateral contains tokens other than Ada, and doesn't return them")]
+    CollateralContainsTokens,
+    #[error(
+        "insufficient collateral\n{:>13} {}\n{:>13} {}",
+        "Provided",
+        provided,
+        "Required",
+        required
+    )]
+    InsufficientCollateral { provided: u64, required: u64 },
+    #[error(
+        "total collateral doesn't match collateral balance\n{:>13} {}\n{:>13} {}",
+        "Declared",
+        declared,
+        "Balance",
+        provided
+    )]
+    IncorrectTotalCollateral { declared: u64, provided: u64 },
+    #[error(
+        "execution units exceed the transaction limit\n{:>13} {}\n{:>13} {}",
+        "Provided",
+        format!("mem={}, steps={}", provided.mem, provided.steps),
+        "Max",
+        format!("mem={}, steps={}", max.mem, max.steps)
+    )]
+    ExUnitsTooBig { provided: ExUnits, max: ExUnits },
+    #[error(
+        "value not conserved\n{:>13} {}\n{:>13} {}",
+        "Consumed",
+        consumed,
+        "Produced",
+        produced
+    )]
+    ValueNotConserved { consumed: String, produced: String },
+    #[error(
+        "script data hash mismatch\n{:>13} {}\n{:>13} {}",
+        "Expected",
+        expected.as_deref().unwrap_or("none"),
+        "Provided",
+        provided.as_deref().unwrap_or("none")
+    )]
+    ScriptDataHashMismatch {
+        expected: Option<String>,
+        provided: Option<String>,
+    },
 }
@@ -7,13 +7,18 @@ use crate::{
     PlutusData,
     ast::{FakeNamedDeBruijn, NamedDeBruijn, Program},
     machine::{cost_model::ExBudget, eval_result::EvalResult},
+    source_map::{SourceLocation, SourceMap},
     tx::{
         phase_one::redeemer_tag_to_string,
         script_context::{DataLookupTable, PlutusScript, TxInfoV1, TxInfoV2, TxInfoV3},
     },
 };
 use pallas_codec::utils::Bytes;
-use pallas_primitives::conway::{CostModel, CostModels, ExUnits, Language, MintedTx, Redeemer};
+use pallas_primitives::conway::{
+    CostModel, CostModels, ExUnits, Language, MintedTx, Redeemer, ScriptHash,
+};
+use pallas_traverse::ComputeHash;
+use std::collections::HashMap;
 
 pub fn eval_redeemer(
     tx: &MintedTx,
@@ -33,21 +38,7 @@ pub fn eval_redeemer(
         tx_info: TxInfo,
         program: Program<NamedDeBruijn>,
     ) -> Result<(Redeemer, EvalResult), Error> {
-        let script_context = tx_info
-            .into_script_context(redeemer, datum.as_ref())
-            .expect("couldn't create script context from transaction?");
-
-        let program = match script_context {
-            ScriptContext::V1V2 { .. } => if let Some(datum) = datum {
-                program.apply_data(datum)
-            } else {
-                program
-            }
-            .apply_data(redeemer.data.clone())
-            .apply_data(script_context.to_plutus_data()),
-
-            ScriptContext::V3 { .. } => program.apply_data(script_context.to_plutus_data()),
-        };
+        let (program, _) = apply_arguments(program, datum, redeemer, tx_info);
 
         let eval_result = if let Some(costs) = cost_mdl_opt {
             program.eval_as(lang, costs, Some(initial_budget))
@@ -74,12 +65,6 @@ pub fn eval_redeemer(
         Ok((new_redeemer, eval_result))
     }
 
-    let program = |script: Bytes| {
-        let mut buffer = Vec::new();
-        Program::<FakeNamedDeBruijn>::from_cbor(&script, &mut buffer)
-            .map(Into::<Program<NamedDeBruijn>>::into)
-    };
-
     match find_script(redeemer, tx, utxos, lookup_table)? {
         (PlutusScript::V1(script), datum) => do_eval_redeemer(
             cost_mdls_opt
@@ -87,7 +72,7 @@ pub fn eval_redeemer(
                     cost_mdls
                         .plutus_v1
                         .as_ref()
-                        .ok_or(Error::CostModelNotFound(Language::PlutusV2))
+                        .ok_or(Error::CostModelNotFound(Language::PlutusV1))
                 })
                 .transpose()?,
             initial_budget,
@@ -95,7 +80,7 @@ pub fn eval_redeemer(
             datum,
             redeemer,
             TxInfoV1::from_transaction(tx, utxos, slot_config)?,
-            program(script.0)?,
+            decode_script(script.0)?,
         ),
 
         (PlutusScript::V2(script), datum) => do_eval_redeemer(
@@ -112,7 +97,7 @@ pub fn eval_redeemer(
             datum,
             redeemer,
             TxInfoV2::from_transaction(tx, utxos, slot_config)?,
-            program(script.0)?,
+            decode_script(script.0)?,
         ),
 
         (PlutusScript::V3(script), datum) => do_eval_redeemer(
@@ -129,7 +114,7 @@ pub fn eval_redeemer(
             datum,
             redeemer,
             TxInfoV3::from_transaction(tx, utxos, slot_config)?,
-            program(script.0)?,
+            decode_script(script.0)?,
         ),
     }
     .map_err(|err| Error::RedeemerError {
@@ -138,3 +123,86 @@ pub fn eval_redeemer(
         err: Box::new(err),
     })
 }
+
+/// Evaluate a redeemer again to find where in the source code of its script it fails, if it does,
+/// according to the source maps of the scripts given by hash. Scripts without a source map aren't
+/// evaluated.
+pub fn locate_failure(
+    tx: &MintedTx,
+    utxos: &[ResolvedInput],
+    slot_config: &SlotConfig,
+    redeemer: &Redeemer,
+    lookup_table: &DataLookupTable,
+    source_maps: &HashMap<ScriptHash, SourceMap>,
+) -> Result<Option<SourceLocation>, Error> {
+    let (script, datum) = find_script(redeemer, tx, utxos, lookup_table)?;
+
+    let (hash, lang, tx_info, script) = match script {
+        PlutusScript::V1(script) => (
+            script.compute_hash(),
+            Language::PlutusV1,
+            TxInfoV1::from_transaction(tx, utxos, slot_config)?,
+            script.0,
+        ),
+        PlutusScript::V2(script) => (
+            script.compute_hash(),
+            Language::PlutusV2,
+            TxInfoV2::from_transaction(tx, utxos, slot_config)?,
+            script.0,
+        ),
+        PlutusScript::V3(script) => (
+            script.compute_hash(),
+            Language::PlutusV3,
+            TxInfoV3::from_transaction(tx, utxos, slot_config)?,
+            script.0,
+        ),
+    };
+
+    let Some(source_map) = source_maps.get(&hash) else {
+        return Ok(None);
+    };
+
+    let (program, arguments) = apply_arguments(decode_script(script)?, datum, redeemer, tx_info);
+
+    let source_map = (0..arguments).fold(source_map.clone(), |source_map, _| source_map.applied());
+
+    Ok(program.locate_failure(ExBudget::default(), &lang, &source_map))
+}
+
+/// Apply a script to its arguments, as the ledger does: its datum (if any), redeemer and script
+/// context for Plutus V1 and V2, or only its script context for Plutus V3. Also gives back the
+/// number of arguments applied.
+fn apply_arguments(
+    program: Program<NamedDeBruijn>,
+    datum: Option<PlutusData>,
+    redeemer: &Redeemer,
+    tx_info: TxInfo,
+) -> (Program<NamedDeBruijn>, usize) {
+    let script_context = tx_info
+        .into_script_context(redeemer, datum.as_ref())
+        .expect("couldn't create script context from transaction?");
+
+    match script_context {
+        ScriptContext::V1V2 { .. } => {
+            let (program, arguments) = match datum {
+                Some(datum) => (program.apply_data(datum), 3),
+                None => (program, 2),
+            };
+
+            (
+                program
+                    .apply_data(redeemer.data.clone())
+                    .apply_data(script_context.to_plutus_data()),
+                arguments,
+            )
+        }
+
+        ScriptContext::V3 { .. } => (program.apply_data(script_context.to_plutus_data()), 1),
+    }
+}
+
+fn decode_script(script: Bytes) -> Result<Program<NamedDeBruijn>, Error> {
+    let mut buffer = Vec::new();
+
+    Ok(Program::<FakeNamedDeBruijn>::from_cbor(&script, &mut buffer)?.into())
+}
@@ -1,15 +1,26 @@
 use super::{
     error::Error,
+    iter_redeemers, map_entries,
+    protocol_parameters::ProtocolParameters,
     script_context::{DataLookupTable, PlutusScript, ResolvedInput, ScriptPurpose, sort_voters},
 };
 use crate::tx::script_context::sort_reward_accounts;
 use itertools::Itertools;
+use num_bigint::BigInt;
+use num_integer::Integer;
 use pallas_addresses::{Address, ScriptHash, ShelleyPaymentPart, StakePayload};
-use pallas_codec::utils::Nullable;
-use pallas_primitives::conway::{
-    Certificate, GovAction, MintedTx, PolicyId, RedeemerTag, Redeemers, RedeemersKey,
-    RewardAccount, StakeCredential, TransactionOutput, Voter,
+use pallas_codec::{minicbor, utils::Nullable};
+use pallas_crypto::hash::{Hash, Hasher};
+use pallas_primitives::{
+    alonzo::Value as LegacyValue,
+    conway::{
+        Certificate, CostModels, ExUnits, GovAction, Language, MintedTransactionOutput, MintedTx,
+        PolicyId, PseudoScript, PseudoTransactionOutput, RationalNumber, RedeemerTag, Redeemers,
+        RedeemersKey, RewardAccount, StakeCredential, TransactionInput, TransactionOutput, Value,
+        Voter,
+    },
 };
+use std::collections::BTreeMap;
 
 type ScriptsNeeded = Vec<(ScriptPurpose, ScriptHash)>;
 
@@ -411,3 +422,601 @@ pub fn redeemer_tag_to_string(redeemer_tag: &RedeemerTag) -> String {
     }
     .to_string()
 }
+
+/// Phase-one ledger rules beyond scripts and redeemers, checked against the given protocol
+/// parameters: validity interval, size, fee, outputs, collateral, execution units, value
+/// conservation and script integrity. The validity interval is only checked against the
+/// current slot when one is given.
+///
+/// Without access to the ledger state, deposits are assumed to be the current ones, and pool
+/// registrations are assumed to be new registrations.
+pub fn eval_phase_one_with_params(
+    tx: &MintedTx,
+    utxos: &[ResolvedInput],
+    lookup_table: &DataLookupTable,
+    params: &ProtocolParameters,
+    current_slot: Option<u64>,
+) -> Result<(), Error> {
+    eval_phase_one(tx, utxos, lookup_table)?;
+
+    validate_validity_interval(tx, current_slot)?;
+    validate_size_and_fee(tx, utxos, params)?;
+    validate_outputs(tx, params)?;
+    validate_collateral(tx, utxos, params)?;
+    validate_ex_units(tx, params)?;
+    validate_value_conservation(tx, utxos, params)?;
+    validate_script_data_hash(tx, utxos, lookup_table, params)
+}
+
+fn validate_validity_interval(tx: &MintedTx, current_slot: Option<u64>) -> Result<(), Error> {
+    let lower = tx.transaction_body.validity_interval_start;
+    let upper = tx.transaction_body.ttl;
+
+    if let (Some(lower), Some(upper)) = (lower, upper) {
+        if lower >= upper {
+            return Err(Error::InvalidValidityInterval { lower, upper });
+        }
+    }
+
+    if let Some(slot) = current_slot {
+        if lower.is_some_and(|lower| slot < lower) || upper.is_some_and(|upper| slot >= upper) {
+            return Err(Error::OutsideValidityInterval { slot, lower, upper });
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_size_and_fee(
+    tx: &MintedTx,
+    utxos: &[ResolvedInput],
+    params: &ProtocolParameters,
+) -> Result<(), Error> {
+    let size = encoded_size(tx);
+
+    if size > params.max_tx_size {
+        return Err(Error::TxTooLarge {
+            size,
+            max: params.max_tx_size,
+        });
+    }
+
+    let ex_units = total_ex_units(tx);
+
+    let (mem_fee, mem_denominator) = scale(ex_units.mem, &params.price_mem);
+    let (steps_fee, steps_denominator) = scale(ex_units.steps, &params.price_steps);
+    let scripts_fee = to_u64(
+        (mem_fee * &steps_denominator + steps_fee * &mem_denominator)
+            .div_ceil(&(mem_denominator * steps_denominator)),
+    );
+
+    let ref_scripts_fee = ref_scripts_fee(ref_scripts_size(tx, utxos), params);
+
+    let min_fee = params.fee_per_byte * size + params.fee_fixed + scripts_fee + ref_scripts_fee;
+
+    if tx.transaction_body.fee < min_fee {
+        return Err(Error::FeeTooSmall {
+            provided: tx.transaction_body.fee,
+            min: min_fee,
+        });
+    }
+
+    Ok(())
+}
+
+fn validate_outputs(tx: &MintedTx, params: &ProtocolParameters) -> Result<(), Error> {
+    let outputs = tx
+        .transaction_body
+        .outputs
+        .iter()
+        .chain(tx.transaction_body.collateral_return.iter());
+
+    for (index, output) in outputs.enumerate() {
+        let (coin, value_size) = match output {
+            PseudoTransactionOutput::Legacy(output) => {
+                (legacy_coin(&output.amount), encoded_size(&output.amount))
+            }
+            PseudoTransactionOutput::PostAlonzo(output) => {
+                (coin(&output.value), encoded_size(&output.value))
+            }
+        };
+
+        if value_size > params.max_value_size {
+            return Err(Error::OutputValueTooLarge {
+                index,
+                size: value_size,
+                max: params.max_value_size,
+            });
+        }
+
+        // Every output must hold enough Ada to pay for its own storage, including a fixed
+        // overhead of 160 bytes for the input that will eventually spend it.
+        let min = (160 + encoded_size(output)) * params.coins_per_utxo_byte;
+
+        if coin < min {
+            return Err(Error::OutputTooSmall {
+                index,
+                provided: coin,
+                min,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_collateral(
+    tx: &MintedTx,
+    utxos: &[ResolvedInput],
+    params: &ProtocolParameters,
+) -> Result<(), Error> {
+    let body = &tx.transaction_body;
+
+    if tx.transaction_witness_set.redeemer.is_none() {
+        return Ok(());
+    }
+
+    let collateral = body
+        .collateral
+        .as_deref()
+        .map(|inputs| inputs.to_vec())
+        .unwrap_or_default();
+
+    if collateral.is_empty() {
+        return Err(Error::NoCollateralInputs);
+    }
+
+    if collateral.len() as u64 > params.max_collateral_inputs {
+        return Err(Error::TooManyCollateralInputs {
+            count: collateral.len(),
+            max: params.max_collateral_inputs,
+        });
+    }
+
+    let mut balance = Balance::default();
+
+    for input in collateral.iter() {
+        let utxo = resolve(utxos, input)?;
+
+        match Address::from_bytes(output_address_bytes(&utxo.output))? {
+            Address::Shelley(address) if address.payment().is_script() => {
+                return Err(Error::ScriptLockedCollateral(input.clone()));
+            }
+            _ => balance.add_output(&utxo.output, 1),
+        }
+    }
+
+    if let Some(collateral_return) = &body.collateral_return {
+        balance.add_minted_output(collateral_return, -1);
+    }
+
+    if balance.assets.values().any(|quantity| *quantity != 0) {
+        return Err(Error::CollateralContainsTokens);
+    }
+
+    let provided = u64::try_from(balance.coin).unwrap_or_default();
+
+    if let Some(total_collateral) = body.total_collateral {
+        if total_collateral != provided {
+            return Err(Error::IncorrectTotalCollateral {
+                declared: total_collateral,
+                provided,
+            });
+        }
+    }
+
+    let required = (body.fee * params.collateral_percentage).div_ceil(100);
+
+    if provided < required {
+        return Err(Error::InsufficientCollateral { provided, required });
+    }
+
+    Ok(())
+}
+
+fn validate_ex_units(tx: &MintedTx, params: &ProtocolParameters) -> Result<(), Error> {
+    let provided = total_ex_units(tx);
+
+    let max = params.max_tx_ex_units;
+
+    if provided.mem > max.mem || provided.steps > max.steps {
+        return Err(Error::ExUnitsTooBig { provided, max });
+    }
+
+    Ok(())
+}
+
+fn validate_value_conservation(
+    tx: &MintedTx,
+    utxos: &[ResolvedInput],
+    params: &ProtocolParameters,
+) -> Result<(), Error> {
+    let body = &tx.transaction_body;
+
+    let mut consumed = Balance::default();
+
+    for input in body.inputs.iter() {
+        consumed.add_output(&resolve(utxos, input)?.output, 1);
+    }
+
+    for (policy_id, assets) in body.mint.iter().flat_map(|mint| mint.iter()) {
+        for (asset_name, quantity) in assets.iter() {
+            consumed.add_asset(policy_id, asset_name, i64::from(quantity) as i128);
+        }
+    }
+
+    for (_, withdrawal) in body.withdrawals.iter().flat_map(|w| w.iter()) {
+        consumed.coin += *withdrawal as i128;
+    }
+
+    let mut produced = Balance::default();
+
+    for output in body.outputs.iter() {
+        produced.add_minted_output(output, 1);
+    }
+
+    produced.coin += body.fee as i128;
+
+    produced.coin += body.donation.as_ref().map(u64::from).unwrap_or_default() as i128;
+
+    for certificate in body.certificates.iter().flat_map(|certs| certs.iter()) {
+        match certificate {
+            Certificate::StakeRegistration(..) => {
+                produced.coin += params.stake_address_deposit as i128
+            }
+            Certificate::StakeDeregistration(..) => {
+                consumed.coin += params.stake_address_deposit as i128
+            }
+            Certificate::PoolRegistration { .. } => {
+                produced.coin += params.stake_pool_deposit as i128
+            }
+            Certificate::Reg(_, deposit)
+            | Certificate::StakeRegDeleg(_, _, deposit)
+            | Certificate::VoteRegDeleg(_, _, deposit)
+            | Certificate::StakeVoteRegDeleg(_, _, _, deposit)
+            | Certificate::RegDRepCert(_, deposit, _) => produced.coin += *deposit as i128,
+            Certificate::UnReg(_, refund) | Certificate::UnRegDRepCert(_, refund) => {
+                consumed.coin += *refund as i128
+            }
+            Certificate::StakeDelegation(..)
+            | Certificate::PoolRetirement(..)
+            | Certificate::VoteDeleg(..)
+            | Certificate::StakeVoteDeleg(..)
+            | Certificate::AuthCommitteeHot(..)
+            | Certificate::ResignCommitteeCold(..)
+            | Certificate::UpdateDRepCert(..) => {}
+        }
+    }
+
+    for procedure in body.proposal_procedures.iter().flat_map(|p| p.iter()) {
+        produced.coin += procedure.deposit as i128;
+    }
+
+    if consumed != produced {
+        return Err(Error::ValueNotConserved {
+            consumed: consumed.to_string(),
+            produced: produced.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+fn validate_script_data_hash(
+    tx: &MintedTx,
+    utxos: &[ResolvedInput],
+    lookup_table: &DataLookupTable,
+    params: &ProtocolParameters,
+) -> Result<(), Error> {
+    let languages = script_languages(tx, utxos, lookup_table)?;
+
+    let expected = script_data_hash(tx, &languages, &params.cost_models)?;
+
+    let provided = tx.transaction_body.script_data_hash;
+
+    if expected != provided {
+        return Err(Error::ScriptDataHashMismatch {
+            expected: expected.map(|hash| hash.to_string()),
+            provided: provided.map(|hash| hash.to_string()),
+        });
+    }
+
+    Ok(())
+}
+
+/// Plutus languages of the scripts a transaction needs to execute.
+pub(super) fn script_languages(
+    tx: &MintedTx,
+    utxos: &[ResolvedInput],
+    lookup_table: &DataLookupTable,
+) -> Result<Vec<Language>, Error> {
+    Ok(scripts_needed(tx, utxos)?
+        .iter()
+        .filter_map(|(_, hash)| match lookup_table.get_script(hash)? {
+            PlutusScript::V1(_) => Some(Language::PlutusV1),
+            PlutusScript::V2(_) => Some(Language::PlutusV2),
+            PlutusScript::V3(_) => Some(Language::PlutusV3),
+        })
+        .collect())
+}
+
+/// The hash binding the redeemers and datums of a transaction to the cost models of the
+/// languages its scripts use, if it has any redeemers or datums.
+pub fn script_data_hash(
+    tx: &MintedTx,
+    languages: &[Language],
+    cost_mdls: &CostModels,
+) -> Result<Option<Hash<32>>, Error> {
+    // Hashes are computed over the original bytes of the witnesses, which the decoded
+    // witness set doesn't retain for the datums, so we look them up in its raw bytes.
+    hash_script_data(tx.transaction_witness_set.raw_cbor(), languages, cost_mdls)
+}
+
+pub(super) fn hash_script_data(
+    witness_set: &[u8],
+    languages: &[Language],
+    cost_mdls: &CostModels,
+) -> Result<Option<Hash<32>>, Error> {
+    let mut redeemers = None;
+    let mut datums = None;
+
+    for (key, _, value) in map_entries(witness_set)?.1 {
+        match key {
+            4 => datums = Some(value),
+            5 => redeemers = Some(value),
+            _ => {}
+        }
+    }
+
+    let (redeemers, language_views) = match (redeemers, datums) {
+        (None, None) => return Ok(None),
+        // Transactions with datums but no redeemers hash an empty map for both.
+        (None, Some(_)) => (&[0xA0][..], vec![0xA0]),
+        (Some(redeemers), _) => (redeemers, language_views(languages, cost_mdls)?),
+    };
+
+    let mut preimage = redeemers.to_vec();
+    preimage.extend(datums.unwrap_or_default());
+    preimage.extend(language_views);
+
+    Ok(Some(Hasher::<256>::hash(&preimage)))
+}
+
+/// Encode the cost models of the given languages as they're hashed in the script data hash.
+/// PlutusV1 comes with a few quirks inherited from the Alonzo era: both its key and its
+/// parameters are serialized twice, and its parameters as an indefinite list.
+fn language_views(languages: &[Language], cost_mdls: &CostModels) -> Result<Vec<u8>, Error> {
+    let cost_model = |language: Language| {
+        match language {
+            Language::PlutusV1 => cost_mdls.plutus_v1.as_ref(),
+            Language::PlutusV2 => cost_mdls.plutus_v2.as_ref(),
+            Language::PlutusV3 => cost_mdls.plutus_v3.as_ref(),
+        }
+        .ok_or(Error::CostModelNotFound(language))
+    };
+
+    // Keys are sorted canonically: shortest first. So PlutusV1, whose key is a byte string,
+    // comes after PlutusV2 and PlutusV3.
+    let languages = [Language::PlutusV2, Language::PlutusV3, Language::PlutusV1]
+        .into_iter()
+        .filter(|language| languages.contains(language))
+        .collect::<Vec<_>>();
+
+    let mut views = minicbor::Encoder::new(vec![]);
+
+    views.map(languages.len() as u64).unwrap();
+
+    for language in languages {
+        match language {
+            Language::PlutusV1 => {
+                let mut parameters = minicbor::Encoder::new(vec![]);
+                parameters.begin_array().unwrap();
+                for parameter in cost_model(Language::PlutusV1)? {
+                    parameters.i64(*parameter).unwrap();
+                }
+                parameters.end().unwrap();
+
+                views.bytes(&[0x00]).unwrap();
+                views.bytes(&parameters.into_writer()).unwrap();
+            }
+            Language::PlutusV2 => {
+                views.u8(1).unwrap();
+                views.encode(cost_model(Language::PlutusV2)?).unwrap();
+            }
+            Language::PlutusV3 => {
+                views.u8(2).unwrap();
+                views.encode(cost_model(Language::PlutusV3)?).unwrap();
+            }
+        }
+    }
+
+    Ok(views.into_writer())
+}
+
+fn resolve<'a>(
+    utxos: &'a [ResolvedInput],
+    input: &TransactionInput,
+) -> Result<&'a ResolvedInput, Error> {
+    utxos
+        .iter()
+        .find(|utxo| &utxo.input == input)
+        .ok_or_else(|| Error::ResolvedInputNotFound(input.clone()))
+}
+
+fn output_address_bytes(output: &TransactionOutput) -> &[u8] {
+    match output {
+        TransactionOutput::Legacy(output) => output.address.as_ref(),
+        TransactionOutput::PostAlonzo(output) => output.address.as_ref(),
+    }
+}
+
+fn encoded_size<T: minicbor::Encode<()>>(value: &T) -> u64 {
+    minicbor::to_vec(value)
+        .expect("encoding to a vector is infallible")
+        .len() as u64
+}
+
+fn total_ex_units(tx: &MintedTx) -> ExUnits {
+    tx.transaction_witness_set
+        .redeemer
+        .as_ref()
+        .map(|redeemers| {
+            iter_redeemers(redeemers).fold(ExUnits { mem: 0, steps: 0 }, |total, (_, _, ex)| {
+                ExUnits {
+                    mem: total.mem + ex.mem,
+                    steps: total.steps + ex.steps,
+                }
+            })
+        })
+        .unwrap_or(ExUnits { mem: 0, steps: 0 })
+}
+
+/// Size of the reference scripts found in the inputs and reference inputs of a transaction,
+/// counting each occurrence.
+fn ref_scripts_size(tx: &MintedTx, utxos: &[ResolvedInput]) -> u64 {
+    let body = &tx.transaction_body;
+
+    body.inputs
+        .iter()
+        .chain(
+            body.reference_inputs
+                .iter()
+                .flat_map(|inputs| inputs.iter()),
+        )
+        .filter_map(|input| match &resolve(utxos, input).ok()?.output {
+            TransactionOutput::PostAlonzo(output) => output.script_ref.as_ref(),
+            TransactionOutput::Legacy(_) => None,
+        })
+        .map(|script| match &script.0 {
+            PseudoScript::NativeScript(script) => encoded_size(script),
+            PseudoScript::PlutusV1Script(script) => script.as_ref().len() as u64,
+            PseudoScript::PlutusV2Script(script) => script.as_ref().len() as u64,
+            PseudoScript::PlutusV3Script(script) => script.as_ref().len() as u64,
+        })
+        .sum()
+}
+
+/// Reference scripts are priced per byte, by tiers of 25KiB, each 1.2 times more expensive
+/// than the previous one.
+fn ref_scripts_fee(size: u64, params: &ProtocolParameters) -> u64 {
+    const TIER_SIZE: u64 = 25_600;
+
+    let tiers = (size / TIER_SIZE) as u32;
+
+    let price = &params.min_fee_ref_script_cost_per_byte;
+
+    // Over a common denominator of `d * 5^tiers`, the price of the k-th tier is
+    // `n * 6^k * 5^(tiers - k)`.
+    let five = BigInt::from(5);
+    let six = BigInt::from(6);
+
+    let full_tiers = (0..tiers).fold(BigInt::from(0), |fee, k| {
+        fee + six.pow(k) * five.pow(tiers - k) * TIER_SIZE
+    });
+
+    let last_tier = six.pow(tiers) * (size % TIER_SIZE);
+
+    let fee = (full_tiers + last_tier) * price.numerator;
+
+    to_u64(fee.div_floor(&(five.pow(tiers) * price.denominator)))
+}
+
+/// Multiply an integer by a rational, as a numerator and a denominator.
+fn scale(n: u64, rational: &RationalNumber) -> (BigInt, BigInt) {
+    (
+        BigInt::from(n) * rational.numerator,
+        BigInt::from(rational.denominator),
+    )
+}
+
+fn to_u64(n: BigInt) -> u64 {
+    u64::try_from(n).unwrap_or(u64::MAX)
+}
+
+fn coin(value: &Value) -> u64 {
+    match value {
+        Value::Coin(coin) | Value::Multiasset(coin, _) => *coin,
+    }
+}
+
+fn legacy_coin(value: &LegacyValue) -> u64 {
+    match value {
+        LegacyValue::Coin(coin) | LegacyValue::Multiasset(coin, _) => *coin,
+    }
+}
+
+/// A signed quantity of Ada and native assets.
+#[derive(Debug, Default, PartialEq)]
+struct Balance {
+    coin: i128,
+    assets: BTreeMap<(PolicyId, Vec<u8>), i128>,
+}
+
+impl Balance {
+    fn add_asset(&mut self, policy_id: &PolicyId, asset_name: &[u8], quantity: i128) {
+        let total = self
+            .assets
+            .entry((*policy_id, asset_name.to_vec()))
+            .or_default();
+
+        *total += quantity;
+
+        if *total == 0 {
+            self.assets.remove(&(*policy_id, asset_name.to_vec()));
+        }
+    }
+
+    fn add_value(&mut self, value: &Value, sign: i128) {
+        match value {
+            Value::Coin(coin) => self.coin += sign * *coin as i128,
+            Value::Multiasset(coin, assets) => {
+                self.coin += sign * *coin as i128;
+                for (policy_id, assets) in assets.iter() {
+                    for (asset_name, quantity) in assets.iter() {
+                        self.add_asset(policy_id, asset_name, sign * u64::from(quantity) as i128);
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_legacy_value(&mut self, value: &LegacyValue, sign: i128) {
+        match value {
+            LegacyValue::Coin(coin) => self.coin += sign * *coin as i128,
+            LegacyValue::Multiasset(coin, assets) => {
+                self.coin += sign * *coin as i128;
+                for (policy_id, assets) in assets.iter() {
+                    for (asset_name, quantity) in assets.iter() {
+                        self.add_asset(policy_id, asset_name, sign * *quantity as i128);
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_output(&mut self, output: &TransactionOutput, sign: i128) {
+        match output {
+            TransactionOutput::Legacy(output) => self.add_legacy_value(&output.amount, sign),
+            TransactionOutput::PostAlonzo(output) => self.add_value(&output.value, sign),
+        }
+    }
+
+    fn add_minted_output(&mut self, output: &MintedTransactionOutput, sign: i128) {
+        match output {
+            PseudoTransactionOutput::Legacy(output) => self.add_legacy_value(&output.amount, sign),
+            PseudoTransactionOutput::PostAlonzo(output) => self.add_value(&output.value, sign),
+        }
+    }
+}
+
+impl std::fmt::Display for Balance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} lovelace", self.coin)?;
+
+        for ((policy_id, asset_name), quantity) in self.assets.iter() {
+            write!(f, " + {quantity} {policy_id}.{}", hex::encode(asset_name))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+use super::error::Error;
+use num_integer::Integer;
+use pallas_primitives::conway::{CostModels, ExUnits, RationalNumber};
+use serde_json::{Map, Value as Json};
+
+/// Protocol parameters relevant to validating transactions, as output by
+/// `cardano-cli query protocol-parameters`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtocolParameters {
+    /// Fee per byte of transaction (a.k.a `minFeeA`).
+    pub fee_per_byte: u64,
+    /// Fixed fee of any transaction (a.k.a `minFeeB`).
+    pub fee_fixed: u64,
+    pub max_tx_size: u64,
+    pub max_value_size: u64,
+    pub max_tx_ex_units: ExUnits,
+    pub max_collateral_inputs: u64,
+    pub collateral_percentage: u64,
+    pub coins_per_utxo_byte: u64,
+    pub price_mem: RationalNumber,
+    pub price_steps: RationalNumber,
+    pub min_fee_ref_script_cost_per_byte: RationalNumber,
+    pub stake_address_deposit: u64,
+    pub stake_pool_deposit: u64,
+    pub cost_models: CostModels,
+}
+
+impl ProtocolParameters {
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let json: Json = serde_json::from_str(json).map_err(invalid)?;
+
+        let params = json
+            .as_object()
+            .ok_or_else(|| invalid("expected an object"))?;
+
+        let max_tx_ex_units = object(params, "maxTxExecutionUnits")?;
+
+        let prices = object(params, "executionUnitPrices")?;
+
+        Ok(ProtocolParameters {
+            fee_per_byte: integer(params, "txFeePerByte")?,
+            fee_fixed: integer(params, "txFeeFixed")?,
+            max_tx_size: integer(params, "maxTxSize")?,
+            max_value_size: integer(params, "maxValueSize")?,
+            max_tx_ex_units: ExUnits {
+                mem: integer(max_tx_ex_units, "memory")?,
+                steps: integer(max_tx_ex_units, "steps")?,
+            },
+            max_collateral_inputs: integer(params, "maxCollateralInputs")?,
+            collateral_percentage: integer(params, "collateralPercentage")?,
+            coins_per_utxo_byte: integer(params, "utxoCostPerByte")?,
+            price_mem: rational(prices, "priceMemory")?,
+            price_steps: rational(prices, "priceSteps")?,
+            // Introduced in Conway, and thus absent from earlier parameters.
+            min_fee_ref_script_cost_per_byte: match params.get("minFeeRefScriptCostPerByte") {
+                None | Some(Json::Null) => RationalNumber {
+                    numerator: 0,
+                    denominator: 1,
+                },
+                Some(_) => rational(params, "minFeeRefScriptCostPerByte")?,
+            },
+            stake_address_deposit: integer(params, "stakeAddressDeposit")?,
+            stake_pool_deposit: integer(params, "stakePoolDeposit")?,
+            cost_models: cost_models(object(params, "costModels")?)?,
+        })
+    }
+}
+
+fn invalid(reason: impl ToString) -> Error {
+    Error::InvalidProtocolParameters(reason.to_string())
+}
+
+fn field<'a>(params: &'a Map<String, Json>, key: &str) -> Result<&'a Json, Error> {
+    params
+        .get(key)
+        .ok_or_else(|| invalid(format!("missing field '{key}'")))
+}
+
+fn object<'a>(params: &'a Map<String, Json>, key: &str) -> Result<&'a Map<String, Json>, Error> {
+    field(params, key)?
+        .as_object()
+        .ok_or_else(|| invalid(format!("expected an object for '{key}'")))
+}
+
+fn integer(params: &Map<String, Json>, key: &str) -> Result<u64, Error> {
+    field(params, key)?
+        .as_u64()
+        .ok_or_else(|| invalid(format!("expected a positive integer for '{key}'")))
+}
+
+/// Parse a decimal number as an exact rational, e.g. `0.0577` as `577/10000`.
+fn rational(params: &Map<String, Json>, key: &str) -> Result<RationalNumber, Error> {
+    let number = match field(params, key)? {
+        Json::Number(number) => number.to_string(),
+        _ => return Err(invalid(format!("expected a number for '{key}'"))),
+    };
+
+    // Small floats may be printed in scientific notation (e.g. 7.21e-5).
+    let (mantissa, exponent) = match number.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (
+            mantissa,
+            exponent
+                .parse::<i32>()
+                .map_err(|_| invalid(format!("invalid number for '{key}': {number}")))?,
+        ),
+        None => (number.as_str(), 0),
+    };
+
+    let (integral, fractional) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let exponent = exponent - fractional.len() as i32;
+
+    let digits = format!("{integral}{fractional}")
+        .parse::<u64>()
+        .map_err(|_| invalid(format!("expected a positive number for '{key}': {number}")))?;
+
+    let (numerator, denominator) = if exponent >= 0 {
+        (
+            10u64
+                .checked_pow(exponent as u32)
+                .and_then(|scale| digits.checked_mul(scale)),
+            Some(1),
+        )
+    } else {
+        (Some(digits), 10u64.checked_pow(exponent.unsigned_abs()))
+    };
+
+    match (numerator, denominator) {
+        (Some(numerator), Some(denominator)) => {
+            let gcd = numerator.gcd(&denominator);
+            Ok(RationalNumber {
+                numerator: numerator / gcd,
+                denominator: denominator / gcd,
+            })
+        }
+        _ => Err(invalid(format!(
+            "number out of bounds for '{key}': {number}"
+        ))),
+    }
+}
+
+/// Cost models, either as arrays of parameters or as objects from parameter names to values.
+///
+/// Only the original parameters of PlutusV1 and PlutusV2 are ordered alphabetically in the
+/// ledger, so objects are taken in the order of their keys for those, and rejected otherwise:
+/// parameters added since (as well as those of PlutusV3) come in an order that only arrays
+/// convey.
+fn cost_models(models: &Map<String, Json>) -> Result<CostModels, Error> {
+    let cost_model = |language: &str, min_len: usize| -> Result<Option<Vec<i64>>, Error> {
+        let parameters = match models.get(language) {
+            None | Some(Json::Null) => return Ok(None),
+            Some(Json::Array(parameters)) => parameters.iter().collect::<Vec<_>>(),
+            Some(Json::Object(parameters)) => {
+                let alphabetical = match language {
+                    "PlutusV1" | "PlutusV2" => min_len,
+                    _ => 0,
+                };
+
+                if parameters.len() != alphabetical {
+                    return Err(invalid(format!(
+                        "expected an array for the {language} cost model: the order of its {} parameters can't be told from their names",
+                        parameters.len()
+                    )));
+                }
+
+                let mut parameters = parameters.iter().collect::<Vec<_>>();
+                parameters.sort_by_key(|(name, _)| *name);
+                parameters.into_iter().map(|(_, value)| value).collect()
+            }
+            Some(_) => {
+                return Err(invalid(format!(
+                    "expected an array or an object for the {language} cost model"
+                )));
+            }
+        };
+
+        parameters
+            .into_iter()
+            .map(|parameter| {
+                parameter.as_i64().ok_or_else(|| {
+                    invalid(format!(
+                        "expected integers in the {language} cost model, got: {parameter}"
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(|parameters| {
+                if parameters.len() < min_len {
+                    Err(invalid(format!(
+                        "expected at least {min_len} parameters in the {language} cost model, got {}",
+                        parameters.len()
+                    )))
+                } else {
+                    Ok(Some(parameters))
+                }
+            })
+    };
+
+    // Minimum number of parameters known to the evaluator for each language; more recent cost
+    // models may extend them with parameters for new builtins.
+    Ok(CostModels {
+        plutus_v1: cost_model("PlutusV1", 166)?,
+        plutus_v2: cost_model("PlutusV2", 175)?,
+        plutus_v3: cost_model("PlutusV3", 251)?,
+    })
+}
@@ -0,0 +1,593 @@
+//! Build ledger transactions from their description as seen by Plutus V3 scripts.
+//!
+//! A scenario is a `Transaction` from the standard library, that is, a Plutus V3 `TxInfo`. It is
+//! turned into a Conway transaction and the UTxO it spends from, so that the script context handed
+//! to validators is the one the ledger would produce rather than the one described. In particular,
+//! the transaction id and the shape of the validity range are recomputed from the transaction.
+
+use super::{
+    ResolvedInput, SlotConfig,
+    error::Error,
+    eval_phase_two_with_override,
+    script_context::{PlutusScript, sort_reward_accounts},
+};
+use crate::{
+    PlutusData,
+    machine::{eval_result::EvalResult, runtime::convert_tag_to_constr, value::from_pallas_bigint},
+};
+use num_bigint::BigInt;
+use pallas_addresses::{
+    Network, Pointer, ScriptHash, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart,
+};
+use pallas_codec::{
+    minicbor,
+    utils::{
+        Bytes, CborWrap, NonEmptyKeyValuePairs, NonEmptySet, NonZeroInt, Nullable, PositiveCoin,
+    },
+};
+use pallas_crypto::hash::Hash;
+use pallas_primitives::conway::{
+    DatumOption, ExUnits, MintedTx, PostAlonzoTransactionOutput, PseudoTransactionOutput, Redeemer,
+    RedeemerTag, Redeemers, RedeemersKey, RedeemersValue, ScriptRef, TransactionBody,
+    TransactionInput, TransactionOutput, Tx, Value, WitnessSet,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Deref,
+};
+
+/// Scenarios are evaluated with one slot per millisecond from the POSIX epoch, so that any
+/// validity range given in POSIX time maps exactly onto slots.
+pub const SCENARIO_SLOT_CONFIG: SlotConfig = SlotConfig {
+    slot_length: 1,
+    zero_slot: 0,
+    zero_time: 0,
+};
+
+/// Build a Conway transaction, serialized as CBOR, and the inputs it resolves from a transaction
+/// description. Scripts referenced by outputs are looked up in `scripts`.
+pub fn build_scenario(
+    transaction: &PlutusData,
+    scripts: &HashMap<ScriptHash, PlutusScript>,
+) -> Result<(Vec<u8>, Vec<ResolvedInput>), Error> {
+    let fields = constr(transaction, 0, 16, "Transaction")?;
+
+    let mut utxos: Vec<ResolvedInput> = vec![];
+
+    let mut resolve = |data: &PlutusData| -> Result<TransactionInput, Error> {
+        let input = fields_of(data, 2, "Input")?;
+        let output_reference = output_reference(&input[0])?;
+        let output = output(&input[1], scripts)?;
+
+        match utxos.iter().find(|utxo| utxo.input == output_reference) {
+            Some(utxo) if utxo.output != output => {
+                return Err(invalid(format!(
+                    "conflicting outputs for {}#{}",
+                    output_reference.transaction_id, output_reference.index
+                )));
+            }
+            Some(..) => {}
+            None => utxos.push(ResolvedInput {
+                input: output_reference.clone(),
+                output,
+            }),
+        }
+
+        Ok(output_reference)
+    };
+
+    let mut inputs = list(&fields[0], "inputs")?
+        .iter()
+        .map(&mut resolve)
+        .collect::<Result<Vec<_>, _>>()?;
+    inputs.sort();
+
+    let mut reference_inputs = list(&fields[1], "reference_inputs")?
+        .iter()
+        .map(&mut resolve)
+        .collect::<Result<Vec<_>, _>>()?;
+    reference_inputs.sort();
+
+    let outputs = list(&fields[2], "outputs")?
+        .iter()
+        .map(|data| output(data, scripts))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let fee = unsigned(&fields[3], "fee")?;
+
+    let mint = mint(&fields[4])?;
+
+    if !list(&fields[5], "certificates")?.is_empty() {
+        return Err(unsupported("certificates"));
+    }
+
+    let mut withdrawals = map(&fields[6], "withdrawals")?
+        .iter()
+        .map(|(credential, amount)| {
+            Ok((reward_account(credential)?, unsigned(amount, "withdrawal")?))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    withdrawals.sort_by(|(a, _), (b, _)| sort_reward_accounts(a, b));
+
+    let (validity_interval_start, ttl) = validity_range(&fields[7])?;
+
+    let required_signers = list(&fields[8], "extra_signatories")?
+        .iter()
+        .map(|data| hash::<28>(data, "extra signatory"))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let redeemers = map(&fields[9], "redeemers")?
+        .iter()
+        .map(|(purpose, data)| {
+            let (tag, index) = redeemer_pointer(purpose, &inputs, &mint, &withdrawals)?;
+            Ok((
+                RedeemersKey { tag, index },
+                RedeemersValue {
+                    data: data.clone(),
+                    ex_units: ExUnits { mem: 0, steps: 0 },
+                },
+            ))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let datums = map(&fields[10], "datums")?
+        .iter()
+        .map(|(_, datum)| datum.clone())
+        .collect::<Vec<_>>();
+
+    if !map(&fields[12], "votes")?.is_empty() {
+        return Err(unsupported("votes"));
+    }
+
+    if !list(&fields[13], "proposal_procedures")?.is_empty() {
+        return Err(unsupported("proposal procedures"));
+    }
+
+    let treasury_value = option(&fields[14], "current_treasury_amount")?
+        .map(|data| unsigned(data, "current_treasury_amount"))
+        .transpose()?;
+
+    let donation = option(&fields[15], "treasury_donation")?
+        .map(|data| {
+            PositiveCoin::try_from(unsigned(data, "treasury_donation")?)
+                .map_err(|_| invalid("treasury donations must be positive"))
+        })
+        .transpose()?;
+
+    let tx = Tx {
+        transaction_body: TransactionBody {
+            inputs: inputs.into(),
+            outputs,
+            fee,
+            ttl,
+            certificates: None,
+            withdrawals: NonEmptyKeyValuePairs::from_vec(withdrawals),
+            auxiliary_data_hash: None,
+            validity_interval_start,
+            mint: NonEmptyKeyValuePairs::from_vec(
+                mint.into_iter()
+                    .filter_map(|(policy_id, assets)| {
+                        Some((policy_id, NonEmptyKeyValuePairs::from_vec(assets)?))
+                    })
+                    .collect(),
+            ),
+            script_data_hash: None,
+            collateral: None,
+            required_signers: NonEmptySet::from_vec(required_signers),
+            network_id: None,
+            collateral_return: None,
+            total_collateral: None,
+            reference_inputs: NonEmptySet::from_vec(reference_inputs),
+            voting_procedures: None,
+            proposal_procedures: None,
+            treasury_value,
+            donation,
+        },
+        transaction_witness_set: WitnessSet {
+            vkeywitness: None,
+            native_script: None,
+            bootstrap_witness: None,
+            plutus_v1_script: None,
+            plutus_data: NonEmptySet::from_vec(datums),
+            redeemer: NonEmptyKeyValuePairs::from_vec(redeemers).map(Redeemers::from),
+            plutus_v2_script: None,
+            plutus_v3_script: None,
+        },
+        success: true,
+        auxiliary_data: Nullable::Null,
+    };
+
+    let mut bytes = vec![];
+
+    minicbor::encode(&tx, &mut bytes).expect("encoding to a vector is infallible");
+
+    Ok((bytes, utxos))
+}
+
+/// Evaluate every redeemer of a transaction description against the given scripts, as the
+/// ledger would in phase two. Evaluation stops at the first failing script.
+pub fn eval_scenario(
+    transaction: &PlutusData,
+    scripts: &HashMap<ScriptHash, PlutusScript>,
+) -> Result<Vec<(Redeemer, EvalResult)>, Error> {
+    let (bytes, utxos) = build_scenario(transaction, scripts)?;
+
+    let tx: MintedTx = minicbor::decode(&bytes)?;
+
+    eval_phase_two_with_override(
+        &tx,
+        &utxos,
+        None,
+        None,
+        &SCENARIO_SLOT_CONFIG,
+        scripts.clone(),
+        false,
+        |_| (),
+    )
+}
+
+fn invalid(reason: impl ToString) -> Error {
+    Error::InvalidScenario(reason.to_string())
+}
+
+fn unsupported(what: &str) -> Error {
+    invalid(format!("{what} aren't supported in scenarios yet"))
+}
+
+/// Fields of a constructor with the given index and arity.
+fn constr<'a>(
+    data: &'a PlutusData,
+    index: u64,
+    arity: usize,
+    what: &str,
+) -> Result<&'a [PlutusData], Error> {
+    match variant(data, what)? {
+        (ix, fields) if ix == index && fields.len() == arity => Ok(fields),
+        _ => Err(invalid(format!("malformed {what}: {data:?}"))),
+    }
+}
+
+fn fields_of<'a>(
+    data: &'a PlutusData,
+    arity: usize,
+    what: &str,
+) -> Result<&'a [PlutusData], Error> {
+    constr(data, 0, arity, what)
+}
+
+/// The constructor index and fields of some data.
+fn variant<'a>(data: &'a PlutusData, what: &str) -> Result<(u64, &'a [PlutusData]), Error> {
+    match data {
+        PlutusData::Constr(constr) => {
+            let index = convert_tag_to_constr(constr.tag)
+                .or(constr.any_constructor)
+                .ok_or_else(|| invalid(format!("malformed {what}: {data:?}")))?;
+            Ok((index, constr.fields.deref()))
+        }
+        _ => Err(invalid(format!(
+            "expected a constructor for {what}, got: {data:?}"
+        ))),
+    }
+}
+
+fn list<'a>(data: &'a PlutusData, what: &str) -> Result<&'a [PlutusData], Error> {
+    match data {
+        PlutusData::Array(xs) => Ok(xs.deref()),
+        _ => Err(invalid(format!(
+            "expected a list for {what}, got: {data:?}"
+        ))),
+    }
+}
+
+fn map<'a>(data: &'a PlutusData, what: &str) -> Result<&'a [(PlutusData, PlutusData)], Error> {
+    match data {
+        PlutusData::Map(kvs) => Ok(kvs.deref()),
+        _ => Err(invalid(format!("expected a map for {what}, got: {data:?}"))),
+    }
+}
+
+fn bytes<'a>(data: &'a PlutusData, what: &str) -> Result<&'a [u8], Error> {
+    match data {
+        PlutusData::BoundedBytes(bytes) => Ok(bytes.deref()),
+        _ => Err(invalid(format!("expected bytes for {what}, got: {data:?}"))),
+    }
+}
+
+fn hash<const N: usize>(data: &PlutusData, what: &str) -> Result<Hash<N>, Error> {
+    <[u8; N]>::try_from(bytes(data, what)?)
+        .map(Hash::from)
+        .map_err(|_| invalid(format!("expected a {N}-byte hash for {what}")))
+}
+
+fn integer(data: &PlutusData, what: &str) -> Result<BigInt, Error> {
+    match data {
+        PlutusData::BigInt(n) => Ok(from_pallas_bigint(n)),
+        _ => Err(invalid(format!(
+            "expected an integer for {what}, got: {data:?}"
+        ))),
+    }
+}
+
+fn unsigned(data: &PlutusData, what: &str) -> Result<u64, Error> {
+    u64::try_from(integer(data, what)?)
+        .map_err(|_| invalid(format!("expected a positive integer for {what}")))
+}
+
+fn option<'a>(data: &'a PlutusData, what: &str) -> Result<Option<&'a PlutusData>, Error> {
+    match variant(data, what)? {
+        (0, [x]) => Ok(Some(x)),
+        (1, []) => Ok(None),
+        _ => Err(invalid(format!("malformed {what}: {data:?}"))),
+    }
+}
+
+fn boolean(data: &PlutusData, what: &str) -> Result<bool, Error> {
+    match variant(data, what)? {
+        (0, []) => Ok(false),
+        (1, []) => Ok(true),
+        _ => Err(invalid(format!("malformed {what}: {data:?}"))),
+    }
+}
+
+fn output_reference(data: &PlutusData) -> Result<TransactionInput, Error> {
+    let fields = fields_of(data, 2, "OutputReference")?;
+
+    Ok(TransactionInput {
+        transaction_id: hash::<32>(&fields[0], "transaction id")?,
+        index: unsigned(&fields[1], "output index")?,
+    })
+}
+
+enum Credential {
+    Key(Hash<28>),
+    Script(Hash<28>),
+}
+
+fn credential(data: &PlutusData) -> Result<Credential, Error> {
+    match variant(data, "Credential")? {
+        (0, [hash]) => Ok(Credential::Key(self::hash(hash, "verification key")?)),
+        (1, [hash]) => Ok(Credential::Script(self::hash(hash, "script")?)),
+        _ => Err(invalid(format!("malformed Credential: {data:?}"))),
+    }
+}
+
+/// Reward accounts are only ever built on the test network.
+fn reward_account(data: &PlutusData) -> Result<Bytes, Error> {
+    let (header, hash) = match credential(data)? {
+        Credential::Key(hash) => (0b1110_0000, hash),
+        Credential::Script(hash) => (0b1111_0000, hash),
+    };
+
+    Ok(Bytes::from([&[header], hash.as_ref()].concat()))
+}
+
+/// Addresses are only ever built on the test network.
+fn address(data: &PlutusData) -> Result<Vec<u8>, Error> {
+    let fields = fields_of(data, 2, "Address")?;
+
+    let payment = match credential(&fields[0])? {
+        Credential::Key(hash) => ShelleyPaymentPart::Key(hash),
+        Credential::Script(hash) => ShelleyPaymentPart::Script(hash),
+    };
+
+    let delegation = match option(&fields[1], "stake credential")? {
+        None => ShelleyDelegationPart::Null,
+        Some(stake_credential) => match variant(stake_credential, "StakeCredential")? {
+            (0, [credential]) => match self::credential(credential)? {
+                Credential::Key(hash) => ShelleyDelegationPart::Key(hash),
+                Credential::Script(hash) => ShelleyDelegationPart::Script(hash),
+            },
+            (1, [slot, tx_ix, cert_ix]) => ShelleyDelegationPart::Pointer(Pointer::new(
+                unsigned(slot, "pointer slot")?,
+                unsigned(tx_ix, "pointer transaction index")?,
+                unsigned(cert_ix, "pointer certificate index")?,
+            )),
+            _ => return Err(invalid(format!("malformed StakeCredential: {data:?}"))),
+        },
+    };
+
+    Ok(ShelleyAddress::new(Network::Testnet, payment, delegation).to_vec())
+}
+
+type Assets = BTreeMap<Vec<u8>, BTreeMap<Vec<u8>, BigInt>>;
+
+/// Assets of a value as nested maps, keeping non-zero quantities only. Lovelace sit under the
+/// empty policy id.
+fn assets(data: &PlutusData, what: &str) -> Result<Assets, Error> {
+    let mut value: Assets = BTreeMap::new();
+
+    for (policy_id, tokens) in map(data, what)? {
+        for (asset_name, quantity) in map(tokens, what)? {
+            let quantity = integer(quantity, what)?;
+            if quantity != BigInt::ZERO {
+                value
+                    .entry(bytes(policy_id, "policy id")?.to_vec())
+                    .or_default()
+                    .insert(bytes(asset_name, "asset name")?.to_vec(), quantity);
+            }
+        }
+    }
+
+    Ok(value)
+}
+
+fn value(data: &PlutusData) -> Result<Value, Error> {
+    let mut assets = assets(data, "value")?;
+
+    let coin = match assets.remove(&vec![]) {
+        None => 0,
+        Some(lovelace) => match lovelace.get(&vec![]) {
+            Some(quantity) if lovelace.len() == 1 => u64::try_from(quantity)
+                .map_err(|_| invalid("lovelace quantities must be positive"))?,
+            _ => return Err(invalid("malformed lovelace entry in value")),
+        },
+    };
+
+    let multiasset = assets
+        .into_iter()
+        .map(|(policy_id, tokens)| {
+            let tokens = tokens
+                .into_iter()
+                .map(|(asset_name, quantity)| {
+                    let quantity = u64::try_from(&quantity)
+                        .ok()
+                        .and_then(|quantity| PositiveCoin::try_from(quantity).ok())
+                        .ok_or_else(|| invalid("asset quantities must be positive"))?;
+                    Ok((Bytes::from(asset_name), quantity))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            Ok((
+                policy_hash(policy_id)?,
+                NonEmptyKeyValuePairs::from_vec(tokens).expect("zero quantities are filtered out"),
+            ))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(match NonEmptyKeyValuePairs::from_vec(multiasset) {
+        None => Value::Coin(coin),
+        Some(multiasset) => Value::Multiasset(coin, multiasset),
+    })
+}
+
+type Mint = Vec<(Hash<28>, Vec<(Bytes, NonZeroInt)>)>;
+
+/// Minted assets, ordered by policy id as in the ledger.
+fn mint(data: &PlutusData) -> Result<Mint, Error> {
+    let mut assets = assets(data, "mint")?;
+
+    // NOTE: Minted values may carry a null lovelace entry, which the ledger doesn't know of.
+    assets.remove(&vec![]);
+
+    assets
+        .into_iter()
+        .map(|(policy_id, tokens)| {
+            let tokens = tokens
+                .into_iter()
+                .map(|(asset_name, quantity)| {
+                    let quantity = i64::try_from(&quantity)
+                        .ok()
+                        .and_then(|quantity| NonZeroInt::try_from(quantity).ok())
+                        .ok_or_else(|| invalid("minted quantity out of bounds"))?;
+                    Ok((Bytes::from(asset_name), quantity))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            Ok((policy_hash(policy_id)?, tokens))
+        })
+        .collect()
+}
+
+fn policy_hash(policy_id: Vec<u8>) -> Result<Hash<28>, Error> {
+    <[u8; 28]>::try_from(policy_id.as_slice())
+        .map(Hash::from)
+        .map_err(|_| invalid(format!("invalid policy id: {}", hex::encode(&policy_id))))
+}
+
+fn output(
+    data: &PlutusData,
+    scripts: &HashMap<ScriptHash, PlutusScript>,
+) -> Result<TransactionOutput, Error> {
+    let fields = fields_of(data, 4, "Output")?;
+
+    let datum_option = match variant(&fields[2], "Datum")? {
+        (0, []) => None,
+        (1, [hash]) => Some(DatumOption::Hash(self::hash(hash, "datum hash")?)),
+        (2, [datum]) => Some(DatumOption::Data(CborWrap(datum.clone()))),
+        _ => return Err(invalid(format!("malformed Datum: {:?}", fields[2]))),
+    };
+
+    let script_ref = option(&fields[3], "reference script")?
+        .map(|hash| {
+            let hash = self::hash::<28>(hash, "reference script")?;
+            match scripts.get(&hash) {
+                Some(PlutusScript::V1(script)) => Ok(ScriptRef::PlutusV1Script(script.clone())),
+                Some(PlutusScript::V2(script)) => Ok(ScriptRef::PlutusV2Script(script.clone())),
+                Some(PlutusScript::V3(script)) => Ok(ScriptRef::PlutusV3Script(script.clone())),
+                None => Err(invalid(format!("unknown reference script: {hash}"))),
+            }
+        })
+        .transpose()?;
+
+    Ok(PseudoTransactionOutput::PostAlonzo(
+        PostAlonzoTransactionOutput {
+            address: Bytes::from(address(&fields[0])?),
+            value: value(&fields[1])?,
+            datum_option,
+            script_ref: script_ref.map(CborWrap),
+        },
+    ))
+}
+
+/// Convert a validity range in POSIX time to slots, with one slot per millisecond (see
+/// [`SCENARIO_SLOT_CONFIG`]). Slots bounds are inclusive below and exclusive above.
+fn validity_range(data: &PlutusData) -> Result<(Option<u64>, Option<u64>), Error> {
+    let fields = fields_of(data, 2, "ValidityRange")?;
+
+    let bound = |data: &PlutusData, what: &str| -> Result<(u64, Option<BigInt>, bool), Error> {
+        let fields = fields_of(data, 2, what)?;
+        let is_inclusive = boolean(&fields[1], what)?;
+        match variant(&fields[0], what)? {
+            (0, []) => Ok((0, None, is_inclusive)),
+            (1, [time]) => Ok((1, Some(integer(time, what)?), is_inclusive)),
+            (2, []) => Ok((2, None, is_inclusive)),
+            _ => Err(invalid(format!("malformed {what}: {data:?}"))),
+        }
+    };
+
+    let to_slot =
+        |time: BigInt| u64::try_from(time).map_err(|_| invalid("validity range out of bounds"));
+
+    let lower = match bound(&fields[0], "lower bound")? {
+        (0, _, _) => None,
+        (1, Some(time), true) => Some(to_slot(time)?),
+        (1, Some(time), false) => Some(to_slot(time + 1)?),
+        _ => return Err(invalid("validity ranges can't start at positive infinity")),
+    };
+
+    let upper = match bound(&fields[1], "upper bound")? {
+        (2, _, _) => None,
+        (1, Some(time), true) => Some(to_slot(time + 1)?),
+        (1, Some(time), false) => Some(to_slot(time)?),
+        _ => return Err(invalid("validity ranges can't end at negative infinity")),
+    };
+
+    Ok((lower, upper))
+}
+
+/// The tag and index of the redeemer for a script purpose, with indexes into the ledger's
+/// ordering of inputs, minting policies and withdrawals.
+fn redeemer_pointer(
+    purpose: &PlutusData,
+    inputs: &[TransactionInput],
+    mint: &Mint,
+    withdrawals: &[(Bytes, u64)],
+) -> Result<(RedeemerTag, u32), Error> {
+    let position = |found: Option<usize>, what: &str| {
+        found
+            .map(|index| index as u32)
+            .ok_or_else(|| invalid(format!("redeemer for {what} not in the transaction")))
+    };
+
+    match variant(purpose, "ScriptPurpose")? {
+        (0, [policy_id]) => {
+            let policy_id = hash::<28>(policy_id, "policy id")?;
+            let index = mint.iter().position(|(policy, _)| policy == &policy_id);
+            Ok((RedeemerTag::Mint, position(index, "minting policy")?))
+        }
+        (1, [output_reference]) => {
+            let output_reference = self::output_reference(output_reference)?;
+            let index = inputs.iter().position(|input| input == &output_reference);
+            Ok((RedeemerTag::Spend, position(index, "spent input")?))
+        }
+        (2, [credential]) => {
+            let account = reward_account(credential)?;
+            let index = withdrawals.iter().position(|(other, _)| other == &account);
+            Ok((RedeemerTag::Reward, position(index, "withdrawal")?))
+        }
+        (3, _) => Err(unsupported("certificates")),
+        (4, _) => Err(unsupported("votes")),
+        (5, _) => Err(unsupported("proposal procedures")),
+        _ => Err(invalid(format!("malformed ScriptPurpose: {purpose:?}"))),
+    }
+}
@@ -490,6 +490,7 @@ pub struct TimeRange {
     pub upper_bound: Option<u64>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SlotConfig {
     pub slot_length: u32,
     pub zero_slot: u64,
@@ -1,9 +1,14 @@
-use super::{ResolvedInput, SlotConfig, eval_phase_two};
-use crate::machine::cost_model::ExBudget;
-use pallas_codec::utils::MaybeIndefArray;
+use super::{
+    DataLookupTable, ProtocolParameters, ResolvedInput, SlotConfig, UtxoSnapshot, apply_ex_units,
+    error::Error, eval_phase_one_with_params, eval_phase_two, iter_redeemers,
+};
+use crate::{ast::Data, machine::cost_model::ExBudget};
+use num_bigint::BigInt;
+use pallas_codec::{minicbor, utils::MaybeIndefArray};
 use pallas_primitives::{
     Fragment,
-    conway::{CostModels, TransactionInput, TransactionOutput},
+    alonzo::Value as LegacyValue,
+    conway::{CostModels, ExUnits, Redeemer, TransactionInput, TransactionOutput, Value},
 };
 use pallas_traverse::{Era, MultiEraTx};
 
@@ -1775,3 +1780,758 @@ fn eval_extraneous_redeemer() {
         _ => unreachable!(),
     };
 }
+
+#[test]
+fn utxo_snapshot_json_shapes() {
+    let cardano_cli = r#"{
+        "275b5da338c8b899035081eb34bfa950b634911a5dd3271b3ad6cf4c2bba0c50#1": {
+            "address": "703a888d65f16790950a72daee1f63aa05add6d268434107cfa5b67712",
+            "value": {
+                "lovelace": 2000000,
+                "15be994a64bdb79dde7fe080d8e7ff81b33a9e4860e9ee0d857a8e85": { "57617761": 1 }
+            },
+            "datumhash": null,
+            "inlineDatum": { "constructor": 0, "fields": [{ "int": 42 }, { "bytes": "cafe" }] },
+            "inlineDatumhash": "ff1a404ece117cc4482d26b072e30b5a6b3cd055a22debda3f90d704957e273a",
+            "referenceScript": {
+                "script": { "cborHex": "4e4d01000033222220051200120011", "type": "PlutusScriptV2" }
+            }
+        }
+    }"#;
+
+    let ogmios = r#"[{
+        "transaction": { "id": "275b5da338c8b899035081eb34bfa950b634911a5dd3271b3ad6cf4c2bba0c50" },
+        "index": 1,
+        "address": "703a888d65f16790950a72daee1f63aa05add6d268434107cfa5b67712",
+        "value": {
+            "ada": { "lovelace": 2000000 },
+            "15be994a64bdb79dde7fe080d8e7ff81b33a9e4860e9ee0d857a8e85": { "57617761": 1 }
+        },
+        "datum": "d8799f182a42cafeff",
+        "script": { "language": "plutus:v2", "cbor": "4d01000033222220051200120011" }
+    }]"#;
+
+    let kupo = r#"[{
+        "transaction_id": "275b5da338c8b899035081eb34bfa950b634911a5dd3271b3ad6cf4c2bba0c50",
+        "output_index": 1,
+        "address": "703a888d65f16790950a72daee1f63aa05add6d268434107cfa5b67712",
+        "value": {
+            "coins": 2000000,
+            "assets": { "15be994a64bdb79dde7fe080d8e7ff81b33a9e4860e9ee0d857a8e85.57617761": 1 }
+        },
+        "datum_type": "inline",
+        "datum_hash": "ff1a404ece117cc4482d26b072e30b5a6b3cd055a22debda3f90d704957e273a",
+        "datum": "d8799f182a42cafeff",
+        "script_hash": "67f33146617a5e61936081db3b2117cbf59bd2123748f58ac9678656",
+        "script": { "language": "plutus:v2", "script": "01000033222220051200120011" }
+    }]"#;
+
+    let snapshot = UtxoSnapshot::from_json(cardano_cli).unwrap();
+
+    assert_eq!(snapshot.len(), 1);
+    assert_eq!(UtxoSnapshot::decode(ogmios.as_bytes()).unwrap(), snapshot);
+    assert_eq!(UtxoSnapshot::decode(kupo.as_bytes()).unwrap(), snapshot);
+
+    let kupo_without_script = kupo.replace(
+        r#""script": { "language": "plutus:v2", "script": "01000033222220051200120011" }"#,
+        r#""script": null"#,
+    );
+
+    assert!(matches!(
+        UtxoSnapshot::from_json(&kupo_without_script),
+        Err(Error::InvalidUtxoSnapshot(..))
+    ));
+}
+
+#[test]
+fn utxo_snapshot_big_integer_datums() {
+    let cardano_cli = r#"{
+        "275b5da338c8b899035081eb34bfa950b634911a5dd3271b3ad6cf4c2bba0c50#1": {
+            "address": "703a888d65f16790950a72daee1f63aa05add6d268434107cfa5b67712",
+            "value": { "lovelace": 2000000 },
+            "inlineDatum": {
+                "list": [
+                    { "int": 1180591620717411303424 },
+                    { "int": -1180591620717411303425 },
+                    { "int": 18446744073709551615 }
+                ]
+            }
+        }
+    }"#;
+
+    let datum = Data::list(vec![
+        Data::integer(BigInt::from(2).pow(70)),
+        Data::integer(-BigInt::from(2).pow(70) - 1),
+        Data::integer(u64::MAX.into()),
+    ]);
+
+    let ogmios = format!(
+        r#"[{{
+            "transaction": {{ "id": "275b5da338c8b899035081eb34bfa950b634911a5dd3271b3ad6cf4c2bba0c50" }},
+            "index": 1,
+            "address": "703a888d65f16790950a72daee1f63aa05add6d268434107cfa5b67712",
+            "value": {{ "ada": {{ "lovelace": 2000000 }} }},
+            "datum": "{}"
+        }}]"#,
+        hex::encode(minicbor::to_vec(&datum).unwrap())
+    );
+
+    assert_eq!(
+        UtxoSnapshot::from_json(cardano_cli).unwrap(),
+        UtxoSnapshot::from_json(&ogmios).unwrap()
+    );
+
+    assert!(matches!(
+        UtxoSnapshot::from_json(&cardano_cli.replace("18446744073709551615", "1.5")),
+        Err(Error::InvalidUtxoSnapshot(..))
+    ));
+}
+
+#[test]
+fn utxo_snapshot_resolves_inputs() {
+    let tx_bytes = hex::decode(
+        "84a30082825820275b5da338c8b899035081eb34bfa950b634911a5dd3271b3ad6cf4c2bba0c5000825820275b5da338c8b899035081eb34bfa950b634911a5dd3271b3ad6cf4c2bba0c50010181825839000af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a02cf47c8021a00028d89a1068149480100002221200101f5f6",
+    )
+    .unwrap();
+
+    let raw_inputs = hex::decode("83825820275b5da338c8b899035081eb34bfa950b634911a5dd3271b3ad6cf4c2bba0c5000825820275b5da338c8b899035081eb34bfa950b634911a5dd3271b3ad6cf4c2bba0c5001825820975c17a4fed0051be622328efa548e206657d2b65a19224bf6ff8132571e6a5002").unwrap();
+    let raw_outputs = hex::decode("83825839000af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a02b3603082581d703a888d65f16790950a72daee1f63aa05add6d268434107cfa5b677121a001e848082581d60b6c8794e9a7a26599440a4d0fd79cd07644d15917ff13694f1f672351a00989680").unwrap();
+
+    let inputs = Vec::<TransactionInput>::decode_fragment(&raw_inputs).unwrap();
+    let outputs = Vec::<TransactionOutput>::decode_fragment(&raw_outputs).unwrap();
+
+    let utxos: Vec<(TransactionInput, TransactionOutput)> =
+        inputs.into_iter().zip(outputs).collect();
+
+    let snapshot = UtxoSnapshot::from_cbor(&minicbor::to_vec(&utxos).unwrap()).unwrap();
+
+    let multi_era_tx = MultiEraTx::decode_for_era(Era::Conway, &tx_bytes).unwrap();
+
+    let tx = multi_era_tx.as_conway().unwrap();
+
+    let resolved = snapshot.resolve(tx).unwrap();
+
+    assert_eq!(
+        resolved,
+        utxos[..2]
+            .iter()
+            .map(|(input, output)| ResolvedInput {
+                input: input.clone(),
+                output: output.clone(),
+            })
+            .collect::<Vec<_>>()
+    );
+
+    let partial = UtxoSnapshot::from_cbor(&minicbor::to_vec(&utxos[1..]).unwrap()).unwrap();
+
+    assert!(matches!(
+        partial.resolve(tx),
+        Err(Error::ResolvedInputNotFound(input)) if input == utxos[0].0
+    ));
+}
+
+#[test]
+fn protocol_parameters_from_json() {
+    // Parameter names are sorted alphabetically, whatever the order of the object.
+    let plutus_v1 = (0..166)
+        .rev()
+        .map(|ix| (format!("param-{ix:03}"), serde_json::json!(ix)))
+        .collect::<serde_json::Map<_, _>>();
+
+    let from_json = |cost_models: serde_json::Value| {
+        ProtocolParameters::from_json(
+            &serde_json::json!({
+                "txFeePerByte": 44,
+                "txFeeFixed": 155381,
+                "maxTxSize": 16384,
+                "maxValueSize": 5000,
+                "maxTxExecutionUnits": { "memory": 14000000, "steps": 10000000000u64 },
+                "maxCollateralInputs": 3,
+                "collateralPercentage": 150,
+                "utxoCostPerByte": 4310,
+                "executionUnitPrices": { "priceMemory": 0.0577, "priceSteps": 7.21e-5 },
+                "minFeeRefScriptCostPerByte": 15,
+                "stakeAddressDeposit": 2000000,
+                "stakePoolDeposit": 500000000,
+                "costModels": cost_models
+            })
+            .to_string(),
+        )
+    };
+
+    let params = from_json(serde_json::json!({
+        "PlutusV1": plutus_v1,
+        "PlutusV3": vec![-1; 251],
+    }))
+    .unwrap();
+
+    assert_eq!(
+        (params.price_mem.numerator, params.price_mem.denominator),
+        (577, 10000)
+    );
+    assert_eq!(
+        (params.price_steps.numerator, params.price_steps.denominator),
+        (721, 10000000)
+    );
+    assert_eq!(
+        params.cost_models,
+        CostModels {
+            plutus_v1: Some((0..166).collect()),
+            plutus_v2: None,
+            plutus_v3: Some(vec![-1; 251]),
+        }
+    );
+
+    // Too few parameters for the evaluator.
+    assert!(matches!(
+        from_json(serde_json::json!({ "PlutusV2": [1, 2] })),
+        Err(Error::InvalidProtocolParameters(..))
+    ));
+
+    // Parameters whose order isn't alphabetical can't be given by name.
+    let by_name = |len: usize| {
+        (0..len)
+            .map(|ix| (format!("param-{ix:03}"), serde_json::json!(ix)))
+            .collect::<serde_json::Map<_, _>>()
+    };
+
+    assert!(matches!(
+        from_json(serde_json::json!({ "PlutusV2": by_name(185) })),
+        Err(Error::InvalidProtocolParameters(..))
+    ));
+
+    assert!(matches!(
+        from_json(serde_json::json!({ "PlutusV3": by_name(251) })),
+        Err(Error::InvalidProtocolParameters(..))
+    ));
+
+    assert!(matches!(
+        ProtocolParameters::from_json(r#"{ "txFeePerByte": 44 }"#),
+        Err(Error::InvalidProtocolParameters(..))
+    ));
+}
+
+#[test]
+fn eval_phase_one_with_protocol_parameters() {
+    let tx_bytes = hex::decode("84a80082825820f375a19211ee8ce75b8306a2604e2a0e8c50dfae7a103dd3688f980bec5e29b200825820a0bfcb30c19402a5bc79ecd33ddea4d67d8d6cb2ebe694c5ac2f14abc47092f4000182825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d5821a00111958a1581c652cc39999ceb1ddbdd788c3206d70536d84d36f42198296736355efa1400a825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a0047acf6021a0002c73209a1581c652cc39999ceb1ddbdd788c3206d70536d84d36f42198296736355efa1400a0b58201ca9076f41fe63a349f05c6346c732fa7f42744e43e0d54f546af0ffc463057f0d81825820f375a19211ee8ce75b8306a2604e2a0e8c50dfae7a103dd3688f980bec5e29b20110825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a054bc2ef111a00042acba30081825820065dd553fbe4e240a8f819bb9e333a7483de4a22b65c7fb6a95ce9450f84dff758402220dcecba177cabf70414676a5b9af9e0d6dec5ce3a7335caef5e4843a2f045995f6e8debbe019d21dcfc8749d780f0cd75c717cfab17ba275702c3aae37309038158235821010000323232223333573460060024931324c4660040020020024466ebc00800410581840100d8798082190fa21a005f14d0f5f6").unwrap();
+
+    let raw_inputs = hex::decode("86825820a0bfcb30c19402a5bc79ecd33ddea4d67d8d6cb2ebe694c5ac2f14abc47092f400825820a0bfcb30c19402a5bc79ecd33ddea4d67d8d6cb2ebe694c5ac2f14abc47092f401825820cd3589fae0ae4ad33dbc8a8d99b4e99b4a78c2b0473040aab34da8e44fba9fa300825820f375a19211ee8ce75b8306a2604e2a0e8c50dfae7a103dd3688f980bec5e29b200825820f375a19211ee8ce75b8306a2604e2a0e8c50dfae7a103dd3688f980bec5e29b201825820c5bc1437117c200f325467dcede3c09fbfca32f9fe399bd40c778ab2fa832f4301").unwrap();
+    let raw_outputs = hex::decode("86825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a000f4240825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d5821a005da7c4ac581c1e852216c006c55490cd85b6e0ba523a37c2be3526f479e61cca41eba2444e616d6902454e616d693201581c21514b6c51f0aaa0c9c337329ce93c52fad208cfd0b4ccd51074d9dcab4d50494e4b44494e4f4331494439014d50494e4b44494e4f4332494439014d50494e4b44494e4f4333494439014d50494e4b44494e4f4334494439014d50494e4b44494e4f4335494439014d50494e4b44494e4f4336494439014d50494e4b44494e4f4337494439014d50494e4b44494e4f4338494439014d50494e4b44494e4f4339494439014e50494e4b44494e4f433130494439014e50494e4b44494e4f43313149443901581c321668648736a09a40bc3ef5fbd05590530be624e39af84fab3bc6bba84d435033503050726f6d6f333431014f4b696e67447572616e747574323531015053616d757257686950726f6d6f313933015143727970746f6d626f50726f6d6f3134360154536b756c6c62616c6c4c6f676f50726f6d6f35320155536b756c6c62616c6c4c6f676f50726f6d6f33313301581b425345647761726473536c616d6d696e48616e647330303034313201581c42697a61727265537461727344726561646c696f6e526f736533323401581c9ec93fd47d1e43ec5bb5dad70af92b6498b92d20d4325f9027ac4743a1574a6f736570684d6972616e64615754503030353754323601581ca7dc26584358f5ca8d99d7aa646a7b658120296a94588e1cefad8940a157546573744e616d69467269656e645368696e794a50454701581cbc5a0f0f3f1bfa92286410388c54655687bb518f47e12b88c0d0728ca14b566563746f724669656c6401581cc4c00fbd8fa227442a5e7cdecde33b24588494d05a2c50fda8938c6da1444b49445a19c346581cd068fe47123ec4c86460eeb74c7d7765c67d2df295a3ac86d664ed45a156506c757475734669727374436c61737350686f746f3101581cd3b65744dd067fd7103cc6a4019cc9cd5f8627b78174c05dc67a9ad6a1544c696665496e4c6f636b646f776e53314d31503201581cd973d8df645da318bb331dbe4af8eb0270079470225d955c6183445ca15148617070794269727468646179416c657301581cdf9974c2192744f3b6dac92990769fe26c36b0ac8bcc623fb457a45da14d575450303035466c796572323401581cee47c1521b55a006c345f739f084f5c205aeb295e3e42fa66b9d1dd2a151446965666e6643727970746f6e6965726505825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a000f4240825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a004c4b40825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a054fedba825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a0028a3c6").unwrap();
+
+    let inputs = MaybeIndefArray::<TransactionInput>::decode_fragment(&raw_inputs).unwrap();
+    let outputs = MaybeIndefArray::<TransactionOutput>::decode_fragment(&raw_outputs).unwrap();
+
+    let utxos: MaybeIndefArray<ResolvedInput> = MaybeIndefArray::Indef(
+        inputs
+            .iter()
+            .zip(outputs.iter())
+            .map(|(input, output)| ResolvedInput {
+                input: input.clone(),
+                output: output.clone(),
+            })
+            .collect(),
+    );
+
+    let costs: Vec<i64> = vec![
+        197209, 0, 1, 1, 396231, 621, 0, 1, 150000, 1000, 0, 1, 150000, 32, 2477736, 29175, 4,
+        29773, 100, 29773, 100, 29773, 100, 29773, 100, 29773, 100, 29773, 100, 100, 100, 29773,
+        100, 150000, 32, 150000, 32, 150000, 32, 150000, 1000, 0, 1, 150000, 32, 150000, 1000, 0,
+        8, 148000, 425507, 118, 0, 1, 1, 150000, 1000, 0, 8, 150000, 112536, 247, 1, 150000, 10000,
+        1, 136542, 1326, 1, 1000, 150000, 1000, 1, 150000, 32, 150000, 32, 150000, 32, 1, 1,
+        150000, 1, 150000, 4, 103599, 248, 1, 103599, 248, 1, 145276, 1366, 1, 179690, 497, 1,
+        150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 148000, 425507,
+        118, 0, 1, 1, 61516, 11218, 0, 1, 150000, 32, 148000, 425507, 118, 0, 1, 1, 148000, 425507,
+        118, 0, 1, 1, 2477736, 29175, 4, 0, 82363, 4, 150000, 5000, 0, 1, 150000, 32, 197209, 0, 1,
+        1, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32,
+        3345831, 1, 1,
+    ];
+
+    let cost_mdl = CostModels {
+        plutus_v1: Some(costs),
+        plutus_v2: None,
+        plutus_v3: None,
+    };
+
+    let mut params = ProtocolParameters::from_json(
+        r#"{
+            "txFeePerByte": 44,
+            "txFeeFixed": 155381,
+            "maxTxSize": 16384,
+            "maxValueSize": 5000,
+            "maxTxExecutionUnits": { "memory": 14000000, "steps": 10000000000 },
+            "maxCollateralInputs": 3,
+            "collateralPercentage": 150,
+            "utxoCostPerByte": 4310,
+            "executionUnitPrices": { "priceMemory": 0.0577, "priceSteps": 0.0000721 },
+            "stakeAddressDeposit": 2000000,
+            "stakePoolDeposit": 500000000,
+            "costModels": {}
+        }"#,
+    )
+    .unwrap();
+
+    params.cost_models = cost_mdl;
+
+    let multi_era_tx = MultiEraTx::decode_for_era(Era::Conway, &tx_bytes).unwrap();
+
+    let tx = multi_era_tx.as_conway().unwrap();
+
+    let lookup_table = DataLookupTable::from_transaction(tx, &utxos);
+
+    let eval = |params: &ProtocolParameters, utxos: &[ResolvedInput], slot: Option<u64>| {
+        eval_phase_one_with_params(tx, utxos, &lookup_table, params, slot)
+    };
+
+    assert!(eval(&params, &utxos, None).is_ok());
+
+    // The transaction has no validity interval.
+    assert!(eval(&params, &utxos, Some(42)).is_ok());
+
+    assert!(matches!(
+        eval(
+            &ProtocolParameters {
+                fee_per_byte: 100,
+                ..params.clone()
+            },
+            &utxos,
+            None
+        ),
+        Err(Error::FeeTooSmall {
+            provided: 182066,
+            ..
+        })
+    ));
+
+    assert!(matches!(
+        eval(
+            &ProtocolParameters {
+                collateral_percentage: 5000,
+                ..params.clone()
+            },
+            &utxos,
+            None
+        ),
+        Err(Error::InsufficientCollateral { .. })
+    ));
+
+    assert!(matches!(
+        eval(
+            &ProtocolParameters {
+                max_tx_ex_units: ExUnits {
+                    mem: 1000,
+                    steps: 10000000000
+                },
+                ..params.clone()
+            },
+            &utxos,
+            None
+        ),
+        Err(Error::ExUnitsTooBig { .. })
+    ));
+
+    let mut other_cost_model = params.cost_models.clone();
+    other_cost_model.plutus_v1.as_mut().unwrap()[0] += 1;
+
+    assert!(matches!(
+        eval(
+            &ProtocolParameters {
+                cost_models: other_cost_model,
+                ..params.clone()
+            },
+            &utxos,
+            None
+        ),
+        Err(Error::ScriptDataHashMismatch { .. })
+    ));
+
+    // Pretend the first input held one more lovelace than it did.
+    let unbalanced = utxos
+        .iter()
+        .map(|utxo| match &utxo.output {
+            TransactionOutput::PostAlonzo(output)
+                if utxo.input == tx.transaction_body.inputs[0] =>
+            {
+                let mut output = output.clone();
+                if let Value::Coin(coin) = &mut output.value {
+                    *coin += 1;
+                }
+                ResolvedInput {
+                    input: utxo.input.clone(),
+                    output: TransactionOutput::PostAlonzo(output),
+                }
+            }
+            TransactionOutput::Legacy(output) if utxo.input == tx.transaction_body.inputs[0] => {
+                let mut output = output.clone();
+                if let LegacyValue::Coin(coin) = &mut output.amount {
+                    *coin += 1;
+                }
+                ResolvedInput {
+                    input: utxo.input.clone(),
+                    output: TransactionOutput::Legacy(output),
+                }
+            }
+            _ => utxo.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    assert!(matches!(
+        eval(&params, &unbalanced, None),
+        Err(Error::ValueNotConserved { .. })
+    ));
+}
+
+/// A transaction spending from a Plutus V1 script, along with the UTxO it spends from and
+/// protocol parameters with matching cost models.
+fn ex_units_fixture() -> (Vec<u8>, MaybeIndefArray<ResolvedInput>, ProtocolParameters) {
+    let tx_bytes = hex::decode("84a80082825820f375a19211ee8ce75b8306a2604e2a0e8c50dfae7a103dd3688f980bec5e29b200825820a0bfcb30c19402a5bc79ecd33ddea4d67d8d6cb2ebe694c5ac2f14abc47092f4000182825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d5821a00111958a1581c652cc39999ceb1ddbdd788c3206d70536d84d36f42198296736355efa1400a825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a0047acf6021a0002c73209a1581c652cc39999ceb1ddbdd788c3206d70536d84d36f42198296736355efa1400a0b58201ca9076f41fe63a349f05c6346c732fa7f42744e43e0d54f546af0ffc463057f0d81825820f375a19211ee8ce75b8306a2604e2a0e8c50dfae7a103dd3688f980bec5e29b20110825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a054bc2ef111a00042acba30081825820065dd553fbe4e240a8f819bb9e333a7483de4a22b65c7fb6a95ce9450f84dff758402220dcecba177cabf70414676a5b9af9e0d6dec5ce3a7335caef5e4843a2f045995f6e8debbe019d21dcfc8749d780f0cd75c717cfab17ba275702c3aae37309038158235821010000323232223333573460060024931324c4660040020020024466ebc00800410581840100d8798082190fa21a005f14d0f5f6").unwrap();
+
+    let raw_inputs = hex::decode("86825820a0bfcb30c19402a5bc79ecd33ddea4d67d8d6cb2ebe694c5ac2f14abc47092f400825820a0bfcb30c19402a5bc79ecd33ddea4d67d8d6cb2ebe694c5ac2f14abc47092f401825820cd3589fae0ae4ad33dbc8a8d99b4e99b4a78c2b0473040aab34da8e44fba9fa300825820f375a19211ee8ce75b8306a2604e2a0e8c50dfae7a103dd3688f980bec5e29b200825820f375a19211ee8ce75b8306a2604e2a0e8c50dfae7a103dd3688f980bec5e29b201825820c5bc1437117c200f325467dcede3c09fbfca32f9fe399bd40c778ab2fa832f4301").unwrap();
+    let raw_outputs = hex::decode("86825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a000f4240825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d5821a005da7c4ac581c1e852216c006c55490cd85b6e0ba523a37c2be3526f479e61cca41eba2444e616d6902454e616d693201581c21514b6c51f0aaa0c9c337329ce93c52fad208cfd0b4ccd51074d9dcab4d50494e4b44494e4f4331494439014d50494e4b44494e4f4332494439014d50494e4b44494e4f4333494439014d50494e4b44494e4f4334494439014d50494e4b44494e4f4335494439014d50494e4b44494e4f4336494439014d50494e4b44494e4f4337494439014d50494e4b44494e4f4338494439014d50494e4b44494e4f4339494439014e50494e4b44494e4f433130494439014e50494e4b44494e4f43313149443901581c321668648736a09a40bc3ef5fbd05590530be624e39af84fab3bc6bba84d435033503050726f6d6f333431014f4b696e67447572616e747574323531015053616d757257686950726f6d6f313933015143727970746f6d626f50726f6d6f3134360154536b756c6c62616c6c4c6f676f50726f6d6f35320155536b756c6c62616c6c4c6f676f50726f6d6f33313301581b425345647761726473536c616d6d696e48616e647330303034313201581c42697a61727265537461727344726561646c696f6e526f736533323401581c9ec93fd47d1e43ec5bb5dad70af92b6498b92d20d4325f9027ac4743a1574a6f736570684d6972616e64615754503030353754323601581ca7dc26584358f5ca8d99d7aa646a7b658120296a94588e1cefad8940a157546573744e616d69467269656e645368696e794a50454701581cbc5a0f0f3f1bfa92286410388c54655687bb518f47e12b88c0d0728ca14b566563746f724669656c6401581cc4c00fbd8fa227442a5e7cdecde33b24588494d05a2c50fda8938c6da1444b49445a19c346581cd068fe47123ec4c86460eeb74c7d7765c67d2df295a3ac86d664ed45a156506c757475734669727374436c61737350686f746f3101581cd3b65744dd067fd7103cc6a4019cc9cd5f8627b78174c05dc67a9ad6a1544c696665496e4c6f636b646f776e53314d31503201581cd973d8df645da318bb331dbe4af8eb0270079470225d955c6183445ca15148617070794269727468646179416c657301581cdf9974c2192744f3b6dac92990769fe26c36b0ac8bcc623fb457a45da14d575450303035466c796572323401581cee47c1521b55a006c345f739f084f5c205aeb295e3e42fa66b9d1dd2a151446965666e6643727970746f6e6965726505825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a000f4240825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a004c4b40825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a054fedba825839010af00cc47500bb64cfffb783e8c42f746b4e8b8a70ede9c08c7113acf3bde34d1041f5a2076ef9aa6cf4539ab1a96ed462a0300acbdb65d51a0028a3c6").unwrap();
+
+    let inputs = MaybeIndefArray::<TransactionInput>::decode_fragment(&raw_inputs).unwrap();
+    let outputs = MaybeIndefArray::<TransactionOutput>::decode_fragment(&raw_outputs).unwrap();
+
+    let utxos: MaybeIndefArray<ResolvedInput> = MaybeIndefArray::Indef(
+        inputs
+            .iter()
+            .zip(outputs.iter())
+            .map(|(input, output)| ResolvedInput {
+                input: input.clone(),
+                output: output.clone(),
+            })
+            .collect(),
+    );
+
+    let costs: Vec<i64> = vec![
+        197209, 0, 1, 1, 396231, 621, 0, 1, 150000, 1000, 0, 1, 150000, 32, 2477736, 29175, 4,
+        29773, 100, 29773, 100, 29773, 100, 29773, 100, 29773, 100, 29773, 100, 100, 100, 29773,
+        100, 150000, 32, 150000, 32, 150000, 32, 150000, 1000, 0, 1, 150000, 32, 150000, 1000, 0,
+        8, 148000, 425507, 118, 0, 1, 1, 150000, 1000, 0, 8, 150000, 112536, 247, 1, 150000, 10000,
+        1, 136542, 1326, 1, 1000, 150000, 1000, 1, 150000, 32, 150000, 32, 150000, 32, 1, 1,
+        150000, 1, 150000, 4, 103599, 248, 1, 103599, 248, 1, 145276, 1366, 1, 179690, 497, 1,
+        150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 148000, 425507,
+        118, 0, 1, 1, 61516, 11218, 0, 1, 150000, 32, 148000, 425507, 118, 0, 1, 1, 148000, 425507,
+        118, 0, 1, 1, 2477736, 29175, 4, 0, 82363, 4, 150000, 5000, 0, 1, 150000, 32, 197209, 0, 1,
+        1, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32, 150000, 32,
+        3345831, 1, 1,
+    ];
+
+    let cost_mdl = CostModels {
+        plutus_v1: Some(costs),
+        plutus_v2: None,
+        plutus_v3: None,
+    };
+
+    let mut params = ProtocolParameters::from_json(
+        r#"{
+            "txFeePerByte": 44,
+            "txFeeFixed": 155381,
+            "maxTxSize": 16384,
+            "maxValueSize": 5000,
+            "maxTxExecutionUnits": { "memory": 14000000, "steps": 10000000000 },
+            "maxCollateralInputs": 3,
+            "collateralPercentage": 150,
+            "utxoCostPerByte": 4310,
+            "executionUnitPrices": { "priceMemory": 0.0577, "priceSteps": 0.0000721 },
+            "stakeAddressDeposit": 2000000,
+            "stakePoolDeposit": 500000000,
+            "costModels": {}
+        }"#,
+    )
+    .unwrap();
+
+    params.cost_models = cost_mdl;
+
+    (tx_bytes, utxos, params)
+}
+
+#[test]
+fn apply_ex_units_to_redeemers() {
+    let (tx_bytes, utxos, params) = ex_units_fixture();
+
+    let multi_era_tx = MultiEraTx::decode_for_era(Era::Conway, &tx_bytes).unwrap();
+
+    let tx = multi_era_tx.as_conway().unwrap();
+
+    let declared = tx
+        .transaction_witness_set
+        .redeemer
+        .as_ref()
+        .map(|redeemers| {
+            iter_redeemers(redeemers)
+                .map(|(key, data, ex_units)| Redeemer {
+                    tag: key.tag,
+                    index: key.index,
+                    data: data.clone(),
+                    ex_units,
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+
+    // Declared units without margin leave the transaction unchanged.
+    assert_eq!(
+        apply_ex_units(tx, &utxos, &declared, 0, &params.cost_models).unwrap(),
+        tx_bytes
+    );
+
+    let evaluated = eval_phase_two(
+        tx,
+        &utxos,
+        Some(&params.cost_models),
+        None,
+        &SlotConfig::default(),
+        false,
+        |_| (),
+    )
+    .unwrap()
+    .into_iter()
+    .map(|(redeemer, _)| redeemer)
+    .collect::<Vec<_>>();
+
+    let fitted_bytes = apply_ex_units(tx, &utxos, &evaluated, 10, &params.cost_models).unwrap();
+
+    let fitted_tx = MultiEraTx::decode_for_era(Era::Conway, &fitted_bytes).unwrap();
+
+    let fitted = fitted_tx.as_conway().unwrap();
+
+    let ex_units = iter_redeemers(fitted.transaction_witness_set.redeemer.as_ref().unwrap())
+        .map(|(_, _, ex_units)| ex_units)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        ex_units,
+        vec![ExUnits {
+            mem: (evaluated[0].ex_units.mem * 110).div_ceil(100),
+            steps: (evaluated[0].ex_units.steps * 110).div_ceil(100),
+        }]
+    );
+
+    assert_ne!(
+        fitted.transaction_body.script_data_hash,
+        tx.transaction_body.script_data_hash
+    );
+
+    let lookup_table = DataLookupTable::from_transaction(fitted, &utxos);
+
+    // The fee isn't adjusted to the new execution units, but the script data hash is.
+    let params = ProtocolParameters {
+        fee_fixed: 0,
+        ..params
+    };
+
+    assert!(eval_phase_one_with_params(fitted, &utxos, &lookup_table, &params, None).is_ok());
+}
+
+#[test]
+fn apply_ex_units_to_draft() {
+    let (tx_bytes, utxos, params) = ex_units_fixture();
+
+    // A draft, with placeholder execution units and a script data hash computed from other
+    // redeemers.
+    let draft_bytes = hex::decode(
+        hex::encode(&tx_bytes)
+            .replace(
+                "1ca9076f41fe63a349f05c6346c732fa7f42744e43e0d54f546af0ffc463057f",
+                &"00".repeat(32),
+            )
+            .replace("840100d8798082190fa21a005f14d0", "840100d87980820000"),
+    )
+    .unwrap();
+
+    let multi_era_tx = MultiEraTx::decode_for_era(Era::Conway, &draft_bytes).unwrap();
+
+    let draft = multi_era_tx.as_conway().unwrap();
+
+    let params = ProtocolParameters {
+        fee_fixed: 0,
+        ..params
+    };
+
+    assert!(
+        eval_phase_one_with_params(
+            draft,
+            &utxos,
+            &DataLookupTable::from_transaction(draft, &utxos),
+            &params,
+            None
+        )
+        .is_err()
+    );
+
+    let evaluated = eval_phase_two(
+        draft,
+        &utxos,
+        Some(&params.cost_models),
+        None,
+        &SlotConfig::default(),
+        false,
+        |_| (),
+    )
+    .unwrap()
+    .into_iter()
+    .map(|(redeemer, _)| redeemer)
+    .collect::<Vec<_>>();
+
+    let fitted_bytes = apply_ex_units(draft, &utxos, &evaluated, 5, &params.cost_models).unwrap();
+
+    let fitted_tx = MultiEraTx::decode_for_era(Era::Conway, &fitted_bytes).unwrap();
+
+    let fitted = fitted_tx.as_conway().unwrap();
+
+    let ex_units = iter_redeemers(fitted.transaction_witness_set.redeemer.as_ref().unwrap())
+        .map(|(_, _, ex_units)| ex_units)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        ex_units,
+        vec![ExUnits {
+            mem: (evaluated[0].ex_units.mem * 105).div_ceil(100),
+            steps: (evaluated[0].ex_units.steps * 105).div_ceil(100),
+        }]
+    );
+
+    let lookup_table = DataLookupTable::from_transaction(fitted, &utxos);
+
+    assert!(eval_phase_one_with_params(fitted, &utxos, &lookup_table, &params, None).is_ok());
+}
+
+#[test]
+fn eval_scenario_against_scripts() {
+    use super::{build_scenario, eval_scenario};
+    use crate::{
+        PlutusData,
+        ast::{Data, DeBruijn, Program, SerializableProgram},
+        parser,
+    };
+    use std::collections::HashMap;
+
+    // Succeeds when the redeemer is 42.
+    let program: Program<DeBruijn> = parser::program(
+        r#"
+        (program 1.1.0
+          (lam ctx
+            (force
+              [ [ [ (force (builtin ifThenElse))
+                    [ [ (builtin equalsInteger) (con integer 42) ]
+                      [ (builtin unIData)
+                        [ (force (builtin headList))
+                          [ (force (builtin tailList))
+                            [ (force (force (builtin sndPair))) [ (builtin unConstrData) ctx ] ]
+                          ]
+                        ]
+                      ]
+                    ]
+                  ]
+                  (delay (con unit ()))
+                ]
+                (delay (error))
+              ]
+            )
+          )
+        )
+        "#,
+    )
+    .unwrap()
+    .try_into()
+    .unwrap();
+
+    let (hash, script) = SerializableProgram::PlutusV3Program(program).compiled_code_and_hash();
+
+    let scripts = HashMap::from([(hash, script)]);
+
+    let none = || Data::constr(1, vec![]);
+    let some = |x| Data::constr(0, vec![x]);
+    let lovelace = |n: i64| {
+        Data::map(vec![(
+            Data::bytestring(vec![]),
+            Data::map(vec![(Data::bytestring(vec![]), Data::integer(n.into()))]),
+        )])
+    };
+    let output = |credential: PlutusData| {
+        Data::constr(
+            0,
+            vec![
+                Data::constr(0, vec![credential, none()]),
+                lovelace(2_000_000),
+                Data::constr(0, vec![]),
+                none(),
+            ],
+        )
+    };
+    let output_reference = |ix: i64| {
+        Data::constr(
+            0,
+            vec![Data::bytestring(vec![0; 32]), Data::integer(ix.into())],
+        )
+    };
+    let script_credential = || Data::constr(1, vec![Data::bytestring(hash.to_vec())]);
+    let key_credential = || Data::constr(0, vec![Data::bytestring(vec![1; 28])]);
+    let bound = |kind: u64, is_inclusive: u64| {
+        Data::constr(
+            0,
+            vec![
+                Data::constr(kind, vec![]),
+                Data::constr(is_inclusive, vec![]),
+            ],
+        )
+    };
+
+    let transaction = |redeemer: i64| {
+        Data::constr(
+            0,
+            vec![
+                // Inputs, out of order, with the script input last.
+                Data::list(vec![
+                    Data::constr(0, vec![output_reference(1), output(script_credential())]),
+                    Data::constr(0, vec![output_reference(0), output(key_credential())]),
+                ]),
+                Data::list(vec![]),
+                Data::list(vec![output(key_credential())]),
+                Data::integer(200_000.into()),
+                Data::map(vec![]),
+                Data::list(vec![]),
+                Data::map(vec![]),
+                Data::constr(0, vec![bound(0, 1), bound(2, 1)]),
+                Data::list(vec![]),
+                Data::map(vec![(
+                    Data::constr(1, vec![output_reference(1)]),
+                    Data::integer(redeemer.into()),
+                )]),
+                Data::map(vec![]),
+                Data::bytestring(vec![0; 32]),
+                Data::map(vec![]),
+                Data::list(vec![]),
+                none(),
+                some(Data::integer(1.into())),
+            ],
+        )
+    };
+
+    let (bytes, utxos) = build_scenario(&transaction(42), &scripts).unwrap();
+    let tx = MultiEraTx::decode_for_era(Era::Conway, &bytes).unwrap();
+    let tx = tx.as_conway().unwrap();
+
+    assert_eq!(utxos.len(), 2);
+    assert_eq!(tx.transaction_body.inputs[0].index, 0);
+    assert_eq!(tx.transaction_body.donation.map(u64::from), Some(1));
+
+    let redeemers = eval_scenario(&transaction(42), &scripts).unwrap();
+    assert_eq!(redeemers.len(), 1);
+    assert_eq!(redeemers[0].0.index, 1);
+
+    assert!(matches!(
+        eval_scenario(&transaction(14), &scripts),
+        Err(Error::RedeemerError { index: 1, err, .. }) if matches!(*err, Error::Machine(..))
+    ));
+
+    assert!(matches!(
+        eval_scenario(&transaction(42), &HashMap::new()),
+        Err(Error::MissingRequiredScript { .. })
+    ));
+
+    assert!(matches!(
+        eval_scenario(&Data::integer(42.into()), &scripts),
+        Err(Error::InvalidScenario(..))
+    ));
+}
@@ -0,0 +1,536 @@
+use super::{ResolvedInput, error::Error};
+use crate::ast::Data;
+use num_bigint::BigInt;
+use pallas_addresses::Address;
+use pallas_codec::{
+    minicbor,
+    utils::{Bytes, CborWrap, KeyValuePairs, NonEmptyKeyValuePairs, PositiveCoin},
+};
+use pallas_crypto::hash::Hash;
+use pallas_primitives::{
+    PlutusScript,
+    conway::{
+        DatumOption, MintedTx, NativeScript, PlutusData, PostAlonzoTransactionOutput,
+        PseudoTransactionOutput, ScriptRef, TransactionInput, TransactionOutput, Value,
+    },
+};
+use serde_json::{Map, Value as Json};
+use std::{collections::BTreeMap, str::FromStr};
+
+/// A set of unspent transaction outputs, indexed by the input spending them; typically exported
+/// from a chain indexer or a node, and used to resolve the inputs of transactions to simulate.
+///
+/// Snapshots can be read from JSON, in any of the following shapes:
+///
+/// - `cardano-cli query utxo --output-json`: an object mapping `{tx_id}#{index}` to outputs.
+/// - Ogmios' `queryLedgerState/utxo`: an array of outputs with a `transaction` and `index`.
+/// - Kupo's `/matches`: an array of outputs with a `transaction_id` and `output_index`. Kupo
+///   doesn't inline datums and scripts in matches, so they must be merged into each match as
+///   `datum` and `script`, as returned by the `/datums` and `/scripts` endpoints.
+///
+/// Or from CBOR, as a map from inputs to outputs or as an array of (input, output) pairs.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct UtxoSnapshot(BTreeMap<TransactionInput, TransactionOutput>);
+
+impl UtxoSnapshot {
+    /// Decode a snapshot from JSON, CBOR or hex-encoded CBOR, whichever it is.
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let text = std::str::from_utf8(bytes).map(str::trim);
+
+        match text {
+            Ok(text) if text.starts_with('{') || text.starts_with('[') => Self::from_json(text),
+            Ok(text) => match hex::decode(text) {
+                Ok(cbor) => Self::from_cbor(&cbor),
+                Err(_) => Self::from_cbor(bytes),
+            },
+            Err(_) => Self::from_cbor(bytes),
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let json: Json = serde_json::from_str(json).map_err(invalid)?;
+
+        let utxos = match json {
+            Json::Object(entries) => entries
+                .iter()
+                .map(|(out_ref, output)| {
+                    Ok((
+                        output_reference(out_ref)?,
+                        cardano_cli_output(as_object(output, out_ref)?)?,
+                    ))
+                })
+                .collect::<Result<_, Error>>()?,
+            Json::Array(entries) => entries
+                .iter()
+                .map(|entry| {
+                    let entry = as_object(entry, "utxo")?;
+
+                    if entry.contains_key("transaction") {
+                        ogmios_utxo(entry)
+                    } else if entry.contains_key("transaction_id") {
+                        kupo_utxo(entry)
+                    } else {
+                        Err(invalid(
+                            "unrecognized utxo; expected an Ogmios or Kupo output reference",
+                        ))
+                    }
+                })
+                .collect::<Result<_, Error>>()?,
+            _ => return Err(invalid("expected an object or an array of utxos")),
+        };
+
+        Ok(UtxoSnapshot(utxos))
+    }
+
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, Error> {
+        if let Ok(utxos) =
+            minicbor::decode::<KeyValuePairs<TransactionInput, TransactionOutput>>(bytes)
+        {
+            return Ok(UtxoSnapshot(utxos.to_vec().into_iter().collect()));
+        }
+
+        minicbor::decode::<Vec<(TransactionInput, TransactionOutput)>>(bytes)
+            .map(|utxos| UtxoSnapshot(utxos.into_iter().collect()))
+            .map_err(invalid)
+    }
+
+    pub fn get(&self, input: &TransactionInput) -> Option<&TransactionOutput> {
+        self.0.get(input)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Resolve the inputs, reference inputs and collateral inputs of a transaction.
+    pub fn resolve(&self, tx: &MintedTx) -> Result<Vec<ResolvedInput>, Error> {
+        let body = &tx.transaction_body;
+
+        let inputs = body
+            .inputs
+            .iter()
+            .chain(
+                body.reference_inputs
+                    .iter()
+                    .flat_map(|inputs| inputs.iter()),
+            )
+            .chain(body.collateral.iter().flat_map(|inputs| inputs.iter()));
+
+        let mut resolved: Vec<ResolvedInput> = vec![];
+
+        for input in inputs {
+            if resolved.iter().any(|utxo| &utxo.input == input) {
+                continue;
+            }
+
+            let output = self
+                .get(input)
+                .ok_or_else(|| Error::ResolvedInputNotFound(input.clone()))?;
+
+            resolved.push(ResolvedInput {
+                input: input.clone(),
+                output: output.clone(),
+            });
+        }
+
+        Ok(resolved)
+    }
+}
+
+fn invalid(reason: impl ToString) -> Error {
+    Error::InvalidUtxoSnapshot(reason.to_string())
+}
+
+fn as_object<'a>(json: &'a Json, what: &str) -> Result<&'a Map<String, Json>, Error> {
+    json.as_object()
+        .ok_or_else(|| invalid(format!("expected an object for {what}")))
+}
+
+fn field<'a>(entry: &'a Map<String, Json>, key: &str) -> Result<&'a Json, Error> {
+    entry
+        .get(key)
+        .ok_or_else(|| invalid(format!("missing field '{key}'")))
+}
+
+/// A field holding a string, treating `null` as absent.
+fn optional_str<'a>(entry: &'a Map<String, Json>, key: &str) -> Result<Option<&'a str>, Error> {
+    match entry.get(key) {
+        None | Some(Json::Null) => Ok(None),
+        Some(Json::String(s)) => Ok(Some(s)),
+        Some(_) => Err(invalid(format!("expected a string for '{key}'"))),
+    }
+}
+
+fn str_field<'a>(entry: &'a Map<String, Json>, key: &str) -> Result<&'a str, Error> {
+    optional_str(entry, key)?.ok_or_else(|| invalid(format!("missing field '{key}'")))
+}
+
+fn u64_field(entry: &Map<String, Json>, key: &str) -> Result<u64, Error> {
+    as_u64(field(entry, key)?, key)
+}
+
+fn as_u64(json: &Json, what: &str) -> Result<u64, Error> {
+    json.as_u64()
+        .ok_or_else(|| invalid(format!("expected a positive integer for '{what}'")))
+}
+
+fn from_hex(s: &str) -> Result<Vec<u8>, Error> {
+    hex::decode(s).map_err(|e| invalid(format!("{e}: {s}")))
+}
+
+fn transaction_input(transaction_id: &str, index: u64) -> Result<TransactionInput, Error> {
+    let transaction_id = Hash::<32>::from_str(transaction_id)
+        .map_err(|_| invalid(format!("invalid transaction id: {transaction_id}")))?;
+
+    Ok(TransactionInput {
+        transaction_id,
+        index,
+    })
+}
+
+/// Parse an output reference of the form `{tx_id}#{index}`.
+fn output_reference(out_ref: &str) -> Result<TransactionInput, Error> {
+    let (transaction_id, index) = out_ref
+        .split_once('#')
+        .ok_or_else(|| invalid(format!("expected {{tx_id}}#{{index}}, got: {out_ref}")))?;
+
+    let index = index
+        .parse()
+        .map_err(|_| invalid(format!("invalid output index: {out_ref}")))?;
+
+    transaction_input(transaction_id, index)
+}
+
+fn output(
+    address: &str,
+    value: Value,
+    datum_option: Option<DatumOption>,
+    script_ref: Option<ScriptRef>,
+) -> Result<TransactionOutput, Error> {
+    let address = Address::from_str(address)?;
+
+    Ok(PseudoTransactionOutput::PostAlonzo(
+        PostAlonzoTransactionOutput {
+            address: Bytes::from(address.to_vec()),
+            value,
+            datum_option,
+            script_ref: script_ref.map(CborWrap),
+        },
+    ))
+}
+
+fn value(coin: u64, assets: Vec<(&str, &str, u64)>) -> Result<Value, Error> {
+    let mut policies: BTreeMap<Hash<28>, BTreeMap<Vec<u8>, PositiveCoin>> = BTreeMap::new();
+
+    for (policy_id, asset_name, quantity) in assets {
+        let policy_id = Hash::<28>::from_str(policy_id)
+            .map_err(|_| invalid(format!("invalid policy id: {policy_id}")))?;
+
+        let quantity = PositiveCoin::try_from(quantity)
+            .map_err(|_| invalid("asset quantities must be positive"))?;
+
+        policies
+            .entry(policy_id)
+            .or_default()
+            .insert(from_hex(asset_name)?, quantity);
+    }
+
+    let multiasset = policies
+        .into_iter()
+        .filter_map(|(policy_id, assets)| {
+            let assets = assets
+                .into_iter()
+                .map(|(asset_name, quantity)| (Bytes::from(asset_name), quantity))
+                .collect();
+
+            Some((policy_id, NonEmptyKeyValuePairs::from_vec(assets)?))
+        })
+        .collect();
+
+    Ok(match NonEmptyKeyValuePairs::from_vec(multiasset) {
+        None => Value::Coin(coin),
+        Some(multiasset) => Value::Multiasset(coin, multiasset),
+    })
+}
+
+/// Assets as nested objects, from policy ids to asset names to quantities.
+fn nested_assets<'a>(
+    policies: impl Iterator<Item = (&'a String, &'a Json)>,
+) -> Result<Vec<(&'a str, &'a str, u64)>, Error> {
+    let mut assets = vec![];
+
+    for (policy_id, policy) in policies {
+        for (asset_name, quantity) in as_object(policy, policy_id)? {
+            assets.push((
+                policy_id.as_str(),
+                asset_name.as_str(),
+                as_u64(quantity, asset_name)?,
+            ));
+        }
+    }
+
+    Ok(assets)
+}
+
+fn datum(cbor: &str) -> Result<DatumOption, Error> {
+    let data = minicbor::decode::<PlutusData>(&from_hex(cbor)?).map_err(invalid)?;
+
+    Ok(DatumOption::Data(CborWrap(data)))
+}
+
+fn datum_hash(hash: &str) -> Result<DatumOption, Error> {
+    Hash::<32>::from_str(hash)
+        .map(DatumOption::Hash)
+        .map_err(|_| invalid(format!("invalid datum hash: {hash}")))
+}
+
+/// Build a script reference from a language tag, as used by Ogmios and Kupo, and the script
+/// serialized as hex.
+fn script(language: &str, script: &str) -> Result<ScriptRef, Error> {
+    let bytes = from_hex(script)?;
+
+    match language {
+        "native" => minicbor::decode::<NativeScript>(&bytes)
+            .map(ScriptRef::NativeScript)
+            .map_err(invalid),
+        "plutus:v1" => Ok(ScriptRef::PlutusV1Script(PlutusScript(plutus_script(
+            bytes,
+        )))),
+        "plutus:v2" => Ok(ScriptRef::PlutusV2Script(PlutusScript(plutus_script(
+            bytes,
+        )))),
+        "plutus:v3" => Ok(ScriptRef::PlutusV3Script(PlutusScript(plutus_script(
+            bytes,
+        )))),
+        _ => Err(invalid(format!("unknown script language: {language}"))),
+    }
+}
+
+/// Plutus scripts are serialized as flat bytes, wrapped in a CBOR byte string. Depending on the
+/// source, snapshots may hold them raw, wrapped once or wrapped twice; so we strip all layers
+/// before wrapping the flat bytes again.
+fn plutus_script(mut bytes: Vec<u8>) -> Bytes {
+    while let Ok(inner) = minicbor::decode::<Bytes>(&bytes) {
+        bytes = inner.to_vec();
+    }
+
+    let mut cbor = vec![];
+
+    minicbor::encode(Bytes::from(bytes), &mut cbor).expect("encoding to a vector is infallible");
+
+    Bytes::from(cbor)
+}
+
+fn cardano_cli_output(output: &Map<String, Json>) -> Result<TransactionOutput, Error> {
+    let amounts = as_object(field(output, "value")?, "value")?;
+
+    let coin = u64_field(amounts, "lovelace")?;
+
+    let assets = nested_assets(amounts.iter().filter(|(key, _)| *key != "lovelace"))?;
+
+    let datum_option = match (
+        optional_str(output, "inlineDatumRaw")?,
+        output.get("inlineDatum").filter(|json| !json.is_null()),
+        optional_str(output, "datumhash")?,
+    ) {
+        (Some(cbor), _, _) => Some(datum(cbor)?),
+        (None, Some(json), _) => Some(DatumOption::Data(CborWrap(script_data(json)?))),
+        (None, None, Some(hash)) => Some(datum_hash(hash)?),
+        (None, None, None) => None,
+    };
+
+    let script_ref = match output.get("referenceScript").filter(|json| !json.is_null()) {
+        None => None,
+        Some(reference_script) => {
+            let script = as_object(
+                field(as_object(reference_script, "referenceScript")?, "script")?,
+                "script",
+            )?;
+
+            let language = match str_field(script, "type")? {
+                "PlutusScriptV1" => "plutus:v1",
+                "PlutusScriptV2" => "plutus:v2",
+                "PlutusScriptV3" => "plutus:v3",
+                other => {
+                    return Err(invalid(format!(
+                        "unsupported reference script type: {other}"
+                    )));
+                }
+            };
+
+            Some(self::script(language, str_field(script, "cborHex")?)?)
+        }
+    };
+
+    self::output(
+        str_field(output, "address")?,
+        value(coin, assets)?,
+        datum_option,
+        script_ref,
+    )
+}
+
+/// Parse plutus data from cardano-cli's detailed JSON schema.
+fn script_data(json: &Json) -> Result<PlutusData, Error> {
+    let data = as_object(json, "inlineDatum")?;
+
+    if let Some(int) = data.get("int") {
+        // Integers are unbounded, and kept as written thanks to serde_json's arbitrary precision.
+        let int = match int {
+            Json::Number(n) => BigInt::from_str(&n.to_string())
+                .map_err(|_| invalid(format!("invalid integer: {int}")))?,
+            _ => return Err(invalid(format!("invalid integer: {int}"))),
+        };
+        return Ok(Data::integer(int));
+    }
+
+    if let Some(bytes) = data.get("bytes") {
+        let bytes = bytes
+            .as_str()
+            .ok_or_else(|| invalid("expected a hex string for 'bytes'"))?;
+        return Ok(Data::bytestring(from_hex(bytes)?));
+    }
+
+    if let Some(list) = data.get("list") {
+        return list
+            .as_array()
+            .ok_or_else(|| invalid("expected an array for 'list'"))?
+            .iter()
+            .map(script_data)
+            .collect::<Result<_, _>>()
+            .map(Data::list);
+    }
+
+    if let Some(map) = data.get("map") {
+        return map
+            .as_array()
+            .ok_or_else(|| invalid("expected an array for 'map'"))?
+            .iter()
+            .map(|kv| {
+                let kv = as_object(kv, "map")?;
+                Ok((script_data(field(kv, "k")?)?, script_data(field(kv, "v")?)?))
+            })
+            .collect::<Result<_, _>>()
+            .map(Data::map);
+    }
+
+    if let Some(constructor) = data.get("constructor") {
+        let fields = field(data, "fields")?
+            .as_array()
+            .ok_or_else(|| invalid("expected an array for 'fields'"))?
+            .iter()
+            .map(script_data)
+            .collect::<Result<_, _>>()?;
+        return Ok(Data::constr(as_u64(constructor, "constructor")?, fields));
+    }
+
+    Err(invalid(format!("invalid script data: {json}")))
+}
+
+fn ogmios_utxo(utxo: &Map<String, Json>) -> Result<(TransactionInput, TransactionOutput), Error> {
+    let input = transaction_input(
+        str_field(as_object(field(utxo, "transaction")?, "transaction")?, "id")?,
+        u64_field(utxo, "index")?,
+    )?;
+
+    let amounts = as_object(field(utxo, "value")?, "value")?;
+
+    let coin = u64_field(as_object(field(amounts, "ada")?, "ada")?, "lovelace")?;
+
+    let assets = nested_assets(amounts.iter().filter(|(key, _)| *key != "ada"))?;
+
+    let datum_option = match (
+        optional_str(utxo, "datum")?,
+        optional_str(utxo, "datumHash")?,
+    ) {
+        (Some(cbor), _) => Some(datum(cbor)?),
+        (None, Some(hash)) => Some(datum_hash(hash)?),
+        (None, None) => None,
+    };
+
+    let script_ref = match utxo.get("script").filter(|json| !json.is_null()) {
+        None => None,
+        Some(script) => {
+            let script = as_object(script, "script")?;
+            Some(self::script(
+                str_field(script, "language")?,
+                str_field(script, "cbor")?,
+            )?)
+        }
+    };
+
+    let output = output(
+        str_field(utxo, "address")?,
+        value(coin, assets)?,
+        datum_option,
+        script_ref,
+    )?;
+
+    Ok((input, output))
+}
+
+fn kupo_utxo(utxo: &Map<String, Json>) -> Result<(TransactionInput, TransactionOutput), Error> {
+    let input = transaction_input(
+        str_field(utxo, "transaction_id")?,
+        u64_field(utxo, "output_index")?,
+    )?;
+
+    let amounts = as_object(field(utxo, "value")?, "value")?;
+
+    let coin = u64_field(amounts, "coins")?;
+
+    let assets = match amounts.get("assets") {
+        None | Some(Json::Null) => vec![],
+        Some(assets) => as_object(assets, "assets")?
+            .iter()
+            .map(|(asset_id, quantity)| {
+                let (policy_id, asset_name) = asset_id.split_once('.').unwrap_or((asset_id, ""));
+                Ok((policy_id, asset_name, as_u64(quantity, asset_id)?))
+            })
+            .collect::<Result<_, Error>>()?,
+    };
+
+    let datum_option = match optional_str(utxo, "datum_type")? {
+        None => None,
+        Some("hash") => Some(datum_hash(str_field(utxo, "datum_hash")?)?),
+        Some("inline") => Some(datum(optional_str(utxo, "datum")?.ok_or_else(|| {
+            invalid(format!(
+                "inline datum of {}#{} is missing; merge it from Kupo's /datums as 'datum'",
+                input.transaction_id, input.index
+            ))
+        })?)?),
+        Some(other) => return Err(invalid(format!("unknown datum type: {other}"))),
+    };
+
+    let script_ref = match (
+        utxo.get("script").filter(|json| !json.is_null()),
+        optional_str(utxo, "script_hash")?,
+    ) {
+        (Some(script), _) => {
+            let script = as_object(script, "script")?;
+            Some(self::script(
+                str_field(script, "language")?,
+                str_field(script, "script")?,
+            )?)
+        }
+        (None, Some(hash)) => {
+            return Err(invalid(format!(
+                "reference script {hash} is missing; merge it from Kupo's /scripts as 'script'"
+            )));
+        }
+        (None, None) => None,
+    };
+
+    let output = output(
+        str_field(utxo, "address")?,
+        value(coin, assets)?,
+        datum_option,
+        script_ref,
+    )?;
+
+    Ok((input, output))
+}